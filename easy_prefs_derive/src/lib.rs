@@ -0,0 +1,235 @@
+//! `#[derive(EasyPrefs)]` for the `easy_prefs` crate.
+//!
+//! Use it through `easy_prefs` with the `derive` feature; see `easy_prefs::prefs` for the
+//! runtime side and the attribute reference.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Expr, ExprLit, Fields, Lit, LitStr, Meta, Token};
+
+/// Implements `easy_prefs::prefs::PrefsData` and `Default` for a struct with named fields.
+///
+/// Struct attribute: `#[prefs(filename = "app-settings", <struct options>)]`, where the struct
+/// options are those accepted after the filename by `easy_prefs!` (e.g. `lenient_types = true`,
+/// `validate_struct = check`).
+///
+/// Field attribute: `#[pref(default = <expr>, name = "<saved key>", <field options>)]`, where
+/// the field options are those accepted in parentheses by `easy_prefs!` (e.g. `secret`,
+/// `min = 0`, `choices = ["a", "b"]`). Without `default` the field uses `Default::default()`;
+/// without `name` it is saved under its Rust name. `#[secret]` is short for `#[pref(secret)]`.
+#[proc_macro_derive(EasyPrefs, attributes(prefs, pref, secret))]
+pub fn derive_easy_prefs(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            input.generics.span(),
+            "EasyPrefs cannot be derived for generic structs",
+        ));
+    }
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new(input.span(), "EasyPrefs requires named fields")),
+        },
+        _ => return Err(syn::Error::new(input.span(), "EasyPrefs can only be derived for structs")),
+    };
+
+    let mut filename = None;
+    let mut options = Vec::new();
+    for meta in attribute_metas(&input.attrs, "prefs")? {
+        match meta {
+            Meta::NameValue(nv) if nv.path.is_ident("filename") => {
+                filename = Some(string_literal(&nv.value)?);
+            }
+            Meta::NameValue(nv) => {
+                let (option, value) = (&nv.path, &nv.value);
+                options.push(quote!(.#option(#value)));
+            }
+            Meta::Path(option) => options.push(quote!(.#option(true))),
+            Meta::List(list) => return Err(syn::Error::new(list.span(), "unsupported option")),
+        }
+    }
+    let filename = filename.ok_or_else(|| {
+        syn::Error::new(
+            input.span(),
+            "missing #[prefs(filename = \"...\")] attribute",
+        )
+    })?;
+    let preferences_filename = format!("{}.toml", filename.value());
+
+    let mut defaults = Vec::new();
+    let mut infos = Vec::new();
+    let mut keys: Vec<(String, String)> = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let mut default = None;
+        let mut key = None;
+        let mut field_options = Vec::new();
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("secret")) {
+            field_options.push(Meta::Path(attr.meta.require_path_only()?.clone()));
+        }
+        let mut aliases = serde_aliases(&field.attrs);
+        for meta in attribute_metas(&field.attrs, "pref")? {
+            match meta {
+                Meta::NameValue(nv) if nv.path.is_ident("default") => default = Some(nv.value),
+                Meta::NameValue(nv) if nv.path.is_ident("name") => {
+                    key = Some(string_literal(&nv.value)?);
+                }
+                Meta::NameValue(nv) if nv.path.is_ident("alias") => {
+                    aliases.push(string_literal(&nv.value)?);
+                }
+                meta => field_options.push(meta),
+            }
+        }
+
+        let default_source = match &default {
+            Some(default) => source_text(&quote!(#default)),
+            None => "Default::default()".to_string(),
+        };
+        let declared = match default {
+            Some(default) => quote!(#default),
+            None => quote!(::core::default::Default::default()),
+        };
+        let doc = doc_comment(&field.attrs);
+        let ty = &field.ty;
+        let type_name = source_text(&quote!(#ty));
+        let field_name = ident.to_string();
+        let key = key.unwrap_or_else(|| LitStr::new(&field_name, ident.span()));
+        if let Some((_, other)) = keys.iter().find(|(saved, _)| *saved == key.value()) {
+            return Err(syn::Error::new(
+                key.span(),
+                format!("duplicate saved name \"{}\", also used by field `{other}`", key.value()),
+            ));
+        }
+        keys.push((key.value(), field_name.clone()));
+        // Fields serde doesn't read can't take a shipped default (and may not be `Deserialize`).
+        defaults.push(if is_serde_skipped(&field.attrs) {
+            quote!(#ident: #declared)
+        } else {
+            quote! {
+                #ident: ::easy_prefs::defaults::field_default(
+                    <Self as ::easy_prefs::prefs::PrefsData>::OPTIONS.defaults,
+                    <Self as ::easy_prefs::prefs::PrefsData>::FIELDS,
+                    #key,
+                )
+                .unwrap_or_else(|| #declared)
+            }
+        });
+        infos.push(quote! {
+            ::easy_prefs::field::FieldInfo {
+                name: #field_name,
+                key: #key,
+                type_name: #type_name,
+                default: #default_source,
+                doc: #doc,
+                aliases: &[#(#aliases),*],
+                options: ::easy_prefs::__easy_prefs_field_options!(#(#field_options),*),
+            }
+        });
+    }
+
+    Ok(quote! {
+        impl ::core::default::Default for #name {
+            fn default() -> Self {
+                Self {
+                    #(#defaults,)*
+                }
+            }
+        }
+
+        impl ::easy_prefs::prefs::PrefsData for #name {
+            const PREFERENCES_FILENAME: &'static str = #preferences_filename;
+            const FIELDS: &'static [::easy_prefs::field::FieldInfo] = &[#(#infos),*];
+            const OPTIONS: ::easy_prefs::PrefsOptions<Self> = ::easy_prefs::PrefsOptions::new() #(#options)*;
+        }
+    })
+}
+
+/// Collects the comma-separated items of every `#[<name>(...)]` attribute.
+fn attribute_metas(attrs: &[syn::Attribute], name: &str) -> syn::Result<Vec<Meta>> {
+    let mut metas = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident(name)) {
+        metas.extend(attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?);
+    }
+    Ok(metas)
+}
+
+/// Whether `#[serde(skip)]` or `#[serde(skip_deserializing)]` is among `attrs`.
+fn is_serde_skipped(attrs: &[syn::Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("serde"))
+        .filter_map(|attr| attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated).ok())
+        .flatten()
+        .any(|meta| meta.path().is_ident("skip") || meta.path().is_ident("skip_deserializing"))
+}
+
+/// The values of `#[serde(alias = "...")]` among `attrs`.
+fn serde_aliases(attrs: &[syn::Attribute]) -> Vec<LitStr> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("serde"))
+        .filter_map(|attr| attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated).ok())
+        .flatten()
+        .filter_map(|meta| match meta {
+            Meta::NameValue(nv) if nv.path.is_ident("alias") => string_literal(&nv.value).ok(),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Joins the `///` lines of a field, the way `easy_prefs!` records them.
+fn doc_comment(attrs: &[syn::Attribute]) -> String {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(nv) => string_literal(&nv.value).ok().map(|lit| lit.value()),
+            _ => None,
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// Renders tokens close to how they are usually written (`Vec<String>` rather than the
+/// `Vec < String >` of `stringify!`), matching what `easy_prefs!` records.
+fn source_text(tokens: &TokenStream2) -> String {
+    const TIGHTEN: [(&str, &str); 12] = [
+        (" <", "<"),
+        ("< ", "<"),
+        (" >", ">"),
+        (" ::", "::"),
+        (":: ", "::"),
+        (" ,", ","),
+        (" ;", ";"),
+        ("& ", "&"),
+        (" . ", "."),
+        (" !", "!"),
+        (" (", "("),
+        ("( ", "("),
+    ];
+    let mut text = tokens.to_string();
+    for (from, to) in TIGHTEN {
+        text = text.replace(from, to);
+    }
+    text.replace(" )", ")")
+}
+
+fn string_literal(expr: &Expr) -> syn::Result<LitStr> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(lit), ..
+        }) => Ok(lit.clone()),
+        _ => Err(syn::Error::new(expr.span(), "expected a string literal")),
+    }
+}
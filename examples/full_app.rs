@@ -0,0 +1,153 @@
+//! A settings setup for a real application, in one file:
+//!
+//! 1. **Migration**: settings from an older release are copied to the new location before
+//!    anything is loaded.
+//! 2. **Profiles**: the migrated settings become the default profile, with a "presentation"
+//!    profile next to it.
+//! 3. **Subscriptions**: the app reacts to changes through the `on_change` hook, telling
+//!    changes made in the settings screen apart from programmatic ones.
+//! 4. **Settings screen**: built from `FIELDS` alone, with `FieldInfo::widget()` choosing the
+//!    control for each field; edits go through `DynamicPrefs` by saved key.
+//!
+//! The screen is printed as text to keep the example free of GUI dependencies. With egui, the
+//! same descriptor maps to widgets like this:
+//!
+//! ```ignore
+//! match field.widget() {
+//!     Widget::Toggle => ui.checkbox(&mut on, label),
+//!     Widget::Choice(choices) => egui::ComboBox::from_label(label).show_ui(ui, |ui| {
+//!         for choice in choices { ui.selectable_value(&mut value, choice.to_string(), *choice); }
+//!     }),
+//!     Widget::Slider { min, max, .. } => ui.add(egui::Slider::new(&mut number, min..=max)),
+//!     Widget::Secret => ui.add(egui::TextEdit::singleline(&mut value).password(true)),
+//!     _ => ui.text_edit_singleline(&mut value),
+//! };
+//! // When the response changed: prefs.set_field_from_str(field.key, &value)
+//! ```
+//!
+//! A Tauri frontend would receive the fields and their widgets as JSON from a command and call
+//! back into a command wrapping `set_field_from_str()`.
+//!
+//! Run with `cargo run --example full_app`. Everything is stored in a temporary directory.
+
+use easy_prefs::change::{ChangeSet, SaveSource};
+use easy_prefs::dynamic::DynamicPrefs;
+use easy_prefs::easy_prefs;
+use easy_prefs::field::Widget;
+
+fn check(prefs: &EditorSettings) -> Result<(), String> {
+    if *prefs.get_autosave() && *prefs.get_autosave_minutes() == 0 {
+        return Err("autosave needs an interval".to_string());
+    }
+    Ok(())
+}
+
+/// Applies a change to the running app, e.g. swapping the color scheme.
+fn on_change(change: &ChangeSet) {
+    let origin = match change.source {
+        SaveSource::Field | SaveSource::EditGuard => "user",
+        SaveSource::Reload => "another process",
+        _ => "app",
+    };
+    println!(
+        "  [event] {} changed by {origin}: {} -> {}",
+        change.field,
+        shown(change.old.as_ref()),
+        shown(change.new.as_ref())
+    );
+}
+
+fn shown(value: Option<&toml::Value>) -> String {
+    value.map_or_else(|| "(none)".to_string(), ToString::to_string)
+}
+
+easy_prefs! {
+    pub struct EditorSettings {
+        /// Color scheme of the editor.
+        pub theme: String = "light".to_string() => "theme" (choices = ["light", "dark", "sepia"]),
+        /// Font size in points.
+        pub font_size: i64 = 14 => "font_size" (min = 8, max = 32, step = 1),
+        /// Save open files periodically.
+        pub autosave: bool = true => "autosave",
+        /// Minutes between autosaves.
+        pub autosave_minutes: i64 = 5 => "autosave_minutes" (min = 0),
+        /// Name shown on shared documents.
+        pub display_name: String = String::new() => "display_name",
+        /// Token for the sync service.
+        pub sync_token: String = String::new() => "sync_token" (secret),
+        /// Recently opened files.
+        pub recent_files: Vec<String> = Vec::new() => "recent_files",
+    },
+    "editor-settings",
+    validate_struct = check,
+    on_change = on_change
+}
+
+/// Prints the settings screen, one row per field.
+fn render(prefs: &EditorSettings) {
+    for field in EditorSettings::FIELDS {
+        let value = prefs.get_field_string(field.key).unwrap_or_default();
+        let control = match field.widget() {
+            Widget::Toggle => format!("[{}]", if value == "true" { "x" } else { " " }),
+            Widget::Choice(choices) => choices
+                .iter()
+                .map(|choice| if *choice == value { format!("({choice})") } else { choice.to_string() })
+                .collect::<Vec<_>>()
+                .join(" "),
+            Widget::Slider { min, max, .. } => format!("{min} |--{value}--| {max}"),
+            Widget::Secret => "********".to_string(),
+            Widget::Number | Widget::Text => format!("[{value}]"),
+            // Edited as TOML, e.g. in a multi-line text box.
+            Widget::Other => value,
+        };
+        println!("  {:<32} {control}", field.description());
+    }
+}
+
+/// Stores settings the way the previous release did, in a directory it no longer uses.
+fn install_old_release(legacy: &str) {
+    let mut prefs = EditorSettings::load_with_error(legacy).expect("Failed to load old settings");
+    prefs.save_theme("dark".to_string()).expect("Failed to save");
+}
+
+fn main() {
+    let root = tempfile::tempdir().expect("Failed to create a temporary directory");
+    let legacy = root.path().join("old-release");
+    let current = root.path().join("config");
+    let (legacy, current) = (legacy.to_str().unwrap(), current.to_str().unwrap());
+    install_old_release(legacy);
+
+    // 1. Migration: copy the old settings once, before the first load. Safe to call on every
+    // start; it does nothing once the new location has settings.
+    if EditorSettings::migrate(legacy, current).expect("Migration failed") {
+        println!("Migrated settings from {legacy}");
+    }
+
+    // 2. Profiles: each profile is its own file with its own single-instance lock.
+    let mut prefs = EditorSettings::load_with_error(current).expect("Failed to load settings");
+    let presentation =
+        EditorSettings::load_profile(current, "presentation").expect("Failed to load presentation profile");
+    println!("Theme: {}, presentation theme: {}", prefs.get_theme(), presentation.get_theme());
+
+    // 3 + 4. The settings screen edits by saved key; every accepted edit raises an event.
+    println!("\nSettings:");
+    render(&prefs);
+
+    println!("\nUser edits:");
+    for (key, input) in [("font_size", "18"), ("theme", "sepia"), ("autosave_minutes", "0"), ("font_size", "99")] {
+        if let Err(e) = prefs.set_field_from_str(key, input) {
+            println!("  rejected {key} = {input}: {e}");
+        }
+    }
+
+    // Changes the app makes itself, e.g. settings pushed by a sync service, are reported
+    // with their own source.
+    println!("\nSync:");
+    prefs
+        .apply_toml_patch("display_name = \"Alice\"\nrecent_files = [\"notes.md\"]")
+        .expect("Failed to apply synced settings");
+
+    println!("\nSettings:");
+    render(&prefs);
+    println!("\nStored at {}", prefs.get_preferences_file_path());
+}
@@ -0,0 +1,97 @@
+//! Renamed keys (aliases) and custom stored formats from serde attributes.
+//!
+//! Attributes written above a field are passed on to the generated struct, so serde's field
+//! attributes decide how the value is stored. `with`, `serialize_with`, and `deserialize_with`
+//! change the format (a duration in minutes, a date as `"2024-05-01"`), and `alias` reads a key
+//! that an earlier app version saved under a different name (the `alias` field option does the
+//! same without serde, see below):
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//! use std::time::Duration;
+//!
+//! mod minutes {
+//!     use serde::{Deserialize, Deserializer, Serializer};
+//!     use std::time::Duration;
+//!
+//!     pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+//!         serializer.serialize_u64(duration.as_secs() / 60)
+//!     }
+//!
+//!     pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+//!         Ok(Duration::from_secs(u64::deserialize(deserializer)? * 60))
+//!     }
+//! }
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         #[serde(with = "minutes", alias = "lock_timeout")]
+//!         pub lock_after: Duration = Duration::from_secs(300) => "lock_after_minutes",
+//!     },
+//!     "alias-doc-prefs"
+//! }
+//!
+//! let dir = tempfile::tempdir().unwrap();
+//! let path = dir.path().join("alias-doc-prefs.toml");
+//! std::fs::write(&path, "lock_timeout = 10\n").unwrap();
+//!
+//! let mut prefs = AppPrefs::load_with_error(dir.path().to_str().unwrap()).unwrap();
+//! assert_eq!(*prefs.get_lock_after(), Duration::from_secs(600));
+//!
+//! prefs.save_lock_after(Duration::from_secs(900)).unwrap();
+//! assert!(std::fs::read_to_string(&path).unwrap().starts_with("lock_after_minutes = 15\n"));
+//! ```
+//!
+//! When renaming a key is all you need, declare the old name as a field option instead:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub dark_mode: bool = false => "dark_mode" (alias "darkMode"),
+//!     },
+//!     "alias-option-doc-prefs"
+//! }
+//!
+//! let dir = tempfile::tempdir().unwrap();
+//! std::fs::write(dir.path().join("alias-option-doc-prefs.toml"), "darkMode = true\n").unwrap();
+//! let prefs = AppPrefs::load_with_error(dir.path().to_str().unwrap()).unwrap();
+//! assert!(*prefs.get_dark_mode());
+//! assert_eq!(AppPrefs::FIELDS[0].aliases, &["darkMode"]);
+//! ```
+//!
+//! `alias = "darkMode"` works too, a field can have several aliases, and with
+//! `#[derive(EasyPrefs)]` the option is written `#[pref(alias = "darkMode")]`. An alias that is
+//! also another field's saved name or alias fails to compile (see [`crate::field::duplicate_key`]).
+//!
+//! Loading renames an aliased key to the field's saved name before anything else looks at the
+//! document, so `strict` doesn't report it, constraints are checked, and the next save writes
+//! the saved name (also with `preserve_formatting`). If the document holds both, the saved name
+//! wins. `get_value()`, `set_value()`, `get_<field>_as_string()`, and `set_<field>_from_str()`
+//! work on the stored form.
+//!
+//! The field's type still needs its own `Serialize` and `Deserialize` implementations, and
+//! value constraints such as `range` expect the value in that form. `rename` can't be given:
+//! the macro sets it from the saved name. Structs with `#[derive(EasyPrefs)]` take the same
+//! attributes on their fields.
+
+use crate::field::FieldInfo;
+use toml::Table;
+
+/// Moves the values stored under a field's aliases in `table` to its saved name, dropping
+/// them if the saved name is already present.
+///
+/// An alias can't be another field's saved name or alias: [`crate::field::duplicate_key`]
+/// rejects that at compile time.
+pub fn rename(table: &mut Table, fields: &[FieldInfo]) {
+    for field in fields {
+        for alias in field.aliases {
+            if let Some(value) = table.remove(*alias) {
+                if !table.contains_key(field.key) {
+                    table.insert(field.key.to_string(), value);
+                }
+            }
+        }
+    }
+}
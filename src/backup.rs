@@ -0,0 +1,117 @@
+//! Backup rotation and corruption recovery (`backups = N`).
+//!
+//! With `backups = N`, every save first copies the stored document to
+//! `<file>.bak` (newest), shifting older copies to `<file>.bak.2` … `<file>.bak.N`. If the
+//! preferences file later fails to deserialize (say, it was half-written when the machine lost
+//! power), loading falls back to the newest backup that loads and reports it through
+//! [`LoadOutcome`]:
+//!
+//! ```rust
+//! use easy_prefs::{easy_prefs, LoadOutcome};
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub theme: String = "light".to_string() => "theme",
+//!     },
+//!     "backup-doc-prefs",
+//!     backups = 3
+//! }
+//!
+//! # let tmp = tempfile::tempdir().unwrap();
+//! # let dir = tmp.path().to_str().unwrap();
+//! let mut prefs = AppPrefs::load_with_error(dir).unwrap();
+//! prefs.save_theme("dark".to_string()).unwrap();
+//! prefs.save_theme("blue".to_string()).unwrap(); // backs up theme = "dark"
+//! let path = prefs.get_preferences_file_path();
+//! drop(prefs);
+//!
+//! std::fs::write(&path, "theme = \"bl").unwrap();
+//! let prefs = AppPrefs::load_with_error(dir).unwrap();
+//! assert_eq!(prefs.get_theme(), "dark");
+//! assert!(matches!(prefs.load_outcome(), LoadOutcome::RecoveredFromBackup { .. }));
+//! ```
+//!
+//! Only documents that parse as TOML are backed up, and a document with the same values as the
+//! newest backup is not stored again, so repeated saves don't push good backups out. Like history
+//! restore points, backups keep references to external blobs, not the blobs.
+
+use crate::field::FieldInfo;
+use crate::generation;
+use crate::integrity;
+use crate::storage::Storage;
+use crate::LoadError;
+
+/// How the values of a loaded struct were obtained.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum LoadOutcome {
+    /// Read from the preferences file, or defaults if there was none.
+    #[default]
+    Loaded,
+    /// The preferences file failed to deserialize and the values were restored from a backup.
+    RecoveredFromBackup {
+        /// Location of the backup that was used.
+        backup: String,
+        /// Why the preferences file could not be loaded.
+        error: String,
+    },
+    /// The preferences file failed its integrity check, no backup passed it, and the defaults
+    /// were loaded (`reset_on_integrity_failure`).
+    ResetToDefaults {
+        /// Why the preferences file could not be loaded.
+        error: String,
+    },
+}
+
+/// Storage key of backup `index` (1 is the newest).
+pub fn backup_key(storage_key: &str, index: usize) -> String {
+    if index == 1 {
+        format!("{storage_key}.bak")
+    } else {
+        format!("{storage_key}.bak.{index}")
+    }
+}
+
+/// Stores `previous`, the document about to be overwritten, as the newest of `count` backups.
+///
+/// Callers only pass documents that parse; a broken file would make a useless backup.
+pub fn rotate(storage: &dyn Storage, storage_key: &str, previous: &str, count: usize) -> Result<(), std::io::Error> {
+    if count == 0 {
+        return Ok(());
+    }
+    let newest = storage.read(&backup_key(storage_key, 1))?;
+    let same = |newest: &str| generation::strip(integrity::strip(newest)) == generation::strip(integrity::strip(previous));
+    if newest.is_some_and(|newest| same(&newest)) {
+        return Ok(());
+    }
+    for index in (1..count).rev() {
+        if let Some(older) = storage.read(&backup_key(storage_key, index))? {
+            storage.write(&backup_key(storage_key, index + 1), &older)?;
+        }
+    }
+    storage.write(&backup_key(storage_key, 1), previous)
+}
+
+/// Returns the newest of `count` backups that `load` accepts, with its location.
+///
+/// Skipped backups are logged with the secret values of `fields` scrubbed.
+pub fn recover<T>(
+    storage: &dyn Storage,
+    storage_key: &str,
+    count: usize,
+    fields: &[FieldInfo],
+    mut load: impl FnMut(&str) -> Result<T, LoadError>,
+) -> Option<(String, T)> {
+    for index in 1..=count {
+        let key = backup_key(storage_key, index);
+        let location = storage.get_path(&key);
+        match storage.read(&key) {
+            Ok(Some(contents)) => match load(&contents) {
+                Ok(loaded) => return Some((location, loaded)),
+                Err(e) => crate::scrub::log(fields, None::<&()>, format_args!("Skipping backup {}: {}", location, e)),
+            },
+            Ok(None) => {}
+            Err(e) => crate::scrub::log(fields, None::<&()>, format_args!("Failed to read backup {}: {}", location, e)),
+        }
+    }
+    None
+}
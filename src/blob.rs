@@ -0,0 +1,111 @@
+//! External blobs: large string values stored next to the preferences (`(external)`).
+//!
+//! An `external` string field keeps its value in a sibling file (storage key
+//! `<key>.<field key>`) and only a reference with a content hash in the main document, so the
+//! preferences file stays small and quick to rewrite:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub custom_css: String = String::new() => "custom_css" (external),
+//!     },
+//!     "blob-doc-prefs"
+//! }
+//!
+//! let mut prefs = AppPrefs::load_testing();
+//! prefs.save_custom_css("body { color: teal; }".repeat(1000)).unwrap();
+//! let contents = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+//! assert!(contents.len() < 200);
+//! ```
+//!
+//! ```toml
+//! [custom_css]
+//! file = "blob-doc-prefs.toml.custom_css"
+//! hash = "5d0c1f29a3e86b74"
+//! ```
+//!
+//! The getter and setter still deal in plain `String`s. A blob is only rewritten when its hash
+//! changes, and it is written before the document referencing it. A blob that doesn't match
+//! its hash (edited by hand, or an interrupted save) is loaded anyway with a warning; a
+//! missing one falls back to the default. The `file` entry is informational: blobs are always
+//! read from the expected key. History restore points keep the reference only, not the blob.
+
+use crate::field::FieldInfo;
+use crate::storage::{content_hash, Storage};
+use toml::{Table, Value};
+
+/// Storage key of the blob for the field saved under `field_key`.
+pub fn blob_key(storage_key: &str, field_key: &str) -> String {
+    format!("{storage_key}.{field_key}")
+}
+
+fn stored_hash(reference: &Value) -> Option<&str> {
+    reference.get("hash")?.as_str()
+}
+
+/// Writes changed external values of a table being saved to their blobs and replaces them with
+/// references. `existing` is the currently stored document, parsed, used to skip unchanged
+/// blobs.
+pub fn extract(
+    table: &mut Table,
+    fields: &[FieldInfo],
+    storage: &dyn Storage,
+    storage_key: &str,
+    existing: Option<&Table>,
+) -> Result<(), std::io::Error> {
+    let mut external = fields.iter().filter(|field| field.options.external).peekable();
+    if external.peek().is_none() {
+        return Ok(());
+    }
+    let empty = Table::new();
+    let existing = existing.unwrap_or(&empty);
+
+    for field in external {
+        let Some(Value::String(text)) = table.get(field.key) else {
+            continue;
+        };
+        let hash = content_hash(text);
+        let key = blob_key(storage_key, field.key);
+        if existing.get(field.key).and_then(stored_hash) != Some(hash.as_str()) {
+            storage.write(&key, text)?;
+        }
+        let mut reference = Table::new();
+        reference.insert("file".to_string(), Value::String(key));
+        reference.insert("hash".to_string(), Value::String(hash));
+        table.insert(field.key.to_string(), Value::Table(reference));
+    }
+    Ok(())
+}
+
+/// Replaces blob references in a stored table with the blob contents.
+///
+/// Plain string values (saved before the field became external) are kept; the next save moves
+/// them into a blob.
+pub fn inject(
+    table: &mut Table,
+    fields: &[FieldInfo],
+    storage: &dyn Storage,
+    storage_key: &str,
+) -> Result<(), std::io::Error> {
+    for field in fields.iter().filter(|field| field.options.external) {
+        let Some(reference @ Value::Table(_)) = table.get(field.key) else {
+            continue;
+        };
+        let hash = stored_hash(reference).map(str::to_owned);
+        match storage.read(&blob_key(storage_key, field.key))? {
+            Some(text) => {
+                if hash.as_deref() != Some(content_hash(&text).as_str()) {
+                    crate::scrub::log(fields, None::<&()>, format_args!("Blob for '{}' does not match its recorded hash, loading it anyway", field.key));
+                }
+                table.insert(field.key.to_string(), Value::String(text));
+            }
+            None => {
+                crate::scrub::log(fields, None::<&()>, format_args!("Blob for '{}' is missing, using default", field.key));
+                table.remove(field.key);
+            }
+        }
+    }
+    Ok(())
+}
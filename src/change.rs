@@ -0,0 +1,122 @@
+//! Change events with before/after values (`on_change = fn(&ChangeSet)`).
+//!
+//! `watch_<field>()` sees new values but not where they came from. The `on_change` struct
+//! option instead calls a function once for every field a save or reload changed, with the
+//! field's saved name, its old and new value, and the [`SaveSource`] — e.g. to count settings
+//! the user changed while ignoring values picked up from another process:
+//!
+//! ```rust
+//! use easy_prefs::change::{ChangeSet, SaveSource};
+//! use easy_prefs::easy_prefs;
+//!
+//! fn track(change: &ChangeSet) {
+//!     if matches!(change.source, SaveSource::Field | SaveSource::EditGuard) {
+//!         println!("user changed {} from {:?} to {:?}", change.field, change.old, change.new);
+//!     }
+//! }
+//!
+//! easy_prefs! {
+//!     pub struct TrackedPrefs {
+//!         pub theme: String = "light".to_string() => "theme",
+//!     },
+//!     "change-doc-prefs",
+//!     on_change = track
+//! }
+//!
+//! let mut prefs = TrackedPrefs::load_testing();
+//! prefs.save_theme("dark".to_string()).unwrap();
+//! ```
+//!
+//! Values are compared in their saved form against the values of the last load, save, or
+//! reload of the instance (the defaults for `load_testing()` and `load_in_memory()`).
+//! Events are sent after the values were written; a failed save sends none. Secret values are
+//! reported as [`crate::secrets::REDACTED`].
+
+use crate::field::FieldInfo;
+use std::sync::Mutex;
+use toml::{Table, Value};
+
+/// Function called for every changed field.
+pub type OnChange = fn(&ChangeSet);
+
+/// What wrote or loaded a change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SaveSource {
+    /// A single-field method: `save_<field>()`, `push_<field>()`, `insert_<field>()`,
+    /// `set_<field>_from_str()`, `set_<field>_temporarily()`, `reset_<field>()`, and the
+    /// `DynamicPrefs` setters used by the IPC, D-Bus, MQTT, and live sync integrations.
+    Field,
+    /// An edit guard, including `transaction()`.
+    EditGuard,
+    /// A direct `save()` call.
+    Save,
+    /// `reset_to_defaults()`.
+    Reset,
+    /// Values carried over from another document or snapshot: `restore()`, `import_toml()`,
+    /// `apply_toml_patch()`, and `apply()`.
+    Migration,
+    /// `sync()` taking values from the remote copy.
+    Sync,
+    /// `reload()` or `reload_fields()` picking up values stored elsewhere.
+    Reload,
+}
+
+/// A field whose value changed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeSet {
+    /// Saved name of the field.
+    pub field: &'static str,
+    /// Value before the change, `None` for an absent value (e.g. a `None` option).
+    pub old: Option<Value>,
+    /// Value after the change.
+    pub new: Option<Value>,
+    /// What made the change.
+    pub source: SaveSource,
+}
+
+/// The values last reported to `on_change`, held by every loaded instance.
+#[derive(Debug, Default)]
+pub struct Tracker {
+    last: Mutex<Option<Table>>,
+}
+
+impl Tracker {
+    /// Remembers `values` (keyed by saved name) as the current state.
+    pub fn record(&self, values: Table) {
+        *self.last.lock().unwrap_or_else(|e| e.into_inner()) = Some(values);
+    }
+
+    /// Calls `hook` for every field whose value in `values` differs from the recorded state
+    /// (or from `defaults()` if nothing was recorded), then records `values`.
+    pub fn report(
+        &self,
+        hook: OnChange,
+        fields: &[FieldInfo],
+        values: Table,
+        defaults: impl FnOnce() -> Table,
+        source: SaveSource,
+    ) {
+        let previous = {
+            let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+            last.replace(values.clone())
+        };
+        let previous = previous.unwrap_or_else(defaults);
+        for field in fields {
+            let (old, new) = (previous.get(field.key), values.get(field.key));
+            if old == new {
+                continue;
+            }
+            let shown = |value: Option<&Value>| match value {
+                Some(_) if field.options.secret => Some(Value::String(crate::secrets::REDACTED.to_string())),
+                value => value.cloned(),
+            };
+            hook(&ChangeSet {
+                field: field.key,
+                old: shown(old),
+                new: shown(new),
+                source,
+            });
+        }
+    }
+}
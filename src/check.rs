@@ -0,0 +1,199 @@
+//! Startup self-check: a structured health report for an "about" or diagnostics screen.
+//!
+//! `self_check(directory)` inspects the stored preferences without loading them (so it doesn't
+//! claim the single instance) and reports whether the storage location is writable, the file
+//! reads and parses, its integrity footer matches (with `integrity = true`), backups are
+//! available (with `backups = N`), and there is free space left:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub theme: String = "light".to_string() => "theme",
+//!     },
+//!     "check-doc-prefs"
+//! }
+//!
+//! # let tmp = tempfile::tempdir().unwrap();
+//! # let dir = tmp.path().to_str().unwrap();
+//! let report = AppPrefs::self_check(dir);
+//! assert!(report.is_healthy());
+//! println!("{report}");
+//! # let path = format!("{dir}/check-doc-prefs.toml");
+//!
+//! std::fs::write(&path, "theme = ").unwrap();
+//! let report = AppPrefs::self_check(dir);
+//! assert!(!report.is_healthy());
+//! ```
+//!
+//! Checks that don't apply (integrity without the option, free space on backends that can't
+//! measure it) are left out of the report.
+
+use crate::backup::backup_key;
+use crate::integrity;
+use crate::storage::{EnvironmentQuirk, Storage};
+use crate::LoadError;
+
+/// Free space below which the `free space` check warns.
+pub const LOW_SPACE_BYTES: u64 = 1024 * 1024;
+
+/// Result of a single check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CheckStatus {
+    /// Nothing to worry about.
+    Ok,
+    /// The preferences work, but something deserves attention.
+    Warning,
+    /// The preferences can't be loaded or saved as they are.
+    Failed,
+}
+
+impl std::fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ok => write!(f, "ok"),
+            Self::Warning => write!(f, "warning"),
+            Self::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// One line of a [`CheckReport`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckItem {
+    /// What was checked (`"permissions"`, `"readable"`, `"integrity"`, `"parse"`, `"backups"`,
+    /// or `"free space"`).
+    pub name: &'static str,
+    /// Outcome of the check.
+    pub status: CheckStatus,
+    /// Human-readable explanation.
+    pub detail: String,
+}
+
+/// Outcome of `self_check()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckReport {
+    /// Resolved path (native) or key (WASM) of the preferences data.
+    pub location: String,
+    /// The checks that ran, in order.
+    pub items: Vec<CheckItem>,
+}
+
+impl CheckReport {
+    /// The worst status of all checks.
+    pub fn status(&self) -> CheckStatus {
+        self.items.iter().map(|item| item.status).max().unwrap_or(CheckStatus::Ok)
+    }
+
+    /// Whether no check failed (warnings are allowed).
+    pub fn is_healthy(&self) -> bool {
+        self.status() != CheckStatus::Failed
+    }
+
+    /// The check called `name`, if it ran.
+    pub fn item(&self, name: &str) -> Option<&CheckItem> {
+        self.items.iter().find(|item| item.name == name)
+    }
+
+    fn push(&mut self, name: &'static str, status: CheckStatus, detail: impl Into<String>) {
+        self.items.push(CheckItem { name, status, detail: detail.into() });
+    }
+}
+
+impl std::fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "location: {}", self.location)?;
+        for item in &self.items {
+            write!(f, "\n[{}] {}: {}", item.status, item.name, item.detail)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks the preferences stored under `storage_key`.
+///
+/// `load` deserializes a stored document (without its integrity footer) the way loading does;
+/// `integrity` and `backups` are the struct's options.
+pub fn run(
+    storage: &dyn Storage,
+    storage_key: &str,
+    integrity: bool,
+    backups: usize,
+    mut load: impl FnMut(&str) -> Result<(), LoadError>,
+) -> CheckReport {
+    let mut report = CheckReport { location: storage.get_path(storage_key), items: Vec::new() };
+
+    let environment = storage.environment_report(storage_key);
+    let problem = environment.quirks.iter().find(|quirk| {
+        matches!(
+            quirk,
+            EnvironmentQuirk::ReadOnly | EnvironmentQuirk::NotADirectory | EnvironmentQuirk::StorageUnavailable(_)
+        )
+    });
+    if let Some(problem) = problem {
+        report.push("permissions", CheckStatus::Failed, problem.to_string());
+    } else if !storage.is_persistent() {
+        report.push("permissions", CheckStatus::Warning, "in-memory storage, changes are lost on exit");
+    } else if environment.quirks.contains(&EnvironmentQuirk::DirectoryMissing) {
+        report.push("permissions", CheckStatus::Ok, "directory is created on the first save");
+    } else {
+        report.push("permissions", CheckStatus::Ok, "writable");
+    }
+
+    let contents = match storage.read(storage_key) {
+        Ok(Some(contents)) => {
+            report.push("readable", CheckStatus::Ok, format!("{} bytes", contents.len()));
+            Some(contents)
+        }
+        Ok(None) => {
+            report.push("readable", CheckStatus::Ok, "nothing saved yet, defaults apply");
+            None
+        }
+        Err(e) => {
+            report.push("readable", CheckStatus::Failed, e.to_string());
+            None
+        }
+    };
+
+    if let Some(contents) = &contents {
+        if integrity {
+            match integrity::verify(contents) {
+                Ok(_) => report.push("integrity", CheckStatus::Ok, "footer matches"),
+                Err(e) => report.push("integrity", CheckStatus::Failed, e.to_string()),
+            }
+        }
+        match load(integrity::strip(contents)) {
+            Ok(()) => report.push("parse", CheckStatus::Ok, "values load"),
+            Err(e) => report.push("parse", CheckStatus::Failed, e.to_string()),
+        }
+    }
+
+    if backups == 0 {
+        report.push("backups", CheckStatus::Ok, "disabled");
+    } else {
+        let (mut found, mut usable) = (0, 0);
+        for index in 1..=backups {
+            let Ok(Some(backup)) = storage.read(&backup_key(storage_key, index)) else {
+                continue;
+            };
+            found += 1;
+            if (!integrity || integrity::verify(&backup).is_ok()) && load(integrity::strip(&backup)).is_ok() {
+                usable += 1;
+            }
+        }
+        match (found, usable) {
+            (0, _) => report.push("backups", CheckStatus::Ok, "none saved yet"),
+            (found, 0) => report.push("backups", CheckStatus::Warning, format!("none of {found} backups load")),
+            (found, usable) => report.push("backups", CheckStatus::Ok, format!("{usable} of {found} backups load")),
+        }
+    }
+
+    if let Some(available) = storage.available_space() {
+        let detail = format!("{:.1} MiB available", available as f64 / (1024.0 * 1024.0));
+        let status = if available < LOW_SPACE_BYTES { CheckStatus::Warning } else { CheckStatus::Ok };
+        report.push("free space", status, detail);
+    }
+
+    report
+}
@@ -0,0 +1,111 @@
+//! Load-time repair of sloppy value types.
+//!
+//! Files edited by hand or written by other tools often store `"true"` instead of `true`,
+//! `"42"` instead of `42`, or `3` where a float is expected. When `lenient_types` is enabled
+//! (per field or per struct), such values are converted to the type of the field's default
+//! before deserialization instead of failing the whole load.
+//!
+//! Likewise, `lenient_keys` maps near-miss keys such as `Dark_Mode` or `dark-mode` to the
+//! declared saved name (`dark_mode`) on load; the next save writes the declared name.
+
+use crate::field::FieldInfo;
+use toml::{Table, Value};
+
+/// Coerces values in `table` towards the types found in `defaults`.
+///
+/// Only fields with `lenient_types` set (or every field, if `all_fields` is true) are touched,
+/// and values that cannot be converted are left alone so normal deserialization reports them.
+pub fn coerce_table(table: &mut Table, defaults: &Table, fields: &[FieldInfo], all_fields: bool) {
+    for field in fields {
+        if !(all_fields || field.options.lenient_types) {
+            continue;
+        }
+        let (Some(value), Some(default)) = (table.get_mut(field.key), defaults.get(field.key))
+        else {
+            continue;
+        };
+        if let Some(coerced) = coerce_value(value, default) {
+            *value = coerced;
+        }
+    }
+}
+
+/// Converts `value` to the variant of `target`, if a sensible conversion exists.
+pub fn coerce_value(value: &Value, target: &Value) -> Option<Value> {
+    match (target, value) {
+        (Value::Boolean(_), Value::String(s)) => parse_bool(s).map(Value::Boolean),
+        (Value::Boolean(_), Value::Integer(i)) if *i == 0 || *i == 1 => {
+            Some(Value::Boolean(*i == 1))
+        }
+        (Value::Integer(_), Value::String(s)) => s.trim().parse().ok().map(Value::Integer),
+        (Value::Integer(_), Value::Float(f)) if f.fract() == 0.0 => Some(Value::Integer(*f as i64)),
+        (Value::Float(_), Value::Integer(i)) => Some(Value::Float(*i as f64)),
+        (Value::Float(_), Value::String(s)) => s.trim().parse().ok().map(Value::Float),
+        (Value::String(_), Value::Integer(i)) => Some(Value::String(i.to_string())),
+        (Value::String(_), Value::Float(f)) => Some(Value::String(f.to_string())),
+        (Value::String(_), Value::Boolean(b)) => Some(Value::String(b.to_string())),
+        _ => None,
+    }
+}
+
+fn parse_bool(s: &str) -> Option<bool> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Some(true),
+        "false" | "0" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// A stored key that was mapped to a declared saved name by `lenient_keys`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyCorrection {
+    /// The key as stored.
+    pub found: String,
+    /// The saved name it was mapped to.
+    pub key: &'static str,
+}
+
+impl std::fmt::Display for KeyCorrection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "'{}' read as '{}'", self.found, self.key)
+    }
+}
+
+/// Returns the saved name that `found` is a near miss of: equal ignoring case, `-`, and `_`.
+/// Exact matches return `None`.
+pub fn matching_key(found: &str, fields: &[FieldInfo]) -> Option<&'static str> {
+    let normalized = normalize_key(found);
+    fields
+        .iter()
+        .find(|field| field.key != found && normalize_key(field.key) == normalized)
+        .map(|field| field.key)
+}
+
+/// Renames near-miss top-level keys in `table` to the saved names they match.
+///
+/// A key is left alone if the table also holds the exact saved name; if several near misses
+/// match the same field, the first one (in key order) is used.
+pub fn normalize_keys(table: &mut Table, fields: &[FieldInfo]) -> Vec<KeyCorrection> {
+    let mut corrections = Vec::new();
+    let found: Vec<String> = table.keys().cloned().collect();
+    for found in found {
+        let Some(key) = matching_key(&found, fields) else {
+            continue;
+        };
+        if table.contains_key(key) {
+            continue;
+        }
+        if let Some(value) = table.remove(&found) {
+            table.insert(key.to_string(), value);
+            corrections.push(KeyCorrection { found, key });
+        }
+    }
+    corrections
+}
+
+fn normalize_key(key: &str) -> String {
+    key.chars()
+        .filter(|c| *c != '-' && *c != '_')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
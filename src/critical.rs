@@ -0,0 +1,124 @@
+//! Escalation of failed saves touching critical fields (`(critical)`).
+//!
+//! A failed save normally surfaces as a `SaveError`, which is easy to drop: an edit guard only
+//! prints it, and `let _ = prefs.save_x(...)` ignores it. For fields whose loss matters (say,
+//! whether monitoring is enabled), mark them `(critical)`. When a write fails and the document
+//! it meant to store changes a critical field, the struct's `on_critical_failure` option
+//! decides what happens:
+//!
+//! - [`Escalation::Panic`] (the default) panics with the storage path and error.
+//! - [`Escalation::RetryUntilSuccess`] blocks, retrying the write every `interval` until it
+//!   succeeds; the save then returns `Ok`.
+//! - [`Escalation::Callback`] calls a function with a [`CriticalFailure`] (to alert the user or
+//!   report to a server), then returns the error as usual.
+//!
+//! ```rust
+//! use easy_prefs::critical::{CriticalFailure, Escalation};
+//! use easy_prefs::easy_prefs;
+//!
+//! fn alert(failure: &CriticalFailure) {
+//!     eprintln!("could not persist {:?}: {}", failure.keys, failure.error);
+//! }
+//!
+//! easy_prefs! {
+//!     pub struct MonitorPrefs {
+//!         pub monitoring: bool = true => "monitoring" (critical),
+//!         pub theme: String = "light".to_string() => "theme",
+//!     },
+//!     "critical-doc-prefs",
+//!     on_critical_failure = Escalation::Callback(alert)
+//! }
+//!
+//! let mut prefs = MonitorPrefs::load_testing();
+//! prefs.save_monitoring(false).unwrap();
+//! ```
+//!
+//! Failed saves that leave every critical field as stored are not escalated. Whether a field
+//! changed is judged against the stored document, so a first save (nothing stored yet) counts
+//! as changing all critical fields.
+
+use crate::field::FieldInfo;
+use crate::storage::Storage;
+use std::time::Duration;
+use toml::Table;
+
+/// What a failed save touching a critical field does.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Escalation {
+    /// Panic with the storage path and error.
+    #[default]
+    Panic,
+    /// Block and retry the write every `interval` until it succeeds. Not available on WASM,
+    /// where it panics like [`Escalation::Panic`].
+    RetryUntilSuccess {
+        /// Pause between attempts.
+        interval: Duration,
+    },
+    /// Call the function, then return the error from the save.
+    Callback(fn(&CriticalFailure)),
+}
+
+/// A failed write that would have changed critical fields.
+#[derive(Debug)]
+pub struct CriticalFailure<'a> {
+    /// Saved keys of the critical fields whose new values were not stored.
+    pub keys: Vec<&'static str>,
+    /// Storage path (native) or key (WASM) of the preferences.
+    pub path: String,
+    /// Error of the failed write.
+    pub error: &'a std::io::Error,
+}
+
+/// Writes `stored` to `key`, escalating a failure if `table` (the values being saved) changes
+/// a critical field relative to `existing` (the parsed stored document).
+pub fn write(
+    storage: &dyn Storage,
+    key: &str,
+    stored: &str,
+    table: &Table,
+    existing: Option<&Table>,
+    fields: &[FieldInfo],
+    escalation: Escalation,
+) -> Result<(), std::io::Error> {
+    let Err(error) = storage.write(key, stored) else {
+        return Ok(());
+    };
+    let keys = changed_keys(table, existing, fields);
+    if keys.is_empty() {
+        return Err(error);
+    }
+    let path = storage.get_path(key);
+    match escalation {
+        Escalation::Panic => panic!("failed to save critical preferences {keys:?} to {path}: {error}"),
+        #[cfg(not(target_arch = "wasm32"))]
+        Escalation::RetryUntilSuccess { interval } => {
+            crate::scrub::log(fields, None::<&()>, format_args!("Failed to save critical preferences {keys:?} to {path}: {error}; retrying"));
+            loop {
+                std::thread::sleep(interval);
+                if storage.write(key, stored).is_ok() {
+                    return Ok(());
+                }
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        Escalation::RetryUntilSuccess { .. } => {
+            panic!("failed to save critical preferences {keys:?} to {path}: {error}")
+        }
+        Escalation::Callback(callback) => {
+            callback(&CriticalFailure { keys, path, error: &error });
+            Err(error)
+        }
+    }
+}
+
+/// Returns the keys of critical fields whose value in `table` differs from `existing`.
+fn changed_keys(table: &Table, existing: Option<&Table>, fields: &[FieldInfo]) -> Vec<&'static str> {
+    let critical = fields.iter().filter(|field| field.options.critical);
+    let Some(stored) = existing else {
+        return critical.map(|field| field.key).collect();
+    };
+    critical
+        .filter(|field| table.get(field.key) != stored.get(field.key))
+        .map(|field| field.key)
+        .collect()
+}
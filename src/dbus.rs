@@ -0,0 +1,372 @@
+//! Exposes preferences as D-Bus properties on Linux (feature `dbus`).
+//!
+//! Each readable field becomes a property named after the Rust field, on an interface chosen by
+//! the host app. Properties are served through the standard `org.freedesktop.DBus.Properties`
+//! interface, so desktop tooling works out of the box:
+//!
+//! ```text
+//! busctl --user get-property com.example.App /com/example/App/Prefs com.example.App.Prefs dark_mode
+//! busctl --user set-property com.example.App /com/example/App/Prefs com.example.App.Prefs dark_mode b true
+//! ```
+//!
+//! Booleans, integers, floats, and strings map to D-Bus `b`, `x`, `d`, and `s`; other values
+//! (arrays, tables, unset `Option`s) are exposed as strings in the syntax of [`crate::parse`].
+//! Access is decided by an [`Access`] policy as for [`crate::ipc`]: read-only with secrets
+//! hidden by default, and readable secrets are always redacted.
+//!
+//! `PropertiesChanged` is emitted after every successful `Set`; call
+//! [`DbusServer::notify_changes`] after editing the preferences in-process.
+//!
+//! The server handles every method call for its object path on the given connection, so give
+//! it a connection that doesn't serve the same path through zbus' `ObjectServer`.
+
+pub use crate::dynamic::Access;
+
+use crate::dynamic::{default_access, AccessPolicy, DynamicPrefs};
+use crate::field::FieldInfo;
+use crate::secrets::REDACTED;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use zbus::blocking::{Connection, MessageIterator};
+use zbus::fdo;
+use zbus::message::{Header, Message, Type as MessageType};
+use zbus::zvariant::{OwnedValue, Value};
+
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+const INTROSPECTABLE_INTERFACE: &str = "org.freedesktop.DBus.Introspectable";
+
+/// Serves one preferences instance as a D-Bus object.
+///
+/// Cloning is cheap; clones share the preferences, connection, and change tracking.
+pub struct DbusServer<P> {
+    prefs: Arc<Mutex<P>>,
+    fields: &'static [FieldInfo],
+    connection: Connection,
+    interface: String,
+    path: String,
+    access: Arc<AccessPolicy>,
+    snapshot: Arc<Mutex<HashMap<&'static str, String>>>,
+}
+
+impl<P> Clone for DbusServer<P> {
+    fn clone(&self) -> Self {
+        Self {
+            prefs: self.prefs.clone(),
+            fields: self.fields,
+            connection: self.connection.clone(),
+            interface: self.interface.clone(),
+            path: self.path.clone(),
+            access: self.access.clone(),
+            snapshot: self.snapshot.clone(),
+        }
+    }
+}
+
+fn lock<T: ?Sized>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl<P: DynamicPrefs + Send + 'static> DbusServer<P> {
+    /// Creates a server for `prefs` on `connection`.
+    ///
+    /// The object path is derived from `interface`: `com.example.App.Prefs` is served at
+    /// `/com/example/App/Prefs`.
+    pub fn new(prefs: Arc<Mutex<P>>, connection: Connection, interface: &str) -> Self {
+        let fields = lock(&prefs).fields();
+        let server = Self {
+            prefs,
+            fields,
+            connection,
+            interface: interface.to_string(),
+            path: format!("/{}", interface.replace('.', "/")),
+            access: Arc::new(default_access),
+            snapshot: Arc::new(Mutex::new(HashMap::new())),
+        };
+        *lock(&server.snapshot) = server.current_values();
+        server
+    }
+
+    /// Connects to the session bus, claims `bus_name`, and creates a server on that connection.
+    pub fn session(prefs: Arc<Mutex<P>>, bus_name: &str, interface: &str) -> zbus::Result<Self> {
+        let connection = zbus::blocking::connection::Builder::session()?
+            .name(bus_name.to_string())?
+            .build()?;
+        Ok(Self::new(prefs, connection, interface))
+    }
+
+    /// Replaces the access policy, which is consulted for every request.
+    pub fn with_access(mut self, policy: impl Fn(&FieldInfo) -> Access + Send + Sync + 'static) -> Self {
+        self.access = Arc::new(policy);
+        *lock(&self.snapshot) = self.current_values();
+        self
+    }
+
+    /// The object path the preferences are served at.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Handles method calls on a background thread until the connection closes.
+    pub fn start(&self) -> std::thread::JoinHandle<()> {
+        let server = self.clone();
+        let messages = MessageIterator::from(&server.connection);
+        std::thread::spawn(move || {
+            for message in messages {
+                match message {
+                    Ok(message) => server.handle(&message),
+                    Err(e) => crate::scrub::log(server.fields, None::<&()>, format_args!("D-Bus receive failed: {e}")),
+                }
+            }
+        })
+    }
+
+    /// Emits `PropertiesChanged` for every readable field whose value differs from the last
+    /// notification.
+    pub fn notify_changes(&self) {
+        let current = self.current_values();
+        let changed: HashMap<&str, Value<'_>> = {
+            let mut snapshot = lock(&self.snapshot);
+            let changed = self
+                .fields
+                .iter()
+                .filter_map(|field| {
+                    let value = current.get(field.key)?;
+                    (snapshot.get(field.key) != Some(value)).then(|| (field.name, to_variant(field, value)))
+                })
+                .collect();
+            *snapshot = current.clone();
+            changed
+        };
+        if changed.is_empty() {
+            return;
+        }
+        let body = (self.interface.as_str(), changed, Vec::<&str>::new());
+        let result = self.connection.emit_signal(
+            None::<&str>,
+            self.path.as_str(),
+            PROPERTIES_INTERFACE,
+            "PropertiesChanged",
+            &body,
+        );
+        if let Err(e) = result {
+            crate::scrub::log(self.fields, None::<&()>, format_args!("Failed to emit PropertiesChanged: {e}"));
+        }
+    }
+
+    fn handle(&self, message: &Message) {
+        let header = message.header();
+        if header.message_type() != MessageType::MethodCall
+            || header.path().map(|p| p.as_str()) != Some(self.path.as_str())
+        {
+            return;
+        }
+        let interface = header.interface().map(|i| i.as_str()).unwrap_or_default();
+        let member = header.member().map(|m| m.as_str()).unwrap_or_default();
+
+        let result = match (interface, member) {
+            (PROPERTIES_INTERFACE, "Get") => self.get(message, &header),
+            (PROPERTIES_INTERFACE, "GetAll") => self.get_all(message, &header),
+            (PROPERTIES_INTERFACE, "Set") => self.set(message, &header),
+            (INTROSPECTABLE_INTERFACE, "Introspect") => self.reply(&header, &self.introspect()),
+            _ => Err(fdo::Error::UnknownMethod(format!("unknown method {interface}.{member}"))),
+        };
+        if let Err(e) = result {
+            if let Err(e) = self.connection.reply_dbus_error(&header, e) {
+                crate::scrub::log(self.fields, None::<&()>, format_args!("D-Bus reply failed: {e}"));
+            }
+        }
+    }
+
+    fn get(&self, message: &Message, header: &Header<'_>) -> fdo::Result<()> {
+        let (interface, name): (String, String) = deserialize(message)?;
+        self.check_interface(&interface)?;
+        let field = self.field(&name)?;
+        let value = lock(&self.prefs).get_field_string(field.key).unwrap_or_default();
+        self.reply(header, &to_variant(field, &value))
+    }
+
+    fn get_all(&self, message: &Message, header: &Header<'_>) -> fdo::Result<()> {
+        let (interface,): (String,) = deserialize(message)?;
+        self.check_interface(&interface)?;
+        let values = self.current_values();
+        let properties: HashMap<&str, Value<'_>> = self
+            .fields
+            .iter()
+            .filter_map(|field| Some((field.name, to_variant(field, values.get(field.key)?))))
+            .collect();
+        self.reply(header, &properties)
+    }
+
+    fn set(&self, message: &Message, header: &Header<'_>) -> fdo::Result<()> {
+        let (interface, name, value): (String, String, OwnedValue) = deserialize(message)?;
+        self.check_interface(&interface)?;
+        let field = self.field(&name)?;
+        if (self.access)(field) != Access::ReadWrite {
+            return Err(fdo::Error::PropertyReadOnly(format!("'{name}' is read-only")));
+        }
+        let input = from_variant(&value)
+            .ok_or_else(|| fdo::Error::InvalidArgs(format!("unsupported type for '{name}'")))?;
+        lock(&self.prefs)
+            .set_field_from_str(field.key, &input)
+            .map_err(|e| fdo::Error::InvalidArgs(e.to_string()))?;
+        self.reply(header, &())?;
+        self.notify_changes();
+        Ok(())
+    }
+
+    fn reply<B>(&self, header: &Header<'_>, body: &B) -> fdo::Result<()>
+    where
+        B: serde::Serialize + zbus::zvariant::DynamicType,
+    {
+        self.connection
+            .reply(header, body)
+            .map_err(|e| fdo::Error::Failed(e.to_string()))
+    }
+
+    fn check_interface(&self, interface: &str) -> fdo::Result<()> {
+        // An empty interface name means "any interface" for Get.
+        if interface.is_empty() || interface == self.interface {
+            Ok(())
+        } else {
+            Err(fdo::Error::UnknownInterface(format!("unknown interface '{interface}'")))
+        }
+    }
+
+    /// Looks up a property by field name, treating hidden fields as unknown.
+    fn field(&self, name: &str) -> fdo::Result<&'static FieldInfo> {
+        self.fields
+            .iter()
+            .find(|field| field.name == name && (self.access)(field) != Access::Hidden)
+            .ok_or_else(|| fdo::Error::UnknownProperty(format!("unknown property '{name}'")))
+    }
+
+    /// Unredacted values of every field that is not hidden.
+    fn current_values(&self) -> HashMap<&'static str, String> {
+        let prefs = lock(&self.prefs);
+        self.fields
+            .iter()
+            .filter(|field| (self.access)(field) != Access::Hidden)
+            .filter_map(|field| Some((field.key, prefs.get_field_string(field.key)?)))
+            .collect()
+    }
+
+    fn introspect(&self) -> String {
+        let values = self.current_values();
+        let mut properties = String::new();
+        for field in self.fields {
+            let Some(value) = values.get(field.key) else {
+                continue;
+            };
+            let access = match (self.access)(field) {
+                Access::ReadWrite => "readwrite",
+                _ => "read",
+            };
+            let signature = to_variant(field, value).value_signature().to_string();
+            let description = field.description();
+            if description.is_empty() {
+                properties.push_str(&format!(
+                    "    <property name=\"{}\" type=\"{signature}\" access=\"{access}\"/>\n",
+                    field.name
+                ));
+            } else {
+                properties.push_str(&format!(
+                    "    <property name=\"{}\" type=\"{signature}\" access=\"{access}\">\n      \
+                     <annotation name=\"org.freedesktop.DBus.DocString\" value=\"{}\"/>\n    </property>\n",
+                    field.name,
+                    escape_xml(&description)
+                ));
+            }
+        }
+        format!(
+            r#"<!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">
+<node>
+  <interface name="{INTROSPECTABLE_INTERFACE}">
+    <method name="Introspect"><arg name="xml" type="s" direction="out"/></method>
+  </interface>
+  <interface name="{PROPERTIES_INTERFACE}">
+    <method name="Get">
+      <arg name="interface" type="s" direction="in"/>
+      <arg name="name" type="s" direction="in"/>
+      <arg name="value" type="v" direction="out"/>
+    </method>
+    <method name="GetAll">
+      <arg name="interface" type="s" direction="in"/>
+      <arg name="properties" type="a{{sv}}" direction="out"/>
+    </method>
+    <method name="Set">
+      <arg name="interface" type="s" direction="in"/>
+      <arg name="name" type="s" direction="in"/>
+      <arg name="value" type="v" direction="in"/>
+    </method>
+    <signal name="PropertiesChanged">
+      <arg name="interface" type="s"/>
+      <arg name="changed" type="a{{sv}}"/>
+      <arg name="invalidated" type="as"/>
+    </signal>
+  </interface>
+  <interface name="{}">
+{properties}  </interface>
+</node>
+"#,
+            self.interface
+        )
+    }
+}
+
+fn deserialize<B>(message: &Message) -> fdo::Result<B>
+where
+    B: serde::de::DeserializeOwned + zbus::zvariant::Type,
+{
+    message
+        .body()
+        .deserialize()
+        .map_err(|e| fdo::Error::InvalidArgs(e.to_string()))
+}
+
+/// Converts a formatted field value to a D-Bus value, redacting secrets.
+fn to_variant(field: &FieldInfo, value: &str) -> Value<'static> {
+    if field.options.secret {
+        return Value::from(REDACTED.to_string());
+    }
+    match crate::parse::parse_toml_value(value) {
+        Some(toml::Value::Boolean(b)) => Value::from(b),
+        Some(toml::Value::Integer(i)) => Value::from(i),
+        Some(toml::Value::Float(f)) => Value::from(f),
+        _ => Value::from(value.to_string()),
+    }
+}
+
+/// Converts a D-Bus value to input for [`DynamicPrefs::set_field_from_str`].
+fn from_variant(value: &Value<'_>) -> Option<String> {
+    Some(match value {
+        Value::Bool(b) => b.to_string(),
+        Value::U8(n) => n.to_string(),
+        Value::I16(n) => n.to_string(),
+        Value::U16(n) => n.to_string(),
+        Value::I32(n) => n.to_string(),
+        Value::U32(n) => n.to_string(),
+        Value::I64(n) => n.to_string(),
+        Value::U64(n) => n.to_string(),
+        Value::F64(n) => n.to_string(),
+        Value::Str(s) => s.to_string(),
+        Value::Value(inner) => return from_variant(inner),
+        _ => return None,
+    })
+}
+
+/// Escapes text for an XML attribute value.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\n' => escaped.push_str("&#10;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
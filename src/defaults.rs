@@ -0,0 +1,85 @@
+//! Shipped defaults: a TOML document embedded in the binary whose values replace the
+//! per-field defaults (`defaults = include_str!("defaults.toml")`).
+//!
+//! Product teams can tune defaults by editing the document instead of Rust code. Fields the
+//! document doesn't mention keep their declared default, and stored values still win:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub theme: String = "light".to_string() => "theme",
+//!         pub font_size: i32 = 14 => "font_size",
+//!     },
+//!     "shipped-defaults-doc-prefs",
+//!     defaults = "theme = \"solarized\"\n" // usually include_str!("defaults.toml")
+//! }
+//!
+//! let mut prefs = AppPrefs::load_testing();
+//! assert_eq!(prefs.get_theme(), "solarized");
+//! assert_eq!(*prefs.get_font_size(), 14);
+//! assert!(prefs.is_default_theme());
+//!
+//! prefs.save_theme("dark".to_string()).unwrap();
+//! prefs.reset_theme().unwrap();
+//! assert_eq!(prefs.get_theme(), "solarized");
+//! ```
+//!
+//! The shipped values apply everywhere a default is used: `Default`, missing keys on load
+//! (including keys missing from a section), `reset_<field>()`, `is_default_<field>()`, and
+//! `reset_to_defaults()`. Values that fail the field's constraints are ignored. A document that
+//! doesn't parse makes loading fail with `LoadError::DeserializationError`.
+
+use crate::field::FieldInfo;
+use serde::de::DeserializeOwned;
+use toml::{Table, Value};
+
+/// Location reported for errors in the shipped defaults.
+pub const LOCATION: &str = "shipped defaults";
+
+/// Parses the shipped defaults document.
+pub fn parse(document: &str) -> Result<Table, toml::de::Error> {
+    toml::from_str(document)
+}
+
+/// Returns the shipped default of the field saved under `key`, if `document` has one that
+/// deserializes and passes the field's constraints.
+pub fn field_default<V: DeserializeOwned>(document: Option<&str>, fields: &[FieldInfo], key: &str) -> Option<V> {
+    let mut table = parse(document?).ok()?;
+    let value = table.remove(key)?;
+    if let Some(field) = fields.iter().find(|field| field.key == key) {
+        if let Err(e) = crate::validate::check_value(field, &value) {
+            crate::scrub::log(fields, None::<&()>, format_args!("Ignoring shipped default: {e}"));
+            return None;
+        }
+    }
+    match value.try_into() {
+        Ok(value) => Some(value),
+        Err(e) => {
+            crate::scrub::log(fields, None::<&()>, format_args!("Ignoring shipped default for '{key}': {e}"));
+            None
+        }
+    }
+}
+
+/// Fills keys missing from a loaded `table` with the shipped defaults in `document`,
+/// descending into tables (sections).
+pub fn apply(table: &mut Table, document: Option<&str>) -> Result<(), toml::de::Error> {
+    if let Some(document) = document {
+        fill(table, parse(document)?);
+    }
+    Ok(())
+}
+
+fn fill(table: &mut Table, defaults: Table) {
+    for (key, default) in defaults {
+        match (table.get_mut(&key), default) {
+            (Some(Value::Table(section)), Value::Table(defaults)) => fill(section, defaults),
+            (Some(_), _) => {}
+            (None, default) => {
+                table.insert(key, default);
+            }
+        }
+    }
+}
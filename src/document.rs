@@ -0,0 +1,63 @@
+//! Per-document preferences: one independent instance per document an editor has open.
+//!
+//! `load_for(directory, document)` loads the struct from a file derived from a document
+//! identifier (usually the document's path). The file name keeps a readable part of the
+//! identifier and a hash of all of it, so different documents never share a file:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct DocumentPrefs {
+//!         pub zoom: f64 = 1.0 => "zoom",
+//!         pub cursor_line: u32 = 0 => "cursor_line",
+//!     },
+//!     "document-prefs",
+//!     single_instance = false
+//! }
+//!
+//! # let tmp = tempfile::tempdir().unwrap();
+//! # let dir = tmp.path().to_str().unwrap();
+//! let mut notes = DocumentPrefs::load_for(dir, "/home/ann/notes.md").unwrap();
+//! let report = DocumentPrefs::load_for(dir, "/home/ann/report.md").unwrap();
+//! notes.save_zoom(1.5).unwrap();
+//! assert_eq!(*report.get_zoom(), 1.0);
+//! assert!(notes.get_preferences_file_path().contains("document-prefs.notes_md-"));
+//! ```
+//!
+//! Each document counts as its own instance for the single-instance constraint, like a
+//! profile. Structs opened for many documents at once usually also set
+//! `single_instance = false`.
+
+use crate::storage::content_hash;
+use crate::LoadError;
+
+/// Longest readable part of a document identifier kept in the file name.
+const MAX_NAME_LEN: usize = 32;
+
+/// Returns the storage key of `document` for preferences normally stored under `filename`:
+/// `<stem>.<name>-<hash>.toml`, where `name` is the last path component of `document` with
+/// characters other than ASCII letters, digits, `-`, and `_` replaced by `_`.
+///
+/// # Errors
+///
+/// Returns `LoadError::StorageError` with `InvalidInput` for an empty identifier.
+pub fn storage_key(filename: &str, document: &str) -> Result<String, LoadError> {
+    if document.is_empty() {
+        return Err(LoadError::StorageError(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "empty document identifier",
+        )));
+    }
+    let last = document
+        .rsplit(['/', '\\'])
+        .find(|component| !component.is_empty())
+        .unwrap_or(document);
+    let name: String = last
+        .chars()
+        .take(MAX_NAME_LEN)
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    let stem = filename.strip_suffix(".toml").unwrap_or(filename);
+    Ok(format!("{stem}.{name}-{}.toml", content_hash(document)))
+}
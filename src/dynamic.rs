@@ -0,0 +1,242 @@
+//! Access to preference fields by saved key, for layers that don't know the struct's type.
+//!
+//! Every struct generated by [`easy_prefs!`](crate::easy_prefs) implements [`DynamicPrefs`].
+//! Values travel as plain strings using the syntax of [`crate::parse`], so a control channel
+//! (a CLI, an IPC socket) can read and change fields without a `match` on field names:
+//!
+//! ```rust
+//! use easy_prefs::{easy_prefs, dynamic::DynamicPrefs};
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub font_size: i32 = 14 => "font_size",
+//!     },
+//!     "dynamic-doc-prefs"
+//! }
+//!
+//! let mut prefs = AppPrefs::load_testing();
+//! prefs.set_field_from_str("font_size", "16").unwrap();
+//! assert_eq!(prefs.get_field_string("font_size").as_deref(), Some("16"));
+//! ```
+//!
+//! Scripting layers that have typed values use [`DynamicPrefs::get_value`] and
+//! [`DynamicPrefs::set_value`] instead, which take and return [`toml::Value`]s:
+//!
+//! ```rust
+//! # use easy_prefs::{easy_prefs, dynamic::DynamicPrefs};
+//! # easy_prefs! {
+//! #     pub struct AppPrefs {
+//! #         pub font_size: i32 = 14 => "font_size",
+//! #     },
+//! #     "dynamic-value-doc-prefs"
+//! # }
+//! let mut prefs = AppPrefs::load_testing();
+//! prefs.set_value("font_size", toml::Value::Integer(18)).unwrap();
+//! assert_eq!(prefs.get_value("font_size"), Some(toml::Value::Integer(18)));
+//! assert!(prefs.set_value("font_size", toml::Value::from("big")).is_err());
+//! ```
+//!
+//! `as_map()` on generated structs and `Prefs<T>` returns every current value at once, keyed by
+//! saved name, with secret fields redacted (see [`PrefValue`]).
+
+use crate::field::FieldInfo;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use toml::Value;
+
+/// A field's value in the map returned by `as_map()`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrefValue {
+    /// The current value.
+    Value(Value),
+    /// The value of a secret field, withheld.
+    Redacted,
+}
+
+impl PrefValue {
+    /// The value, or `None` if it was redacted.
+    pub fn as_value(&self) -> Option<&Value> {
+        match self {
+            PrefValue::Value(value) => Some(value),
+            PrefValue::Redacted => None,
+        }
+    }
+}
+
+impl std::fmt::Display for PrefValue {
+    /// Formats the value like [`DynamicPrefs::get_field_string`], or as
+    /// [`crate::secrets::REDACTED`].
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PrefValue::Value(value) => f.write_str(&crate::parse::format_value(value)),
+            PrefValue::Redacted => f.write_str(crate::secrets::REDACTED),
+        }
+    }
+}
+
+/// Collects field values into the map returned by `as_map()`: fields without a value (a `None`
+/// option) are left out and secret fields are redacted.
+pub fn value_map<'a>(
+    values: impl IntoIterator<Item = (&'a FieldInfo, Option<Value>)>,
+) -> BTreeMap<&'static str, PrefValue> {
+    values
+        .into_iter()
+        .filter_map(|(field, value)| {
+            let value = value?;
+            let value = if field.options.secret { PrefValue::Redacted } else { PrefValue::Value(value) };
+            Some((field.key, value))
+        })
+        .collect()
+}
+
+/// Field access by saved key.
+pub trait DynamicPrefs {
+    /// Metadata for every field, in declaration order.
+    fn fields(&self) -> &'static [FieldInfo];
+
+    /// Formats a field's value; `None` if no field is saved under `key`.
+    ///
+    /// Secret fields are returned unredacted; callers exposing values must check
+    /// [`crate::field::FieldOptions::secret`] themselves.
+    fn get_field_string(&self, key: &str) -> Option<String>;
+
+    /// Parses and saves a field's value.
+    ///
+    /// Unknown keys fail with `NotFound`; unparsable or invalid values with `InvalidInput`.
+    fn set_field_from_str(&mut self, key: &str, input: &str) -> Result<(), std::io::Error>;
+
+    /// Returns a field's value as TOML; `None` if no field is saved under `key` or the value
+    /// has no TOML form (an optional field set to `None`).
+    ///
+    /// Secret fields are returned unredacted, like in [`DynamicPrefs::get_field_string`].
+    fn get_value(&self, key: &str) -> Option<Value>;
+
+    /// Saves a field's value given as TOML. Values of a related type are converted like on a
+    /// lenient load (e.g. `2.0` for an integer field).
+    ///
+    /// Unknown keys fail with `NotFound`; values of the wrong type or invalid values with
+    /// `InvalidInput`.
+    fn set_value(&mut self, key: &str, value: Value) -> Result<(), std::io::Error>;
+
+    /// Returns the doc comment of the field saved under `key` (see
+    /// [`FieldInfo::description`]); `None` for unknown keys, empty if undocumented.
+    fn field_description(&self, key: &str) -> Option<String> {
+        self.fields().iter().find(|field| field.key == key).map(FieldInfo::description)
+    }
+}
+
+/// Whole-struct operations shared by every generated struct and [`crate::Prefs`], for
+/// framework code that manages several preferences structs at once:
+///
+/// ```rust
+/// use easy_prefs::{easy_prefs, Preferences};
+///
+/// easy_prefs! {
+///     pub struct UiPrefs {
+///         pub theme: String = "light".to_string() => "theme",
+///     },
+///     "preferences-doc-ui"
+/// }
+///
+/// easy_prefs! {
+///     pub struct NetPrefs {
+///         pub retries: i32 = 3 => "retries",
+///     },
+///     "preferences-doc-net"
+/// }
+///
+/// fn flush_all(all: &[&dyn Preferences]) {
+///     for prefs in all {
+///         if let Err(e) = prefs.save() {
+///             eprintln!("Failed to save {}: {e}", prefs.get_preferences_file_path());
+///         }
+///     }
+/// }
+///
+/// let (ui, net) = (UiPrefs::load_testing(), NetPrefs::load_testing());
+/// flush_all(&[&ui, &net]);
+/// ```
+///
+/// Field access comes from [`DynamicPrefs`]. Generic code can load too, via
+/// [`Preferences::load_with_error`].
+pub trait Preferences: DynamicPrefs {
+    /// Loads the preferences stored in `directory`, like the inherent `load_with_error()`.
+    fn load_with_error(directory: &str) -> Result<Self, crate::LoadError>
+    where
+        Self: Sized;
+
+    /// Saves the current values.
+    fn save(&self) -> Result<(), crate::SaveError>;
+
+    /// Replaces the in-memory values with the stored ones.
+    fn reload(&mut self) -> Result<(), crate::LoadError>;
+
+    /// Path (native) or key (WASM) of the stored data, e.g. for error messages.
+    fn get_preferences_file_path(&self) -> String;
+}
+
+/// Converts a TOML value to a field's type, coercing it towards the type of `current` if it
+/// doesn't fit as is.
+pub fn convert_value<T>(key: &str, value: Value, current: &T) -> Result<T, std::io::Error>
+where
+    T: Serialize + DeserializeOwned,
+{
+    convert_value_with(key, value, Value::try_from(current).ok().as_ref(), |value| value.try_into())
+}
+
+/// Converts a TOML value like [`convert_value`], coercing towards the stored form `hint` of the
+/// current value and converting with `convert`, e.g. through the field's serde attributes.
+pub fn convert_value_with<T>(
+    key: &str,
+    value: Value,
+    hint: Option<&Value>,
+    convert: impl Fn(Value) -> Result<T, toml::de::Error>,
+) -> Result<T, std::io::Error> {
+    let error = match convert(value.clone()) {
+        Ok(converted) => return Ok(converted),
+        Err(e) => e,
+    };
+    hint.and_then(|hint| crate::coerce::coerce_value(&value, hint))
+        .and_then(|coerced| convert(coerced).ok())
+        .ok_or_else(|| invalid_value(key, error))
+}
+
+/// Error returned for a value that doesn't fit the field saved under `key`.
+pub fn invalid_value(key: &str, reason: impl std::fmt::Display) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("invalid value for '{key}': {reason}"),
+    )
+}
+
+/// Error returned for a key that no field is saved under.
+pub fn unknown_key(key: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("no preference is saved under '{key}'"),
+    )
+}
+
+/// What an external client (IPC, D-Bus) may do with a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Not listed, and requests for it fail as if the key did not exist.
+    Hidden,
+    /// May be read and subscribed to.
+    ReadOnly,
+    /// May also be set.
+    ReadWrite,
+}
+
+/// Decides the [`Access`] for each field.
+pub type AccessPolicy = dyn Fn(&FieldInfo) -> Access + Send + Sync;
+
+/// Policy used when the host app doesn't set one: secret fields are hidden, the rest read-only.
+pub fn default_access(field: &FieldInfo) -> Access {
+    if field.options.secret {
+        Access::Hidden
+    } else {
+        Access::ReadOnly
+    }
+}
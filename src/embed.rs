@@ -0,0 +1,78 @@
+//! Embedding preferences in another document, such as a project file or a save game.
+//!
+//! `embed()` returns the exported fields (see [`export`](crate::export)) as a TOML table, tagged
+//! with the embedding format [`VERSION`] and the struct's schema hash, for the application to
+//! store wherever it likes. `unembed()` applies such a table later and saves, going through
+//! the same repairs and validation as a load, so snapshots written by older app versions are
+//! read like their old preferences files:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct EditorPrefs {
+//!         pub theme: String = "light".to_string() => "theme",
+//!         pub font_size: i32 = 14 => "font_size" (range = 8..=72, on_invalid = clamp),
+//!     },
+//!     "embed-doc-prefs"
+//! }
+//!
+//! let mut prefs = EditorPrefs::load_testing();
+//! prefs.save_theme("dark".to_string()).unwrap();
+//!
+//! let mut project = easy_prefs::toml::Table::new();
+//! project.insert("title".to_string(), "My project".into());
+//! project.insert("editor".to_string(), prefs.embed().unwrap().into());
+//!
+//! prefs.save_theme("light".to_string()).unwrap();
+//! let embedded = project["editor"].as_table().unwrap();
+//! prefs.unembed(embedded).unwrap();
+//! assert_eq!(prefs.get_theme(), "dark");
+//! ```
+//!
+//! Tables of a newer embedding format are rejected with `LoadError::StorageError`
+//! (`InvalidData`). Keys that are unknown or not exported are ignored, and fields missing from
+//! the table keep their current values.
+
+use crate::field::FieldInfo;
+use toml::{Table, Value};
+
+/// Key holding the embedding format version.
+pub const VERSION_KEY: &str = "_embed_version";
+
+/// Key holding the schema hash of the struct that embedded the table, as 16 hex digits.
+pub const SCHEMA_KEY: &str = "_schema";
+
+/// Current embedding format version.
+pub const VERSION: i64 = 1;
+
+/// Turns the saved values in `table` into an embedded table.
+#[doc(hidden)]
+pub fn wrap(mut table: Table, fields: &[FieldInfo], schema_hash: u64) -> Table {
+    crate::export::retain_exported(&mut table, fields);
+    table.insert(VERSION_KEY.to_string(), Value::Integer(VERSION));
+    table.insert(SCHEMA_KEY.to_string(), Value::String(format!("{schema_hash:016x}")));
+    table
+}
+
+/// Returns the values of an embedded table, checking its format version.
+#[doc(hidden)]
+pub fn unwrap(embedded: &Table, fields: &[FieldInfo]) -> Result<Table, std::io::Error> {
+    let invalid = |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+    match embedded.get(VERSION_KEY) {
+        Some(Value::Integer(version)) if *version > VERSION => {
+            return Err(invalid(format!("embedded preferences have format version {version}, newer than {VERSION}")));
+        }
+        Some(Value::Integer(version)) if *version >= 1 => {}
+        _ => return Err(invalid(format!("not embedded preferences: '{VERSION_KEY}' is missing or invalid"))),
+    }
+    let mut table = embedded.clone();
+    crate::export::retain_exported(&mut table, fields);
+    Ok(table)
+}
+
+/// Returns the schema hash recorded in an embedded table, to tell whether it was embedded by a
+/// struct with a different layout (compare with `SCHEMA_HASH`).
+pub fn schema_hash(embedded: &Table) -> Option<u64> {
+    u64::from_str_radix(embedded.get(SCHEMA_KEY)?.as_str()?, 16).ok()
+}
@@ -0,0 +1,42 @@
+//! Export and import of settings for backups and moving between machines.
+//!
+//! `export_toml()` writes the exported fields as a TOML document and `import_toml()` applies
+//! one and saves. A field is exported unless it is secret, device-scoped, or marked
+//! `no_export`; the check lives here and in live sync, so callers can't forget it:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub theme: String = "light".to_string() => "theme",
+//!         pub machine_id: String = String::new() => "machine_id" (no_export),
+//!     },
+//!     "export-doc-prefs"
+//! }
+//!
+//! let mut prefs = AppPrefs::load_testing();
+//! prefs.save_machine_id("a1b2".to_string()).unwrap();
+//! let exported = prefs.export_toml().unwrap();
+//! assert_eq!(exported, "theme = \"light\"\n");
+//!
+//! prefs.import_toml("theme = \"dark\"\nmachine_id = \"other\"\n").unwrap();
+//! assert_eq!(prefs.get_theme(), "dark");
+//! assert_eq!(prefs.get_machine_id(), "a1b2");
+//! ```
+//!
+//! Imported keys that are unknown or not exported are ignored, fields missing from the
+//! document keep their current values, and invalid values fall back to the default like on
+//! load.
+
+use crate::field::FieldInfo;
+use toml::Table;
+
+/// Removes every key that is not an exported field from `table`.
+pub fn retain_exported(table: &mut Table, fields: &[FieldInfo]) {
+    table.retain(|key, _| {
+        fields
+            .iter()
+            .any(|field| field.key == key && field.options.is_exported())
+    });
+}
@@ -0,0 +1,532 @@
+//! Per-field metadata generated by the [`easy_prefs!`](crate::easy_prefs) macro.
+//!
+//! Every generated struct exposes a `FIELDS` constant describing its fields. Options are
+//! declared in parentheses after the saved name:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub dark_mode: bool = false => "dark_mode" (lenient_types),
+//!         pub font_size: i32 = 14 => "font_size",
+//!     },
+//!     "app-settings"
+//! }
+//!
+//! assert_eq!(AppPrefs::FIELDS[0].key, "dark_mode");
+//! assert!(AppPrefs::FIELDS[0].options.lenient_types);
+//! ```
+//!
+//! `FIELDS` also records each field's type, default expression, and doc comment, and
+//! `iter()` walks the current values alongside, so a settings screen can be built from the
+//! struct alone:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         /// Use the dark color scheme.
+//!         pub dark_mode: bool = false => "dark_mode",
+//!         pub font_size: i32 = 14 => "font_size",
+//!     },
+//!     "field-doc-prefs"
+//! }
+//!
+//! let prefs = AppPrefs::load_testing();
+//! for (field, value) in prefs.iter() {
+//!     println!("{} ({}): {:?}", field.description(), field.type_name, value);
+//! }
+//! assert_eq!(AppPrefs::FIELDS[0].description(), "Use the dark color scheme.");
+//! assert_eq!(AppPrefs::FIELDS[1].default, "14");
+//! ```
+//!
+//! [`FieldInfo::widget`] suggests the control to show for a field (a toggle for a `bool`, a
+//! dropdown for `choices`, a slider for a bounded number), so egui, Tauri, or terminal
+//! frontends can share one mapping; `examples/full_app.rs` renders a settings screen this way.
+
+/// Options attached to a single field.
+///
+/// Built in const context by the macro; each option name maps to a builder method of the
+/// same name, and a bare flag such as `(lenient_types)` is shorthand for `lenient_types = true`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldOptions {
+    /// Coerce sloppy stored types (e.g. `"true"` → `true`) on load.
+    pub lenient_types: bool,
+    /// Allowed values for a string field, e.g. `(choices = ["light", "dark", "system"])`.
+    pub choices: Option<&'static [&'static str]>,
+    /// Keep the value out of logs and, if a secret store is installed, out of the file
+    /// (see [`crate::secrets`]).
+    pub secret: bool,
+    /// Smallest allowed value for a numeric field.
+    pub min: Option<f64>,
+    /// Largest allowed value for a numeric field.
+    pub max: Option<f64>,
+    /// Increment between allowed values, counted from `min` (or zero).
+    pub step: Option<f64>,
+    /// What loading does with a stored value violating the constraints above.
+    pub on_invalid: crate::validate::OnInvalid,
+    /// Device-scoped: the value belongs to this machine (a cache path, window geometry) and
+    /// stays out of sync between instances. Fields are user-scoped by default.
+    pub device: bool,
+    /// Never leaves this instance: left out of exports, ignored on import, and not synced
+    /// (for machine ids, caches, tokens; see [`crate::export`]).
+    pub no_export: bool,
+    /// Store a large string value in a sibling file, keeping only a reference in the main
+    /// document (see [`crate::blob`]).
+    pub external: bool,
+    /// A string expected to be filled in; `lint()` flags it when empty (see [`crate::lint`]).
+    pub required: bool,
+    /// An integer holding Unix seconds; `lint()` flags it when in the future.
+    pub timestamp: bool,
+    /// A failed save changing this field is escalated instead of only returning an error
+    /// (see [`crate::critical`]).
+    pub critical: bool,
+    /// Runtime state that is never stored: left out of saves, and at its default after every
+    /// load (see [`crate::transient`]).
+    pub transient: bool,
+}
+
+impl FieldOptions {
+    /// Options with every feature switched off.
+    pub const fn new() -> Self {
+        Self {
+            lenient_types: false,
+            choices: None,
+            secret: false,
+            min: None,
+            max: None,
+            step: None,
+            on_invalid: crate::validate::OnInvalid::UseDefault,
+            device: false,
+            no_export: false,
+            external: false,
+            required: false,
+            timestamp: false,
+            critical: false,
+            transient: false,
+        }
+    }
+
+    /// Sets [`FieldOptions::lenient_types`].
+    pub const fn lenient_types(mut self, lenient_types: bool) -> Self {
+        self.lenient_types = lenient_types;
+        self
+    }
+
+    /// Sets [`FieldOptions::choices`].
+    pub const fn choices(mut self, choices: &'static [&'static str]) -> Self {
+        self.choices = Some(choices);
+        self
+    }
+
+    /// Sets [`FieldOptions::secret`].
+    pub const fn secret(mut self, secret: bool) -> Self {
+        self.secret = secret;
+        self
+    }
+
+    /// Sets [`FieldOptions::min`].
+    pub const fn min(mut self, min: f64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    /// Sets [`FieldOptions::max`].
+    pub const fn max(mut self, max: f64) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// Sets [`FieldOptions::step`].
+    pub const fn step(mut self, step: f64) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    /// Sets [`FieldOptions::on_invalid`].
+    pub const fn on_invalid(mut self, on_invalid: crate::validate::OnInvalid) -> Self {
+        self.on_invalid = on_invalid;
+        self
+    }
+
+    /// Sets [`FieldOptions::device`].
+    pub const fn device(mut self, device: bool) -> Self {
+        self.device = device;
+        self
+    }
+
+    /// Returns true unless the field is [device-scoped](FieldOptions::device).
+    pub const fn is_user_scoped(&self) -> bool {
+        !self.device
+    }
+
+    /// Sets [`FieldOptions::no_export`].
+    pub const fn no_export(mut self, no_export: bool) -> Self {
+        self.no_export = no_export;
+        self
+    }
+
+    /// Sets [`FieldOptions::external`].
+    pub const fn external(mut self, external: bool) -> Self {
+        self.external = external;
+        self
+    }
+
+    /// Sets [`FieldOptions::required`].
+    pub const fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// Sets [`FieldOptions::timestamp`].
+    pub const fn timestamp(mut self, timestamp: bool) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Sets [`FieldOptions::critical`].
+    pub const fn critical(mut self, critical: bool) -> Self {
+        self.critical = critical;
+        self
+    }
+
+    /// Sets [`FieldOptions::transient`].
+    pub const fn transient(mut self, transient: bool) -> Self {
+        self.transient = transient;
+        self
+    }
+
+    /// Returns true if the value may appear in export, import, and sync payloads: the field is
+    /// user-scoped and neither secret, `no_export`, nor transient.
+    pub const fn is_exported(&self) -> bool {
+        self.is_user_scoped() && !self.secret && !self.no_export && !self.transient
+    }
+
+    /// Returns true if any value constraint is declared (see [`crate::validate`]).
+    pub const fn has_constraints(&self) -> bool {
+        self.choices.is_some() || self.min.is_some() || self.max.is_some() || self.step.is_some()
+    }
+}
+
+impl Default for FieldOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Static description of a preferences field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldInfo {
+    /// Rust field name (as used in `get_<name>()`).
+    pub name: &'static str,
+    /// Key the value is saved under.
+    pub key: &'static str,
+    /// Declared type, as written (e.g. `"Vec<String>"`).
+    pub type_name: &'static str,
+    /// Default value expression, as written (e.g. `"\"light\".to_string()"`).
+    pub default: &'static str,
+    /// Doc comment, one line per `///` line with the text as written (usually starting with a
+    /// space); empty if undocumented. See [`FieldInfo::description`].
+    pub doc: &'static str,
+    /// Earlier saved names, from `#[serde(alias = "...")]`, that loading reads as this field
+    /// (see [`crate::alias`]).
+    pub aliases: &'static [&'static str],
+    /// Options declared for the field.
+    pub options: FieldOptions,
+}
+
+impl FieldInfo {
+    /// Returns the doc comment with each line trimmed, e.g. as a label or tooltip.
+    pub fn description(&self) -> String {
+        self.doc.lines().map(str::trim).collect::<Vec<_>>().join("\n").trim().to_string()
+    }
+
+    /// Describes the declared constraints, e.g. `"one of: light, dark"` or `"range: 0 to 10"`;
+    /// `None` if there are none.
+    pub fn constraints(&self) -> Option<String> {
+        let options = &self.options;
+        let mut parts = Vec::new();
+        if let Some(choices) = options.choices {
+            parts.push(format!("one of: {}", choices.join(", ")));
+        }
+        match (options.min, options.max) {
+            (Some(min), Some(max)) => parts.push(format!("range: {min} to {max}")),
+            (Some(min), None) => parts.push(format!("at least {min}")),
+            (None, Some(max)) => parts.push(format!("at most {max}")),
+            (None, None) => {}
+        }
+        if let Some(step) = options.step {
+            parts.push(format!("step: {step}"));
+        }
+        (!parts.is_empty()).then(|| parts.join("; "))
+    }
+
+    /// Suggests the control for editing the field in a settings screen, based on its options
+    /// and declared type (`Option<T>` is treated as `T`).
+    pub fn widget(&self) -> Widget {
+        let options = &self.options;
+        if options.secret {
+            return Widget::Secret;
+        }
+        if let Some(choices) = options.choices {
+            return Widget::Choice(choices);
+        }
+        let type_name: String = self.type_name.split_whitespace().collect();
+        let inner = type_name
+            .strip_prefix("Option<")
+            .and_then(|inner| inner.strip_suffix('>'))
+            .unwrap_or(&type_name);
+        match inner {
+            "bool" => Widget::Toggle,
+            "String" => Widget::Text,
+            "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize" | "f32"
+            | "f64" => match (options.min, options.max) {
+                (Some(min), Some(max)) => Widget::Slider { min, max, step: options.step },
+                _ => Widget::Number,
+            },
+            _ => Widget::Other,
+        }
+    }
+}
+
+/// Control suggested by [`FieldInfo::widget`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Widget {
+    /// A checkbox or switch.
+    Toggle,
+    /// A single-line text input.
+    Text,
+    /// A text input that hides its contents.
+    Secret,
+    /// A dropdown of the allowed values.
+    Choice(&'static [&'static str]),
+    /// A slider over the field's range.
+    Slider {
+        /// Lowest allowed value.
+        min: f64,
+        /// Highest allowed value.
+        max: f64,
+        /// Increment, if declared.
+        step: Option<f64>,
+    },
+    /// A numeric input without bounds on both sides.
+    Number,
+    /// Anything else (collections, sections): edit the value as TOML, e.g. with
+    /// `set_<field>_from_str()`.
+    Other,
+}
+
+/// Formats `fields` as `--help`-style text: a line with each field's saved key, type, and
+/// default, followed by its description and constraints, indented.
+///
+/// ```text
+///   font_size <i32> (default: 14)
+///       Editor font size in points.
+///       range: 8 to 72
+/// ```
+pub fn help_text(fields: &[FieldInfo]) -> String {
+    let mut text = String::new();
+    for field in fields {
+        let type_name: String = field.type_name.split_whitespace().collect();
+        text.push_str(&format!("  {} <{type_name}> (default: {})\n", field.key, default_hint(field.default)));
+        for line in field.description().lines().chain(field.constraints().as_deref()) {
+            text.push_str(&format!("      {line}\n"));
+        }
+    }
+    text
+}
+
+/// Returns the first saved key or alias declared more than once by `fields`.
+///
+/// Generated structs check this at compile time, so a copy-pasted key fails the build:
+///
+/// ```compile_fail
+/// use easy_prefs::easy_prefs;
+///
+/// easy_prefs! {
+///     pub struct AppPrefs {
+///         pub dark_mode: bool = false => "dark_mode",
+///         pub high_contrast: bool = false => "dark_mode",
+///     },
+///     "duplicate-key-prefs"
+/// }
+/// ```
+///
+/// So does an alias that is another field's saved key, since loading couldn't tell which
+/// field the stored value belongs to:
+///
+/// ```compile_fail
+/// use easy_prefs::easy_prefs;
+///
+/// easy_prefs! {
+///     pub struct AppPrefs {
+///         pub dark_mode: bool = false => "dark_mode",
+///         pub theme: String = "light".to_string() => "theme" (alias "dark_mode"),
+///     },
+///     "duplicate-alias-prefs"
+/// }
+/// ```
+pub const fn duplicate_key(fields: &[FieldInfo]) -> Option<&'static str> {
+    let mut index = 0;
+    while index < fields.len() {
+        let field = &fields[index];
+        if key_count(fields, field.key) > 1 {
+            return Some(field.key);
+        }
+        let mut alias = 0;
+        while alias < field.aliases.len() {
+            if key_count(fields, field.aliases[alias]) > 1 {
+                return Some(field.aliases[alias]);
+            }
+            alias += 1;
+        }
+        index += 1;
+    }
+    None
+}
+
+/// Fails const evaluation if two of `fields` share a saved key (see [`duplicate_key`]).
+#[doc(hidden)]
+pub const fn assert_unique_keys(fields: &[FieldInfo]) {
+    assert!(duplicate_key(fields).is_none(), "Duplicate saved_name found in FIELDS");
+}
+
+/// Counts the saved keys and aliases of `fields` equal to `key`.
+const fn key_count(fields: &[FieldInfo], key: &str) -> usize {
+    let mut count = 0;
+    let mut index = 0;
+    while index < fields.len() {
+        if str_eq(fields[index].key, key) {
+            count += 1;
+        }
+        let mut alias = 0;
+        while alias < fields[index].aliases.len() {
+            if str_eq(fields[index].aliases[alias], key) {
+                count += 1;
+            }
+            alias += 1;
+        }
+        index += 1;
+    }
+    count
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut index = 0;
+    while index < a.len() {
+        if a[index] != b[index] {
+            return false;
+        }
+        index += 1;
+    }
+    true
+}
+
+/// Drops conversions that don't help a reader, e.g. `"light".to_string()` → `"light"`.
+fn default_hint(default: &str) -> &str {
+    [".to_string()", ".to_owned()", ".into()"]
+        .iter()
+        .find_map(|suffix| default.strip_suffix(suffix))
+        .unwrap_or(default)
+}
+
+/// Joins the doc comment lines among the macro's field attributes into a string literal.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __easy_prefs_doc {
+    (@acc [$($lines:tt)*];) => { concat!($($lines)*) };
+    (@acc []; [doc = $line:literal] $($rest:tt)*) => {
+        $crate::__easy_prefs_doc!(@acc [$line]; $($rest)*)
+    };
+    (@acc [$($lines:tt)+]; [doc = $line:literal] $($rest:tt)*) => {
+        $crate::__easy_prefs_doc!(@acc [$($lines)+, "\n", $line]; $($rest)*)
+    };
+    (@acc [$($lines:tt)*]; [$($attr:tt)*] $($rest:tt)*) => {
+        $crate::__easy_prefs_doc!(@acc [$($lines)*]; $($rest)*)
+    };
+    ($($attrs:tt)*) => {
+        $crate::__easy_prefs_doc!(@acc []; $($attrs)*)
+    };
+}
+
+/// Collects the `alias` values of a field's option list (`options [...]`) and of the `serde`
+/// attributes among the macro's field attributes into a slice.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __easy_prefs_aliases {
+    (@acc [$($aliases:tt)*];) => { &[$($aliases)*] };
+    (@acc [$($aliases:tt)*]; [serde($($args:tt)*)] $($rest:tt)*) => {
+        $crate::__easy_prefs_aliases!(@args [$($aliases)*]; [$($args)*] $($rest)*)
+    };
+    (@acc [$($aliases:tt)*]; [$($attr:tt)*] $($rest:tt)*) => {
+        $crate::__easy_prefs_aliases!(@acc [$($aliases)*]; $($rest)*)
+    };
+    (@args [$($aliases:tt)*]; [alias = $alias:literal $($args:tt)*] $($rest:tt)*) => {
+        $crate::__easy_prefs_aliases!(@args [$($aliases)* $alias,]; [$($args)*] $($rest)*)
+    };
+    (@args [$($aliases:tt)*]; [alias $alias:literal $($args:tt)*] $($rest:tt)*) => {
+        $crate::__easy_prefs_aliases!(@args [$($aliases)* $alias,]; [$($args)*] $($rest)*)
+    };
+    (@args [$($aliases:tt)*]; [$skipped:tt $($args:tt)*] $($rest:tt)*) => {
+        $crate::__easy_prefs_aliases!(@args [$($aliases)*]; [$($args)*] $($rest)*)
+    };
+    (@args [$($aliases:tt)*]; [] $($rest:tt)*) => {
+        $crate::__easy_prefs_aliases!(@acc [$($aliases)*]; $($rest)*)
+    };
+    (options [$($options:tt)*] $($attrs:tt)*) => {
+        $crate::__easy_prefs_aliases!(@args []; [$($options)*] $($attrs)*)
+    };
+    ($($attrs:tt)*) => {
+        $crate::__easy_prefs_aliases!(@acc []; $($attrs)*)
+    };
+}
+
+/// Builds a [`FieldOptions`] value from the macro's field option list.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __easy_prefs_field_options {
+    (@acc $acc:expr;) => { $acc };
+    (@acc $acc:expr; choices = [$($choice:expr),* $(,)?] $(, $($rest:tt)*)?) => {
+        $crate::__easy_prefs_field_options!(@acc $acc.choices(&[$($choice),*]); $($($rest)*)?)
+    };
+    (@acc $acc:expr; min = $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__easy_prefs_field_options!(@acc $acc.min($value as f64); $($($rest)*)?)
+    };
+    (@acc $acc:expr; max = $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__easy_prefs_field_options!(@acc $acc.max($value as f64); $($($rest)*)?)
+    };
+    (@acc $acc:expr; range = $min:literal ..= $max:literal $(, $($rest:tt)*)?) => {
+        $crate::__easy_prefs_field_options!(@acc $acc.min($min as f64).max($max as f64); $($($rest)*)?)
+    };
+    (@acc $acc:expr; on_invalid = default $(, $($rest:tt)*)?) => {
+        $crate::__easy_prefs_field_options!(@acc $acc.on_invalid($crate::validate::OnInvalid::UseDefault); $($($rest)*)?)
+    };
+    (@acc $acc:expr; on_invalid = clamp $(, $($rest:tt)*)?) => {
+        $crate::__easy_prefs_field_options!(@acc $acc.on_invalid($crate::validate::OnInvalid::Clamp); $($($rest)*)?)
+    };
+    (@acc $acc:expr; on_invalid = error $(, $($rest:tt)*)?) => {
+        $crate::__easy_prefs_field_options!(@acc $acc.on_invalid($crate::validate::OnInvalid::Error); $($($rest)*)?)
+    };
+    (@acc $acc:expr; step = $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__easy_prefs_field_options!(@acc $acc.step($value as f64); $($($rest)*)?)
+    };
+    // Aliases are collected into `FieldInfo::aliases` by `__easy_prefs_aliases!`.
+    (@acc $acc:expr; alias $(=)? $alias:literal $(, $($rest:tt)*)?) => {
+        $crate::__easy_prefs_field_options!(@acc $acc; $($($rest)*)?)
+    };
+    (@acc $acc:expr; $option:ident = $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__easy_prefs_field_options!(@acc $acc.$option($value); $($($rest)*)?)
+    };
+    (@acc $acc:expr; $flag:ident $(, $($rest:tt)*)?) => {
+        $crate::__easy_prefs_field_options!(@acc $acc.$flag(true); $($($rest)*)?)
+    };
+    ($($options:tt)*) => {
+        $crate::__easy_prefs_field_options!(@acc $crate::field::FieldOptions::new(); $($options)*)
+    };
+}
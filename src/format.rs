@@ -0,0 +1,99 @@
+//! Document formats for stored preferences (`format = &MyFormat`).
+//!
+//! Values are handled as a [`toml::Table`] keyed by saved names throughout; a [`Format`] only
+//! turns that table into the stored text and back. The default is [`Toml`]. To store another
+//! format, implement the trait and select it per struct:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//! use easy_prefs::format::Format;
+//! use serde::{de::Error as _, ser::Error as _};
+//! use toml::{Table, Value};
+//!
+//! /// One `key=value` line per string value.
+//! struct KeyValue;
+//!
+//! impl Format for KeyValue {
+//!     fn name(&self) -> &'static str {
+//!         "key-value"
+//!     }
+//!
+//!     fn to_string(&self, table: &Table) -> Result<String, toml::ser::Error> {
+//!         let mut text = String::new();
+//!         for (key, value) in table {
+//!             let value = match value {
+//!                 Value::String(value) => value.clone(),
+//!                 Value::Integer(value) => value.to_string(),
+//!                 _ => return Err(toml::ser::Error::custom(format!("unsupported value for '{key}'"))),
+//!             };
+//!             text.push_str(&format!("{key}={value}\n"));
+//!         }
+//!         Ok(text)
+//!     }
+//!
+//!     fn parse(&self, document: &str) -> Result<Table, toml::de::Error> {
+//!         let mut table = Table::new();
+//!         for line in document.lines().filter(|line| !line.is_empty() && !line.starts_with('#')) {
+//!             let (key, value) = line.split_once('=').ok_or_else(|| toml::de::Error::custom("missing '='"))?;
+//!             let value = match value.parse() {
+//!                 Ok(number) => Value::Integer(number),
+//!                 Err(_) => Value::String(value.to_string()),
+//!             };
+//!             table.insert(key.to_string(), value);
+//!         }
+//!         Ok(table)
+//!     }
+//! }
+//!
+//! easy_prefs! {
+//!     pub struct KioskPrefs {
+//!         pub station: String = "lobby".to_string() => "station",
+//!     },
+//!     "format-doc-prefs",
+//!     format = &KeyValue
+//! }
+//!
+//! let mut prefs = KioskPrefs::load_testing();
+//! prefs.save_station("entrance".to_string()).unwrap();
+//! let stored = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+//! assert!(stored.starts_with("station=entrance\n"));
+//! ```
+//!
+//! Errors go through `serde`'s `custom` constructors, so they surface as the usual
+//! `LoadError::DeserializationError` and `SaveError::SerializationFailed`. The stored file keeps
+//! its `.toml` name. The formatted document also holds the `_generation` counter, and with
+//! `integrity = true` a footer line starting with `#` is appended and removed before parsing.
+//! `preserve_formatting`, `lint()`, `to_string()`, and the export and patch helpers work on TOML
+//! and should not be combined with another format.
+
+use toml::Table;
+
+/// Converts between a table of saved values and the stored document.
+pub trait Format: Send + Sync {
+    /// Short name for diagnostics (e.g. `"toml"`).
+    fn name(&self) -> &'static str;
+
+    /// Formats the saved values as a document.
+    fn to_string(&self, table: &Table) -> Result<String, toml::ser::Error>;
+
+    /// Parses a stored document back into saved values.
+    fn parse(&self, document: &str) -> Result<Table, toml::de::Error>;
+}
+
+/// The default format: a TOML document.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Toml;
+
+impl Format for Toml {
+    fn name(&self) -> &'static str {
+        "toml"
+    }
+
+    fn to_string(&self, table: &Table) -> Result<String, toml::ser::Error> {
+        toml::to_string(table)
+    }
+
+    fn parse(&self, document: &str) -> Result<Table, toml::de::Error> {
+        toml::from_str(document)
+    }
+}
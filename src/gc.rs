@@ -0,0 +1,181 @@
+//! Removing the files of profiles and documents that are no longer used (`gc()`).
+//!
+//! Every profile (`load_profile()`) and document (`load_for()`) gets its own file next to the
+//! default one, and nothing removes them when a profile is deleted in the app or a document
+//! is gone. `gc(directory, &policy)` on generated structs (and `Prefs<T>`) removes them:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//! use easy_prefs::gc::GcPolicy;
+//! use std::time::Duration;
+//!
+//! easy_prefs! {
+//!     pub struct EditorPrefs {
+//!         pub zoom: f64 = 1.0 => "zoom",
+//!     },
+//!     "gc-doc-prefs"
+//! }
+//!
+//! # let tmp = tempfile::tempdir().unwrap();
+//! # let dir = tmp.path().to_str().unwrap();
+//! EditorPrefs::load_profile(dir, "work").unwrap().save_zoom(1.5).unwrap();
+//! EditorPrefs::load_for(dir, "/tmp/deleted.md").unwrap().save_zoom(2.0).unwrap();
+//!
+//! // Only the "work" profile is still in use.
+//! let policy = GcPolicy::new().keep_only(&["work"], &[]).dry_run(true);
+//! let report = EditorPrefs::gc(dir, &policy).unwrap();
+//! assert_eq!(report.removed.len(), 1); // would remove the document's file
+//!
+//! // Files nobody saved for 90 days.
+//! let policy = GcPolicy::new().unused_for(Duration::from_secs(90 * 24 * 60 * 60));
+//! assert!(EditorPrefs::gc(dir, &policy).unwrap().removed.is_empty());
+//! ```
+//!
+//! A file is removed if a reference list was given and it isn't on it, or if it was not
+//! saved for the `unused_for` duration. Files of instances loaded in this process are kept
+//! (for structs with `single_instance = false`, which aren't tracked, that is up to the
+//! caller). The default file is never touched. Removing a file
+//! also removes its companions (backups, history, blobs, sidecar, sync state) and its secrets
+//! in the secret store. With `dry_run(true)`, nothing is removed and the report lists what
+//! would be.
+
+use crate::field::FieldInfo;
+use crate::storage::Storage;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Which profile and document files [`run`] removes.
+#[derive(Debug, Clone, Default)]
+pub struct GcPolicy {
+    /// Profile names and document identifiers in use; when set, the files of all others are
+    /// removed.
+    pub keep: Option<(Vec<String>, Vec<String>)>,
+    /// Remove files not saved for this long.
+    pub unused_for: Option<Duration>,
+    /// Only report what would be removed.
+    pub dry_run: bool,
+}
+
+impl GcPolicy {
+    /// A policy that removes nothing; add criteria with the other methods.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets [`GcPolicy::keep`]: the profiles and documents (by the identifier passed to
+    /// `load_for()`) still in use.
+    pub fn keep_only(mut self, profiles: &[&str], documents: &[&str]) -> Self {
+        let owned = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        self.keep = Some((owned(profiles), owned(documents)));
+        self
+    }
+
+    /// Sets [`GcPolicy::unused_for`].
+    pub fn unused_for(mut self, age: Duration) -> Self {
+        self.unused_for = Some(age);
+        self
+    }
+
+    /// Sets [`GcPolicy::dry_run`].
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+/// What [`run`] removed, or would remove in a dry run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GcReport {
+    /// Storage keys (file names) of the removed profiles and documents.
+    pub removed: Vec<String>,
+    /// Every removed file, companions included.
+    pub files: Vec<PathBuf>,
+    /// Whether this was a dry run.
+    pub dry_run: bool,
+}
+
+/// Whether `name` is the file of a profile or document of the preferences under `filename`:
+/// `<stem>.<name>.toml` with a name as profiles and documents have.
+fn is_instance_file(stem: &str, name: &str) -> bool {
+    let Some(middle) = name
+        .strip_prefix(stem)
+        .and_then(|rest| rest.strip_prefix('.'))
+        .and_then(|rest| rest.strip_suffix(".toml"))
+    else {
+        return false;
+    };
+    !middle.is_empty() && middle.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn is_stale(path: &Path, age: Duration) -> Result<bool, std::io::Error> {
+    let modified = std::fs::metadata(path)?.modified()?;
+    Ok(SystemTime::now().duration_since(modified).is_ok_and(|elapsed| elapsed >= age))
+}
+
+/// Removes the profile and document files in `directory` of the preferences under
+/// `filename` that `policy` selects, skipping those at the storage paths of `loaded` instances.
+pub fn run(
+    directory: &str,
+    filename: &str,
+    fields: &[FieldInfo],
+    loaded: &[String],
+    policy: &GcPolicy,
+) -> Result<GcReport, std::io::Error> {
+    let mut report = GcReport { dry_run: policy.dry_run, ..Default::default() };
+    let stem = filename.strip_suffix(".toml").unwrap_or(filename);
+    // A nested filename (`profiles/default`) keeps its instances in the subdirectory.
+    let (subdirectory, stem) = stem.rsplit_once('/').unwrap_or(("", stem));
+    let scanned = Path::new(directory).join(subdirectory);
+    let entries = match std::fs::read_dir(&scanned) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(report),
+        Err(e) => return Err(e),
+    };
+    let names: Vec<String> = entries
+        .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+        .collect::<Result<_, std::io::Error>>()?;
+
+    let kept: Option<Vec<String>> = policy.keep.as_ref().map(|(profiles, documents)| {
+        let profiles = profiles.iter().filter_map(|profile| crate::profile::storage_key(filename, profile).ok());
+        let documents = documents.iter().filter_map(|document| crate::document::storage_key(filename, document).ok());
+        profiles.chain(documents).collect()
+    });
+    let storage = crate::storage::native::FileStorage::new(directory);
+
+    let mut candidates: Vec<&String> = names.iter().filter(|name| is_instance_file(stem, name)).collect();
+    candidates.sort();
+    for name in candidates {
+        let key = match subdirectory {
+            "" => name.clone(),
+            subdirectory => format!("{subdirectory}/{name}"),
+        };
+        if loaded.contains(&storage.get_path(&key)) {
+            continue;
+        }
+        let path = scanned.join(name);
+        let unreferenced = kept.as_ref().is_some_and(|kept| !kept.contains(&key));
+        let remove = unreferenced || policy.unused_for.map_or(Ok(false), |age| is_stale(&path, age))?;
+        if !remove {
+            continue;
+        }
+
+        // Companions first: once the file itself is gone, they can't be found anymore.
+        let prefix = format!("{name}.");
+        let mut files: Vec<PathBuf> = names
+            .iter()
+            .filter(|name| name.starts_with(&prefix))
+            .map(|name| scanned.join(name))
+            .collect();
+        files.sort();
+        files.push(path);
+        if !policy.dry_run {
+            crate::secrets::delete(fields, &storage, &key)?;
+            for file in &files {
+                std::fs::remove_file(file)?;
+            }
+        }
+        report.removed.push(key);
+        report.files.extend(files);
+    }
+    Ok(report)
+}
@@ -0,0 +1,69 @@
+//! Write generation: a counter bumped by every successful save.
+//!
+//! The generation is stored in the preferences document itself, so it is written atomically
+//! with the values it describes and never gets ahead of or behind them after a crash:
+//!
+//! ```toml
+//! theme = "dark"
+//! _generation = 42
+//! ```
+//!
+//! `generation()` on a loaded struct returns the generation of the last save or load. Tools
+//! and sync layers can check for changes since generation X with [`read`] instead of comparing
+//! file contents. A save continues from the generation in memory, without reading the stored
+//! document. Only when it reads the document anyway (`preserve_formatting`, `backups`,
+//! `compat_sidecar`, external or critical fields) does it continue from the higher of the
+//! two, which keeps the counter increasing when another process saved in between.
+
+use crate::storage::Storage;
+use toml::{Table, Value};
+
+/// Key of the generation in the stored document.
+pub const KEY: &str = "_generation";
+
+/// Removes the generation from a stored table, returning it (0 if absent).
+pub fn take(table: &mut Table) -> u64 {
+    match table.remove(KEY) {
+        Some(Value::Integer(generation)) => u64::try_from(generation).unwrap_or(0),
+        Some(_) => {
+            crate::scrub::log(&[], None::<&()>, format_args!("Ignoring malformed '{KEY}' value"));
+            0
+        }
+        None => 0,
+    }
+}
+
+/// Returns the generation stored in `document` (0 if absent or unreadable).
+pub fn parse(document: &str) -> u64 {
+    toml::from_str::<Table>(document)
+        .map(|mut table| take(&mut table))
+        .unwrap_or(0)
+}
+
+/// Reads the stored generation of the preferences at `key` (0 if nothing is stored).
+pub fn read(storage: &dyn Storage, key: &str) -> Result<u64, std::io::Error> {
+    Ok(storage.read(key)?.as_deref().map_or(0, parse))
+}
+
+/// Adds `generation` to a table being saved.
+pub fn insert(table: &mut Table, generation: u64) {
+    let generation = i64::try_from(generation).unwrap_or(i64::MAX);
+    table.insert(KEY.to_string(), Value::Integer(generation));
+}
+
+/// Returns `document` without its generation, so identical values compare equal.
+pub fn strip(document: &str) -> String {
+    match document.parse::<toml_edit::DocumentMut>() {
+        Ok(mut document) => {
+            document.remove(KEY);
+            document.to_string()
+        }
+        Err(_) => document.to_string(),
+    }
+}
+
+/// Returns the generation for a save, given the current one and the parsed stored document.
+pub fn next(current: u64, existing: Option<&Table>) -> u64 {
+    let stored = existing.map_or(0, |existing| take(&mut existing.clone()));
+    current.max(stored).saturating_add(1)
+}
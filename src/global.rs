@@ -0,0 +1,94 @@
+//! Process-wide preferences (`global = ...`).
+//!
+//! With the `global` struct option naming a function that returns the preferences directory,
+//! `global()` returns a `&'static` handle (see `load_shared()`) that is loaded on first access,
+//! so no `once_cell` wrapper is needed to reach the preferences from anywhere in the app:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! fn prefs_directory() -> Result<String, std::io::Error> {
+//!     // Typically `easy_prefs::storage::config_directory("com.example.app")`.
+//!     let directory = std::env::temp_dir().join(format!("global-doc-prefs-{}", std::process::id()));
+//!     Ok(directory.to_string_lossy().into_owned())
+//! }
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub dark_mode: bool = false => "dark_mode",
+//!     },
+//!     "global-doc-prefs",
+//!     global = prefs_directory
+//! }
+//!
+//! AppPrefs::global().save_dark_mode(true).unwrap();
+//! assert!(AppPrefs::global().get_dark_mode());
+//! # std::fs::remove_dir_all(prefs_directory().unwrap()).unwrap();
+//! ```
+//!
+//! Structs generated by `easy_prefs!` only have `global()` and `try_global()` with the
+//! option, so calling them on other structs doesn't compile:
+//!
+//! ```compile_fail
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct LocalPrefs {
+//!         pub dark_mode: bool = false => "dark_mode",
+//!     },
+//!     "local-doc-prefs"
+//! }
+//!
+//! LocalPrefs::global();
+//! ```
+//!
+//! `Prefs<T>` has them for every `T`; without the option, `try_global()` fails with a
+//! `LoadError::StorageError` of kind `Unsupported`.
+//!
+//! `global()` panics if loading fails; `try_global()` returns the error instead. A failed
+//! load (or a panicking directory function) leaves nothing behind, so the next call tries
+//! again, and threads calling either at the same time wait for one load. As the handle's lock
+//! recovers from poisoning, a thread panicking while it holds the lock doesn't make the
+//! preferences unusable for the others. The global handle holds the single-instance claim
+//! for the rest of the process, so other loads from the same directory fail.
+
+use once_cell::sync::{Lazy, OnceCell};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Function returning the directory `global()` loads from.
+pub type DirectoryProvider = fn() -> Result<String, std::io::Error>;
+
+/// One cell per handle type, leaked so they can be handed out as `&'static`.
+static CELLS: Lazy<Mutex<HashMap<TypeId, &'static (dyn Any + Send + Sync)>>> = Lazy::new(Default::default);
+
+/// Returns the process-wide value of type `H`, creating it with `init` if there is none yet.
+///
+/// If `init` fails, nothing is stored and the next call runs its own `init`.
+#[doc(hidden)]
+pub fn get_or_try_init<H: Send + Sync + 'static, E>(init: impl FnOnce() -> Result<H, E>) -> Result<&'static H, E> {
+    let cell = {
+        let mut cells = CELLS.lock().unwrap_or_else(|e| e.into_inner());
+        *cells
+            .entry(TypeId::of::<H>())
+            .or_insert_with(|| Box::leak(Box::new(OnceCell::<H>::new())))
+    };
+    // Initialize outside the map's lock, so loading one struct's preferences may use another's.
+    let cell: &'static OnceCell<H> = cell.downcast_ref().expect("cells are keyed by their type");
+    cell.get_or_try_init(init)
+}
+
+/// Returns the directory of the `global` option of struct `name`.
+///
+/// Fails with `Unsupported` if the struct has no `global` option.
+#[doc(hidden)]
+pub fn directory(provider: Option<DirectoryProvider>, name: &str) -> Result<String, std::io::Error> {
+    match provider {
+        Some(provider) => provider(),
+        None => Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!("{name} has no `global` option; add `global = <directory function>` to use global()"),
+        )),
+    }
+}
@@ -0,0 +1,148 @@
+//! Local history of saved states (`history = N`).
+//!
+//! With the struct option set, every save also records the saved document in a history
+//! stored next to the preferences (storage key `<key>.history`). The last `N` distinct states
+//! are kept, each identified by a hash of its contents and compressed with DEFLATE:
+//!
+//! ```rust
+//! # use easy_prefs::easy_prefs;
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub volume: i32 = 50 => "volume",
+//!     },
+//!     "app-prefs",
+//!     history = 20
+//! }
+//!
+//! let mut prefs = AppPrefs::load_testing();
+//! prefs.save_volume(10).unwrap();
+//! prefs.save_volume(80).unwrap();
+//!
+//! let history = prefs.history().unwrap(); // newest first
+//! prefs.restore(&history[1].hash).unwrap();
+//! assert_eq!(*prefs.get_volume(), 10);
+//! ```
+//!
+//! Saving a state that is already in the history moves it to the front instead of storing
+//! it twice. Restoring saves the restored values, so it becomes the newest state and can be
+//! undone the same way. Values kept in a secret store are not part of the history.
+
+use crate::storage::{content_hash, decode_hex, encode_hex, Storage};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use web_time::{SystemTime, UNIX_EPOCH};
+
+/// A saved state, as listed by `history()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    /// Hash of the saved document; pass it (or a unique prefix) to `restore()`.
+    pub hash: String,
+    /// When the state was last saved.
+    pub saved_at: SystemTime,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Log {
+    #[serde(default, rename = "state")]
+    states: Vec<StoredState>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredState {
+    hash: String,
+    saved_at: u64,
+    /// DEFLATE-compressed document, hex-encoded.
+    data: String,
+}
+
+pub(crate) fn history_key(key: &str) -> String {
+    format!("{key}.history")
+}
+
+fn invalid_data(message: impl Into<String>) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.into())
+}
+
+fn read_log(storage: &dyn Storage, key: &str) -> Result<Log, std::io::Error> {
+    match storage.read(&history_key(key))? {
+        Some(contents) => toml::from_str(&contents)
+            .map_err(|e| invalid_data(format!("preferences history is malformed: {e}"))),
+        None => Ok(Log::default()),
+    }
+}
+
+/// Adds `serialized` as the newest state, keeping at most `limit` states.
+pub fn record(storage: &dyn Storage, key: &str, serialized: &str, limit: usize) -> Result<(), std::io::Error> {
+    // Every save has a new write generation; only the values make a distinct state.
+    let serialized = &crate::generation::strip(serialized);
+    let mut log = read_log(storage, key)?;
+    let hash = content_hash(serialized);
+    let saved_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    let data = match log.states.iter().position(|state| state.hash == hash) {
+        Some(index) => log.states.remove(index).data,
+        None => {
+            let compressed = miniz_oxide::deflate::compress_to_vec(serialized.as_bytes(), 6);
+            let mut data = String::with_capacity(compressed.len() * 2);
+            encode_hex(&compressed, &mut data);
+            data
+        }
+    };
+    log.states.push(StoredState { hash, saved_at, data });
+    let excess = log.states.len().saturating_sub(limit);
+    log.states.drain(..excess);
+
+    let serialized_log = toml::to_string(&log)
+        .map_err(|e| std::io::Error::other(format!("serialization failed: {e}")))?;
+    storage.write(&history_key(key), &serialized_log)
+}
+
+/// Lists the recorded states, newest first.
+pub fn entries(storage: &dyn Storage, key: &str) -> Result<Vec<HistoryEntry>, std::io::Error> {
+    let log = read_log(storage, key)?;
+    Ok(log
+        .states
+        .into_iter()
+        .rev()
+        .map(|state| HistoryEntry {
+            hash: state.hash,
+            saved_at: UNIX_EPOCH + Duration::from_secs(state.saved_at),
+        })
+        .collect())
+}
+
+/// Returns the document saved under `hash`, which may be a unique prefix of the full hash.
+pub fn state(storage: &dyn Storage, key: &str, hash: &str) -> Result<String, std::io::Error> {
+    let log = read_log(storage, key)?;
+    let mut matches = log
+        .states
+        .iter()
+        .filter(|state| !hash.is_empty() && state.hash.starts_with(hash));
+    let state = match (matches.next(), matches.next()) {
+        (Some(state), None) => state,
+        (None, _) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("no saved state matches '{hash}'"),
+            ))
+        }
+        (Some(_), Some(_)) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("'{hash}' matches more than one saved state"),
+            ))
+        }
+    };
+
+    let compressed = decode_hex(&state.data).ok_or_else(|| invalid_data("saved state is malformed"))?;
+    let bytes = miniz_oxide::inflate::decompress_to_vec(&compressed)
+        .map_err(|e| invalid_data(format!("saved state is corrupted: {e}")))?;
+    let document = String::from_utf8(bytes).map_err(|_| invalid_data("saved state is not valid UTF-8"))?;
+    if content_hash(&document) != state.hash {
+        return Err(invalid_data("saved state does not match its hash"));
+    }
+    Ok(document)
+}
@@ -0,0 +1,57 @@
+//! Waiting for the single instance to be released.
+//!
+//! Loading preferences another instance of the same struct (and profile) still holds fails
+//! with `LoadError::InstanceAlreadyLoaded`. When the other instance is about to go away, say
+//! during a hot restart where the old one lingers for a few milliseconds, `try_load_timeout()`
+//! waits for it instead:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//! use std::time::Duration;
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub volume: i32 = 5 => "volume",
+//!     },
+//!     "instance-doc-prefs"
+//! }
+//!
+//! let dir = tempfile::tempdir().unwrap();
+//! let dir = dir.path().to_str().unwrap().to_string();
+//! let old = AppPrefs::load_with_error(&dir).unwrap();
+//! std::thread::spawn(move || {
+//!     std::thread::sleep(Duration::from_millis(20));
+//!     drop(old);
+//! });
+//! let prefs = AppPrefs::try_load_timeout(&dir, Duration::from_secs(5)).unwrap();
+//! assert_eq!(*prefs.get_volume(), 5);
+//! ```
+//!
+//! Other load errors are returned right away. On WASM, where nothing can release the instance
+//! while the caller waits, the load doesn't wait.
+
+use crate::LoadError;
+use web_time::{Duration, Instant};
+
+/// Longest pause between attempts.
+const MAX_PAUSE: Duration = Duration::from_millis(10);
+
+/// Runs `load` until it no longer fails with `InstanceAlreadyLoaded` or `timeout` has passed.
+#[doc(hidden)]
+pub fn retry_while_loaded<T>(timeout: Duration, mut load: impl FnMut() -> Result<T, LoadError>) -> Result<T, LoadError> {
+    let deadline = Instant::now() + timeout;
+    let mut pause = Duration::from_millis(1);
+    loop {
+        match load() {
+            Err(LoadError::InstanceAlreadyLoaded) if !cfg!(target_arch = "wasm32") => {
+                let now = Instant::now();
+                if now >= deadline {
+                    return Err(LoadError::InstanceAlreadyLoaded);
+                }
+                std::thread::sleep(pause.min(deadline - now));
+                pause = (pause * 2).min(MAX_PAUSE);
+            }
+            result => return result,
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! Integrity footer for preference files (`integrity = true`).
+//!
+//! Storage on flash devices suffers from bit rot and silent truncation, and a truncated TOML
+//! file often still parses, just with fewer values. With `integrity = true`, every save ends
+//! the file with a comment holding a hash of everything above it:
+//!
+//! ```toml
+//! theme = "dark"
+//! _generation = 7
+//! # easy_prefs integrity: fnv1a-64 5d2c3e0f9a8b7c61
+//! ```
+//!
+//! Loading verifies the hash and fails with `LoadError::IntegrityFailure` if it is missing or
+//! doesn't match. With `backups = N` the newest backup that passes the check is loaded
+//! instead, and with `reset_on_integrity_failure = true` the defaults are; both are reported
+//! through `load_outcome()`:
+//!
+//! ```rust
+//! use easy_prefs::{easy_prefs, LoadError};
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub theme: String = "light".to_string() => "theme",
+//!         pub volume: i32 = 5 => "volume",
+//!     },
+//!     "integrity-doc-prefs",
+//!     integrity = true
+//! }
+//!
+//! # let tmp = tempfile::tempdir().unwrap();
+//! # let dir = tmp.path().to_str().unwrap();
+//! let mut prefs = AppPrefs::load_with_error(dir).unwrap();
+//! prefs.save_theme("dark".to_string()).unwrap();
+//! let path = prefs.get_preferences_file_path();
+//! drop(prefs);
+//!
+//! // Lose the end of the file: what's left still parses as TOML.
+//! let contents = std::fs::read_to_string(&path).unwrap();
+//! std::fs::write(&path, &contents[..contents.find("volume").unwrap()]).unwrap();
+//! assert!(matches!(AppPrefs::load_with_error(dir), Err(LoadError::IntegrityFailure(..))));
+//! ```
+//!
+//! Files written before the option was enabled have no footer and fail the check too, so
+//! enable it together with `reset_on_integrity_failure` or on a new preferences file. Only the
+//! preferences file is sealed; history, blobs, and secrets are not.
+
+use crate::storage::content_hash;
+
+/// Start of the footer line.
+const PREFIX: &str = "# easy_prefs integrity: fnv1a-64 ";
+
+/// Why a stored document failed the integrity check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityError {
+    /// The document has no footer, e.g. because it was truncated.
+    MissingFooter,
+    /// The footer's hash doesn't match the document.
+    Mismatch {
+        /// Hash recorded in the footer.
+        expected: String,
+        /// Hash of the document as read.
+        actual: String,
+    },
+}
+
+impl std::fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingFooter => write!(f, "integrity footer missing"),
+            Self::Mismatch { expected, actual } => {
+                write!(f, "content hash {actual} does not match {expected}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for IntegrityError {}
+
+/// Returns `document` with its footer appended.
+pub fn seal(document: &str) -> String {
+    let mut sealed = document.to_string();
+    if !sealed.is_empty() && !sealed.ends_with('\n') {
+        sealed.push('\n');
+    }
+    let hash = content_hash(&sealed);
+    sealed.push_str(PREFIX);
+    sealed.push_str(&hash);
+    sealed.push('\n');
+    sealed
+}
+
+/// Checks the footer of `document` and returns the document without it.
+pub fn verify(document: &str) -> Result<&str, IntegrityError> {
+    let (body, footer) = split(document).ok_or(IntegrityError::MissingFooter)?;
+    let actual = content_hash(body);
+    if footer != actual {
+        return Err(IntegrityError::Mismatch { expected: footer.to_string(), actual });
+    }
+    Ok(body)
+}
+
+/// Returns `document` without its footer, if it has one, whether or not the hash matches.
+pub fn strip(document: &str) -> &str {
+    split(document).map_or(document, |(body, _)| body)
+}
+
+/// Splits a document into the part covered by the footer and the footer's hash.
+fn split(document: &str) -> Option<(&str, &str)> {
+    let trimmed = document.strip_suffix('\n').unwrap_or(document);
+    let start = trimmed.rfind('\n').map_or(0, |index| index + 1);
+    let hash = trimmed[start..].strip_prefix(PREFIX)?;
+    Some((&document[..start], hash.trim_end_matches('\r')))
+}
@@ -0,0 +1,315 @@
+//! Serves preferences to companion processes over a local socket (feature `ipc`).
+//!
+//! The protocol is newline-delimited JSON. Each request is one object with an `op` and an
+//! optional `id` that is echoed in the response:
+//!
+//! ```text
+//! → {"id": 1, "op": "list"}
+//! ← {"id": 1, "ok": true, "fields": [{"key": "theme", "value": "dark", "writable": true,
+//!    "description": "Color scheme of the main window."}]}
+//! → {"id": 2, "op": "get", "key": "theme"}
+//! ← {"id": 2, "ok": true, "value": "dark"}
+//! → {"id": 3, "op": "set", "key": "theme", "value": "light"}
+//! ← {"id": 3, "ok": true}
+//! → {"id": 4, "op": "describe", "key": "theme"}
+//! ← {"id": 4, "ok": true, "type": "String", "default": "\"light\".to_string()",
+//!    "description": "Color scheme of the main window.", "constraints": "one of: light, dark"}
+//! → {"id": 5, "op": "subscribe"}
+//! ← {"id": 5, "ok": true}
+//! ← {"event": "changed", "key": "theme", "value": "light"}
+//! ```
+//!
+//! Values are strings in the syntax of [`crate::parse`]. Descriptions are the fields' doc
+//! comments (empty if undocumented), so clients can show help without a copy of the docs;
+//! `constraints` is `null` for fields without any. Failures answer with
+//! `{"ok": false, "error": "..."}`.
+//!
+//! The host app decides what is reachable with [`IpcServer::with_access`]; by default every
+//! field is read-only and secret fields are hidden. Changes made through the socket are
+//! broadcast to subscribers automatically; call [`IpcServer::notify_changes`] after editing
+//! the preferences in-process so subscribers see those too.
+//!
+//! [`IpcServer::listen`] binds a Unix domain socket (readable by the current user only). On
+//! Windows, accept connections on a named pipe with the crate of your choice and hand each one
+//! to [`IpcServer::serve_stream`].
+
+pub use crate::dynamic::Access;
+
+use crate::dynamic::{default_access, AccessPolicy, DynamicPrefs};
+use crate::field::FieldInfo;
+use crate::secrets::REDACTED;
+use serde::Deserialize;
+use serde_json::{json, Value as Json};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::sync::{Arc, Mutex};
+
+type Subscriber = Arc<Mutex<Box<dyn Write + Send>>>;
+
+/// Serves one preferences instance to any number of connections.
+///
+/// Cloning is cheap; clones share the preferences, access policy, and subscribers.
+pub struct IpcServer<P> {
+    prefs: Arc<Mutex<P>>,
+    fields: &'static [FieldInfo],
+    access: Arc<AccessPolicy>,
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+    snapshot: Arc<Mutex<HashMap<&'static str, String>>>,
+}
+
+impl<P> Clone for IpcServer<P> {
+    fn clone(&self) -> Self {
+        Self {
+            prefs: self.prefs.clone(),
+            fields: self.fields,
+            access: self.access.clone(),
+            subscribers: self.subscribers.clone(),
+            snapshot: self.snapshot.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Option<Json>,
+    op: String,
+    #[serde(default)]
+    key: Option<String>,
+    #[serde(default)]
+    value: Option<String>,
+}
+
+fn lock<T: ?Sized>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl<P: DynamicPrefs + Send + 'static> IpcServer<P> {
+    /// Creates a server for `prefs` with the default (read-only) access policy.
+    pub fn new(prefs: Arc<Mutex<P>>) -> Self {
+        let fields = lock(&prefs).fields();
+        let server = Self {
+            prefs,
+            fields,
+            access: Arc::new(default_access),
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            snapshot: Arc::new(Mutex::new(HashMap::new())),
+        };
+        *lock(&server.snapshot) = server.current_values();
+        server
+    }
+
+    /// Replaces the access policy, which is consulted for every request.
+    pub fn with_access(mut self, policy: impl Fn(&FieldInfo) -> Access + Send + Sync + 'static) -> Self {
+        self.access = Arc::new(policy);
+        *lock(&self.snapshot) = self.current_values();
+        self
+    }
+
+    /// Handles requests from `reader` until it reaches end of file, writing responses and,
+    /// after a `subscribe` request, change events to `writer`.
+    pub fn serve_stream(
+        &self,
+        reader: impl BufRead,
+        writer: impl Write + Send + 'static,
+    ) -> Result<(), std::io::Error> {
+        let writer: Subscriber = Arc::new(Mutex::new(Box::new(writer)));
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = self.handle_line(&line, &writer);
+            send(&writer, &response)?;
+            if response["ok"] == true {
+                self.notify_changes();
+            }
+        }
+        let mut subscribers = lock(&self.subscribers);
+        subscribers.retain(|s| !Arc::ptr_eq(s, &writer));
+        Ok(())
+    }
+
+    /// Sends a `changed` event to subscribers for every readable field whose value differs
+    /// from the last notification. Secret fields are announced with a redacted value.
+    pub fn notify_changes(&self) {
+        let current = self.current_values();
+        let changed: Vec<Json> = {
+            let mut snapshot = lock(&self.snapshot);
+            let changed = self
+                .fields
+                .iter()
+                .filter_map(|field| {
+                    let value = current.get(field.key)?;
+                    (snapshot.get(field.key) != Some(value)).then(|| {
+                        json!({"event": "changed", "key": field.key, "value": shown(field, value)})
+                    })
+                })
+                .collect();
+            *snapshot = current;
+            changed
+        };
+        if changed.is_empty() {
+            return;
+        }
+        let mut subscribers = lock(&self.subscribers);
+        subscribers.retain(|subscriber| changed.iter().all(|event| send(subscriber, event).is_ok()));
+    }
+
+    /// Listens on a Unix domain socket at `path`, serving each connection on its own thread.
+    ///
+    /// A stale socket left at `path` by a previous run is replaced; any other existing file
+    /// is an error. The socket is made accessible to the current user only.
+    #[cfg(unix)]
+    pub fn listen(
+        &self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<std::thread::JoinHandle<()>, std::io::Error> {
+        use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+        use std::os::unix::net::UnixListener;
+
+        let path = path.as_ref();
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AlreadyExists,
+                    format!("{} exists and is not a socket", path.display()),
+                ));
+            }
+            std::fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+
+        let server = self.clone();
+        Ok(std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        crate::scrub::log(server.fields, None::<&()>, format_args!("IPC accept failed: {e}"));
+                        continue;
+                    }
+                };
+                let server = server.clone();
+                std::thread::spawn(move || {
+                    let result = stream.try_clone().and_then(|writer| {
+                        server.serve_stream(std::io::BufReader::new(stream), writer)
+                    });
+                    if let Err(e) = result {
+                        crate::scrub::log(server.fields, None::<&()>, format_args!("IPC connection failed: {e}"));
+                    }
+                });
+            }
+        }))
+    }
+
+    fn handle_line(&self, line: &str, writer: &Subscriber) -> Json {
+        let request: Request = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => return json!({"ok": false, "error": format!("invalid request: {e}")}),
+        };
+        let mut response = match self.handle(&request, writer) {
+            Ok(Json::Object(mut body)) => {
+                body.insert("ok".to_string(), Json::Bool(true));
+                Json::Object(body)
+            }
+            Ok(_) => json!({"ok": true}),
+            Err(e) => json!({"ok": false, "error": e}),
+        };
+        if let Some(id) = request.id {
+            response["id"] = id;
+        }
+        response
+    }
+
+    fn handle(&self, request: &Request, writer: &Subscriber) -> Result<Json, String> {
+        match request.op.as_str() {
+            "list" => {
+                let values = self.current_values();
+                let fields: Vec<Json> = self
+                    .fields
+                    .iter()
+                    .filter_map(|field| {
+                        let value = values.get(field.key)?;
+                        Some(json!({
+                            "key": field.key,
+                            "value": shown(field, value),
+                            "writable": (self.access)(field) == Access::ReadWrite,
+                            "description": field.description(),
+                        }))
+                    })
+                    .collect();
+                Ok(json!({"fields": fields}))
+            }
+            "get" => {
+                let field = self.field(request)?;
+                let value = lock(&self.prefs).get_field_string(field.key).unwrap_or_default();
+                Ok(json!({"value": shown(field, &value)}))
+            }
+            "set" => {
+                let field = self.field(request)?;
+                if (self.access)(field) != Access::ReadWrite {
+                    return Err(format!("'{}' is read-only", field.key));
+                }
+                let value = request.value.as_deref().ok_or("missing 'value'")?;
+                lock(&self.prefs)
+                    .set_field_from_str(field.key, value)
+                    .map_err(|e| e.to_string())?;
+                Ok(Json::Null)
+            }
+            "describe" => {
+                let field = self.field(request)?;
+                Ok(json!({
+                    "type": field.type_name,
+                    "default": field.default,
+                    "description": field.description(),
+                    "constraints": field.constraints(),
+                }))
+            }
+            "subscribe" => {
+                let mut subscribers = lock(&self.subscribers);
+                if !subscribers.iter().any(|s| Arc::ptr_eq(s, writer)) {
+                    subscribers.push(writer.clone());
+                }
+                Ok(Json::Null)
+            }
+            op => Err(format!("unknown op '{op}'")),
+        }
+    }
+
+    /// Looks up the requested field, treating hidden fields as unknown.
+    fn field(&self, request: &Request) -> Result<&'static FieldInfo, String> {
+        let key = request.key.as_deref().ok_or("missing 'key'")?;
+        self.fields
+            .iter()
+            .find(|field| field.key == key && (self.access)(field) != Access::Hidden)
+            .ok_or_else(|| crate::dynamic::unknown_key(key).to_string())
+    }
+
+    /// Unredacted values of every field that is not hidden.
+    fn current_values(&self) -> HashMap<&'static str, String> {
+        let prefs = lock(&self.prefs);
+        self.fields
+            .iter()
+            .filter(|field| (self.access)(field) != Access::Hidden)
+            .filter_map(|field| Some((field.key, prefs.get_field_string(field.key)?)))
+            .collect()
+    }
+}
+
+/// The value as sent to clients: secrets are redacted.
+fn shown<'a>(field: &FieldInfo, value: &'a str) -> &'a str {
+    if field.options.secret {
+        REDACTED
+    } else {
+        value
+    }
+}
+
+fn send(writer: &Subscriber, message: &Json) -> Result<(), std::io::Error> {
+    let mut writer = lock(writer);
+    serde_json::to_writer(&mut *writer, message)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
@@ -0,0 +1,95 @@
+//! Export and import as JSON, for apps and services that don't speak TOML (feature `json`).
+//!
+//! `to_json()` writes the exported fields (see [`export`](crate::export)) as a JSON object
+//! keyed by their saved names, and `from_json()` applies such an object and saves, like
+//! `export_toml()` and `import_toml()`:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub theme: String = "light".to_string() => "theme",
+//!         pub font_size: i32 = 14 => "font_size" (range = 8..=72, on_invalid = clamp),
+//!         pub api_token: String = String::new() => "api_token" (secret),
+//!     },
+//!     "json-doc-prefs"
+//! }
+//!
+//! # easy_prefs::secrets::set_secret_store(easy_prefs::secrets::MemorySecretStore::new());
+//! let mut prefs = AppPrefs::load_testing();
+//! assert_eq!(prefs.to_json().unwrap(), r#"{"font_size":14,"theme":"light"}"#);
+//!
+//! prefs.from_json(r#"{"theme": "dark", "font_size": 100, "api_token": null}"#).unwrap();
+//! assert_eq!(prefs.get_theme(), "dark");
+//! assert_eq!(*prefs.get_font_size(), 72);
+//! ```
+//!
+//! Object keys come out sorted. Dates and times are written as strings. `null` stands for a
+//! missing value: an `Option` field set to `None`, or a field to leave as it is. Integers
+//! beyond the range of `i64` can't be imported, and `null` inside an array is an error.
+
+use serde::de::Error as _;
+use serde_json::{Map, Number, Value as Json};
+use toml::{Table, Value};
+
+/// Location reported in `LoadError::DeserializationError` for JSON that doesn't import.
+pub const LOCATION: &str = "json";
+
+/// Writes `table` as a compact JSON object.
+#[doc(hidden)]
+pub fn to_string(table: &Table) -> String {
+    let object = Json::Object(table.iter().map(|(key, value)| (key.clone(), to_json(value))).collect());
+    serde_json::to_string(&object).expect("JSON values always serialize")
+}
+
+/// Reads a JSON object as a table, leaving out `null` members.
+#[doc(hidden)]
+pub fn parse(json: &str) -> Result<Table, toml::de::Error> {
+    match serde_json::from_str(json).map_err(toml::de::Error::custom)? {
+        Json::Object(object) => from_object(object),
+        _ => Err(toml::de::Error::custom("expected a JSON object")),
+    }
+}
+
+fn to_json(value: &Value) -> Json {
+    match value {
+        Value::String(s) => Json::String(s.clone()),
+        Value::Integer(i) => Json::Number((*i).into()),
+        // JSON has no NaN or infinity.
+        Value::Float(f) => Number::from_f64(*f).map_or(Json::Null, Json::Number),
+        Value::Boolean(b) => Json::Bool(*b),
+        Value::Datetime(datetime) => Json::String(datetime.to_string()),
+        Value::Array(array) => Json::Array(array.iter().map(to_json).collect()),
+        Value::Table(table) => {
+            Json::Object(table.iter().map(|(key, value)| (key.clone(), to_json(value))).collect::<Map<_, _>>())
+        }
+    }
+}
+
+fn from_object(object: Map<String, Json>) -> Result<Table, toml::de::Error> {
+    let mut table = Table::new();
+    for (key, value) in object {
+        if value.is_null() {
+            continue;
+        }
+        let value = from_json(value).map_err(|e| toml::de::Error::custom(format!("'{key}': {}", e.message())))?;
+        table.insert(key, value);
+    }
+    Ok(table)
+}
+
+fn from_json(value: Json) -> Result<Value, toml::de::Error> {
+    Ok(match value {
+        Json::Null => return Err(toml::de::Error::custom("null is only allowed as an object member")),
+        Json::Bool(b) => Value::Boolean(b),
+        Json::Number(n) => match (n.as_i64(), n.as_f64()) {
+            (Some(i), _) if !n.is_f64() => Value::Integer(i),
+            (_, Some(f)) if n.is_f64() => Value::Float(f),
+            _ => return Err(toml::de::Error::custom(format!("{n} is out of range for a 64-bit integer"))),
+        },
+        Json::String(s) => Value::String(s),
+        Json::Array(array) => Value::Array(array.into_iter().map(from_json).collect::<Result<_, _>>()?),
+        Json::Object(object) => Value::Table(from_object(object)?),
+    })
+}
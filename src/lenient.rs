@@ -0,0 +1,214 @@
+//! Lenient loading: keeping every stored value that still loads.
+//!
+//! A single unparseable line or mistyped value makes `load_with_error()` fail, and `load()` then
+//! falls back to defaults for *every* field in release builds. `load_lenient()` instead drops
+//! only what doesn't load and returns a [`LoadWarning`] for each thing it dropped, so the app
+//! can tell the user which settings were reset:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub volume: i32 = 5 => "volume",
+//!         pub theme: String = "light".to_string() => "theme",
+//!         pub dark_mode: bool = false => "dark_mode",
+//!     },
+//!     "lenient-doc-prefs"
+//! }
+//!
+//! let dir = tempfile::tempdir().unwrap();
+//! let document = "volume = \"loud\"\ntheme = \"dark\nlight\"\ndark_mode = true\n";
+//! std::fs::write(dir.path().join("lenient-doc-prefs.toml"), document).unwrap();
+//!
+//! let (prefs, warnings) = AppPrefs::load_lenient(dir.path().to_str().unwrap());
+//! assert_eq!(*prefs.get_volume(), 5);
+//! assert_eq!(prefs.get_theme(), "light");
+//! assert!(*prefs.get_dark_mode());
+//! assert_eq!(warnings.len(), 3);
+//! assert_eq!(warnings[0].field, "theme");
+//! assert_eq!(warnings[0].raw_value.as_deref(), Some("theme = \"dark"));
+//! ```
+//!
+//! For the TOML format, lines that don't parse are dropped one at a time. Values that don't
+//! deserialize into their field, and values breaking a constraint of an `on_invalid = error`
+//! field, are dropped so the field takes its default. Values repaired by their constraints are
+//! reported too. With `strict`, unknown keys are dropped and reported rather than failing the
+//! load. If nothing can be salvaged (storage error, another format that doesn't parse, or a
+//! struct-level validator failing), all fields take their defaults and a single warning with an
+//! empty `field` says why. Raw values of secret fields are redacted.
+
+use crate::field::FieldInfo;
+use crate::options::PrefsOptions;
+use crate::scrub::scrub;
+use crate::secrets::REDACTED;
+use toml::Table;
+
+/// A stored value (or line) that `load_lenient()` didn't load.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadWarning {
+    /// Saved name of the field, the key on a dropped line (with its section, as in
+    /// `window.width`), or empty if the whole document was dropped or the dropped line has no
+    /// key.
+    pub field: String,
+    /// Why the value was not loaded.
+    pub reason: String,
+    /// The stored value, or the whole dropped line; `None` if the whole document was dropped.
+    pub raw_value: Option<String>,
+}
+
+impl LoadWarning {
+    /// A warning that nothing stored could be loaded, with `error` (scrubbed) as the reason.
+    #[doc(hidden)]
+    pub fn document(error: &dyn std::fmt::Display, fields: &[FieldInfo]) -> Self {
+        LoadWarning { field: String::new(), reason: scrub(&error.to_string(), fields, None::<&()>), raw_value: None }
+    }
+}
+
+impl std::fmt::Display for LoadWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.field.as_str(), &self.raw_value) {
+            ("", Some(raw)) => write!(f, "line dropped ({raw}): {}", self.reason),
+            ("", None) => write!(f, "stored preferences dropped: {}", self.reason),
+            (field, Some(raw)) => write!(f, "'{field}' dropped ({raw}): {}", self.reason),
+            (field, None) => write!(f, "'{field}' dropped: {}", self.reason),
+        }
+    }
+}
+
+/// Parses the stored `contents` (as read from storage), leaving out what doesn't load.
+///
+/// Returns the salvaged table, or `None` if nothing was stored or nothing could be salvaged.
+#[doc(hidden)]
+pub fn salvage<T>(
+    contents: std::io::Result<Option<String>>,
+    fields: &[FieldInfo],
+    options: &PrefsOptions<T>,
+    defaults: &Table,
+    deserialize: impl Fn(Table) -> Result<(), toml::de::Error>,
+) -> (Option<Table>, Vec<LoadWarning>) {
+    let mut warnings = Vec::new();
+    let contents = match contents {
+        Ok(Some(contents)) => contents,
+        Ok(None) => return (None, warnings),
+        Err(e) => {
+            warnings.push(LoadWarning::document(&format_args!("storage error: {e}"), fields));
+            return (None, warnings);
+        }
+    };
+    let contents = match crate::integrity::verify(&contents) {
+        Err(e) if options.integrity => {
+            warnings.push(LoadWarning::document(&format_args!("integrity check failed: {e}"), fields));
+            crate::integrity::strip(&contents)
+        }
+        Ok(body) if options.integrity => body,
+        _ => &contents,
+    };
+
+    let Some(mut table) = parse(contents, fields, options, &mut warnings) else {
+        return (None, warnings);
+    };
+    if options.lenient_keys {
+        crate::coerce::normalize_keys(&mut table, fields);
+    }
+
+    let keys: Vec<String> = table.keys().cloned().collect();
+    for key in keys {
+        if key == crate::generation::KEY || key == crate::temporary::TABLE_KEY {
+            continue;
+        }
+        let Some(field) = fields.iter().find(|field| field.key == key) else {
+            if options.strict && !options.compat_sidecar {
+                let raw_value = table.remove(&key).map(|value| value.to_string());
+                warnings.push(LoadWarning { field: key, reason: "unknown key".to_string(), raw_value });
+            }
+            continue;
+        };
+        let raw_value = Some(match field.options.secret {
+            true => REDACTED.to_string(),
+            false => table[&key].to_string(),
+        });
+
+        let mut single = Table::new();
+        single.insert(key.clone(), table[&key].clone());
+        crate::coerce::coerce_table(&mut single, defaults, fields, options.lenient_types);
+        let mut probe = defaults.clone();
+        probe.insert(key.clone(), single[&key].clone());
+        if let Err(e) = deserialize(probe) {
+            let reason = match field.options.secret {
+                true => "value has the wrong type".to_string(),
+                false => e.message().trim_end().to_string(),
+            };
+            table.remove(&key);
+            warnings.push(LoadWarning { field: key, reason, raw_value });
+            continue;
+        }
+
+        let reason = match crate::validate::sanitize_table(&mut single, fields) {
+            Ok(repairs) => match repairs.into_iter().next() {
+                Some(repair) => match repair.clamped {
+                    Some(clamped) => format!("{}, clamped to {clamped}", repair.error.reason),
+                    None => repair.error.reason,
+                },
+                None => continue,
+            },
+            Err(error) => {
+                table.remove(&key);
+                error.reason
+            }
+        };
+        warnings.push(LoadWarning { field: key, reason, raw_value });
+    }
+    (Some(table), warnings)
+}
+
+/// Parses `contents` with the struct's format, dropping (for TOML) each line that doesn't
+/// parse until the rest does.
+fn parse<T>(
+    contents: &str,
+    fields: &[FieldInfo],
+    options: &PrefsOptions<T>,
+    warnings: &mut Vec<LoadWarning>,
+) -> Option<Table> {
+    let mut lines: Vec<&str> = contents.split_inclusive('\n').collect();
+    loop {
+        let document = lines.concat();
+        let error = match options.format.parse(&document) {
+            Ok(table) => return Some(table),
+            Err(error) => error,
+        };
+        let index = error
+            .span()
+            .filter(|_| options.format.name() == "toml")
+            .map(|span| document[..span.start.min(document.len())].matches('\n').count())
+            .filter(|index| *index < lines.len());
+        let Some(index) = index else {
+            warnings.push(LoadWarning::document(&format_args!("deserialization error: {error}"), fields));
+            return None;
+        };
+
+        let line = lines.remove(index).trim();
+        let field = match line.split_once('=') {
+            Some((key, _)) if !line.starts_with('[') => {
+                let key = key.trim().trim_matches('"');
+                match section(&lines[..index]) {
+                    Some(section) => format!("{section}.{key}"),
+                    None => key.to_string(),
+                }
+            }
+            _ => String::new(),
+        };
+        warnings.push(LoadWarning {
+            field,
+            reason: error.message().trim_end().to_string(),
+            raw_value: Some(scrub(line, fields, None::<&()>)),
+        });
+    }
+}
+
+/// Returns the name of the table the line after `lines` belongs to, if it isn't the root.
+fn section(lines: &[&str]) -> Option<String> {
+    let header = lines.iter().rev().map(|line| line.trim()).find(|line| line.starts_with('['))?;
+    let name = header.trim_start_matches('[').split(']').next()?.trim();
+    Some(name.to_string())
+}
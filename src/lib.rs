@@ -34,14 +34,105 @@
 //! This library supports WebAssembly targets for use in browser extensions and web applications.
 //! When compiled to WASM, preferences are stored in localStorage instead of the file system.
 
+pub mod alias;
+pub mod backup;
+pub mod blob;
+pub mod change;
+pub mod check;
+pub mod coerce;
+pub mod critical;
+pub mod defaults;
+#[cfg(all(feature = "dbus", target_os = "linux"))]
+pub mod dbus;
+pub mod document;
+pub mod dynamic;
+pub mod embed;
+pub mod export;
+pub mod field;
+pub mod format;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod gc;
+pub mod generation;
+pub mod global;
+pub mod history;
+pub mod instance;
+pub mod integrity;
+#[cfg(all(feature = "ipc", not(target_arch = "wasm32")))]
+pub mod ipc;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod lenient;
+pub mod lint;
+#[cfg(all(feature = "websocket", not(target_arch = "wasm32")))]
+pub mod live_sync;
+pub mod migrate;
+#[cfg(all(feature = "mqtt", not(target_arch = "wasm32")))]
+pub mod mqtt;
+pub mod options;
+pub mod parse;
+pub mod patch;
+pub mod prefs;
+pub mod preserve;
+pub mod profile;
+pub mod registry;
+pub mod removed;
+#[cfg(all(feature = "rest", not(target_arch = "wasm32")))]
+pub mod rest;
+pub mod schedule;
+pub mod schema;
+pub mod scrub;
+pub mod secrets;
+pub mod section;
+pub mod serialize;
+pub mod sidecar;
+pub mod stats;
 pub mod storage;
+pub mod strict;
+pub mod sync;
+pub mod temporary;
+pub mod transaction;
+pub mod transient;
+pub mod validate;
+pub mod watch;
+pub mod write_token;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod writer;
 
-// Re-export dependencies for convenience
+pub use backup::LoadOutcome;
+pub use dynamic::Preferences;
+pub use options::PrefsOptions;
+pub use prefs::{Prefs, PrefsData, PrefsHandle};
+pub use registry::flush_all;
+pub use stats::LoadStats;
+
+#[cfg(feature = "derive")]
+pub use easy_prefs_derive::EasyPrefs;
+
+// Re-export dependencies for convenience (`reexports` feature, on by default)
+#[cfg(feature = "reexports")]
 pub use once_cell;
+#[cfg(feature = "reexports")]
 pub use paste; // Macro utilities
+#[cfg(feature = "reexports")]
 pub use toml; // TOML serialization
+#[cfg(all(feature = "reexports", feature = "watch"))]
+pub use tokio; // Change channels for `watch_<field>()`
+#[cfg(feature = "reexports")]
 pub use web_time; // Cross-platform time implementation
 
+/// Dependencies used by the generated code, whether or not the `reexports` feature is on.
+#[doc(hidden)]
+pub mod __private {
+    pub use once_cell;
+    pub use paste;
+    #[cfg(not(target_arch = "wasm32"))]
+    pub use tempfile;
+    #[cfg(feature = "watch")]
+    pub use tokio;
+    pub use toml;
+    pub use web_time;
+}
+
 /// Errors that can occur when loading preferences.
 #[derive(Debug)]
 pub enum LoadError {
@@ -51,6 +142,12 @@ pub enum LoadError {
     DeserializationError(String, toml::de::Error),
     /// Storage operation failed
     StorageError(std::io::Error),
+    /// Loaded data violates a struct-level validator.
+    ValidationError(validate::ValidationError),
+    /// The stored file failed its integrity check (see `integrity`).
+    IntegrityFailure(String, integrity::IntegrityError),
+    /// Saving the restored, imported, patched, or synced values failed.
+    SaveFailed(SaveError),
 }
 
 impl std::fmt::Display for LoadError {
@@ -63,15 +160,192 @@ impl std::fmt::Display for LoadError {
                 write!(f, "deserialization error: {e} at {location}")
             }
             Self::StorageError(e) => write!(f, "storage error: {e}"),
+            Self::ValidationError(e) => write!(f, "validation error: {e}"),
+            Self::IntegrityFailure(location, e) => {
+                write!(f, "integrity check failed: {e} at {location}")
+            }
+            Self::SaveFailed(e) => write!(f, "save error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::SaveFailed(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Errors that can occur when saving preferences.
+#[derive(Debug)]
+pub enum SaveError {
+    /// The values could not be serialized to TOML.
+    SerializationFailed(toml::ser::Error),
+    /// The value of a field (named by its saved name) could not be serialized to TOML (see
+    /// [`serialize`]).
+    FieldSerializationFailed(&'static str, toml::ser::Error),
+    /// The preferences have no storage backend (e.g. a struct created with `Default`).
+    StorageUninitialized,
+    /// The storage is full (disk full or browser storage quota exceeded).
+    QuotaExceeded,
+    /// Reading or writing storage failed.
+    Io(std::io::Error),
+    /// The values violate a field constraint or struct-level validator.
+    ValidationError(validate::ValidationError),
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SerializationFailed(e) => write!(f, "serialization failed: {e}"),
+            Self::FieldSerializationFailed(field, e) => {
+                write!(f, "serialization failed: {e} at field '{field}'")
+            }
+            Self::StorageUninitialized => write!(f, "storage not initialized"),
+            Self::QuotaExceeded => write!(f, "storage quota exceeded"),
+            Self::Io(e) => write!(f, "storage error: {e}"),
+            Self::ValidationError(e) => write!(f, "validation error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::SerializationFailed(e) | Self::FieldSerializationFailed(_, e) => Some(e),
+            Self::Io(e) => Some(e),
+            Self::ValidationError(e) => Some(e),
+            Self::StorageUninitialized | Self::QuotaExceeded => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SaveError {
+    fn from(e: std::io::Error) -> Self {
+        if e.get_ref().is_some_and(|inner| inner.is::<validate::ValidationError>()) {
+            let inner = e.into_inner().expect("checked above");
+            return Self::ValidationError(*inner.downcast().expect("checked above"));
+        }
+        match e.kind() {
+            std::io::ErrorKind::StorageFull => Self::QuotaExceeded,
+            _ => Self::Io(e),
         }
     }
 }
 
-impl std::error::Error for LoadError {}
+impl From<validate::ValidationError> for SaveError {
+    fn from(e: validate::ValidationError) -> Self {
+        Self::ValidationError(e)
+    }
+}
+
+impl From<toml::ser::Error> for SaveError {
+    fn from(e: toml::ser::Error) -> Self {
+        Self::SerializationFailed(e)
+    }
+}
+
+/// For APIs that report `std::io::Error` (e.g. `DynamicPrefs`); validation errors become
+/// `InvalidInput` errors wrapping the `ValidationError`.
+impl From<SaveError> for std::io::Error {
+    fn from(e: SaveError) -> Self {
+        match e {
+            SaveError::Io(e) => e,
+            SaveError::ValidationError(e) => e.into(),
+            SaveError::QuotaExceeded => std::io::Error::new(std::io::ErrorKind::StorageFull, e.to_string()),
+            e => std::io::Error::other(e.to_string()),
+        }
+    }
+}
+/// Expands its input only when the `encryption` feature is enabled (native targets).
+#[doc(hidden)]
+#[cfg(all(feature = "encryption", not(target_arch = "wasm32")))]
+#[macro_export]
+macro_rules! __easy_prefs_if_encryption {
+    ($($item:tt)*) => { $($item)* };
+}
+
+#[doc(hidden)]
+#[cfg(not(all(feature = "encryption", not(target_arch = "wasm32"))))]
+#[macro_export]
+macro_rules! __easy_prefs_if_encryption {
+    ($($item:tt)*) => {};
+}
+
+/// Expands its input only when the `watch` feature is enabled.
+#[doc(hidden)]
+#[cfg(feature = "watch")]
+#[macro_export]
+macro_rules! __easy_prefs_if_watch {
+    ($($item:tt)*) => { $($item)* };
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "watch"))]
+#[macro_export]
+macro_rules! __easy_prefs_if_watch {
+    ($($item:tt)*) => {};
+}
+
+/// Expands its input only when the `json` feature is enabled.
+#[doc(hidden)]
+#[cfg(feature = "json")]
+#[macro_export]
+macro_rules! __easy_prefs_if_json {
+    ($($item:tt)*) => { $($item)* };
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "json"))]
+#[macro_export]
+macro_rules! __easy_prefs_if_json {
+    ($($item:tt)*) => {};
+}
+
+/// Generates the methods that only exist with a struct option: `global()` and `try_global()`
+/// for `global`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __easy_prefs_option_items {
+    (global, $name:ident, $handle:ident) => {
+        impl $name {
+            /// Returns the process-wide handle, loading the preferences on first access from
+            /// the directory of the `global` option (see `easy_prefs::global`).
+            ///
+            /// # Panics
+            ///
+            /// Panics if loading fails.
+            pub fn global() -> &'static $handle
+            where
+                for<'a> $handle: Send + Sync,
+            {
+                Self::try_global().unwrap_or_else(|e| {
+                    $crate::scrub::panic(Self::FIELDS, None::<&Self>, format_args!("Failed to load global preferences: {}", e))
+                })
+            }
+
+            /// Like `global()`, but returns load errors; the next call tries again.
+            pub fn try_global() -> Result<&'static $handle, $crate::LoadError>
+            where
+                for<'a> $handle: Send + Sync,
+            {
+                $crate::global::get_or_try_init(|| {
+                    let directory = $crate::global::directory(Self::OPTIONS.global, stringify!($name))
+                        .map_err($crate::LoadError::StorageError)?;
+                    Self::load_shared(&directory)
+                })
+            }
+        }
+    };
+    ($option:ident, $name:ident, $handle:ident) => {};
+}
+
 /// Macro to define a preferences struct with persistence.
 ///
 /// Generates a struct with methods for loading, saving, and editing preferences.
-/// Enforces a single instance (except in test mode) using a static flag.
+/// Enforces a single instance per stored file (except in test mode) using a process-wide registry.
 ///
 /// # Example
 ///
@@ -87,6 +361,23 @@ impl std::error::Error for LoadError {}
 /// }
 /// ```
 ///
+/// Fields may carry options in parentheses after the saved name, and struct-level options
+/// follow the filename as `name = value` pairs (see [`field::FieldOptions`] and [`PrefsOptions`]):
+///
+/// ```rust
+/// use easy_prefs::easy_prefs;
+///
+/// easy_prefs! {
+///     pub struct ImportedPrefs {
+///         pub enabled: bool = true => "enabled" (lenient_types),
+///     },
+///     "imported-settings",
+///     lenient_types = true
+/// }
+/// ```
+///
+/// Related fields can be grouped into a TOML table with a section (see [`section`]).
+///
 /// # Platform Behavior
 ///
 /// - **Native**: Stores preferences as TOML files in the specified directory
@@ -97,32 +388,19 @@ macro_rules! easy_prefs {
         $(#[$outer:meta])*
         $vis:vis struct $name:ident {
             $(
-                $(#[$inner:meta])*
-                $field_vis:vis $field:ident: $type:ty = $default:expr => $saved_name:expr,
+                $(#[$($inner:tt)*])*
+                $field_vis:vis $field:ident: $type:ty = $default:expr => $saved_name:literal $( ( $($field_option:tt)* ) )?,
             )*
         },
-        $preferences_filename:expr
+        $preferences_filename:expr $(, $option:ident = $option_value:expr)* $(,)?
     ) => {
-        $crate::paste::paste!{
-            // Static flag to enforce single instance.
-            static [<$name:upper _INSTANCE_EXISTS>]: $crate::once_cell::sync::Lazy<std::sync::atomic::AtomicBool> =
-                $crate::once_cell::sync::Lazy::new(|| std::sync::atomic::AtomicBool::new(false));
-
-            // Guard that resets the instance flag on drop.
-            #[derive(Debug)]
-            struct [<$name InstanceGuard>];
-            impl Drop for [<$name InstanceGuard>] {
-                fn drop(&mut self) {
-                    [<$name:upper _INSTANCE_EXISTS>].store(false, std::sync::atomic::Ordering::Release);
-                }
-            }
-
+        $crate::__private::paste::paste!{
             $(#[$outer])*
-            #[derive(serde::Serialize, serde::Deserialize, Debug)]
+            #[derive(serde::Serialize, serde::Deserialize)]
             #[serde(default)]  // Use defaults for missing fields.
             $vis struct $name {
                 $(
-                    $(#[$inner])*
+                    $(#[$($inner)*])*
                     #[serde(rename = $saved_name)]
                     $field_vis [<_ $field>]: $type,
                 )*
@@ -132,20 +410,158 @@ macro_rules! easy_prefs {
                 storage_key: Option<String>,
                 #[serde(skip_serializing, skip_deserializing)]
                 #[cfg(not(target_arch = "wasm32"))]
-                temp_file: Option<tempfile::NamedTempFile>,
+                temp_file: Option<$crate::__private::tempfile::NamedTempFile>,
+                #[serde(skip_serializing, skip_deserializing)]
+                #[cfg(target_arch = "wasm32")]
+                test_keys: Option<$crate::storage::wasm::TestKeys>,
+                #[serde(skip_serializing, skip_deserializing)]
+                _instance_guard: Option<$crate::prefs::InstanceGuard>,
+                #[serde(skip_serializing, skip_deserializing)]
+                _registration: Option<$crate::registry::Registration>,
+                #[serde(skip_serializing, skip_deserializing)]
+                generation: std::sync::atomic::AtomicU64,
+                #[serde(skip_serializing, skip_deserializing)]
+                load_outcome: $crate::LoadOutcome,
+                #[serde(skip_serializing, skip_deserializing)]
+                load_stats: $crate::LoadStats,
                 #[serde(skip_serializing, skip_deserializing)]
-                _instance_guard: Option<[<$name InstanceGuard>]>,
+                _changes: $crate::change::Tracker,
+                $(
+                    #[serde(skip_serializing, skip_deserializing)]
+                    [<_temporary_ $field>]: Option<$crate::temporary::Override<$type>>,
+                )*
+                $(
+                    #[serde(skip_serializing, skip_deserializing)]
+                    [<_watch_ $field>]: $crate::watch::Watch<$type>,
+                )*
+            }
+
+            // Debug output lists the fields only, with secret values redacted.
+            impl std::fmt::Debug for $name {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    let mut debug = f.debug_struct(stringify!($name));
+                    $(
+                        if Self::field_info($saved_name).options.secret {
+                            debug.field(stringify!($field), &$crate::secrets::REDACTED);
+                        } else {
+                            debug.field(stringify!($field), self.[<get_ $field>]());
+                        }
+                    )*
+                    debug.finish_non_exhaustive()
+                }
+            }
+
+            impl $crate::dynamic::DynamicPrefs for $name {
+                fn fields(&self) -> &'static [$crate::field::FieldInfo] {
+                    Self::FIELDS
+                }
+
+                fn get_field_string(&self, key: &str) -> Option<String> {
+                    match key {
+                        $( $saved_name => Some(self.[<get_ $field _as_string>]()), )*
+                        _ => None,
+                    }
+                }
+
+                fn set_field_from_str(&mut self, key: &str, input: &str) -> Result<(), std::io::Error> {
+                    match key {
+                        $( $saved_name => self.[<set_ $field _from_str>](input), )*
+                        _ => Err($crate::dynamic::unknown_key(key)),
+                    }
+                }
+
+                fn get_value(&self, key: &str) -> Option<$crate::__private::toml::Value> {
+                    match key {
+                        $( $saved_name => self.stored_value(key), )*
+                        _ => None,
+                    }
+                }
+
+                fn set_value(&mut self, key: &str, value: $crate::__private::toml::Value) -> Result<(), std::io::Error> {
+                    match key {
+                        $(
+                            $saved_name => {
+                                let hint = self.stored_value(key);
+                                let value = $crate::dynamic::convert_value_with(key, value, hint.as_ref(), |value| {
+                                    Self::from_stored_value(key, value, |data| data.$field)
+                                })?;
+                                Ok(self.[<save_ $field>](value)?)
+                            }
+                        )*
+                        _ => Err($crate::dynamic::unknown_key(key)),
+                    }
+                }
+            }
+
+            impl $crate::dynamic::Preferences for $name {
+                fn load_with_error(directory: &str) -> Result<Self, $crate::LoadError> {
+                    Self::load_with_error(directory)
+                }
+
+                fn save(&self) -> Result<(), $crate::SaveError> {
+                    Self::save(self)
+                }
+
+                fn reload(&mut self) -> Result<(), $crate::LoadError> {
+                    Self::reload(self)
+                }
+
+                fn get_preferences_file_path(&self) -> String {
+                    Self::get_preferences_file_path(self)
+                }
+            }
+
+            // Loading and saving go through the generic functions of `easy_prefs::prefs`.
+            impl $crate::PrefsData for $name {
+                const PREFERENCES_FILENAME: &'static str = $name::PREFERENCES_FILENAME;
+                const FIELDS: &'static [$crate::field::FieldInfo] = $name::FIELDS;
+                const OPTIONS: $crate::PrefsOptions<Self> = $name::OPTIONS;
+                const SCHEMA_HASH: u64 = $name::SCHEMA_HASH;
+
+                // Fields are serialized under their saved names already.
+                fn to_saved_table(&self) -> Result<$crate::__private::toml::Table, $crate::SaveError> {
+                    $crate::__private::toml::Table::try_from(self).map_err(|e| $crate::serialize::error(e, self, Self::FIELDS))
+                }
+
+                fn from_saved_table(
+                    table: $crate::__private::toml::Table,
+                ) -> Result<Self, $crate::__private::toml::de::Error> {
+                    $crate::__private::toml::Value::Table(table).try_into()
+                }
+
+                fn store_temporary(&self, table: &mut $crate::__private::toml::Table) -> Result<(), $crate::SaveError> {
+                    $( $crate::temporary::store(table, $saved_name, self.[<_temporary_ $field>].as_ref())?; )*
+                    Ok(())
+                }
+
+                fn read_temporary(&mut self, overrides: &$crate::__private::toml::Table) {
+                    $( self.[<_temporary_ $field>] = $crate::temporary::read(overrides, $saved_name); )*
+                }
             }
 
+            $( $crate::__easy_prefs_option_items!($option, $name, [<$name Handle>]); )*
+
+            // Two fields saved under one key would overwrite each other; fail the build.
+            const _: () = $crate::field::assert_unique_keys($name::FIELDS);
+
             impl Default for $name {
                 fn default() -> Self {
                     Self {
-                        $( [<_ $field>]: $default, )*
+                        $( [<_ $field>]: Self::[<default_ $field>](), )*
                         storage: None,
                         storage_key: None,
                         #[cfg(not(target_arch = "wasm32"))]
                         temp_file: None,
+                        #[cfg(target_arch = "wasm32")]
+                        test_keys: None,
                         _instance_guard: None,
+                        _registration: None,
+                        generation: std::sync::atomic::AtomicU64::new(0),
+                        load_outcome: $crate::LoadOutcome::Loaded,
+                        load_stats: Default::default(),
+                        _changes: Default::default(),
+                        $( [<_temporary_ $field>]: None, )*
+                        $( [<_watch_ $field>]: Default::default(), )*
                     }
                 }
             }
@@ -153,6 +569,41 @@ macro_rules! easy_prefs {
             impl $name {
                 pub const PREFERENCES_FILENAME: &'static str = concat!($preferences_filename, ".toml");
 
+                /// Struct-level options given to the macro.
+                pub const OPTIONS: $crate::PrefsOptions<Self> = $crate::PrefsOptions::new() $( .$option($option_value) )*;
+
+                /// Metadata for every field, in declaration order.
+                pub const FIELDS: &'static [$crate::field::FieldInfo] = &[
+                    $(
+                        $crate::field::FieldInfo {
+                            name: stringify!($field),
+                            key: $saved_name,
+                            type_name: stringify!($type),
+                            default: stringify!($default),
+                            doc: $crate::__easy_prefs_doc!($([$($inner)*])*),
+                            aliases: $crate::__easy_prefs_aliases!(options [$($($field_option)*)?] $([$($inner)*])*),
+                            options: $crate::__easy_prefs_field_options!($($($field_option)*)?),
+                        },
+                    )*
+                ];
+
+                /// Hash of the fields' names, keys, and types (see `easy_prefs::schema`).
+                pub const SCHEMA_HASH: u64 = $crate::schema::hash(Self::FIELDS);
+
+                /// Iterates over the fields in declaration order with their current values (as
+                /// returned by `DynamicPrefs::get_value()`; `None` for a `None` option).
+                pub fn iter(&self) -> impl Iterator<Item = (&'static $crate::field::FieldInfo, Option<$crate::__private::toml::Value>)> + '_ {
+                    Self::FIELDS.iter().map(move |field| {
+                        (field, <Self as $crate::dynamic::DynamicPrefs>::get_value(self, field.key))
+                    })
+                }
+
+                /// Returns the current values keyed by saved name, leaving out `None` options and
+                /// redacting secret fields.
+                pub fn as_map(&self) -> std::collections::BTreeMap<&'static str, $crate::dynamic::PrefValue> {
+                    $crate::dynamic::value_map(self.iter())
+                }
+
                 /// Loads preferences from a file, gracefully handling errors.
                 ///
                 /// This method provides a simple API that always succeeds:
@@ -176,49 +627,77 @@ macro_rules! easy_prefs {
                         Err(e) => {
                             // Always panic if another instance exists - this is a programming error
                             if matches!(e, $crate::LoadError::InstanceAlreadyLoaded) {
-                                panic!("Failed to load preferences: {}", e);
+                                $crate::scrub::panic(Self::FIELDS, None::<&Self>, format_args!("Failed to load preferences: {}", e));
                             }
 
                             #[cfg(any(debug_assertions, test))]
                             {
                                 // Panic in debug/test to catch issues early
-                                panic!("Failed to load preferences: {}", e);
+                                $crate::scrub::panic(Self::FIELDS, None::<&Self>, format_args!("Failed to load preferences: {}", e));
                             }
 
                             #[cfg(not(any(debug_assertions, test)))]
                             {
                                 // In production, log the error and return defaults
-                                eprintln!("Failed to load preferences from {}: {}, using defaults", directory, e);
-
-                                // We need to acquire the instance guard for the default instance
-                                // First, try to acquire it
-                                let was_free = [<$name:upper _INSTANCE_EXISTS>].compare_exchange(
-                                    false, true, std::sync::atomic::Ordering::Acquire, std::sync::atomic::Ordering::Relaxed
+                                $crate::scrub::log(
+                                    Self::FIELDS,
+                                    None::<&Self>,
+                                    format_args!("Failed to load preferences from {}: {}, using defaults", directory, e),
                                 );
 
-                                if was_free.is_err() {
-                                    // This should be rare - means load_with_error failed but instance still exists
-                                    panic!("Failed to load preferences and instance is still locked: {}", e);
-                                }
-
-                                let guard = [<$name InstanceGuard>];
-                                let storage = $crate::storage::create_storage(directory);
+                                let storage = $crate::prefs::configure_storage::<Self>($crate::storage::create_storage(directory));
                                 let storage_key = Self::PREFERENCES_FILENAME;
+                                // We need to acquire the instance guard for the default instance
+                                let guard = $crate::prefs::claim_instance::<Self>(&storage.get_path(storage_key)).unwrap_or_else(|_| {
+                                    // This should be rare - means load_with_error failed but instance still exists
+                                    $crate::scrub::panic(
+                                        Self::FIELDS,
+                                        None::<&Self>,
+                                        format_args!("Failed to load preferences and instance is still locked: {}", e),
+                                    )
+                                });
 
+                                let (storage, registration) = $crate::registry::register(storage, storage_key);
                                 let mut cfg = Self::default();
                                 cfg.storage = Some(storage);
                                 cfg.storage_key = Some(storage_key.to_string());
-                                cfg._instance_guard = Some(guard);
+                                cfg._registration = Some(registration);
+                                cfg._instance_guard = guard;
                                 cfg
                             }
                         }
                     }
                 }
 
+                /// Loads preferences, keeping every stored value that loads and using defaults
+                /// for the rest: unparseable lines and mistyped values are dropped, each with a
+                /// warning (see `easy_prefs::lenient`).
+                ///
+                /// # Panics
+                ///
+                /// Panics if another instance is already loaded.
+                pub fn load_lenient(directory: &str) -> (Self, Vec<$crate::lenient::LoadWarning>) {
+                    let storage = $crate::prefs::configure_storage::<Self>($crate::storage::create_storage(directory));
+                    let storage_key = Self::PREFERENCES_FILENAME;
+                    let guard = $crate::prefs::claim_instance::<Self>(&storage.get_path(storage_key)).unwrap_or_else(|e| {
+                        $crate::scrub::panic(Self::FIELDS, None::<&Self>, format_args!("Failed to load preferences: {}", e))
+                    });
+                    let (loaded, warnings) = $crate::prefs::read_lenient::<Self>(storage.as_ref(), storage_key);
+                    let mut cfg = Self::from_loaded(loaded);
+
+                    let (storage, registration) = $crate::registry::register(storage, storage_key);
+                    cfg.storage = Some(storage);
+                    cfg.storage_key = Some(storage_key.to_string());
+                    cfg._instance_guard = guard;
+                    cfg._registration = Some(registration);
+                    (cfg, warnings)
+                }
+
                 /// Loads preferences from a file with explicit error handling.
                 ///
                 /// Deserializes from file if it exists; otherwise uses defaults.
-                /// Only one instance can exist at a time (tracked by a static flag).
+                /// Only one instance per stored file can exist at a time (tracked in a process-wide
+                /// registry), unless the struct sets `single_instance = false`.
                 ///
                 /// # Arguments
                 ///
@@ -231,44 +710,518 @@ macro_rules! easy_prefs {
                 /// - Storage operations fail.
                 /// - TOML deserialization fails.
                 pub fn load_with_error(directory: &str) -> Result<Self, $crate::LoadError> {
+                    Self::load_with_storage($crate::storage::create_storage(directory))
+                }
 
-                    {
-                        // Runtime duplicate check for field_names. We don't want duplicates!
-                        use std::collections::HashSet;
-                        let keys = [ $( ($saved_name, stringify!($field) ), )* ];
-                        let mut seen = HashSet::new();
-                        for (key, field_name) in keys.iter() {
-                            if !seen.insert(*key) {
-                                panic!("Duplicate saved_name '{}' found for field '{}'", key, field_name);
+                /// Loads preferences like `load_with_error()`, but if another instance is still
+                /// loaded, waits up to `timeout` for it to be dropped (see `easy_prefs::instance`).
+                ///
+                /// # Errors
+                ///
+                /// Returns `LoadError::InstanceAlreadyLoaded` if the other instance is still loaded
+                /// after `timeout`, and the other errors of `load_with_error()` right away.
+                pub fn try_load_timeout(directory: &str, timeout: std::time::Duration) -> Result<Self, $crate::LoadError> {
+                    $crate::instance::retry_while_loaded(timeout, || Self::load_with_error(directory))
+                }
+
+                /// Loads preferences from the platform's configuration directory for `app_id`
+                /// (e.g. `"com.example.app"`; see `easy_prefs::storage::config_directory`), or
+                /// from localStorage under `app_id` on WASM.
+                ///
+                /// # Errors
+                ///
+                /// Returns the errors of `load_with_error()`, and `LoadError::StorageError` if
+                /// the directory can't be resolved (no home directory).
+                pub fn load_auto(app_id: &str) -> Result<Self, $crate::LoadError> {
+                    let directory = $crate::storage::config_directory(app_id).map_err($crate::LoadError::StorageError)?;
+                    Self::load_with_error(&directory)
+                }
+
+                /// Loads preferences like `load_with_error()` and wraps them in a cloneable,
+                /// thread-safe handle.
+                pub fn load_shared(directory: &str) -> Result<[<$name Handle>], $crate::LoadError> {
+                    Self::load_with_error(directory).map(Self::into_shared)
+                }
+
+                /// Wraps the preferences in a cloneable, thread-safe handle.
+                pub fn into_shared(self) -> [<$name Handle>] {
+                    [<$name Handle>] {
+                        inner: std::sync::Arc::new(std::sync::RwLock::new(self)),
+                    }
+                }
+
+                /// Loads preferences from a custom storage backend with explicit error handling.
+                ///
+                /// Behaves like `load_with_error()`, including the single-instance constraint,
+                /// but reads and writes through `storage` instead of the platform default.
+                ///
+                /// # Errors
+                ///
+                /// Returns a `LoadError` if:
+                /// - Another instance is already loaded.
+                /// - Storage operations fail.
+                /// - TOML deserialization fails.
+                pub fn load_with_storage(storage: Box<dyn $crate::storage::Storage>) -> Result<Self, $crate::LoadError> {
+                    Self::load_from(storage, Self::PREFERENCES_FILENAME.to_string())
+                }
+
+                /// Loads the named profile, stored as `<filename>.<profile>.toml` (see
+                /// `easy_prefs::profile`).
+                ///
+                /// # Errors
+                ///
+                /// Returns the errors of `load_with_error()`, where the single-instance constraint
+                /// applies per profile, and `LoadError::StorageError` for an invalid profile name.
+                pub fn load_profile(directory: &str, profile: &str) -> Result<Self, $crate::LoadError> {
+                    let storage_key = $crate::profile::storage_key(Self::PREFERENCES_FILENAME, profile)?;
+                    Self::load_from($crate::storage::create_storage(directory), storage_key)
+                }
+
+                /// Loads the preferences of `document` (e.g. its path), stored in a file derived
+                /// from the identifier (see `easy_prefs::document`).
+                ///
+                /// # Errors
+                ///
+                /// Returns the errors of `load_with_error()`, where the single-instance constraint
+                /// applies per document, and `LoadError::StorageError` for an empty identifier.
+                pub fn load_for(directory: &str, document: &str) -> Result<Self, $crate::LoadError> {
+                    let storage_key = $crate::document::storage_key(Self::PREFERENCES_FILENAME, document)?;
+                    Self::load_from($crate::storage::create_storage(directory), storage_key)
+                }
+
+                /// Copies the stored preferences from `old_directory` to `new_directory`, with their
+                /// blobs, history, backups, and secrets, verifying each copy (see
+                /// `easy_prefs::migrate`).
+                ///
+                /// Returns `Ok(false)` if there is nothing to migrate or `new_directory` already has
+                /// preferences. Call it before loading.
+                pub fn migrate(old_directory: &str, new_directory: &str) -> Result<bool, std::io::Error> {
+                    $crate::migrate::migrate_prefs(
+                        &$crate::prefs::resolved_storage::<Self>(old_directory),
+                        &$crate::prefs::resolved_storage::<Self>(new_directory),
+                        Self::PREFERENCES_FILENAME,
+                        Self::FIELDS,
+                        Self::OPTIONS.history > 0,
+                        Self::OPTIONS.backups,
+                    )
+                }
+
+                /// Removes the profile and document files in `directory` that `policy` selects,
+                /// with their companions and secrets, except those loaded in this process (see
+                /// `easy_prefs::gc`).
+                #[cfg(not(target_arch = "wasm32"))]
+                pub fn gc(directory: &str, policy: &$crate::gc::GcPolicy) -> Result<$crate::gc::GcReport, std::io::Error> {
+                    let filename = $crate::storage::resolve_key(Self::PREFERENCES_FILENAME, Self::OPTIONS.key_separators)?;
+                    $crate::gc::run(directory, &filename, Self::FIELDS, &$crate::prefs::loaded_paths::<Self>(), policy)
+                }
+
+                /// Checks the preferences stored in `directory` (storage permissions, parsing,
+                /// integrity, backups, free space) without loading them, for a diagnostics
+                /// screen (see `easy_prefs::check`).
+                pub fn self_check(directory: &str) -> $crate::check::CheckReport {
+                    let storage = $crate::prefs::resolved_storage::<Self>(directory);
+                    let storage = &storage;
+                    $crate::check::run(
+                        storage,
+                        Self::PREFERENCES_FILENAME,
+                        Self::OPTIONS.integrity,
+                        Self::OPTIONS.backups,
+                        |contents| $crate::prefs::from_stored::<Self>(Some(contents), storage, Self::PREFERENCES_FILENAME).map(drop),
+                    )
+                }
+
+                fn load_from(storage: Box<dyn $crate::storage::Storage>, storage_key: String) -> Result<Self, $crate::LoadError> {
+                    let storage = $crate::prefs::configure_storage::<Self>(storage);
+                    let guard = $crate::prefs::claim_instance::<Self>(&storage.get_path(&storage_key))?;
+                    let mut cfg = Self::read_from_storage(storage.as_ref(), &storage_key)?;
+
+                    let (storage, registration) = $crate::registry::register(storage, &storage_key);
+                    cfg.storage = Some(storage);
+                    cfg.storage_key = Some(storage_key);
+                    cfg._instance_guard = guard;
+                    cfg._registration = Some(registration);
+                    Ok(cfg)
+                }
+
+                $crate::__easy_prefs_if_encryption! {
+                    /// Loads preferences stored encrypted at rest with AES-256-GCM.
+                    ///
+                    /// Behaves like `load_with_error()` but uses an `EncryptedFileStorage` with the
+                    /// caller-supplied 256-bit key. Requires the `encryption` feature.
+                    ///
+                    /// # Errors
+                    ///
+                    /// Returns `LoadError::StorageError` if the file cannot be decrypted (for
+                    /// example with the wrong key), plus the errors of `load_with_error()`.
+                    pub fn load_encrypted(directory: &str, key: &[u8; 32]) -> Result<Self, $crate::LoadError> {
+                        Self::load_with_storage(Box::new(
+                            $crate::storage::encrypted::EncryptedFileStorage::new(directory, key)
+                        ))
+                    }
+                }
+
+                /// Re-reads the preferences from storage and replaces the in-memory values.
+                ///
+                /// Use this to pick up changes made by another process or by hand-editing the file.
+                /// The instance guard and storage location are kept. If the stored data no longer
+                /// exists, all fields are reset to their defaults.
+                ///
+                /// # Errors
+                ///
+                /// Returns a `LoadError` if storage is not initialized, the read fails, or the
+                /// stored data cannot be deserialized. In-memory values are left untouched on error.
+                pub fn reload(&mut self) -> Result<(), $crate::LoadError> {
+                    let (storage, storage_key) = match (&self.storage, &self.storage_key) {
+                        (Some(storage), Some(key)) => (storage, key),
+                        _ => return Err($crate::LoadError::StorageError(std::io::Error::new(
+                            std::io::ErrorKind::Other,
+                            "storage not initialized"
+                        ))),
+                    };
+
+                    let fresh = Self::read_from_storage(storage.as_ref(), storage_key)?;
+                    $(
+                        self.[<_ $field>] = fresh.[<_ $field>];
+                        self.[<_temporary_ $field>] = fresh.[<_temporary_ $field>];
+                    )*
+                    self.generation = fresh.generation;
+                    self.load_outcome = fresh.load_outcome;
+                    self.load_stats = fresh.load_stats;
+                    self.notify_watchers();
+                    self.report_changes($crate::change::SaveSource::Reload);
+                    Ok(())
+                }
+
+                /// Re-reads only the fields saved under `keys` from storage, keeping the in-memory
+                /// values of all others (e.g. keys managed by another tool in the same file).
+                ///
+                /// # Errors
+                ///
+                /// Like `reload()`, plus `StorageError` with `NotFound` for a key that is not a field
+                /// and `ValidationError` if the combined values fail struct validation. In-memory
+                /// values are left untouched on error.
+                pub fn reload_fields(&mut self, keys: &[&str]) -> Result<(), $crate::LoadError> {
+                    if let Some(key) = keys.iter().find(|key| !Self::FIELDS.iter().any(|f| f.key == **key)) {
+                        return Err($crate::LoadError::StorageError($crate::dynamic::unknown_key(key)));
+                    }
+                    let (storage, storage_key) = match (&self.storage, &self.storage_key) {
+                        (Some(storage), Some(key)) => (storage, key),
+                        _ => return Err($crate::LoadError::StorageError(std::io::Error::new(
+                            std::io::ErrorKind::Other,
+                            "storage not initialized"
+                        ))),
+                    };
+
+                    let mut fresh = Self::read_from_storage(storage.as_ref(), storage_key)?;
+                    let swap = |prefs: &mut Self, fresh: &mut Self| {
+                        $(
+                            if keys.contains(&$saved_name) {
+                                std::mem::swap(&mut prefs.[<_ $field>], &mut fresh.[<_ $field>]);
+                                std::mem::swap(&mut prefs.[<_temporary_ $field>], &mut fresh.[<_temporary_ $field>]);
                             }
-                        }
+                        )*
+                    };
+                    swap(self, &mut fresh);
+                    if let Err(e) = $crate::validate::check_struct(Self::OPTIONS.validate_struct, self) {
+                        swap(self, &mut fresh);
+                        return Err($crate::LoadError::ValidationError(e));
                     }
+                    self.notify_watchers();
+                    self.report_changes($crate::change::SaveSource::Reload);
+                    Ok(())
+                }
 
-                    let was_free = [<$name:upper _INSTANCE_EXISTS>].compare_exchange(
-                        false, true, std::sync::atomic::Ordering::Acquire, std::sync::atomic::Ordering::Relaxed
-                    );
-                    if was_free.is_err() {
-                        return Err($crate::LoadError::InstanceAlreadyLoaded);
+                /// Sends the current values to `watch_<field>()` receivers.
+                fn notify_watchers(&self) {
+                    $( self.[<_watch_ $field>].notify(self.[<get_ $field>]()); )*
+                }
+
+                /// Reports the fields changed since the last load, save, or reload to the
+                /// `on_change` hook, if any.
+                fn report_changes(&self, source: $crate::change::SaveSource) {
+                    if let Some(hook) = Self::OPTIONS.on_change {
+                        let values = $crate::prefs::serialize_or_panic(self);
+                        self._changes.report(hook, Self::FIELDS, values, || $crate::prefs::serialize_or_panic(&Self::default()), source);
                     }
+                }
 
-                    let guard = [<$name InstanceGuard>];
-                    let storage = $crate::storage::create_storage(directory);
-                    let storage_key = Self::PREFERENCES_FILENAME;
+                /// Reads and deserializes the stored data, falling back to defaults if none exists and
+                /// to the newest loadable backup if it doesn't deserialize.
+                fn read_from_storage(
+                    storage: &dyn $crate::storage::Storage,
+                    storage_key: &str,
+                ) -> Result<Self, $crate::LoadError> {
+                    $crate::prefs::read_from_storage::<Self>(storage, storage_key).map(Self::from_loaded)
+                }
+
+                /// Takes the values read from storage with their generation and load details.
+                fn from_loaded(loaded: $crate::prefs::Loaded<Self>) -> Self {
+                    let $crate::prefs::Loaded { data: mut prefs, generation, outcome, stats } = loaded;
+                    prefs.generation = std::sync::atomic::AtomicU64::new(generation);
+                    prefs.load_outcome = outcome;
+                    prefs.load_stats = stats;
+                    if Self::OPTIONS.on_change.is_some() {
+                        prefs._changes.record($crate::prefs::serialize_or_panic(&prefs));
+                    }
+                    prefs
+                }
+
+                /// Reports suspicious values in the stored document (out-of-range values, empty
+                /// `required` strings, future timestamps, unknown keys) without changing anything
+                /// (see `easy_prefs::lint`).
+                ///
+                /// # Errors
+                ///
+                /// Returns the storage error, or `InvalidData` if the document doesn't parse.
+                pub fn lint(&self) -> Result<$crate::lint::LintReport, std::io::Error> {
+                    let (storage, storage_key) = match (&self.storage, &self.storage_key) {
+                        (Some(storage), Some(key)) => (storage, key),
+                        _ => return Err(std::io::Error::new(std::io::ErrorKind::Other, "storage not initialized")),
+                    };
+                    let document = storage.read(storage_key)?.unwrap_or_default();
+                    $crate::lint::lint_document(&document, Self::FIELDS)
+                        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+                }
+
+                /// Lists the states recorded by the `history` option, newest first.
+                ///
+                /// Returns an empty list if history is disabled or nothing was saved yet.
+                pub fn history(&self) -> Result<Vec<$crate::history::HistoryEntry>, std::io::Error> {
+                    match (&self.storage, &self.storage_key) {
+                        (Some(storage), Some(key)) => $crate::history::entries(storage.as_ref(), key),
+                        _ => Err(std::io::Error::new(std::io::ErrorKind::Other, "storage not initialized")),
+                    }
+                }
+
+                /// Replaces the values with the recorded state `hash` (or a unique prefix of it) and saves them.
+                ///
+                /// # Errors
+                ///
+                /// Returns `LoadError::StorageError` if no single state matches or the history
+                /// cannot be read, and the other `LoadError` variants if the state no longer loads
+                /// (e.g. it fails struct validation). In-memory values are left untouched on error.
+                pub fn restore(&mut self, hash: &str) -> Result<(), $crate::LoadError> {
+                    let (storage, storage_key) = match (&self.storage, &self.storage_key) {
+                        (Some(storage), Some(key)) => (storage, key),
+                        _ => return Err($crate::LoadError::StorageError(std::io::Error::new(
+                            std::io::ErrorKind::Other,
+                            "storage not initialized"
+                        ))),
+                    };
+
+                    let contents = $crate::history::state(storage.as_ref(), storage_key, hash)
+                        .map_err($crate::LoadError::StorageError)?;
+                    let (restored, _) = $crate::prefs::from_stored::<Self>(Some(&contents), storage.as_ref(), storage_key)?;
+                    $(
+                        self.[<_ $field>] = restored.[<_ $field>];
+                        self.[<_temporary_ $field>] = restored.[<_temporary_ $field>];
+                    )*
+                    self.save_as($crate::change::SaveSource::Migration).map_err($crate::LoadError::SaveFailed)
+                }
+
+                /// Serializes the exported fields as TOML (see `easy_prefs::export`).
+                pub fn export_toml(&self) -> Result<String, $crate::SaveError> {
+                    let mut table = $crate::__private::toml::Table::try_from(self)
+                        .map_err(|e| $crate::serialize::error(e, self, Self::FIELDS))?;
+                    $crate::export::retain_exported(&mut table, Self::FIELDS);
+                    Ok($crate::__private::toml::to_string(&table)?)
+                }
+
+                /// Applies the exported fields in `contents` and saves them (see `easy_prefs::export`).
+                ///
+                /// In-memory values are left untouched if `contents` doesn't parse or fails validation.
+                pub fn import_toml(&mut self, contents: &str) -> Result<(), $crate::LoadError> {
+                    let mut imported: $crate::__private::toml::Table = contents
+                        .parse()
+                        .map_err(|e| $crate::LoadError::DeserializationError("import".to_string(), e))?;
+                    $crate::export::retain_exported(&mut imported, Self::FIELDS);
+                    self.import_table(imported)
+                }
+
+                $crate::__easy_prefs_if_json! {
+                    /// Serializes the exported fields as a JSON object (see `easy_prefs::json`).
+                    pub fn to_json(&self) -> Result<String, $crate::SaveError> {
+                        let mut table = $crate::__private::toml::Table::try_from(self)
+                            .map_err(|e| $crate::serialize::error(e, self, Self::FIELDS))?;
+                        $crate::export::retain_exported(&mut table, Self::FIELDS);
+                        Ok($crate::json::to_string(&table))
+                    }
+
+                    /// Applies the exported fields of a JSON object and saves them (see
+                    /// `easy_prefs::json`).
+                    ///
+                    /// In-memory values are left untouched if `json` doesn't parse or fails validation.
+                    #[allow(clippy::wrong_self_convention)]
+                    pub fn from_json(&mut self, json: &str) -> Result<(), $crate::LoadError> {
+                        let mut imported = $crate::json::parse(json)
+                            .map_err(|e| $crate::LoadError::DeserializationError($crate::json::LOCATION.to_string(), e))?;
+                        $crate::export::retain_exported(&mut imported, Self::FIELDS);
+                        self.import_table(imported)
+                    }
+                }
 
-                    let mut cfg = match storage.read(storage_key).map_err($crate::LoadError::StorageError)? {
-                        Some(contents) => {
-                            $crate::toml::from_str::<Self>(&contents)
-                                .map_err(|e| $crate::LoadError::DeserializationError(
-                                    storage.get_path(storage_key), e
-                                ))?
+                /// Returns the exported fields as a table tagged with a format version, to store
+                /// inside another document such as a project file (see `easy_prefs::embed`).
+                pub fn embed(&self) -> Result<$crate::__private::toml::Table, $crate::SaveError> {
+                    let table = $crate::__private::toml::Table::try_from(self)
+                        .map_err(|e| $crate::serialize::error(e, self, Self::FIELDS))?;
+                    Ok($crate::embed::wrap(table, Self::FIELDS, Self::SCHEMA_HASH))
+                }
+
+                /// Applies a table returned by `embed()` and saves (see `easy_prefs::embed`).
+                ///
+                /// In-memory values are left untouched if the table is of a newer format or fails
+                /// validation.
+                pub fn unembed(&mut self, embedded: &$crate::__private::toml::Table) -> Result<(), $crate::LoadError> {
+                    let imported = $crate::embed::unwrap(embedded, Self::FIELDS).map_err($crate::LoadError::StorageError)?;
+                    self.import_table(imported)
+                }
+
+                /// Applies the exported fields in `imported` (see `import_toml()`).
+                fn import_table(&mut self, imported: $crate::__private::toml::Table) -> Result<(), $crate::LoadError> {
+                    let mut table = $crate::__private::toml::Table::try_from(&*self)
+                        .map_err(|e| $crate::LoadError::SaveFailed($crate::serialize::error(e, self, Self::FIELDS)))?;
+                    let imported_keys: Vec<String> = imported.keys().cloned().collect();
+                    table.extend(imported);
+                    let fresh = $crate::prefs::from_table::<Self>(table, "import")?;
+                    $(
+                        self.[<_ $field>] = fresh.[<_ $field>];
+                        if imported_keys.iter().any(|key| key == $saved_name) {
+                            self.[<_temporary_ $field>] = None;
+                        }
+                    )*
+                    self.save_as($crate::change::SaveSource::Migration).map_err($crate::LoadError::SaveFailed)
+                }
+
+                /// Applies the keys present in a partial TOML document and saves (see
+                /// `easy_prefs::patch`).
+                ///
+                /// In-memory values are left untouched if the patch doesn't parse or a patched value
+                /// has the wrong type or fails validation.
+                pub fn apply_toml_patch(&mut self, patch: &str) -> Result<$crate::patch::PatchReport, $crate::LoadError> {
+                    let patch: $crate::__private::toml::Table = patch
+                        .parse()
+                        .map_err(|e| $crate::LoadError::DeserializationError($crate::patch::LOCATION.to_string(), e))?;
+                    self.apply_patch_table(patch)
+                }
+
+                /// Applies the values of a TOML document that `policy` selects, like
+                /// `apply_toml_patch()`, and saves once (see `easy_prefs::patch`).
+                ///
+                /// In-memory values are left untouched if the document doesn't parse or a selected
+                /// value has the wrong type or fails validation.
+                pub fn merge_from_toml(
+                    &mut self,
+                    toml: &str,
+                    policy: $crate::patch::MergePolicy,
+                ) -> Result<$crate::patch::PatchReport, $crate::LoadError> {
+                    let patch: $crate::__private::toml::Table = toml
+                        .parse()
+                        .map_err(|e| $crate::LoadError::DeserializationError($crate::patch::LOCATION.to_string(), e))?;
+                    let current = $crate::__private::toml::Table::try_from(&*self)
+                        .map_err(|e| $crate::LoadError::SaveFailed($crate::serialize::error(e, self, Self::FIELDS)))?;
+                    let defaults = $crate::prefs::serialize_or_panic(&Self::default());
+                    let (patch, skipped) = $crate::patch::select(patch, &current, &defaults, Self::FIELDS, policy);
+                    let mut report = self.apply_patch_table(patch)?;
+                    report.skipped = skipped;
+                    Ok(report)
+                }
+
+                /// Applies the keys present in `patch` (see `apply_toml_patch()`).
+                fn apply_patch_table(
+                    &mut self,
+                    patch: $crate::__private::toml::Table,
+                ) -> Result<$crate::patch::PatchReport, $crate::LoadError> {
+                    let mut table = $crate::__private::toml::Table::try_from(&*self)
+                        .map_err(|e| $crate::LoadError::SaveFailed($crate::serialize::error(e, self, Self::FIELDS)))?;
+                    let report = $crate::patch::merge(&mut table, patch, Self::FIELDS)
+                        .map_err($crate::LoadError::ValidationError)?;
+                    if !report.changed() {
+                        return Ok(report);
+                    }
+                    let fresh = $crate::prefs::from_table::<Self>(table, $crate::patch::LOCATION)?;
+                    $(
+                        if report.applied.contains(&$saved_name) {
+                            self.[<_ $field>] = fresh.[<_ $field>];
+                            self.[<_temporary_ $field>] = None;
                         }
-                        None => Self::default(),
+                    )*
+                    self.save_as($crate::change::SaveSource::Migration).map_err($crate::LoadError::SaveFailed)?;
+                    Ok(report)
+                }
+
+                /// Reconciles the exported fields with the copy held by `provider`, saves the
+                /// fields taken from it, and pushes the result (see `easy_prefs::sync`).
+                ///
+                /// In-memory values are left untouched if pulling fails or the remote copy is
+                /// invalid.
+                pub fn sync(
+                    &mut self,
+                    provider: &mut dyn $crate::sync::SyncProvider,
+                    resolution: $crate::sync::Resolution,
+                ) -> Result<$crate::sync::SyncReport, $crate::LoadError> {
+                    let (storage, storage_key) = match (&self.storage, &self.storage_key) {
+                        (Some(storage), Some(key)) => (storage, key),
+                        _ => return Err($crate::LoadError::StorageError(std::io::Error::new(
+                            std::io::ErrorKind::Other,
+                            "storage not initialized"
+                        ))),
                     };
+                    let mut table = $crate::__private::toml::Table::try_from(&*self)
+                        .map_err(|e| $crate::LoadError::SaveFailed($crate::serialize::error(e, self, Self::FIELDS)))?;
+                    let plan = $crate::sync::plan(
+                        provider,
+                        storage.as_ref(),
+                        storage_key,
+                        Self::FIELDS,
+                        table.clone(),
+                        || $crate::prefs::serialize_or_panic(&Self::default()),
+                        resolution,
+                    )?;
 
-                    cfg.storage = Some(storage);
-                    cfg.storage_key = Some(storage_key.to_string());
-                    cfg._instance_guard = Some(guard);
-                    Ok(cfg)
+                    if !plan.report.pulled.is_empty() {
+                        plan.apply(&mut table);
+                        let fresh = $crate::prefs::from_table::<Self>(table, $crate::sync::LOCATION)?;
+                        $(
+                            if plan.report.pulled.contains(&$saved_name) {
+                                self.[<_ $field>] = fresh.[<_ $field>];
+                                self.[<_temporary_ $field>] = None;
+                            }
+                        )*
+                        self.save_as($crate::change::SaveSource::Sync).map_err($crate::LoadError::SaveFailed)?;
+                    }
+                    match (&self.storage, &self.storage_key) {
+                        (Some(storage), Some(key)) => $crate::sync::finish(provider, storage.as_ref(), key, plan),
+                        _ => unreachable!("storage checked above"),
+                    }
+                }
+
+                /// Returns the stored form of the field saved under `key`, as written by its serde
+                /// attributes; `None` for a `None` option.
+                fn stored_value(&self, key: &str) -> Option<$crate::__private::toml::Value> {
+                    $(
+                        // An expired override's previous value isn't in the struct's fields yet.
+                        if key == $saved_name && self.[<_temporary_ $field>].as_ref().is_some_and(|t| t.is_expired()) {
+                            return $crate::__private::toml::Value::try_from(self.[<get_ $field>]()).ok();
+                        }
+                    )*
+                    $crate::__private::toml::Table::try_from(self).ok()?.remove(key)
+                }
+
+                /// Reads a field from its stored form under `key`, through its serde attributes.
+                fn from_stored_value<F>(
+                    key: &str,
+                    value: $crate::__private::toml::Value,
+                    field: impl FnOnce([<$name Data>]) -> F,
+                ) -> Result<F, $crate::__private::toml::de::Error> {
+                    let mut table = $crate::__private::toml::Table::new();
+                    table.insert(key.to_string(), value);
+                    $crate::__private::toml::Value::Table(table).try_into::<[<$name Data>]>().map(field)
+                }
+
+                /// Looks up the metadata of a field by its saved key.
+                fn field_info(key: &str) -> &'static $crate::field::FieldInfo {
+                    Self::FIELDS.iter().find(|f| f.key == key).expect("unknown field key")
                 }
 
                 /// DEPRECATED: This method is no longer supported.
@@ -306,42 +1259,74 @@ macro_rules! easy_prefs {
                 /// Loads preferences into a temporary location for testing (ignores the single-instance constraint).
                 #[cfg(not(target_arch = "wasm32"))]
                 pub fn load_testing() -> Self {
-                    let tmp_file = tempfile::NamedTempFile::with_prefix(Self::PREFERENCES_FILENAME)
+                    let tmp_file = $crate::__private::tempfile::NamedTempFile::with_prefix(Self::PREFERENCES_FILENAME)
                         .expect("Failed to create temporary file for testing preferences");
                     let tmp_dir = tmp_file.path().parent().unwrap().to_str().unwrap();
-                    let storage = $crate::storage::create_storage(tmp_dir);
+                    let storage = $crate::storage::PrivateSecrets::new($crate::storage::create_storage(tmp_dir));
+                    let storage = $crate::prefs::configure_storage::<Self>(Box::new(storage));
                     let storage_key = tmp_file.path().file_name().unwrap().to_str().unwrap();
 
                     let mut cfg = Self::default();
-                    let serialized = $crate::toml::to_string(&cfg).unwrap();
+                    let table = $crate::__private::toml::Table::try_from(&cfg).unwrap();
+                    let serialized = Self::OPTIONS.format.to_string(&table).unwrap();
                     storage.write(storage_key, &serialized)
                         .expect("Failed to write preferences data to temporary file");
 
+                    let (storage, registration) = $crate::registry::register(storage, storage_key);
                     cfg.storage = Some(storage);
                     cfg.storage_key = Some(storage_key.to_string());
                     cfg.temp_file = Some(tmp_file);
+                    cfg._registration = Some(registration);
                     cfg
                 }
 
                 /// Loads preferences into a temporary location for testing (ignores the single-instance constraint).
+                ///
+                /// Each instance gets its own localStorage keys, removed when it is dropped.
                 #[cfg(target_arch = "wasm32")]
                 pub fn load_testing() -> Self {
-                    let test_id = format!("test_{}", $crate::web_time::SystemTime::now()
-                        .duration_since($crate::web_time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_millis());
-                    let storage = $crate::storage::create_storage(&test_id);
+                    let test_id = $crate::storage::wasm::test_app_id();
+                    let storage = Box::new($crate::storage::PrivateSecrets::new($crate::storage::create_storage(&test_id)));
                     let storage_key = Self::PREFERENCES_FILENAME;
+                    let (storage, registration) = $crate::registry::register(storage, storage_key);
 
                     let mut cfg = Self::default();
                     cfg.storage = Some(storage);
                     cfg.storage_key = Some(storage_key.to_string());
+                    cfg.test_keys = Some($crate::storage::wasm::TestKeys::new(&test_id));
+                    cfg._registration = Some(registration);
                     cfg
                 }
 
-                /// Serializes preferences to a TOML string.
+                /// Loads defaults into a `MemoryStorage`, for tests and ephemeral ("incognito")
+                /// sessions that must not touch the filesystem or localStorage. Values are lost
+                /// when the instance is dropped (ignores the single-instance constraint).
+                pub fn load_in_memory() -> Self {
+                    let storage: Box<dyn $crate::storage::Storage> = Box::new($crate::storage::MemoryStorage::new());
+                    let storage_key = Self::PREFERENCES_FILENAME;
+                    let (storage, registration) = $crate::registry::register(storage, storage_key);
+
+                    let mut cfg = Self::default();
+                    cfg.storage = Some(storage);
+                    cfg.storage_key = Some(storage_key.to_string());
+                    cfg._registration = Some(registration);
+                    cfg
+                }
+
+                /// Runs `test` with an isolated in-memory instance (see `load_in_memory()`) and
+                /// drops it afterwards, also when `test` panics, so nothing is left behind.
+                pub fn with_test_prefs<R>(test: impl FnOnce(&mut Self) -> R) -> R {
+                    let mut prefs = Self::load_in_memory();
+                    test(&mut prefs)
+                }
+
+                /// Serializes preferences to a TOML string, with secret fields redacted.
                 pub fn to_string(&self) -> String {
-                    $crate::toml::to_string(self).expect("Serialization failed")
+                    let mut table = $crate::prefs::serialize_or_panic(self);
+                    $crate::secrets::redact(&mut table, Self::FIELDS);
+                    $crate::__private::toml::to_string(&table).unwrap_or_else(|e| {
+                        $crate::scrub::panic(Self::FIELDS, Some(self), format_args!("Serialization failed: {}", e))
+                    })
                 }
 
                 /// Save the preferences data to storage.
@@ -355,30 +1340,47 @@ macro_rules! easy_prefs {
                 /// - Storage is not initialized
                 /// - Serialization fails
                 /// - Storage write operation fails
-                pub fn save(&self) -> Result<(), std::io::Error> {
-                    // Ensure storage is initialized
-                    let storage = self.storage.as_ref().ok_or_else(|| std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        "storage not initialized"
-                    ))?;
-
-                    let storage_key = self.storage_key.as_ref().ok_or_else(|| std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        "storage key not set"
-                    ))?;
-
-                    // Serialize the preferences data to TOML
-                    let serialized = $crate::toml::to_string(self).map_err(|e| std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        format!("serialization failed: {}", e)
-                    ))?;
-
-                    // Write to storage
-                    storage.write(storage_key, &serialized)?;
+                pub fn save(&self) -> Result<(), $crate::SaveError> {
+                    self.save_as($crate::change::SaveSource::Save)
+                }
 
+                /// Saves like `save()`, reporting changes to the `on_change` hook as made by `source`.
+                fn save_as(&self, source: $crate::change::SaveSource) -> Result<(), $crate::SaveError> {
+                    let (storage, storage_key) = match (&self.storage, &self.storage_key) {
+                        (Some(storage), Some(key)) => (storage, key),
+                        _ => return Err($crate::SaveError::StorageUninitialized),
+                    };
+                    // If the write fails, `easy_prefs::flush_all()` can retry it.
+                    let generation = $crate::prefs::write(
+                        self,
+                        storage.as_ref(),
+                        storage_key,
+                        self.generation(),
+                        self._registration.as_ref(),
+                    )?;
+                    self.generation.store(generation, std::sync::atomic::Ordering::Relaxed);
+                    self.notify_watchers();
+                    self.report_changes(source);
                     Ok(())
                 }
 
+                /// Returns how the values were obtained on the last load or reload, e.g. whether
+                /// they were recovered from a backup (see `easy_prefs::backup`).
+                pub fn load_outcome(&self) -> &$crate::LoadOutcome {
+                    &self.load_outcome
+                }
+
+                /// Returns how long the last load or reload took and the size of the document it
+                /// read (see `easy_prefs::stats`).
+                pub fn load_stats(&self) -> $crate::LoadStats {
+                    self.load_stats
+                }
+
+                /// Returns the write generation of the last save or load (see `easy_prefs::generation`).
+                pub fn generation(&self) -> u64 {
+                    self.generation.load(std::sync::atomic::Ordering::Relaxed)
+                }
+
                 /// Returns the storage path/key as a string.
                 pub fn get_preferences_file_path(&self) -> String {
                     match (&self.storage, &self.storage_key) {
@@ -387,57 +1389,623 @@ macro_rules! easy_prefs {
                     }
                 }
 
+                /// Returns false if saved values don't outlive the process, e.g. when
+                /// localStorage is disabled and the preferences live in memory (see
+                /// `easy_prefs::storage::MemoryStorage`), or the struct was not loaded.
+                pub fn is_persistent(&self) -> bool {
+                    self.storage.as_ref().is_some_and(|storage| storage.is_persistent())
+                }
+
+                /// Describes the storage backend, resolved location, and detected platform quirks.
+                ///
+                /// Intended for bug reports and diagnostics screens.
+                pub fn environment_report(&self) -> $crate::storage::EnvironmentReport {
+                    match (&self.storage, &self.storage_key) {
+                        (Some(storage), Some(key)) => storage.environment_report(key),
+                        _ => panic!("storage not initialized"),
+                    }
+                }
+
                 $(
                     /// Gets the value of the field.
                     pub fn [<get_ $field>](&self) -> &$type {
-                        &self.[<_ $field>]
+                        match &self.[<_temporary_ $field>] {
+                            Some(temporary) if temporary.is_expired() => &temporary.previous,
+                            _ => &self.[<_ $field>],
+                        }
                     }
 
-                    /// Sets the field's value and immediately saves.
-                    pub fn [<save_ $field>](&mut self, value: $type) -> Result<(), std::io::Error> {
-                        if self.[<_ $field>] != value {
-                            self.[<_ $field>] = value;
-                            self.save()
+                    /// Gets the value of the field by value, for `Copy` types such as `bool` or `i32`.
+                    // The higher-ranked bound keeps this from being a compile error for non-`Copy` types.
+                    pub fn [<get_ $field _copied>](&self) -> $type
+                    where
+                        for<'a> $type: Copy,
+                    {
+                        *self.[<get_ $field>]()
+                    }
+
+                    /// Returns when the field's temporary override ends, if one is active.
+                    pub fn [<get_ $field _expiry>](&self) -> Option<$crate::__private::web_time::SystemTime> {
+                        self.[<_temporary_ $field>]
+                            .as_ref()
+                            .filter(|temporary| !temporary.is_expired())
+                            .map(|temporary| temporary.expires)
+                    }
+
+                    /// Gets the field's value in effect at `now` (local time). Only available for
+                    /// scheduled fields (see `easy_prefs::schedule`).
+                    pub fn [<effective_ $field>](
+                        &self,
+                        now: $crate::schedule::TimeOfDay,
+                    ) -> &<$type as $crate::schedule::Schedule>::Value
+                    where
+                        for<'a> $type: $crate::schedule::Schedule,
+                    {
+                        $crate::schedule::Schedule::effective(self.[<get_ $field>](), now)
+                    }
+
+                    $crate::__easy_prefs_if_watch! {
+                        /// Returns a receiver that sees every change to the field. Requires the
+                        /// `watch` feature.
+                        pub fn [<watch_ $field>](&self) -> $crate::__private::tokio::sync::watch::Receiver<$type>
+                        where
+                            for<'a> $type: Clone,
+                        {
+                            self.[<_watch_ $field>].subscribe(self.[<get_ $field>]())
+                        }
+                    }
+
+                    /// Sets the field's value for `duration` and immediately saves; afterwards the
+                    /// field reverts to its current value (see `easy_prefs::temporary`).
+                    ///
+                    /// Fails with a validation error for secret fields.
+                    pub fn [<set_ $field _temporarily>](
+                        &mut self,
+                        value: $type,
+                        duration: std::time::Duration,
+                    ) -> Result<(), $crate::SaveError> {
+                        $crate::temporary::check_field(Self::field_info($saved_name))?;
+                        $crate::validate::check(Self::field_info($saved_name), &value)?;
+                        self.revert_expired();
+                        let current = std::mem::replace(&mut self.[<_ $field>], value);
+                        let (previous, superseded) = match self.[<_temporary_ $field>].take() {
+                            // A new override keeps the value from before the first one.
+                            Some(active) => (active.previous, Some((current, active.expires))),
+                            None => (current, None),
+                        };
+                        self.[<_temporary_ $field>] = Some($crate::temporary::Override::new(previous, duration));
+                        if let Err(e) = $crate::validate::check_struct(Self::OPTIONS.validate_struct, self) {
+                            let previous = self.[<_temporary_ $field>].take().expect("override was just set").previous;
+                            match superseded {
+                                Some((current, expires)) => {
+                                    self.[<_ $field>] = current;
+                                    self.[<_temporary_ $field>] = Some($crate::temporary::Override { previous, expires });
+                                }
+                                None => self.[<_ $field>] = previous,
+                            }
+                            return Err(e.into());
+                        }
+                        self.save_as($crate::change::SaveSource::Field)
+                    }
+
+                    /// Parses the value from a string (as typed on a command line) and immediately saves.
+                    ///
+                    /// See `easy_prefs::parse` for the accepted syntax. Parse failures and
+                    /// constraint violations are returned as `InvalidInput` errors.
+                    pub fn [<set_ $field _from_str>](&mut self, input: &str) -> Result<(), std::io::Error> {
+                        let hint = self.stored_value($saved_name);
+                        let value = $crate::parse::parse_value_with(Self::field_info($saved_name), input, hint.as_ref(), |value| {
+                            Self::from_stored_value($saved_name, value, |data| data.$field)
+                        })?;
+                        Ok(self.[<save_ $field>](value)?)
+                    }
+
+                    /// Formats the field's value as a plain string (strings unquoted, others as TOML).
+                    pub fn [<get_ $field _as_string>](&self) -> String {
+                        self.stored_value($saved_name).map(|value| $crate::parse::format_value(&value)).unwrap_or_default()
+                    }
+
+                    /// Appends an item to a collection field (e.g. `Vec<T>`) and immediately saves.
+                    ///
+                    /// The extended collection is validated like a value passed to `save_<field>()`;
+                    /// if it is rejected, the field keeps its previous value.
+                    pub fn [<push_ $field>]<V>(&mut self, item: V) -> Result<(), $crate::SaveError>
+                    where
+                        $type: Extend<V>,
+                        for<'a> $type: Clone,
+                    {
+                        self.revert_expired();
+                        let mut value = self.[<_ $field>].clone();
+                        value.extend(std::iter::once(item));
+                        self.[<save_ $field>](value)
+                    }
+
+                    /// Inserts an entry into a map field (e.g. `HashMap<String, T>`) and immediately saves.
+                    ///
+                    /// The extended map is validated like a value passed to `save_<field>()`; if it
+                    /// is rejected, the field keeps its previous value.
+                    pub fn [<insert_ $field>]<K, V>(&mut self, key: K, value: V) -> Result<(), $crate::SaveError>
+                    where
+                        $type: Extend<(K, V)>,
+                        for<'a> $type: Clone,
+                    {
+                        self.revert_expired();
+                        let mut map = self.[<_ $field>].clone();
+                        map.extend(std::iter::once((key, value)));
+                        self.[<save_ $field>](map)
+                    }
+
+                    /// Sets the field's value and immediately saves, ending any temporary override.
+                    ///
+                    /// Values violating the field's declared constraints are rejected with
+                    /// `SaveError::ValidationError`.
+                    pub fn [<save_ $field>](&mut self, value: $type) -> Result<(), $crate::SaveError> {
+                        $crate::validate::check(Self::field_info($saved_name), &value)?;
+                        self.revert_expired();
+                        if self.[<_ $field>] != value || self.[<_temporary_ $field>].is_some() {
+                            let previous = std::mem::replace(&mut self.[<_ $field>], value);
+                            let previous_override = self.[<_temporary_ $field>].take();
+                            // Don't keep values that break struct-level invariants in memory.
+                            if let Err(e) = $crate::validate::check_struct(Self::OPTIONS.validate_struct, self) {
+                                self.[<_ $field>] = previous;
+                                self.[<_temporary_ $field>] = previous_override;
+                                return Err(e.into());
+                            }
+                            self.save_as($crate::change::SaveSource::Field)
                         } else {
                             Ok(())
                         }
                     }
+
+                    /// Sets the field's value and saves like `save_<field>()`, returning the previous
+                    /// value without cloning it.
+                    ///
+                    /// The previous value stays in place if validation or saving fails.
+                    pub fn [<replace_ $field>](&mut self, value: $type) -> Result<$type, $crate::SaveError> {
+                        $crate::validate::check(Self::field_info($saved_name), &value)?;
+                        self.revert_expired();
+                        let changed = self.[<_ $field>] != value || self.[<_temporary_ $field>].is_some();
+                        let previous = std::mem::replace(&mut self.[<_ $field>], value);
+                        if !changed {
+                            return Ok(previous);
+                        }
+                        let previous_override = self.[<_temporary_ $field>].take();
+                        let saved = $crate::validate::check_struct(Self::OPTIONS.validate_struct, self)
+                            .map_err($crate::SaveError::from)
+                            .and_then(|()| self.save_as($crate::change::SaveSource::Field));
+                        if let Err(e) = saved {
+                            self.[<_ $field>] = previous;
+                            self.[<_temporary_ $field>] = previous_override;
+                            return Err(e);
+                        }
+                        Ok(previous)
+                    }
+
+                    /// Moves the field's value out, leaving its default, and saves (see
+                    /// `replace_<field>()`).
+                    pub fn [<take_ $field>](&mut self) -> Result<$type, $crate::SaveError> {
+                        self.[<replace_ $field>](Self::[<default_ $field>]())
+                    }
+
+                    /// Restores the field's default and saves, ending any temporary override.
+                    pub fn [<reset_ $field>](&mut self) -> Result<(), $crate::SaveError> {
+                        self.[<save_ $field>](Self::[<default_ $field>]())
+                    }
+
+                    /// Re-reads the field from storage, keeping the in-memory values of all others
+                    /// (see `reload_fields()`).
+                    pub fn [<reload_ $field>](&mut self) -> Result<(), $crate::LoadError> {
+                        self.reload_fields(&[$saved_name])
+                    }
+
+                    /// Returns true if the field currently has its default value.
+                    pub fn [<is_default_ $field>](&self) -> bool {
+                        *self.[<get_ $field>]() == Self::[<default_ $field>]()
+                    }
+
+                    /// The field's default: the shipped default if there is one, else the declared one.
+                    fn [<default_ $field>]() -> $type {
+                        $crate::defaults::field_default(Self::OPTIONS.defaults, Self::FIELDS, $saved_name).unwrap_or_else(|| $default)
+                    }
                 )*
 
+                /// Restores every field's default and saves, ending all temporary
+                /// overrides.
+                ///
+                /// In-memory values are left untouched if the defaults fail struct validation.
+                pub fn reset_to_defaults(&mut self) -> Result<(), $crate::SaveError> {
+                    let mut defaults = Self::default();
+                    $crate::validate::check_struct(Self::OPTIONS.validate_struct, &defaults)?;
+                    $(
+                        std::mem::swap(&mut self.[<_ $field>], &mut defaults.[<_ $field>]);
+                        self.[<_temporary_ $field>] = None;
+                    )*
+                    self.save_as($crate::change::SaveSource::Reset)
+                }
+
+                /// Returns a copy of the values without the storage, to pass to another thread or
+                /// process (for `Clone` field types).
+                // The higher-ranked bounds keep this from being a compile error for non-`Clone` types.
+                pub fn snapshot(&self) -> [<$name Data>]
+                where
+                    $( for<'a> $type: Clone, )*
+                {
+                    [<$name Data>] {
+                        $( $field: self.[<get_ $field>]().clone(), )*
+                    }
+                }
+
+                /// Replaces every value with those of `data` and saves, ending all temporary
+                /// overrides.
+                ///
+                /// In-memory values are left untouched if a value fails validation.
+                pub fn apply(&mut self, data: [<$name Data>]) -> Result<(), $crate::SaveError> {
+                    $( $crate::validate::check(Self::field_info($saved_name), &data.$field)?; )*
+                    let mut previous = data;
+                    $( std::mem::swap(&mut self.[<_ $field>], &mut previous.$field); )*
+                    if let Err(e) = $crate::validate::check_struct(Self::OPTIONS.validate_struct, self) {
+                        $( self.[<_ $field>] = previous.$field; )*
+                        return Err(e.into());
+                    }
+                    $( self.[<_temporary_ $field>] = None; )*
+                    self.save_as($crate::change::SaveSource::Migration)
+                }
+
                 /// Creates an edit guard for batching updates (saves on drop).
-                pub fn edit(&mut self) -> [<$name EditGuard>] {
+                ///
+                /// # Panics
+                ///
+                /// Panics if write tokens are enforced (see `easy_prefs::write_token`);
+                /// use `edit_with_token()` in that case.
+                pub fn edit(&mut self) -> [<$name EditGuard>]<'_> {
+                    if $crate::write_token::is_enforced() {
+                        panic!("write tokens are enforced; use edit_with_token() to edit preferences");
+                    }
+                    self.new_edit_guard()
+                }
+
+                /// Creates an edit guard for batching updates while holding the app-wide write token.
+                pub fn edit_with_token(&mut self, _token: &$crate::write_token::WriteToken) -> [<$name EditGuard>]<'_> {
+                    self.new_edit_guard()
+                }
+
+                /// Runs `f` on an edit guard, keeping its changes only if `f` returns `Ok` and
+                /// saving them succeeds; otherwise all fields are rolled back (see
+                /// `easy_prefs::transaction`).
+                ///
+                /// # Panics
+                ///
+                /// Panics like `edit()` if write tokens are enforced.
+                pub fn transaction<E>(
+                    &mut self,
+                    f: impl FnOnce(&mut [<$name EditGuard>]<'_>) -> Result<(), E>,
+                ) -> Result<(), $crate::transaction::TxError<E>> {
+                    let mut guard = self.edit();
+                    guard.transactional = true;
+                    match f(&mut guard) {
+                        Ok(()) => guard.commit_or_roll_back().map_err($crate::transaction::TxError::Save),
+                        Err(e) => {
+                            guard.cancel();
+                            Err($crate::transaction::TxError::Aborted(e))
+                        }
+                    }
+                }
+
+                /// Puts back the previous values of expired temporary overrides.
+                fn revert_expired(&mut self) {
+                    $(
+                        if let Some(expired) = self.[<_temporary_ $field>].take_if(|temporary| temporary.is_expired()) {
+                            self.[<_ $field>] = expired.previous;
+                        }
+                    )*
+                }
+
+                fn new_edit_guard(&mut self) -> [<$name EditGuard>]<'_> {
+                    self.revert_expired();
                     [<$name EditGuard>] {
                         preferences: self,
                         modified: false,
-                        created: $crate::web_time::Instant::now()
+                        original: None,
+                        $( [<_temporary_ $field>]: None, )*
+                        transactional: false,
+                        created: $crate::__private::web_time::Instant::now()
                     }
                 }
             }
 
             /// Guard for batch editing; saves changes on drop if any fields were modified.
+            ///
+            /// Use `commit()` to handle save errors, or `cancel()` to discard the changes.
             $vis struct [<$name EditGuard>]<'a> {
                 preferences: &'a mut $name,
                 modified: bool,
-                created: $crate::web_time::Instant,
+                // Saved values from before the first change, for `cancel()`.
+                original: Option<$crate::__private::toml::Table>,
+                // Temporary overrides ended by a change, for `cancel()`.
+                $( [<_temporary_ $field>]: Option<$crate::temporary::Override<$type>>, )*
+                // Inside `transaction()`: roll back instead of saving on drop (i.e. on panic).
+                transactional: bool,
+                created: $crate::__private::web_time::Instant,
             }
 
             impl<'a> [<$name EditGuard>]<'a> {
+                /// Saves the changes now, returning any error instead of printing it on drop.
+                ///
+                /// Does nothing if no field was modified.
+                pub fn commit(mut self) -> Result<(), $crate::SaveError> {
+                    if !std::mem::take(&mut self.modified) {
+                        return Ok(());
+                    }
+                    self.preferences.save_as($crate::change::SaveSource::EditGuard)
+                }
+
+                /// Discards the changes made through this guard without saving.
+                ///
+                /// Fields are restored as they would load from their saved values, along with
+                /// the temporary overrides that changing them ended.
+                pub fn cancel(mut self) {
+                    if std::mem::take(&mut self.modified) {
+                        self.restore_original();
+                    }
+                }
+
+                /// Saves like `commit()`, restoring the original values if that fails.
+                fn commit_or_roll_back(mut self) -> Result<(), $crate::SaveError> {
+                    if !std::mem::take(&mut self.modified) {
+                        return Ok(());
+                    }
+                    let saved = self.preferences.save_as($crate::change::SaveSource::EditGuard);
+                    if saved.is_err() {
+                        self.restore_original();
+                    }
+                    saved
+                }
+
+                fn restore_original(&mut self) {
+                    let restored = self.original.take().map($crate::__private::toml::Value::Table).and_then(|table| {
+                        table.try_into::<$name>()
+                            .map_err(|e| $crate::scrub::log($name::FIELDS, Some(&*self.preferences), format_args!("Failed to restore values: {}", e)))
+                            .ok()
+                    });
+                    if let Some(restored) = restored {
+                        $( self.preferences.[<_ $field>] = restored.[<_ $field>]; )*
+                    }
+                    $(
+                        if let Some(ended) = self.[<_temporary_ $field>].take() {
+                            self.preferences.[<_temporary_ $field>] = Some(ended);
+                        }
+                    )*
+                }
+
+                fn mark_modified(&mut self) {
+                    if !self.modified {
+                        self.original = $crate::__private::toml::Table::try_from(&*self.preferences).ok();
+                        self.modified = true;
+                    }
+                }
+
                 $(
                     /// Sets the field's value (save is deferred until the guard is dropped).
+                    ///
+                    /// Values violating the field's declared constraints are ignored with a
+                    /// warning; use `try_set_<field>()` to handle them.
                     pub fn [<set_ $field>](&mut self, value: $type) {
-                        if self.preferences.[<_ $field>] != value {
+                        if let Err(e) = self.[<try_set_ $field>](value) {
+                            $crate::scrub::log($name::FIELDS, Some(&*self.preferences), format_args!("Ignoring invalid value: {}", e));
+                        }
+                    }
+
+                    /// Sets the field's value, rejecting values that violate its constraints.
+                    pub fn [<try_set_ $field>](&mut self, value: $type) -> Result<(), $crate::validate::ValidationError> {
+                        $crate::validate::check($name::field_info($saved_name), &value)?;
+                        if self.preferences.[<_ $field>] != value || self.preferences.[<_temporary_ $field>].is_some() {
+                            self.mark_modified();
                             self.preferences.[<_ $field>] = value;
-                            self.modified = true;
+                            if let Some(ended) = self.preferences.[<_temporary_ $field>].take() {
+                                self.[<_temporary_ $field>] = Some(ended);
+                            }
                         }
+                        Ok(())
+                    }
+
+                    /// Appends an item to a collection field (save is deferred until the guard is dropped).
+                    ///
+                    /// Like `set_<field>()`, an extended collection violating the field's
+                    /// constraints is ignored with a warning.
+                    pub fn [<push_ $field>]<V>(&mut self, item: V)
+                    where
+                        $type: Extend<V>,
+                        for<'b> $type: Clone,
+                    {
+                        self.preferences.revert_expired();
+                        let mut value = self.preferences.[<_ $field>].clone();
+                        value.extend(std::iter::once(item));
+                        self.[<set_ $field>](value);
+                    }
+
+                    /// Inserts an entry into a map field (save is deferred until the guard is dropped).
+                    ///
+                    /// Like `set_<field>()`, an extended map violating the field's constraints is
+                    /// ignored with a warning.
+                    pub fn [<insert_ $field>]<K, V>(&mut self, key: K, value: V)
+                    where
+                        $type: Extend<(K, V)>,
+                        for<'b> $type: Clone,
+                    {
+                        self.preferences.revert_expired();
+                        let mut map = self.preferences.[<_ $field>].clone();
+                        map.extend(std::iter::once((key, value)));
+                        self.[<set_ $field>](map);
                     }
 
                     /// Gets the current value of the field.
                     pub fn [<get_ $field>](&self) -> &$type {
-                        &self.preferences.[<_ $field>]
+                        self.preferences.[<get_ $field>]()
+                    }
+
+                    /// Gets the current value of the field by value, for `Copy` types.
+                    pub fn [<get_ $field _copied>](&self) -> $type
+                    where
+                        for<'b> $type: Copy,
+                    {
+                        *self.preferences.[<get_ $field>]()
                     }
                 )*
             }
 
+            /// The values of the preferences without their storage, for passing between threads
+            /// or over IPC (see `snapshot()` and `apply()`).
+            ///
+            /// Secret fields are included; `Debug` output redacts them.
+            #[derive(PartialEq, serde::Serialize, serde::Deserialize)]
+            #[serde(default)]
+            $vis struct [<$name Data>] {
+                $(
+                    $(#[$($inner)*])*
+                    #[serde(rename = $saved_name)]
+                    $field_vis $field: $type,
+                )*
+            }
+
+            impl Default for [<$name Data>] {
+                fn default() -> Self {
+                    Self {
+                        $( $field: $name::[<default_ $field>](), )*
+                    }
+                }
+            }
+
+            impl Clone for [<$name Data>]
+            where
+                $( for<'a> $type: Clone, )*
+            {
+                fn clone(&self) -> Self {
+                    Self {
+                        $( $field: self.$field.clone(), )*
+                    }
+                }
+            }
+
+            impl std::fmt::Debug for [<$name Data>] {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    let mut debug = f.debug_struct(stringify!([<$name Data>]));
+                    $(
+                        if $name::field_info($saved_name).options.secret {
+                            debug.field(stringify!($field), &$crate::secrets::REDACTED);
+                        } else {
+                            debug.field(stringify!($field), &self.$field);
+                        }
+                    )*
+                    debug.finish()
+                }
+            }
+
+            /// Cloneable handle for sharing the preferences between threads (see `load_shared()`).
+            ///
+            /// Reads take a shared lock, so they only wait for writes, not for each other.
+            #[derive(Clone)]
+            $vis struct [<$name Handle>] {
+                inner: std::sync::Arc<std::sync::RwLock<$name>>,
+            }
+
+            impl [<$name Handle>] {
+                /// Locks the preferences for reading.
+                pub fn read(&self) -> std::sync::RwLockReadGuard<'_, $name> {
+                    self.inner.read().unwrap_or_else(|e| e.into_inner())
+                }
+
+                /// Locks the preferences for writing, e.g. to use an edit guard.
+                pub fn write(&self) -> std::sync::RwLockWriteGuard<'_, $name> {
+                    self.inner.write().unwrap_or_else(|e| e.into_inner())
+                }
+
+                /// Runs `f` with an edit guard under the write lock and saves the changes once.
+                ///
+                /// # Panics
+                ///
+                /// Panics if write tokens are enforced (see `easy_prefs::write_token`); use
+                /// `write()` and `edit_with_token()` in that case.
+                pub fn edit<R>(&self, f: impl FnOnce(&mut [<$name EditGuard>]<'_>) -> R) -> Result<R, $crate::SaveError> {
+                    let mut prefs = self.write();
+                    let mut guard = prefs.edit();
+                    let result = f(&mut guard);
+                    guard.commit()?;
+                    Ok(result)
+                }
+
+                /// Re-reads all fields from storage (see `reload()`).
+                pub fn reload(&self) -> Result<(), $crate::LoadError> {
+                    self.write().reload()
+                }
+
+                $(
+                    /// Returns a copy of the field's value (for `Clone` types; use `read()` otherwise).
+                    // The higher-ranked bound keeps this from being a compile error for non-`Clone` types.
+                    pub fn [<get_ $field>](&self) -> $type
+                    where
+                        for<'a> $type: Clone,
+                    {
+                        self.read().[<get_ $field>]().clone()
+                    }
+
+                    /// Sets the field's value and immediately saves.
+                    pub fn [<save_ $field>](&self, value: $type) -> Result<(), $crate::SaveError> {
+                        self.write().[<save_ $field>](value)
+                    }
+
+                    /// Changes a copy of the field's value with `f` and saves it, under a single
+                    /// write lock so concurrent updates aren't lost (for `Clone` types).
+                    pub fn [<update_ $field>](&self, f: impl FnOnce(&mut $type)) -> Result<(), $crate::SaveError>
+                    where
+                        for<'a> $type: Clone,
+                    {
+                        let mut prefs = self.write();
+                        let mut value = prefs.[<get_ $field>]().clone();
+                        f(&mut value);
+                        prefs.[<save_ $field>](value)
+                    }
+
+                    /// Appends an item to a collection field and immediately saves.
+                    pub fn [<push_ $field>]<V>(&self, item: V) -> Result<(), $crate::SaveError>
+                    where
+                        $type: Extend<V>,
+                        for<'a> $type: Clone,
+                    {
+                        self.write().[<push_ $field>](item)
+                    }
+
+                    /// Inserts an entry into a map field and immediately saves.
+                    pub fn [<insert_ $field>]<K, V>(&self, key: K, value: V) -> Result<(), $crate::SaveError>
+                    where
+                        $type: Extend<(K, V)>,
+                        for<'a> $type: Clone,
+                    {
+                        self.write().[<insert_ $field>](key, value)
+                    }
+
+                    /// Restores the field's default and saves.
+                    pub fn [<reset_ $field>](&self) -> Result<(), $crate::SaveError> {
+                        self.write().[<reset_ $field>]()
+                    }
+
+                    /// Sets the field's value and saves, returning the previous value.
+                    pub fn [<replace_ $field>](&self, value: $type) -> Result<$type, $crate::SaveError> {
+                        self.write().[<replace_ $field>](value)
+                    }
+
+                    /// Moves the field's value out, leaving its default, and saves.
+                    pub fn [<take_ $field>](&self) -> Result<$type, $crate::SaveError> {
+                        self.write().[<take_ $field>]()
+                    }
+                )*
+            }
+
+            impl std::fmt::Debug for [<$name Handle>] {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    std::fmt::Debug::fmt(&*self.read(), f)
+                }
+            }
+
             impl<'a> Drop for [<$name EditGuard>]<'a> {
                 fn drop(&mut self) {
                     if cfg!(debug_assertions) && !std::thread::panicking() {
@@ -447,15 +2015,70 @@ macro_rules! easy_prefs {
                             eprintln!("Warning: Edit guard held for {:?} - consider reducing the scope", duration);
                         }
                     }
-                    if self.modified {
-                        if let Err(e) = self.preferences.save() {
-                            eprintln!("Failed to save: {}", e);
+                    if self.modified && self.transactional {
+                        self.restore_original();
+                    } else if self.modified {
+                        if let Err(e) = self.preferences.save_as($crate::change::SaveSource::EditGuard) {
+                            $crate::scrub::log($name::FIELDS, Some(&*self.preferences), format_args!("Failed to save: {}", e));
                         }
                     }
                 }
             }
         }
-    }
+    };
+
+    // Declarations with sections or removed fields: replace each section with a generated
+    // struct and a plain field of that type, collect the removed fields into the `removed`
+    // struct option, then expand the flat form above.
+    (
+        $(#[$outer:meta])*
+        $vis:vis struct $name:ident { $($body:tt)* },
+        $($tail:tt)*
+    ) => {
+        $crate::easy_prefs!(@munch [$(#[$outer])* $vis struct $name] [$($tail)*] [] [] $($body)*);
+    };
+    (@munch [$($head:tt)*] [$($tail:tt)*] [$($done:tt)*] [$($removed:tt)*]
+        removed $key:literal => $migrate:expr,
+        $($rest:tt)*
+    ) => {
+        $crate::easy_prefs!(@munch [$($head)*] [$($tail)*] [$($done)*] [
+            $($removed)*
+            $crate::removed::RemovedField { key: $key, migrate: $migrate },
+        ] $($rest)*);
+    };
+    (@munch [$($head:tt)*] [$($tail:tt)*] [$($done:tt)*] [$($removed:tt)*]
+        $(#[$($inner:tt)*])*
+        $field_vis:vis $field:ident: $section:ident { $($section_body:tt)* } => $saved_name:literal $( ( $($field_option:tt)* ) )?,
+        $($rest:tt)*
+    ) => {
+        $crate::__easy_prefs_section! { $field_vis struct $section { $($section_body)* } }
+        $crate::easy_prefs!(@munch [$($head)*] [$($tail)*] [
+            $($done)*
+            $(#[$($inner)*])*
+            $field_vis $field: $section = <$section as Default>::default() => $saved_name $( ( $($field_option)* ) )?,
+        ] [$($removed)*] $($rest)*);
+    };
+    (@munch [$($head:tt)*] [$($tail:tt)*] [$($done:tt)*] [$($removed:tt)*]
+        $(#[$($inner:tt)*])*
+        $field_vis:vis $field:ident: $type:ty = $default:expr => $saved_name:literal $( ( $($field_option:tt)* ) )?,
+        $($rest:tt)*
+    ) => {
+        $crate::easy_prefs!(@munch [$($head)*] [$($tail)*] [
+            $($done)*
+            $(#[$($inner)*])*
+            $field_vis $field: $type = $default => $saved_name $( ( $($field_option)* ) )?,
+        ] [$($removed)*] $($rest)*);
+    };
+    (@munch [$($head:tt)*] [$($tail:tt)*] [$($done:tt)*] []) => {
+        $crate::easy_prefs! { $($head)* { $($done)* }, $($tail)* }
+    };
+    (@munch [$($head:tt)*] [$preferences_filename:expr $(, $option:ident = $option_value:expr)* $(,)?] [$($done:tt)*] [$($removed:tt)+]) => {
+        $crate::easy_prefs! {
+            $($head)* { $($done)* },
+            $preferences_filename $(, $option = $option_value)*,
+            removed = &[$($removed)+]
+        }
+    };
 }
 
 #[allow(dead_code)]
@@ -0,0 +1,192 @@
+//! Advisory lint of stored preferences, to spot corrupted-but-parseable settings.
+//!
+//! Loading quietly repairs values that violate their field's constraints and ignores unknown
+//! keys, so a damaged file can look fine from inside the app. `lint()` reads the stored
+//! document as it is and reports what looks wrong, without changing or failing anything:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//! use easy_prefs::lint::LintIssue;
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub volume: i32 = 5 => "volume" (min = 0, max = 10),
+//!         pub account: String = "guest".to_string() => "account" (required),
+//!         pub last_sync: i64 = 0 => "last_sync" (timestamp),
+//!     },
+//!     "lint-doc-prefs"
+//! }
+//!
+//! let prefs = AppPrefs::load_testing();
+//! std::fs::write(
+//!     prefs.get_preferences_file_path(),
+//!     "volume = 4000\naccount = \"\"\nlast_sync = 32503680000\n",
+//! )
+//! .unwrap();
+//!
+//! let report = prefs.lint().unwrap();
+//! assert_eq!(report.issues.len(), 3);
+//! assert!(matches!(report.issues[1], LintIssue::EmptyRequired { key: "account" }));
+//! println!("{report}");
+//! ```
+//!
+//! The checks:
+//! - values violating the field's `min`, `max`, `step`, or `choices` (loading replaces them),
+//! - empty strings in fields marked `required`,
+//! - timestamps more than a day in the future: TOML datetimes and strings holding one
+//!   (e.g. from chrono), anywhere in the document, and integers in fields marked `timestamp`
+//!   (Unix seconds),
+//! - stored keys that match no field.
+//!
+//! Values of secret fields are only checked for being empty, so reports never contain them.
+//! Temporary overrides are skipped: their expiry is meant to be in the future.
+
+use crate::field::FieldInfo;
+use crate::validate::{check_value, ValidationError};
+use std::time::Duration;
+use toml::value::{Datetime, Offset};
+use toml::{Table, Value};
+use web_time::{SystemTime, UNIX_EPOCH};
+
+/// How far in the future a timestamp may be before it is flagged, covering time zones of
+/// local datetimes and clock skew between devices.
+pub const FUTURE_TOLERANCE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Something suspicious in a stored document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintIssue {
+    /// A value violates its field's constraints; loading repairs or rejects it.
+    Invalid(ValidationError),
+    /// A field marked `required` holds an empty string.
+    EmptyRequired {
+        /// Saved key of the field.
+        key: &'static str,
+    },
+    /// A timestamp lies in the future.
+    FutureTimestamp {
+        /// Dotted path of the value (e.g. `"sync.last_run"`).
+        key: String,
+        /// The value as stored.
+        value: String,
+    },
+    /// A stored key matches no field; loading ignores it.
+    UnknownKey(String),
+}
+
+impl std::fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Invalid(e) => write!(f, "{e}"),
+            Self::EmptyRequired { key } => write!(f, "required value '{key}' is empty"),
+            Self::FutureTimestamp { key, value } => write!(f, "timestamp '{key}' is in the future: {value}"),
+            Self::UnknownKey(key) => write!(f, "unknown key '{key}'"),
+        }
+    }
+}
+
+/// Outcome of `lint()`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LintReport {
+    /// The issues found, in document order.
+    pub issues: Vec<LintIssue>,
+}
+
+impl LintReport {
+    /// Whether nothing suspicious was found.
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl std::fmt::Display for LintReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.issues.is_empty() {
+            return write!(f, "no issues");
+        }
+        let lines: Vec<String> = self.issues.iter().map(ToString::to_string).collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Lints a stored document (which may carry an integrity footer) against `fields`.
+pub fn lint_document(document: &str, fields: &[FieldInfo]) -> Result<LintReport, toml::de::Error> {
+    let table = toml::from_str(crate::integrity::strip(document))?;
+    Ok(lint_table(&table, fields, SystemTime::now()))
+}
+
+/// Lints a parsed document against `fields`, taking `now` as the current time.
+pub fn lint_table(table: &Table, fields: &[FieldInfo], now: SystemTime) -> LintReport {
+    let now = now.duration_since(UNIX_EPOCH).map_or(0, |now| now.as_secs() as i64);
+    let latest = now + FUTURE_TOLERANCE.as_secs() as i64;
+    let mut report = LintReport::default();
+    for (key, value) in table {
+        if key == crate::generation::KEY || key == crate::temporary::TABLE_KEY {
+            continue;
+        }
+        let Some(field) = fields.iter().find(|field| field.key == key || field.aliases.contains(&key.as_str())) else {
+            report.issues.push(LintIssue::UnknownKey(key.clone()));
+            continue;
+        };
+        if field.options.required && value.as_str() == Some("") {
+            report.issues.push(LintIssue::EmptyRequired { key: field.key });
+        }
+        if field.options.secret {
+            continue;
+        }
+        if let Err(e) = check_value(field, value) {
+            report.issues.push(LintIssue::Invalid(e));
+        }
+        match value {
+            Value::Integer(seconds) if field.options.timestamp && *seconds > latest => {
+                report.issues.push(LintIssue::FutureTimestamp { key: key.clone(), value: value.to_string() });
+            }
+            value => future_datetimes(key, value, latest, &mut report),
+        }
+    }
+    report
+}
+
+/// Flags datetimes after `latest` (Unix seconds) in `value` and its nested values.
+fn future_datetimes(path: &str, value: &Value, latest: i64, report: &mut LintReport) {
+    let datetime = match value {
+        Value::Datetime(datetime) => Some(*datetime),
+        Value::String(text) => text.parse::<Datetime>().ok().filter(|datetime| datetime.time.is_some()),
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                future_datetimes(&format!("{path}[{index}]"), item, latest, report);
+            }
+            None
+        }
+        Value::Table(table) => {
+            for (key, item) in table {
+                future_datetimes(&format!("{path}.{key}"), item, latest, report);
+            }
+            None
+        }
+        _ => None,
+    };
+    if datetime.and_then(|datetime| unix_seconds(&datetime)).is_some_and(|seconds| seconds > latest) {
+        report.issues.push(LintIssue::FutureTimestamp { key: path.to_string(), value: value.to_string() });
+    }
+}
+
+/// Seconds since the Unix epoch of a datetime with a date; local datetimes are taken as UTC.
+fn unix_seconds(datetime: &Datetime) -> Option<i64> {
+    let date = datetime.date?;
+    let (year, month, day) = (i64::from(date.year), i64::from(date.month), i64::from(date.day));
+    // Days from 1970-01-01 in the proleptic Gregorian calendar (H. Hinnant's `days_from_civil`).
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+    let time = datetime.time.map_or(0, |time| {
+        i64::from(time.hour) * 3600 + i64::from(time.minute) * 60 + i64::from(time.second)
+    });
+    let offset = match datetime.offset {
+        Some(Offset::Custom { minutes }) => i64::from(minutes) * 60,
+        _ => 0,
+    };
+    Some(days * 86_400 + time - offset)
+}
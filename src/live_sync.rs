@@ -0,0 +1,330 @@
+//! Mirrors preferences between two running instances over a local WebSocket (feature `websocket`).
+//!
+//! One instance listens (typically the desktop app) and the other connects (e.g. a browser
+//! extension's native helper). Every selected field is kept equal on both sides: changes made
+//! on either side are sent to the other and saved there.
+//!
+//! ```rust,no_run
+//! use easy_prefs::easy_prefs;
+//! use easy_prefs::live_sync::LiveSync;
+//! use std::sync::{Arc, Mutex};
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub theme: String = "light".to_string() => "theme",
+//!     },
+//!     "app-settings"
+//! }
+//!
+//! let prefs = Arc::new(Mutex::new(AppPrefs::load("com.example.app")));
+//! let sync = LiveSync::new(prefs.clone())
+//!     .with_allowed_origins(&["chrome-extension://abcdefghijklmnop"]);
+//! sync.listen(std::net::TcpListener::bind("127.0.0.1:7421").unwrap());
+//!
+//! // In the other instance:
+//! // LiveSync::new(other_prefs).connect("ws://127.0.0.1:7421").unwrap();
+//! ```
+//!
+//! # Conflicts
+//!
+//! Each field carries an edit counter that is bumped whenever it changes locally and travels
+//! with the value. The value with the higher counter wins; on a tie (e.g. both sides edited
+//! the field while disconnected, or neither has edited it since startup) the listening side
+//! wins. Counters live in memory, so after a restart the listening side's stored values are
+//! authoritative until something is edited.
+//!
+//! # Protocol
+//!
+//! Text frames holding one JSON object each. On connect, both sides send every field:
+//!
+//! ```text
+//! {"op": "set", "key": "theme", "value": "dark", "version": 3}
+//! ```
+//!
+//! Values are strings in the syntax of [`crate::parse`], so non-Rust peers can take part.
+//!
+//! Connections are polled every 100 ms for local changes; call [`LiveSync::publish_changes`]
+//! after editing the preferences to send them right away. Secret, device-scoped, and
+//! `no_export` fields are never synced.
+//! Browsers let any web page connect to `localhost`, so connections announcing an `Origin`
+//! are refused unless the origin was allowed with [`LiveSync::with_allowed_origins`].
+
+use crate::dynamic::DynamicPrefs;
+use crate::field::FieldInfo;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tungstenite::WebSocket;
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Message {
+    Set { key: String, value: String, version: u64 },
+}
+
+struct Entry {
+    value: String,
+    version: u64,
+}
+
+struct Peer {
+    outgoing: Sender<String>,
+    /// The peer is the listening side, so it wins ties.
+    is_server: bool,
+}
+
+#[derive(Default)]
+struct State {
+    entries: HashMap<&'static str, Entry>,
+    peers: HashMap<usize, Peer>,
+    next_peer: usize,
+}
+
+/// Keeps selected fields of one preferences instance equal to those of connected instances.
+///
+/// Cloning is cheap; clones share the preferences and connections.
+pub struct LiveSync<P> {
+    prefs: Arc<Mutex<P>>,
+    fields: Vec<&'static FieldInfo>,
+    allowed_origins: Vec<String>,
+    state: Arc<Mutex<State>>,
+}
+
+impl<P> Clone for LiveSync<P> {
+    fn clone(&self) -> Self {
+        Self {
+            prefs: self.prefs.clone(),
+            fields: self.fields.clone(),
+            allowed_origins: self.allowed_origins.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+fn lock<T: ?Sized>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn encode(key: &str, entry: &Entry) -> String {
+    let message = Message::Set {
+        key: key.to_string(),
+        value: entry.value.clone(),
+        version: entry.version,
+    };
+    serde_json::to_string(&message).expect("message serialization cannot fail")
+}
+
+impl<P: DynamicPrefs + Send + 'static> LiveSync<P> {
+    /// Creates a sync handle for every exported field
+    /// (see [`crate::field::FieldOptions::is_exported`]).
+    pub fn new(prefs: Arc<Mutex<P>>) -> Self {
+        let fields: Vec<&'static FieldInfo> = lock(&prefs)
+            .fields()
+            .iter()
+            .filter(|field| field.options.is_exported())
+            .collect();
+        let sync = Self {
+            prefs,
+            fields,
+            allowed_origins: Vec::new(),
+            state: Arc::new(Mutex::new(State::default())),
+        };
+        sync.publish_changes();
+        sync
+    }
+
+    /// Restricts syncing to the fields saved under `keys`. Unknown and non-exported keys are
+    /// ignored.
+    pub fn with_fields(mut self, keys: &[&str]) -> Self {
+        self.fields.retain(|field| keys.contains(&field.key));
+        lock(&self.state).entries.retain(|key, _| keys.contains(key));
+        self
+    }
+
+    /// Accepts connections from browser pages or extensions with these origins
+    /// (e.g. `"chrome-extension://<id>"`). Clients that send no `Origin` are always accepted.
+    pub fn with_allowed_origins(mut self, origins: &[&str]) -> Self {
+        self.allowed_origins = origins.iter().map(|origin| origin.to_string()).collect();
+        self
+    }
+
+    /// Sends every selected field that changed locally since it was last sent or received.
+    pub fn publish_changes(&self) {
+        let prefs = lock(&self.prefs);
+        let mut state = lock(&self.state);
+        let mut changed = Vec::new();
+        for field in &self.fields {
+            let Some(value) = prefs.get_field_string(field.key) else {
+                continue;
+            };
+            match state.entries.get_mut(field.key) {
+                Some(entry) if entry.value == value => {}
+                Some(entry) => {
+                    entry.value = value;
+                    entry.version += 1;
+                    changed.push(encode(field.key, entry));
+                }
+                None => {
+                    state.entries.insert(field.key, Entry { value, version: 0 });
+                }
+            }
+        }
+        for message in changed {
+            for peer in state.peers.values() {
+                let _ = peer.outgoing.send(message.clone());
+            }
+        }
+    }
+
+    /// Listens for instances to sync with, serving each connection on its own thread.
+    ///
+    /// This side wins conflicts that the edit counters don't decide.
+    pub fn listen(&self, listener: TcpListener) -> std::thread::JoinHandle<()> {
+        let sync = self.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        crate::scrub::log(&[], None::<&()>, format_args!("Live sync accept failed: {e}"));
+                        continue;
+                    }
+                };
+                let sync = sync.clone();
+                std::thread::spawn(move || {
+                    let result = sync.accept(stream).and_then(|socket| sync.run(socket, false));
+                    if let Err(e) = result {
+                        crate::scrub::log(&[], None::<&()>, format_args!("Live sync connection failed: {e}"));
+                    }
+                });
+            }
+        })
+    }
+
+    /// Connects to a listening instance at `url` (e.g. `"ws://127.0.0.1:7421"`) and syncs
+    /// with it on a background thread until either side disconnects.
+    pub fn connect(&self, url: &str) -> Result<std::thread::JoinHandle<()>, std::io::Error> {
+        use tungstenite::client::IntoClientRequest;
+
+        let request = url.into_client_request().map_err(std::io::Error::other)?;
+        let host = request.uri().host().unwrap_or("localhost").to_string();
+        let port = request.uri().port_u16().unwrap_or(80);
+        let stream = TcpStream::connect((host.as_str(), port))?;
+        let (socket, _) = tungstenite::client(request, stream).map_err(std::io::Error::other)?;
+
+        let sync = self.clone();
+        Ok(std::thread::spawn(move || {
+            if let Err(e) = sync.run(socket, true) {
+                crate::scrub::log(&[], None::<&()>, format_args!("Live sync connection failed: {e}"));
+            }
+        }))
+    }
+
+    // The callback's error type is fixed by tungstenite.
+    #[allow(clippy::result_large_err)]
+    fn accept(&self, stream: TcpStream) -> Result<WebSocket<TcpStream>, std::io::Error> {
+        use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+
+        let allowed_origins = self.allowed_origins.clone();
+        let check_origin = move |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+            match request.headers().get("Origin") {
+                Some(origin) if !allowed_origins.iter().any(|allowed| origin == allowed.as_str()) => {
+                    let mut refusal = ErrorResponse::new(Some("origin not allowed".to_string()));
+                    *refusal.status_mut() = tungstenite::http::StatusCode::FORBIDDEN;
+                    Err(refusal)
+                }
+                _ => Ok(response),
+            }
+        };
+        tungstenite::accept_hdr(stream, check_origin).map_err(std::io::Error::other)
+    }
+
+    fn run(&self, mut socket: WebSocket<TcpStream>, peer_is_server: bool) -> Result<(), std::io::Error> {
+        use tungstenite::Error;
+
+        socket.get_ref().set_read_timeout(Some(Duration::from_millis(100)))?;
+        let (id, outgoing) = self.attach(peer_is_server);
+        let result = loop {
+            self.publish_changes();
+            if let Err(e) = self.flush(&mut socket, &outgoing) {
+                break Err(e);
+            }
+            match socket.read() {
+                Ok(tungstenite::Message::Text(text)) => {
+                    if let Err(e) = self.receive(id, &text) {
+                        crate::scrub::log(&[], None::<&()>, format_args!("Ignoring live sync message: {e}"));
+                    }
+                }
+                Ok(tungstenite::Message::Close(_)) => break Ok(()),
+                Ok(_) => {}
+                Err(Error::Io(e))
+                    if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {}
+                Err(Error::ConnectionClosed | Error::AlreadyClosed) => break Ok(()),
+                Err(e) => break Err(std::io::Error::other(e)),
+            }
+        };
+        lock(&self.state).peers.remove(&id);
+        result
+    }
+
+    fn flush(&self, socket: &mut WebSocket<TcpStream>, outgoing: &Receiver<String>) -> Result<(), std::io::Error> {
+        while let Ok(text) = outgoing.try_recv() {
+            socket
+                .send(tungstenite::Message::Text(text))
+                .map_err(std::io::Error::other)?;
+        }
+        Ok(())
+    }
+
+    /// Registers a peer and queues every selected field for it.
+    fn attach(&self, is_server: bool) -> (usize, Receiver<String>) {
+        self.publish_changes();
+        let (sender, receiver) = mpsc::channel();
+        let mut state = lock(&self.state);
+        for (key, entry) in &state.entries {
+            let _ = sender.send(encode(key, entry));
+        }
+        let id = state.next_peer;
+        state.next_peer += 1;
+        state.peers.insert(id, Peer { outgoing: sender, is_server });
+        (id, receiver)
+    }
+
+    /// Applies a message from peer `from`, answering with the local value if that wins.
+    fn receive(&self, from: usize, text: &str) -> Result<(), std::io::Error> {
+        let Message::Set { key, value, version } = serde_json::from_str(text)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let Some(field) = self.fields.iter().find(|field| field.key == key) else {
+            return Ok(());
+        };
+        // Count local edits made since the last poll before comparing.
+        self.publish_changes();
+
+        let mut prefs = lock(&self.prefs);
+        let mut state = lock(&self.state);
+        let State { entries, peers, .. } = &mut *state;
+        let (Some(entry), Some(peer)) = (entries.get_mut(field.key), peers.get(&from)) else {
+            return Ok(());
+        };
+        if entry.value == value {
+            entry.version = entry.version.max(version);
+            return Ok(());
+        }
+        if version < entry.version || (version == entry.version && !peer.is_server) {
+            let _ = peer.outgoing.send(encode(field.key, entry));
+            return Ok(());
+        }
+
+        prefs.set_field_from_str(field.key, &value)?;
+        entry.value = prefs.get_field_string(field.key).unwrap_or(value);
+        entry.version = version;
+        let message = encode(field.key, entry);
+        for (_, peer) in peers.iter().filter(|(id, _)| **id != from) {
+            let _ = peer.outgoing.send(message.clone());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+//! Moving stored preferences between locations and backends.
+//!
+//! App updates sometimes move preferences, e.g. from a legacy directory to an XDG one, or to a
+//! different storage backend. `migrate(old_directory, new_directory)` on a generated struct
+//! copies the preferences file, everything stored next to it (external blobs, history,
+//! backups), and secrets kept in a secret store, and reads each copy back to verify it. Call
+//! it before loading:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub theme: String = "light".to_string() => "theme",
+//!     },
+//!     "migrate-doc-prefs"
+//! }
+//!
+//! # let tmp = tempfile::tempdir().unwrap();
+//! # let old_dir = tmp.path().join("old");
+//! # let new_dir = tmp.path().join("new");
+//! # let (old_dir, new_dir) = (old_dir.to_str().unwrap(), new_dir.to_str().unwrap());
+//! # std::fs::create_dir(old_dir).unwrap();
+//! # std::fs::write(tmp.path().join("old/migrate-doc-prefs.toml"), "theme = \"dark\"").unwrap();
+//! if AppPrefs::migrate(old_dir, new_dir).unwrap() {
+//!     println!("moved preferences to {new_dir}");
+//! }
+//! let prefs = AppPrefs::load_with_error(new_dir).unwrap();
+//! assert_eq!(prefs.get_theme(), "dark");
+//! ```
+//!
+//! Migration never overwrites: if the destination already has preferences (say, the migration
+//! ran before), nothing is copied. The source is left in place; remove it once the app no
+//! longer needs to roll back. [`migrate_storage`] copies a single key between any two
+//! [`Storage`] backends.
+
+use crate::field::FieldInfo;
+use crate::storage::Storage;
+
+/// Copies the data stored under `key` from one backend to another and reads it back to verify
+/// the copy.
+///
+/// Returns `Ok(false)` without writing if `from` has nothing under `key` or `to` already has
+/// data there.
+///
+/// # Errors
+///
+/// Returns the read or write error of either backend, or `InvalidData` if the copy reads back
+/// differently.
+pub fn migrate_storage(from: &dyn Storage, to: &dyn Storage, key: &str) -> Result<bool, std::io::Error> {
+    let Some(data) = from.read(key)? else {
+        return Ok(false);
+    };
+    if to.read(key)?.is_some() {
+        return Ok(false);
+    }
+    to.write(key, &data)?;
+    if to.read(key)?.as_deref() != Some(data.as_str()) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("migrated data at {} does not match the original", to.get_path(key)),
+        ));
+    }
+    Ok(true)
+}
+
+/// Storage keys stored next to the preferences under `key`: blobs of external fields, the
+/// history, `backups` backups, the compatibility sidecar (see `sidecar`), and the last synced
+/// copy (see `sync`).
+pub fn companion_keys(key: &str, fields: &[FieldInfo], history: bool, backups: usize) -> Vec<String> {
+    let blobs = fields
+        .iter()
+        .filter(|field| field.options.external)
+        .map(|field| crate::blob::blob_key(key, field.key));
+    let history = history.then(|| crate::history::history_key(key));
+    let backups = (1..=backups).map(|index| crate::backup::backup_key(key, index));
+    let sidecar = crate::sidecar::sidecar_key(key);
+    let sync = crate::sync::state_key(key);
+    blobs.chain(history).chain(backups).chain([sidecar, sync]).collect()
+}
+
+/// Migrates the preferences under `key` with their companion keys and stored secrets.
+///
+/// Companions and secrets are copied first, so the preferences only appear at the destination
+/// once everything they refer to is there. Returns `Ok(false)` like [`migrate_storage`].
+pub fn migrate_prefs(
+    from: &dyn Storage,
+    to: &dyn Storage,
+    key: &str,
+    fields: &[FieldInfo],
+    history: bool,
+    backups: usize,
+) -> Result<bool, std::io::Error> {
+    if from.read(key)?.is_none() || to.read(key)?.is_some() {
+        return Ok(false);
+    }
+    for companion in companion_keys(key, fields, history, backups) {
+        migrate_storage(from, to, &companion)?;
+    }
+    crate::secrets::copy(fields, from, to, key)?;
+    migrate_storage(from, to, key)
+}
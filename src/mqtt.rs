@@ -0,0 +1,245 @@
+//! Mirrors selected preferences to MQTT topics (feature `mqtt`).
+//!
+//! Each selected field is published, retained, to `<prefix>/<key>` as a plain string in the
+//! syntax of [`crate::parse`]. The bridge also listens on the broker:
+//!
+//! - **`<prefix>/<key>/set`**: sets the field (e.g. from a Home Assistant switch) and publishes
+//!   the new state.
+//! - **Retained `<prefix>/<key>`**: applied when delivered on subscribe, so settings changed
+//!   on the broker while the app was offline are picked up on startup.
+//!
+//! ```rust,no_run
+//! use easy_prefs::easy_prefs;
+//! use easy_prefs::mqtt::MqttBridge;
+//! use rumqttc::{Client, MqttOptions};
+//! use std::sync::{Arc, Mutex};
+//!
+//! easy_prefs! {
+//!     pub struct DevicePrefs {
+//!         pub brightness: i32 = 80 => "brightness" (min = 0, max = 100),
+//!     },
+//!     "device-settings"
+//! }
+//!
+//! let prefs = Arc::new(Mutex::new(DevicePrefs::load("com.example.device")));
+//! let (client, connection) = Client::new(MqttOptions::new("device-1", "localhost", 1883), 10);
+//! let bridge = MqttBridge::new(prefs.clone(), client, "home/device-1/settings");
+//! bridge.run(connection);
+//!
+//! // After changing preferences in-process:
+//! prefs.lock().unwrap().save_brightness(40).unwrap();
+//! bridge.publish_changes();
+//! ```
+//!
+//! Secret fields are never selected by default, and their values are never published.
+
+use crate::dynamic::DynamicPrefs;
+use crate::field::FieldInfo;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Sends messages to the broker; implemented for `rumqttc::Client`.
+pub trait MqttTransport: Send + Sync {
+    /// Publishes `payload` to `topic`.
+    fn publish(&self, topic: &str, payload: &str, retain: bool) -> Result<(), std::io::Error>;
+
+    /// Subscribes to a topic filter.
+    fn subscribe(&self, filter: &str) -> Result<(), std::io::Error>;
+}
+
+impl MqttTransport for rumqttc::Client {
+    fn publish(&self, topic: &str, payload: &str, retain: bool) -> Result<(), std::io::Error> {
+        rumqttc::Client::publish(self, topic, rumqttc::QoS::AtLeastOnce, retain, payload)
+            .map_err(std::io::Error::other)
+    }
+
+    fn subscribe(&self, filter: &str) -> Result<(), std::io::Error> {
+        rumqttc::Client::subscribe(self, filter, rumqttc::QoS::AtLeastOnce)
+            .map_err(std::io::Error::other)
+    }
+}
+
+/// Keeps selected fields of one preferences instance in sync with MQTT topics.
+///
+/// Cloning is cheap; clones share the preferences, transport, and change tracking.
+pub struct MqttBridge<P> {
+    prefs: Arc<Mutex<P>>,
+    transport: Arc<dyn MqttTransport>,
+    prefix: String,
+    fields: Vec<&'static FieldInfo>,
+    published: Arc<Mutex<HashMap<&'static str, String>>>,
+    startup_delay: Duration,
+}
+
+impl<P> Clone for MqttBridge<P> {
+    fn clone(&self) -> Self {
+        Self {
+            prefs: self.prefs.clone(),
+            transport: self.transport.clone(),
+            prefix: self.prefix.clone(),
+            fields: self.fields.clone(),
+            published: self.published.clone(),
+            startup_delay: self.startup_delay,
+        }
+    }
+}
+
+fn lock<T: ?Sized>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl<P: DynamicPrefs + Send + 'static> MqttBridge<P> {
+    /// Creates a bridge publishing every exported field under `prefix`: the user-scoped fields
+    /// that are neither secret, `no_export`, nor transient (see
+    /// [`crate::field::FieldOptions::is_exported`]).
+    pub fn new(prefs: Arc<Mutex<P>>, transport: impl MqttTransport + 'static, prefix: &str) -> Self {
+        let fields = lock(&prefs)
+            .fields()
+            .iter()
+            .filter(|field| field.options.is_exported())
+            .collect();
+        Self {
+            prefs,
+            transport: Arc::new(transport),
+            prefix: prefix.trim_end_matches('/').to_string(),
+            fields,
+            published: Arc::new(Mutex::new(HashMap::new())),
+            startup_delay: Duration::from_secs(1),
+        }
+    }
+
+    /// Restricts the bridge to the fields saved under `keys`. Unknown keys and keys of fields
+    /// that aren't exported are ignored.
+    pub fn with_fields(mut self, keys: &[&str]) -> Self {
+        self.fields.retain(|field| keys.contains(&field.key));
+        self
+    }
+
+    /// Sets how long [`MqttBridge::run`] waits for retained messages after connecting before
+    /// publishing local values (default one second).
+    pub fn with_startup_delay(mut self, delay: Duration) -> Self {
+        self.startup_delay = delay;
+        self
+    }
+
+    /// The topic a field's state is published to.
+    pub fn state_topic(&self, key: &str) -> String {
+        format!("{}/{key}", self.prefix)
+    }
+
+    /// Subscribes to the bridge's topics and forgets what was published before.
+    ///
+    /// Called by [`MqttBridge::run`] on every (re)connection. Call
+    /// [`MqttBridge::publish_changes`] once retained messages have been handled.
+    pub fn start(&self) -> Result<(), std::io::Error> {
+        self.transport.subscribe(&format!("{}/#", self.prefix))?;
+        lock(&self.published).clear();
+        Ok(())
+    }
+
+    /// Publishes, retained, every selected field whose value changed since it was last published.
+    pub fn publish_changes(&self) {
+        let current: Vec<(&'static str, String)> = {
+            let prefs = lock(&self.prefs);
+            self.fields
+                .iter()
+                .filter_map(|field| Some((field.key, prefs.get_field_string(field.key)?)))
+                .collect()
+        };
+        let mut published = lock(&self.published);
+        for (key, value) in current {
+            if published.get(key) == Some(&value) {
+                continue;
+            }
+            match self.transport.publish(&self.state_topic(key), &value, true) {
+                Ok(()) => {
+                    published.insert(key, value);
+                }
+                Err(e) => crate::scrub::log(&[], None::<&()>, format_args!("Failed to publish '{key}' to MQTT: {e}")),
+            }
+        }
+    }
+
+    /// Applies an incoming message; returns whether it changed a preference.
+    ///
+    /// `retain` is the message's retain flag: state topics are only applied from retained
+    /// messages delivered on subscribe, so the bridge's own live publishes are not fed back.
+    pub fn handle_message(&self, topic: &str, payload: &[u8], retain: bool) -> Result<bool, std::io::Error> {
+        let Some(rest) = topic
+            .strip_prefix(self.prefix.as_str())
+            .and_then(|rest| rest.strip_prefix('/'))
+        else {
+            return Ok(false);
+        };
+        let (key, is_command) = match rest.strip_suffix("/set") {
+            Some(key) => (key, true),
+            None => (rest, false),
+        };
+        let Some(field) = self.fields.iter().find(|field| field.key == key) else {
+            return Ok(false);
+        };
+        if !is_command && !retain {
+            return Ok(false);
+        }
+
+        let input = std::str::from_utf8(payload)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let changed = {
+            let mut prefs = lock(&self.prefs);
+            if prefs.get_field_string(field.key).as_deref() == Some(input) {
+                false
+            } else {
+                prefs.set_field_from_str(field.key, input)?;
+                true
+            }
+        };
+        if is_command {
+            self.publish_changes();
+        } else {
+            // The retained state is what the broker already has.
+            lock(&self.published).insert(field.key, input.to_string());
+        }
+        Ok(changed)
+    }
+
+    /// Drives a `rumqttc` connection on a background thread, applying incoming messages.
+    ///
+    /// After each (re)connection, retained messages are applied for the startup delay, then
+    /// every selected field that differs from the broker's state is published.
+    pub fn run(&self, mut connection: rumqttc::Connection) -> std::thread::JoinHandle<()> {
+        use rumqttc::{Event, Packet, RecvTimeoutError};
+
+        let bridge = self.clone();
+        std::thread::spawn(move || {
+            let mut publish_at = None;
+            loop {
+                if publish_at.is_some_and(|at| Instant::now() >= at) {
+                    publish_at = None;
+                    bridge.publish_changes();
+                }
+                let notification = match connection.recv_timeout(Duration::from_millis(100)) {
+                    Ok(notification) => notification,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+                match notification {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => match bridge.start() {
+                        Ok(()) => publish_at = Some(Instant::now() + bridge.startup_delay),
+                        Err(e) => crate::scrub::log(&[], None::<&()>, format_args!("Failed to subscribe to MQTT topics: {e}")),
+                    },
+                    Ok(Event::Incoming(Packet::Publish(publish))) => {
+                        if let Err(e) = bridge.handle_message(&publish.topic, &publish.payload, publish.retain) {
+                            crate::scrub::log(&[], None::<&()>, format_args!("Ignoring MQTT message on {}: {e}", publish.topic));
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        crate::scrub::log(&[], None::<&()>, format_args!("MQTT connection error: {e}"));
+                        std::thread::sleep(std::time::Duration::from_secs(1));
+                    }
+                }
+            }
+        })
+    }
+}
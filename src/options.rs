@@ -0,0 +1,340 @@
+//! Struct-level options for the [`easy_prefs!`](crate::easy_prefs) macro.
+//!
+//! Options are listed after the preferences filename as `name = value` pairs. Each name maps
+//! to a const builder method on [`PrefsOptions`]:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! fn check(prefs: &LegacyPrefs) -> Result<(), String> {
+//!     if *prefs.get_max_items() < *prefs.get_min_items() {
+//!         return Err("max_items must be at least min_items".to_string());
+//!     }
+//!     Ok(())
+//! }
+//!
+//! easy_prefs! {
+//!     pub struct LegacyPrefs {
+//!         pub min_items: i32 = 1 => "min_items",
+//!         pub max_items: i32 = 10 => "max_items",
+//!     },
+//!     "legacy-settings",
+//!     lenient_types = true,
+//!     validate_struct = check
+//! }
+//!
+//! assert!(LegacyPrefs::OPTIONS.lenient_types);
+//! ```
+
+/// Validator for invariants spanning several fields.
+pub type StructValidator<T> = fn(&T) -> Result<(), String>;
+
+/// Hook adjusting freshly loaded values.
+pub type AfterLoad<T> = fn(&mut T);
+
+/// Options that apply to a whole preferences struct `T`.
+pub struct PrefsOptions<T: 'static> {
+    /// Coerce sloppy stored types on load for every field (see `coerce`).
+    pub lenient_types: bool,
+    /// Read near-miss keys (`Dark_Mode`, `dark-mode`) as the declared saved names (see `coerce`).
+    pub lenient_keys: bool,
+    /// Shipped defaults document whose values replace the declared field defaults (see
+    /// `defaults`).
+    pub defaults: Option<&'static str>,
+    /// Checked before every save and after every load.
+    pub validate_struct: Option<StructValidator<T>>,
+    /// Called before every save, after `validate_struct`; an error aborts the save with
+    /// `SaveError::ValidationError`. For side effects around saving, such as scheduling a sync.
+    pub on_before_save: Option<StructValidator<T>>,
+    /// Called with the values of every load, reload, restore, import, and patch before they
+    /// are validated, e.g. to normalize them.
+    pub on_after_load: Option<AfterLoad<T>>,
+    /// Update the stored file in place, keeping unknown keys and comments (see `preserve`).
+    pub preserve_formatting: bool,
+    /// Number of distinct saved states to keep for `restore()`; 0 disables history (see `history`).
+    pub history: usize,
+    /// Number of `.bak` copies of the previous file to keep, used when the file fails to load;
+    /// 0 disables backups (see `backup`).
+    pub backups: usize,
+    /// Allow only one loaded instance per stored file (the default). Set to `false` for structs
+    /// that are meant to be loaded many times, such as per-document settings; loads then never
+    /// fail with `InstanceAlreadyLoaded`, and keeping instances from overwriting each other is
+    /// up to the caller.
+    pub single_instance: bool,
+    /// End the stored file with a content hash and verify it on load (see `integrity`).
+    pub integrity: bool,
+    /// Load the defaults instead of failing when the integrity check fails and no backup
+    /// passes it.
+    pub reset_on_integrity_failure: bool,
+    /// Unix permission bits for every file written (e.g. `0o600` for files holding tokens);
+    /// `None` keeps the storage's default. Ignored on other platforms.
+    pub file_mode: Option<u32>,
+    /// Whether file writes are flushed to disk (`durability = Durability::Fsync`); `None`
+    /// keeps the storage's setting, which is `Durability::Fast` by default.
+    pub durability: Option<crate::storage::Durability>,
+    /// Cache file contents and re-read a file only when its modification time or size
+    /// changed, making frequent `reload()` polling cheap.
+    pub cache_reads: bool,
+    /// Mark every file written as excluded from OS backups, for device-specific or sensitive
+    /// values that must not roam through Time Machine, iCloud, or backup tools.
+    pub exclude_from_backup: bool,
+    /// How `/` in the storage key is stored (see `storage::KeySeparators`); nested by default.
+    pub key_separators: crate::storage::KeySeparators,
+    /// What a failed save changing a `(critical)` field does (see `critical`).
+    pub on_critical_failure: crate::critical::Escalation,
+    /// Format of the stored document (see `format`); TOML by default.
+    pub format: &'static dyn crate::format::Format,
+    /// Called for every field a save or reload changed (see `change`).
+    pub on_change: Option<crate::change::OnChange>,
+    /// Move stored keys that are not fields to a sidecar on save and merge them back on load,
+    /// so older app versions don't drop the settings of newer ones (see `sidecar`).
+    pub compat_sidecar: bool,
+    /// Fail loading with every unknown key and mistyped value of the stored document, with
+    /// line and column, instead of ignoring unknown keys and stopping at the first type error
+    /// (see `strict`).
+    pub strict: bool,
+    /// Print a warning for every load or reload taking longer than this (see `stats`); `None`
+    /// (the default) never warns.
+    pub load_budget: Option<std::time::Duration>,
+    /// Where `global()` loads the process-wide preferences from (see `global`); with `None`
+    /// (the default), `Prefs::try_global()` fails. Structs generated by `easy_prefs!` only
+    /// have `global()` with the option.
+    pub global: Option<crate::global::DirectoryProvider>,
+    /// Saved names of removed fields, each with a migration for its stored value (see
+    /// `removed`); usually declared as `removed "key" => migration` entries in the struct body.
+    pub removed: &'static [crate::removed::RemovedField<T>],
+}
+
+impl<T> PrefsOptions<T> {
+    /// Default options.
+    pub const fn new() -> Self {
+        Self {
+            lenient_types: false,
+            lenient_keys: false,
+            defaults: None,
+            validate_struct: None,
+            on_before_save: None,
+            on_after_load: None,
+            preserve_formatting: false,
+            history: 0,
+            backups: 0,
+            single_instance: true,
+            integrity: false,
+            reset_on_integrity_failure: false,
+            file_mode: None,
+            durability: None,
+            cache_reads: false,
+            exclude_from_backup: false,
+            key_separators: crate::storage::KeySeparators::Nested,
+            on_critical_failure: crate::critical::Escalation::Panic,
+            format: &crate::format::Toml,
+            on_change: None,
+            compat_sidecar: false,
+            strict: false,
+            load_budget: None,
+            global: None,
+            removed: &[],
+        }
+    }
+
+    /// Sets [`PrefsOptions::lenient_types`].
+    pub const fn lenient_types(mut self, lenient_types: bool) -> Self {
+        self.lenient_types = lenient_types;
+        self
+    }
+
+    /// Sets [`PrefsOptions::lenient_keys`].
+    pub const fn lenient_keys(mut self, lenient_keys: bool) -> Self {
+        self.lenient_keys = lenient_keys;
+        self
+    }
+
+    /// Sets [`PrefsOptions::history`].
+    pub const fn history(mut self, history: usize) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// Sets [`PrefsOptions::backups`].
+    pub const fn backups(mut self, backups: usize) -> Self {
+        self.backups = backups;
+        self
+    }
+
+    /// Sets [`PrefsOptions::preserve_formatting`].
+    pub const fn preserve_formatting(mut self, preserve_formatting: bool) -> Self {
+        self.preserve_formatting = preserve_formatting;
+        self
+    }
+
+    /// Sets [`PrefsOptions::single_instance`].
+    pub const fn single_instance(mut self, single_instance: bool) -> Self {
+        self.single_instance = single_instance;
+        self
+    }
+
+    /// Sets [`PrefsOptions::single_instance`]; `singleton = false` reads as `single_instance = false`.
+    pub const fn singleton(self, singleton: bool) -> Self {
+        self.single_instance(singleton)
+    }
+
+    /// Sets [`PrefsOptions::integrity`].
+    pub const fn integrity(mut self, integrity: bool) -> Self {
+        self.integrity = integrity;
+        self
+    }
+
+    /// Sets [`PrefsOptions::reset_on_integrity_failure`].
+    pub const fn reset_on_integrity_failure(mut self, reset: bool) -> Self {
+        self.reset_on_integrity_failure = reset;
+        self
+    }
+
+    /// Sets [`PrefsOptions::file_mode`].
+    pub const fn file_mode(mut self, mode: u32) -> Self {
+        self.file_mode = Some(mode);
+        self
+    }
+
+    /// Sets [`PrefsOptions::durability`].
+    pub const fn durability(mut self, durability: crate::storage::Durability) -> Self {
+        self.durability = Some(durability);
+        self
+    }
+
+    /// Sets [`PrefsOptions::cache_reads`].
+    pub const fn cache_reads(mut self, cache_reads: bool) -> Self {
+        self.cache_reads = cache_reads;
+        self
+    }
+
+    /// Sets [`PrefsOptions::exclude_from_backup`].
+    pub const fn exclude_from_backup(mut self, exclude_from_backup: bool) -> Self {
+        self.exclude_from_backup = exclude_from_backup;
+        self
+    }
+
+    /// Sets [`PrefsOptions::key_separators`].
+    pub const fn key_separators(mut self, key_separators: crate::storage::KeySeparators) -> Self {
+        self.key_separators = key_separators;
+        self
+    }
+
+    /// Sets [`PrefsOptions::on_critical_failure`].
+    pub const fn on_critical_failure(mut self, escalation: crate::critical::Escalation) -> Self {
+        self.on_critical_failure = escalation;
+        self
+    }
+
+    /// Sets [`PrefsOptions::format`].
+    pub const fn format(mut self, format: &'static dyn crate::format::Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets [`PrefsOptions::compat_sidecar`].
+    pub const fn compat_sidecar(mut self, compat_sidecar: bool) -> Self {
+        self.compat_sidecar = compat_sidecar;
+        self
+    }
+
+    /// Sets [`PrefsOptions::strict`].
+    pub const fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets [`PrefsOptions::load_budget`].
+    pub const fn load_budget(mut self, budget: std::time::Duration) -> Self {
+        self.load_budget = Some(budget);
+        self
+    }
+
+    /// Sets [`PrefsOptions::global`].
+    pub const fn global(mut self, directory: crate::global::DirectoryProvider) -> Self {
+        self.global = Some(directory);
+        self
+    }
+
+    /// Sets [`PrefsOptions::on_change`].
+    pub const fn on_change(mut self, hook: crate::change::OnChange) -> Self {
+        self.on_change = Some(hook);
+        self
+    }
+
+    /// Sets [`PrefsOptions::defaults`].
+    pub const fn defaults(mut self, document: &'static str) -> Self {
+        self.defaults = Some(document);
+        self
+    }
+
+    /// Sets [`PrefsOptions::validate_struct`].
+    pub const fn validate_struct(mut self, validator: StructValidator<T>) -> Self {
+        self.validate_struct = Some(validator);
+        self
+    }
+
+    /// Sets [`PrefsOptions::on_before_save`].
+    pub const fn on_before_save(mut self, hook: StructValidator<T>) -> Self {
+        self.on_before_save = Some(hook);
+        self
+    }
+
+    /// Sets [`PrefsOptions::on_after_load`].
+    pub const fn on_after_load(mut self, hook: AfterLoad<T>) -> Self {
+        self.on_after_load = Some(hook);
+        self
+    }
+
+    /// Sets [`PrefsOptions::removed`].
+    pub const fn removed(mut self, removed: &'static [crate::removed::RemovedField<T>]) -> Self {
+        self.removed = removed;
+        self
+    }
+}
+
+impl<T> Default for PrefsOptions<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for PrefsOptions<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for PrefsOptions<T> {}
+
+impl<T> std::fmt::Debug for PrefsOptions<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PrefsOptions")
+            .field("lenient_types", &self.lenient_types)
+            .field("lenient_keys", &self.lenient_keys)
+            .field("defaults", &self.defaults.is_some())
+            .field("validate_struct", &self.validate_struct.is_some())
+            .field("on_before_save", &self.on_before_save.is_some())
+            .field("on_after_load", &self.on_after_load.is_some())
+            .field("preserve_formatting", &self.preserve_formatting)
+            .field("history", &self.history)
+            .field("backups", &self.backups)
+            .field("single_instance", &self.single_instance)
+            .field("integrity", &self.integrity)
+            .field("reset_on_integrity_failure", &self.reset_on_integrity_failure)
+            .field("file_mode", &self.file_mode)
+            .field("durability", &self.durability)
+            .field("cache_reads", &self.cache_reads)
+            .field("exclude_from_backup", &self.exclude_from_backup)
+            .field("key_separators", &self.key_separators)
+            .field("on_critical_failure", &self.on_critical_failure)
+            .field("format", &self.format.name())
+            .field("on_change", &self.on_change.is_some())
+            .field("compat_sidecar", &self.compat_sidecar)
+            .field("strict", &self.strict)
+            .field("load_budget", &self.load_budget)
+            .field("global", &self.global.is_some())
+            .field("removed", &self.removed)
+            .finish()
+    }
+}
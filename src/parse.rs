@@ -0,0 +1,96 @@
+//! Parsing and formatting field values as plain strings, for CLI and IPC layers.
+//!
+//! Input is interpreted the way a user would type it on a command line: `true`, `42`, `1.5`,
+//! `dark`, `"quoted text"`, or TOML arrays such as `["a", "b"]`. The current value of the
+//! field tells the parser which type to aim for, so `1` sets a boolean to `true` and `7` sets
+//! a string field to `"7"`.
+
+use crate::coerce::coerce_value;
+use crate::field::FieldInfo;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use toml::{Table, Value};
+
+/// A string could not be parsed into a field's type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseValueError {
+    /// Saved key of the field.
+    pub key: &'static str,
+    /// The rejected input.
+    pub input: String,
+    /// Why parsing failed.
+    pub reason: String,
+}
+
+impl std::fmt::Display for ParseValueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "cannot parse \"{}\" for '{}': {}",
+            self.input, self.key, self.reason
+        )
+    }
+}
+
+impl std::error::Error for ParseValueError {}
+
+impl From<ParseValueError> for std::io::Error {
+    fn from(e: ParseValueError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, e)
+    }
+}
+
+/// Parses `input` into the type of `current`.
+pub fn parse_value<T>(field: &FieldInfo, input: &str, current: &T) -> Result<T, ParseValueError>
+where
+    T: Serialize + DeserializeOwned,
+{
+    parse_value_with(field, input, Value::try_from(current).ok().as_ref(), |value| value.try_into())
+}
+
+/// Parses `input` like [`parse_value`], coercing towards the stored form `hint` of the current
+/// value and converting with `convert`, e.g. through the field's serde attributes.
+pub fn parse_value_with<T>(
+    field: &FieldInfo,
+    input: &str,
+    hint: Option<&Value>,
+    convert: impl Fn(Value) -> Result<T, toml::de::Error>,
+) -> Result<T, ParseValueError> {
+    let candidates = [parse_toml_value(input), Some(Value::String(input.to_string()))];
+
+    let mut first_error = None;
+    for candidate in candidates.into_iter().flatten() {
+        let candidate = match hint {
+            Some(hint) => coerce_value(&candidate, hint).unwrap_or(candidate),
+            None => candidate,
+        };
+        match convert(candidate) {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                first_error.get_or_insert_with(|| e.to_string());
+            }
+        }
+    }
+
+    Err(ParseValueError {
+        key: field.key,
+        input: input.to_string(),
+        reason: first_error.unwrap_or_else(|| "unsupported value".to_string()),
+    })
+}
+
+/// Formats a value for display: strings without quotes, everything else as TOML.
+pub fn format_value<T: Serialize + ?Sized>(value: &T) -> String {
+    match Value::try_from(value) {
+        Ok(Value::String(s)) => s,
+        Ok(value) => value.to_string(),
+        // `None` has no TOML representation.
+        Err(_) => String::new(),
+    }
+}
+
+/// Parses a single TOML value such as `42`, `true`, or `["a", "b"]`.
+pub(crate) fn parse_toml_value(text: &str) -> Option<Value> {
+    let mut wrapper = format!("value = {text}").parse::<Table>().ok()?;
+    wrapper.remove("value")
+}
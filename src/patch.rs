@@ -0,0 +1,215 @@
+//! Applying partial TOML documents ("patches") to loaded preferences.
+//!
+//! `apply_toml_patch()` changes only the keys present in the patch and saves, which suits
+//! remote config pushes, support-driven fixes, and test fixtures written as small snippets.
+//! Unlike `import_toml()`, every field can be patched and invalid values are rejected instead
+//! of falling back to the default:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub theme: String = "light".to_string() => "theme",
+//!         pub retries: i32 = 3 => "retries" (min = 0, max = 10),
+//!     },
+//!     "patch-doc-prefs"
+//! }
+//!
+//! let mut prefs = AppPrefs::load_testing();
+//! let report = prefs.apply_toml_patch("retries = 5\nlegacy_flag = true\n").unwrap();
+//! assert_eq!(report.applied, ["retries"]);
+//! assert_eq!(report.unknown, ["legacy_flag"]);
+//! assert_eq!(*prefs.get_retries(), 5);
+//!
+//! assert!(prefs.apply_toml_patch("retries = 50").is_err());
+//! assert!(prefs.apply_toml_patch("retries = \"many\"").is_err());
+//! assert_eq!(*prefs.get_retries(), 5);
+//! ```
+//!
+//! Tables patch sections key by key, so `[window]` with only `width` keeps the other values of
+//! the section. Patched fields end their temporary overrides.
+//!
+//! `merge_from_toml()` applies a whole document, such as one exported from another instance,
+//! the same way, choosing values with a [`MergePolicy`] first, e.g. for an "Import settings"
+//! command that shouldn't undo what the user already customized:
+//!
+//! ```rust
+//! # use easy_prefs::easy_prefs;
+//! use easy_prefs::patch::MergePolicy;
+//! # easy_prefs! {
+//! #     pub struct AppPrefs {
+//! #         pub theme: String = "light".to_string() => "theme",
+//! #         pub retries: i32 = 3 => "retries" (min = 0, max = 10),
+//! #     },
+//! #     "merge-doc-prefs"
+//! # }
+//!
+//! let mut prefs = AppPrefs::load_testing();
+//! prefs.save_retries(7).unwrap();
+//! let report = prefs.merge_from_toml("theme = \"dark\"\nretries = 1\n", MergePolicy::OnlyMissing).unwrap();
+//! assert_eq!(report.applied, ["theme"]);
+//! assert_eq!(report.skipped, ["retries"]);
+//! assert_eq!(*prefs.get_retries(), 7);
+//! ```
+
+use crate::field::FieldInfo;
+use crate::validate::{check_value, ValidationError};
+use toml::{Table, Value};
+
+/// Location reported for errors in a patch.
+pub const LOCATION: &str = "patch";
+
+/// What [`merge`] did with the keys of a patch.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PatchReport {
+    /// Saved keys of the fields whose values changed.
+    pub applied: Vec<&'static str>,
+    /// Saved keys of the fields the patch set to their current values.
+    pub unchanged: Vec<&'static str>,
+    /// Keys of the patch that are not fields; they are ignored.
+    pub unknown: Vec<String>,
+    /// Saved keys of the fields whose values the [`MergePolicy`] left out.
+    pub skipped: Vec<&'static str>,
+}
+
+impl PatchReport {
+    /// Whether the patch changed any value.
+    pub fn changed(&self) -> bool {
+        !self.applied.is_empty()
+    }
+}
+
+impl std::fmt::Display for PatchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "applied: [{}]", self.applied.join(", "))?;
+        write!(f, ", unchanged: [{}]", self.unchanged.join(", "))?;
+        write!(f, ", unknown: [{}]", self.unknown.join(", "))?;
+        if !self.skipped.is_empty() {
+            write!(f, ", skipped: [{}]", self.skipped.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Which values of a document `merge_from_toml()` applies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergePolicy {
+    /// Every value present in the document.
+    #[default]
+    OverwriteAll,
+    /// Values that differ from the field's default, so defaults in the document don't undo
+    /// customized values.
+    SkipDefaults,
+    /// Values of fields that still have their default, so customized values are kept.
+    OnlyMissing,
+}
+
+/// Removes the values `policy` leaves out from `patch`, given the `current` and `defaults`
+/// saved values of `fields`; sections are compared key by key.
+///
+/// Returns the saved keys of the fields that had values removed.
+pub fn select(
+    patch: Table,
+    current: &Table,
+    defaults: &Table,
+    fields: &[FieldInfo],
+    policy: MergePolicy,
+) -> (Table, Vec<&'static str>) {
+    let mut selected = Table::new();
+    let mut skipped = Vec::new();
+    for (key, value) in patch {
+        let Some(field) = fields.iter().find(|field| field.key == key) else {
+            selected.insert(key, value);
+            continue;
+        };
+        match select_value(value, current.get(&key), defaults.get(&key), policy) {
+            (Some(value), complete) => {
+                if !complete {
+                    skipped.push(field.key);
+                }
+                selected.insert(key, value);
+            }
+            (None, _) => skipped.push(field.key),
+        }
+    }
+    (selected, skipped)
+}
+
+/// Returns what `policy` keeps of `value`, and whether that is all of it.
+fn select_value(
+    value: Value,
+    current: Option<&Value>,
+    default: Option<&Value>,
+    policy: MergePolicy,
+) -> (Option<Value>, bool) {
+    if let (Value::Table(patch), Some(Value::Table(current)), Some(Value::Table(default))) = (&value, current, default) {
+        let mut kept = Table::new();
+        let mut complete = true;
+        for (key, value) in patch {
+            let (value, all) = select_value(value.clone(), current.get(key), default.get(key), policy);
+            complete &= all && value.is_some();
+            if let Some(value) = value {
+                kept.insert(key.clone(), value);
+            }
+        }
+        return match kept.is_empty() && !patch.is_empty() {
+            true => (None, false),
+            false => (Some(Value::Table(kept)), complete),
+        };
+    }
+    let keep = match policy {
+        MergePolicy::OverwriteAll => true,
+        MergePolicy::SkipDefaults => default != Some(&value),
+        MergePolicy::OnlyMissing => current == default,
+    };
+    match keep {
+        true => (Some(value), true),
+        false => (None, false),
+    }
+}
+
+/// Applies `patch` to `table`, the saved values of `fields`.
+///
+/// # Errors
+///
+/// Returns the first patched value that violates its field's constraints; `table` is left
+/// untouched in that case. Type errors surface when the merged table is deserialized.
+pub fn merge(table: &mut Table, patch: Table, fields: &[FieldInfo]) -> Result<PatchReport, ValidationError> {
+    let mut report = PatchReport::default();
+    let mut updates = Vec::new();
+    for (key, value) in patch {
+        let Some(field) = fields.iter().find(|field| field.key == key) else {
+            report.unknown.push(key);
+            continue;
+        };
+        let value = match (table.get(&key), value) {
+            (Some(Value::Table(current)), Value::Table(patch)) => {
+                let mut section = current.clone();
+                overlay(&mut section, patch);
+                Value::Table(section)
+            }
+            (_, value) => value,
+        };
+        check_value(field, &value)?;
+        if table.get(&key) == Some(&value) {
+            report.unchanged.push(field.key);
+        } else {
+            report.applied.push(field.key);
+            updates.push((key, value));
+        }
+    }
+    table.extend(updates);
+    Ok(report)
+}
+
+fn overlay(table: &mut Table, patch: Table) {
+    for (key, value) in patch {
+        match (table.get_mut(&key), value) {
+            (Some(Value::Table(current)), Value::Table(patch)) => overlay(current, patch),
+            (_, value) => {
+                table.insert(key, value);
+            }
+        }
+    }
+}
@@ -0,0 +1,1326 @@
+//! Preferences for plain structs, as used by `#[derive(EasyPrefs)]` (feature `derive`).
+//!
+//! The derive implements [`PrefsData`] for an ordinary struct; [`Prefs<T>`] then does what the
+//! methods generated by [`easy_prefs!`](crate::easy_prefs) do: load, save, validate, keep secrets
+//! out of the file, and enforce a single instance per type.
+//!
+//! ```rust,ignore
+//! use easy_prefs::{EasyPrefs, Prefs};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, Serialize, Deserialize, EasyPrefs)]
+//! #[prefs(filename = "app-settings")]
+//! pub struct AppPrefs {
+//!     #[pref(default = false)]
+//!     pub dark_mode: bool,
+//!     #[pref(default = 14, name = "font_size", min = 6, max = 72)]
+//!     pub text_size: i32,
+//! }
+//!
+//! let mut prefs = Prefs::<AppPrefs>::load("com.example.app");
+//! println!("{}", prefs.get().text_size);
+//! prefs.edit().dark_mode = true; // saved when the guard drops
+//! ```
+//!
+//! Values are serialized with serde, so serde attributes such as `#[serde(skip)]` apply. Use
+//! `#[pref(name = "...")]` rather than `#[serde(rename)]` to choose the saved key.
+
+use crate::change::SaveSource;
+use crate::dynamic::{invalid_value, unknown_key, DynamicPrefs, Preferences};
+use crate::field::FieldInfo;
+use crate::options::PrefsOptions;
+use crate::registry::{self, Registration};
+use crate::scrub;
+use crate::storage::{EnvironmentReport, Storage};
+use crate::{LoadError, LoadOutcome, LoadStats, SaveError};
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::any::TypeId;
+use std::collections::HashSet;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use toml::{Table, Value};
+
+/// A struct that can be stored as preferences; implemented by `#[derive(EasyPrefs)]`.
+///
+/// Structs generated by [`easy_prefs!`](crate::easy_prefs) implement it too: their methods
+/// load and save through the same functions as [`Prefs<T>`].
+pub trait PrefsData: Serialize + DeserializeOwned + Default + 'static {
+    /// Storage key of the preferences, including the `.toml` extension.
+    const PREFERENCES_FILENAME: &'static str;
+    /// Metadata for every field, in declaration order.
+    const FIELDS: &'static [FieldInfo];
+    /// Struct-level options.
+    const OPTIONS: PrefsOptions<Self>;
+    /// Hash of the fields' names, keys, and types (see [`crate::schema`]).
+    const SCHEMA_HASH: u64 = crate::schema::hash(Self::FIELDS);
+
+    /// Serializes the values to a table keyed by saved names.
+    #[doc(hidden)]
+    fn to_saved_table(&self) -> Result<Table, SaveError> {
+        let mut table = Table::try_from(self).map_err(|e| crate::serialize::error(e, self, Self::FIELDS))?;
+        rename_keys(&mut table, Self::FIELDS.iter().map(|f| (f.name, f.key)));
+        Ok(table)
+    }
+
+    /// Deserializes a table keyed by saved names; missing fields take their defaults.
+    #[doc(hidden)]
+    fn from_saved_table(mut table: Table) -> Result<Self, toml::de::Error> {
+        rename_keys(&mut table, Self::FIELDS.iter().map(|f| (f.key, f.name)));
+        if let Ok(defaults) = Table::try_from(Self::default()) {
+            for (name, value) in defaults {
+                table.entry(name).or_insert(value);
+            }
+        }
+        Value::Table(table).try_into()
+    }
+
+    /// Adds the active temporary overrides to a table being saved (see [`crate::temporary`]).
+    #[doc(hidden)]
+    fn store_temporary(&self, _table: &mut Table) -> Result<(), SaveError> {
+        Ok(())
+    }
+
+    /// Takes the overrides returned by [`crate::temporary::take`] after loading.
+    #[doc(hidden)]
+    fn read_temporary(&mut self, _overrides: &Table) {}
+}
+
+/// Loaded (type, storage path) pairs of the types with `single_instance`, shared by every
+/// `PrefsData` type including those of `easy_prefs!`.
+static LOADED: Lazy<Mutex<HashSet<(TypeId, String)>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+fn loaded() -> std::sync::MutexGuard<'static, HashSet<(TypeId, String)>> {
+    LOADED.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Releases the single-instance claim for a type and storage path on drop.
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct InstanceGuard((TypeId, String));
+
+impl Drop for InstanceGuard {
+    fn drop(&mut self) {
+        loaded().remove(&self.0);
+    }
+}
+
+/// Loaded preferences of type `T`, bound to their storage.
+pub struct Prefs<T: PrefsData> {
+    data: T,
+    generation: AtomicU64,
+    load_outcome: LoadOutcome,
+    load_stats: LoadStats,
+    changes: crate::change::Tracker,
+    storage: Box<dyn Storage>,
+    storage_key: String,
+    #[cfg(not(target_arch = "wasm32"))]
+    _temp_file: Option<tempfile::NamedTempFile>,
+    #[cfg(target_arch = "wasm32")]
+    _test_keys: Option<crate::storage::wasm::TestKeys>,
+    _instance_guard: Option<InstanceGuard>,
+    registration: Registration,
+}
+
+impl<T: PrefsData> Prefs<T> {
+    /// Loads preferences, gracefully handling errors like the macro's `load()`.
+    ///
+    /// # Panics
+    ///
+    /// - Always panics if another instance is already loaded
+    /// - In debug/test builds only: panics on storage or deserialization errors
+    pub fn load(directory: &str) -> Self {
+        match Self::load_with_error(directory) {
+            Ok(prefs) => prefs,
+            Err(e) => {
+                if matches!(e, LoadError::InstanceAlreadyLoaded) || cfg!(any(debug_assertions, test)) {
+                    scrub::panic(T::FIELDS, None::<&T>, format_args!("Failed to load preferences: {}", e));
+                }
+                scrub::log(T::FIELDS, None::<&T>, format_args!("Failed to load preferences, using defaults: {}", e));
+                let storage = configure_storage::<T>(crate::storage::create_storage(directory));
+                let guard = claim_instance::<T>(&storage.get_path(T::PREFERENCES_FILENAME)).unwrap_or_else(|e| {
+                    scrub::panic(
+                        T::FIELDS,
+                        None::<&T>,
+                        format_args!("Failed to load preferences and instance is still locked: {}", e),
+                    )
+                });
+                let (storage, registration) = registry::register(storage, T::PREFERENCES_FILENAME);
+                Self {
+                    data: T::default(),
+                    generation: AtomicU64::new(0),
+                    load_outcome: LoadOutcome::Loaded,
+                    load_stats: LoadStats::default(),
+                    changes: Default::default(),
+                    storage,
+                    storage_key: T::PREFERENCES_FILENAME.to_string(),
+                    #[cfg(not(target_arch = "wasm32"))]
+                    _temp_file: None,
+                    #[cfg(target_arch = "wasm32")]
+                    _test_keys: None,
+                    _instance_guard: guard,
+                    registration,
+                }
+            }
+        }
+    }
+
+    /// Loads preferences, keeping every stored value that loads, like the macro's
+    /// `load_lenient()` (see [`crate::lenient`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if another instance is already loaded.
+    pub fn load_lenient(directory: &str) -> (Self, Vec<crate::lenient::LoadWarning>) {
+        const { crate::field::assert_unique_keys(T::FIELDS) };
+
+        let storage = configure_storage::<T>(crate::storage::create_storage(directory));
+        let guard = claim_instance::<T>(&storage.get_path(T::PREFERENCES_FILENAME)).unwrap_or_else(|e| {
+            scrub::panic(T::FIELDS, None::<&T>, format_args!("Failed to load preferences: {}", e))
+        });
+        let (loaded, warnings) = read_lenient::<T>(storage.as_ref(), T::PREFERENCES_FILENAME);
+        (Self::bind(loaded, storage, T::PREFERENCES_FILENAME.to_string(), guard), warnings)
+    }
+
+    /// Loads preferences with explicit error handling.
+    pub fn load_with_error(directory: &str) -> Result<Self, LoadError> {
+        Self::load_with_storage(crate::storage::create_storage(directory))
+    }
+
+    /// Loads preferences like [`Prefs::load_with_error`], but if another instance is still
+    /// loaded, waits up to `timeout` for it to be dropped (see [`crate::instance`]).
+    pub fn try_load_timeout(directory: &str, timeout: std::time::Duration) -> Result<Self, LoadError> {
+        crate::instance::retry_while_loaded(timeout, || Self::load_with_error(directory))
+    }
+
+    /// Loads preferences from the platform's configuration directory for `app_id` (see
+    /// [`crate::storage::config_directory`]).
+    pub fn load_auto(app_id: &str) -> Result<Self, LoadError> {
+        let directory = crate::storage::config_directory(app_id).map_err(LoadError::StorageError)?;
+        Self::load_with_error(&directory)
+    }
+
+    /// Loads preferences like [`Prefs::load_with_error`] and wraps them in a [`PrefsHandle`].
+    pub fn load_shared(directory: &str) -> Result<PrefsHandle<T>, LoadError> {
+        Self::load_with_error(directory).map(Self::into_shared)
+    }
+
+    /// Wraps the preferences in a cloneable, thread-safe handle.
+    pub fn into_shared(self) -> PrefsHandle<T> {
+        PrefsHandle {
+            inner: Arc::new(RwLock::new(self)),
+        }
+    }
+
+    /// Returns the process-wide handle, loading the preferences on first access from the
+    /// directory of the `global` option (see [`crate::global`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` has no `global` option or loading fails.
+    pub fn global() -> &'static PrefsHandle<T>
+    where
+        PrefsHandle<T>: Send + Sync,
+    {
+        Self::try_global().unwrap_or_else(|e| {
+            scrub::panic(T::FIELDS, None::<&T>, format_args!("Failed to load global preferences: {}", e))
+        })
+    }
+
+    /// Like [`Prefs::global`], but returns load errors; the next call tries again.
+    ///
+    /// Fails with a [`LoadError::StorageError`] of kind `Unsupported` if `T` has no `global`
+    /// option.
+    pub fn try_global() -> Result<&'static PrefsHandle<T>, LoadError>
+    where
+        PrefsHandle<T>: Send + Sync,
+    {
+        crate::global::get_or_try_init(|| {
+            let directory = crate::global::directory(T::OPTIONS.global, std::any::type_name::<T>())
+                .map_err(LoadError::StorageError)?;
+            Self::load_shared(&directory)
+        })
+    }
+
+    /// Loads preferences from a custom storage backend.
+    pub fn load_with_storage(storage: Box<dyn Storage>) -> Result<Self, LoadError> {
+        Self::load_from(storage, T::PREFERENCES_FILENAME.to_string())
+    }
+
+    /// Loads the named profile, stored as `<filename>.<profile>.toml` (see [`crate::profile`]).
+    pub fn load_profile(directory: &str, profile: &str) -> Result<Self, LoadError> {
+        let storage_key = crate::profile::storage_key(T::PREFERENCES_FILENAME, profile)?;
+        Self::load_from(crate::storage::create_storage(directory), storage_key)
+    }
+
+    /// Loads the preferences of `document` (e.g. its path), stored in a file derived from the
+    /// identifier (see [`crate::document`]).
+    pub fn load_for(directory: &str, document: &str) -> Result<Self, LoadError> {
+        let storage_key = crate::document::storage_key(T::PREFERENCES_FILENAME, document)?;
+        Self::load_from(crate::storage::create_storage(directory), storage_key)
+    }
+
+    /// Copies the stored preferences from `old_directory` to `new_directory`, with their blobs,
+    /// history, backups, and secrets (see [`crate::migrate`]). Returns `Ok(false)` if there is nothing to
+    /// migrate or `new_directory` already has preferences.
+    pub fn migrate(old_directory: &str, new_directory: &str) -> Result<bool, std::io::Error> {
+        crate::migrate::migrate_prefs(
+            &resolved_storage::<T>(old_directory),
+            &resolved_storage::<T>(new_directory),
+            T::PREFERENCES_FILENAME,
+            T::FIELDS,
+            T::OPTIONS.history > 0,
+            T::OPTIONS.backups,
+        )
+    }
+
+    /// Removes the profile and document files in `directory` that `policy` selects, with their
+    /// companions and secrets, except those loaded in this process (see [`crate::gc`]).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn gc(directory: &str, policy: &crate::gc::GcPolicy) -> Result<crate::gc::GcReport, std::io::Error> {
+        let filename = crate::storage::resolve_key(T::PREFERENCES_FILENAME, T::OPTIONS.key_separators)?;
+        crate::gc::run(directory, &filename, T::FIELDS, &loaded_paths::<T>(), policy)
+    }
+
+    /// Checks the preferences stored in `directory` without loading them (see [`crate::check`]).
+    pub fn self_check(directory: &str) -> crate::check::CheckReport {
+        let storage = resolved_storage::<T>(directory);
+        let storage = &storage;
+        crate::check::run(storage, T::PREFERENCES_FILENAME, T::OPTIONS.integrity, T::OPTIONS.backups, |contents| {
+            from_stored::<T>(Some(contents), storage, T::PREFERENCES_FILENAME).map(drop)
+        })
+    }
+
+    fn load_from(storage: Box<dyn Storage>, storage_key: String) -> Result<Self, LoadError> {
+        // Evaluated when `T` is instantiated, so a duplicate key fails the build.
+        const { crate::field::assert_unique_keys(T::FIELDS) };
+
+        let storage = configure_storage::<T>(storage);
+        let guard = claim_instance::<T>(&storage.get_path(&storage_key))?;
+        let loaded = read_from_storage::<T>(storage.as_ref(), &storage_key)?;
+        Ok(Self::bind(loaded, storage, storage_key, guard))
+    }
+
+    /// Registers `storage` and wraps the loaded values.
+    fn bind(loaded: Loaded<T>, storage: Box<dyn Storage>, storage_key: String, guard: Option<InstanceGuard>) -> Self {
+        let Loaded { data, generation, outcome, stats } = loaded;
+        let (storage, registration) = registry::register(storage, &storage_key);
+        let changes = crate::change::Tracker::default();
+        if T::OPTIONS.on_change.is_some() {
+            changes.record(data.to_saved_table().unwrap_or_default());
+        }
+        Self {
+            data,
+            generation: AtomicU64::new(generation),
+            load_outcome: outcome,
+            load_stats: stats,
+            changes,
+            storage,
+            storage_key,
+            #[cfg(not(target_arch = "wasm32"))]
+            _temp_file: None,
+            #[cfg(target_arch = "wasm32")]
+            _test_keys: None,
+            _instance_guard: guard,
+            registration,
+        }
+    }
+
+    /// Loads defaults into a temporary location for testing (ignores the single-instance constraint).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_testing() -> Self {
+        let temp_file = tempfile::NamedTempFile::with_prefix(T::PREFERENCES_FILENAME)
+            .expect("Failed to create temporary file for testing preferences");
+        let dir = temp_file.path().parent().unwrap().to_str().unwrap();
+        let storage_key = temp_file.path().file_name().unwrap().to_str().unwrap().to_string();
+        let storage = crate::storage::PrivateSecrets::new(crate::storage::create_storage(dir));
+        let storage = configure_storage::<T>(Box::new(storage));
+        let (storage, registration) = registry::register(storage, &storage_key);
+        let prefs = Self {
+            data: T::default(),
+            generation: AtomicU64::new(0),
+            load_outcome: LoadOutcome::Loaded,
+            load_stats: LoadStats::default(),
+            changes: Default::default(),
+            storage,
+            storage_key,
+            _temp_file: Some(temp_file),
+            _instance_guard: None,
+            registration,
+        };
+        prefs
+            .save()
+            .expect("Failed to write preferences data to temporary file");
+        prefs
+    }
+
+    /// Loads defaults into a temporary location for testing (ignores the single-instance constraint).
+    ///
+    /// Each instance gets its own localStorage keys, removed when it is dropped.
+    #[cfg(target_arch = "wasm32")]
+    pub fn load_testing() -> Self {
+        let test_id = crate::storage::wasm::test_app_id();
+        let storage = Box::new(crate::storage::PrivateSecrets::new(crate::storage::create_storage(&test_id)));
+        let (storage, registration) = registry::register(storage, T::PREFERENCES_FILENAME);
+        Self {
+            data: T::default(),
+            generation: AtomicU64::new(0),
+            load_outcome: LoadOutcome::Loaded,
+            load_stats: LoadStats::default(),
+            changes: Default::default(),
+            storage,
+            storage_key: T::PREFERENCES_FILENAME.to_string(),
+            _test_keys: Some(crate::storage::wasm::TestKeys::new(&test_id)),
+            _instance_guard: None,
+            registration,
+        }
+    }
+
+    /// Loads defaults into a [`crate::storage::MemoryStorage`], touching neither the filesystem
+    /// nor localStorage (ignores the single-instance constraint).
+    pub fn load_in_memory() -> Self {
+        let storage: Box<dyn Storage> = Box::new(crate::storage::MemoryStorage::new());
+        let (storage, registration) = registry::register(storage, T::PREFERENCES_FILENAME);
+        Self {
+            data: T::default(),
+            generation: AtomicU64::new(0),
+            load_outcome: LoadOutcome::Loaded,
+            load_stats: LoadStats::default(),
+            changes: Default::default(),
+            storage,
+            storage_key: T::PREFERENCES_FILENAME.to_string(),
+            #[cfg(not(target_arch = "wasm32"))]
+            _temp_file: None,
+            #[cfg(target_arch = "wasm32")]
+            _test_keys: None,
+            _instance_guard: None,
+            registration,
+        }
+    }
+
+    /// Runs `test` with an isolated in-memory instance (see [`Prefs::load_in_memory`]) and drops
+    /// it afterwards, also when `test` panics.
+    pub fn with_test_prefs<R>(test: impl FnOnce(&mut Self) -> R) -> R {
+        let mut prefs = Self::load_in_memory();
+        test(&mut prefs)
+    }
+
+    /// The current values.
+    pub fn get(&self) -> &T {
+        &self.data
+    }
+
+    /// Iterates over `T::FIELDS` with their current saved values (`None` for a skipped field
+    /// or a `None` option).
+    pub fn iter(&self) -> impl Iterator<Item = (&'static FieldInfo, Option<Value>)> {
+        let table = self.data.to_saved_table().unwrap_or_default();
+        T::FIELDS.iter().map(move |field| (field, table.get(field.key).cloned()))
+    }
+
+    /// Returns the current values keyed by saved name, leaving out `None` options and
+    /// redacting secret fields.
+    pub fn as_map(&self) -> std::collections::BTreeMap<&'static str, crate::dynamic::PrefValue> {
+        crate::dynamic::value_map(self.iter())
+    }
+
+    /// Restores `T::default()` and saves, keeping the old values if saving fails (e.g. the
+    /// defaults fail struct validation).
+    pub fn reset_to_defaults(&mut self) -> Result<(), SaveError> {
+        let previous = std::mem::take(&mut self.data);
+        if let Err(e) = self.save_as(SaveSource::Reset) {
+            self.data = previous;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Creates an edit guard that derefs to the values and saves on drop if they were touched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if write tokens are enforced (see `easy_prefs::write_token`);
+    /// use `edit_with_token()` in that case.
+    pub fn edit(&mut self) -> PrefsEditGuard<'_, T> {
+        if crate::write_token::is_enforced() {
+            panic!("write tokens are enforced; use edit_with_token() to edit preferences");
+        }
+        self.new_edit_guard()
+    }
+
+    /// Creates an edit guard while holding the app-wide write token.
+    pub fn edit_with_token(&mut self, _token: &crate::write_token::WriteToken) -> PrefsEditGuard<'_, T> {
+        self.new_edit_guard()
+    }
+
+    fn new_edit_guard(&mut self) -> PrefsEditGuard<'_, T> {
+        PrefsEditGuard {
+            prefs: self,
+            modified: false,
+            original: None,
+            transactional: false,
+            created: web_time::Instant::now(),
+        }
+    }
+
+    /// Runs `f` on an edit guard, keeping its changes only if `f` returns `Ok` and saving them
+    /// succeeds; otherwise the values are rolled back (see [`crate::transaction`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics like [`Prefs::edit`] if write tokens are enforced.
+    pub fn transaction<E>(
+        &mut self,
+        f: impl FnOnce(&mut PrefsEditGuard<'_, T>) -> Result<(), E>,
+    ) -> Result<(), crate::transaction::TxError<E>> {
+        let mut guard = self.edit();
+        guard.transactional = true;
+        match f(&mut guard) {
+            Ok(()) => guard.commit_or_roll_back().map_err(crate::transaction::TxError::Save),
+            Err(e) => {
+                guard.cancel();
+                Err(crate::transaction::TxError::Aborted(e))
+            }
+        }
+    }
+
+    /// Validates the values and writes them to storage.
+    pub fn save(&self) -> Result<(), SaveError> {
+        self.save_as(SaveSource::Save)
+    }
+
+    /// Saves like [`Prefs::save`], reporting changes to the `on_change` hook as made by `source`.
+    fn save_as(&self, source: SaveSource) -> Result<(), SaveError> {
+        let generation = write(&self.data, self.storage.as_ref(), &self.storage_key, self.generation(), Some(&self.registration))?;
+        self.generation.store(generation, Ordering::Relaxed);
+        self.report_changes(source);
+        Ok(())
+    }
+
+    /// Re-reads the values from storage; in-memory values are kept on error.
+    pub fn reload(&mut self) -> Result<(), LoadError> {
+        let Loaded { data, generation, outcome, stats } = read_from_storage::<T>(self.storage.as_ref(), &self.storage_key)?;
+        self.data = data;
+        *self.generation.get_mut() = generation;
+        self.load_outcome = outcome;
+        self.load_stats = stats;
+        self.report_changes(SaveSource::Reload);
+        Ok(())
+    }
+
+    /// Re-reads only the fields saved under `keys` from storage, keeping the in-memory values
+    /// of all others. In-memory values are kept on error, including `NotFound` for a key that is
+    /// not a field and a `ValidationError` if the combined values fail struct validation.
+    pub fn reload_fields(&mut self, keys: &[&str]) -> Result<(), LoadError> {
+        if let Some(key) = keys.iter().find(|key| !T::FIELDS.iter().any(|f| f.key == **key)) {
+            return Err(LoadError::StorageError(unknown_key(key)));
+        }
+        let Loaded { data: fresh, .. } = read_from_storage::<T>(self.storage.as_ref(), &self.storage_key)?;
+        let stored = fresh.to_saved_table().map_err(LoadError::SaveFailed)?;
+        let mut table = self.data.to_saved_table().map_err(LoadError::SaveFailed)?;
+        for key in keys {
+            match stored.get(*key) {
+                Some(value) => table.insert(key.to_string(), value.clone()),
+                None => table.remove(*key),
+            };
+        }
+        self.data = from_table::<T>(table, &self.get_preferences_file_path())?;
+        self.report_changes(SaveSource::Reload);
+        Ok(())
+    }
+
+    /// Reports the fields changed since the last load, save, or reload to the `on_change`
+    /// hook, if any.
+    fn report_changes(&self, source: SaveSource) {
+        if let Some(hook) = T::OPTIONS.on_change {
+            let values = self.data.to_saved_table().unwrap_or_default();
+            self.changes.report(hook, T::FIELDS, values, || T::default().to_saved_table().unwrap_or_default(), source);
+        }
+    }
+
+    /// How the values were obtained on the last load or reload (see [`crate::backup`]).
+    pub fn load_outcome(&self) -> &LoadOutcome {
+        &self.load_outcome
+    }
+
+    /// Returns how long the last load or reload took and the size of the document it read (see
+    /// [`crate::stats`]).
+    pub fn load_stats(&self) -> LoadStats {
+        self.load_stats
+    }
+
+    /// Reports suspicious values in the stored document without changing anything (see
+    /// [`crate::lint`]).
+    pub fn lint(&self) -> Result<crate::lint::LintReport, std::io::Error> {
+        let document = self.storage.read(&self.storage_key)?.unwrap_or_default();
+        crate::lint::lint_document(&document, T::FIELDS)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    /// Lists the states recorded by the `history` option, newest first.
+    pub fn history(&self) -> Result<Vec<crate::history::HistoryEntry>, std::io::Error> {
+        crate::history::entries(self.storage.as_ref(), &self.storage_key)
+    }
+
+    /// Replaces the values with the recorded state `hash` (or a unique prefix of it) and saves them.
+    ///
+    /// In-memory values are left untouched on error.
+    pub fn restore(&mut self, hash: &str) -> Result<(), LoadError> {
+        let contents = crate::history::state(self.storage.as_ref(), &self.storage_key, hash)
+            .map_err(LoadError::StorageError)?;
+        let (data, _) = from_stored::<T>(Some(&contents), self.storage.as_ref(), &self.storage_key)?;
+        let previous = std::mem::replace(&mut self.data, data);
+        if let Err(e) = self.save_as(SaveSource::Migration) {
+            self.data = previous;
+            return Err(LoadError::SaveFailed(e));
+        }
+        Ok(())
+    }
+
+    /// Serializes the exported fields as TOML (see [`crate::export`]).
+    pub fn export_toml(&self) -> Result<String, SaveError> {
+        let mut table = self.data.to_saved_table()?;
+        crate::export::retain_exported(&mut table, T::FIELDS);
+        Ok(toml::to_string(&table)?)
+    }
+
+    /// Applies the exported fields in `contents` and saves them (see [`crate::export`]).
+    ///
+    /// In-memory values are left untouched on error.
+    pub fn import_toml(&mut self, contents: &str) -> Result<(), LoadError> {
+        let mut imported: Table = contents
+            .parse()
+            .map_err(|e| LoadError::DeserializationError("import".to_string(), e))?;
+        crate::export::retain_exported(&mut imported, T::FIELDS);
+        self.import_table(imported)
+    }
+
+    /// Serializes the exported fields as a JSON object (see [`crate::json`]).
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Result<String, SaveError> {
+        let mut table = self.data.to_saved_table()?;
+        crate::export::retain_exported(&mut table, T::FIELDS);
+        Ok(crate::json::to_string(&table))
+    }
+
+    /// Applies the exported fields of a JSON object and saves them (see [`crate::json`]).
+    ///
+    /// In-memory values are left untouched on error.
+    #[cfg(feature = "json")]
+    #[allow(clippy::wrong_self_convention)]
+    pub fn from_json(&mut self, json: &str) -> Result<(), LoadError> {
+        let mut imported = crate::json::parse(json)
+            .map_err(|e| LoadError::DeserializationError(crate::json::LOCATION.to_string(), e))?;
+        crate::export::retain_exported(&mut imported, T::FIELDS);
+        self.import_table(imported)
+    }
+
+    /// Returns the exported fields as a table tagged with a format version, to store inside
+    /// another document (see [`crate::embed`]).
+    pub fn embed(&self) -> Result<Table, SaveError> {
+        Ok(crate::embed::wrap(self.data.to_saved_table()?, T::FIELDS, T::SCHEMA_HASH))
+    }
+
+    /// Applies a table returned by `embed()` and saves (see [`crate::embed`]).
+    ///
+    /// In-memory values are left untouched on error.
+    pub fn unembed(&mut self, embedded: &Table) -> Result<(), LoadError> {
+        let imported = crate::embed::unwrap(embedded, T::FIELDS).map_err(LoadError::StorageError)?;
+        self.import_table(imported)
+    }
+
+    /// Applies the exported fields in `imported` (see [`Prefs::import_toml`]).
+    fn import_table(&mut self, imported: Table) -> Result<(), LoadError> {
+        let mut table = self.data.to_saved_table().map_err(LoadError::SaveFailed)?;
+        table.extend(imported);
+        let merged = toml::to_string(&table).map_err(|e| LoadError::SaveFailed(e.into()))?;
+        let (data, _) = from_stored::<T>(Some(&merged), self.storage.as_ref(), &self.storage_key)?;
+        let previous = std::mem::replace(&mut self.data, data);
+        if let Err(e) = self.save_as(SaveSource::Migration) {
+            self.data = previous;
+            return Err(LoadError::SaveFailed(e));
+        }
+        Ok(())
+    }
+
+    /// Applies the keys present in a partial TOML document and saves (see [`crate::patch`]).
+    ///
+    /// In-memory values are left untouched if the patch doesn't parse, a patched value has the
+    /// wrong type or fails validation, or saving fails.
+    pub fn apply_toml_patch(&mut self, patch: &str) -> Result<crate::patch::PatchReport, LoadError> {
+        let patch: Table = patch
+            .parse()
+            .map_err(|e| LoadError::DeserializationError(crate::patch::LOCATION.to_string(), e))?;
+        self.apply_patch_table(patch)
+    }
+
+    /// Applies the values of a TOML document that `policy` selects, like `apply_toml_patch()`,
+    /// and saves once (see [`crate::patch`]).
+    ///
+    /// In-memory values are left untouched on error.
+    pub fn merge_from_toml(
+        &mut self,
+        toml: &str,
+        policy: crate::patch::MergePolicy,
+    ) -> Result<crate::patch::PatchReport, LoadError> {
+        let patch: Table = toml
+            .parse()
+            .map_err(|e| LoadError::DeserializationError(crate::patch::LOCATION.to_string(), e))?;
+        let current = self.data.to_saved_table().map_err(LoadError::SaveFailed)?;
+        let defaults = serialize_or_panic(&T::default());
+        let (patch, skipped) = crate::patch::select(patch, &current, &defaults, T::FIELDS, policy);
+        let mut report = self.apply_patch_table(patch)?;
+        report.skipped = skipped;
+        Ok(report)
+    }
+
+    /// Applies the keys present in `patch` (see [`Prefs::apply_toml_patch`]).
+    fn apply_patch_table(&mut self, patch: Table) -> Result<crate::patch::PatchReport, LoadError> {
+        let mut table = self.data.to_saved_table().map_err(LoadError::SaveFailed)?;
+        let report = crate::patch::merge(&mut table, patch, T::FIELDS).map_err(LoadError::ValidationError)?;
+        if !report.changed() {
+            return Ok(report);
+        }
+        let data = from_table::<T>(table, crate::patch::LOCATION)?;
+        let previous = std::mem::replace(&mut self.data, data);
+        if let Err(e) = self.save_as(SaveSource::Migration) {
+            self.data = previous;
+            return Err(LoadError::SaveFailed(e));
+        }
+        Ok(report)
+    }
+
+    /// Reconciles the exported fields with the copy held by `provider`, saves the fields
+    /// taken from it, and pushes the result (see [`crate::sync`]).
+    ///
+    /// In-memory values are left untouched if pulling fails, the remote copy is invalid, or
+    /// saving fails.
+    pub fn sync(
+        &mut self,
+        provider: &mut dyn crate::sync::SyncProvider,
+        resolution: crate::sync::Resolution,
+    ) -> Result<crate::sync::SyncReport, LoadError> {
+        let mut table = self.data.to_saved_table().map_err(LoadError::SaveFailed)?;
+        let plan = crate::sync::plan(
+            provider,
+            self.storage.as_ref(),
+            &self.storage_key,
+            T::FIELDS,
+            table.clone(),
+            || serialize_or_panic(&T::default()),
+            resolution,
+        )?;
+
+        if !plan.report.pulled.is_empty() {
+            plan.apply(&mut table);
+            let data = from_table::<T>(table, crate::sync::LOCATION)?;
+            let previous = std::mem::replace(&mut self.data, data);
+            if let Err(e) = self.save_as(SaveSource::Sync) {
+                self.data = previous;
+                return Err(LoadError::SaveFailed(e));
+            }
+        }
+        crate::sync::finish(provider, self.storage.as_ref(), &self.storage_key, plan)
+    }
+
+    /// Returns the write generation of the last save or load (see [`crate::generation`]).
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
+    /// Returns the storage path/key as a string.
+    pub fn get_preferences_file_path(&self) -> String {
+        self.storage.get_path(&self.storage_key)
+    }
+
+    /// Returns false if saved values don't outlive the process (see
+    /// [`crate::storage::MemoryStorage`]).
+    pub fn is_persistent(&self) -> bool {
+        self.storage.is_persistent()
+    }
+
+    /// Describes the storage backend, resolved location, and detected platform quirks.
+    pub fn environment_report(&self) -> EnvironmentReport {
+        self.storage.environment_report(&self.storage_key)
+    }
+}
+
+/// Formats the values as TOML, with secret fields redacted.
+impl<T: PrefsData> std::fmt::Display for Prefs<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut table = self.data.to_saved_table().map_err(|_| std::fmt::Error)?;
+        crate::secrets::redact(&mut table, T::FIELDS);
+        f.write_str(&toml::to_string(&table).map_err(|_| std::fmt::Error)?)
+    }
+}
+
+impl<T: PrefsData> Preferences for Prefs<T> {
+    fn load_with_error(directory: &str) -> Result<Self, LoadError> {
+        Self::load_with_error(directory)
+    }
+
+    fn save(&self) -> Result<(), SaveError> {
+        Self::save(self)
+    }
+
+    fn reload(&mut self) -> Result<(), LoadError> {
+        Self::reload(self)
+    }
+
+    fn get_preferences_file_path(&self) -> String {
+        Self::get_preferences_file_path(self)
+    }
+}
+
+impl<T: PrefsData> DynamicPrefs for Prefs<T> {
+    fn fields(&self) -> &'static [FieldInfo] {
+        T::FIELDS
+    }
+
+    fn get_field_string(&self, key: &str) -> Option<String> {
+        T::FIELDS.iter().find(|f| f.key == key)?;
+        let table = self.data.to_saved_table().ok()?;
+        Some(table.get(key).map(crate::parse::format_value).unwrap_or_default())
+    }
+
+    fn set_field_from_str(&mut self, key: &str, input: &str) -> Result<(), std::io::Error> {
+        let field = T::FIELDS.iter().find(|f| f.key == key).ok_or_else(|| unknown_key(key))?;
+        let mut table = self.data.to_saved_table().map_err(std::io::Error::other)?;
+        let value = match table.get(key) {
+            Some(current) => crate::parse::parse_value::<Value>(field, input, current)?,
+            None => crate::parse::parse_toml_value(input).unwrap_or_else(|| Value::String(input.to_string())),
+        };
+        crate::validate::check_value(field, &value)?;
+        table.insert(key.to_string(), value);
+        let data = T::from_saved_table(table).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                crate::parse::ParseValueError {
+                    key: field.key,
+                    input: input.to_string(),
+                    reason: e.to_string(),
+                },
+            )
+        })?;
+        self.replace_and_save(data)
+    }
+
+    fn get_value(&self, key: &str) -> Option<Value> {
+        T::FIELDS.iter().find(|f| f.key == key)?;
+        self.data.to_saved_table().ok()?.remove(key)
+    }
+
+    fn set_value(&mut self, key: &str, value: Value) -> Result<(), std::io::Error> {
+        let field = T::FIELDS.iter().find(|f| f.key == key).ok_or_else(|| unknown_key(key))?;
+        let mut table = self.data.to_saved_table().map_err(std::io::Error::other)?;
+        let value = match table.get(key) {
+            Some(current) => crate::coerce::coerce_value(&value, current).unwrap_or(value),
+            None => value,
+        };
+        crate::validate::check_value(field, &value)?;
+        table.insert(key.to_string(), value);
+        let data = T::from_saved_table(table).map_err(|e| invalid_value(key, e))?;
+        self.replace_and_save(data)
+    }
+}
+
+impl<T: PrefsData> Prefs<T> {
+    /// Swaps in new values and saves them, keeping the old values if saving fails.
+    fn replace_and_save(&mut self, data: T) -> Result<(), std::io::Error> {
+        let previous = std::mem::replace(&mut self.data, data);
+        if let Err(e) = self.save_as(SaveSource::Field) {
+            self.data = previous;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+}
+
+/// Cloneable handle for sharing preferences between threads (see [`Prefs::load_shared`]).
+///
+/// Reads take a shared lock, so they only wait for writes, not for each other.
+pub struct PrefsHandle<T: PrefsData> {
+    inner: Arc<RwLock<Prefs<T>>>,
+}
+
+impl<T: PrefsData> Clone for PrefsHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: PrefsData> PrefsHandle<T> {
+    /// Locks the preferences for reading.
+    pub fn read(&self) -> RwLockReadGuard<'_, Prefs<T>> {
+        self.inner.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Locks the preferences for writing, e.g. to use an edit guard.
+    pub fn write(&self) -> RwLockWriteGuard<'_, Prefs<T>> {
+        self.inner.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns a value computed from the current values under a read lock.
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(self.read().get())
+    }
+
+    /// Changes the values with `f` under the write lock and saves them once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if write tokens are enforced (see [`crate::write_token`]); use `write()` and
+    /// [`Prefs::edit_with_token`] in that case.
+    pub fn edit<R>(&self, f: impl FnOnce(&mut T) -> R) -> Result<R, SaveError> {
+        let mut prefs = self.write();
+        let mut guard = prefs.edit();
+        let result = f(&mut guard);
+        guard.commit()?;
+        Ok(result)
+    }
+
+    /// Re-reads the values from storage (see [`Prefs::reload`]).
+    pub fn reload(&self) -> Result<(), LoadError> {
+        self.write().reload()
+    }
+}
+
+// Values are left out: `T`'s own `Debug` would show secret fields.
+impl<T: PrefsData> std::fmt::Debug for PrefsHandle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PrefsHandle").finish_non_exhaustive()
+    }
+}
+
+/// Guard for batch editing; saves on drop if the values were mutably accessed.
+///
+/// Use [`PrefsEditGuard::commit`] to handle save errors, or [`PrefsEditGuard::cancel`] to
+/// discard the changes.
+pub struct PrefsEditGuard<'a, T: PrefsData> {
+    prefs: &'a mut Prefs<T>,
+    modified: bool,
+    // Values from before the first mutable access, for `cancel()`.
+    original: Option<Table>,
+    // Inside `transaction()`: roll back instead of saving on drop (i.e. on panic).
+    transactional: bool,
+    created: web_time::Instant,
+}
+
+impl<T: PrefsData> PrefsEditGuard<'_, T> {
+    /// Saves the changes now, returning any error instead of printing it on drop.
+    ///
+    /// Does nothing if the values were not mutably accessed.
+    pub fn commit(mut self) -> Result<(), SaveError> {
+        if !std::mem::take(&mut self.modified) {
+            return Ok(());
+        }
+        self.prefs.save_as(SaveSource::EditGuard)
+    }
+
+    /// Discards the changes made through this guard without saving.
+    ///
+    /// Values are restored as they would load from their saved form.
+    pub fn cancel(mut self) {
+        if std::mem::take(&mut self.modified) {
+            self.restore_original();
+        }
+    }
+
+    /// Saves like [`PrefsEditGuard::commit`], restoring the original values if that fails.
+    fn commit_or_roll_back(mut self) -> Result<(), SaveError> {
+        if !std::mem::take(&mut self.modified) {
+            return Ok(());
+        }
+        let saved = self.prefs.save_as(SaveSource::EditGuard);
+        if saved.is_err() {
+            self.restore_original();
+        }
+        saved
+    }
+
+    fn restore_original(&mut self) {
+        if let Some(original) = self.original.take() {
+            match Value::Table(original).try_into() {
+                Ok(data) => self.prefs.data = data,
+                Err(e) => scrub::log(T::FIELDS, Some(&self.prefs.data), format_args!("Failed to restore values: {}", e)),
+            }
+        }
+    }
+}
+
+impl<T: PrefsData> Deref for PrefsEditGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.prefs.data
+    }
+}
+
+impl<T: PrefsData> DerefMut for PrefsEditGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        if !self.modified {
+            self.original = Table::try_from(&self.prefs.data).ok();
+            self.modified = true;
+        }
+        &mut self.prefs.data
+    }
+}
+
+impl<T: PrefsData> Drop for PrefsEditGuard<'_, T> {
+    fn drop(&mut self) {
+        if cfg!(debug_assertions) && !std::thread::panicking() {
+            let duration = self.created.elapsed();
+            // Warn if edit guard is held for more than 1 second in debug mode
+            if duration.as_secs() >= 1 {
+                eprintln!("Warning: Edit guard held for {:?} - consider reducing the scope", duration);
+            }
+        }
+        if self.modified && self.transactional {
+            self.restore_original();
+        } else if self.modified {
+            if let Err(e) = self.prefs.save_as(SaveSource::EditGuard) {
+                scrub::log(T::FIELDS, Some(&self.prefs.data), format_args!("Failed to save: {}", e));
+            }
+        }
+    }
+}
+
+/// Claims the single instance of `T` stored at `path` (as [`Storage::get_path`] gives it);
+/// `None` if `T` opted out with `single_instance = false`, which never touches the registry.
+#[doc(hidden)]
+pub fn claim_instance<T: PrefsData>(path: &str) -> Result<Option<InstanceGuard>, LoadError> {
+    if !T::OPTIONS.single_instance {
+        return Ok(None);
+    }
+    let id = (TypeId::of::<T>(), path.to_string());
+    if !loaded().insert(id.clone()) {
+        return Err(LoadError::InstanceAlreadyLoaded);
+    }
+    Ok(Some(InstanceGuard(id)))
+}
+
+/// Storage paths of the loaded instances of `T`.
+#[doc(hidden)]
+pub fn loaded_paths<T: PrefsData>() -> Vec<String> {
+    if !T::OPTIONS.single_instance {
+        return Vec::new();
+    }
+    loaded()
+        .iter()
+        .filter(|(type_id, _)| *type_id == TypeId::of::<T>())
+        .map(|(_, path)| path.clone())
+        .collect()
+}
+
+/// Serializes `data` for purposes that can't fail, panicking (with secrets scrubbed) if TOML
+/// can't express it.
+#[doc(hidden)]
+pub fn serialize_or_panic<T: PrefsData>(data: &T) -> Table {
+    data.to_saved_table().unwrap_or_else(|e| scrub::panic(T::FIELDS, None::<&T>, format_args!("{}", e)))
+}
+
+/// Moves values from one key to another, preserving the order of the other keys.
+fn rename_keys<'k>(table: &mut Table, renames: impl Iterator<Item = (&'k str, &'k str)>) {
+    let renames: Vec<_> = renames.filter(|(from, to)| from != to).collect();
+    if renames.is_empty() {
+        return;
+    }
+    *table = std::mem::take(table)
+        .into_iter()
+        .map(|(key, value)| {
+            match renames.iter().find(|(from, _)| *from == key) {
+                Some((_, to)) => (to.to_string(), value),
+                None => (key, value),
+            }
+        })
+        .collect();
+}
+
+/// Applies the storage-related struct options (`file_mode`, `durability`, `cache_reads`,
+/// `exclude_from_backup`, `key_separators`) to `storage`.
+#[doc(hidden)]
+pub fn configure_storage<T: PrefsData>(mut storage: Box<dyn Storage>) -> Box<dyn Storage> {
+    if let Some(mode) = T::OPTIONS.file_mode {
+        storage.set_file_mode(mode);
+    }
+    if let Some(durability) = T::OPTIONS.durability {
+        storage.set_durability(durability);
+    }
+    if T::OPTIONS.cache_reads {
+        storage.set_read_cache(true);
+    }
+    if T::OPTIONS.exclude_from_backup {
+        storage.set_backup_exclusion(true);
+    }
+    Box::new(crate::storage::ResolvedKeys::new(storage, T::OPTIONS.key_separators))
+}
+
+/// The storage of `directory` with the struct's `key_separators` applied.
+#[doc(hidden)]
+pub fn resolved_storage<T: PrefsData>(directory: &str) -> crate::storage::ResolvedKeys {
+    crate::storage::ResolvedKeys::new(crate::storage::create_storage(directory), T::OPTIONS.key_separators)
+}
+
+/// Validates `data` and writes it to `storage`, continuing from write generation `generation`;
+/// returns the new generation.
+///
+/// A failed write is left with `registration` for [`crate::flush_all`] to retry.
+#[doc(hidden)]
+pub fn write<T: PrefsData>(
+    data: &T,
+    storage: &dyn Storage,
+    storage_key: &str,
+    generation: u64,
+    registration: Option<&Registration>,
+) -> Result<u64, SaveError> {
+    crate::validate::check_struct(T::OPTIONS.validate_struct, data)?;
+    let mut table = data.to_saved_table()?;
+    for field in T::FIELDS {
+        if let Some(value) = table.get(field.key) {
+            crate::validate::check_value(field, value)?;
+        }
+    }
+    crate::validate::check_struct(T::OPTIONS.on_before_save, data)?;
+
+    // `None` is stored as an absent key, which loads back as the declared default.
+    if table.len() < T::FIELDS.len() {
+        let defaults = serialize_or_panic(&T::default());
+        for field in T::FIELDS {
+            if !table.contains_key(field.key) && defaults.contains_key(field.key) {
+                scrub::log(
+                    T::FIELDS,
+                    Some(data),
+                    format_args!("Warning: '{}' is None but its default is not; it will load as the default", field.key),
+                );
+            }
+        }
+    }
+
+    crate::transient::remove(&mut table, T::FIELDS);
+    data.store_temporary(&mut table)?;
+    // Secret fields go to the secret store (if any) instead of the file.
+    crate::secrets::extract(&mut table, T::FIELDS, storage, storage_key)?;
+    // The stored document is only read for the options below; the generation continues from
+    // memory otherwise.
+    let compares_existing = T::OPTIONS.compat_sidecar
+        || T::OPTIONS.backups > 0
+        || T::FIELDS.iter().any(|f| f.options.external || f.options.critical);
+    let existing = if T::OPTIONS.preserve_formatting {
+        storage.read_before_write(storage_key)?
+    } else if compares_existing {
+        storage.read_before_write(storage_key).ok().flatten()
+    } else {
+        None
+    };
+    let existing_table = existing
+        .as_deref()
+        .and_then(|existing| T::OPTIONS.format.parse(crate::integrity::strip(existing)).ok());
+    if T::OPTIONS.compat_sidecar {
+        crate::sidecar::stash(storage, storage_key, existing_table.as_ref(), T::FIELDS, T::SCHEMA_HASH)?;
+    }
+    let generation = crate::generation::next(generation, existing_table.as_ref());
+    crate::generation::insert(&mut table, generation);
+    crate::blob::extract(&mut table, T::FIELDS, storage, storage_key, existing_table.as_ref())?;
+    let serialized = if T::OPTIONS.preserve_formatting {
+        crate::preserve::merge(existing.as_deref().map(crate::integrity::strip), &table, T::FIELDS, &T::OPTIONS)?
+    } else {
+        T::OPTIONS.format.to_string(&table)?
+    };
+    let stored = if T::OPTIONS.integrity { crate::integrity::seal(&serialized) } else { serialized.clone() };
+    // A file that doesn't parse or fails its integrity check would make a useless backup.
+    let backed_up = existing.as_deref().filter(|previous| {
+        T::OPTIONS.backups > 0
+            && existing_table.is_some()
+            && (!T::OPTIONS.integrity || crate::integrity::verify(previous).is_ok())
+    });
+    if let Some(previous) = backed_up {
+        if let Err(e) = crate::backup::rotate(storage, storage_key, previous, T::OPTIONS.backups) {
+            scrub::log(T::FIELDS, Some(data), format_args!("Failed to back up preferences: {}", e));
+        }
+    }
+    let written = crate::critical::write(
+        storage,
+        storage_key,
+        &stored,
+        &table,
+        existing_table.as_ref(),
+        T::FIELDS,
+        T::OPTIONS.on_critical_failure,
+    );
+    if let Some(registration) = registration {
+        registration.set_pending(written.is_err().then(|| stored.clone()));
+    }
+    written?;
+    if T::OPTIONS.history > 0 {
+        if let Err(e) = crate::history::record(storage, storage_key, &serialized, T::OPTIONS.history) {
+            scrub::log(T::FIELDS, Some(data), format_args!("Failed to record preferences history: {}", e));
+        }
+    }
+    Ok(generation)
+}
+
+/// Values read from storage, with their write generation.
+#[doc(hidden)]
+pub struct Loaded<T> {
+    pub data: T,
+    pub generation: u64,
+    pub outcome: LoadOutcome,
+    pub stats: LoadStats,
+}
+
+/// Reads the stored values, falling back to the newest loadable backup if they don't
+/// deserialize.
+#[doc(hidden)]
+pub fn read_from_storage<T: PrefsData>(storage: &dyn Storage, storage_key: &str) -> Result<Loaded<T>, LoadError> {
+    let started = web_time::Instant::now();
+    let contents = storage.read(storage_key).map_err(LoadError::StorageError)?;
+    let (data, generation, outcome) = match from_verified::<T>(contents.as_deref(), storage, storage_key) {
+        Ok((data, generation)) => (data, generation, LoadOutcome::Loaded),
+        Err(e @ (LoadError::DeserializationError(..) | LoadError::IntegrityFailure(..))) => {
+            let recovered = crate::backup::recover(storage, storage_key, T::OPTIONS.backups, T::FIELDS, |contents| {
+                from_verified::<T>(Some(contents), storage, storage_key)
+            });
+            if let Some((backup, (data, generation))) = recovered {
+                scrub::log(T::FIELDS, None::<&T>, format_args!("Failed to load preferences ({}), restored them from {}", e, backup));
+                (data, generation, LoadOutcome::RecoveredFromBackup { backup, error: e.to_string() })
+            } else if !matches!(e, LoadError::IntegrityFailure(..)) || !T::OPTIONS.reset_on_integrity_failure {
+                return Err(e);
+            } else {
+                scrub::log(T::FIELDS, None::<&T>, format_args!("Failed to load preferences ({}), using defaults", e));
+                let (data, generation) = from_stored::<T>(None, storage, storage_key)?;
+                (data, generation, LoadOutcome::ResetToDefaults { error: e.to_string() })
+            }
+        }
+        Err(e) => return Err(e),
+    };
+    let size = contents.map_or(0, |contents| contents.len());
+    let stats = crate::stats::measure(started, size, T::OPTIONS.load_budget, &storage.get_path(storage_key));
+    Ok(Loaded { data, generation, outcome, stats })
+}
+
+/// Reads the stored values like [`read_from_storage`], but drops what doesn't load instead of
+/// failing.
+#[doc(hidden)]
+pub fn read_lenient<T: PrefsData>(storage: &dyn Storage, storage_key: &str) -> (Loaded<T>, Vec<crate::lenient::LoadWarning>) {
+    let started = web_time::Instant::now();
+    let contents = storage.read(storage_key);
+    let size = contents.as_ref().ok().and_then(Option::as_ref).map_or(0, String::len);
+    let defaults = serialize_or_panic(&T::default());
+    let (table, mut warnings) =
+        crate::lenient::salvage(contents, T::FIELDS, &T::OPTIONS, &defaults, |table| {
+            T::from_saved_table(table).map(drop)
+        });
+    let loaded = match table {
+        Some(table) => from_parsed::<T>(table, None, storage, storage_key),
+        None => from_stored::<T>(None, storage, storage_key),
+    };
+    let (data, generation) = loaded
+        .or_else(|e| {
+            warnings.push(crate::lenient::LoadWarning::document(&e, T::FIELDS));
+            from_stored::<T>(None, storage, storage_key)
+        })
+        .unwrap_or_else(|e| {
+            warnings.push(crate::lenient::LoadWarning::document(&e, T::FIELDS));
+            (T::default(), 0)
+        });
+    let stats = crate::stats::measure(started, size, T::OPTIONS.load_budget, &storage.get_path(storage_key));
+    (Loaded { data, generation, outcome: LoadOutcome::Loaded, stats }, warnings)
+}
+
+/// Checks the integrity footer if enabled, then deserializes like [`from_stored`].
+fn from_verified<T: PrefsData>(
+    contents: Option<&str>,
+    storage: &dyn Storage,
+    storage_key: &str,
+) -> Result<(T, u64), LoadError> {
+    let contents = match contents {
+        Some(contents) if T::OPTIONS.integrity => Some(
+            crate::integrity::verify(contents)
+                .map_err(|e| LoadError::IntegrityFailure(storage.get_path(storage_key), e))?,
+        ),
+        contents => contents,
+    };
+    from_stored::<T>(contents, storage, storage_key)
+}
+
+/// Deserializes a stored document (or defaults if there is none), filling in secrets and blobs.
+///
+/// Also returns the document's write generation.
+#[doc(hidden)]
+pub fn from_stored<T: PrefsData>(
+    contents: Option<&str>,
+    storage: &dyn Storage,
+    storage_key: &str,
+) -> Result<(T, u64), LoadError> {
+    let table = match contents {
+        Some(contents) => T::OPTIONS.format.parse(contents)
+            .map_err(|e| LoadError::DeserializationError(storage.get_path(storage_key), e))?,
+        None => Table::new(),
+    };
+    from_parsed::<T>(table, contents, storage, storage_key)
+}
+
+/// Finishes [`from_stored`] with the table parsed from `contents`.
+fn from_parsed<T: PrefsData>(
+    mut table: Table,
+    contents: Option<&str>,
+    storage: &dyn Storage,
+    storage_key: &str,
+) -> Result<(T, u64), LoadError> {
+    let location = &storage.get_path(storage_key);
+    crate::alias::rename(&mut table, T::FIELDS);
+    if T::OPTIONS.compat_sidecar {
+        crate::sidecar::merge(&mut table, T::FIELDS, storage, storage_key);
+    }
+    crate::transient::remove(&mut table, T::FIELDS);
+    let generation = crate::generation::take(&mut table);
+
+    crate::secrets::inject(&mut table, T::FIELDS, storage, storage_key).map_err(LoadError::StorageError)?;
+    crate::blob::inject(&mut table, T::FIELDS, storage, storage_key).map_err(LoadError::StorageError)?;
+    if T::OPTIONS.strict {
+        let defaults = serialize_or_panic(&T::default());
+        crate::strict::check(contents, &table, T::FIELDS, &T::OPTIONS, &defaults, |table| {
+            T::from_saved_table(table).map(drop)
+        })
+        .map_err(LoadError::ValidationError)?;
+    }
+
+    Ok((from_table::<T>(table, location)?, generation))
+}
+
+/// Applies load-time repairs to saved values, then deserializes and validates them.
+#[doc(hidden)]
+pub fn from_table<T: PrefsData>(mut table: Table, location: &str) -> Result<T, LoadError> {
+    let overrides = crate::temporary::take(&mut table);
+    let removed = crate::removed::take(&mut table, T::OPTIONS.removed);
+    if T::OPTIONS.lenient_keys {
+        for correction in crate::coerce::normalize_keys(&mut table, T::FIELDS) {
+            scrub::log(T::FIELDS, Some(&table), format_args!("Correcting stored key: {}", correction));
+        }
+    }
+
+    crate::defaults::apply(&mut table, T::OPTIONS.defaults)
+        .map_err(|e| LoadError::DeserializationError(crate::defaults::LOCATION.to_string(), e))?;
+
+    if T::OPTIONS.lenient_types || T::FIELDS.iter().any(|f| f.options.lenient_types) {
+        let defaults = serialize_or_panic(&T::default());
+        crate::coerce::coerce_table(&mut table, &defaults, T::FIELDS, T::OPTIONS.lenient_types);
+    }
+
+    let repairs = crate::validate::sanitize_table(&mut table, T::FIELDS).map_err(LoadError::ValidationError)?;
+    for repair in repairs {
+        scrub::log(T::FIELDS, Some(&table), format_args!("Repairing stored value: {}", repair));
+    }
+
+    let mut data = T::from_saved_table(table).map_err(|e| LoadError::DeserializationError(location.to_string(), e))?;
+    data.read_temporary(&overrides);
+    crate::removed::apply(removed, &mut data);
+    if let Some(hook) = T::OPTIONS.on_after_load {
+        hook(&mut data);
+    }
+    crate::validate::check_struct(T::OPTIONS.validate_struct, &data).map_err(LoadError::ValidationError)?;
+    Ok(data)
+}
@@ -0,0 +1,141 @@
+//! Saving into the existing file instead of rewriting it (`preserve_formatting = true`).
+//!
+//! With the struct option enabled, `save()` reads the stored document and updates the keys of
+//! the struct's fields in place. Everything else survives the round-trip:
+//!
+//! - keys the struct doesn't know about (e.g. written by a newer or older app version),
+//! - comments, blank lines, and key order,
+//! - the spelling of unchanged values (`0x10`, `'literal strings'`, inline tables).
+//!
+//! Fields missing from the serialized data (`None`, secrets moved to a secret store) are
+//! removed from the document. Within a field that is a table (a section or a map), the
+//! table's keys mirror the value exactly.
+
+use crate::field::FieldInfo;
+use crate::options::PrefsOptions;
+use toml::Table;
+use toml_edit::{DocumentMut, Item};
+
+/// Serializes `table`, merging it into `existing` if that is a valid TOML document.
+///
+/// Aliased keys in `existing` are renamed to their field's saved name (see [`crate::alias`]),
+/// and the keys of removed fields are dropped (see [`crate::removed`]). With `lenient_keys`,
+/// near-miss keys in `existing` are renamed to the saved names they match (see
+/// [`crate::coerce::normalize_keys`]).
+pub fn merge<T>(
+    existing: Option<&str>,
+    table: &Table,
+    fields: &[FieldInfo],
+    options: &PrefsOptions<T>,
+) -> Result<String, std::io::Error> {
+    let fresh = toml::to_string(table)
+        .map_err(|e| std::io::Error::other(format!("serialization failed: {e}")))?;
+    let Some(existing) = existing else {
+        return Ok(fresh);
+    };
+    let mut document = match existing.parse::<DocumentMut>() {
+        Ok(document) => document,
+        Err(e) => {
+            crate::scrub::log(fields, None::<&()>, format_args!("Warning: existing preferences are not valid TOML ({e}); rewriting them"));
+            return Ok(fresh);
+        }
+    };
+    let fresh = fresh
+        .parse::<DocumentMut>()
+        .map_err(|e| std::io::Error::other(format!("serialization failed: {e}")))?;
+
+    rename_aliases(&mut document, fields);
+    for removed in options.removed {
+        document.remove(removed.key);
+    }
+    if options.lenient_keys {
+        rename_near_misses(&mut document, fields);
+    }
+
+    let reserved = [crate::temporary::TABLE_KEY, crate::generation::KEY];
+    let keys = fields.iter().map(|field| field.key).chain(reserved);
+    for key in keys {
+        match (document.get_mut(key), fresh.get(key)) {
+            (Some(target), Some(value)) => merge_item(target, value),
+            (None, Some(value)) => {
+                document.insert(key, value.clone());
+            }
+            (Some(_), None) => {
+                document.remove(key);
+            }
+            (None, None) => {}
+        }
+    }
+    Ok(document.to_string())
+}
+
+/// Moves items stored under near-miss keys to the saved names they match.
+/// Moves aliased keys in `document` to their saved names (see [`crate::alias::rename`]).
+fn rename_aliases(document: &mut DocumentMut, fields: &[FieldInfo]) {
+    for field in fields {
+        for alias in field.aliases {
+            if let Some(item) = document.remove(alias) {
+                if !document.contains_key(field.key) {
+                    document.insert(field.key, item);
+                }
+            }
+        }
+    }
+}
+
+fn rename_near_misses(document: &mut DocumentMut, fields: &[FieldInfo]) {
+    let found: Vec<String> = document.iter().map(|(key, _)| key.to_string()).collect();
+    for found in found {
+        let Some(key) = crate::coerce::matching_key(&found, fields) else {
+            continue;
+        };
+        if document.contains_key(key) {
+            continue;
+        }
+        if let Some(item) = document.remove(&found) {
+            document.insert(key, item);
+        }
+    }
+}
+
+/// Updates `target` to hold `fresh`, keeping comments and the spelling of equal values.
+fn merge_item(target: &mut Item, fresh: &Item) {
+    match (target, fresh) {
+        (Item::Value(target), Item::Value(fresh)) => merge_value(target, fresh),
+        (Item::Table(target), Item::Table(fresh)) => {
+            target.retain(|key, _| fresh.contains_key(key));
+            for (key, value) in fresh.iter() {
+                match target.get_mut(key) {
+                    Some(item) => merge_item(item, value),
+                    None => {
+                        target.insert(key, value.clone());
+                    }
+                }
+            }
+        }
+        // A table written inline by hand stays inline.
+        (Item::Value(target), Item::Table(fresh)) => {
+            merge_value(target, &fresh.clone().into_inline_table().into());
+        }
+        (target, fresh) => *target = fresh.clone(),
+    }
+}
+
+fn merge_value(target: &mut toml_edit::Value, fresh: &toml_edit::Value) {
+    if !same_value(target, fresh) {
+        let decor = target.decor().clone();
+        *target = fresh.clone();
+        *target.decor_mut() = decor;
+    }
+}
+
+/// Compares two values by meaning, ignoring how they are written.
+fn same_value(a: &toml_edit::Value, b: &toml_edit::Value) -> bool {
+    let parse = |value: &toml_edit::Value| {
+        crate::parse::parse_toml_value(&value.clone().decorated("", "").to_string())
+    };
+    match (parse(a), parse(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
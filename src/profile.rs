@@ -0,0 +1,49 @@
+//! Profiles: several named instances of the same preferences struct.
+//!
+//! `load_profile(directory, name)` loads the struct from `<filename>.<name>.toml` instead of
+//! `<filename>.toml`. The single-instance constraint applies per stored file, so one instance of
+//! each profile (and of the default file) can be loaded at a time:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub theme: String = "light".to_string() => "theme",
+//!     },
+//!     "app-settings"
+//! }
+//!
+//! # let tmp = tempfile::tempdir().unwrap();
+//! # let dir = tmp.path().to_str().unwrap();
+//! let mut work = AppPrefs::load_profile(dir, "work").unwrap();
+//! let _home = AppPrefs::load_profile(dir, "home").unwrap(); // both loaded at once
+//! work.save_theme("dark".to_string()).unwrap();
+//! assert!(work.get_preferences_file_path().ends_with("app-settings.work.toml"));
+//! assert!(AppPrefs::load_profile(dir, "work").is_err());
+//! ```
+//!
+//! Profile names may contain ASCII letters, digits, `-`, and `_`.
+
+use crate::LoadError;
+
+/// Returns the storage key of `profile` for preferences normally stored under `filename`.
+///
+/// # Errors
+///
+/// Returns `LoadError::StorageError` with `InvalidInput` for an empty name or one with other
+/// characters than ASCII letters, digits, `-`, and `_`.
+pub fn storage_key(filename: &str, profile: &str) -> Result<String, LoadError> {
+    let valid = !profile.is_empty()
+        && profile
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(LoadError::StorageError(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("invalid profile name '{profile}'"),
+        )));
+    }
+    let stem = filename.strip_suffix(".toml").unwrap_or(filename);
+    Ok(format!("{stem}.{profile}.toml"))
+}
@@ -0,0 +1,195 @@
+//! Registry of loaded preferences, so shutdown paths can flush them all with one call.
+//!
+//! Setters save immediately, so a loaded instance only holds unsaved state when a save failed
+//! to write (disk full, directory briefly unavailable): its values in memory are then ahead of
+//! storage. Each loaded instance registers itself, a failed write leaves the document it meant
+//! to store pending, and [`flush_all()`] retries every pending write:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub theme: String = "light".to_string() => "theme",
+//!     },
+//!     "registry-doc-prefs"
+//! }
+//!
+//! let mut prefs = AppPrefs::load_testing();
+//! prefs.save_theme("dark".to_string()).unwrap();
+//!
+//! // On shutdown:
+//! if let Err(e) = easy_prefs::flush_all() {
+//!     eprintln!("{e}");
+//! }
+//! ```
+//!
+//! Dropping an instance unregisters it along with anything still pending. Changes held by an
+//! open edit guard are not covered; they are saved when the guard drops.
+//!
+//! A flush writes the document exactly as the failed save prepared it. That save already
+//! rotated the backups and bumped the write generation, so the flush doesn't repeat either,
+//! but it doesn't add the document to the history (`history = N`) either; the next successful
+//! save of the instance records its state there.
+
+use crate::storage::{Durability, EnvironmentReport, Storage};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+struct Entry {
+    storage: Arc<dyn Storage>,
+    storage_key: String,
+    pending: Option<String>,
+}
+
+static ENTRIES: Lazy<Mutex<HashMap<u64, Entry>>> = Lazy::new(Default::default);
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+fn entries() -> MutexGuard<'static, HashMap<u64, Entry>> {
+    ENTRIES.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Storage shared between a loaded instance and the registry.
+#[derive(Debug)]
+struct SharedStorage(Arc<dyn Storage>);
+
+impl Storage for SharedStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+        self.0.read(key)
+    }
+
+    fn read_before_write(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+        self.0.read_before_write(key)
+    }
+
+    fn write(&self, key: &str, data: &str) -> Result<(), std::io::Error> {
+        write(&self.0, key, data)
+    }
+
+    fn get_path(&self, key: &str) -> String {
+        self.0.get_path(key)
+    }
+
+    fn backend_name(&self) -> &'static str {
+        self.0.backend_name()
+    }
+
+    fn environment_report(&self, key: &str) -> EnvironmentReport {
+        self.0.environment_report(key)
+    }
+
+    fn is_persistent(&self) -> bool {
+        self.0.is_persistent()
+    }
+
+    fn secret_store(&self) -> Option<Arc<dyn crate::secrets::SecretStore>> {
+        self.0.secret_store()
+    }
+
+    fn available_space(&self) -> Option<u64> {
+        self.0.available_space()
+    }
+
+    // Registered storage is configured before it is shared.
+    fn set_file_mode(&mut self, _mode: u32) {}
+
+    fn set_durability(&mut self, _durability: Durability) {}
+
+    fn set_read_cache(&mut self, _enabled: bool) {}
+
+    fn set_backup_exclusion(&mut self, _excluded: bool) {}
+}
+
+/// Writes through the writer thread when it is enabled (see [`crate::writer`]).
+fn write(storage: &Arc<dyn Storage>, key: &str, data: &str) -> Result<(), std::io::Error> {
+    #[cfg(not(target_arch = "wasm32"))]
+    return crate::writer::write(storage, key, data);
+    #[cfg(target_arch = "wasm32")]
+    storage.write(key, data)
+}
+
+/// A loaded instance's place in the registry; unregisters on drop.
+#[derive(Debug)]
+pub struct Registration(u64);
+
+impl Registration {
+    /// Records the document a failed save meant to write, or clears it after a successful one.
+    pub fn set_pending(&self, document: Option<String>) {
+        if let Some(entry) = entries().get_mut(&self.0) {
+            entry.pending = document;
+        }
+    }
+
+    /// Whether a failed save is waiting to be flushed.
+    pub fn is_pending(&self) -> bool {
+        entries().get(&self.0).is_some_and(|entry| entry.pending.is_some())
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        entries().remove(&self.0);
+    }
+}
+
+/// Registers preferences stored under `storage_key`, returning the storage the instance should
+/// use from now on.
+pub fn register(storage: Box<dyn Storage>, storage_key: &str) -> (Box<dyn Storage>, Registration) {
+    let storage: Arc<dyn Storage> = Arc::from(storage);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let entry = Entry { storage: storage.clone(), storage_key: storage_key.to_string(), pending: None };
+    entries().insert(id, entry);
+    (Box::new(SharedStorage(storage)), Registration(id))
+}
+
+/// Error of [`flush_all()`]: the pending saves that still couldn't be written.
+#[derive(Debug)]
+pub struct FlushError {
+    /// Storage path (native) or key (WASM) of each failed write, with its error.
+    pub failures: Vec<(String, std::io::Error)>,
+}
+
+impl std::fmt::Display for FlushError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let failures: Vec<String> = self.failures.iter().map(|(path, e)| format!("{path}: {e}")).collect();
+        write!(f, "failed to flush preferences: {}", failures.join("; "))
+    }
+}
+
+impl std::error::Error for FlushError {}
+
+/// Writes the pending saves of all loaded preferences, returning how many were written.
+///
+/// Saves that fail again stay pending, so a later call retries them. The registry isn't
+/// locked while writing, so instances can be loaded, saved, and dropped meanwhile.
+pub fn flush_all() -> Result<usize, FlushError> {
+    let pending: Vec<(u64, Arc<dyn Storage>, String, String)> = entries()
+        .iter()
+        .filter_map(|(id, entry)| {
+            let document = entry.pending.clone()?;
+            Some((*id, entry.storage.clone(), entry.storage_key.clone(), document))
+        })
+        .collect();
+
+    let mut flushed = 0;
+    let mut failures = Vec::new();
+    for (id, storage, storage_key, document) in pending {
+        match write(&storage, &storage_key, &document) {
+            Ok(()) => {
+                // A save that failed meanwhile left a newer document pending; keep that one.
+                if let Some(entry) = entries().get_mut(&id).filter(|entry| entry.pending.as_ref() == Some(&document)) {
+                    entry.pending = None;
+                }
+                flushed += 1;
+            }
+            Err(e) => failures.push((storage.get_path(&storage_key), e)),
+        }
+    }
+    if failures.is_empty() {
+        Ok(flushed)
+    } else {
+        Err(FlushError { failures })
+    }
+}
@@ -0,0 +1,85 @@
+//! Retiring fields: carrying the stored value of a removed key over to the current fields.
+//!
+//! Deleting a field from the struct silently drops its stored value. To keep it, declare the
+//! old saved name as `removed` in the struct body, with a migration that receives the stored
+//! value and the freshly loaded preferences:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub theme: String = "light".to_string() => "theme",
+//!         removed "dark_mode" => |value, prefs| {
+//!             if value.as_bool() == Some(true) {
+//!                 prefs._theme = "dark".to_string();
+//!             }
+//!         },
+//!     },
+//!     "removed-doc-prefs"
+//! }
+//!
+//! let dir = tempfile::tempdir().unwrap();
+//! let path = dir.path().join("removed-doc-prefs.toml");
+//! std::fs::write(&path, "dark_mode = true\n").unwrap();
+//!
+//! let prefs = AppPrefs::load_with_error(dir.path().to_str().unwrap()).unwrap();
+//! assert_eq!(prefs.get_theme(), "dark");
+//!
+//! prefs.save().unwrap();
+//! assert!(!std::fs::read_to_string(&path).unwrap().contains("dark_mode"));
+//! ```
+//!
+//! A migration runs when a load finds its key, after the stored values are read and before
+//! `on_after_load` and `validate_struct`. Like in `on_after_load`, assign the generated
+//! `_<field>` fields directly; the setters would save. The key is dropped from the loaded
+//! values, so `strict` doesn't report it, and the next save leaves it out of the file (also
+//! with `preserve_formatting`), after which the migration no longer runs.
+//!
+//! Removed keys are the struct option `removed` underneath, which is how structs with
+//! `#[derive(EasyPrefs)]` declare them: `#[prefs(removed = &[RemovedField { key: "dark_mode",
+//! migrate: migrate_dark_mode }])]`.
+
+use toml::{Table, Value};
+
+/// Migration receiving the stored value of a removed key and the loaded preferences.
+pub type Migrate<T> = fn(Value, &mut T);
+
+/// A removed field's saved name and the migration for its stored value.
+pub struct RemovedField<T> {
+    /// Saved name of the removed field.
+    pub key: &'static str,
+    /// Called with the stored value by every load that finds `key`.
+    pub migrate: Migrate<T>,
+}
+
+impl<T> Clone for RemovedField<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for RemovedField<T> {}
+
+impl<T> std::fmt::Debug for RemovedField<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemovedField").field("key", &self.key).finish_non_exhaustive()
+    }
+}
+
+/// Takes the stored values of the `removed` keys out of `table`, paired with their migrations.
+#[doc(hidden)]
+pub fn take<T>(table: &mut Table, removed: &[RemovedField<T>]) -> Vec<(Migrate<T>, Value)> {
+    removed
+        .iter()
+        .filter_map(|field| Some((field.migrate, table.remove(field.key)?)))
+        .collect()
+}
+
+/// Runs the migrations returned by [`take`] on `prefs`, in declaration order.
+#[doc(hidden)]
+pub fn apply<T>(taken: Vec<(Migrate<T>, Value)>, prefs: &mut T) {
+    for (migrate, value) in taken {
+        migrate(value, prefs);
+    }
+}
@@ -0,0 +1,266 @@
+//! Serves preferences to admin panels over HTTP (feature `rest`).
+//!
+//! [`RestService::router`] builds an [`axum::Router`] for one preferences instance, to be
+//! nested into the service's own router or served on its own:
+//!
+//! ```text
+//! GET /                → 200, the document: every visible field as TOML
+//! PUT /                → 204, sets the fields in the TOML body
+//! GET /fields/{key}    → 200, the field's value
+//! PUT /fields/{key}    → 204, sets the field to the value in the body
+//! ```
+//!
+//! Single values are plain text in the syntax of [`crate::parse`]. A document sent with `PUT /`
+//! may be partial; fields it leaves out keep their values, as do fields it repeats unchanged,
+//! even read-only ones, so a client can send back an edited copy of `GET /`. Its fields are
+//! saved one by one; if one is rejected, those saved before it are restored.
+//!
+//! Every response carries an `ETag` of the current document. A `PUT` with `If-Match` fails
+//! with `412 Precondition Failed` if the document changed since, so two admins editing at once
+//! don't overwrite each other unseen. Other failures answer with a plain-text message and
+//! `400` (a document that doesn't parse), `403` (a read-only field), `404` (an unknown or
+//! hidden field), `422` (an invalid value), or `500` (a failed save).
+//!
+//! As for the IPC server, the host decides what is reachable with
+//! [`RestService::with_access`]; by default every field is read-only and secret fields are
+//! hidden. Secret values are always redacted. The router does no authentication; put it
+//! behind the service's own middleware.
+
+pub use crate::dynamic::Access;
+
+use crate::dynamic::{default_access, AccessPolicy, DynamicPrefs};
+use crate::field::FieldInfo;
+use crate::secrets::REDACTED;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, HeaderName, StatusCode};
+use axum::routing::get;
+use axum::Router;
+use std::sync::{Arc, Mutex};
+use toml::{Table, Value};
+
+/// A status, the document's `ETag`, and a plain-text body.
+type Reply = (StatusCode, [(HeaderName, String); 1], String);
+
+/// Serves one preferences instance over HTTP.
+///
+/// Cloning is cheap; clones share the preferences and access policy.
+pub struct RestService<P> {
+    prefs: Arc<Mutex<P>>,
+    fields: &'static [FieldInfo],
+    access: Arc<AccessPolicy>,
+}
+
+impl<P> Clone for RestService<P> {
+    fn clone(&self) -> Self {
+        Self {
+            prefs: self.prefs.clone(),
+            fields: self.fields,
+            access: self.access.clone(),
+        }
+    }
+}
+
+fn lock<T: ?Sized>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl<P: DynamicPrefs + Send + 'static> RestService<P> {
+    /// Creates a service for `prefs` with the default (read-only) access policy.
+    pub fn new(prefs: Arc<Mutex<P>>) -> Self {
+        let fields = lock(&prefs).fields();
+        Self {
+            prefs,
+            fields,
+            access: Arc::new(default_access),
+        }
+    }
+
+    /// Replaces the access policy, which is consulted for every request.
+    pub fn with_access(mut self, policy: impl Fn(&FieldInfo) -> Access + Send + Sync + 'static) -> Self {
+        self.access = Arc::new(policy);
+        self
+    }
+
+    /// Builds the router serving the document and field endpoints.
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/", get(get_document::<P>).put(put_document::<P>))
+            .route("/fields/{key}", get(get_field::<P>).put(put_field::<P>))
+            .with_state(self.clone())
+    }
+
+    fn get_document(&self) -> Reply {
+        let prefs = lock(&self.prefs);
+        let document = self.document(&prefs);
+        (StatusCode::OK, [(header::ETAG, etag(&document))], document)
+    }
+
+    fn get_field(&self, key: &str) -> Reply {
+        let prefs = lock(&self.prefs);
+        let result = self.field(key).map(|field| {
+            if field.options.secret {
+                REDACTED.to_string()
+            } else {
+                prefs.get_field_string(field.key).unwrap_or_default()
+            }
+        });
+        self.reply(&prefs, result.map(|value| (StatusCode::OK, value)))
+    }
+
+    fn put_field(&self, key: &str, headers: &HeaderMap, body: &str) -> Reply {
+        let mut prefs = lock(&self.prefs);
+        let result = self.precondition(&prefs, headers).and_then(|()| {
+            let field = self.writable(key)?;
+            prefs.set_field_from_str(field.key, body.trim()).map_err(|e| failure(&e))
+        });
+        self.reply(&prefs, result.map(|()| (StatusCode::NO_CONTENT, String::new())))
+    }
+
+    fn put_document(&self, headers: &HeaderMap, body: &str) -> Reply {
+        let mut prefs = lock(&self.prefs);
+        let result = self
+            .precondition(&prefs, headers)
+            .and_then(|()| self.changes(&prefs, body))
+            .and_then(|changes| apply(&mut *prefs, changes));
+        self.reply(&prefs, result.map(|()| (StatusCode::NO_CONTENT, String::new())))
+    }
+
+    /// The fields of a `PUT /` body whose values differ from the current ones.
+    fn changes(&self, prefs: &P, body: &str) -> Result<Vec<(&'static str, Value)>, (StatusCode, String)> {
+        let table: Table = body
+            .parse()
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("invalid document: {e}")))?;
+        let mut changes = Vec::new();
+        for (key, value) in table {
+            let field = self.field(&key)?;
+            let unchanged = if field.options.secret {
+                value.as_str() == Some(REDACTED)
+            } else {
+                prefs.get_value(field.key).as_ref() == Some(&value)
+            };
+            if !unchanged {
+                changes.push((self.writable(&key)?.key, value));
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Fails with `412` unless `If-Match` is absent or names the current document.
+    fn precondition(&self, prefs: &P, headers: &HeaderMap) -> Result<(), (StatusCode, String)> {
+        let Some(expected) = headers.get(header::IF_MATCH) else {
+            return Ok(());
+        };
+        let current = etag(&self.document(prefs));
+        let expected = expected.to_str().unwrap_or_default();
+        if expected.split(',').map(str::trim).any(|tag| tag == "*" || tag == current) {
+            Ok(())
+        } else {
+            Err((StatusCode::PRECONDITION_FAILED, "the preferences changed since they were read".to_string()))
+        }
+    }
+
+    /// Looks up a field, treating hidden fields as unknown.
+    fn field(&self, key: &str) -> Result<&'static FieldInfo, (StatusCode, String)> {
+        self.fields
+            .iter()
+            .find(|field| field.key == key && (self.access)(field) != Access::Hidden)
+            .ok_or_else(|| (StatusCode::NOT_FOUND, crate::dynamic::unknown_key(key).to_string()))
+    }
+
+    /// Looks up a field that may be set.
+    fn writable(&self, key: &str) -> Result<&'static FieldInfo, (StatusCode, String)> {
+        let field = self.field(key)?;
+        if (self.access)(field) != Access::ReadWrite {
+            return Err((StatusCode::FORBIDDEN, format!("'{}' is read-only", field.key)));
+        }
+        Ok(field)
+    }
+
+    /// The visible fields as a TOML document, with secrets redacted.
+    fn document(&self, prefs: &P) -> String {
+        let mut table = Table::new();
+        for field in self.fields.iter().filter(|field| (self.access)(field) != Access::Hidden) {
+            let value = if field.options.secret {
+                Some(Value::String(REDACTED.to_string()))
+            } else {
+                prefs.get_value(field.key)
+            };
+            if let Some(value) = value {
+                table.insert(field.key.to_string(), value);
+            }
+        }
+        toml::to_string(&table).unwrap_or_default()
+    }
+
+    /// Adds the `ETag` of the document after the request was handled.
+    fn reply(&self, prefs: &P, result: Result<(StatusCode, String), (StatusCode, String)>) -> Reply {
+        let (status, body) = result.unwrap_or_else(|error| error);
+        (status, [(header::ETAG, etag(&self.document(prefs)))], body)
+    }
+}
+
+/// Sets each changed field, restoring the ones set before a failure.
+fn apply<P: DynamicPrefs>(prefs: &mut P, changes: Vec<(&'static str, Value)>) -> Result<(), (StatusCode, String)> {
+    let mut applied = Vec::new();
+    for (key, value) in changes {
+        let previous = prefs.get_value(key);
+        if let Err(e) = prefs.set_value(key, value) {
+            for (key, previous) in applied.into_iter().rev() {
+                if let Some(previous) = previous {
+                    if let Err(e) = prefs.set_value(key, previous) {
+                        crate::scrub::log(prefs.fields(), None::<&()>, format_args!("Failed to restore '{key}': {e}"));
+                    }
+                }
+            }
+            return Err(failure(&e));
+        }
+        applied.push((key, previous));
+    }
+    Ok(())
+}
+
+/// Maps an error of a `DynamicPrefs` setter to a status.
+fn failure(e: &std::io::Error) -> (StatusCode, String) {
+    let status = match e.kind() {
+        std::io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+        std::io::ErrorKind::InvalidInput => StatusCode::UNPROCESSABLE_ENTITY,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, e.to_string())
+}
+
+/// A strong `ETag` for `document`: its 64-bit FNV-1a hash.
+fn etag(document: &str) -> String {
+    let hash = document.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("\"{hash:016x}\"")
+}
+
+async fn get_document<P: DynamicPrefs + Send + 'static>(State(service): State<RestService<P>>) -> Reply {
+    service.get_document()
+}
+
+async fn put_document<P: DynamicPrefs + Send + 'static>(
+    State(service): State<RestService<P>>,
+    headers: HeaderMap,
+    body: String,
+) -> Reply {
+    service.put_document(&headers, &body)
+}
+
+async fn get_field<P: DynamicPrefs + Send + 'static>(
+    State(service): State<RestService<P>>,
+    Path(key): Path<String>,
+) -> Reply {
+    service.get_field(&key)
+}
+
+async fn put_field<P: DynamicPrefs + Send + 'static>(
+    State(service): State<RestService<P>>,
+    Path(key): Path<String>,
+    headers: HeaderMap,
+    body: String,
+) -> Reply {
+    service.put_field(&key, &headers, &body)
+}
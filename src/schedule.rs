@@ -0,0 +1,179 @@
+//! Scheduled values: fields whose effective value depends on the time of day.
+//!
+//! A [`Scheduled`] field holds a default value and time windows that override it, e.g. "do
+//! not disturb between 22:00 and 07:00". The schedule itself is what gets saved and edited;
+//! `effective_<field>(now)` picks the value for a given local time:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//! use easy_prefs::schedule::{Scheduled, TimeOfDay};
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub do_not_disturb: Scheduled<bool> = Scheduled::new(false)
+//!             .with_window(TimeOfDay::new(22, 0), TimeOfDay::new(7, 0), true) => "do_not_disturb",
+//!     },
+//!     "schedule-doc-prefs"
+//! }
+//!
+//! let prefs = AppPrefs::load_testing();
+//! assert!(*prefs.effective_do_not_disturb(TimeOfDay::new(23, 30)));
+//! assert!(!*prefs.effective_do_not_disturb(TimeOfDay::new(12, 0)));
+//! ```
+//!
+//! ```toml
+//! [do_not_disturb]
+//! default = false
+//!
+//! [[do_not_disturb.windows]]
+//! start = "22:00"
+//! end = "07:00"
+//! value = true
+//! ```
+//!
+//! A window runs from `start` up to (not including) `end` and wraps past midnight when `end`
+//! is earlier than `start`. The first matching window wins. The library has no notion of
+//! time zones: pass the user's local time, e.g. from `chrono::Local::now()`.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// A time of day with minute precision, stored as `"HH:MM"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimeOfDay {
+    hour: u8,
+    minute: u8,
+}
+
+impl TimeOfDay {
+    /// Creates a time of day.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hour` is above 23 or `minute` above 59.
+    pub const fn new(hour: u8, minute: u8) -> Self {
+        assert!(hour < 24 && minute < 60, "time of day out of range");
+        Self { hour, minute }
+    }
+
+    /// The hour, 0 to 23.
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    /// The minute, 0 to 59.
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+impl FromStr for TimeOfDay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid time of day \"{s}\", expected HH:MM");
+        let (hour, minute) = s.split_once(':').ok_or_else(invalid)?;
+        let hour: u8 = hour.parse().map_err(|_| invalid())?;
+        let minute: u8 = minute.parse().map_err(|_| invalid())?;
+        if hour >= 24 || minute >= 60 {
+            return Err(invalid());
+        }
+        Ok(Self { hour, minute })
+    }
+}
+
+impl Serialize for TimeOfDay {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TimeOfDay {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// A value that applies from `start` until `end`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Window<T> {
+    pub start: TimeOfDay,
+    pub end: TimeOfDay,
+    pub value: T,
+}
+
+impl<T> Window<T> {
+    /// Whether `now` falls inside the window. A window whose start equals its end is empty.
+    pub fn contains(&self, now: TimeOfDay) -> bool {
+        if self.start <= self.end {
+            self.start <= now && now < self.end
+        } else {
+            now >= self.start || now < self.end
+        }
+    }
+}
+
+/// A default value overridden during time windows.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scheduled<T> {
+    /// The value outside all windows.
+    pub default: T,
+    /// Windows in priority order.
+    #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
+    pub windows: Vec<Window<T>>,
+}
+
+impl<T> Scheduled<T> {
+    /// A schedule without windows.
+    pub fn new(default: T) -> Self {
+        Self {
+            default,
+            windows: Vec::new(),
+        }
+    }
+
+    /// Adds a window after the existing ones.
+    pub fn with_window(mut self, start: TimeOfDay, end: TimeOfDay, value: T) -> Self {
+        self.windows.push(Window { start, end, value });
+        self
+    }
+
+    /// The value in effect at `now`.
+    pub fn effective(&self, now: TimeOfDay) -> &T {
+        self.windows
+            .iter()
+            .find(|window| window.contains(now))
+            .map_or(&self.default, |window| &window.value)
+    }
+}
+
+impl<T: Default> Default for Scheduled<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+/// Field types with an effective value per time of day; enables `effective_<field>()`.
+pub trait Schedule {
+    /// The type of the effective value.
+    type Value;
+
+    /// The value in effect at `now`.
+    fn effective(&self, now: TimeOfDay) -> &Self::Value;
+}
+
+impl<T> Schedule for Scheduled<T> {
+    type Value = T;
+
+    fn effective(&self, now: TimeOfDay) -> &T {
+        Scheduled::effective(self, now)
+    }
+}
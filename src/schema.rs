@@ -0,0 +1,139 @@
+//! Schema hash: a fingerprint of the stored layout, to catch unreviewed schema changes.
+//!
+//! `SCHEMA_HASH` on generated structs (and `PrefsData::SCHEMA_HASH`) is a stable 64-bit hash
+//! of every field's Rust name, saved key, and declared type, computed at compile time.
+//! Renaming a key or changing a type changes it; editing defaults or doc comments doesn't.
+//!
+//! Downstream apps can pin it in a test, or record the schema in a file checked into the
+//! repository with [`check_recorded`], which writes the file if it is missing and otherwise
+//! fails with a readable diff when the schema changed:
+//!
+//! ```rust
+//! use easy_prefs::{easy_prefs, schema};
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub theme: String = "light".to_string() => "theme",
+//!         pub font_size: i32 = 14 => "font_size",
+//!     },
+//!     "schema-doc-prefs"
+//! }
+//!
+//! # let tmp = tempfile::tempdir().unwrap();
+//! # let recorded = tmp.path().join("app-prefs.schema");
+//! // In a test: fails once someone changes the schema without updating the recorded file.
+//! schema::check_recorded(&recorded, AppPrefs::FIELDS).unwrap();
+//! assert_eq!(schema::hash(AppPrefs::FIELDS), AppPrefs::SCHEMA_HASH);
+//! ```
+//!
+//! Set `EASY_PREFS_UPDATE_SCHEMA=1` to accept a change and rewrite the file. Sections are
+//! covered by their type name only, so changes inside a section struct need their own check
+//! (via its `FIELDS`).
+
+use crate::field::FieldInfo;
+
+/// Environment variable that makes [`check_recorded`] rewrite a differing file.
+pub const UPDATE_VAR: &str = "EASY_PREFS_UPDATE_SCHEMA";
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// 64-bit FNV-1a hash of the fields' names, keys, and types (whitespace in types ignored).
+pub const fn hash(fields: &[FieldInfo]) -> u64 {
+    let mut hash = FNV_OFFSET;
+    let mut index = 0;
+    while index < fields.len() {
+        let field = &fields[index];
+        hash = hash_str(hash, field.name, false);
+        hash = hash_str(hash, field.key, false);
+        hash = hash_str(hash, field.type_name, true);
+        index += 1;
+    }
+    hash
+}
+
+/// Mixes `text` and a terminator into `hash`.
+const fn hash_str(mut hash: u64, text: &str, skip_whitespace: bool) -> u64 {
+    let bytes = text.as_bytes();
+    let mut index = 0;
+    while index < bytes.len() {
+        let byte = bytes[index];
+        if !(skip_whitespace && byte.is_ascii_whitespace()) {
+            hash = (hash ^ byte as u64).wrapping_mul(FNV_PRIME);
+        }
+        index += 1;
+    }
+    (hash ^ 0xff).wrapping_mul(FNV_PRIME)
+}
+
+/// Describes the schema as text: a hash line followed by one `key: Type (name)` line per field.
+pub fn describe(fields: &[FieldInfo]) -> String {
+    let mut text = format!("schema_hash = {:016x}\n", hash(fields));
+    for field in fields {
+        let type_name: String = field.type_name.split_whitespace().collect();
+        text.push_str(&format!("{}: {} ({})\n", field.key, type_name, field.name));
+    }
+    text
+}
+
+/// A recorded schema that no longer matches the fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaMismatch {
+    /// Lines of the recorded schema that are gone.
+    pub removed: Vec<String>,
+    /// Lines of the current schema that are new.
+    pub added: Vec<String>,
+}
+
+impl std::fmt::Display for SchemaMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "preferences schema changed (set {UPDATE_VAR}=1 to accept):")?;
+        for line in &self.removed {
+            write!(f, "\n- {line}")?;
+        }
+        for line in &self.added {
+            write!(f, "\n+ {line}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SchemaMismatch {}
+
+/// Compares the schema of `fields` with the one recorded at `path`.
+///
+/// Writes the file if it doesn't exist or [`UPDATE_VAR`] is set.
+///
+/// # Errors
+///
+/// Returns `InvalidData` wrapping a [`SchemaMismatch`] if the recorded schema differs, or the
+/// error of reading or writing the file.
+pub fn check_recorded(path: impl AsRef<std::path::Path>, fields: &[FieldInfo]) -> Result<(), std::io::Error> {
+    let path = path.as_ref();
+    let current = describe(fields);
+    let recorded = match std::fs::read_to_string(path) {
+        Ok(recorded) => recorded,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return std::fs::write(path, current),
+        Err(e) => return Err(e),
+    };
+    if recorded == current {
+        return Ok(());
+    }
+    if std::env::var_os(UPDATE_VAR).is_some_and(|value| value != "0") {
+        return std::fs::write(path, current);
+    }
+    let removed = recorded
+        .lines()
+        .filter(|line| !current.lines().any(|current| current == *line))
+        .map(str::to_string)
+        .collect();
+    let added = current
+        .lines()
+        .filter(|line| !recorded.lines().any(|recorded| recorded == *line))
+        .map(str::to_string)
+        .collect();
+    Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        SchemaMismatch { removed, added },
+    ))
+}
@@ -0,0 +1,123 @@
+//! Keeping secret values and home-directory paths out of the messages easy_prefs prints.
+//!
+//! Errors logged or panicked with by generated code can quote stored data: a TOML parse error
+//! shows the offending line, and a failed save may mention a value; warnings from storage
+//! backends and backup recovery name file paths. Before such a message is printed, [`scrub`]
+//! replaces the values of `(secret)` fields with [`REDACTED`](crate::secrets::REDACTED), both
+//! where the current value appears and in any `key = value` line of a secret field. With
+//! [`set_home_redaction`], the user's home directory is also shortened to `~`, so crash reports
+//! don't carry user names:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct ApiPrefs {
+//!         pub token: String = "hunter22".to_string() => "token" (secret),
+//!     },
+//!     "scrub-doc-prefs"
+//! }
+//!
+//! let prefs = ApiPrefs::load_testing();
+//! let message = "TOML parse error at line 1\n1 | token = \"hunter22\n  |         ^";
+//! let scrubbed = easy_prefs::scrub::scrub(message, ApiPrefs::FIELDS, Some(&prefs));
+//! assert!(!scrubbed.contains("hunter22"));
+//! ```
+//!
+//! Values shorter than four characters are too common to replace wherever they appear; they
+//! are only removed from `key = value` lines.
+
+use crate::field::FieldInfo;
+use crate::secrets::REDACTED;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use toml::{Table, Value};
+
+static HOME_REDACTION: AtomicBool = AtomicBool::new(false);
+
+/// Shortest secret value replaced wherever it appears in a message.
+const MIN_REPLACED_LEN: usize = 4;
+
+/// Turns shortening the home directory to `~` in printed messages on or off for the whole
+/// process.
+pub fn set_home_redaction(enabled: bool) {
+    HOME_REDACTION.store(enabled, Ordering::Release);
+}
+
+/// Returns whether printed messages shorten the home directory to `~`.
+pub fn is_home_redaction_enabled() -> bool {
+    HOME_REDACTION.load(Ordering::Acquire)
+}
+
+/// Removes the secret values of `fields` from `message`, taking the current values from
+/// `values` (a generated struct or `Prefs<T>` data) if given, and shortens the home directory
+/// if enabled.
+pub fn scrub<T: Serialize + ?Sized>(message: &str, fields: &[FieldInfo], values: Option<&T>) -> String {
+    let secrets: Vec<&FieldInfo> = fields.iter().filter(|field| field.options.secret).collect();
+    let mut message: String = message
+        .split_inclusive('\n')
+        .map(|line| redact_assignment(line, &secrets))
+        .collect();
+
+    let table = values.and_then(|values| Table::try_from(values).ok());
+    for field in &secrets {
+        // Generated structs serialize saved names; `Prefs<T>` data keeps Rust names.
+        let value = table.as_ref().and_then(|table| table.get(field.key).or_else(|| table.get(field.name)));
+        let text = match value {
+            Some(Value::String(s)) => s.clone(),
+            Some(value) => value.to_string(),
+            None => continue,
+        };
+        if text.len() >= MIN_REPLACED_LEN {
+            message = message.replace(&text, REDACTED);
+        }
+    }
+
+    if is_home_redaction_enabled() {
+        if let Some(home) = home_dir().filter(|home| home.len() > 1) {
+            message = message.replace(&home, "~");
+        }
+    }
+    message
+}
+
+/// Prints a scrubbed message to stderr.
+#[doc(hidden)]
+pub fn log<T: Serialize + ?Sized>(fields: &[FieldInfo], values: Option<&T>, message: std::fmt::Arguments) {
+    eprintln!("{}", scrub(&message.to_string(), fields, values));
+}
+
+/// Panics with a scrubbed message.
+#[doc(hidden)]
+#[track_caller]
+pub fn panic<T: Serialize + ?Sized>(fields: &[FieldInfo], values: Option<&T>, message: std::fmt::Arguments) -> ! {
+    panic!("{}", scrub(&message.to_string(), fields, values))
+}
+
+/// Replaces the value in a `key = value` line (possibly behind a TOML error's `N |` gutter or
+/// inside an inline table) of a secret field.
+fn redact_assignment(line: &str, secrets: &[&FieldInfo]) -> String {
+    for field in secrets {
+        let mut start = 0;
+        while let Some(offset) = line[start..].find(field.key) {
+            let at = start + offset;
+            let end = at + field.key.len();
+            let before = line[..at].chars().next_back();
+            let rest = line[end..].trim_start();
+            if matches!(before, None | Some(' ' | '\t' | '|' | '{' | ',' | '.')) && rest.starts_with('=') {
+                let value_start = line.len() - rest.len() + 1;
+                let newline = if line.ends_with('\n') { "\n" } else { "" };
+                return format!("{} {REDACTED}{newline}", &line[..value_start]);
+            }
+            start = end;
+        }
+    }
+    line.to_string()
+}
+
+fn home_dir() -> Option<String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    return directories::BaseDirs::new().map(|dirs| dirs.home_dir().to_string_lossy().into_owned());
+    #[cfg(target_arch = "wasm32")]
+    None
+}
@@ -0,0 +1,229 @@
+//! Storage for fields marked `(secret)`.
+//!
+//! Secret fields are always redacted from `Debug` and `to_string()` output. When a
+//! [`SecretStore`] is installed, their values are also kept out of the preferences file:
+//! `save()` moves them into the store and loading reads them back.
+//!
+//! With the `keyring` feature, the OS keyring (Keychain, Credential Manager, kernel keyutils)
+//! is installed by default. Without it, secret values stay in the preferences file unless an
+//! application installs its own store with [`set_secret_store`].
+//!
+//! Values are stored in their TOML representation (strings keep their quotes), under the
+//! service name `easy_prefs:<preferences path>` and the field's saved key as the account.
+//!
+//! Instances from `load_testing()` and `load_in_memory()` never reach the installed store:
+//! their storage brings its own [`MemorySecretStore`] (see [`Storage::secret_store`]), used
+//! whenever a store is installed, so secrets still leave the file as they would in production
+//! but are dropped with the instance.
+
+use crate::field::FieldInfo;
+use crate::storage::Storage;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use toml::{Table, Value};
+
+/// Placeholder shown instead of secret values.
+pub const REDACTED: &str = "<redacted>";
+
+/// A place to keep secret field values outside the preferences file.
+pub trait SecretStore: Send + Sync {
+    /// Reads a secret; `Ok(None)` if nothing is stored.
+    fn get(&self, service: &str, account: &str) -> Result<Option<String>, std::io::Error>;
+
+    /// Stores or replaces a secret.
+    fn set(&self, service: &str, account: &str, secret: &str) -> Result<(), std::io::Error>;
+
+    /// Removes a secret; succeeds if nothing was stored.
+    fn delete(&self, service: &str, account: &str) -> Result<(), std::io::Error>;
+}
+
+/// In-process secret store, useful for tests and ephemeral sessions.
+#[derive(Debug, Default)]
+pub struct MemorySecretStore {
+    secrets: Mutex<HashMap<(String, String), String>>,
+}
+
+impl MemorySecretStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SecretStore for MemorySecretStore {
+    fn get(&self, service: &str, account: &str) -> Result<Option<String>, std::io::Error> {
+        let secrets = self.secrets.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(secrets
+            .get(&(service.to_string(), account.to_string()))
+            .cloned())
+    }
+
+    fn set(&self, service: &str, account: &str, secret: &str) -> Result<(), std::io::Error> {
+        let mut secrets = self.secrets.lock().unwrap_or_else(|e| e.into_inner());
+        secrets.insert(
+            (service.to_string(), account.to_string()),
+            secret.to_string(),
+        );
+        Ok(())
+    }
+
+    fn delete(&self, service: &str, account: &str) -> Result<(), std::io::Error> {
+        let mut secrets = self.secrets.lock().unwrap_or_else(|e| e.into_inner());
+        secrets.remove(&(service.to_string(), account.to_string()));
+        Ok(())
+    }
+}
+
+/// Secret store backed by the OS keyring.
+#[cfg(all(feature = "keyring", not(target_arch = "wasm32")))]
+#[derive(Debug, Default)]
+pub struct KeyringSecretStore;
+
+#[cfg(all(feature = "keyring", not(target_arch = "wasm32")))]
+impl KeyringSecretStore {
+    fn entry(service: &str, account: &str) -> Result<keyring::Entry, std::io::Error> {
+        keyring::Entry::new(service, account).map_err(std::io::Error::other)
+    }
+}
+
+#[cfg(all(feature = "keyring", not(target_arch = "wasm32")))]
+impl SecretStore for KeyringSecretStore {
+    fn get(&self, service: &str, account: &str) -> Result<Option<String>, std::io::Error> {
+        match Self::entry(service, account)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(std::io::Error::other(e)),
+        }
+    }
+
+    fn set(&self, service: &str, account: &str, secret: &str) -> Result<(), std::io::Error> {
+        Self::entry(service, account)?
+            .set_password(secret)
+            .map_err(std::io::Error::other)
+    }
+
+    fn delete(&self, service: &str, account: &str) -> Result<(), std::io::Error> {
+        match Self::entry(service, account)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(std::io::Error::other(e)),
+        }
+    }
+}
+
+static STORE: Lazy<RwLock<Option<Arc<dyn SecretStore>>>> = Lazy::new(|| {
+    #[cfg(all(feature = "keyring", not(target_arch = "wasm32")))]
+    let store: Option<Arc<dyn SecretStore>> = Some(Arc::new(KeyringSecretStore));
+    #[cfg(not(all(feature = "keyring", not(target_arch = "wasm32"))))]
+    let store: Option<Arc<dyn SecretStore>> = None;
+    RwLock::new(store)
+});
+
+/// Installs the process-wide secret store used by all preferences structs.
+pub fn set_secret_store(store: impl SecretStore + 'static) {
+    *STORE.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(store));
+}
+
+/// Removes the secret store; secret values are then kept in the preferences file.
+pub fn clear_secret_store() {
+    *STORE.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+fn current_store() -> Option<Arc<dyn SecretStore>> {
+    STORE.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// The store for secrets of preferences in `storage`: its own store if it has one, the
+/// installed store otherwise; `None` if no store is installed.
+fn store_for(storage: &dyn Storage) -> Option<Arc<dyn SecretStore>> {
+    let installed = current_store()?;
+    Some(storage.secret_store().unwrap_or(installed))
+}
+
+fn service_name(location: &str) -> String {
+    format!("easy_prefs:{location}")
+}
+
+/// Replaces secret values in `table` with [`REDACTED`].
+pub fn redact(table: &mut Table, fields: &[FieldInfo]) {
+    for field in fields.iter().filter(|f| f.options.secret) {
+        if let Some(value) = table.get_mut(field.key) {
+            *value = Value::String(REDACTED.to_string());
+        }
+    }
+}
+
+/// Moves secret values out of `table` into the store for the preferences stored under
+/// `storage_key` in `storage`.
+///
+/// Does nothing if no store is installed. Secrets missing from the table (e.g. `None`) are
+/// deleted from the store.
+pub fn extract(table: &mut Table, fields: &[FieldInfo], storage: &dyn Storage, storage_key: &str) -> Result<(), std::io::Error> {
+    let Some(store) = store_for(storage) else {
+        return Ok(());
+    };
+    let service = service_name(&storage.get_path(storage_key));
+    for field in fields.iter().filter(|f| f.options.secret) {
+        match table.remove(field.key) {
+            Some(value) => store.set(&service, field.key, &value.to_string())?,
+            None => store.delete(&service, field.key)?,
+        }
+    }
+    Ok(())
+}
+
+/// Reads secret values for the preferences stored under `storage_key` in `storage` into
+/// `table`.
+///
+/// Values already present in the table (e.g. written before the field became secret) are
+/// only replaced if the store has a value; the next save moves them into the store.
+pub fn inject(table: &mut Table, fields: &[FieldInfo], storage: &dyn Storage, storage_key: &str) -> Result<(), std::io::Error> {
+    let Some(store) = store_for(storage) else {
+        return Ok(());
+    };
+    let service = service_name(&storage.get_path(storage_key));
+    for field in fields.iter().filter(|f| f.options.secret) {
+        if let Some(stored) = store.get(&service, field.key)? {
+            let value = crate::parse::parse_toml_value(&stored).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("stored secret for '{}' is not a valid TOML value", field.key),
+                )
+            })?;
+            table.insert(field.key.to_string(), value);
+        }
+    }
+    Ok(())
+}
+
+/// Deletes the stored secrets of `fields` for the preferences stored under `storage_key` in
+/// `storage`, e.g. when their file is removed.
+///
+/// Does nothing if no store is installed.
+pub fn delete(fields: &[FieldInfo], storage: &dyn Storage, storage_key: &str) -> Result<(), std::io::Error> {
+    let Some(store) = store_for(storage) else {
+        return Ok(());
+    };
+    let service = service_name(&storage.get_path(storage_key));
+    for field in fields.iter().filter(|f| f.options.secret) {
+        store.delete(&service, field.key)?;
+    }
+    Ok(())
+}
+
+/// Copies the stored secrets of `fields` for the preferences stored under `storage_key` from
+/// `from` to `to`, e.g. when the preferences move.
+///
+/// Does nothing if no store is installed.
+pub fn copy(fields: &[FieldInfo], from: &dyn Storage, to: &dyn Storage, storage_key: &str) -> Result<(), std::io::Error> {
+    let (Some(from_store), Some(to_store)) = (store_for(from), store_for(to)) else {
+        return Ok(());
+    };
+    let from_service = service_name(&from.get_path(storage_key));
+    let to_service = service_name(&to.get_path(storage_key));
+    for field in fields.iter().filter(|f| f.options.secret) {
+        if let Some(secret) = from_store.get(&from_service, field.key)? {
+            to_store.set(&to_service, field.key, &secret)?;
+        }
+    }
+    Ok(())
+}
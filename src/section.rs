@@ -0,0 +1,68 @@
+//! Sections: groups of fields saved as a TOML table.
+//!
+//! A field declared with a struct name and a braced field list instead of a type and default
+//! generates that struct and saves it as a table:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub theme: String = "light".to_string() => "theme",
+//!         pub window: WindowPrefs {
+//!             pub width: i32 = 800 => "width",
+//!             pub height: i32 = 600 => "height",
+//!         } => "window",
+//!     },
+//!     "section-doc-prefs"
+//! }
+//!
+//! let mut prefs = AppPrefs::load_testing();
+//! prefs.save_window(WindowPrefs { width: 1024, ..WindowPrefs::default() }).unwrap();
+//! assert_eq!(prefs.get_window().height, 600);
+//! ```
+//!
+//! ```toml
+//! theme = "light"
+//!
+//! [window]
+//! width = 1024
+//! height = 600
+//! ```
+//!
+//! The section struct derives `Debug`, `Clone`, `PartialEq`, and serde's traits, and keys
+//! missing from the stored table fall back to their defaults. The section as a whole is an
+//! ordinary field: it has `get_`/`save_`/`set_` methods and accepts field options. Options on
+//! the fields inside a section, and sections inside sections, are not supported.
+
+/// Generates the struct for a section.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __easy_prefs_section {
+    (
+        $vis:vis struct $section:ident {
+            $(
+                $(#[$inner:meta])*
+                $field_vis:vis $field:ident: $type:ty = $default:expr => $saved_name:literal,
+            )*
+        }
+    ) => {
+        #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+        #[serde(default)]
+        $vis struct $section {
+            $(
+                $(#[$inner])*
+                #[serde(rename = $saved_name)]
+                $field_vis $field: $type,
+            )*
+        }
+
+        impl Default for $section {
+            fn default() -> Self {
+                Self {
+                    $( $field: $default, )*
+                }
+            }
+        }
+    };
+}
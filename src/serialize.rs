@@ -0,0 +1,182 @@
+//! Naming the field a failed serialization came from.
+//!
+//! TOML can't express every value serde can (e.g. a `u64` above `i64::MAX` or a map with
+//! integer keys), and `toml`'s error doesn't say where it hit one. When serializing the values
+//! for a save fails, the save serializes the fields one by one to find the culprit and reports
+//! it as `SaveError::FieldSerializationFailed` with the field's saved name:
+//!
+//! ```rust
+//! use easy_prefs::{easy_prefs, SaveError};
+//!
+//! easy_prefs! {
+//!     pub struct CounterPrefs {
+//!         pub bytes_sent: u64 = 0 => "bytes_sent",
+//!     },
+//!     "serialize-doc-prefs"
+//! }
+//!
+//! let mut prefs = CounterPrefs::load_testing();
+//! match prefs.save_bytes_sent(u64::MAX) {
+//!     Err(SaveError::FieldSerializationFailed(field, _)) => assert_eq!(field, "bytes_sent"),
+//!     other => panic!("unexpected result: {other:?}"),
+//! }
+//! ```
+//!
+//! Fields of sections are reported by the section's saved name. If no single field fails on
+//! its own, the error stays `SaveError::SerializationFailed`.
+
+use crate::field::FieldInfo;
+use crate::SaveError;
+use serde::ser::{Impossible, Serialize, SerializeStruct, Serializer};
+use std::collections::BTreeMap;
+
+/// Turns a failed serialization of `values` into a `SaveError`, naming the offending field
+/// if one fails on its own.
+pub fn error<T: Serialize + ?Sized>(error: toml::ser::Error, values: &T, fields: &[FieldInfo]) -> SaveError {
+    let field = values.serialize(FieldProbe).ok().flatten();
+    // Generated structs rename fields to their saved names; `Prefs<T>` data keeps Rust names.
+    match field.and_then(|name| fields.iter().find(|field| field.key == name || field.name == name)) {
+        Some(field) => SaveError::FieldSerializationFailed(field.key, error),
+        None => SaveError::SerializationFailed(error),
+    }
+}
+
+/// Serializes the fields of a struct one by one, returning the serde name of the first field
+/// TOML can't express.
+struct FieldProbe;
+
+/// The fields of the struct being probed.
+struct FieldProbeStruct {
+    failed: Option<&'static str>,
+}
+
+fn not_a_struct() -> toml::ser::Error {
+    serde::ser::Error::custom("not a struct")
+}
+
+/// Rejects values that aren't structs.
+macro_rules! reject {
+    ($($method:ident($($arg:ty),*);)*) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<Self::Ok, Self::Error> {
+                Err(not_a_struct())
+            }
+        )*
+    };
+}
+
+impl Serializer for FieldProbe {
+    type Ok = Option<&'static str>;
+    type Error = toml::ser::Error;
+    type SerializeSeq = Impossible<Self::Ok, Self::Error>;
+    type SerializeTuple = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleStruct = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleVariant = Impossible<Self::Ok, Self::Error>;
+    type SerializeMap = Impossible<Self::Ok, Self::Error>;
+    type SerializeStruct = FieldProbeStruct;
+    type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
+
+    reject! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_char(char);
+        serialize_str(&str);
+        serialize_bytes(&[u8]);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_struct(&'static str);
+        serialize_unit_variant(&'static str, u32, &'static str);
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        Err(not_a_struct())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Err(not_a_struct())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Err(not_a_struct())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Err(not_a_struct())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(not_a_struct())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Err(not_a_struct())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct, Self::Error> {
+        Ok(FieldProbeStruct { failed: None })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(not_a_struct())
+    }
+}
+
+impl SerializeStruct for FieldProbeStruct {
+    type Ok = Option<&'static str>;
+    type Error = toml::ser::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error> {
+        // A one-entry table, so a `None` is skipped the way the struct's serialization skips it.
+        if self.failed.is_none() && toml::Table::try_from(BTreeMap::from([(key, value)])).is_err() {
+            self.failed = Some(key);
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.failed)
+    }
+}
@@ -0,0 +1,125 @@
+//! Keeping the settings of newer app versions (`compat_sidecar = true`).
+//!
+//! When two versions of an app share a preferences file (say, a beta next to the stable
+//! release), the older one doesn't know the fields the newer one added and drops them on its
+//! next save. With `compat_sidecar = true`, a save instead moves stored keys that are not
+//! fields of the struct to a sidecar (storage key `<key>.compat`), in a section named after
+//! the saving build's `SCHEMA_HASH`. Loading merges sidecar values of known fields back in
+//! when the document itself lacks them, so the newer version gets its settings back; its next
+//! save stores them in the document again and clears them from the sidecar.
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct StablePrefs {
+//!         pub theme: String = "light".to_string() => "theme",
+//!     },
+//!     "sidecar-doc-prefs",
+//!     compat_sidecar = true
+//! }
+//!
+//! let mut prefs = StablePrefs::load_testing();
+//! let path = prefs.get_preferences_file_path();
+//! // The beta build saved a field this build doesn't know.
+//! std::fs::write(&path, "theme = \"dark\"\nminimap = true\n").unwrap();
+//! prefs.reload().unwrap();
+//! prefs.save_theme("light".to_string()).unwrap();
+//!
+//! let sidecar = std::fs::read_to_string(format!("{path}.compat")).unwrap();
+//! assert!(sidecar.contains("minimap = true"));
+//! ```
+//!
+//! Both versions need the option. Values in the document always win over the sidecar.
+//! `preserve_formatting = true` keeps unknown keys in the document itself, which makes the
+//! sidecar unnecessary when all versions use it.
+
+use crate::field::FieldInfo;
+use crate::storage::Storage;
+use toml::Table;
+
+/// Storage key of the sidecar for the preferences under `storage_key`.
+pub fn sidecar_key(storage_key: &str) -> String {
+    format!("{storage_key}.compat")
+}
+
+/// Keys in stored documents that are not fields but belong to easy_prefs.
+const INTERNAL_KEYS: [&str; 2] = [crate::generation::KEY, crate::temporary::TABLE_KEY];
+
+fn is_field(fields: &[FieldInfo], key: &str) -> bool {
+    fields.iter().any(|field| field.key == key)
+}
+
+fn read(storage: &dyn Storage, storage_key: &str) -> Result<Table, std::io::Error> {
+    let key = sidecar_key(storage_key);
+    match storage.read(&key)? {
+        Some(contents) => contents.parse().map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid sidecar {}: {e}", storage.get_path(&key)),
+            )
+        }),
+        None => Ok(Table::new()),
+    }
+}
+
+/// Before a save: moves keys of `existing` (the stored document, parsed) that are not fields
+/// to this build's section of the sidecar, and drops sidecar values of fields, which the
+/// save stores in the document. Writes the sidecar only if it changed.
+pub fn stash(
+    storage: &dyn Storage,
+    storage_key: &str,
+    existing: Option<&Table>,
+    fields: &[FieldInfo],
+    schema_hash: u64,
+) -> Result<(), std::io::Error> {
+    let mut sidecar = read(storage, storage_key)?;
+    let before = sidecar.clone();
+
+    for (_, section) in sidecar.iter_mut() {
+        if let Some(section) = section.as_table_mut() {
+            section.retain(|key, _| !is_field(fields, key));
+        }
+    }
+    let unknown: Table = existing
+        .into_iter()
+        .flatten()
+        .filter(|(key, _)| !is_field(fields, key) && !INTERNAL_KEYS.contains(&key.as_str()))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    if !unknown.is_empty() {
+        let section = sidecar
+            .entry(format!("{schema_hash:016x}"))
+            .or_insert_with(|| Table::new().into());
+        if let Some(section) = section.as_table_mut() {
+            section.extend(unknown);
+        }
+    }
+    sidecar.retain(|_, section| section.as_table().is_none_or(|section| !section.is_empty()));
+
+    if sidecar == before {
+        return Ok(());
+    }
+    let contents = toml::to_string(&sidecar).map_err(std::io::Error::other)?;
+    storage.write(&sidecar_key(storage_key), &contents)
+}
+
+/// While loading: adds sidecar values of fields missing from `table`.
+///
+/// An unreadable sidecar is reported and skipped; the document itself still loads.
+pub fn merge(table: &mut Table, fields: &[FieldInfo], storage: &dyn Storage, storage_key: &str) {
+    let sidecar = match read(storage, storage_key) {
+        Ok(sidecar) => sidecar,
+        Err(e) => {
+            crate::scrub::log(fields, None::<&()>, format_args!("Ignoring preferences sidecar: {}", e));
+            return;
+        }
+    };
+    for section in sidecar.values().filter_map(|section| section.as_table()) {
+        for (key, value) in section {
+            if is_field(fields, key) && !table.contains_key(key) {
+                table.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! Load-time measurement (`load_budget = ...`).
+//!
+//! Every load and reload records how long reading, parsing, and validating the stored document
+//! took and how large it was, available as [`LoadStats`] through `load_stats()`. Preferences
+//! that grow over time (say, a `Vec` field collecting recent files) slow down app startup;
+//! with `load_budget`, each load taking longer than the budget prints a warning, so the
+//! regression shows up during development:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//! use std::time::Duration;
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub recent_files: Vec<String> = Vec::new() => "recent_files",
+//!     },
+//!     "stats-doc-prefs",
+//!     load_budget = Duration::from_millis(20)
+//! }
+//!
+//! let dir = tempfile::tempdir().unwrap();
+//! let mut prefs = AppPrefs::load_with_error(dir.path().to_str().unwrap()).unwrap();
+//! assert_eq!(prefs.load_stats().size, 0); // nothing stored yet
+//!
+//! prefs.save_recent_files(vec!["notes.txt".to_string()]).unwrap();
+//! prefs.reload().unwrap();
+//! let stored = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+//! assert_eq!(prefs.load_stats().size, stored.len());
+//! ```
+//!
+//! The duration includes falling back to backups, if that happens, but not waiting for the
+//! single-instance claim.
+
+use web_time::{Duration, Instant};
+
+/// How long the last load or reload took and how much it read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LoadStats {
+    /// Time spent reading, parsing, and validating the stored document.
+    pub duration: Duration,
+    /// Size of the stored document in bytes; 0 if nothing was stored.
+    pub size: usize,
+}
+
+/// Measures a load that began at `started` and read `size` bytes from `location`, warning if
+/// it took longer than `budget`.
+#[doc(hidden)]
+pub fn measure(started: Instant, size: usize, budget: Option<Duration>, location: &str) -> LoadStats {
+    let stats = LoadStats { duration: started.elapsed(), size };
+    if let Some(budget) = budget.filter(|budget| stats.duration > *budget) {
+        crate::scrub::log(
+            &[],
+            None::<&()>,
+            format_args!(
+                "Loading preferences from {} took {:?} ({} bytes), over the budget of {:?}",
+                location, stats.duration, size, budget
+            ),
+        );
+    }
+    stats
+}
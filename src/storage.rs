@@ -1,78 +1,1701 @@
+use crate::secrets::{MemorySecretStore, SecretStore};
 use std::fmt::Debug;
+use std::sync::Arc;
 
 /// Storage abstraction trait for cross-platform preferences storage
 pub trait Storage: Send + Sync + Debug {
     /// Read data from storage
     fn read(&self, key: &str) -> Result<Option<String>, std::io::Error>;
 
+    /// Reads the stored data ahead of overwriting it (the stored generation, formatting, and
+    /// backups come from it). Backends guarding writes with versions (see `http`) keep the
+    /// version the caller loaded instead of adopting the one read here.
+    fn read_before_write(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+        self.read(key)
+    }
+
     /// Write data to storage
     fn write(&self, key: &str, data: &str) -> Result<(), std::io::Error>;
 
-    /// Get the full path/key for display purposes
-    fn get_path(&self, key: &str) -> String;
+    /// Get the full path/key for display purposes
+    fn get_path(&self, key: &str) -> String;
+
+    /// Short, stable name of the backend (e.g. `"file"`, `"localStorage"`)
+    fn backend_name(&self) -> &'static str {
+        "custom"
+    }
+
+    /// Describe the backend and any environment quirks for diagnostics
+    fn environment_report(&self, key: &str) -> EnvironmentReport {
+        EnvironmentReport::new(self.backend_name(), self.get_path(key))
+    }
+
+    /// Whether written data outlives the process (false for in-memory storage)
+    fn is_persistent(&self) -> bool {
+        true
+    }
+
+    /// Store for the secret fields of preferences kept here, replacing the installed one (see
+    /// [`crate::secrets`]); `None` uses the installed store
+    fn secret_store(&self) -> Option<Arc<dyn SecretStore>> {
+        None
+    }
+
+    /// Bytes available for new data, if the backend can tell
+    fn available_space(&self) -> Option<u64> {
+        None
+    }
+
+    /// Sets the Unix permission bits of files written from now on (ignored by backends
+    /// without files)
+    fn set_file_mode(&mut self, _mode: u32) {}
+
+    /// Sets how hard writes try to reach the disk (ignored by backends without files)
+    fn set_durability(&mut self, _durability: Durability) {}
+
+    /// Enables caching of read contents, re-reading a file only when its modification time or
+    /// size changed (ignored by backends without files)
+    fn set_read_cache(&mut self, _enabled: bool) {}
+
+    /// Marks files written from now on as excluded from OS backups (Time Machine and iCloud
+    /// on Apple platforms; ignored by backends without files)
+    fn set_backup_exclusion(&mut self, _excluded: bool) {}
+}
+
+/// How hard file writes try to reach the disk before returning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Durability {
+    /// Rely on the atomic rename alone: the file is never half-written, but after a power loss
+    /// the last saves may be missing (the OS flushes within seconds). Cheapest on battery.
+    #[default]
+    Fast,
+    /// Also flush the new file to disk before the rename and the directory entry after it,
+    /// so a save that returned survives a crash. Costs a disk flush per save.
+    Fsync,
+}
+
+/// How `/` in a storage key (e.g. from a filename like `"profiles/default"`) is stored.
+///
+/// Keys are relative, and `/` is their only separator: segments can't be empty, `.`, or `..`,
+/// and keys can't contain `\`, `:`, or NUL, whatever the backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeySeparators {
+    /// `/` nests: files go to subdirectories (created on the first write); localStorage,
+    /// extension storage, User Defaults, and SQLite keep it in the item key, and HTTP storage
+    /// in the URL path.
+    #[default]
+    Nested,
+    /// `/` is replaced by `_`, keeping every key at the top level.
+    Flatten,
+    /// Keys containing `/` fail with `ErrorKind::InvalidInput`.
+    Reject,
+}
+
+/// Checks `key` against the rules of [`KeySeparators`] and applies `separators` to it.
+///
+/// # Errors
+///
+/// Returns `ErrorKind::InvalidInput` for a key breaking the rules, or containing `/` with
+/// `KeySeparators::Reject`.
+pub fn resolve_key(key: &str, separators: KeySeparators) -> Result<String, std::io::Error> {
+    let invalid = |reason: &str| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid storage key '{key}': {reason}"))
+    };
+    if key.contains(['\\', ':', '\0']) {
+        return Err(invalid("only '/' may separate segments"));
+    }
+    if key.split('/').any(|segment| matches!(segment, "" | "." | "..")) {
+        return Err(invalid("segments must not be empty, '.', or '..'"));
+    }
+    match separators {
+        KeySeparators::Nested => Ok(key.to_string()),
+        KeySeparators::Flatten => Ok(key.replace('/', "_")),
+        KeySeparators::Reject if key.contains('/') => Err(invalid("'/' is not allowed")),
+        KeySeparators::Reject => Ok(key.to_string()),
+    }
+}
+
+/// Storage passing every key through [`resolve_key`] before handing it to the backend, so
+/// all backends check keys alike. Structs wrap their storage in it with their
+/// `key_separators` option.
+#[derive(Debug)]
+pub struct ResolvedKeys {
+    inner: Box<dyn Storage>,
+    separators: KeySeparators,
+}
+
+impl ResolvedKeys {
+    /// Wraps `inner`, applying `separators` to its keys.
+    pub fn new(inner: Box<dyn Storage>, separators: KeySeparators) -> Self {
+        Self { inner, separators }
+    }
+
+    fn key(&self, key: &str) -> Result<String, std::io::Error> {
+        resolve_key(key, self.separators)
+    }
+}
+
+impl Storage for ResolvedKeys {
+    fn read(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+        self.inner.read(&self.key(key)?)
+    }
+
+    fn read_before_write(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+        self.inner.read_before_write(&self.key(key)?)
+    }
+
+    fn write(&self, key: &str, data: &str) -> Result<(), std::io::Error> {
+        self.inner.write(&self.key(key)?, data)
+    }
+
+    /// Shows invalid keys as given; reading or writing them fails.
+    fn get_path(&self, key: &str) -> String {
+        self.inner.get_path(&self.key(key).unwrap_or_else(|_| key.to_string()))
+    }
+
+    fn backend_name(&self) -> &'static str {
+        self.inner.backend_name()
+    }
+
+    fn environment_report(&self, key: &str) -> EnvironmentReport {
+        self.inner.environment_report(&self.key(key).unwrap_or_else(|_| key.to_string()))
+    }
+
+    fn is_persistent(&self) -> bool {
+        self.inner.is_persistent()
+    }
+
+    fn secret_store(&self) -> Option<Arc<dyn SecretStore>> {
+        self.inner.secret_store()
+    }
+
+    fn available_space(&self) -> Option<u64> {
+        self.inner.available_space()
+    }
+
+    fn set_file_mode(&mut self, mode: u32) {
+        self.inner.set_file_mode(mode);
+    }
+
+    fn set_durability(&mut self, durability: Durability) {
+        self.inner.set_durability(durability);
+    }
+
+    fn set_read_cache(&mut self, enabled: bool) {
+        self.inner.set_read_cache(enabled);
+    }
+
+    fn set_backup_exclusion(&mut self, excluded: bool) {
+        self.inner.set_backup_exclusion(excluded);
+    }
+}
+
+/// Storage keeping secret fields in its own [`MemorySecretStore`], dropped with it, instead of
+/// the installed secret store. `load_testing()` wraps its storage in it, so tests never reach
+/// the OS keyring.
+#[derive(Debug)]
+pub struct PrivateSecrets {
+    inner: Box<dyn Storage>,
+    secrets: Arc<MemorySecretStore>,
+}
+
+impl PrivateSecrets {
+    /// Wraps `inner` with an empty secret store.
+    pub fn new(inner: Box<dyn Storage>) -> Self {
+        Self { inner, secrets: Arc::default() }
+    }
+}
+
+impl Storage for PrivateSecrets {
+    fn read(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+        self.inner.read(key)
+    }
+
+    fn read_before_write(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+        self.inner.read_before_write(key)
+    }
+
+    fn write(&self, key: &str, data: &str) -> Result<(), std::io::Error> {
+        self.inner.write(key, data)
+    }
+
+    fn get_path(&self, key: &str) -> String {
+        self.inner.get_path(key)
+    }
+
+    fn backend_name(&self) -> &'static str {
+        self.inner.backend_name()
+    }
+
+    fn environment_report(&self, key: &str) -> EnvironmentReport {
+        self.inner.environment_report(key)
+    }
+
+    fn is_persistent(&self) -> bool {
+        self.inner.is_persistent()
+    }
+
+    fn secret_store(&self) -> Option<Arc<dyn SecretStore>> {
+        Some(self.secrets.clone())
+    }
+
+    fn available_space(&self) -> Option<u64> {
+        self.inner.available_space()
+    }
+
+    fn set_file_mode(&mut self, mode: u32) {
+        self.inner.set_file_mode(mode);
+    }
+
+    fn set_durability(&mut self, durability: Durability) {
+        self.inner.set_durability(durability);
+    }
+
+    fn set_read_cache(&mut self, enabled: bool) {
+        self.inner.set_read_cache(enabled);
+    }
+
+    fn set_backup_exclusion(&mut self, excluded: bool) {
+        self.inner.set_backup_exclusion(excluded);
+    }
+}
+
+/// Storage that keeps everything in memory and loses it when dropped.
+///
+/// Used on WASM when localStorage is disabled (private browsing, enterprise policies), so the
+/// app keeps working for the session; also handy as a test double. Secret fields stay in
+/// memory too, in a store of its own rather than the installed one.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    items: std::sync::Mutex<std::collections::HashMap<String, String>>,
+    secrets: Arc<MemorySecretStore>,
+    unavailable: Option<String>,
+}
+
+impl MemoryStorage {
+    /// Creates an empty in-memory storage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty in-memory storage standing in for a backend that is unavailable for
+    /// `reason`, which its environment report mentions.
+    pub fn unavailable(reason: String) -> Self {
+        Self {
+            unavailable: Some(reason),
+            ..Self::default()
+        }
+    }
+
+    fn items(&self) -> std::sync::MutexGuard<'_, std::collections::HashMap<String, String>> {
+        self.items.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+        Ok(self.items().get(key).cloned())
+    }
+
+    fn write(&self, key: &str, data: &str) -> Result<(), std::io::Error> {
+        self.items().insert(key.to_string(), data.to_string());
+        Ok(())
+    }
+
+    fn get_path(&self, key: &str) -> String {
+        format!("memory::{key}")
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn environment_report(&self, key: &str) -> EnvironmentReport {
+        let mut report = EnvironmentReport::new(self.backend_name(), self.get_path(key));
+        if let Some(reason) = &self.unavailable {
+            report
+                .quirks
+                .push(EnvironmentQuirk::StorageUnavailable(reason.clone()));
+        }
+        report
+    }
+
+    fn is_persistent(&self) -> bool {
+        false
+    }
+
+    fn secret_store(&self) -> Option<Arc<dyn SecretStore>> {
+        Some(self.secrets.clone())
+    }
+}
+
+/// Environment quirks detected by a storage backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvironmentQuirk {
+    /// The process runs inside an app sandbox or container (name of the sandbox).
+    Sandboxed(String),
+    /// The storage location exists but cannot be written to.
+    ReadOnly,
+    /// The storage directory does not exist yet (it is created on the first write).
+    DirectoryMissing,
+    /// The configured directory path points at something that is not a directory.
+    NotADirectory,
+    /// The backing store has a small fixed quota; `used_bytes` is the current usage.
+    QuotaLimited { used_bytes: u64 },
+    /// The backing store cannot be accessed at all.
+    StorageUnavailable(String),
+}
+
+impl std::fmt::Display for EnvironmentQuirk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sandboxed(name) => write!(f, "running in sandbox: {name}"),
+            Self::ReadOnly => write!(f, "storage location is read-only"),
+            Self::DirectoryMissing => write!(f, "storage directory does not exist yet"),
+            Self::NotADirectory => write!(f, "storage path is not a directory"),
+            Self::QuotaLimited { used_bytes } => {
+                write!(f, "storage has a limited quota ({used_bytes} bytes used)")
+            }
+            Self::StorageUnavailable(reason) => write!(f, "storage unavailable: {reason}"),
+        }
+    }
+}
+
+/// Summary of where and how preferences are stored, meant for bug reports.
+///
+/// The `Display` implementation renders a short multi-line text block that
+/// users can paste into a support ticket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvironmentReport {
+    /// Version of easy_prefs that produced the report.
+    pub crate_version: &'static str,
+    /// Name of the selected storage backend.
+    pub backend: &'static str,
+    /// Resolved path (native) or key (WASM) of the preferences data.
+    pub location: String,
+    /// Target operating system / platform.
+    pub platform: &'static str,
+    /// Quirks detected in the environment.
+    pub quirks: Vec<EnvironmentQuirk>,
+}
+
+impl EnvironmentReport {
+    /// Creates a report without any detected quirks.
+    pub fn new(backend: &'static str, location: String) -> Self {
+        Self {
+            crate_version: env!("CARGO_PKG_VERSION"),
+            backend,
+            location,
+            platform: if cfg!(target_arch = "wasm32") {
+                "wasm32"
+            } else {
+                std::env::consts::OS
+            },
+            quirks: Vec::new(),
+        }
+    }
+}
+
+impl std::fmt::Display for EnvironmentReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "easy_prefs {}", self.crate_version)?;
+        writeln!(f, "backend: {}", self.backend)?;
+        writeln!(f, "location: {}", self.location)?;
+        write!(f, "platform: {}", self.platform)?;
+        if self.quirks.is_empty() {
+            write!(f, "\nquirks: none")?;
+        }
+        for quirk in &self.quirks {
+            write!(f, "\nquirk: {quirk}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Detects well-known sandboxes from the process environment.
+#[cfg(not(target_arch = "wasm32"))]
+fn detect_sandbox() -> Option<String> {
+    const MARKERS: [(&str, &str); 4] = [
+        ("APP_SANDBOX_CONTAINER_ID", "macOS App Sandbox"),
+        ("FLATPAK_ID", "Flatpak"),
+        ("SNAP", "Snap"),
+        ("container", "container"),
+    ];
+    MARKERS
+        .iter()
+        .find(|(var, _)| std::env::var_os(var).is_some())
+        .map(|(_, name)| name.to_string())
+}
+
+/// Appends `bytes` as lowercase hex, for binary data in string-based storage.
+pub(crate) fn encode_hex(bytes: &[u8], out: &mut String) {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    for byte in bytes {
+        out.push(DIGITS[(byte >> 4) as usize] as char);
+        out.push(DIGITS[(byte & 0x0f) as usize] as char);
+    }
+}
+
+pub(crate) fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// 64-bit FNV-1a of `text`, as 16 hex digits.
+pub(crate) fn content_hash(text: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in text.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    format!("{hash:016x}")
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod native {
+    use super::{EnvironmentQuirk, EnvironmentReport, Storage};
+    use std::collections::HashMap;
+    use std::io::{Read, Write};
+    use std::path::{Path, PathBuf};
+    use std::sync::{Mutex, MutexGuard};
+    use std::time::SystemTime;
+
+    #[derive(Debug)]
+    pub struct FileStorage {
+        base_dir: PathBuf,
+        file_mode: Option<u32>,
+        durability: super::Durability,
+        read_cache: Option<Mutex<HashMap<String, CachedRead>>>,
+        exclude_from_backup: bool,
+    }
+
+    /// Contents of a file as last read or written, with the metadata that tells whether they
+    /// are still current.
+    #[derive(Debug)]
+    struct CachedRead {
+        modified: SystemTime,
+        len: u64,
+        contents: String,
+    }
+
+    impl FileStorage {
+        /// Creates a storage for `directory`. Nothing is touched on disk until the first write,
+        /// which creates the directory if needed.
+        pub fn new(directory: &str) -> Self {
+            Self {
+                base_dir: PathBuf::from(directory),
+                file_mode: None,
+                durability: super::Durability::Fast,
+                read_cache: None,
+                exclude_from_backup: false,
+            }
+        }
+
+        fn cache(&self) -> Option<MutexGuard<'_, HashMap<String, CachedRead>>> {
+            let cache = self.read_cache.as_ref()?;
+            Some(cache.lock().unwrap_or_else(|e| e.into_inner()))
+        }
+
+        /// Records `contents` as current for `key`, or forgets the entry if the file can't
+        /// be examined.
+        fn update_cache(&self, key: &str, path: &Path, contents: &str) {
+            let Some(mut cache) = self.cache() else {
+                return;
+            };
+            match std::fs::metadata(path).and_then(|metadata| Ok((metadata.modified()?, metadata.len()))) {
+                Ok((modified, len)) => {
+                    let entry = CachedRead { modified, len, contents: contents.to_string() };
+                    cache.insert(key.to_string(), entry);
+                }
+                Err(_) => {
+                    cache.remove(key);
+                }
+            }
+        }
+
+        /// Path of the file for `key`, which must follow the rules of `KeySeparators` so it
+        /// can't point outside the directory; `/` leads to subdirectories.
+        fn path(&self, key: &str) -> Result<PathBuf, std::io::Error> {
+            super::resolve_key(key, super::KeySeparators::Nested)?;
+            Ok(self.base_dir.join(key))
+        }
+
+        /// Fails with `ErrorKind::NotADirectory` if the directory, or the closest ancestor
+        /// that exists, is a file, instead of the confusing errors reading and writing
+        /// through it would give.
+        fn check_directory(&self) -> Result<(), std::io::Error> {
+            for path in self.base_dir.ancestors() {
+                if let Ok(metadata) = std::fs::metadata(path) {
+                    if metadata.is_dir() {
+                        return Ok(());
+                    }
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::NotADirectory,
+                        format!("'{}' is not a directory", path.display()),
+                    ));
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Binary property list holding the string `com.apple.backupd`: the value Foundation
+    /// stores for `NSURLIsExcludedFromBackupKey`.
+    #[cfg(target_vendor = "apple")]
+    const APPLE_BACKUP_EXCLUDE_PLIST: &[u8] = &[
+        b'b', b'p', b'l', b'i', b's', b't', b'0', b'0', 0x5F, 0x10, 0x11, b'c', b'o', b'm', b'.', b'a', b'p', b'p',
+        b'l', b'e', b'.', b'b', b'a', b'c', b'k', b'u', b'p', b'd', 0x08, 0, 0, 0, 0, 0, 0, 1, 1, 0, 0, 0, 0, 0,
+        0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x1C,
+    ];
+
+    /// Sets the extended attribute that keeps `file` out of backups: the one behind
+    /// `NSURLIsExcludedFromBackupKey` on Apple platforms, freedesktop's `user.xdg.robots.backup`
+    /// on Linux. Other platforms have no such attribute.
+    #[cfg(target_vendor = "apple")]
+    fn exclude_from_backup(file: &std::fs::File) -> Result<(), std::io::Error> {
+        use rustix::fs::{fsetxattr, XattrFlags};
+        let name = "com.apple.metadata:com_apple_backup_excludeItem";
+        Ok(fsetxattr(file, name, APPLE_BACKUP_EXCLUDE_PLIST, XattrFlags::empty())?)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn exclude_from_backup(file: &std::fs::File) -> Result<(), std::io::Error> {
+        use rustix::fs::{fsetxattr, XattrFlags};
+        Ok(fsetxattr(file, "user.xdg.robots.backup", b"false", XattrFlags::empty())?)
+    }
+
+    #[cfg(not(any(target_vendor = "apple", target_os = "linux")))]
+    fn exclude_from_backup(_file: &std::fs::File) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    impl Storage for FileStorage {
+        fn read(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+            self.check_directory()?;
+            let path = self.path(key)?;
+
+            if !path.exists() {
+                if let Some(mut cache) = self.cache() {
+                    cache.remove(key);
+                }
+                return Ok(None);
+            }
+
+            if let Some(cache) = self.cache() {
+                let metadata = std::fs::metadata(&path)?;
+                let cached = cache.get(key).filter(|cached| {
+                    metadata.len() == cached.len && metadata.modified().is_ok_and(|modified| modified == cached.modified)
+                });
+                if let Some(cached) = cached {
+                    return Ok(Some(cached.contents.clone()));
+                }
+            }
+
+            let mut file = std::fs::File::open(&path)?;
+            let mut contents = String::new();
+            file.read_to_string(&mut contents)?;
+            self.update_cache(key, &path, &contents);
+            Ok(Some(contents))
+        }
+
+        fn write(&self, key: &str, data: &str) -> Result<(), std::io::Error> {
+            self.check_directory()?;
+            let path = self.path(key)?;
+
+            // Ensure parent directory exists
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            // Write to temporary file first
+            let parent_dir = path.parent().unwrap_or(&self.base_dir);
+            let mut tmp_file = tempfile::NamedTempFile::new_in(parent_dir)?;
+            // Set the mode before any data is written; the rename keeps it.
+            #[cfg(unix)]
+            if let Some(mode) = self.file_mode {
+                use std::os::unix::fs::PermissionsExt;
+                tmp_file.as_file().set_permissions(std::fs::Permissions::from_mode(mode))?;
+            }
+            if self.exclude_from_backup {
+                if let Err(e) = exclude_from_backup(tmp_file.as_file()) {
+                    crate::scrub::log(&[], None::<&()>, format_args!("Failed to exclude {} from backups: {}", path.display(), e));
+                }
+            }
+            tmp_file.write_all(data.as_bytes())?;
+            if self.durability == super::Durability::Fsync {
+                tmp_file.as_file().sync_all()?;
+            }
+
+            // Atomically move temp file to final location
+            tmp_file.persist(&path).map_err(|e| e.error)?;
+
+            // The rename lives in the directory; flush it too. Windows can't open directories
+            // as files, and its rename is already written through.
+            #[cfg(unix)]
+            if self.durability == super::Durability::Fsync {
+                std::fs::File::open(parent_dir)?.sync_all()?;
+            }
+
+            self.update_cache(key, &path, data);
+            Ok(())
+        }
+
+        fn get_path(&self, key: &str) -> String {
+            self.base_dir.join(key).display().to_string()
+        }
+
+        fn backend_name(&self) -> &'static str {
+            "file"
+        }
+
+        fn set_file_mode(&mut self, mode: u32) {
+            self.file_mode = Some(mode);
+        }
+
+        fn set_durability(&mut self, durability: super::Durability) {
+            self.durability = durability;
+        }
+
+        fn set_read_cache(&mut self, enabled: bool) {
+            self.read_cache = enabled.then(Default::default);
+        }
+
+        fn set_backup_exclusion(&mut self, excluded: bool) {
+            self.exclude_from_backup = excluded;
+        }
+
+        #[cfg(unix)]
+        fn available_space(&self) -> Option<u64> {
+            // Measure the closest existing ancestor: that's where the directory will be created.
+            let directory = self
+                .base_dir
+                .ancestors()
+                .find(|path| path.is_dir())
+                .unwrap_or_else(|| std::path::Path::new("."));
+            let stats = rustix::fs::statvfs(directory).ok()?;
+            Some(stats.f_bavail.saturating_mul(stats.f_frsize))
+        }
+
+        fn environment_report(&self, key: &str) -> EnvironmentReport {
+            let mut report = EnvironmentReport::new(self.backend_name(), self.get_path(key));
+
+            if let Some(sandbox) = super::detect_sandbox() {
+                report.quirks.push(EnvironmentQuirk::Sandboxed(sandbox));
+            }
+
+            match std::fs::metadata(&self.base_dir) {
+                Ok(metadata) if !metadata.is_dir() => {
+                    report.quirks.push(EnvironmentQuirk::NotADirectory);
+                }
+                Ok(_) => {
+                    // Permission bits don't tell the whole story (read-only mounts, ACLs),
+                    // so probe with a throwaway file that is removed immediately.
+                    if tempfile::tempfile_in(&self.base_dir).is_err() {
+                        report.quirks.push(EnvironmentQuirk::ReadOnly);
+                    }
+                }
+                Err(_) => report.quirks.push(EnvironmentQuirk::DirectoryMissing),
+            }
+
+            report
+        }
+    }
+}
+
+#[cfg(all(feature = "encryption", not(target_arch = "wasm32")))]
+pub mod encrypted {
+    use super::native::FileStorage;
+    use super::{decode_hex, encode_hex, EnvironmentReport, Storage};
+    use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+    use aes_gcm::{Aes256Gcm, Key, Nonce};
+
+    /// Marker prepended to every encrypted document so the format can evolve.
+    const HEADER: &str = "easy_prefs:aes-256-gcm:v1:";
+    const NONCE_LEN: usize = 12;
+
+    /// File storage that encrypts documents at rest with AES-256-GCM.
+    ///
+    /// The storage key is bound to each document as associated data, so a file copied to
+    /// another key fails to decrypt instead of loading someone else's preferences.
+    pub struct EncryptedFileStorage {
+        inner: FileStorage,
+        cipher: Aes256Gcm,
+    }
+
+    impl EncryptedFileStorage {
+        pub fn new(directory: &str, key: &[u8; 32]) -> Self {
+            Self {
+                inner: FileStorage::new(directory),
+                cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+            }
+        }
+
+        fn invalid_data(message: &str) -> std::io::Error {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+        }
+    }
+
+    // Never print key material.
+    impl std::fmt::Debug for EncryptedFileStorage {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("EncryptedFileStorage")
+                .field("inner", &self.inner)
+                .finish_non_exhaustive()
+        }
+    }
+
+    impl Storage for EncryptedFileStorage {
+        fn read(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+            let Some(contents) = self.inner.read(key)? else {
+                return Ok(None);
+            };
+
+            let encoded = contents
+                .trim()
+                .strip_prefix(HEADER)
+                .ok_or_else(|| Self::invalid_data("preferences file is not encrypted"))?;
+            let bytes = decode_hex(encoded)
+                .filter(|bytes| bytes.len() > NONCE_LEN)
+                .ok_or_else(|| Self::invalid_data("encrypted preferences are malformed"))?;
+
+            let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+            let payload = Payload {
+                msg: ciphertext,
+                aad: key.as_bytes(),
+            };
+            let plaintext = self
+                .cipher
+                .decrypt(Nonce::from_slice(nonce), payload)
+                .map_err(|_| {
+                    Self::invalid_data("failed to decrypt preferences (wrong key or corrupted data)")
+                })?;
+
+            String::from_utf8(plaintext)
+                .map(Some)
+                .map_err(|_| Self::invalid_data("decrypted preferences are not valid UTF-8"))
+        }
+
+        fn write(&self, key: &str, data: &str) -> Result<(), std::io::Error> {
+            let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+            let payload = Payload {
+                msg: data.as_bytes(),
+                aad: key.as_bytes(),
+            };
+            let ciphertext = self
+                .cipher
+                .encrypt(&nonce, payload)
+                .map_err(|_| std::io::Error::other("failed to encrypt preferences"))?;
+
+            let mut encoded = String::with_capacity(HEADER.len() + 2 * (NONCE_LEN + ciphertext.len()));
+            encoded.push_str(HEADER);
+            encode_hex(&nonce, &mut encoded);
+            encode_hex(&ciphertext, &mut encoded);
+            self.inner.write(key, &encoded)
+        }
+
+        fn get_path(&self, key: &str) -> String {
+            self.inner.get_path(key)
+        }
+
+        fn backend_name(&self) -> &'static str {
+            "encrypted-file"
+        }
+
+        fn environment_report(&self, key: &str) -> EnvironmentReport {
+            EnvironmentReport {
+                backend: self.backend_name(),
+                ..self.inner.environment_report(key)
+            }
+        }
+
+        fn available_space(&self) -> Option<u64> {
+            self.inner.available_space()
+        }
+
+        fn set_file_mode(&mut self, mode: u32) {
+            self.inner.set_file_mode(mode);
+        }
+
+        fn set_durability(&mut self, durability: super::Durability) {
+            self.inner.set_durability(durability);
+        }
+
+        fn set_read_cache(&mut self, enabled: bool) {
+            self.inner.set_read_cache(enabled);
+        }
+
+        fn set_backup_exclusion(&mut self, excluded: bool) {
+            self.inner.set_backup_exclusion(excluded);
+        }
+    }
+}
+
+/// Preferences stored as documents on an HTTP server (feature `http`).
+///
+/// Each storage key is a URL below a base URL: GET reads it (404 means nothing stored) and PUT
+/// writes it. Writes are conditional on the `ETag` of the document the caller last loaded, so
+/// a save never silently overwrites a change someone else made in between:
+///
+/// ```rust,no_run
+/// use easy_prefs::easy_prefs;
+/// use easy_prefs::storage::http::{self, HttpStorage};
+///
+/// easy_prefs! {
+///     pub struct TeamPrefs {
+///         pub release_channel: String = "stable".to_string() => "release_channel",
+///     },
+///     "team-settings"
+/// }
+///
+/// let storage = HttpStorage::new("http://settings.internal:8080/teams/42")
+///     .with_header("Authorization", "Bearer s3cr3t");
+/// let mut prefs = TeamPrefs::load_with_storage(Box::new(storage)).unwrap();
+/// if let Err(e) = prefs.save_release_channel("beta".to_string()) {
+///     if matches!(&e, easy_prefs::SaveError::Io(e) if http::is_conflict(e)) {
+///         // Someone else saved first: pick up their values and decide again.
+///         prefs.reload().unwrap();
+///     }
+/// }
+/// ```
+///
+/// A write to a key read as absent sends `If-None-Match: *`, and one to a key read with an
+/// `ETag` sends `If-Match`; the server answers `412 Precondition Failed` if the document
+/// changed, which surfaces as a [`Conflict`]. `reload()` (or loading again) adopts the
+/// current document. Servers should return the new `ETag` from PUT; otherwise the next write
+/// is unconditional.
+///
+/// **The built-in client speaks cleartext HTTP/1.1 (`http://` URLs only): the preferences and
+/// every configured header, such as `Authorization`, cross the network unencrypted.** Use it on
+/// loopback or a network you trust. For TLS, proxies, or connection reuse, implement
+/// [`Transport`] on top of an HTTP client and pass it to [`HttpStorage::with_transport`].
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+pub mod http {
+    use super::Storage;
+    use std::collections::HashMap;
+    use std::io::{Read, Write};
+    use std::net::{TcpStream, ToSocketAddrs};
+    use std::sync::{Mutex, MutexGuard};
+    use std::time::Duration;
+
+    /// Longest response (head and body) [`PlainHttp`] reads: a preferences document, with
+    /// plenty of room.
+    const MAX_RESPONSE_LEN: u64 = 64 * 1024 * 1024;
+
+    /// An HTTP request made by [`HttpStorage`]. `Debug` output leaves out header values.
+    #[derive(Clone)]
+    pub struct Request {
+        /// `"GET"` or `"PUT"`.
+        pub method: &'static str,
+        /// Absolute URL of the document.
+        pub url: String,
+        /// Headers to send, including conditional and configured ones.
+        pub headers: Vec<(String, String)>,
+        /// Body of a PUT.
+        pub body: Option<String>,
+    }
+
+    impl std::fmt::Debug for Request {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("Request")
+                .field("method", &self.method)
+                .field("url", &self.url)
+                .field("headers", &RedactedHeaders(&self.headers))
+                .field("body", &self.body)
+                .finish()
+        }
+    }
+
+    /// Header names with their values redacted, for `Debug` output.
+    struct RedactedHeaders<'a>(&'a [(String, String)]);
+
+    impl std::fmt::Debug for RedactedHeaders<'_> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_map()
+                .entries(self.0.iter().map(|(name, _)| (name, crate::secrets::REDACTED)))
+                .finish()
+        }
+    }
+
+    /// The parts of an HTTP response [`HttpStorage`] looks at.
+    #[derive(Debug, Clone, Default)]
+    pub struct Response {
+        /// Status code, e.g. 200.
+        pub status: u16,
+        /// Value of the `ETag` header, if any.
+        pub etag: Option<String>,
+        /// Body, decoded as UTF-8.
+        pub body: String,
+    }
+
+    /// Sends requests for [`HttpStorage`]; implement it to use another HTTP client.
+    pub trait Transport: Send + Sync + std::fmt::Debug {
+        /// Sends `request` and returns the response, whatever its status.
+        fn send(&self, request: &Request) -> Result<Response, std::io::Error>;
+    }
+
+    /// A write was rejected because the document changed since it was last read.
+    #[derive(Debug)]
+    pub struct Conflict {
+        /// URL of the document.
+        pub url: String,
+    }
+
+    impl std::fmt::Display for Conflict {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{} was changed by someone else since it was loaded", self.url)
+        }
+    }
+
+    impl std::error::Error for Conflict {}
+
+    /// Whether `error` is a rejected write ([`Conflict`]).
+    pub fn is_conflict(error: &std::io::Error) -> bool {
+        error.get_ref().is_some_and(|inner| inner.is::<Conflict>())
+    }
+
+    /// What the storage knows about the stored version of a key.
+    #[derive(Debug, Clone)]
+    enum Version {
+        /// Read as absent (404).
+        Absent,
+        /// Read or written with this `ETag`.
+        Tagged(String),
+    }
+
+    /// Storage on an HTTP server, with writes guarded by `ETag`s. `Debug` output leaves out
+    /// header values.
+    pub struct HttpStorage {
+        base_url: String,
+        headers: Vec<(String, String)>,
+        transport: Box<dyn Transport>,
+        /// Versions of the keys as last loaded or written; missing for keys never read.
+        versions: Mutex<HashMap<String, Version>>,
+    }
+
+    impl std::fmt::Debug for HttpStorage {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("HttpStorage")
+                .field("base_url", &self.base_url)
+                .field("headers", &RedactedHeaders(&self.headers))
+                .field("transport", &self.transport)
+                .finish_non_exhaustive()
+        }
+    }
+
+    impl HttpStorage {
+        /// Creates a storage for documents below `base_url` (e.g.
+        /// `http://localhost:8080/settings`), using the built-in plain HTTP client with a
+        /// 30-second timeout. That client sends everything unencrypted; see the module docs.
+        pub fn new(base_url: &str) -> Self {
+            Self {
+                base_url: base_url.trim_end_matches('/').to_string(),
+                headers: Vec::new(),
+                transport: Box::new(PlainHttp::new(Duration::from_secs(30))),
+                versions: Mutex::new(HashMap::new()),
+            }
+        }
+
+        /// Sends `name: value` with every request, e.g. an `Authorization` header.
+        pub fn with_header(mut self, name: &str, value: &str) -> Self {
+            self.headers.push((name.to_string(), value.to_string()));
+            self
+        }
+
+        /// Sends requests through `transport` instead of the built-in client.
+        pub fn with_transport(mut self, transport: impl Transport + 'static) -> Self {
+            self.transport = Box::new(transport);
+            self
+        }
+
+        fn versions(&self) -> MutexGuard<'_, HashMap<String, Version>> {
+            self.versions.lock().unwrap_or_else(|e| e.into_inner())
+        }
+
+        /// GETs `key`, returning the document and its version.
+        fn get(&self, key: &str) -> Result<(Option<String>, Option<Version>), std::io::Error> {
+            let request = Request {
+                method: "GET",
+                url: self.get_path(key),
+                headers: self.headers.clone(),
+                body: None,
+            };
+            let response = self.transport.send(&request)?;
+            match response.status {
+                200..=299 => Ok((Some(response.body), response.etag.map(Version::Tagged))),
+                404 | 410 => Ok((None, Some(Version::Absent))),
+                status => Err(status_error(&request, status)),
+            }
+        }
+    }
+
+    fn status_error(request: &Request, status: u16) -> std::io::Error {
+        let kind = match status {
+            401 | 403 => std::io::ErrorKind::PermissionDenied,
+            413 | 507 => std::io::ErrorKind::StorageFull,
+            _ => std::io::ErrorKind::Other,
+        };
+        std::io::Error::new(kind, format!("{} {} failed with HTTP {status}", request.method, request.url))
+    }
+
+    impl Storage for HttpStorage {
+        fn read(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+            let (contents, version) = self.get(key)?;
+            let mut versions = self.versions();
+            match version {
+                Some(version) => versions.insert(key.to_string(), version),
+                None => versions.remove(key),
+            };
+            Ok(contents)
+        }
+
+        /// Reads without adopting the current version, so the write that follows still
+        /// fails if the document changed since it was loaded.
+        fn read_before_write(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+            let (contents, version) = self.get(key)?;
+            if let Some(version) = version {
+                self.versions().entry(key.to_string()).or_insert(version);
+            }
+            Ok(contents)
+        }
+
+        fn write(&self, key: &str, data: &str) -> Result<(), std::io::Error> {
+            let mut headers = self.headers.clone();
+            match self.versions().get(key) {
+                Some(Version::Absent) => headers.push(("If-None-Match".to_string(), "*".to_string())),
+                Some(Version::Tagged(etag)) => headers.push(("If-Match".to_string(), etag.clone())),
+                None => {}
+            }
+            let request = Request {
+                method: "PUT",
+                url: self.get_path(key),
+                headers,
+                body: Some(data.to_string()),
+            };
+            let response = self.transport.send(&request)?;
+            match response.status {
+                200..=299 => {
+                    let mut versions = self.versions();
+                    match response.etag {
+                        Some(etag) => versions.insert(key.to_string(), Version::Tagged(etag)),
+                        None => versions.remove(key),
+                    };
+                    Ok(())
+                }
+                412 => Err(std::io::Error::other(Conflict { url: request.url })),
+                status => Err(status_error(&request, status)),
+            }
+        }
+
+        fn get_path(&self, key: &str) -> String {
+            format!("{}/{key}", self.base_url)
+        }
+
+        fn backend_name(&self) -> &'static str {
+            "http"
+        }
+    }
+
+    /// The built-in client: one cleartext HTTP/1.1 connection per request, `http://` URLs
+    /// only, no TLS.
+    ///
+    /// Requests whose path or headers contain CR or LF fail with `ErrorKind::InvalidInput`
+    /// rather than splitting the request. Responses longer than 64 MiB fail with
+    /// `ErrorKind::InvalidData`.
+    #[derive(Debug, Clone)]
+    pub struct PlainHttp {
+        timeout: Duration,
+    }
+
+    impl PlainHttp {
+        /// Creates a client giving up on connecting, sending, or receiving after `timeout`.
+        pub fn new(timeout: Duration) -> Self {
+            Self { timeout }
+        }
+    }
+
+    impl Transport for PlainHttp {
+        fn send(&self, request: &Request) -> Result<Response, std::io::Error> {
+            let (authority, path) = split_url(&request.url)?;
+            check_line(&request.url, "the URL")?;
+            for (name, value) in &request.headers {
+                check_line(name, "a header name")?;
+                check_line(value, &format!("the value of header {name}"))?;
+            }
+            let address = authority
+                .to_socket_addrs()
+                .or_else(|_| (authority, 80).to_socket_addrs())?
+                .next()
+                .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, format!("cannot resolve {authority}")))?;
+            let mut stream = TcpStream::connect_timeout(&address, self.timeout)?;
+            stream.set_read_timeout(Some(self.timeout))?;
+            stream.set_write_timeout(Some(self.timeout))?;
+
+            let body = request.body.as_deref().unwrap_or("");
+            let mut head = format!("{} {path} HTTP/1.1\r\nHost: {authority}\r\nConnection: close\r\n", request.method);
+            if request.body.is_some() {
+                head.push_str(&format!("Content-Type: application/toml\r\nContent-Length: {}\r\n", body.len()));
+            }
+            for (name, value) in &request.headers {
+                head.push_str(&format!("{name}: {value}\r\n"));
+            }
+            head.push_str("\r\n");
+            stream.write_all(head.as_bytes())?;
+            stream.write_all(body.as_bytes())?;
+
+            let mut received = Vec::new();
+            (&mut stream).take(MAX_RESPONSE_LEN + 1).read_to_end(&mut received)?;
+            if received.len() as u64 > MAX_RESPONSE_LEN {
+                return Err(invalid_response(format!("longer than {MAX_RESPONSE_LEN} bytes")));
+            }
+            parse_response(&received)
+        }
+    }
+
+    /// Splits an `http://` URL into authority (`host[:port]`) and path.
+    fn split_url(url: &str) -> Result<(&str, &str), std::io::Error> {
+        let rest = url.strip_prefix("http://").ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{url}: the built-in client only supports http:// URLs; use a custom Transport"),
+            )
+        })?;
+        Ok(match rest.find('/') {
+            Some(index) => (&rest[..index], &rest[index..]),
+            None => (rest, "/"),
+        })
+    }
+
+    /// Rejects text that would end a line of the request head; `what` names it in the error,
+    /// which leaves out the text itself (it may be a credential).
+    fn check_line(text: &str, what: &str) -> Result<(), std::io::Error> {
+        if text.contains(['\r', '\n']) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{what} contains CR or LF, which would split the HTTP request"),
+            ));
+        }
+        Ok(())
+    }
+
+    fn invalid_response(reason: impl std::fmt::Display) -> std::io::Error {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, format!("invalid HTTP response: {reason}"))
+    }
+
+    fn parse_response(received: &[u8]) -> Result<Response, std::io::Error> {
+        let mut headers = [httparse::EMPTY_HEADER; 64];
+        let mut parsed = httparse::Response::new(&mut headers);
+        let httparse::Status::Complete(offset) = parsed.parse(received).map_err(invalid_response)? else {
+            return Err(invalid_response("truncated headers"));
+        };
+        let header = |name: &str| {
+            parsed
+                .headers
+                .iter()
+                .find(|header| header.name.eq_ignore_ascii_case(name))
+                .map(|header| String::from_utf8_lossy(header.value).trim().to_string())
+        };
+        let mut body = &received[offset..];
+        let dechunked;
+        if header("Transfer-Encoding").is_some_and(|encoding| encoding.eq_ignore_ascii_case("chunked")) {
+            dechunked = dechunk(body)?;
+            body = &dechunked;
+        } else if let Some(length) = header("Content-Length").and_then(|length| length.parse().ok()) {
+            body = body.get(..length).ok_or_else(|| invalid_response("truncated body"))?;
+        }
+        Ok(Response {
+            status: parsed.code.ok_or_else(|| invalid_response("missing status"))?,
+            etag: header("ETag"),
+            body: String::from_utf8(body.to_vec()).map_err(invalid_response)?,
+        })
+    }
+
+    /// Decodes a `Transfer-Encoding: chunked` body.
+    fn dechunk(mut body: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        let mut decoded = Vec::new();
+        loop {
+            let line_end = body
+                .windows(2)
+                .position(|window| window == b"\r\n")
+                .ok_or_else(|| invalid_response("truncated chunk"))?;
+            let size = std::str::from_utf8(&body[..line_end]).unwrap_or("");
+            let size = size.split(';').next().unwrap_or("").trim();
+            let size = usize::from_str_radix(size, 16).map_err(|_| invalid_response("bad chunk size"))?;
+            body = &body[line_end + 2..];
+            if size == 0 {
+                return Ok(decoded);
+            }
+            let chunk = body.get(..size).ok_or_else(|| invalid_response("truncated chunk"))?;
+            decoded.extend_from_slice(chunk);
+            body = body.get(size + 2..).unwrap_or(&[]);
+        }
+    }
 }
 
-#[cfg(not(target_arch = "wasm32"))]
-pub mod native {
+/// Preferences stored as rows of an SQLite database (feature `sqlite`).
+///
+/// Each storage key is one row of the `easy_prefs` table, so any number of preferences
+/// structs (with their backups and history) can share a single database file, e.g. the one
+/// the app already keeps its data in:
+///
+/// ```rust,no_run
+/// use easy_prefs::easy_prefs;
+/// use easy_prefs::storage::sqlite::SqliteStorage;
+///
+/// easy_prefs! {
+///     pub struct AppPrefs {
+///         pub theme: String = "light".to_string() => "theme",
+///     },
+///     "app-settings"
+/// }
+///
+/// let storage = SqliteStorage::open("/var/lib/myapp/data.db").unwrap();
+/// let mut prefs = AppPrefs::load_with_storage(Box::new(storage)).unwrap();
+/// prefs.save_theme("dark".to_string()).unwrap();
+/// ```
+///
+/// Every write is a single `INSERT OR REPLACE`, which SQLite applies atomically, so there is
+/// no temporary file. Concurrent writers (other connections, other processes) wait up to five
+/// seconds for the database lock. `Durability::Fsync` sets `PRAGMA synchronous = FULL`, and
+/// `Durability::Fast` sets `NORMAL`; without the option, SQLite's default applies.
+///
+/// The feature builds SQLite into the app (through `rusqlite`), so no system library is
+/// needed.
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+pub mod sqlite {
     use super::Storage;
-    use std::io::{Read, Write};
-    use std::path::PathBuf;
+    use rusqlite::{ErrorCode, OptionalExtension};
+    use std::sync::{Mutex, MutexGuard};
+    use std::time::Duration;
 
+    /// An open database with the durability setting still to apply.
     #[derive(Debug)]
-    pub struct FileStorage {
-        base_dir: PathBuf,
+    struct Database {
+        connection: rusqlite::Connection,
+        /// `PRAGMA synchronous` statement from `set_durability`, run before the next write so
+        /// that a failure surfaces from it.
+        synchronous: Option<&'static str>,
     }
 
-    impl FileStorage {
-        pub fn new(directory: &str) -> Self {
-            Self {
-                base_dir: PathBuf::from(directory),
+    /// Maps an SQLite error to an `io::Error` of a matching kind.
+    fn io_error(error: rusqlite::Error) -> std::io::Error {
+        let kind = match error.sqlite_error_code() {
+            Some(ErrorCode::PermissionDenied | ErrorCode::ReadOnly | ErrorCode::AuthorizationForStatementDenied) => {
+                std::io::ErrorKind::PermissionDenied
+            }
+            Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked) => std::io::ErrorKind::TimedOut,
+            Some(ErrorCode::DiskFull) => std::io::ErrorKind::StorageFull,
+            Some(ErrorCode::CannotOpen) => std::io::ErrorKind::NotFound,
+            Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase) => std::io::ErrorKind::InvalidData,
+            Some(ErrorCode::TooBig) => std::io::ErrorKind::InvalidInput,
+            _ if matches!(error, rusqlite::Error::FromSqlConversionFailure(..) | rusqlite::Error::InvalidColumnType(..)) => {
+                std::io::ErrorKind::InvalidData
+            }
+            _ => std::io::ErrorKind::Other,
+        };
+        std::io::Error::new(kind, format!("SQLite error: {error}"))
+    }
+
+    /// Storage in the `easy_prefs` table of an SQLite database.
+    #[derive(Debug)]
+    pub struct SqliteStorage {
+        path: String,
+        database: Mutex<Database>,
+    }
+
+    impl SqliteStorage {
+        /// Opens (or creates) the database at `path`, with its directory, and the
+        /// `easy_prefs` table in it. `":memory:"` opens a private in-memory database.
+        pub fn open(path: &str) -> Result<Self, std::io::Error> {
+            if let Some(parent) = std::path::Path::new(path).parent().filter(|parent| !parent.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
             }
+            let connection = rusqlite::Connection::open(path).map_err(io_error)?;
+            connection.busy_timeout(Duration::from_secs(5)).map_err(io_error)?;
+            connection
+                .execute("CREATE TABLE IF NOT EXISTS easy_prefs (key TEXT PRIMARY KEY NOT NULL, value TEXT NOT NULL)", [])
+                .map_err(io_error)?;
+            Ok(Self {
+                path: path.to_string(),
+                database: Mutex::new(Database { connection, synchronous: None }),
+            })
+        }
+
+        fn database(&self) -> MutexGuard<'_, Database> {
+            self.database.lock().unwrap_or_else(|e| e.into_inner())
         }
     }
 
-    impl Storage for FileStorage {
+    impl Storage for SqliteStorage {
         fn read(&self, key: &str) -> Result<Option<String>, std::io::Error> {
-            let path = self.base_dir.join(key);
+            self.database()
+                .connection
+                .query_row("SELECT value FROM easy_prefs WHERE key = ?1", [key], |row| row.get(0))
+                .optional()
+                .map_err(io_error)
+        }
 
-            if !path.exists() {
-                return Ok(None);
+        fn write(&self, key: &str, data: &str) -> Result<(), std::io::Error> {
+            let mut database = self.database();
+            if let Some(pragma) = database.synchronous {
+                database.connection.execute_batch(pragma).map_err(io_error)?;
+                database.synchronous = None;
             }
+            database
+                .connection
+                .execute("INSERT OR REPLACE INTO easy_prefs (key, value) VALUES (?1, ?2)", [key, data])
+                .map_err(io_error)?;
+            Ok(())
+        }
 
-            let mut file = std::fs::File::open(&path)?;
-            let mut contents = String::new();
-            file.read_to_string(&mut contents)?;
-            Ok(Some(contents))
+        fn get_path(&self, key: &str) -> String {
+            format!("{}#{key}", self.path)
         }
 
-        fn write(&self, key: &str, data: &str) -> Result<(), std::io::Error> {
-            let path = self.base_dir.join(key);
+        fn backend_name(&self) -> &'static str {
+            "sqlite"
+        }
 
-            // Ensure parent directory exists
-            if let Some(parent) = path.parent() {
-                std::fs::create_dir_all(parent)?;
+        fn is_persistent(&self) -> bool {
+            self.path != ":memory:"
+        }
+
+        /// Applied by the next write, which fails if SQLite rejects it.
+        fn set_durability(&mut self, durability: super::Durability) {
+            let pragma = match durability {
+                super::Durability::Fast => "PRAGMA synchronous = NORMAL",
+                super::Durability::Fsync => "PRAGMA synchronous = FULL",
+            };
+            self.database.get_mut().unwrap_or_else(|e| e.into_inner()).synchronous = Some(pragma);
+        }
+    }
+}
+
+/// Preferences stored in Redis (or a compatible server such as KeyDB or Valkey; feature
+/// `redis`).
+///
+/// Server deployments can keep runtime-tunable settings in Redis and use the same structs as
+/// desktop builds; only the storage differs:
+///
+/// ```rust,no_run
+/// use easy_prefs::easy_prefs;
+/// use easy_prefs::storage::redis::RedisStorage;
+///
+/// easy_prefs! {
+///     pub struct ServicePrefs {
+///         pub max_connections: i64 = 100 => "max_connections",
+///     },
+///     "service-settings"
+/// }
+///
+/// let storage = RedisStorage::open("redis://:secret@cache.internal:6379/2")
+///     .unwrap()
+///     .with_prefix("billing:");
+/// let prefs = ServicePrefs::load_with_storage(Box::new(storage)).unwrap();
+/// ```
+///
+/// Each storage key is one Redis string, named `<prefix><key>`, written with a single `SET`.
+/// URLs have the form `redis://[[user]:password@]host[:port][/database]`; `AUTH` and `SELECT`
+/// are sent on every new connection. The connection is reused and reopened once if the server
+/// closed it. Errors from the server map to `ErrorKind`s: `NOAUTH` and `WRONGPASS` to
+/// `PermissionDenied`, `OOM` (the `maxmemory` limit) to `StorageFull`, which saves report as
+/// `SaveError::QuotaExceeded`.
+///
+/// The built-in client speaks plain RESP over TCP; put a TLS tunnel in front of servers that
+/// require TLS. Preferences of several instances of a service share values, so load them with
+/// `single_instance = false` or one struct per instance and call `reload()` to pick up values
+/// other instances saved.
+#[cfg(all(feature = "redis", not(target_arch = "wasm32")))]
+pub mod redis {
+    use super::Storage;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpStream;
+    use std::sync::{Mutex, MutexGuard};
+    use std::time::Duration;
+
+    /// Longest bulk reply accepted: a preferences document or blob, with plenty of room.
+    /// Longer lengths fail before anything is allocated.
+    const MAX_BULK_LEN: usize = 64 * 1024 * 1024;
+
+    /// Longest reply line (status, error, or length) accepted.
+    const MAX_LINE_LEN: u64 = 64 * 1024;
+
+    /// A reply of the server (RESP2), as far as the storage needs them. No command the storage
+    /// sends answers with an array, so array replies are rejected as unexpected.
+    #[derive(Debug)]
+    enum Reply {
+        Status,
+        Bulk(Option<Vec<u8>>),
+    }
+
+    /// An error reply: the command reached the server, so reconnecting won't help.
+    #[derive(Debug)]
+    struct ServerError(String);
+
+    impl std::fmt::Display for ServerError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Redis: {}", self.0)
+        }
+    }
+
+    impl std::error::Error for ServerError {}
+
+    #[derive(Debug)]
+    struct Connection {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+    }
+
+    impl Connection {
+        fn send(&mut self, command: &[&[u8]]) -> Result<Reply, std::io::Error> {
+            let mut request = format!("*{}\r\n", command.len()).into_bytes();
+            for argument in command {
+                request.extend_from_slice(format!("${}\r\n", argument.len()).as_bytes());
+                request.extend_from_slice(argument);
+                request.extend_from_slice(b"\r\n");
             }
+            self.writer.write_all(&request)?;
+            self.read_reply()
+        }
 
-            // Write to temporary file first
-            let parent_dir = path.parent().unwrap_or(&self.base_dir);
-            let mut tmp_file = tempfile::NamedTempFile::new_in(parent_dir)?;
-            tmp_file.write_all(data.as_bytes())?;
+        fn read_reply(&mut self) -> Result<Reply, std::io::Error> {
+            let mut line = String::new();
+            if (&mut self.reader).take(MAX_LINE_LEN).read_line(&mut line)? == 0 {
+                return Err(std::io::Error::new(std::io::ErrorKind::ConnectionAborted, "Redis closed the connection"));
+            }
+            if !line.ends_with('\n') {
+                return Err(protocol_error("reply line too long"));
+            }
+            let line = line.trim_end_matches(['\r', '\n']);
+            let (kind, rest) = line.split_at(line.len().min(1));
+            match kind {
+                "+" => Ok(Reply::Status),
+                "-" => Err(server_error(rest)),
+                "$" => {
+                    let Ok(len) = usize::try_from(rest.parse::<i64>().map_err(|_| protocol_error(line))?) else {
+                        return Ok(Reply::Bulk(None));
+                    };
+                    if len > MAX_BULK_LEN {
+                        return Err(protocol_error(&format!("bulk reply of {len} bytes exceeds {MAX_BULK_LEN}")));
+                    }
+                    let mut data = vec![0; len + 2];
+                    self.reader.read_exact(&mut data)?;
+                    data.truncate(len);
+                    Ok(Reply::Bulk(Some(data)))
+                }
+                _ => Err(protocol_error(line)),
+            }
+        }
+    }
 
-            // Atomically move temp file to final location
-            tmp_file.persist(&path).map_err(|e| e.error)?;
+    fn is_server_error(error: &std::io::Error) -> bool {
+        error.get_ref().is_some_and(|inner| inner.is::<ServerError>())
+    }
 
-            Ok(())
+    fn protocol_error(line: &str) -> std::io::Error {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, format!("unexpected reply from Redis: {line}"))
+    }
+
+    /// Maps an error reply (`ERR ...`, `WRONGPASS ...`) to an `io::Error`.
+    fn server_error(message: &str) -> std::io::Error {
+        let kind = match message.split(' ').next().unwrap_or_default() {
+            "NOAUTH" | "WRONGPASS" | "NOPERM" => std::io::ErrorKind::PermissionDenied,
+            "OOM" => std::io::ErrorKind::StorageFull,
+            "READONLY" => std::io::ErrorKind::ReadOnlyFilesystem,
+            _ => std::io::ErrorKind::Other,
+        };
+        std::io::Error::new(kind, ServerError(message.to_string()))
+    }
+
+    /// Decodes `%XX` escapes in the user and password of a URL.
+    fn percent_decode(text: &str) -> String {
+        let bytes = text.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut index = 0;
+        while index < bytes.len() {
+            let escaped = text.get(index + 1..index + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            match (bytes[index], escaped) {
+                (b'%', Some(byte)) => {
+                    decoded.push(byte);
+                    index += 3;
+                }
+                (byte, _) => {
+                    decoded.push(byte);
+                    index += 1;
+                }
+            }
+        }
+        String::from_utf8_lossy(&decoded).into_owned()
+    }
+
+    /// Stores preferences as strings of a Redis server. `Debug` output leaves out the password.
+    pub struct RedisStorage {
+        address: String,
+        user: Option<String>,
+        password: Option<String>,
+        database: u32,
+        prefix: String,
+        timeout: Duration,
+        connection: Mutex<Option<Connection>>,
+    }
+
+    impl std::fmt::Debug for RedisStorage {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("RedisStorage")
+                .field("address", &self.address)
+                .field("user", &self.user)
+                .field("password", &self.password.as_ref().map(|_| crate::secrets::REDACTED))
+                .field("database", &self.database)
+                .field("prefix", &self.prefix)
+                .field("timeout", &self.timeout)
+                .finish_non_exhaustive()
+        }
+    }
+
+    impl RedisStorage {
+        /// Connects to the server at `url` (`redis://[[user]:password@]host[:port][/database]`),
+        /// failing early if it can't be reached or refuses the credentials.
+        pub fn open(url: &str) -> Result<Self, std::io::Error> {
+            let rest = url.strip_prefix("redis://").unwrap_or(url);
+            let (authority, database) = rest.split_once('/').unwrap_or((rest, ""));
+            let (credentials, host) = match authority.rsplit_once('@') {
+                Some((credentials, host)) => (Some(credentials), host),
+                None => (None, authority),
+            };
+            // Errors name the URL without its credentials.
+            let shown = match credentials {
+                Some(_) => url.replacen(authority, &format!("{}@{host}", crate::secrets::REDACTED), 1),
+                None => url.to_string(),
+            };
+            let invalid = |reason: &str| std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("{shown}: {reason}"));
+            if !url.starts_with("redis://") {
+                return Err(invalid("expected a redis:// URL"));
+            }
+            let (user, password) = match credentials.map(|credentials| credentials.split_once(':')) {
+                Some(Some((user, password))) => ((!user.is_empty()).then(|| percent_decode(user)), Some(percent_decode(password))),
+                // A user without a password can't authenticate.
+                Some(None) => (credentials.map(percent_decode), None),
+                None => (None, None),
+            };
+            if host.is_empty() {
+                return Err(invalid("missing host"));
+            }
+            let database = match database {
+                "" => 0,
+                database => database.parse().map_err(|_| invalid("invalid database number"))?,
+            };
+            let storage = Self {
+                address: if host.contains(':') { host.to_string() } else { format!("{host}:6379") },
+                user,
+                password,
+                database,
+                prefix: String::new(),
+                timeout: Duration::from_secs(10),
+                connection: Mutex::new(None),
+            };
+            storage.command(&[b"PING"])?;
+            Ok(storage)
+        }
+
+        /// Prepends `prefix` to every key, e.g. `"billing:"` to keep the settings of several
+        /// services apart in one database.
+        pub fn with_prefix(mut self, prefix: &str) -> Self {
+            self.prefix = prefix.to_string();
+            self
+        }
+
+        /// Sets how long connecting, sending, and waiting for a reply may take (10 seconds by
+        /// default).
+        pub fn with_timeout(mut self, timeout: Duration) -> Self {
+            self.timeout = timeout;
+            self
+        }
+
+        fn connect(&self) -> Result<Connection, std::io::Error> {
+            let address = std::net::ToSocketAddrs::to_socket_addrs(&self.address)?
+                .next()
+                .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, format!("{}: no address", self.address)))?;
+            let stream = TcpStream::connect_timeout(&address, self.timeout)?;
+            stream.set_read_timeout(Some(self.timeout))?;
+            stream.set_write_timeout(Some(self.timeout))?;
+            stream.set_nodelay(true)?;
+            let mut connection = Connection { reader: BufReader::new(stream.try_clone()?), writer: stream };
+            if let Some(password) = &self.password {
+                match &self.user {
+                    Some(user) => connection.send(&[b"AUTH", user.as_bytes(), password.as_bytes()])?,
+                    None => connection.send(&[b"AUTH", password.as_bytes()])?,
+                };
+            }
+            if self.database != 0 {
+                connection.send(&[b"SELECT", self.database.to_string().as_bytes()])?;
+            }
+            Ok(connection)
+        }
+
+        fn connection(&self) -> MutexGuard<'_, Option<Connection>> {
+            self.connection.lock().unwrap_or_else(|e| e.into_inner())
+        }
+
+        /// Sends `command` on the open connection, reopening it once if it fails for reasons
+        /// other than the server's reply.
+        fn command(&self, command: &[&[u8]]) -> Result<Reply, std::io::Error> {
+            let mut connection = self.connection();
+            if let Some(open) = connection.as_mut() {
+                match open.send(command) {
+                    Ok(reply) => return Ok(reply),
+                    Err(e) if is_server_error(&e) => return Err(e),
+                    Err(_) => *connection = None,
+                }
+            }
+            let mut open = self.connect()?;
+            let reply = open.send(command);
+            // After any other error, the rest of the reply may still be in the stream.
+            if reply.as_ref().map_or_else(is_server_error, |_| true) {
+                *connection = Some(open);
+            }
+            reply
+        }
+
+        fn full_key(&self, key: &str) -> String {
+            format!("{}{key}", self.prefix)
+        }
+    }
+
+    impl Storage for RedisStorage {
+        fn read(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+            match self.command(&[b"GET", self.full_key(key).as_bytes()])? {
+                Reply::Bulk(None) => Ok(None),
+                Reply::Bulk(Some(data)) => String::from_utf8(data)
+                    .map(Some)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+                reply => Err(protocol_error(&format!("{reply:?}"))),
+            }
+        }
+
+        fn write(&self, key: &str, data: &str) -> Result<(), std::io::Error> {
+            match self.command(&[b"SET", self.full_key(key).as_bytes(), data.as_bytes()])? {
+                Reply::Status => Ok(()),
+                reply => Err(protocol_error(&format!("{reply:?}"))),
+            }
         }
 
         fn get_path(&self, key: &str) -> String {
-            self.base_dir.join(key).display().to_string()
+            format!("redis://{}/{}/{}", self.address, self.database, self.full_key(key))
+        }
+
+        fn backend_name(&self) -> &'static str {
+            "redis"
         }
     }
 }
 
 #[cfg(target_arch = "wasm32")]
 pub mod wasm {
-    use super::Storage;
+    use super::{EnvironmentQuirk, EnvironmentReport, Storage};
     use web_sys::{window, Storage as WebStorage};
 
     #[derive(Debug)]
@@ -104,6 +1727,79 @@ pub mod wasm {
         fn full_key(&self, key: &str) -> String {
             format!("{}{}", self.prefix, key)
         }
+
+        /// Removes every item of this app from localStorage, returning how many were removed.
+        pub fn remove_all(&self) -> Result<usize, std::io::Error> {
+            remove_prefixed(&self.prefix)
+        }
+
+        /// Checks that localStorage can be written, which fails in private browsing on some
+        /// browsers and under policies that disable site data.
+        pub fn probe() -> Result<(), std::io::Error> {
+            const PROBE_KEY: &str = "easy_prefs__probe";
+            let storage = Self::get_storage()?;
+            storage
+                .set_item(PROBE_KEY, "1")
+                .and_then(|()| storage.remove_item(PROBE_KEY))
+                .map_err(|_| {
+                    std::io::Error::new(std::io::ErrorKind::PermissionDenied, "localStorage is not writable")
+                })
+        }
+    }
+
+    /// Removes the localStorage items whose key starts with `prefix`.
+    fn remove_prefixed(prefix: &str) -> Result<usize, std::io::Error> {
+        let storage = LocalStorage::get_storage()?;
+        let length = storage.length().unwrap_or(0);
+        // Removing shifts the indices, so collect the keys first.
+        let keys: Vec<String> = (0..length)
+            .filter_map(|index| storage.key(index).ok().flatten())
+            .filter(|key| key.starts_with(prefix))
+            .collect();
+        for key in &keys {
+            storage.remove_item(key).map_err(|_| {
+                std::io::Error::new(std::io::ErrorKind::Other, "failed to remove from localStorage")
+            })?;
+        }
+        Ok(keys.len())
+    }
+
+    /// Returns a fresh app id for a `load_testing()` instance, `test_<millis>_<counter>_<random>`,
+    /// so instances created in the same millisecond (or by test runners in other tabs) don't
+    /// share storage.
+    pub fn test_app_id() -> String {
+        static COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+        let millis = web_time::SystemTime::now()
+            .duration_since(web_time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis());
+        let counter = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let random = (js_sys::Math::random() * f64::from(u32::MAX)) as u32;
+        format!("test_{millis}_{counter}_{random:08x}")
+    }
+
+    /// Removes the items of every `load_testing()` instance from localStorage, e.g. ones left
+    /// behind by a test run that was killed. Returns how many items were removed.
+    pub fn purge_test_keys() -> Result<usize, std::io::Error> {
+        remove_prefixed("easy_prefs_test_")
+    }
+
+    /// Items of a `load_testing()` instance, removed from localStorage when dropped.
+    #[derive(Debug)]
+    pub struct TestKeys(LocalStorage);
+
+    impl TestKeys {
+        /// Takes over cleanup of the items stored under `app_id`.
+        pub fn new(app_id: &str) -> Self {
+            Self(LocalStorage::new(app_id))
+        }
+    }
+
+    impl Drop for TestKeys {
+        fn drop(&mut self) {
+            if let Err(e) = self.0.remove_all() {
+                crate::scrub::log(&[], None::<&()>, format_args!("Failed to remove test preferences: {}", e));
+            }
+        }
     }
 
     impl Storage for LocalStorage {
@@ -123,15 +1819,404 @@ pub mod wasm {
             let storage = Self::get_storage()?;
             let full_key = self.full_key(key);
 
+            // `setItem` only throws when the quota is exceeded.
             storage.set_item(&full_key, data).map_err(|_| {
-                std::io::Error::new(std::io::ErrorKind::Other, "failed to write to localStorage")
+                std::io::Error::new(std::io::ErrorKind::StorageFull, "localStorage quota exceeded")
             })
         }
 
         fn get_path(&self, key: &str) -> String {
             format!("localStorage::{}", self.full_key(key))
         }
+
+        fn backend_name(&self) -> &'static str {
+            "localStorage"
+        }
+
+        fn environment_report(&self, key: &str) -> EnvironmentReport {
+            let mut report = EnvironmentReport::new(self.backend_name(), self.get_path(key));
+
+            let storage = match Self::get_storage() {
+                Ok(storage) => storage,
+                Err(e) => {
+                    report
+                        .quirks
+                        .push(EnvironmentQuirk::StorageUnavailable(e.to_string()));
+                    return report;
+                }
+            };
+
+            // localStorage quotas are per origin (typically ~5 MB), so report what this app uses.
+            let mut used_bytes = 0u64;
+            let length = storage.length().unwrap_or(0);
+            for index in 0..length {
+                if let Ok(Some(item_key)) = storage.key(index) {
+                    if item_key.starts_with(&self.prefix) {
+                        let value = storage.get_item(&item_key).ok().flatten().unwrap_or_default();
+                        used_bytes += (item_key.len() + value.len()) as u64;
+                    }
+                }
+            }
+            report
+                .quirks
+                .push(EnvironmentQuirk::QuotaLimited { used_bytes });
+
+            report
+        }
+    }
+}
+
+/// Browser extension storage (`browser.storage` / `chrome.storage`), including the `sync` area
+/// that follows the user across browsers.
+///
+/// The extension storage API is asynchronous, so [`ExtensionStorage::open`] reads the app's
+/// items once and later reads are served from that copy. Writes update the copy and are sent
+/// to the browser in the background; changes made elsewhere (another browser, another
+/// extension page) update the copy as they arrive, so `reload()` picks them up.
+#[cfg(all(feature = "webextension", target_arch = "wasm32"))]
+pub mod extension {
+    use super::{EnvironmentQuirk, EnvironmentReport, Storage};
+    use js_sys::{Function, Object, Promise, Reflect};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex, MutexGuard};
+    use wasm_bindgen::closure::Closure;
+    use wasm_bindgen::{JsCast, JsValue};
+    use wasm_bindgen_futures::JsFuture;
+
+    /// Largest item `chrome.storage.sync` accepts: key plus JSON-encoded value, in bytes.
+    pub const SYNC_QUOTA_BYTES_PER_ITEM: usize = 8192;
+
+    /// Storage area of the extension storage API.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum StorageArea {
+        /// `storage.local`: kept on this device.
+        Local,
+        /// `storage.sync`: synced across the user's browsers, with small quotas.
+        Sync,
+    }
+
+    impl StorageArea {
+        fn name(self) -> &'static str {
+            match self {
+                StorageArea::Local => "local",
+                StorageArea::Sync => "sync",
+            }
+        }
+    }
+
+    /// Storage backed by an extension storage area, for `load_with_storage()`.
+    #[derive(Debug)]
+    pub struct ExtensionStorage {
+        area: StorageArea,
+        prefix: String,
+        items: Arc<Mutex<HashMap<String, String>>>,
+    }
+
+    impl ExtensionStorage {
+        /// Reads the items of `app_id` from `area` and starts following changes to them.
+        ///
+        /// Fails if the page is not part of a browser extension or lacks the `storage`
+        /// permission.
+        pub async fn open(app_id: &str, area: StorageArea) -> Result<Self, std::io::Error> {
+            let storage = Self {
+                area,
+                prefix: format!("easy_prefs_{}_", app_id.replace('/', "_").replace('.', "_")),
+                items: Arc::default(),
+            };
+            let area_object = storage.area_object()?;
+            let all = call(&area_object, "get", &JsValue::NULL)?;
+            let all = JsFuture::from(all)
+                .await
+                .map_err(|e| js_error("failed to read extension storage", e))?;
+            if let Ok(all) = all.dyn_into::<Object>() {
+                let mut items = storage.items();
+                for entry in Object::entries(&all).iter() {
+                    let key = Reflect::get(&entry, &0.into()).ok().and_then(|key| key.as_string());
+                    let value = Reflect::get(&entry, &1.into()).ok().and_then(|value| value.as_string());
+                    if let (Some(key), Some(value)) = (key, value) {
+                        if let Some(key) = key.strip_prefix(&storage.prefix) {
+                            items.insert(key.to_string(), value);
+                        }
+                    }
+                }
+            }
+            storage.follow_changes()?;
+            Ok(storage)
+        }
+
+        fn items(&self) -> MutexGuard<'_, HashMap<String, String>> {
+            self.items.lock().unwrap_or_else(|e| e.into_inner())
+        }
+
+        /// `browser.storage` in Firefox and Safari, `chrome.storage` in Chromium browsers.
+        fn storage_namespace() -> Result<JsValue, std::io::Error> {
+            let global = js_sys::global();
+            for name in ["browser", "chrome"] {
+                let namespace = Reflect::get(&global, &name.into()).unwrap_or(JsValue::UNDEFINED);
+                if namespace.is_object() {
+                    let storage = Reflect::get(&namespace, &"storage".into()).unwrap_or(JsValue::UNDEFINED);
+                    if storage.is_object() {
+                        return Ok(storage);
+                    }
+                }
+            }
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "extension storage not available (not an extension page, or missing \"storage\" permission)",
+            ))
+        }
+
+        fn area_object(&self) -> Result<JsValue, std::io::Error> {
+            let area = Reflect::get(&Self::storage_namespace()?, &self.area.name().into())
+                .unwrap_or(JsValue::UNDEFINED);
+            if !area.is_object() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    format!("storage.{} not available", self.area.name()),
+                ));
+            }
+            Ok(area)
+        }
+
+        /// Keeps the local copy up to date with changes from other browsers and pages.
+        fn follow_changes(&self) -> Result<(), std::io::Error> {
+            let on_changed = Reflect::get(&Self::storage_namespace()?, &"onChanged".into())
+                .map_err(|e| js_error("storage.onChanged not available", e))?;
+            let (items, prefix, area) = (self.items.clone(), self.prefix.clone(), self.area.name());
+            let listener = Closure::<dyn FnMut(JsValue, JsValue)>::new(move |changes: JsValue, area_name: JsValue| {
+                if area_name.as_string().as_deref() != Some(area) {
+                    return;
+                }
+                let Ok(changes) = changes.dyn_into::<Object>() else {
+                    return;
+                };
+                let mut items = items.lock().unwrap_or_else(|e| e.into_inner());
+                for entry in Object::entries(&changes).iter() {
+                    let Some(key) = Reflect::get(&entry, &0.into()).ok().and_then(|key| key.as_string()) else {
+                        continue;
+                    };
+                    let Some(key) = key.strip_prefix(&prefix) else {
+                        continue;
+                    };
+                    let change = Reflect::get(&entry, &1.into()).unwrap_or(JsValue::UNDEFINED);
+                    match Reflect::get(&change, &"newValue".into()).ok().and_then(|value| value.as_string()) {
+                        Some(value) => items.insert(key.to_string(), value),
+                        None => items.remove(key),
+                    };
+                }
+            });
+            call(&on_changed, "addListener", listener.as_ref())?;
+            // The listener lives as long as the page.
+            listener.forget();
+            Ok(())
+        }
+
+        fn full_key(&self, key: &str) -> String {
+            format!("{}{}", self.prefix, key)
+        }
     }
+
+    impl Storage for ExtensionStorage {
+        fn read(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+            Ok(self.items().get(key).cloned())
+        }
+
+        fn write(&self, key: &str, data: &str) -> Result<(), std::io::Error> {
+            let full_key = self.full_key(key);
+            let value = JsValue::from_str(data);
+            if self.area == StorageArea::Sync {
+                let encoded_len = js_sys::JSON::stringify(&value)
+                    .map(|json| json.length() as usize)
+                    .unwrap_or(data.len());
+                if full_key.len() + encoded_len > SYNC_QUOTA_BYTES_PER_ITEM {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::StorageFull,
+                        format!("{full_key} exceeds the storage.sync item quota of {SYNC_QUOTA_BYTES_PER_ITEM} bytes"),
+                    ));
+                }
+            }
+
+            let update = Object::new();
+            Reflect::set(&update, &full_key.as_str().into(), &value)
+                .map_err(|e| js_error("failed to prepare extension storage write", e))?;
+            let written = call(&self.area_object()?, "set", &update)?;
+            self.items().insert(key.to_string(), data.to_string());
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Err(e) = JsFuture::from(written).await {
+                    crate::scrub::log(&[], None::<&()>, format_args!("Failed to write {full_key} to extension storage: {e:?}"));
+                }
+            });
+            Ok(())
+        }
+
+        fn get_path(&self, key: &str) -> String {
+            format!("storage.{}::{}", self.area.name(), self.full_key(key))
+        }
+
+        fn backend_name(&self) -> &'static str {
+            match self.area {
+                StorageArea::Local => "storage.local",
+                StorageArea::Sync => "storage.sync",
+            }
+        }
+
+        fn environment_report(&self, key: &str) -> EnvironmentReport {
+            let mut report = EnvironmentReport::new(self.backend_name(), self.get_path(key));
+            let used_bytes = self
+                .items()
+                .iter()
+                .map(|(key, value)| (self.prefix.len() + key.len() + value.len()) as u64)
+                .sum();
+            report.quirks.push(EnvironmentQuirk::QuotaLimited { used_bytes });
+            report
+        }
+    }
+
+    /// Calls `object[method](argument)`, expecting a promise.
+    fn call(object: &JsValue, method: &str, argument: &JsValue) -> Result<Promise, std::io::Error> {
+        let function: Function = Reflect::get(object, &method.into())
+            .ok()
+            .and_then(|function| function.dyn_into().ok())
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::Unsupported, format!("{method} not available")))?;
+        let result = function
+            .call1(object, argument)
+            .map_err(|e| js_error(&format!("{method} failed"), e))?;
+        // `addListener` returns nothing; wrap it so callers can treat every call alike.
+        Ok(result.dyn_into().unwrap_or_else(|_| Promise::resolve(&JsValue::UNDEFINED)))
+    }
+
+    fn js_error(context: &str, error: JsValue) -> std::io::Error {
+        std::io::Error::other(format!("{context}: {error:?}"))
+    }
+}
+
+#[cfg(all(feature = "user-defaults", target_vendor = "apple"))]
+pub mod apple {
+    use super::{EnvironmentQuirk, EnvironmentReport, Storage};
+    use core_foundation::base::{CFType, TCFType};
+    use core_foundation::string::CFString;
+    use core_foundation_sys::preferences::{
+        kCFPreferencesCurrentApplication, CFPreferencesAppSynchronize, CFPreferencesCopyAppValue,
+        CFPreferencesSetAppValue,
+    };
+
+    /// Storage backed by `NSUserDefaults` (through CFPreferences), for `load_with_storage()`.
+    ///
+    /// Each preferences file is one string value named after its storage key, so it shows up in
+    /// `defaults read <domain>` and is part of the app's backups, iCloud device backups included.
+    #[derive(Debug, Clone)]
+    pub struct UserDefaultsStorage {
+        suite: Option<String>,
+    }
+
+    impl UserDefaultsStorage {
+        /// Stores preferences in the standard user defaults of the running app.
+        pub fn standard() -> Self {
+            Self { suite: None }
+        }
+
+        /// Stores preferences in the defaults domain `suite`, e.g. an app group
+        /// (`group.com.mycompany.app`) shared with the app's extensions.
+        pub fn suite(suite: &str) -> Self {
+            Self { suite: Some(suite.to_string()) }
+        }
+
+        fn domain(&self) -> CFString {
+            match &self.suite {
+                Some(suite) => CFString::new(suite),
+                // SAFETY: the constant is a valid, immortal CFString provided by CoreFoundation.
+                None => unsafe { CFString::wrap_under_get_rule(kCFPreferencesCurrentApplication) },
+            }
+        }
+    }
+
+    impl Storage for UserDefaultsStorage {
+        fn read(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+            let (key, domain) = (CFString::new(key), self.domain());
+            // SAFETY: both arguments are valid CFStrings that outlive the call.
+            let value = unsafe { CFPreferencesCopyAppValue(key.as_concrete_TypeRef(), domain.as_concrete_TypeRef()) };
+            if value.is_null() {
+                return Ok(None);
+            }
+            // SAFETY: a non-null result of a Copy function is owned by the caller.
+            let value = unsafe { CFType::wrap_under_create_rule(value) };
+            match value.downcast::<CFString>() {
+                Some(value) => Ok(Some(value.to_string())),
+                None => Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("{} is not a string", self.get_path(&key.to_string())),
+                )),
+            }
+        }
+
+        fn write(&self, key: &str, data: &str) -> Result<(), std::io::Error> {
+            let (key, value, domain) = (CFString::new(key), CFString::new(data), self.domain());
+            // SAFETY: all arguments are valid CFStrings that outlive the calls; CFPreferences
+            // retains the value.
+            let synchronized = unsafe {
+                CFPreferencesSetAppValue(key.as_concrete_TypeRef(), value.as_CFTypeRef(), domain.as_concrete_TypeRef());
+                CFPreferencesAppSynchronize(domain.as_concrete_TypeRef())
+            };
+            if synchronized == 0 {
+                return Err(std::io::Error::other("failed to synchronize user defaults"));
+            }
+            Ok(())
+        }
+
+        fn get_path(&self, key: &str) -> String {
+            format!("defaults::{}::{key}", self.suite.as_deref().unwrap_or("standard"))
+        }
+
+        fn backend_name(&self) -> &'static str {
+            "user-defaults"
+        }
+
+        fn environment_report(&self, key: &str) -> EnvironmentReport {
+            let mut report = EnvironmentReport::new(self.backend_name(), self.get_path(key));
+            if let Some(sandbox) = super::detect_sandbox() {
+                report.quirks.push(EnvironmentQuirk::Sandboxed(sandbox));
+            }
+            report
+        }
+    }
+}
+
+/// Resolves the per-user configuration directory of `app_id`, a reverse-domain identifier
+/// such as `"com.example.app"`: `$XDG_CONFIG_HOME/app` (usually `~/.config/app`) on Linux,
+/// `~/Library/Application Support/com.example.app` on macOS, and
+/// `%APPDATA%\example\app\config` on Windows.
+///
+/// Identifiers with fewer than three parts leave out the qualifier and organization.
+///
+/// # Errors
+///
+/// Returns `NotFound` if the platform has no home directory for the user, or `InvalidData` if
+/// the path is not valid UTF-8.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn config_directory(app_id: &str) -> Result<String, std::io::Error> {
+    let mut parts: Vec<&str> = app_id.splitn(3, '.').collect();
+    while parts.len() < 3 {
+        parts.insert(0, "");
+    }
+    let dirs = directories::ProjectDirs::from(parts[0], parts[1], parts[2]).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("no configuration directory for '{app_id}': home directory unknown"),
+        )
+    })?;
+    let directory = dirs.config_dir();
+    directory.to_str().map(str::to_string).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("configuration directory {} is not valid UTF-8", directory.display()),
+        )
+    })
+}
+
+/// On WASM, storage is keyed by the app ID itself, so it is returned unchanged.
+#[cfg(target_arch = "wasm32")]
+pub fn config_directory(app_id: &str) -> Result<String, std::io::Error> {
+    Ok(app_id.to_string())
 }
 
 /// Platform-specific storage factory
@@ -140,7 +2225,15 @@ pub fn create_storage(directory: &str) -> Box<dyn Storage> {
     Box::new(native::FileStorage::new(directory))
 }
 
+/// On WASM, falls back to a [`MemoryStorage`] if localStorage is disabled, so loading and
+/// saving keep working for the session (see `is_persistent()`).
 #[cfg(target_arch = "wasm32")]
 pub fn create_storage(app_id: &str) -> Box<dyn Storage> {
-    Box::new(wasm::LocalStorage::new(app_id))
+    match wasm::LocalStorage::probe() {
+        Ok(()) => Box::new(wasm::LocalStorage::new(app_id)),
+        Err(e) => {
+            crate::scrub::log(&[], None::<&()>, format_args!("{e}, keeping preferences in memory for this session"));
+            Box::new(MemoryStorage::unavailable(e.to_string()))
+        }
+    }
 }
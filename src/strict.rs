@@ -0,0 +1,132 @@
+//! Strict loading: failing on every problem of a hand-edited document at once.
+//!
+//! By default, loading ignores stored keys that match no field and stops at the first value
+//! of the wrong type. With `strict = true`, loading checks the whole stored document first and
+//! fails with a `LoadError::ValidationError` listing every unknown key and mistyped value,
+//! with its line and column, so a user who edited the file can fix it in one go:
+//!
+//! ```rust
+//! use easy_prefs::{easy_prefs, LoadError};
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub volume: i32 = 5 => "volume",
+//!         pub dark_mode: bool = false => "dark_mode",
+//!     },
+//!     "strict-doc-prefs",
+//!     strict = true
+//! }
+//!
+//! let dir = tempfile::tempdir().unwrap();
+//! std::fs::write(dir.path().join("strict-doc-prefs.toml"), "volume = \"loud\"\ndarkmode = true\n").unwrap();
+//! match AppPrefs::load_with_error(dir.path().to_str().unwrap()) {
+//!     Err(LoadError::ValidationError(e)) => assert_eq!(
+//!         e.reason,
+//!         "2 problems in the stored document:\n\
+//!          line 1, column 10: 'volume': invalid type: string \"loud\", expected i32\n\
+//!          line 2, column 1: unknown key 'darkmode' (did you mean 'dark_mode'?)"
+//!     ),
+//!     other => panic!("unexpected result: {:?}", other.map(|_| ())),
+//! }
+//! ```
+//!
+//! Keys that `lenient_keys` reads as a field and keys of `removed` fields aren't unknown,
+//! values of fields with `lenient_types` aren't checked for their type, and with
+//! `compat_sidecar` unknown keys are expected (they belong to newer app versions) and not
+//! reported. Messages about secret fields leave out the value. Positions are only given for
+//! the TOML format.
+
+use crate::field::FieldInfo;
+use crate::options::PrefsOptions;
+use crate::validate::ValidationError;
+use toml::Table;
+
+/// Something wrong with one top-level key of the stored document.
+struct Problem {
+    /// 1-based line and column of the key or value.
+    position: Option<(usize, usize)>,
+    message: String,
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.position {
+            Some((line, column)) => write!(f, "line {line}, column {column}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Checks the stored `table` (parsed from `document`) for unknown keys and for values that
+/// don't deserialize on their own, taking the other fields from `defaults`.
+#[doc(hidden)]
+pub fn check<T>(
+    document: Option<&str>,
+    table: &Table,
+    fields: &[FieldInfo],
+    options: &PrefsOptions<T>,
+    defaults: &Table,
+    deserialize: impl Fn(Table) -> Result<(), toml::de::Error>,
+) -> Result<(), ValidationError> {
+    let parsed = document
+        .filter(|_| options.format.name() == "toml")
+        .and_then(|document| toml_edit::ImDocument::parse(document).ok());
+    let span = |key: &str, of_value: bool| {
+        let (found, item) = parsed.as_ref()?.as_table().get_key_value(key)?;
+        let span = if of_value { item.span() } else { found.span() };
+        Some(position(document?, span?.start))
+    };
+
+    let mut problems = Vec::new();
+    for (key, value) in table {
+        if key == crate::generation::KEY || key == crate::temporary::TABLE_KEY {
+            continue;
+        }
+        let near_miss = crate::coerce::matching_key(key, fields);
+        let field = match fields.iter().find(|field| field.key == key) {
+            Some(field) => field,
+            None if options.lenient_keys && near_miss.is_some() => continue,
+            None if options.compat_sidecar => continue,
+            None if options.removed.iter().any(|removed| removed.key == key) => continue,
+            None => {
+                let message = match near_miss {
+                    Some(field) => format!("unknown key '{key}' (did you mean '{field}'?)"),
+                    None => format!("unknown key '{key}'"),
+                };
+                problems.push(Problem { position: span(key, false), message });
+                continue;
+            }
+        };
+        if options.lenient_types || field.options.lenient_types {
+            continue;
+        }
+        let mut probe = defaults.clone();
+        probe.insert(key.clone(), value.clone());
+        if let Err(e) = deserialize(probe) {
+            let reason = if field.options.secret {
+                "value has the wrong type".to_string()
+            } else {
+                e.message().trim_end().to_string()
+            };
+            problems.push(Problem { position: span(key, true), message: format!("'{key}': {reason}") });
+        }
+    }
+
+    if problems.is_empty() {
+        return Ok(());
+    }
+    let count = match problems.len() {
+        1 => "1 problem".to_string(),
+        count => format!("{count} problems"),
+    };
+    let lines: Vec<String> = problems.iter().map(ToString::to_string).collect();
+    Err(ValidationError { key: "", reason: format!("{count} in the stored document:\n{}", lines.join("\n")) })
+}
+
+/// Turns a byte offset into a 1-based line and column (in characters).
+fn position(document: &str, offset: usize) -> (usize, usize) {
+    let before = &document[..offset.min(document.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+    (line, column)
+}
@@ -0,0 +1,261 @@
+//! Synchronizing preferences with a remote copy (`sync()`).
+//!
+//! A [`SyncProvider`] stores one serialized copy of the preferences with a version number,
+//! e.g. in a cloud document or a key-value service. `sync()` on generated structs (and
+//! `Prefs<T>`) pulls that copy, reconciles it with the local values, saves what changed locally,
+//! and pushes the result if it differs from the remote copy:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//! use easy_prefs::sync::{Resolution, SyncProvider};
+//!
+//! /// A remote copy held in memory; a real provider talks to a server.
+//! #[derive(Default)]
+//! struct Remote(Option<(String, u64)>);
+//!
+//! impl SyncProvider for Remote {
+//!     fn pull(&mut self) -> Result<Option<(String, u64)>, std::io::Error> {
+//!         Ok(self.0.clone())
+//!     }
+//!
+//!     fn push(&mut self, serialized: &str, version: u64) -> Result<(), std::io::Error> {
+//!         self.0 = Some((serialized.to_string(), version));
+//!         Ok(())
+//!     }
+//! }
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub theme: String = "light".to_string() => "theme",
+//!     },
+//!     "sync-doc-prefs"
+//! }
+//!
+//! let mut remote = Remote::default();
+//! let mut prefs = AppPrefs::load_testing();
+//! prefs.save_theme("dark".to_string()).unwrap();
+//! let report = prefs.sync(&mut remote, Resolution::MergeFields).unwrap();
+//! assert!(report.pushed);
+//! assert_eq!(remote.0.unwrap().1, 1);
+//! ```
+//!
+//! # Reconciling
+//!
+//! Each instance remembers the copy it last synced (storage key `<key>.sync`, next to the
+//! preferences). A field changed locally if it differs from that copy, and remotely if the
+//! remote copy differs from it. Fields changed on one side take that side's value; fields
+//! changed on both sides to different values are conflicts, settled by the [`Resolution`].
+//! Before its first sync, an instance counts the fields that differ from their defaults as
+//! changed locally, so a fresh install adopts the remote settings.
+//!
+//! Only exported fields are synced (see [`crate::export`]): secret, device-scoped, and
+//! `no_export` fields stay on the device. Remote values are checked against the field
+//! constraints; a remote copy violating them fails the sync without changing anything.
+//!
+//! # Versions
+//!
+//! Every push carries the pulled version plus one (1 for the first push). Providers with
+//! conditional writes should fail a push whose version isn't one more than the stored one;
+//! `sync()` then returns the error and can simply be called again.
+
+use crate::field::FieldInfo;
+use crate::storage::Storage;
+use crate::LoadError;
+use toml::{Table, Value};
+
+/// Location reported for errors in a pulled copy.
+pub const LOCATION: &str = "sync";
+
+/// A place holding the synced copy of the preferences.
+pub trait SyncProvider {
+    /// Returns the remote copy and its version, or `None` if nothing was pushed yet.
+    fn pull(&mut self) -> Result<Option<(String, u64)>, std::io::Error>;
+
+    /// Replaces the remote copy with `serialized` (a TOML document) at `version`.
+    fn push(&mut self, serialized: &str, version: u64) -> Result<(), std::io::Error>;
+}
+
+/// A field changed on both sides since the last sync.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldConflict<'a> {
+    /// Saved name of the field.
+    pub field: &'static str,
+    /// Value at the last sync (`None` for an absent value).
+    pub base: Option<&'a Value>,
+    /// Local value.
+    pub local: Option<&'a Value>,
+    /// Remote value.
+    pub remote: Option<&'a Value>,
+}
+
+/// How `sync()` settles fields changed on both sides.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Resolution {
+    /// The side syncing now wrote last: if any field conflicts, the local copy replaces the
+    /// remote one as a whole, dropping the other remote changes too. Without conflicts,
+    /// changes of both sides are kept.
+    LastWriteWins,
+    /// Keep the changes of both sides field by field; conflicting fields take the local value.
+    #[default]
+    MergeFields,
+    /// Like `MergeFields`, but the function picks the value of each conflicting field
+    /// (`None` for an absent value).
+    MergeFieldsWith(fn(&FieldConflict) -> Option<Value>),
+}
+
+/// What a sync did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Saved keys of the fields that took remote values and were saved locally.
+    pub pulled: Vec<&'static str>,
+    /// Saved keys of the fields changed on both sides.
+    pub conflicts: Vec<&'static str>,
+    /// Whether the reconciled copy was pushed.
+    pub pushed: bool,
+    /// Version of the remote copy after the sync.
+    pub version: u64,
+}
+
+/// The reconciled values of a sync in progress, from [`plan`] to [`finish`].
+#[derive(Debug)]
+pub struct Plan {
+    merged: Table,
+    /// Version to push, if the remote copy needs replacing.
+    push: Option<u64>,
+    /// The report so far.
+    pub report: SyncReport,
+}
+
+impl Plan {
+    /// Sets the pulled fields in `table`, the saved values of the instance.
+    pub fn apply(&self, table: &mut Table) {
+        for key in &self.report.pulled {
+            match self.merged.get(*key) {
+                Some(value) => table.insert(key.to_string(), value.clone()),
+                None => table.remove(*key),
+            };
+        }
+    }
+}
+
+/// Storage key of the last synced copy of the preferences under `storage_key`.
+pub fn state_key(storage_key: &str) -> String {
+    format!("{storage_key}.sync")
+}
+
+/// Reads the last synced copy and its version; an unreadable one is reported and ignored.
+fn read_state(storage: &dyn Storage, storage_key: &str) -> Option<(Table, u64)> {
+    let contents = match storage.read(&state_key(storage_key)) {
+        Ok(contents) => contents?,
+        Err(e) => {
+            crate::scrub::log(&[], None::<&()>, format_args!("Ignoring sync state: {}", e));
+            return None;
+        }
+    };
+    let mut state: Table = match contents.parse() {
+        Ok(state) => state,
+        Err(e) => {
+            crate::scrub::log(&[], None::<&()>, format_args!("Ignoring invalid sync state: {}", e));
+            return None;
+        }
+    };
+    let version = state.get("version").and_then(Value::as_integer)?;
+    let Some(Value::Table(values)) = state.remove("values") else {
+        return None;
+    };
+    Some((values, u64::try_from(version).unwrap_or(0)))
+}
+
+/// Pulls the remote copy and reconciles it with `local`, the saved values of the instance.
+/// `defaults` gives the saved defaults, the base before the first sync.
+pub fn plan(
+    provider: &mut dyn SyncProvider,
+    storage: &dyn Storage,
+    storage_key: &str,
+    fields: &[FieldInfo],
+    mut local: Table,
+    defaults: impl FnOnce() -> Table,
+    resolution: Resolution,
+) -> Result<Plan, LoadError> {
+    crate::export::retain_exported(&mut local, fields);
+    let Some((remote, version)) = provider.pull().map_err(LoadError::StorageError)? else {
+        let report = SyncReport { pushed: true, version: 1, ..Default::default() };
+        return Ok(Plan { merged: local, push: Some(1), report });
+    };
+    let mut remote: Table = remote
+        .parse()
+        .map_err(|e| LoadError::DeserializationError(LOCATION.to_string(), e))?;
+    crate::export::retain_exported(&mut remote, fields);
+
+    let base = match read_state(storage, storage_key) {
+        // The remote copy is the one we synced last.
+        Some((_, synced)) if synced == version => remote.clone(),
+        Some((base, _)) => base,
+        None => {
+            let mut defaults = defaults();
+            crate::export::retain_exported(&mut defaults, fields);
+            defaults
+        }
+    };
+
+    let mut merged = local.clone();
+    let mut report = SyncReport { version, ..Default::default() };
+    for field in fields.iter().filter(|field| field.options.is_exported()) {
+        let (base, local, remote) = (base.get(field.key), local.get(field.key), remote.get(field.key));
+        if remote == base || remote == local {
+            continue;
+        }
+        if let Some(value) = remote {
+            crate::validate::check_value(field, value).map_err(LoadError::ValidationError)?;
+        }
+        let value = if local == base {
+            remote.cloned()
+        } else {
+            report.conflicts.push(field.key);
+            match resolution {
+                Resolution::LastWriteWins | Resolution::MergeFields => local.cloned(),
+                Resolution::MergeFieldsWith(pick) => pick(&FieldConflict { field: field.key, base, local, remote }),
+            }
+        };
+        if value.as_ref() != local {
+            report.pulled.push(field.key);
+            match value {
+                Some(value) => merged.insert(field.key.to_string(), value),
+                None => merged.remove(field.key),
+            };
+        }
+    }
+    if matches!(resolution, Resolution::LastWriteWins) && !report.conflicts.is_empty() {
+        report.pulled.clear();
+        merged = local;
+    }
+
+    let push = (merged != remote).then(|| version.saturating_add(1));
+    report.pushed = push.is_some();
+    report.version = push.unwrap_or(version);
+    Ok(Plan { merged, push, report })
+}
+
+/// Pushes the reconciled copy if needed and remembers it as the last synced one. Call after
+/// the pulled fields were saved locally.
+pub fn finish(
+    provider: &mut dyn SyncProvider,
+    storage: &dyn Storage,
+    storage_key: &str,
+    plan: Plan,
+) -> Result<SyncReport, LoadError> {
+    let serialized = toml::to_string(&plan.merged).map_err(|e| LoadError::StorageError(std::io::Error::other(e)))?;
+    if let Some(version) = plan.push {
+        provider.push(&serialized, version).map_err(LoadError::StorageError)?;
+    }
+
+    let mut state = Table::new();
+    state.insert("version".to_string(), Value::Integer(i64::try_from(plan.report.version).unwrap_or(i64::MAX)));
+    state.insert("values".to_string(), Value::Table(plan.merged));
+    let state = toml::to_string(&state).map_err(|e| LoadError::StorageError(std::io::Error::other(e)))?;
+    let key = state_key(storage_key);
+    if storage.read(&key).map_err(LoadError::StorageError)?.as_deref() != Some(state.as_str()) {
+        storage.write(&key, &state).map_err(LoadError::StorageError)?;
+    }
+    Ok(plan.report)
+}
@@ -0,0 +1,168 @@
+//! Temporary overrides that revert on their own (`set_<field>_temporarily()`).
+//!
+//! An override changes a field until it expires, e.g. "snooze notifications for 2 hours":
+//!
+//! ```rust
+//! # use easy_prefs::easy_prefs;
+//! # use std::time::Duration;
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub notifications: bool = true => "notifications",
+//!     },
+//!     "app-prefs"
+//! }
+//!
+//! let mut prefs = AppPrefs::load_testing();
+//! prefs.set_notifications_temporarily(false, Duration::from_secs(2 * 60 * 60)).unwrap();
+//! assert!(!prefs.get_notifications());
+//! assert!(prefs.get_notifications_expiry().is_some());
+//! ```
+//!
+//! The override is saved together with its expiry and the value it replaced, in a reserved
+//! `_temporary` table:
+//!
+//! ```toml
+//! notifications = false
+//!
+//! [_temporary.notifications]
+//! expires_at = 1760000000  # seconds since the Unix epoch
+//! previous = true
+//! ```
+//!
+//! After the expiry, getters return the previous value and the next load or save puts it
+//! back in storage. Saving the field normally (`save_<field>()`, an edit guard) cancels the
+//! override. Secret fields can't be overridden: their previous value would end up in the
+//! table, outside the secret store.
+
+use crate::field::FieldInfo;
+use crate::validate::ValidationError;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use toml::{Table, Value};
+use web_time::{SystemTime, UNIX_EPOCH};
+
+/// Key of the table holding the overrides in the stored document.
+pub const TABLE_KEY: &str = "_temporary";
+
+/// A field value that reverts to `previous` at `expires`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Override<T> {
+    /// The value to go back to.
+    pub previous: T,
+    /// When the override ends.
+    pub expires: SystemTime,
+}
+
+impl<T> Override<T> {
+    /// Creates an override ending `duration` from now.
+    pub fn new(previous: T, duration: Duration) -> Self {
+        Self {
+            previous,
+            expires: SystemTime::now() + duration,
+        }
+    }
+
+    /// Whether the override has ended.
+    pub fn is_expired(&self) -> bool {
+        SystemTime::now() >= self.expires
+    }
+}
+
+// `previous` is left out for `None`, like any other `None` value.
+#[derive(Serialize)]
+struct StoredEntry<'a, T> {
+    expires_at: u64,
+    previous: &'a T,
+}
+
+#[derive(Deserialize)]
+struct StoredPrevious<T> {
+    previous: T,
+}
+
+/// Rejects overrides of secret fields.
+pub fn check_field(field: &FieldInfo) -> Result<(), ValidationError> {
+    if field.options.secret {
+        return Err(ValidationError {
+            key: field.key,
+            reason: "secret fields can't be overridden temporarily".to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn expires_at(entry: &Value) -> Option<SystemTime> {
+    let seconds = entry.get("expires_at")?.as_integer()?;
+    Some(UNIX_EPOCH + Duration::from_secs(u64::try_from(seconds).ok()?))
+}
+
+/// Moves the overrides out of a stored table, putting back the previous values of expired ones.
+///
+/// Returns the active overrides for [`read`].
+pub fn take(table: &mut Table) -> Table {
+    let Some(Value::Table(mut overrides)) = table.remove(TABLE_KEY) else {
+        return Table::new();
+    };
+    overrides.retain(|key, entry| match expires_at(entry) {
+        Some(expires) if SystemTime::now() < expires => true,
+        Some(_) => {
+            match entry.get("previous") {
+                Some(previous) => table.insert(key.to_string(), previous.clone()),
+                None => table.remove(key),
+            };
+            false
+        }
+        None => {
+            crate::scrub::log(&[], None::<&()>, format_args!("Ignoring malformed temporary override for '{key}'"));
+            false
+        }
+    });
+    overrides
+}
+
+/// Reads the active override for `key` from the table returned by [`take`].
+pub fn read<T: DeserializeOwned>(overrides: &Table, key: &str) -> Option<Override<T>> {
+    let entry = overrides.get(key)?;
+    let expires = expires_at(entry)?;
+    match entry.clone().try_into::<StoredPrevious<T>>() {
+        Ok(StoredPrevious { previous }) => Some(Override { previous, expires }),
+        Err(e) => {
+            crate::scrub::log(&[], None::<&()>, format_args!("Ignoring temporary override for '{key}': {e}"));
+            None
+        }
+    }
+}
+
+/// Adds an override to a table being saved; an expired one puts back its previous value instead.
+pub fn store<T: Serialize>(
+    table: &mut Table,
+    key: &str,
+    entry: Option<&Override<T>>,
+) -> Result<(), toml::ser::Error> {
+    let Some(entry) = entry else {
+        return Ok(());
+    };
+    let expires_at = entry
+        .expires
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let mut stored = Table::try_from(StoredEntry {
+        expires_at,
+        previous: &entry.previous,
+    })?;
+
+    if entry.is_expired() {
+        match stored.remove("previous") {
+            Some(previous) => table.insert(key.to_string(), previous),
+            None => table.remove(key),
+        };
+    } else if let Value::Table(overrides) = table
+        .entry(TABLE_KEY)
+        .or_insert_with(|| Value::Table(Table::new()))
+    {
+        overrides.insert(key.to_string(), Value::Table(stored));
+    }
+    Ok(())
+}
@@ -0,0 +1,62 @@
+//! All-or-nothing edits with `transaction()`.
+//!
+//! An edit guard keeps its changes in memory even when saving them fails, so the values can
+//! end up ahead of storage. `transaction(|guard| ...)` runs a closure on an edit guard and
+//! keeps its changes only if the closure returns `Ok` and the save succeeds; otherwise every
+//! field is rolled back, also when the closure panics:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//! use easy_prefs::transaction::TxError;
+//!
+//! easy_prefs! {
+//!     pub struct AccountPrefs {
+//!         pub username: String = String::new() => "username",
+//!         pub server: String = String::new() => "server",
+//!     },
+//!     "transaction-doc-prefs"
+//! }
+//!
+//! let mut prefs = AccountPrefs::load_testing();
+//! let result = prefs.transaction(|guard| {
+//!     guard.set_username("alice".to_string());
+//!     if guard.get_server().is_empty() {
+//!         return Err("no server configured");
+//!     }
+//!     Ok(())
+//! });
+//! assert!(matches!(result, Err(TxError::Aborted("no server configured"))));
+//! assert_eq!(prefs.get_username(), "");
+//! ```
+//!
+//! Rolled-back fields are restored as they would load from their saved values, with their
+//! temporary overrides, like `cancel()` on an edit guard.
+
+use crate::SaveError;
+
+/// Error of a transaction, whose changes were rolled back.
+#[derive(Debug)]
+pub enum TxError<E> {
+    /// The closure returned an error.
+    Aborted(E),
+    /// The closure succeeded but saving its changes failed.
+    Save(SaveError),
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for TxError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Aborted(e) => write!(f, "transaction aborted: {e}"),
+            Self::Save(e) => write!(f, "transaction not saved: {e}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for TxError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Aborted(e) => Some(e),
+            Self::Save(e) => Some(e),
+        }
+    }
+}
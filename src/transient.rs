@@ -0,0 +1,44 @@
+//! Transient fields: runtime state kept with the preferences but never stored.
+//!
+//! A field marked `(transient)` has the usual accessors, but saving leaves it out of the stored
+//! document, and every load or reload starts it at its default. It suits values derived from
+//! other fields, which `on_after_load` can compute:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! fn hash_block_list(prefs: &mut FilterPrefs) {
+//!     // Assigned directly: setters would save.
+//!     prefs._block_list_hash = prefs._block_list.bytes().fold(0, |hash, b| hash.rotate_left(5) ^ u32::from(b));
+//! }
+//!
+//! easy_prefs! {
+//!     pub struct FilterPrefs {
+//!         pub block_list: String = String::new() => "block_list",
+//!         pub block_list_hash: u32 = 0 => "block_list_hash" (transient),
+//!     },
+//!     "transient-doc-prefs",
+//!     on_after_load = hash_block_list
+//! }
+//!
+//! let mut prefs = FilterPrefs::load_testing();
+//! prefs.save_block_list("ads.example.com".to_string()).unwrap();
+//! let stored = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+//! assert!(!stored.contains("block_list_hash"));
+//!
+//! prefs.reload().unwrap();
+//! assert_ne!(*prefs.get_block_list_hash(), 0);
+//! ```
+//!
+//! Values stored under the key of a transient field (say, from before it became transient)
+//! are ignored. Transient fields are left out of exports, imports, and sync, like `no_export`
+//! ones. The field type still needs `Serialize` and `Deserialize`, and its values must serialize
+//! to TOML like those of stored fields.
+
+use crate::field::FieldInfo;
+use toml::Table;
+
+/// Removes the values of transient fields from `table`.
+pub fn remove(table: &mut Table, fields: &[FieldInfo]) {
+    table.retain(|key, _| !fields.iter().any(|field| field.key == key && field.options.transient));
+}
@@ -0,0 +1,210 @@
+//! Per-field value constraints declared through field options.
+//!
+//! Constraints are checked in two places:
+//! - **On set**: `save_<field>()` and the edit guard's `try_set_<field>()` reject invalid values
+//!   with a [`ValidationError`].
+//! - **On load**: what happens to an invalid stored value depends on the field's
+//!   [`OnInvalid`] mode: by default it is dropped so the field falls back to its default;
+//!   `(on_invalid = clamp)` moves a number into range instead, and `(on_invalid = error)` fails
+//!   the load with `LoadError::ValidationError`.
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct EditorPrefs {
+//!         pub font_size: i32 = 14 => "font_size" (range = 8..=72, on_invalid = clamp),
+//!     },
+//!     "validate-doc-prefs"
+//! }
+//!
+//! let mut prefs = EditorPrefs::load_testing();
+//! assert!(prefs.save_font_size(-3).is_err());
+//!
+//! std::fs::write(prefs.get_preferences_file_path(), "font_size = -3").unwrap();
+//! prefs.reload().unwrap();
+//! assert_eq!(*prefs.get_font_size(), 8);
+//! ```
+//!
+//! Struct-level validators (`validate_struct = my_fn`) run before every save and after every
+//! load; failures surface as `LoadError::ValidationError` on load.
+
+use crate::field::FieldInfo;
+use serde::Serialize;
+use toml::{Table, Value};
+
+/// A value violated a field's declared constraints or a struct-level validator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// Saved key of the offending field; empty for struct-level validation.
+    pub key: &'static str,
+    /// Human-readable explanation.
+    pub reason: String,
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.key.is_empty() {
+            write!(f, "invalid preferences: {}", self.reason)
+        } else {
+            write!(f, "invalid value for '{}': {}", self.key, self.reason)
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl From<ValidationError> for std::io::Error {
+    fn from(e: ValidationError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, e)
+    }
+}
+
+/// What loading does with a stored value that violates its field's constraints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnInvalid {
+    /// Drop the value so the field falls back to its default (`on_invalid = default`).
+    #[default]
+    UseDefault,
+    /// Move a number to the nearest allowed value (`on_invalid = clamp`). Values that can't
+    /// be clamped, such as a string outside `choices`, fall back to the default.
+    Clamp,
+    /// Fail the load with `LoadError::ValidationError` (`on_invalid = error`).
+    Error,
+}
+
+/// A stored value that was replaced on load.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Repair {
+    /// Why the stored value was invalid.
+    pub error: ValidationError,
+    /// The value it was clamped to, or `None` if the default applies.
+    pub clamped: Option<Value>,
+}
+
+impl std::fmt::Display for Repair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.clamped {
+            Some(value) => write!(f, "{}, clamped to {value}", self.error),
+            None => write!(f, "{}, using default", self.error),
+        }
+    }
+}
+
+/// Checks a typed value against the constraints of `field`.
+///
+/// Values that cannot be represented as TOML are not checked here; serialization reports them.
+pub fn check<T: Serialize + ?Sized>(field: &FieldInfo, value: &T) -> Result<(), ValidationError> {
+    if !field.options.has_constraints() {
+        return Ok(());
+    }
+    match Value::try_from(value) {
+        Ok(value) => check_value(field, &value),
+        Err(_) => Ok(()),
+    }
+}
+
+/// Checks a TOML value against the constraints of `field`.
+pub fn check_value(field: &FieldInfo, value: &Value) -> Result<(), ValidationError> {
+    // Errors end up in logs; keep secret values out of them.
+    let shown = if field.options.secret { crate::secrets::REDACTED.to_string() } else { value.to_string() };
+    if let (Some(choices), Value::String(s)) = (field.options.choices, value) {
+        if !choices.contains(&s.as_str()) {
+            return Err(ValidationError {
+                key: field.key,
+                reason: format!("{shown} is not one of {choices:?}"),
+            });
+        }
+    }
+
+    let number = match value {
+        Value::Integer(i) => *i as f64,
+        Value::Float(f) => *f,
+        _ => return Ok(()),
+    };
+    let options = &field.options;
+    if let Some(min) = options.min.filter(|min| number < *min) {
+        return Err(ValidationError {
+            key: field.key,
+            reason: format!("{shown} is below the minimum of {min}"),
+        });
+    }
+    if let Some(max) = options.max.filter(|max| number > *max) {
+        return Err(ValidationError {
+            key: field.key,
+            reason: format!("{shown} is above the maximum of {max}"),
+        });
+    }
+    if let Some(step) = options.step.filter(|step| *step > 0.0) {
+        let steps = (number - options.min.unwrap_or(0.0)) / step;
+        if (steps - steps.round()).abs() > 1e-9 {
+            return Err(ValidationError {
+                key: field.key,
+                reason: format!("{shown} is not a multiple of the step {step}"),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Runs a struct-level validator, if one is configured.
+pub fn check_struct<T>(
+    validator: Option<crate::options::StructValidator<T>>,
+    prefs: &T,
+) -> Result<(), ValidationError> {
+    match validator {
+        Some(validator) => validator(prefs).map_err(|reason| ValidationError { key: "", reason }),
+        None => Ok(()),
+    }
+}
+
+/// Repairs stored values that violate their field's constraints according to each field's
+/// [`OnInvalid`] mode.
+///
+/// Returns the repairs made, or the first violation of a field with `OnInvalid::Error`.
+pub fn sanitize_table(table: &mut Table, fields: &[FieldInfo]) -> Result<Vec<Repair>, ValidationError> {
+    let mut repairs = Vec::new();
+    for field in fields.iter().filter(|f| f.options.has_constraints()) {
+        let Some(value) = table.get(field.key) else {
+            continue;
+        };
+        let Err(error) = check_value(field, value) else {
+            continue;
+        };
+        let clamped = match field.options.on_invalid {
+            OnInvalid::Error => return Err(error),
+            OnInvalid::Clamp => clamp_value(field, value),
+            OnInvalid::UseDefault => None,
+        };
+        match &clamped {
+            Some(value) => table.insert(field.key.to_string(), value.clone()),
+            None => table.remove(field.key),
+        };
+        repairs.push(Repair { error, clamped });
+    }
+    Ok(repairs)
+}
+
+/// Returns the allowed number closest to `value` for `field`, keeping integers integers, or
+/// `None` if `value` is not a number or no allowed value is found.
+pub fn clamp_value(field: &FieldInfo, value: &Value) -> Option<Value> {
+    let options = &field.options;
+    let number = match value {
+        Value::Integer(i) => *i as f64,
+        Value::Float(f) => *f,
+        _ => return None,
+    };
+    let mut clamped = number.clamp(options.min.unwrap_or(f64::MIN), options.max.unwrap_or(f64::MAX));
+    if let Some(step) = options.step.filter(|step| *step > 0.0) {
+        let base = options.min.unwrap_or(0.0);
+        clamped = base + ((clamped - base) / step).round() * step;
+        if options.max.is_some_and(|max| clamped > max) {
+            clamped -= step;
+        }
+    }
+    let clamped = match value {
+        Value::Integer(_) => Value::Integer(clamped.round() as i64),
+        _ => Value::Float(clamped),
+    };
+    check_value(field, &clamped).ok().map(|()| clamped)
+}
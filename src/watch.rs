@@ -0,0 +1,98 @@
+//! Per-field change channels (`watch` feature).
+//!
+//! With the feature enabled, generated structs get `watch_<field>()`, returning a
+//! [`tokio::sync::watch::Receiver`] that sees the field's value every time it changes:
+//!
+//! ```rust
+//! # #[cfg(feature = "watch")]
+//! # {
+//! # use easy_prefs::easy_prefs;
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub theme: String = "light".to_string() => "theme",
+//!     },
+//!     "app-prefs"
+//! }
+//!
+//! let mut prefs = AppPrefs::load_testing();
+//! let mut theme = prefs.watch_theme();
+//! prefs.save_theme("dark".to_string()).unwrap();
+//! assert!(theme.has_changed().unwrap());
+//! assert_eq!(*theme.borrow_and_update(), "dark");
+//! # }
+//! ```
+//!
+//! Receivers are updated whenever the preferences are written or reloaded, including saves
+//! from edit guards, `set_<field>_from_str()`, and the IPC, D-Bus, MQTT, and live sync
+//! integrations. A save that fails to write, or saving an unchanged value, does not wake
+//! receivers. A temporary override that expires shows up on the next save or reload.
+
+#[cfg(feature = "watch")]
+use std::sync::OnceLock;
+
+/// The channel behind `watch_<field>()`, created by the first call.
+#[cfg(feature = "watch")]
+pub struct Watch<T> {
+    channel: OnceLock<Channel<T>>,
+}
+
+#[cfg(feature = "watch")]
+struct Channel<T> {
+    sender: tokio::sync::watch::Sender<T>,
+    // Captured where `T: Clone` is known, so `notify()` works for every field type.
+    clone: fn(&T) -> T,
+}
+
+#[cfg(feature = "watch")]
+impl<T> Watch<T> {
+    /// Returns a receiver whose current value is `value`.
+    pub fn subscribe(&self, value: &T) -> tokio::sync::watch::Receiver<T>
+    where
+        T: Clone,
+    {
+        let channel = self.channel.get_or_init(|| Channel {
+            sender: tokio::sync::watch::Sender::new(value.clone()),
+            clone: T::clone,
+        });
+        channel.sender.subscribe()
+    }
+
+    /// Sends `value` to receivers if it differs from the last value sent.
+    pub fn notify(&self, value: &T)
+    where
+        T: PartialEq,
+    {
+        if let Some(channel) = self.channel.get() {
+            channel.sender.send_if_modified(|current| {
+                if current == value {
+                    return false;
+                }
+                *current = (channel.clone)(value);
+                true
+            });
+        }
+    }
+}
+
+/// Placeholder for `watch_<field>()` when the `watch` feature is disabled.
+#[cfg(not(feature = "watch"))]
+pub struct Watch<T> {
+    _marker: std::marker::PhantomData<fn() -> T>,
+}
+
+#[cfg(not(feature = "watch"))]
+impl<T> Watch<T> {
+    /// Does nothing without the `watch` feature.
+    pub fn notify(&self, _value: &T) {}
+}
+
+impl<T> Default for Watch<T> {
+    fn default() -> Self {
+        Self {
+            #[cfg(feature = "watch")]
+            channel: OnceLock::new(),
+            #[cfg(not(feature = "watch"))]
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
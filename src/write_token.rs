@@ -0,0 +1,96 @@
+//! App-wide write token used to serialize preference writers.
+//!
+//! Large applications sometimes want a runtime guarantee that only one subsystem mutates
+//! settings at a time. The broker in this module hands out a single [`WriteToken`] per process.
+//! When enforcement is switched on with [`set_enforced`], generated `edit()` methods panic and
+//! callers must use `edit_with_token(&token)` instead, proving they hold the token.
+//!
+//! ```rust
+//! use easy_prefs::write_token::WriteToken;
+//!
+//! let token = WriteToken::acquire("settings-dialog");
+//! assert_eq!(token.owner(), "settings-dialog");
+//! assert!(WriteToken::try_acquire("sync-service").is_none());
+//! drop(token);
+//! assert!(WriteToken::try_acquire("sync-service").is_some());
+//! ```
+
+use once_cell::sync::Lazy;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex};
+
+// Name of the current token holder, plus a condition variable to wake waiting writers.
+static BROKER: Lazy<(Mutex<Option<String>>, Condvar)> =
+    Lazy::new(|| (Mutex::new(None), Condvar::new()));
+
+static ENFORCED: AtomicBool = AtomicBool::new(false);
+
+/// Proof that the holder is the only writer allowed to open edit guards.
+///
+/// The token is released when dropped.
+#[derive(Debug)]
+pub struct WriteToken {
+    owner: String,
+}
+
+impl WriteToken {
+    /// Acquires the token, blocking until the current holder releases it.
+    ///
+    /// `owner` names the subsystem and shows up in [`current_holder`] for debugging.
+    pub fn acquire(owner: &str) -> Self {
+        let (lock, released) = &*BROKER;
+        let mut holder = lock.lock().unwrap_or_else(|e| e.into_inner());
+        while holder.is_some() {
+            holder = released.wait(holder).unwrap_or_else(|e| e.into_inner());
+        }
+        *holder = Some(owner.to_string());
+        Self {
+            owner: owner.to_string(),
+        }
+    }
+
+    /// Acquires the token if nobody holds it; returns `None` otherwise.
+    pub fn try_acquire(owner: &str) -> Option<Self> {
+        let (lock, _) = &*BROKER;
+        let mut holder = lock.lock().unwrap_or_else(|e| e.into_inner());
+        if holder.is_some() {
+            return None;
+        }
+        *holder = Some(owner.to_string());
+        Some(Self {
+            owner: owner.to_string(),
+        })
+    }
+
+    /// The subsystem name this token was acquired for.
+    pub fn owner(&self) -> &str {
+        &self.owner
+    }
+}
+
+impl Drop for WriteToken {
+    fn drop(&mut self) {
+        let (lock, released) = &*BROKER;
+        let mut holder = lock.lock().unwrap_or_else(|e| e.into_inner());
+        *holder = None;
+        released.notify_one();
+    }
+}
+
+/// Returns the owner name of the current token holder, if any.
+pub fn current_holder() -> Option<String> {
+    let (lock, _) = &*BROKER;
+    lock.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Turns enforcement on or off for the whole process.
+///
+/// While enforced, `edit()` panics and `edit_with_token()` must be used instead.
+pub fn set_enforced(enforced: bool) {
+    ENFORCED.store(enforced, Ordering::Release);
+}
+
+/// Returns whether write tokens are currently enforced.
+pub fn is_enforced() -> bool {
+    ENFORCED.load(Ordering::Acquire)
+}
@@ -0,0 +1,97 @@
+//! An optional writer thread performing the process's preferences writes one at a time.
+//!
+//! Each save normally writes from the thread that made it, so when many structs save at once
+//! (several threads shutting down together, say) their temporary files and renames interleave.
+//! With [`set_enabled`], writes of loaded preferences (saves, backups, history entries, and
+//! [`flush_all()`](crate::flush_all)) are queued to a single background thread instead, which
+//! performs them in the order they were queued. A save still waits for its write and returns
+//! its error, so callers see no difference:
+//!
+//! ```rust
+//! use easy_prefs::easy_prefs;
+//!
+//! easy_prefs! {
+//!     pub struct AppPrefs {
+//!         pub theme: String = "light".to_string() => "theme",
+//!     },
+//!     "writer-doc-prefs"
+//! }
+//!
+//! easy_prefs::writer::set_enabled(true);
+//!
+//! let mut prefs = AppPrefs::load_testing();
+//! prefs.save_theme("dark".to_string()).unwrap(); // written by the writer thread
+//! ```
+//!
+//! Writes made while loading, before an instance is registered, don't go through the queue.
+//! The thread starts with the first queued write and lives as long as the process.
+
+use crate::storage::Storage;
+use once_cell::sync::Lazy;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+
+/// A queued write and where to send its result.
+struct Job {
+    storage: Arc<dyn Storage>,
+    key: String,
+    data: String,
+    done: Sender<std::io::Result<()>>,
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static QUEUE: Lazy<Mutex<Sender<Job>>> = Lazy::new(|| Mutex::new(spawn()));
+
+thread_local! {
+    static ON_WRITER: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+/// Starts the writer thread, returning its queue.
+fn spawn() -> Sender<Job> {
+    let (queue, jobs) = mpsc::channel::<Job>();
+    std::thread::Builder::new()
+        .name("easy_prefs-writer".to_string())
+        .spawn(move || {
+            ON_WRITER.with(|on_writer| on_writer.set(true));
+            for job in jobs {
+                // A panicking backend fails its write without stopping the queue.
+                let result = catch_unwind(AssertUnwindSafe(|| job.storage.write(&job.key, &job.data)))
+                    .unwrap_or_else(|_| Err(std::io::Error::other("storage panicked while writing")));
+                let _ = job.done.send(result);
+            }
+        })
+        .expect("failed to spawn the easy_prefs writer thread");
+    queue
+}
+
+/// Turns the writer thread on or off for the whole process.
+///
+/// Writes already queued are still performed after it is turned off.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Release);
+}
+
+/// Returns whether writes are queued to the writer thread.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Acquire)
+}
+
+/// Writes `data` under `key`, through the writer thread if it is enabled, and waits for the
+/// result.
+pub(crate) fn write(storage: &Arc<dyn Storage>, key: &str, data: &str) -> std::io::Result<()> {
+    // Backends writing through other loaded preferences would wait for themselves.
+    if !is_enabled() || ON_WRITER.with(|on_writer| on_writer.get()) {
+        return storage.write(key, data);
+    }
+    let (done, result) = mpsc::channel();
+    let job = Job { storage: storage.clone(), key: key.to_string(), data: data.to_string(), done };
+    QUEUE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .send(job)
+        .map_err(|_| std::io::Error::other("the easy_prefs writer thread stopped"))?;
+    result.recv().unwrap_or_else(|_| Err(std::io::Error::other("the easy_prefs writer thread stopped")))
+}
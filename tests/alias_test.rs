@@ -0,0 +1,144 @@
+use easy_prefs::dynamic::DynamicPrefs;
+use easy_prefs::easy_prefs;
+use toml::Value;
+
+/// Stores seconds as a string such as `"45s"`.
+mod seconds {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(seconds: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{seconds}s"))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.trim_end_matches('s').parse().map_err(serde::de::Error::custom)
+    }
+}
+
+easy_prefs! {
+    pub struct AliasPrefs {
+        #[serde(with = "seconds")]
+        pub timeout: u64 = 30 => "timeout",
+        /// Accent color.
+        #[serde(alias = "colour", alias = "accent")]
+        pub color: String = "red".to_string() => "color",
+    },
+    "alias-prefs",
+    strict = true
+}
+
+easy_prefs! {
+    pub struct PreservedAliasPrefs {
+        #[serde(alias = "old_volume")]
+        pub volume: i32 = 5 => "volume",
+    },
+    "preserved-alias-prefs",
+    preserve_formatting = true
+}
+
+easy_prefs! {
+    pub struct RenamedKeyPrefs {
+        pub font_size: i32 = 14 => "font_size" (alias "fontSize", alias = "text_size", range = 8..=72),
+        pub size: i32 = 1 => "size",
+    },
+    "renamed-key-prefs",
+    strict = true
+}
+
+easy_prefs! {
+    pub struct OldKeyPrefs {
+        pub font_size: i32 = 14 => "text_size",
+    },
+    "evolved-key-prefs"
+}
+
+easy_prefs! {
+    pub struct EvolvedKeyPrefs {
+        pub font_size: i32 = 14 => "font_size" (alias "text_size"),
+    },
+    "evolved-key-prefs"
+}
+
+#[test]
+fn test_aliases_are_recorded() {
+    assert_eq!(AliasPrefs::FIELDS[0].aliases, &[] as &[&str]);
+    assert_eq!(AliasPrefs::FIELDS[1].aliases, &["colour", "accent"]);
+    assert_eq!(AliasPrefs::FIELDS[1].description(), "Accent color.");
+    assert_eq!(RenamedKeyPrefs::FIELDS[0].aliases, &["fontSize", "text_size"]);
+    assert_eq!(RenamedKeyPrefs::FIELDS[0].options.max, Some(72.0));
+}
+
+#[test]
+fn test_alias_option_keeps_renamed_values() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+    let path = tmp.path().join("renamed-key-prefs.toml");
+    std::fs::write(&path, "fontSize = 18\nsize = 3\n").unwrap();
+
+    let mut prefs = RenamedKeyPrefs::load_with_error(dir).unwrap();
+    assert_eq!(*prefs.get_font_size(), 18);
+    assert_eq!(*prefs.get_size(), 3);
+
+    prefs.save_font_size(20).unwrap();
+    let stored = std::fs::read_to_string(&path).unwrap();
+    assert!(stored.starts_with("font_size = 20\nsize = 3\n"), "{stored}");
+}
+
+#[test]
+fn test_alias_reads_values_saved_by_older_versions() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+    OldKeyPrefs::load_with_error(dir).unwrap().save_font_size(18).unwrap();
+
+    let prefs = EvolvedKeyPrefs::load_with_error(dir).unwrap();
+    assert_eq!(*prefs.get_font_size(), 18);
+}
+
+#[test]
+fn test_custom_format_and_legacy_key() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+    let path = tmp.path().join("alias-prefs.toml");
+    std::fs::write(&path, "timeout = \"45s\"\ncolour = \"blue\"\n").unwrap();
+
+    let mut prefs = AliasPrefs::load_with_error(dir).unwrap();
+    assert_eq!(*prefs.get_timeout(), 45);
+    assert_eq!(prefs.get_color(), "blue");
+    assert!(prefs.lint().unwrap().is_clean());
+
+    // Dynamic access works on the stored form.
+    assert_eq!(prefs.get_value("timeout"), Some(Value::String("45s".to_string())));
+    assert_eq!(prefs.get_timeout_as_string(), "45s");
+    prefs.set_timeout_from_str("50s").unwrap();
+    assert_eq!(*prefs.get_timeout(), 50);
+    prefs.set_value("timeout", Value::Integer(55)).unwrap();
+    assert_eq!(*prefs.get_timeout(), 55);
+    assert!(prefs.set_timeout_from_str("soon").is_err());
+
+    let stored = std::fs::read_to_string(&path).unwrap();
+    assert!(stored.contains("timeout = \"55s\"") && stored.contains("color = \"blue\""), "{stored}");
+    assert!(!stored.contains("colour"), "{stored}");
+    drop(prefs);
+
+    // The saved name wins over an alias.
+    std::fs::write(&path, "color = \"green\"\naccent = \"blue\"\n").unwrap();
+    let prefs = AliasPrefs::load_with_error(dir).unwrap();
+    assert_eq!(prefs.get_color(), "green");
+}
+
+#[test]
+fn test_preserved_formatting_renames_aliases() {
+    let mut prefs = PreservedAliasPrefs::load_testing();
+    let path = prefs.get_preferences_file_path();
+    std::fs::write(&path, "old_volume = 7\nadded_by_newer_version = true\n").unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(*prefs.get_volume(), 7);
+
+    prefs.save_volume(8).unwrap();
+    let stored = std::fs::read_to_string(&path).unwrap();
+    assert!(stored.contains("volume = 8\n") && stored.contains("added_by_newer_version"), "{stored}");
+    assert!(!stored.contains("old_volume"), "{stored}");
+    prefs.reload().unwrap();
+    assert_eq!(*prefs.get_volume(), 8);
+}
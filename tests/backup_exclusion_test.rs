@@ -0,0 +1,49 @@
+#![cfg(target_os = "linux")]
+
+use easy_prefs::easy_prefs;
+use easy_prefs::storage::native::FileStorage;
+use easy_prefs::storage::Storage;
+
+easy_prefs! {
+    struct DevicePrefs {
+        pub device_id: String = String::new() => "device_id",
+    },
+    "device-prefs",
+    exclude_from_backup = true,
+    backups = 1
+}
+
+/// The `user.xdg.robots.backup` attribute of `path`, or `None` if the file has none. Panics
+/// if the filesystem doesn't support user attributes.
+fn robots_backup(path: impl AsRef<std::path::Path>) -> Option<String> {
+    let mut value = [0u8; 16];
+    match rustix::fs::getxattr(path.as_ref(), "user.xdg.robots.backup", &mut value[..]) {
+        Ok(len) => Some(String::from_utf8_lossy(&value[..len]).into_owned()),
+        Err(rustix::io::Errno::NODATA) => None,
+        Err(e) => panic!("cannot read attributes of {}: {e}", path.as_ref().display()),
+    }
+}
+
+#[test]
+fn test_file_storage_backup_exclusion() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut storage = FileStorage::new(tmp.path().to_str().unwrap());
+    storage.write("default.toml", "a = 1\n").unwrap();
+    assert_eq!(robots_backup(tmp.path().join("default.toml")), None);
+
+    storage.set_backup_exclusion(true);
+    storage.write("default.toml", "a = 2\n").unwrap();
+    assert_eq!(robots_backup(tmp.path().join("default.toml")).as_deref(), Some("false"));
+}
+
+#[test]
+fn test_exclude_from_backup_option() {
+    const { assert!(DevicePrefs::OPTIONS.exclude_from_backup) };
+    let tmp = tempfile::tempdir().unwrap();
+    let mut prefs = DevicePrefs::load_with_error(tmp.path().to_str().unwrap()).unwrap();
+    prefs.save_device_id("first".to_string()).unwrap();
+    prefs.save_device_id("second".to_string()).unwrap();
+    let path = prefs.get_preferences_file_path();
+    assert_eq!(robots_backup(&path).as_deref(), Some("false"));
+    assert_eq!(robots_backup(format!("{path}.bak")).as_deref(), Some("false"));
+}
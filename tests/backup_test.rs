@@ -0,0 +1,102 @@
+use easy_prefs::backup::backup_key;
+use easy_prefs::storage::{MemoryStorage, Storage};
+use easy_prefs::{easy_prefs, LoadError, LoadOutcome};
+
+easy_prefs! {
+    struct BackupPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub volume: i32 = 5 => "volume",
+    },
+    "backup-prefs",
+    backups = 2
+}
+
+easy_prefs! {
+    struct NoBackupPrefs {
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "no-backup-prefs"
+}
+
+fn read(dir: &tempfile::TempDir, name: &str) -> Option<String> {
+    std::fs::read_to_string(dir.path().join(name)).ok()
+}
+
+#[test]
+fn test_backups_rotate() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut prefs = BackupPrefs::load_with_error(dir.path().to_str().unwrap()).unwrap();
+
+    prefs.save_theme("one".to_string()).unwrap();
+    assert_eq!(read(&dir, "backup-prefs.toml.bak"), None, "nothing to back up yet");
+    prefs.save_theme("two".to_string()).unwrap();
+    prefs.save_theme("three".to_string()).unwrap();
+    prefs.save_theme("four".to_string()).unwrap();
+
+    assert!(read(&dir, "backup-prefs.toml.bak").unwrap().contains("three"));
+    assert!(read(&dir, "backup-prefs.toml.bak.2").unwrap().contains("two"));
+    assert_eq!(read(&dir, "backup-prefs.toml.bak.3"), None);
+
+    // Saving unchanged values doesn't push older backups out.
+    prefs.save().unwrap();
+    prefs.save().unwrap();
+    assert!(read(&dir, "backup-prefs.toml.bak").unwrap().contains("four"));
+    assert!(read(&dir, "backup-prefs.toml.bak.2").unwrap().contains("three"));
+}
+
+#[test]
+fn test_recover_from_newest_valid_backup() {
+    let storage = MemoryStorage::new();
+    let key = BackupPrefs::PREFERENCES_FILENAME;
+    storage.write(key, "theme = \"da").unwrap();
+    storage.write(&backup_key(key, 1), "volume = \"loud\"\n").unwrap();
+    storage.write(&backup_key(key, 2), "theme = \"dark\"\nvolume = 7\n").unwrap();
+
+    let prefs = BackupPrefs::load_with_storage(Box::new(storage)).unwrap();
+    assert_eq!(prefs.get_theme(), "dark");
+    assert_eq!(*prefs.get_volume(), 7);
+    match prefs.load_outcome() {
+        LoadOutcome::RecoveredFromBackup { backup, error } => {
+            assert_eq!(backup, "memory::backup-prefs.toml.bak.2");
+            assert!(error.contains("deserialization error"));
+        }
+        other => panic!("expected a recovery, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_no_usable_backup() {
+    let storage = MemoryStorage::new();
+    storage.write(BackupPrefs::PREFERENCES_FILENAME, "theme = ").unwrap();
+    assert!(matches!(
+        BackupPrefs::load_with_storage(Box::new(storage)),
+        Err(LoadError::DeserializationError(..))
+    ));
+
+    let storage = MemoryStorage::new();
+    let key = NoBackupPrefs::PREFERENCES_FILENAME;
+    storage.write(key, "theme = ").unwrap();
+    storage.write(&backup_key(key, 1), "theme = \"dark\"\n").unwrap();
+    assert!(
+        NoBackupPrefs::load_with_storage(Box::new(storage)).is_err(),
+        "backups are only used when enabled"
+    );
+}
+
+#[test]
+fn test_reload_recovers_and_save_repairs() {
+    let mut prefs = BackupPrefs::load_testing();
+    assert_eq!(prefs.load_outcome(), &LoadOutcome::Loaded);
+    prefs.save_volume(8).unwrap();
+    prefs.save_volume(9).unwrap();
+
+    let path = prefs.get_preferences_file_path();
+    std::fs::write(&path, "volume = 9\ntheme = [").unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(*prefs.get_volume(), 8);
+    assert!(matches!(prefs.load_outcome(), LoadOutcome::RecoveredFromBackup { .. }));
+
+    prefs.save().unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(prefs.load_outcome(), &LoadOutcome::Loaded);
+}
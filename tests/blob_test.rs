@@ -0,0 +1,68 @@
+use easy_prefs::easy_prefs;
+
+easy_prefs! {
+    struct StyledPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub custom_css: String = String::new() => "custom_css" (external),
+    },
+    "blob-prefs"
+}
+
+fn blob_path(prefs: &StyledPrefs) -> String {
+    format!("{}.custom_css", prefs.get_preferences_file_path())
+}
+
+#[test]
+fn test_large_value_goes_to_sibling_file() {
+    let mut prefs = StyledPrefs::load_testing();
+    let css = "body { color: teal; }\n".repeat(500);
+    prefs.save_custom_css(css.clone()).unwrap();
+
+    let contents = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+    assert!(contents.contains("[custom_css]"));
+    assert!(contents.contains("hash = "));
+    assert!(!contents.contains("teal"));
+    assert_eq!(std::fs::read_to_string(blob_path(&prefs)).unwrap(), css);
+
+    prefs.reload().unwrap();
+    assert_eq!(*prefs.get_custom_css(), css);
+}
+
+#[test]
+fn test_unchanged_blob_is_not_rewritten() {
+    let mut prefs = StyledPrefs::load_testing();
+    prefs.save_custom_css("a { }".to_string()).unwrap();
+    let modified = std::fs::metadata(blob_path(&prefs)).unwrap().modified().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+
+    prefs.save_theme("dark".to_string()).unwrap();
+    let after = std::fs::metadata(blob_path(&prefs)).unwrap().modified().unwrap();
+    assert_eq!(modified, after);
+}
+
+#[test]
+fn test_edited_and_missing_blobs() {
+    let mut prefs = StyledPrefs::load_testing();
+    prefs.save_custom_css("a { }".to_string()).unwrap();
+
+    // Edited by hand: loaded anyway.
+    std::fs::write(blob_path(&prefs), "b { }").unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_custom_css(), "b { }");
+
+    std::fs::remove_file(blob_path(&prefs)).unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_custom_css(), "");
+}
+
+#[test]
+fn test_inline_value_moves_to_blob() {
+    let mut prefs = StyledPrefs::load_testing();
+    std::fs::write(prefs.get_preferences_file_path(), "custom_css = \"p { }\"\n").unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_custom_css(), "p { }");
+
+    prefs.save_theme("dark".to_string()).unwrap();
+    assert_eq!(std::fs::read_to_string(blob_path(&prefs)).unwrap(), "p { }");
+    assert_eq!(prefs.export_toml().unwrap(), "theme = \"dark\"\ncustom_css = \"p { }\"\n");
+}
@@ -0,0 +1,132 @@
+use easy_prefs::change::{ChangeSet, SaveSource};
+use easy_prefs::easy_prefs;
+use std::sync::Mutex;
+use toml::Value;
+
+// Each struct reports to its own list, so tests running in parallel don't mix events.
+static EDITOR_CHANGES: Mutex<Vec<ChangeSet>> = Mutex::new(Vec::new());
+static ACCOUNT_CHANGES: Mutex<Vec<ChangeSet>> = Mutex::new(Vec::new());
+
+fn take(changes: &Mutex<Vec<ChangeSet>>) -> Vec<ChangeSet> {
+    std::mem::take(&mut *changes.lock().unwrap())
+}
+
+fn record_editor(change: &ChangeSet) {
+    EDITOR_CHANGES.lock().unwrap().push(change.clone());
+}
+
+fn record_account(change: &ChangeSet) {
+    ACCOUNT_CHANGES.lock().unwrap().push(change.clone());
+}
+
+easy_prefs! {
+    struct EditorPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub font_size: i64 = 12 => "font_size",
+        pub word_wrap: bool = false => "word_wrap",
+    },
+    "change-editor-prefs",
+    on_change = record_editor
+}
+
+easy_prefs! {
+    struct AccountPrefs {
+        pub username: String = String::new() => "username",
+        pub token: String = String::new() => "token" (secret),
+    },
+    "change-account-prefs",
+    on_change = record_account
+}
+
+fn change(field: &'static str, old: Value, new: Value, source: SaveSource) -> ChangeSet {
+    ChangeSet { field, old: Some(old), new: Some(new), source }
+}
+
+// All in one test because the events of one struct share a list.
+#[test]
+fn test_change_sources() {
+    let mut prefs = EditorPrefs::load_testing();
+    take(&EDITOR_CHANGES);
+
+    prefs.save_theme("dark".to_string()).unwrap();
+    assert_eq!(
+        take(&EDITOR_CHANGES),
+        vec![change("theme", "light".into(), "dark".into(), SaveSource::Field)]
+    );
+
+    // Only fields that changed are reported.
+    {
+        let mut guard = prefs.edit();
+        guard.set_font_size(14);
+        guard.set_word_wrap(false);
+    }
+    assert_eq!(
+        take(&EDITOR_CHANGES),
+        vec![change("font_size", 12.into(), 14.into(), SaveSource::EditGuard)]
+    );
+
+    prefs.save().unwrap();
+    assert!(take(&EDITOR_CHANGES).is_empty());
+
+    // Another process edits the file.
+    std::fs::write(
+        prefs.get_preferences_file_path(),
+        "theme = \"dark\"\nfont_size = 16\nword_wrap = true\n",
+    )
+    .unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(
+        take(&EDITOR_CHANGES),
+        vec![
+            change("font_size", 14.into(), 16.into(), SaveSource::Reload),
+            change("word_wrap", false.into(), true.into(), SaveSource::Reload),
+        ]
+    );
+
+    prefs.apply_toml_patch("theme = \"solarized\"").unwrap();
+    assert_eq!(
+        take(&EDITOR_CHANGES),
+        vec![change("theme", "dark".into(), "solarized".into(), SaveSource::Migration)]
+    );
+
+    prefs.reset_to_defaults().unwrap();
+    let fields: Vec<_> = take(&EDITOR_CHANGES)
+        .into_iter()
+        .map(|change| (change.field, change.source))
+        .collect();
+    assert_eq!(
+        fields,
+        vec![
+            ("theme", SaveSource::Reset),
+            ("font_size", SaveSource::Reset),
+            ("word_wrap", SaveSource::Reset),
+        ]
+    );
+}
+
+#[test]
+fn test_changes_compare_against_loaded_values() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path().to_str().unwrap();
+    std::fs::write(
+        format!("{dir}/{}", AccountPrefs::PREFERENCES_FILENAME),
+        "username = \"alice\"\n",
+    )
+    .unwrap();
+
+    let mut prefs = AccountPrefs::load_with_error(dir).unwrap();
+    prefs.save_username("bob".to_string()).unwrap();
+    prefs.save_token("hunter2".to_string()).unwrap();
+    assert_eq!(
+        take(&ACCOUNT_CHANGES),
+        vec![
+            change("username", "alice".into(), "bob".into(), SaveSource::Field),
+            change(
+                "token",
+                easy_prefs::secrets::REDACTED.into(),
+                easy_prefs::secrets::REDACTED.into(),
+                SaveSource::Field
+            ),
+        ]
+    );
+}
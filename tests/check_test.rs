@@ -0,0 +1,99 @@
+use easy_prefs::check::CheckStatus;
+use easy_prefs::easy_prefs;
+
+easy_prefs! {
+    struct CheckedPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub volume: i32 = 5 => "volume" (min = 0, max = 10),
+    },
+    "checked-prefs"
+}
+
+easy_prefs! {
+    struct CheckedSealedPrefs {
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "checked-sealed-prefs",
+    integrity = true,
+    backups = 2
+}
+
+#[test]
+fn test_fresh_directory_is_healthy() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("missing");
+    let report = CheckedPrefs::self_check(dir.to_str().unwrap());
+    assert!(report.is_healthy(), "{report}");
+    assert!(report.location.ends_with("checked-prefs.toml"));
+    assert_eq!(report.item("readable").unwrap().detail, "nothing saved yet, defaults apply");
+    assert!(report.item("parse").is_none());
+    assert!(report.item("integrity").is_none());
+    assert_eq!(report.item("backups").unwrap().detail, "disabled");
+    assert!(report.item("free space").is_some());
+}
+
+#[test]
+fn test_saved_and_corrupt_files() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+    let mut prefs = CheckedPrefs::load_with_error(dir).unwrap();
+    prefs.save_theme("dark".to_string()).unwrap();
+    let path = prefs.get_preferences_file_path();
+
+    // Checking doesn't claim the instance, so it works while the preferences are loaded.
+    let report = CheckedPrefs::self_check(dir);
+    assert_eq!(report.status(), CheckStatus::Ok, "{report}");
+    assert_eq!(report.item("permissions").unwrap().detail, "writable");
+    assert_eq!(report.item("parse").unwrap().detail, "values load");
+    drop(prefs);
+
+    std::fs::write(&path, "theme = ").unwrap();
+    let report = CheckedPrefs::self_check(dir);
+    assert!(!report.is_healthy());
+    assert_eq!(report.item("parse").unwrap().status, CheckStatus::Failed);
+    assert!(report.to_string().contains("[failed] parse: "));
+}
+
+#[test]
+fn test_directory_path_is_a_file() {
+    let tmp = tempfile::tempdir().unwrap();
+    let file = tmp.path().join("not-a-directory");
+    std::fs::write(&file, "").unwrap();
+    let report = CheckedPrefs::self_check(file.to_str().unwrap());
+    let permissions = report.item("permissions").unwrap();
+    assert_eq!(permissions.status, CheckStatus::Failed);
+    assert_eq!(permissions.detail, "storage path is not a directory");
+}
+
+#[test]
+fn test_integrity_and_backups() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+    let mut prefs = CheckedSealedPrefs::load_with_error(dir).unwrap();
+    assert_eq!(CheckedSealedPrefs::self_check(dir).item("backups").unwrap().detail, "none saved yet");
+
+    prefs.save_theme("dark".to_string()).unwrap();
+    prefs.save_theme("blue".to_string()).unwrap();
+    prefs.save_theme("green".to_string()).unwrap();
+    let path = prefs.get_preferences_file_path();
+    drop(prefs);
+
+    let report = CheckedSealedPrefs::self_check(dir);
+    assert!(report.is_healthy(), "{report}");
+    assert_eq!(report.item("integrity").unwrap().detail, "footer matches");
+    assert_eq!(report.item("backups").unwrap().detail, "2 of 2 backups load");
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    std::fs::write(&path, contents.replace("green", "grey")).unwrap();
+    std::fs::write(format!("{path}.bak"), "theme = \"dark\"\n").unwrap();
+    let report = CheckedSealedPrefs::self_check(dir);
+    assert_eq!(report.item("integrity").unwrap().status, CheckStatus::Failed);
+    // The document itself still parses; only its footer is wrong.
+    assert_eq!(report.item("parse").unwrap().status, CheckStatus::Ok);
+    assert_eq!(report.item("backups").unwrap().detail, "1 of 2 backups load");
+
+    std::fs::remove_file(format!("{path}.bak.2")).unwrap();
+    let backups = CheckedSealedPrefs::self_check(dir).item("backups").cloned().unwrap();
+    assert_eq!(backups.status, CheckStatus::Warning);
+    assert_eq!(backups.detail, "none of 1 backups load");
+}
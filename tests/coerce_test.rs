@@ -0,0 +1,146 @@
+use easy_prefs::coerce::{coerce_value, normalize_keys, KeyCorrection};
+use easy_prefs::{easy_prefs, LoadError};
+use toml::Value;
+
+easy_prefs! {
+    struct PerFieldLenientPrefs {
+        pub enabled: bool = false => "enabled" (lenient_types),
+        pub retries: i32 = 3 => "retries" (lenient_types),
+        pub ratio: f64 = 0.5 => "ratio" (lenient_types = true),
+        pub strict_count: i32 = 0 => "strict_count",
+    },
+    "per-field-lenient-prefs"
+}
+
+easy_prefs! {
+    struct StructLenientPrefs {
+        pub enabled: bool = false => "enabled",
+        pub label: String = String::new() => "label",
+    },
+    "struct-lenient-prefs",
+    lenient_types = true,
+}
+
+easy_prefs! {
+    struct LenientKeyPrefs {
+        pub dark_mode: bool = false => "dark_mode",
+        pub font_size: i32 = 14 => "font-size",
+    },
+    "lenient-key-prefs",
+    lenient_keys = true,
+}
+
+easy_prefs! {
+    struct PreservedLenientKeyPrefs {
+        pub dark_mode: bool = false => "dark_mode",
+    },
+    "preserved-lenient-key-prefs",
+    lenient_keys = true,
+    preserve_formatting = true,
+}
+
+#[test]
+fn test_coerce_value_conversions() {
+    let bool_target = Value::Boolean(false);
+    assert_eq!(coerce_value(&Value::String("1".into()), &bool_target), Some(Value::Boolean(true)));
+    assert_eq!(coerce_value(&Value::String(" Yes ".into()), &bool_target), Some(Value::Boolean(true)));
+    assert_eq!(coerce_value(&Value::Integer(0), &bool_target), Some(Value::Boolean(false)));
+    assert_eq!(coerce_value(&Value::String("maybe".into()), &bool_target), None);
+
+    let int_target = Value::Integer(0);
+    assert_eq!(coerce_value(&Value::String("42".into()), &int_target), Some(Value::Integer(42)));
+    assert_eq!(coerce_value(&Value::Float(7.0), &int_target), Some(Value::Integer(7)));
+    assert_eq!(coerce_value(&Value::Float(7.5), &int_target), None);
+
+    let float_target = Value::Float(0.0);
+    assert_eq!(coerce_value(&Value::Integer(2), &float_target), Some(Value::Float(2.0)));
+}
+
+#[test]
+fn test_per_field_lenient_types_on_load() {
+    let mut prefs = PerFieldLenientPrefs::load_testing();
+    let path = prefs.get_preferences_file_path();
+    std::fs::write(&path, "enabled = \"true\"\nretries = \"5\"\nratio = 1\n").unwrap();
+
+    prefs.reload().expect("sloppy types should be repaired");
+    assert!(*prefs.get_enabled());
+    assert_eq!(*prefs.get_retries(), 5);
+    assert_eq!(*prefs.get_ratio(), 1.0);
+
+    // Fields without the flag keep strict typing.
+    std::fs::write(&path, "strict_count = \"5\"\n").unwrap();
+    assert!(matches!(prefs.reload(), Err(LoadError::DeserializationError(_, _))));
+}
+
+#[test]
+fn test_struct_level_lenient_types() {
+    let mut prefs = StructLenientPrefs::load_testing();
+    std::fs::write(prefs.get_preferences_file_path(), "enabled = 1\nlabel = 12\n").unwrap();
+
+    prefs.reload().unwrap();
+    assert!(*prefs.get_enabled());
+    assert_eq!(prefs.get_label(), "12");
+
+    // The repaired types are written back on the next save.
+    prefs.save_enabled(false).unwrap();
+    let contents = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+    assert!(contents.contains("label = \"12\""));
+}
+
+#[test]
+fn test_normalize_keys() {
+    let mut table: toml::Table = "Dark-Mode = true\nFONT_SIZE = 12\nunrelated = 1\n".parse().unwrap();
+    let corrections = normalize_keys(&mut table, LenientKeyPrefs::FIELDS);
+    assert_eq!(
+        corrections,
+        [
+            KeyCorrection { found: "Dark-Mode".to_string(), key: "dark_mode" },
+            KeyCorrection { found: "FONT_SIZE".to_string(), key: "font-size" },
+        ]
+    );
+    assert_eq!(corrections[0].to_string(), "'Dark-Mode' read as 'dark_mode'");
+    assert_eq!(table.get("dark_mode"), Some(&Value::Boolean(true)));
+    assert!(table.contains_key("unrelated"));
+
+    // The exact key wins over near misses.
+    let mut table: toml::Table = "dark_mode = false\nDarkMode = true\n".parse().unwrap();
+    assert!(normalize_keys(&mut table, LenientKeyPrefs::FIELDS).is_empty());
+    assert_eq!(table.get("dark_mode"), Some(&Value::Boolean(false)));
+}
+
+#[test]
+fn test_lenient_keys_on_load_and_save() {
+    let mut prefs = LenientKeyPrefs::load_testing();
+    let path = prefs.get_preferences_file_path();
+    std::fs::write(&path, "Dark_Mode = true\nfont_size = 18\n").unwrap();
+
+    prefs.reload().unwrap();
+    assert!(*prefs.get_dark_mode());
+    assert_eq!(*prefs.get_font_size(), 18);
+
+    prefs.save().unwrap();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(contents.contains("dark_mode = true") && contents.contains("font-size = 18"));
+    assert!(!contents.contains("Dark_Mode"));
+
+    // Without the option, near misses are ignored.
+    let mut strict = StructLenientPrefs::load_testing();
+    std::fs::write(strict.get_preferences_file_path(), "Enabled = true\n").unwrap();
+    strict.reload().unwrap();
+    assert!(!*strict.get_enabled());
+}
+
+#[test]
+fn test_lenient_keys_with_preserved_formatting() {
+    let mut prefs = PreservedLenientKeyPrefs::load_testing();
+    let path = prefs.get_preferences_file_path();
+    std::fs::write(&path, "# Appearance\ndark-mode = true # night owl\nextra = 1\n").unwrap();
+
+    prefs.reload().unwrap();
+    assert!(*prefs.get_dark_mode());
+    prefs.save().unwrap();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(contents.contains("dark_mode = true"));
+    assert!(!contents.contains("dark-mode"));
+    assert!(contents.contains("extra = 1"));
+}
@@ -0,0 +1,139 @@
+use easy_prefs::easy_prefs;
+use std::collections::HashMap;
+
+easy_prefs! {
+    struct CollectionPrefs {
+        pub title: String = "main".to_string() => "title",
+        pub recent_files: Vec<String> = Vec::new() => "recent_files",
+        pub zoom_by_file: HashMap<String, f64> = HashMap::new() => "zoom_by_file",
+        pub enabled: bool = true => "enabled",
+        pub layout: String = "grid".to_string() => "layout" (choices = ["grid", "list"]),
+    },
+    "collection-prefs"
+}
+
+#[test]
+fn test_push_and_insert_save_immediately() {
+    let mut prefs = CollectionPrefs::load_testing();
+    prefs.push_recent_files("a.txt".to_string()).unwrap();
+    prefs.push_recent_files("b.txt".to_string()).unwrap();
+    prefs.insert_zoom_by_file("a.txt".to_string(), 1.5).unwrap();
+
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_recent_files(), &["a.txt", "b.txt"]);
+    assert_eq!(prefs.get_zoom_by_file().get("a.txt"), Some(&1.5));
+    assert_eq!(prefs.get_title(), "main");
+    assert!(*prefs.get_enabled());
+}
+
+#[test]
+fn test_collections_in_edit_guard() {
+    let mut prefs = CollectionPrefs::load_testing();
+    {
+        let mut edit = prefs.edit();
+        edit.push_recent_files("c.txt".to_string());
+        edit.insert_zoom_by_file("c.txt".to_string(), 0.75);
+        edit.set_enabled(false);
+    }
+
+    let contents = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+    assert!(contents.contains("recent_files = [\"c.txt\"]"));
+    assert!(contents.contains("[zoom_by_file]"));
+
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_recent_files(), &["c.txt"]);
+    assert_eq!(prefs.get_zoom_by_file().get("c.txt"), Some(&0.75));
+    assert!(!*prefs.get_enabled());
+}
+
+#[test]
+fn test_take_and_replace() {
+    let mut prefs = CollectionPrefs::load_testing();
+    prefs.save_recent_files(vec!["a.txt".to_string()]).unwrap();
+
+    let previous = prefs.replace_recent_files(vec!["b.txt".to_string()]).unwrap();
+    assert_eq!(previous, ["a.txt"]);
+    let taken = prefs.take_recent_files().unwrap();
+    assert_eq!(taken, ["b.txt"]);
+    assert!(prefs.get_recent_files().is_empty());
+    assert_eq!(prefs.take_title().unwrap(), "main");
+
+    prefs.reload().unwrap();
+    assert!(prefs.get_recent_files().is_empty());
+
+    // A rejected value leaves the previous one in place.
+    assert!(prefs.replace_layout("table".to_string()).is_err());
+    assert_eq!(prefs.get_layout(), "grid");
+    assert_eq!(prefs.replace_layout("list".to_string()).unwrap(), "grid");
+
+    let handle = prefs.into_shared();
+    assert_eq!(handle.take_layout().unwrap(), "list");
+    assert_eq!(handle.get_layout(), "grid");
+}
+
+#[test]
+fn test_replacing_whole_collections() {
+    let mut prefs = CollectionPrefs::load_testing();
+    prefs
+        .save_recent_files(vec!["x".to_string(), "y".to_string()])
+        .unwrap();
+    prefs.save_zoom_by_file(HashMap::new()).unwrap();
+
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_recent_files().len(), 2);
+    assert!(prefs.get_zoom_by_file().is_empty());
+}
+
+fn at_most_two_files(prefs: &LimitedPrefs) -> Result<(), String> {
+    if prefs.get_recent_files().len() > 2 {
+        return Err("at most 2 recent files".into());
+    }
+    Ok(())
+}
+
+easy_prefs! {
+    struct LimitedPrefs {
+        pub recent_files: Vec<String> = Vec::new() => "recent_files",
+        pub zoom_by_file: HashMap<String, i64> = HashMap::new() => "zoom_by_file",
+    },
+    "limited-collection-prefs",
+    validate_struct = at_most_two_files
+}
+
+#[test]
+fn test_rejected_push_keeps_collection_and_override() {
+    let mut prefs = LimitedPrefs::load_testing();
+    prefs.push_recent_files("a.txt".to_string()).unwrap();
+    prefs
+        .set_recent_files_temporarily(vec!["a.txt".to_string(), "b.txt".to_string()], std::time::Duration::from_secs(3600))
+        .unwrap();
+
+    match prefs.push_recent_files("c.txt".to_string()) {
+        Err(easy_prefs::SaveError::ValidationError(e)) => assert!(e.reason.contains("at most 2")),
+        other => panic!("expected a validation error, got {other:?}"),
+    }
+    assert_eq!(prefs.get_recent_files(), &["a.txt", "b.txt"]);
+    assert!(prefs.get_recent_files_expiry().is_some());
+
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_recent_files(), &["a.txt", "b.txt"]);
+    assert!(prefs.get_recent_files_expiry().is_some());
+
+    // A push that passes ends the override like save_<field>().
+    prefs.insert_zoom_by_file("a.txt".to_string(), 2).unwrap();
+    let handle = prefs.into_shared();
+    assert!(handle.push_recent_files("c.txt".to_string()).is_err());
+    assert_eq!(handle.get_recent_files(), ["a.txt", "b.txt"]);
+}
+
+#[test]
+fn test_guard_push_is_validated_on_commit() {
+    let mut prefs = LimitedPrefs::load_testing();
+    prefs.save_recent_files(vec!["a.txt".to_string(), "b.txt".to_string()]).unwrap();
+    let mut edit = prefs.edit();
+    edit.push_recent_files("c.txt".to_string());
+    assert!(matches!(edit.commit(), Err(easy_prefs::SaveError::ValidationError(_))));
+
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_recent_files(), &["a.txt", "b.txt"]);
+}
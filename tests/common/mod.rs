@@ -0,0 +1,11 @@
+//! Helpers shared by the integration tests.
+
+use std::sync::Once;
+
+/// Keeps secret fields away from the OS keyring when the `keyring` feature is on.
+///
+/// The secret store is process-wide, so every test in a binary shares one memory store.
+pub fn use_memory_secret_store() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| easy_prefs::secrets::set_secret_store(easy_prefs::secrets::MemorySecretStore::new()));
+}
@@ -0,0 +1,124 @@
+use easy_prefs::critical::{CriticalFailure, Escalation};
+use easy_prefs::easy_prefs;
+use easy_prefs::storage::{MemoryStorage, Storage};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Storage whose next `failures` writes fail.
+#[derive(Debug, Clone, Default)]
+struct FailingStorage {
+    inner: Arc<MemoryStorage>,
+    failures: Arc<AtomicUsize>,
+}
+
+impl FailingStorage {
+    fn fail_next(&self, writes: usize) {
+        self.failures.store(writes, Ordering::SeqCst);
+    }
+}
+
+impl Storage for FailingStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+        self.inner.read(key)
+    }
+
+    fn write(&self, key: &str, data: &str) -> Result<(), std::io::Error> {
+        if self.failures.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_ok() {
+            return Err(std::io::Error::other("disk unavailable"));
+        }
+        self.inner.write(key, data)
+    }
+
+    fn get_path(&self, key: &str) -> String {
+        format!("failing::{key}")
+    }
+}
+
+static REPORTED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn report(failure: &CriticalFailure) {
+    REPORTED.lock().unwrap().push(format!("{:?} at {}: {}", failure.keys, failure.path, failure.error));
+}
+
+easy_prefs! {
+    struct PanickingPrefs {
+        pub monitoring: bool = true => "monitoring" (critical),
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "panicking-prefs"
+}
+
+easy_prefs! {
+    struct RetryingPrefs {
+        pub monitoring: bool = true => "monitoring" (critical),
+    },
+    "retrying-prefs",
+    on_critical_failure = Escalation::RetryUntilSuccess { interval: Duration::from_millis(1) }
+}
+
+easy_prefs! {
+    struct ReportingPrefs {
+        pub monitoring: bool = true => "monitoring" (critical),
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "reporting-prefs",
+    on_critical_failure = Escalation::Callback(report)
+}
+
+#[test]
+fn test_critical_option() {
+    assert!(PanickingPrefs::FIELDS[0].options.critical);
+    assert!(!PanickingPrefs::FIELDS[1].options.critical);
+    assert!(matches!(PanickingPrefs::OPTIONS.on_critical_failure, Escalation::Panic));
+}
+
+#[test]
+#[should_panic(expected = "failed to save critical preferences [\"monitoring\"] to failing::panicking-prefs.toml")]
+fn test_panic_on_critical_failure() {
+    let storage = FailingStorage::default();
+    let mut prefs = PanickingPrefs::load_with_storage(Box::new(storage.clone())).unwrap();
+    prefs.save().unwrap();
+
+    storage.fail_next(1);
+    let _ = prefs.save_monitoring(false);
+}
+
+#[test]
+fn test_non_critical_failure_is_returned() {
+    let storage = FailingStorage::default();
+    let mut prefs = PanickingPrefs::load_with_storage(Box::new(storage.clone())).unwrap();
+    prefs.save().unwrap();
+
+    storage.fail_next(1);
+    assert!(prefs.save_theme("dark".to_string()).is_err());
+}
+
+#[test]
+fn test_retry_until_success() {
+    let storage = FailingStorage::default();
+    let mut prefs = RetryingPrefs::load_with_storage(Box::new(storage.clone())).unwrap();
+
+    storage.fail_next(3);
+    prefs.save_monitoring(false).unwrap();
+    let stored = storage.read("retrying-prefs.toml").unwrap().unwrap();
+    assert!(stored.contains("monitoring = false"));
+}
+
+#[test]
+fn test_callback_on_critical_failure() {
+    let storage = FailingStorage::default();
+    let mut prefs = ReportingPrefs::load_with_storage(Box::new(storage.clone())).unwrap();
+    prefs.save_theme("dark".to_string()).unwrap();
+
+    storage.fail_next(1);
+    assert!(prefs.save_theme("blue".to_string()).is_err());
+    assert!(REPORTED.lock().unwrap().is_empty());
+
+    storage.fail_next(1);
+    assert!(prefs.save_monitoring(false).is_err());
+    assert_eq!(
+        *REPORTED.lock().unwrap(),
+        ["[\"monitoring\"] at failing::reporting-prefs.toml: disk unavailable"]
+    );
+}
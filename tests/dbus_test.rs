@@ -0,0 +1,138 @@
+#![cfg(all(feature = "dbus", target_os = "linux"))]
+
+use easy_prefs::dbus::{Access, DbusServer};
+use easy_prefs::easy_prefs;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use zbus::blocking::connection::Builder;
+use zbus::blocking::fdo::PropertiesProxy;
+use zbus::names::InterfaceName;
+use zbus::zvariant::{OwnedValue, Value};
+
+mod common;
+
+easy_prefs! {
+    struct DbusPrefs {
+        pub dark_mode: bool = false => "dark_mode",
+        pub volume: i32 = 5 => "volume" (max = 10),
+        /// Name shown in the "title" bar.
+        pub label: String = "main".to_string() => "label",
+        pub api_key: String = "abc".to_string() => "api_key" (secret),
+    },
+    "dbus-prefs"
+}
+
+const BUS_NAME: &str = "com.example.PrefsTest";
+const INTERFACE: &str = "com.example.PrefsTest.Prefs";
+
+/// A private bus daemon, killed on drop.
+struct Bus {
+    daemon: Child,
+    address: String,
+}
+
+impl Bus {
+    fn start() -> Option<Self> {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address=1"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take()?)
+            .read_line(&mut address)
+            .ok()?;
+        Some(Self {
+            daemon,
+            address: address.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for Bus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
+
+#[test]
+fn test_properties_over_dbus() {
+    let Some(bus) = Bus::start() else {
+        eprintln!("dbus-daemon not available; skipping");
+        return;
+    };
+    common::use_memory_secret_store();
+
+    let prefs = Arc::new(Mutex::new(DbusPrefs::load_testing()));
+    let service = Builder::address(bus.address.as_str())
+        .unwrap()
+        .name(BUS_NAME)
+        .unwrap()
+        .build()
+        .unwrap();
+    let server = DbusServer::new(prefs.clone(), service, INTERFACE).with_access(|field| {
+        if field.key == "label" {
+            Access::ReadOnly
+        } else {
+            Access::ReadWrite
+        }
+    });
+    server.start();
+    assert_eq!(server.path(), "/com/example/PrefsTest/Prefs");
+
+    let client = Builder::address(bus.address.as_str()).unwrap().build().unwrap();
+    let proxy = PropertiesProxy::builder(&client)
+        .destination(BUS_NAME)
+        .unwrap()
+        .path(server.path())
+        .unwrap()
+        .build()
+        .unwrap();
+    let interface = InterfaceName::try_from(INTERFACE).unwrap();
+    let mut changes = proxy.receive_properties_changed().unwrap();
+
+    // Typed reads.
+    let volume: i64 = proxy.get(interface.clone(), "volume").unwrap().try_into().unwrap();
+    assert_eq!(volume, 5);
+    let all = proxy.get_all(interface.clone()).unwrap();
+    assert_eq!(all.len(), 4);
+    assert_eq!(String::try_from(all["api_key"].clone()).unwrap(), "<redacted>");
+
+    let introspection = zbus::blocking::fdo::IntrospectableProxy::builder(&client)
+        .destination(BUS_NAME)
+        .unwrap()
+        .path(server.path())
+        .unwrap()
+        .build()
+        .unwrap()
+        .introspect()
+        .unwrap();
+    assert!(introspection.contains(r#"<property name="volume" type="x" access="readwrite"/>"#));
+    assert!(introspection.contains(
+        r#"<property name="label" type="s" access="read">
+      <annotation name="org.freedesktop.DBus.DocString" value="Name shown in the &quot;title&quot; bar."/>
+    </property>"#
+    ));
+
+    // Writes go through validation and emit PropertiesChanged.
+    proxy.set(interface.clone(), "dark_mode", Value::from(true)).unwrap();
+    assert!(*prefs.lock().unwrap().get_dark_mode());
+    let signal = changes.next().unwrap();
+    let args = signal.args().unwrap();
+    assert_eq!(args.changed_properties().get("dark_mode"), Some(&Value::from(true)));
+
+    assert!(proxy.set(interface.clone(), "volume", Value::from(11i32)).is_err());
+    assert!(proxy.set(interface.clone(), "label", Value::from("x")).is_err());
+    assert!(proxy.get(interface.clone(), "missing").is_err());
+    assert_eq!(*prefs.lock().unwrap().get_volume(), 5);
+
+    // In-process changes are announced after notify_changes().
+    prefs.lock().unwrap().save_volume(8).unwrap();
+    server.notify_changes();
+    let signal = changes.next().unwrap();
+    let value: OwnedValue = signal.args().unwrap().changed_properties()["volume"].try_to_owned().unwrap();
+    assert_eq!(i64::try_from(value).unwrap(), 8);
+}
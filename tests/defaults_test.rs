@@ -0,0 +1,82 @@
+use easy_prefs::{easy_prefs, LoadError};
+
+easy_prefs! {
+    struct ShippedPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub font_size: i32 = 14 => "font_size" (min = 6, max = 72),
+        pub autosave: bool = true => "autosave",
+        pub window: ShippedWindow {
+            pub width: i32 = 800 => "width",
+            pub height: i32 = 600 => "height",
+        } => "window",
+    },
+    "shipped-prefs",
+    defaults = include_str!("shipped_defaults.toml")
+}
+
+easy_prefs! {
+    struct InvalidShippedPrefs {
+        pub font_size: i32 = 14 => "font_size" (max = 72),
+    },
+    "invalid-shipped-prefs",
+    defaults = "font_size = 100\n"
+}
+
+easy_prefs! {
+    struct BrokenShippedPrefs {
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "broken-shipped-prefs",
+    defaults = "theme = "
+}
+
+#[test]
+fn test_shipped_defaults_replace_declared_defaults() {
+    let prefs = ShippedPrefs::load_testing();
+    assert_eq!(prefs.get_theme(), "solarized");
+    assert_eq!(*prefs.get_font_size(), 16);
+    assert!(*prefs.get_autosave(), "fields the document leaves out keep their declared default");
+    assert_eq!(prefs.get_window().width, 1280);
+    assert_eq!(prefs.get_window().height, 600);
+    assert_eq!(ShippedPrefs::default().get_theme(), "solarized");
+}
+
+#[test]
+fn test_stored_values_win() {
+    let mut prefs = ShippedPrefs::load_testing();
+    let path = prefs.get_preferences_file_path();
+    std::fs::write(&path, "theme = \"dark\"\n[window]\nheight = 900\n").unwrap();
+
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_theme(), "dark");
+    assert_eq!(*prefs.get_font_size(), 16);
+    assert_eq!(prefs.get_window().width, 1280, "missing section keys use the shipped defaults too");
+    assert_eq!(prefs.get_window().height, 900);
+}
+
+#[test]
+fn test_reset_uses_shipped_defaults() {
+    let mut prefs = ShippedPrefs::load_testing();
+    assert!(prefs.is_default_font_size());
+    prefs.save_font_size(20).unwrap();
+    prefs.save_theme("dark".to_string()).unwrap();
+    assert!(!prefs.is_default_font_size());
+
+    prefs.reset_font_size().unwrap();
+    assert_eq!(*prefs.get_font_size(), 16);
+    prefs.reset_to_defaults().unwrap();
+    assert_eq!(prefs.get_theme(), "solarized");
+}
+
+#[test]
+fn test_shipped_defaults_are_validated() {
+    let prefs = InvalidShippedPrefs::load_testing();
+    assert_eq!(*prefs.get_font_size(), 14, "out-of-range shipped defaults are ignored");
+    assert!(prefs.is_default_font_size());
+
+    let mut prefs = BrokenShippedPrefs::load_testing();
+    match prefs.reload() {
+        Err(LoadError::DeserializationError(location, _)) => assert_eq!(location, "shipped defaults"),
+        other => panic!("expected a deserialization error, got {other:?}"),
+    }
+}
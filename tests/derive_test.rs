@@ -0,0 +1,764 @@
+#![cfg(feature = "derive")]
+
+use easy_prefs::dynamic::{DynamicPrefs, PrefValue};
+use easy_prefs::{EasyPrefs, LoadError, Prefs, PrefsData, SaveError};
+use serde::{Deserialize, Serialize};
+
+mod common;
+
+fn check_layout(prefs: &DerivedPrefs) -> Result<(), String> {
+    if prefs.sidebar_width > prefs.text_size * 100 {
+        return Err("sidebar too wide".to_string());
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, EasyPrefs)]
+#[prefs(filename = "derived-prefs", validate_struct = check_layout)]
+pub struct DerivedPrefs {
+    #[pref(default = false)]
+    pub dark_mode: bool,
+    /// Editor font size.
+    #[pref(default = 14, name = "font_size", min = 6, max = 72)]
+    pub text_size: i32,
+    #[pref(default = "light".to_string(), choices = ["light", "dark"])]
+    pub theme: String,
+    #[pref(device)]
+    pub sidebar_width: i32,
+    #[secret]
+    pub api_token: Option<String>,
+    #[serde(skip)]
+    pub session_only: u32,
+}
+
+#[test]
+fn test_derive_metadata() {
+    let defaults = DerivedPrefs::default();
+    assert_eq!(defaults.text_size, 14);
+    assert_eq!(defaults.theme, "light");
+    assert_eq!(defaults.sidebar_width, 0);
+
+    assert_eq!(DerivedPrefs::PREFERENCES_FILENAME, "derived-prefs.toml");
+    let keys: Vec<_> = DerivedPrefs::FIELDS.iter().map(|f| f.key).collect();
+    assert_eq!(keys, ["dark_mode", "font_size", "theme", "sidebar_width", "api_token", "session_only"]);
+    assert_eq!(DerivedPrefs::FIELDS[1].name, "text_size");
+    assert_eq!(DerivedPrefs::FIELDS[1].options.max, Some(72.0));
+    assert_eq!(DerivedPrefs::FIELDS[1].type_name, "i32");
+    assert_eq!(DerivedPrefs::FIELDS[1].default, "14");
+    assert_eq!(DerivedPrefs::FIELDS[1].description(), "Editor font size.");
+    assert_eq!(DerivedPrefs::FIELDS[2].default, "\"light\".to_string()");
+    assert_eq!(DerivedPrefs::FIELDS[3].default, "Default::default()");
+    assert_eq!(DerivedPrefs::FIELDS[4].type_name, "Option<String>");
+    assert!(DerivedPrefs::FIELDS[4].options.secret);
+    assert!(DerivedPrefs::OPTIONS.validate_struct.is_some());
+}
+
+#[test]
+fn test_edit_saves_with_saved_names() {
+    common::use_memory_secret_store();
+    let mut prefs = Prefs::<DerivedPrefs>::load_testing();
+    {
+        let mut edit = prefs.edit();
+        edit.text_size = 18;
+        edit.dark_mode = true;
+        edit.session_only = 3;
+    }
+
+    let contents = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+    assert!(contents.contains("font_size = 18"));
+    assert!(!contents.contains("text_size"));
+    assert!(!contents.contains("session_only"));
+
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get().text_size, 18);
+    assert!(prefs.get().dark_mode);
+    assert_eq!(prefs.get().session_only, 0);
+
+    let (field, value) = prefs.iter().nth(1).unwrap();
+    assert_eq!((field.name, value), ("text_size", Some(toml::Value::Integer(18))));
+    assert_eq!(prefs.iter().last().unwrap().1, None);
+
+    let report = prefs.apply_toml_patch("theme = \"dark\"\nunknown = 1\n").unwrap();
+    assert_eq!((report.applied, report.unknown), (vec!["theme"], vec!["unknown".to_string()]));
+    assert_eq!(prefs.get().theme, "dark");
+    assert!(prefs.apply_toml_patch("theme = \"blue\"").is_err());
+
+    prefs.edit().api_token = Some("hunter2".to_string());
+    std::fs::write(prefs.get_preferences_file_path(), "theme = \"light\"\nfont_size = 30\n").unwrap();
+    prefs.reload_fields(&["font_size"]).unwrap();
+    assert_eq!((prefs.get().text_size, prefs.get().theme.as_str()), (30, "dark"));
+    prefs.edit().text_size = 18;
+
+    let map = prefs.as_map();
+    assert_eq!(map["font_size"], PrefValue::Value(toml::Value::Integer(18)));
+    assert_eq!(map["api_token"], PrefValue::Redacted);
+    assert!(!map.contains_key("session_only"));
+}
+
+#[test]
+fn test_constraints_and_struct_validation() {
+    common::use_memory_secret_store();
+    let mut prefs = Prefs::<DerivedPrefs>::load_testing();
+    assert!(prefs.set_field_from_str("font_size", "100").is_err());
+    assert!(prefs.set_field_from_str("theme", "blue").is_err());
+    assert!(prefs.set_field_from_str("sidebar_width", "5000").is_err());
+    assert!(prefs.set_field_from_str("text_size", "20").is_err());
+    assert_eq!(prefs.get(), &DerivedPrefs::default());
+
+    prefs.set_field_from_str("font_size", "20").unwrap();
+    prefs.set_field_from_str("api_token", "abc").unwrap();
+    assert_eq!(prefs.get_field_string("font_size").as_deref(), Some("20"));
+    assert_eq!(prefs.get().api_token.as_deref(), Some("abc"));
+    assert!(!prefs.to_string().contains("abc"));
+
+    prefs.set_value("font_size", toml::Value::Float(24.0)).unwrap();
+    assert_eq!(prefs.get_value("font_size"), Some(toml::Value::Integer(24)));
+    assert!(prefs.set_value("font_size", toml::Value::Integer(100)).is_err());
+    assert!(prefs.set_value("dark_mode", toml::Value::from("maybe")).is_err());
+    let generic: &dyn easy_prefs::Preferences = &prefs;
+    generic.save().unwrap();
+    assert_eq!(generic.get_preferences_file_path(), prefs.get_preferences_file_path());
+
+    // Invalid stored values fall back to their defaults.
+    std::fs::write(prefs.get_preferences_file_path(), "font_size = 2\ntheme = \"dark\"\n").unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get().text_size, 14);
+    assert_eq!(prefs.get().theme, "dark");
+}
+
+#[test]
+fn test_single_instance() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(filename = "single")]
+    struct SinglePrefs {
+        #[pref(default = 1)]
+        count: i32,
+    }
+
+    let first = Prefs::<SinglePrefs>::load_with_error(path).unwrap();
+    assert!(matches!(
+        Prefs::<SinglePrefs>::load_with_error(path),
+        Err(LoadError::InstanceAlreadyLoaded)
+    ));
+    drop(first);
+
+    let mut prefs = Prefs::<SinglePrefs>::load_with_error(path).unwrap();
+    prefs.edit().count = 5;
+    drop(prefs);
+    assert_eq!(Prefs::<SinglePrefs>::load_with_error(path).unwrap().get().count, 5);
+}
+
+#[test]
+fn test_multi_instance() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(filename = "multi", single_instance = false)]
+    struct MultiPrefs {
+        count: i32,
+    }
+
+    let mut first = Prefs::<MultiPrefs>::load_with_error(path).unwrap();
+    first.edit().count = 2;
+    let second = Prefs::<MultiPrefs>::load_with_error(path).unwrap();
+    assert_eq!(second.get().count, 2);
+
+    let mut document = Prefs::<MultiPrefs>::load_for(path, "/docs/a.txt").unwrap();
+    assert_eq!(document.get().count, 0);
+    document.edit().count = 3;
+    assert_eq!(Prefs::<MultiPrefs>::load_for(path, "/docs/a.txt").unwrap().get().count, 3);
+}
+
+#[test]
+fn test_global() {
+    fn directory() -> Result<String, std::io::Error> {
+        let directory = std::env::temp_dir().join(format!("easy_prefs_derived_global_{}", std::process::id()));
+        Ok(directory.to_string_lossy().into_owned())
+    }
+
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(filename = "derived-global", global = directory)]
+    struct GlobalPrefs {
+        count: i32,
+    }
+
+    Prefs::<GlobalPrefs>::global().edit(|prefs| prefs.count = 4).unwrap();
+    assert!(std::ptr::eq(Prefs::<GlobalPrefs>::global(), Prefs::<GlobalPrefs>::try_global().unwrap()));
+    assert_eq!(Prefs::<GlobalPrefs>::global().with(|prefs| prefs.count), 4);
+    let _ = std::fs::remove_dir_all(directory().unwrap());
+}
+
+#[test]
+fn test_try_global_requires_the_option() {
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(filename = "derived-local")]
+    struct LocalPrefs {
+        count: i32,
+    }
+
+    match Prefs::<LocalPrefs>::try_global() {
+        Err(LoadError::StorageError(e)) => assert_eq!(e.kind(), std::io::ErrorKind::Unsupported),
+        other => panic!("Expected StorageError, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_transient_field() {
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(filename = "derived-transient")]
+    struct CachePrefs {
+        path: String,
+        #[pref(default = 0, transient)]
+        entries: i64,
+    }
+
+    let mut prefs = Prefs::<CachePrefs>::load_testing();
+    {
+        let mut edit = prefs.edit();
+        edit.path = "/tmp/cache".to_string();
+        edit.entries = 12;
+    }
+    let stored = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+    assert!(!stored.contains("entries"), "{stored}");
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get().entries, 0);
+    assert_eq!(prefs.get().path, "/tmp/cache");
+}
+
+#[test]
+fn test_serde_alias() {
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(filename = "derived-alias")]
+    struct RenamedPrefs {
+        #[serde(alias = "colour")]
+        color: String,
+        #[pref(alias = "font")]
+        font_name: String,
+    }
+
+    assert_eq!(RenamedPrefs::FIELDS[0].aliases, &["colour"]);
+    assert_eq!(RenamedPrefs::FIELDS[1].aliases, &["font"]);
+    let mut prefs = Prefs::<RenamedPrefs>::load_testing();
+    std::fs::write(prefs.get_preferences_file_path(), "colour = \"blue\"\nfont = \"Inter\"\n").unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get().color, "blue");
+    assert_eq!(prefs.get().font_name, "Inter");
+}
+
+#[test]
+fn test_removed_field() {
+    use easy_prefs::removed::RemovedField;
+
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(
+        filename = "derived-removed",
+        removed = &[RemovedField { key: "zoom_percent", migrate: |value, prefs| {
+            prefs.zoom = value.as_integer().unwrap_or(100) as f64 / 100.0;
+        } }]
+    )]
+    struct ZoomPrefs {
+        #[pref(default = 1.0)]
+        zoom: f64,
+    }
+
+    let mut prefs = Prefs::<ZoomPrefs>::load_testing();
+    std::fs::write(prefs.get_preferences_file_path(), "zoom_percent = 150\n").unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get().zoom, 1.5);
+    prefs.save().unwrap();
+    let stored = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+    assert!(!stored.contains("zoom_percent"), "{stored}");
+}
+
+#[test]
+fn test_commit_and_cancel() {
+    common::use_memory_secret_store();
+    let mut prefs = Prefs::<DerivedPrefs>::load_testing();
+    let mut edit = prefs.edit();
+    edit.text_size = 20;
+    edit.commit().unwrap();
+
+    let mut edit = prefs.edit();
+    edit.sidebar_width = 100_000;
+    assert!(matches!(edit.commit(), Err(easy_prefs::SaveError::ValidationError(_))));
+    prefs.edit().cancel();
+    assert_eq!(prefs.get().sidebar_width, 100_000);
+
+    let mut edit = prefs.edit();
+    edit.sidebar_width = 50;
+    edit.theme = "dark".to_string();
+    edit.cancel();
+    assert_eq!(prefs.get().sidebar_width, 100_000);
+    assert_eq!(prefs.get().theme, "light");
+
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get().text_size, 20);
+    assert_eq!(prefs.get().sidebar_width, 0);
+
+    prefs.reset_to_defaults().unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get(), &DerivedPrefs::default());
+}
+
+#[test]
+fn test_shared_handle() {
+    common::use_memory_secret_store();
+    let handle = Prefs::<DerivedPrefs>::load_testing().into_shared();
+    let writer = {
+        let handle = handle.clone();
+        std::thread::spawn(move || handle.write().edit().text_size = 30)
+    };
+    writer.join().unwrap();
+    assert_eq!(handle.with(|prefs| prefs.text_size), 30);
+    assert_eq!(format!("{handle:?}"), "PrefsHandle { .. }");
+
+    let previous = handle.edit(|prefs| std::mem::replace(&mut prefs.text_size, 40)).unwrap();
+    assert_eq!(previous, 30);
+    assert_eq!(handle.with(|prefs| prefs.text_size), 40);
+}
+
+#[test]
+fn test_export_and_import() {
+    common::use_memory_secret_store();
+    let mut prefs = Prefs::<DerivedPrefs>::load_testing();
+    {
+        let mut edit = prefs.edit();
+        edit.sidebar_width = 300;
+        edit.api_token = Some("hunter2".to_string());
+    }
+    assert_eq!(
+        prefs.export_toml().unwrap(),
+        "dark_mode = false\nfont_size = 14\ntheme = \"light\"\n"
+    );
+
+    prefs.import_toml("font_size = 20\nsidebar_width = 10\n").unwrap();
+    assert_eq!(prefs.get().text_size, 20);
+    assert_eq!(prefs.get().sidebar_width, 300);
+}
+
+#[test]
+fn test_range_and_clamp() {
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(filename = "derived-range")]
+    struct RangePrefs {
+        #[pref(default = 14, range = 8..=72, on_invalid = clamp)]
+        font_size: i32,
+    }
+
+    let mut prefs = Prefs::<RangePrefs>::load_testing();
+    assert!(prefs.set_field_from_str("font_size", "-3").is_err());
+    std::fs::write(prefs.get_preferences_file_path(), "font_size = 100\n").unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get().font_size, 72);
+}
+
+#[test]
+fn test_backup_recovery() {
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(filename = "derived-backup", backups = 1)]
+    struct BackupPrefs {
+        count: i32,
+    }
+
+    let mut prefs = Prefs::<BackupPrefs>::load_testing();
+    prefs.edit().count = 1;
+    prefs.edit().count = 2;
+    std::fs::write(prefs.get_preferences_file_path(), "count = ").unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get().count, 1);
+    assert!(matches!(prefs.load_outcome(), easy_prefs::LoadOutcome::RecoveredFromBackup { .. }));
+}
+
+#[test]
+fn test_migrate() {
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(filename = "derived-migrate")]
+    struct MigratePrefs {
+        count: i32,
+    }
+
+    let tmp = tempfile::tempdir().unwrap();
+    let (old_dir, new_dir) = (tmp.path().join("old"), tmp.path().join("new"));
+    let (old_dir, new_dir) = (old_dir.to_str().unwrap(), new_dir.to_str().unwrap());
+    {
+        let mut prefs = Prefs::<MigratePrefs>::load_with_error(old_dir).unwrap();
+        prefs.edit().count = 4;
+    }
+    assert!(Prefs::<MigratePrefs>::migrate(old_dir, new_dir).unwrap());
+    assert!(!Prefs::<MigratePrefs>::migrate(old_dir, new_dir).unwrap());
+    assert_eq!(Prefs::<MigratePrefs>::load_with_error(new_dir).unwrap().get().count, 4);
+}
+
+#[test]
+fn test_integrity() {
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(filename = "derived-integrity", integrity, reset_on_integrity_failure)]
+    struct SealedPrefs {
+        count: i32,
+    }
+
+    let mut prefs = Prefs::<SealedPrefs>::load_testing();
+    prefs.edit().count = 6;
+    let path = prefs.get_preferences_file_path();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(easy_prefs::integrity::verify(&contents).is_ok());
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get().count, 6);
+
+    std::fs::write(&path, contents.replace('6', "2")).unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get().count, 0);
+    assert!(matches!(prefs.load_outcome(), easy_prefs::LoadOutcome::ResetToDefaults { .. }));
+}
+
+#[test]
+fn test_self_check() {
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(filename = "derived-check", integrity, backups = 1)]
+    struct CheckedPrefs {
+        count: i32,
+    }
+
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+    let mut prefs = Prefs::<CheckedPrefs>::load_with_error(dir).unwrap();
+    prefs.edit().count = 6;
+    prefs.edit().count = 7;
+    let report = Prefs::<CheckedPrefs>::self_check(dir);
+    assert!(report.is_healthy(), "{report}");
+    assert_eq!(report.item("backups").unwrap().detail, "1 of 1 backups load");
+
+    let path = prefs.get_preferences_file_path();
+    std::fs::write(&path, "count = 7\n").unwrap();
+    let report = Prefs::<CheckedPrefs>::self_check(dir);
+    assert_eq!(report.item("integrity").unwrap().detail, "integrity footer missing");
+    assert!(!report.is_healthy());
+}
+
+#[test]
+fn test_lint() {
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(filename = "derived-lint")]
+    struct LintedPrefs {
+        #[pref(default = "guest".to_string(), required)]
+        account: String,
+        #[pref(timestamp)]
+        last_sync: i64,
+    }
+
+    let prefs = Prefs::<LintedPrefs>::load_testing();
+    assert!(prefs.lint().unwrap().is_clean());
+    std::fs::write(prefs.get_preferences_file_path(), "account = \"\"\nlast_sync = 32503680000\n").unwrap();
+    assert_eq!(prefs.lint().unwrap().issues.len(), 2);
+}
+
+#[test]
+fn test_load_in_memory() {
+    let mut prefs = Prefs::<DerivedPrefs>::load_in_memory();
+    assert!(!prefs.is_persistent());
+    prefs.edit().dark_mode = true;
+    prefs.reload().unwrap();
+    assert!(prefs.get().dark_mode);
+}
+
+#[test]
+fn test_transaction() {
+    let mut prefs = Prefs::<DerivedPrefs>::load_in_memory();
+    let result = prefs.transaction(|values| {
+        values.text_size = 20;
+        Err("cancelled")
+    });
+    assert!(matches!(result, Err(easy_prefs::transaction::TxError::Aborted("cancelled"))));
+    assert_eq!(prefs.get().text_size, 14);
+
+    // Struct validation fails the save: the sidebar is too wide for the font.
+    let result = prefs.transaction(|values| {
+        values.sidebar_width = 5000;
+        Ok::<_, String>(())
+    });
+    assert!(matches!(result, Err(easy_prefs::transaction::TxError::Save(_))));
+    assert_eq!(prefs.get().sidebar_width, 0);
+
+    prefs
+        .transaction(|values| {
+            values.dark_mode = true;
+            Ok::<_, String>(())
+        })
+        .unwrap();
+    assert!(prefs.get().dark_mode);
+}
+
+#[test]
+fn test_on_change() {
+    use easy_prefs::change::{ChangeSet, SaveSource};
+    use std::sync::Mutex;
+
+    static CHANGES: Mutex<Vec<ChangeSet>> = Mutex::new(Vec::new());
+
+    fn record(change: &ChangeSet) {
+        CHANGES.lock().unwrap().push(change.clone());
+    }
+
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(filename = "derived-changes", on_change = record)]
+    struct TrackedPrefs {
+        #[pref(default = 1, name = "zoom_level")]
+        zoom: i32,
+    }
+
+    let mut prefs = Prefs::<TrackedPrefs>::load_in_memory();
+    prefs.edit().zoom = 2;
+    let changes = std::mem::take(&mut *CHANGES.lock().unwrap());
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].field, "zoom_level");
+    assert_eq!(changes[0].old, Some(1.into()));
+    assert_eq!(changes[0].new, Some(2.into()));
+    assert_eq!(changes[0].source, SaveSource::EditGuard);
+}
+
+#[test]
+fn test_load_and_save_hooks() {
+    fn normalize(prefs: &mut HookedPrefs) {
+        prefs.tags.sort();
+        prefs.tags.dedup();
+    }
+
+    fn require_tags(prefs: &HookedPrefs) -> Result<(), String> {
+        if prefs.tags.is_empty() {
+            return Err("at least one tag".to_string());
+        }
+        Ok(())
+    }
+
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(filename = "derived-hooks", on_after_load = normalize, on_before_save = require_tags)]
+    struct HookedPrefs {
+        #[pref(default = vec!["inbox".to_string()])]
+        tags: Vec<String>,
+    }
+
+    let mut prefs = Prefs::<HookedPrefs>::load_testing();
+    std::fs::write(prefs.get_preferences_file_path(), "tags = [\"work\", \"home\", \"work\"]\n").unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get().tags, ["home", "work"]);
+
+    let mut guard = prefs.edit();
+    guard.tags.clear();
+    assert!(matches!(guard.commit(), Err(easy_prefs::SaveError::ValidationError(_))));
+}
+
+#[test]
+fn test_flush_all() {
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(filename = "derived-flush")]
+    struct FlushedPrefs {
+        #[pref(default = 1)]
+        volume: i32,
+    }
+
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("prefs");
+    let mut prefs = Prefs::<FlushedPrefs>::load_with_error(dir.to_str().unwrap()).unwrap();
+    prefs.edit().volume = 2;
+
+    // A file where the directory should be makes the next save fail.
+    std::fs::remove_dir_all(&dir).unwrap();
+    std::fs::write(&dir, "").unwrap();
+    let mut guard = prefs.edit();
+    guard.volume = 3;
+    assert!(guard.commit().is_err());
+    assert!(easy_prefs::flush_all().is_err());
+
+    std::fs::remove_file(&dir).unwrap();
+    std::fs::create_dir(&dir).unwrap();
+    assert!(easy_prefs::flush_all().unwrap() >= 1);
+    let stored = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+    assert!(stored.contains("volume = 3"));
+}
+
+#[test]
+fn test_shipped_defaults() {
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(filename = "derived-shipped", defaults = "count = 7\nlabel = \"shipped\"\n")]
+    struct ShippedPrefs {
+        #[pref(default = 1)]
+        count: i32,
+        label: String,
+        #[serde(skip)]
+        cache: Vec<u8>,
+    }
+
+    let prefs = Prefs::<ShippedPrefs>::load_testing();
+    assert_eq!(prefs.get().count, 7);
+    assert_eq!(prefs.get().label, "shipped");
+    assert!(prefs.get().cache.is_empty());
+}
+
+#[test]
+fn test_schema_hash_matches_macro() {
+    easy_prefs::easy_prefs! {
+        struct MacroSchema {
+            pub count: i32 = 0 => "count",
+            pub tags: Vec<String> = Vec::new() => "tags",
+        },
+        "macro-schema"
+    }
+
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(filename = "derived-schema")]
+    struct DerivedSchema {
+        count: i32,
+        tags: Vec<String>,
+    }
+
+    assert_eq!(<DerivedSchema as PrefsData>::SCHEMA_HASH, MacroSchema::SCHEMA_HASH);
+}
+
+#[test]
+fn test_sync() {
+    use easy_prefs::sync::{Resolution, SyncProvider};
+
+    #[derive(Default)]
+    struct Remote(Option<(String, u64)>);
+
+    impl SyncProvider for Remote {
+        fn pull(&mut self) -> Result<Option<(String, u64)>, std::io::Error> {
+            Ok(self.0.clone())
+        }
+
+        fn push(&mut self, serialized: &str, version: u64) -> Result<(), std::io::Error> {
+            self.0 = Some((serialized.to_string(), version));
+            Ok(())
+        }
+    }
+
+    common::use_memory_secret_store();
+    let mut remote = Remote::default();
+    let mut desktop = Prefs::<DerivedPrefs>::load_testing();
+    desktop.edit().theme = "dark".to_string();
+    desktop.edit().sidebar_width = 300;
+    desktop.sync(&mut remote, Resolution::MergeFields).unwrap();
+    assert!(!remote.0.as_ref().unwrap().0.contains("sidebar_width"));
+
+    let mut laptop = Prefs::<DerivedPrefs>::load_testing();
+    let report = laptop.sync(&mut remote, Resolution::MergeFields).unwrap();
+    assert_eq!(report.pulled, ["theme"]);
+    assert_eq!(laptop.get().theme, "dark");
+    assert_eq!(laptop.get().sidebar_width, 0);
+}
+
+#[test]
+fn test_gc() {
+    use easy_prefs::gc::GcPolicy;
+
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(filename = "derived-gc")]
+    struct GcPrefs {
+        zoom: i32,
+    }
+
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+    Prefs::<GcPrefs>::load_profile(dir, "old").unwrap().edit().zoom = 2;
+    let mut open = Prefs::<GcPrefs>::load_profile(dir, "open").unwrap();
+    open.edit().zoom = 3;
+
+    let report = Prefs::<GcPrefs>::gc(dir, &GcPolicy::new().keep_only(&[], &[])).unwrap();
+    assert_eq!(report.removed, ["derived-gc.old.toml"]);
+    assert!(std::path::Path::new(&open.get_preferences_file_path()).exists());
+}
+
+#[test]
+fn test_serialization_error_names_field() {
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(filename = "derived-unserializable")]
+    struct CounterPrefs {
+        label: String,
+        #[pref(name = "bytes.sent")]
+        bytes_sent: u64,
+    }
+
+    let mut prefs = Prefs::<CounterPrefs>::load_testing();
+    let result = prefs.transaction(|prefs| {
+        prefs.bytes_sent = u64::MAX;
+        Ok::<_, ()>(())
+    });
+    match result {
+        Err(easy_prefs::transaction::TxError::Save(SaveError::FieldSerializationFailed(field, _))) => assert_eq!(field, "bytes.sent"),
+        other => panic!("Expected FieldSerializationFailed, got {:?}", other),
+    }
+    assert_eq!(prefs.get().bytes_sent, 0);
+}
+
+#[test]
+fn test_strict_load() {
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(filename = "derived-strict", strict = true)]
+    struct StrictPrefs {
+        #[pref(name = "font_size")]
+        text_size: i32,
+    }
+
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(tmp.path().join("derived-strict.toml"), "text_size = 12\nfont_size = \"big\"\n").unwrap();
+    match Prefs::<StrictPrefs>::load_with_error(tmp.path().to_str().unwrap()) {
+        Err(LoadError::ValidationError(e)) => assert_eq!(
+            e.reason,
+            "2 problems in the stored document:\n\
+             line 1, column 1: unknown key 'text_size'\n\
+             line 2, column 13: 'font_size': invalid type: string \"big\", expected i32"
+        ),
+        other => panic!("Expected ValidationError, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_load_lenient() {
+    #[derive(Serialize, Deserialize, EasyPrefs)]
+    #[prefs(filename = "derived-lenient")]
+    struct LenientPrefs {
+        #[pref(default = 14, name = "font_size")]
+        text_size: i32,
+        #[pref(default = false)]
+        dark_mode: bool,
+    }
+
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(tmp.path().join("derived-lenient.toml"), "font_size = \"big\"\ndark_mode = true\n").unwrap();
+    let (prefs, warnings) = Prefs::<LenientPrefs>::load_lenient(tmp.path().to_str().unwrap());
+    assert_eq!(prefs.get().text_size, 14);
+    assert!(prefs.get().dark_mode);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].field, "font_size");
+    assert_eq!(warnings[0].raw_value.as_deref(), Some("\"big\""));
+}
+
+#[test]
+fn test_embed() {
+    let mut prefs = Prefs::<DerivedPrefs>::load_testing();
+    prefs.edit().text_size = 20;
+    let embedded = prefs.embed().unwrap();
+    assert_eq!(embedded["font_size"].as_integer(), Some(20));
+    assert_eq!(easy_prefs::embed::schema_hash(&embedded), Some(DerivedPrefs::SCHEMA_HASH));
+
+    let mut other = Prefs::<DerivedPrefs>::load_testing();
+    other.unembed(&embedded).unwrap();
+    assert_eq!(other.get().text_size, 20);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_json() {
+    let mut prefs = Prefs::<DerivedPrefs>::load_testing();
+    prefs.edit().text_size = 20;
+    let json = prefs.to_json().unwrap();
+    assert!(json.contains(r#""font_size":20"#), "{json}");
+
+    let mut other = Prefs::<DerivedPrefs>::load_testing();
+    other.from_json(&json).unwrap();
+    assert_eq!(other.get().text_size, 20);
+}
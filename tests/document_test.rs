@@ -0,0 +1,70 @@
+use easy_prefs::{easy_prefs, LoadError};
+
+easy_prefs! {
+    struct DocumentPrefs {
+        pub zoom: f64 = 1.0 => "zoom",
+        pub cursor_line: u32 = 0 => "cursor_line",
+    },
+    "document-prefs",
+    single_instance = false
+}
+
+easy_prefs! {
+    struct SingleDocumentPrefs {
+        pub zoom: f64 = 1.0 => "zoom",
+    },
+    "single-document-prefs"
+}
+
+#[test]
+fn test_documents_are_independent() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    let mut notes = DocumentPrefs::load_for(path, "/home/ann/notes.md").unwrap();
+    let mut other_notes = DocumentPrefs::load_for(path, "/tmp/notes.md").unwrap();
+    notes.save_zoom(1.5).unwrap();
+    other_notes.save_cursor_line(42).unwrap();
+
+    let notes_path = notes.get_preferences_file_path();
+    let file_name = std::path::Path::new(&notes_path).file_name().unwrap().to_str().unwrap();
+    assert!(file_name.starts_with("document-prefs.notes_md-"));
+    assert_ne!(notes_path, other_notes.get_preferences_file_path());
+
+    // Same identifier, same file; many instances are fine with `single_instance = false`.
+    let reopened = DocumentPrefs::load_for(path, "/home/ann/notes.md").unwrap();
+    assert_eq!(*reopened.get_zoom(), 1.5);
+    assert_eq!(*reopened.get_cursor_line(), 0);
+    assert_eq!(*DocumentPrefs::load_for(path, "/tmp/notes.md").unwrap().get_cursor_line(), 42);
+}
+
+#[test]
+fn test_one_instance_per_document() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    let _default = SingleDocumentPrefs::load_with_error(path).unwrap();
+    let _profile = SingleDocumentPrefs::load_profile(path, "a").unwrap();
+    let doc = SingleDocumentPrefs::load_for(path, "a").unwrap();
+    assert!(matches!(
+        SingleDocumentPrefs::load_for(path, "a"),
+        Err(LoadError::InstanceAlreadyLoaded)
+    ));
+    drop(doc);
+    SingleDocumentPrefs::load_for(path, "a").unwrap();
+}
+
+#[test]
+fn test_document_keys() {
+    let key = easy_prefs::document::storage_key("prefs.toml", "C:\\work\\My Report (v2).docx").unwrap();
+    assert!(key.starts_with("prefs.My_Report__v2__docx-"));
+    assert!(key.ends_with(".toml"));
+    assert!(!key.contains(['/', '\\', ' ']));
+
+    let long = easy_prefs::document::storage_key("prefs.toml", &"x".repeat(200)).unwrap();
+    assert!(long.len() < 80);
+    assert!(matches!(
+        easy_prefs::document::storage_key("prefs.toml", ""),
+        Err(LoadError::StorageError(e)) if e.kind() == std::io::ErrorKind::InvalidInput
+    ));
+}
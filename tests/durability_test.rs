@@ -0,0 +1,52 @@
+use easy_prefs::easy_prefs;
+use easy_prefs::storage::native::FileStorage;
+use easy_prefs::storage::{Durability, Storage};
+
+easy_prefs! {
+    struct DurablePrefs {
+        pub activation: String = String::new() => "activation",
+    },
+    "durable-prefs",
+    durability = Durability::Fsync,
+    backups = 1
+}
+
+easy_prefs! {
+    struct FastPrefs {
+        pub volume: i32 = 5 => "volume",
+    },
+    "fast-prefs"
+}
+
+#[test]
+fn test_default_durability() {
+    assert_eq!(Durability::default(), Durability::Fast);
+    assert_eq!(FastPrefs::OPTIONS.durability, None);
+    assert_eq!(DurablePrefs::OPTIONS.durability, Some(Durability::Fsync));
+}
+
+#[test]
+fn test_fsync_writes() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("nested");
+    let mut storage = FileStorage::new(dir.to_str().unwrap());
+    storage.set_durability(Durability::Fsync);
+    storage.write("default.toml", "a = 1\n").unwrap();
+    storage.write("default.toml", "a = 2\n").unwrap();
+    assert_eq!(storage.read("default.toml").unwrap().as_deref(), Some("a = 2\n"));
+}
+
+#[test]
+fn test_durability_option() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+    let mut prefs = DurablePrefs::load_with_error(dir).unwrap();
+    prefs.save_activation("first".to_string()).unwrap();
+    prefs.save_activation("second".to_string()).unwrap();
+    drop(prefs);
+
+    let prefs = DurablePrefs::load_with_error(dir).unwrap();
+    assert_eq!(prefs.get_activation(), "second");
+    let backup = std::fs::read_to_string(format!("{}.bak", prefs.get_preferences_file_path())).unwrap();
+    assert!(backup.contains("first"));
+}
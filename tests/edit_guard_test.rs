@@ -113,3 +113,92 @@ fn test_edit_guard_warning_on_long_hold() {
     // Verify the value was still saved
     assert_eq!(*prefs.get_value1(), 100);
 }
+
+mod commit {
+    use easy_prefs::storage::Storage;
+    use easy_prefs::{easy_prefs, SaveError};
+
+    #[derive(Debug)]
+    struct FullDisk;
+
+    impl Storage for FullDisk {
+        fn read(&self, _key: &str) -> Result<Option<String>, std::io::Error> {
+            Ok(None)
+        }
+
+        fn write(&self, _key: &str, _data: &str) -> Result<(), std::io::Error> {
+            Err(std::io::Error::other("no space left on device"))
+        }
+
+        fn get_path(&self, key: &str) -> String {
+            key.to_string()
+        }
+    }
+
+    fn check_limit(prefs: &CommitPrefs) -> Result<(), String> {
+        if *prefs.get_count() > 10 {
+            return Err("count must be at most 10".into());
+        }
+        Ok(())
+    }
+
+    easy_prefs! {
+        pub struct CommitPrefs {
+            pub count: i32 = 0 => "count",
+            pub name: Option<String> = None => "name",
+            pub tags: Vec<String> = Vec::new() => "tags",
+        },
+        "commit-test",
+        validate_struct = check_limit
+    }
+
+    #[test]
+    fn test_commit_saves_and_reports_errors() {
+        {
+            let mut prefs = CommitPrefs::load_testing();
+            let mut edit = prefs.edit();
+            edit.set_count(3);
+            edit.commit().unwrap();
+            let contents = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+            assert!(contents.contains("count = 3"));
+
+            let mut edit = prefs.edit();
+            edit.set_count(11);
+            match edit.commit() {
+                Err(SaveError::ValidationError(e)) => assert!(e.reason.contains("at most 10")),
+                other => panic!("expected a validation error, got {other:?}"),
+            }
+        }
+
+        let mut prefs = CommitPrefs::load_with_storage(Box::new(FullDisk)).unwrap();
+        let mut edit = prefs.edit();
+        edit.set_count(5);
+        match edit.commit() {
+            Err(SaveError::Io(e)) => assert!(e.to_string().contains("no space left")),
+            other => panic!("expected a storage error, got {other:?}"),
+        }
+        // The value stays in memory so the caller can retry.
+        assert_eq!(*prefs.get_count(), 5);
+        assert!(prefs.edit().commit().is_ok());
+    }
+
+    #[test]
+    fn test_cancel_discards_changes() {
+        let mut prefs = CommitPrefs::load_testing();
+        prefs.save_count(2).unwrap();
+        prefs.save_name(Some("kept".to_string())).unwrap();
+        let path = prefs.get_preferences_file_path();
+        let before = std::fs::read_to_string(&path).unwrap();
+
+        let mut edit = prefs.edit();
+        edit.set_count(9);
+        edit.set_name(None);
+        edit.push_tags("new".to_string());
+        edit.cancel();
+
+        assert_eq!(*prefs.get_count(), 2);
+        assert_eq!(prefs.get_name().as_deref(), Some("kept"));
+        assert!(prefs.get_tags().is_empty());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), before);
+    }
+}
@@ -0,0 +1,63 @@
+use easy_prefs::toml::{Table, Value};
+use easy_prefs::{easy_prefs, embed, LoadError};
+
+mod common;
+
+easy_prefs! {
+    pub struct EmbeddedPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub font_size: i32 = 14 => "font_size" (range = 8..=72, on_invalid = clamp),
+        pub token: String = String::new() => "token" (secret),
+        pub window_x: i32 = 0 => "window_x" (device),
+    },
+    "embedded-prefs"
+}
+
+#[test]
+fn test_embed_round_trip() {
+    common::use_memory_secret_store();
+    let mut prefs = EmbeddedPrefs::load_testing();
+    prefs.save_theme("dark".to_string()).unwrap();
+    prefs.save_token("hunter22".to_string()).unwrap();
+    prefs.save_window_x(300).unwrap();
+
+    let embedded = prefs.embed().unwrap();
+    let keys: Vec<&str> = embedded.keys().map(String::as_str).collect();
+    assert_eq!(keys, ["theme", "font_size", embed::VERSION_KEY, embed::SCHEMA_KEY]);
+    assert_eq!(embed::schema_hash(&embedded), Some(EmbeddedPrefs::SCHEMA_HASH));
+
+    // Stored and read back as part of another document.
+    let mut project = Table::new();
+    project.insert("prefs".to_string(), Value::Table(embedded));
+    let project: Table = easy_prefs::toml::to_string(&project).unwrap().parse().unwrap();
+
+    let mut other = EmbeddedPrefs::load_testing();
+    other.unembed(project["prefs"].as_table().unwrap()).unwrap();
+    assert_eq!(other.get_theme(), "dark");
+    assert_eq!(other.get_token(), "");
+    assert_eq!(*other.get_window_x(), 0);
+
+    // Values go through the load-time repairs; missing fields keep their values.
+    let mut older = Table::new();
+    older.insert(embed::VERSION_KEY.to_string(), Value::Integer(1));
+    older.insert("font_size".to_string(), Value::Integer(100));
+    other.unembed(&older).unwrap();
+    assert_eq!(*other.get_font_size(), 72);
+    assert_eq!(other.get_theme(), "dark");
+}
+
+#[test]
+fn test_unembed_rejects_unknown_formats() {
+    let mut prefs = EmbeddedPrefs::load_testing();
+    let mut newer = prefs.embed().unwrap();
+    newer.insert(embed::VERSION_KEY.to_string(), Value::Integer(embed::VERSION + 1));
+    newer.insert("theme".to_string(), Value::String("dark".to_string()));
+
+    for table in [newer, Table::new()] {
+        match prefs.unembed(&table) {
+            Err(LoadError::StorageError(e)) => assert_eq!(e.kind(), std::io::ErrorKind::InvalidData),
+            other => panic!("Expected InvalidData, got {:?}", other),
+        }
+    }
+    assert_eq!(prefs.get_theme(), "light");
+}
@@ -0,0 +1,56 @@
+#![cfg(all(feature = "encryption", not(target_arch = "wasm32")))]
+
+use easy_prefs::storage::encrypted::EncryptedFileStorage;
+use easy_prefs::storage::Storage;
+use easy_prefs::{easy_prefs, LoadError};
+
+easy_prefs! {
+    struct SecretPrefs {
+        pub refresh_token: String = String::new() => "refresh_token",
+    },
+    "secret-prefs"
+}
+
+const KEY: [u8; 32] = [7; 32];
+
+#[test]
+fn test_encrypted_storage_round_trip() {
+    let test_dir = format!("/tmp/easy_prefs_encrypted_{}", std::process::id());
+    let storage = EncryptedFileStorage::new(&test_dir, &KEY);
+
+    storage.write("doc.toml", "token = \"abc123\"").unwrap();
+    let on_disk = std::fs::read_to_string(storage.get_path("doc.toml")).unwrap();
+    assert!(!on_disk.contains("abc123"), "plaintext must not reach the disk");
+
+    assert_eq!(storage.read("doc.toml").unwrap().as_deref(), Some("token = \"abc123\""));
+
+    // A different key, or the same file under another name, must not decrypt.
+    let wrong_key = EncryptedFileStorage::new(&test_dir, &[8; 32]);
+    assert!(wrong_key.read("doc.toml").is_err());
+    std::fs::copy(storage.get_path("doc.toml"), storage.get_path("other.toml")).unwrap();
+    assert!(storage.read("other.toml").is_err());
+
+    let _ = std::fs::remove_dir_all(&test_dir);
+}
+
+#[test]
+fn test_load_encrypted() {
+    let test_dir = format!("/tmp/easy_prefs_load_encrypted_{}", std::process::id());
+    {
+        let mut prefs = SecretPrefs::load_encrypted(&test_dir, &KEY).unwrap();
+        prefs.save_refresh_token("oauth-refresh".to_string()).unwrap();
+    }
+
+    let contents = std::fs::read_to_string(format!("{test_dir}/secret-prefs.toml")).unwrap();
+    assert!(!contents.contains("oauth-refresh"));
+
+    {
+        let prefs = SecretPrefs::load_encrypted(&test_dir, &KEY).unwrap();
+        assert_eq!(prefs.get_refresh_token(), "oauth-refresh");
+    }
+
+    let result = SecretPrefs::load_encrypted(&test_dir, &[0; 32]);
+    assert!(matches!(result, Err(LoadError::StorageError(_))));
+
+    let _ = std::fs::remove_dir_all(&test_dir);
+}
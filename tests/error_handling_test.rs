@@ -1,4 +1,5 @@
-use easy_prefs::{easy_prefs, LoadError};
+use easy_prefs::storage::Storage;
+use easy_prefs::{easy_prefs, LoadError, SaveError};
 
 easy_prefs! {
     struct TestErrorPrefs {
@@ -7,6 +8,54 @@ easy_prefs! {
     "test-error-prefs"
 }
 
+easy_prefs! {
+    struct MultiInstancePrefs {
+        pub value: i32 = 0 => "value",
+    },
+    "multi-instance-prefs",
+    single_instance = false
+}
+
+easy_prefs! {
+    struct PluginPrefs {
+        pub value: i32 = 0 => "value",
+    },
+    "plugin-prefs",
+    singleton = false
+}
+
+easy_prefs! {
+    struct TakeoverPrefs {
+        pub value: i32 = 0 => "value",
+    },
+    "takeover-prefs"
+}
+
+easy_prefs! {
+    struct SaveErrorPrefs {
+        pub value: i32 = 0 => "value",
+    },
+    "save-error-prefs"
+}
+
+/// Storage whose writes always fail with the given error kind.
+#[derive(Debug)]
+struct FailingStorage(std::io::ErrorKind);
+
+impl Storage for FailingStorage {
+    fn read(&self, _key: &str) -> Result<Option<String>, std::io::Error> {
+        Ok(None)
+    }
+
+    fn write(&self, _key: &str, _data: &str) -> Result<(), std::io::Error> {
+        Err(std::io::Error::new(self.0, "write failed"))
+    }
+
+    fn get_path(&self, key: &str) -> String {
+        key.to_string()
+    }
+}
+
 #[test]
 fn test_instance_already_loaded_error() {
     let test_dir = format!("/tmp/easy_prefs_error_test_{}", std::process::id());
@@ -28,6 +77,81 @@ fn test_instance_already_loaded_error() {
     let _ = std::fs::remove_dir_all(&test_dir);
 }
 
+#[test]
+fn test_single_instance_per_directory() {
+    let first_dir = format!("/tmp/easy_prefs_directory_a_{}", std::process::id());
+    let second_dir = format!("/tmp/easy_prefs_directory_b_{}", std::process::id());
+
+    let mut first = TestErrorPrefs::load_with_error(&first_dir).unwrap();
+    let mut second = TestErrorPrefs::load_with_error(&second_dir).unwrap();
+    first.save_value(1).unwrap();
+    second.save_value(2).unwrap();
+    let _profile = TestErrorPrefs::load_profile(&first_dir, "work").unwrap();
+    assert!(matches!(
+        TestErrorPrefs::load_with_error(&format!("{first_dir}/")),
+        Err(LoadError::InstanceAlreadyLoaded)
+    ));
+    assert!(matches!(
+        TestErrorPrefs::load_profile(&first_dir, "work"),
+        Err(LoadError::InstanceAlreadyLoaded)
+    ));
+
+    drop(first);
+    assert_eq!(*TestErrorPrefs::load_with_error(&first_dir).unwrap().get_value(), 1);
+    assert_eq!(*second.get_value(), 2);
+
+    let _ = std::fs::remove_dir_all(&first_dir);
+    let _ = std::fs::remove_dir_all(&second_dir);
+}
+
+#[test]
+fn test_try_load_timeout_waits_for_the_instance() {
+    let test_dir = format!("/tmp/easy_prefs_takeover_test_{}", std::process::id());
+    let timeout = std::time::Duration::from_secs(10);
+
+    let mut old = TakeoverPrefs::load_with_error(&test_dir).unwrap();
+    old.save_value(3).unwrap();
+    let started = std::time::Instant::now();
+    let short = TakeoverPrefs::try_load_timeout(&test_dir, std::time::Duration::from_millis(30));
+    assert!(matches!(short, Err(LoadError::InstanceAlreadyLoaded)));
+    assert!(started.elapsed() >= std::time::Duration::from_millis(30));
+
+    let release = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        drop(old);
+    });
+    let prefs = TakeoverPrefs::try_load_timeout(&test_dir, timeout).unwrap();
+    assert_eq!(*prefs.get_value(), 3);
+    release.join().unwrap();
+    drop(prefs);
+
+    // Other errors are not waited out.
+    std::fs::write(format!("{}/takeover-prefs.toml", test_dir), "value = \"x\"").unwrap();
+    let started = std::time::Instant::now();
+    let result = TakeoverPrefs::try_load_timeout(&test_dir, timeout);
+    assert!(matches!(result, Err(LoadError::DeserializationError(..))));
+    assert!(started.elapsed() < timeout);
+
+    let _ = std::fs::remove_dir_all(&test_dir);
+}
+
+#[test]
+fn test_single_instance_opt_out() {
+    let test_dir = format!("/tmp/easy_prefs_multi_instance_test_{}", std::process::id());
+
+    let mut first = MultiInstancePrefs::load_with_error(&test_dir).expect("First load should succeed");
+    first.save_value(7).unwrap();
+    let second = MultiInstancePrefs::load_with_error(&test_dir).expect("Second load should succeed");
+    assert_eq!(*second.get_value(), 7);
+    let _profile = MultiInstancePrefs::load_profile(&test_dir, "other").unwrap();
+    let _third = MultiInstancePrefs::load(&test_dir);
+
+    let _plugin = PluginPrefs::load_with_error(&test_dir).unwrap();
+    let _other_plugin = PluginPrefs::load_with_error(&test_dir).unwrap();
+
+    let _ = std::fs::remove_dir_all(&test_dir);
+}
+
 #[test]
 fn test_deserialization_error() {
     let test_dir = format!("/tmp/easy_prefs_deser_test_{}", std::process::id());
@@ -66,3 +190,68 @@ fn test_error_trait_implementation() {
     // Verify it implements std::error::Error
     let _: &dyn std::error::Error = &error;
 }
+
+#[test]
+fn test_save_error_variants() {
+    let unloaded = SaveErrorPrefs::default();
+    assert!(matches!(unloaded.save(), Err(SaveError::StorageUninitialized)));
+
+    {
+        let mut prefs = SaveErrorPrefs::load_with_storage(Box::new(FailingStorage(
+            std::io::ErrorKind::StorageFull,
+        )))
+        .unwrap();
+        let err = prefs.save_value(1).unwrap_err();
+        assert!(matches!(err, SaveError::QuotaExceeded));
+        assert_eq!(err.to_string(), "storage quota exceeded");
+    }
+
+    let mut prefs = SaveErrorPrefs::load_with_storage(Box::new(FailingStorage(
+        std::io::ErrorKind::PermissionDenied,
+    )))
+    .unwrap();
+    match prefs.save_value(2) {
+        Err(SaveError::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::PermissionDenied),
+        other => panic!("Expected Io, got {:?}", other),
+    }
+
+    // APIs reporting io::Error keep the original kind.
+    let err = prefs.set_value_from_str("3").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+}
+
+#[test]
+fn test_failed_save_after_import_keeps_save_error() {
+    let mut prefs = SaveErrorPrefs::load_with_storage(Box::new(FailingStorage(
+        std::io::ErrorKind::StorageFull,
+    )))
+    .unwrap();
+    let err = prefs.import_toml("value = 4").unwrap_err();
+    assert!(matches!(err, LoadError::SaveFailed(SaveError::QuotaExceeded)), "{err:?}");
+    assert_eq!(err.to_string(), "save error: storage quota exceeded");
+    assert!(std::error::Error::source(&err).is_some());
+}
+
+easy_prefs! {
+    struct UnserializablePrefs {
+        pub name: String = String::new() => "name",
+        pub bytes_sent: u64 = 0 => "bytes.sent",
+    },
+    "unserializable-prefs"
+}
+
+#[test]
+fn test_serialization_error_names_field() {
+    let mut prefs = UnserializablePrefs::load_testing();
+    let err = prefs.save_bytes_sent(u64::MAX).unwrap_err();
+    match &err {
+        SaveError::FieldSerializationFailed(field, _) => assert_eq!(*field, "bytes.sent"),
+        other => panic!("Expected FieldSerializationFailed, got {:?}", other),
+    }
+    assert!(err.to_string().ends_with("at field 'bytes.sent'"));
+    assert!(std::error::Error::source(&err).is_some());
+
+    // The value never reached the file.
+    let stored = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap_or_default();
+    assert!(!stored.contains(&u64::MAX.to_string()));
+}
@@ -0,0 +1,57 @@
+use easy_prefs::easy_prefs;
+
+mod common;
+
+easy_prefs! {
+    struct PortablePrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub volume: i32 = 50 => "volume" (min = 0, max = 100),
+        pub machine_id: String = String::new() => "machine_id" (no_export),
+        pub window_width: i32 = 800 => "window_width" (device),
+        pub token: String = String::new() => "token" (secret),
+    },
+    "export-prefs"
+}
+
+#[test]
+fn test_export_leaves_out_local_fields() {
+    common::use_memory_secret_store();
+    let mut prefs = PortablePrefs::load_testing();
+    {
+        let mut edit = prefs.edit();
+        edit.set_theme("dark".to_string());
+        edit.set_machine_id("a1b2".to_string());
+        edit.set_window_width(1920);
+        edit.set_token("hunter2".to_string());
+    }
+
+    let exported = prefs.export_toml().unwrap();
+    assert_eq!(exported, "theme = \"dark\"\nvolume = 50\n");
+    assert!(PortablePrefs::FIELDS[2].options.no_export);
+    assert!(!PortablePrefs::FIELDS[2].options.is_exported());
+}
+
+#[test]
+fn test_import_ignores_local_fields() {
+    common::use_memory_secret_store();
+    let mut prefs = PortablePrefs::load_testing();
+    prefs.save_machine_id("a1b2".to_string()).unwrap();
+    prefs
+        .import_toml("theme = \"dark\"\nmachine_id = \"other\"\nwindow_width = 640\ntoken = \"x\"\nunknown = 1\n")
+        .unwrap();
+    assert_eq!(prefs.get_theme(), "dark");
+    assert_eq!(*prefs.get_volume(), 50);
+    assert_eq!(prefs.get_machine_id(), "a1b2");
+    assert_eq!(*prefs.get_window_width(), 800);
+    assert_eq!(prefs.get_token(), "");
+
+    // The imported values were saved.
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_theme(), "dark");
+
+    assert!(matches!(
+        prefs.import_toml("theme = "),
+        Err(easy_prefs::LoadError::DeserializationError(..))
+    ));
+    assert_eq!(prefs.get_theme(), "dark");
+}
@@ -0,0 +1,158 @@
+use easy_prefs::dynamic::{DynamicPrefs, PrefValue};
+use easy_prefs::easy_prefs;
+use easy_prefs::field::Widget;
+use toml::Value;
+
+easy_prefs! {
+    pub struct InfoPrefs {
+        /// Use the dark color scheme.
+        pub dark_mode: bool = false => "dark_mode",
+        /// Size of the editor font,
+        ///   in points.
+        #[allow(dead_code)]
+        pub font_size: i32 = 14 => "font_size" (min = 6, max = 72),
+        pub recent_files: Vec<String> = Vec::new() => "recent_files",
+        pub nickname: Option<String> = None => "nickname",
+    },
+    "field-info-prefs"
+}
+
+easy_prefs! {
+    struct MapPrefs {
+        pub font_size: i32 = 14 => "font_size",
+        pub nickname: Option<String> = None => "nickname",
+        pub token: String = String::new() => "token" (secret),
+    },
+    "field-info-map-prefs"
+}
+
+easy_prefs! {
+    struct SectionInfoPrefs {
+        /// Main window geometry.
+        pub window: WindowInfo {
+            pub width: i32 = 800 => "width",
+        } => "window",
+        /// Interface language.
+        pub language: String = "en".to_string() => "language",
+    },
+    "field-info-section-prefs"
+}
+
+#[test]
+fn test_field_metadata() {
+    let [dark_mode, font_size, recent_files, nickname] = InfoPrefs::FIELDS else {
+        panic!("expected four fields");
+    };
+
+    assert_eq!(dark_mode.type_name, "bool");
+    assert_eq!(dark_mode.default, "false");
+    assert_eq!(dark_mode.doc, " Use the dark color scheme.");
+    assert_eq!(dark_mode.description(), "Use the dark color scheme.");
+
+    assert_eq!(font_size.default, "14");
+    assert_eq!(font_size.doc, " Size of the editor font,\n   in points.");
+    assert_eq!(font_size.description(), "Size of the editor font,\nin points.");
+    assert_eq!(font_size.options.max, Some(72.0));
+
+    assert_eq!(recent_files.type_name, "Vec<String>");
+    assert_eq!(recent_files.default, "Vec::new()");
+    assert_eq!(recent_files.doc, "");
+    assert_eq!(nickname.type_name, "Option<String>");
+}
+
+#[test]
+fn test_help_text() {
+    let [dark_mode, font_size, ..] = InfoPrefs::FIELDS else {
+        panic!("expected four fields");
+    };
+    assert_eq!(dark_mode.constraints(), None);
+    assert_eq!(font_size.constraints().as_deref(), Some("range: 6 to 72"));
+
+    assert_eq!(
+        easy_prefs::field::help_text(InfoPrefs::FIELDS),
+        "  dark_mode <bool> (default: false)
+      Use the dark color scheme.
+  font_size <i32> (default: 14)
+      Size of the editor font,
+      in points.
+      range: 6 to 72
+  recent_files <Vec<String>> (default: Vec::new())
+  nickname <Option<String>> (default: None)
+"
+    );
+    assert!(easy_prefs::field::help_text(SectionInfoPrefs::FIELDS)
+        .contains("  language <String> (default: \"en\")\n      Interface language.\n"));
+
+    let prefs = InfoPrefs::load_testing();
+    assert_eq!(prefs.field_description("dark_mode").as_deref(), Some("Use the dark color scheme."));
+    assert_eq!(prefs.field_description("recent_files").as_deref(), Some(""));
+    assert_eq!(prefs.field_description("missing"), None);
+}
+
+#[test]
+fn test_iter_current_values() {
+    let mut prefs = InfoPrefs::load_testing();
+    prefs.save_font_size(18).unwrap();
+    prefs.push_recent_files("notes.txt".to_string()).unwrap();
+
+    let values: Vec<_> = prefs.iter().map(|(field, value)| (field.key, value)).collect();
+    assert_eq!(
+        values,
+        [
+            ("dark_mode", Some(Value::Boolean(false))),
+            ("font_size", Some(Value::Integer(18))),
+            ("recent_files", Some(Value::Array(vec![Value::from("notes.txt")]))),
+            ("nickname", None),
+        ]
+    );
+}
+
+#[test]
+fn test_as_map() {
+    let prefs = MapPrefs::load_testing();
+    let map = prefs.as_map();
+    assert_eq!(map.keys().copied().collect::<Vec<_>>(), ["font_size", "token"]);
+    assert_eq!(map["font_size"], PrefValue::Value(Value::Integer(14)));
+    assert_eq!(map["font_size"].to_string(), "14");
+    assert_eq!(map["token"], PrefValue::Redacted);
+    assert_eq!(map["token"].as_value(), None);
+    assert_eq!(map["token"].to_string(), easy_prefs::secrets::REDACTED);
+}
+
+#[test]
+fn test_widgets() {
+    let widgets: Vec<_> = InfoPrefs::FIELDS.iter().map(|field| field.widget()).collect();
+    assert_eq!(
+        widgets,
+        [
+            Widget::Toggle,
+            Widget::Slider { min: 6.0, max: 72.0, step: None },
+            Widget::Other,
+            Widget::Text,
+        ]
+    );
+    let widgets: Vec<_> = MapPrefs::FIELDS.iter().map(|field| field.widget()).collect();
+    assert_eq!(widgets, [Widget::Number, Widget::Text, Widget::Secret]);
+    assert_eq!(SectionInfoPrefs::FIELDS[0].widget(), Widget::Other);
+}
+
+#[test]
+fn test_metadata_with_sections() {
+    let [window, language] = SectionInfoPrefs::FIELDS else {
+        panic!("expected two fields");
+    };
+    assert_eq!(window.type_name, "WindowInfo");
+    assert_eq!(window.description(), "Main window geometry.");
+    assert_eq!(language.description(), "Interface language.");
+
+    let prefs = SectionInfoPrefs::load_testing();
+    let (_, window_value) = prefs.iter().next().unwrap();
+    assert_eq!(window_value.unwrap().get("width"), Some(&Value::Integer(800)));
+}
+
+#[test]
+fn test_duplicate_key() {
+    const { assert!(easy_prefs::field::duplicate_key(InfoPrefs::FIELDS).is_none()) };
+    let fields = [InfoPrefs::FIELDS[0], InfoPrefs::FIELDS[1], InfoPrefs::FIELDS[0]];
+    assert_eq!(easy_prefs::field::duplicate_key(&fields), Some("dark_mode"));
+}
@@ -0,0 +1,50 @@
+#![cfg(unix)]
+
+use easy_prefs::easy_prefs;
+use easy_prefs::storage::native::FileStorage;
+use easy_prefs::storage::Storage;
+use std::os::unix::fs::PermissionsExt;
+
+easy_prefs! {
+    struct ModePrefs {
+        pub token: String = String::new() => "token",
+    },
+    "mode-prefs",
+    file_mode = 0o640,
+    backups = 1
+}
+
+fn mode(path: impl AsRef<std::path::Path>) -> u32 {
+    std::fs::metadata(path).unwrap().permissions().mode() & 0o777
+}
+
+#[test]
+fn test_file_storage_mode() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut storage = FileStorage::new(tmp.path().to_str().unwrap());
+    storage.write("default.toml", "a = 1\n").unwrap();
+    // NamedTempFile creates files readable by the owner only.
+    assert_eq!(mode(tmp.path().join("default.toml")), 0o600);
+
+    storage.set_file_mode(0o644);
+    storage.write("default.toml", "a = 2\n").unwrap();
+    storage.write("shared.toml", "a = 1\n").unwrap();
+    assert_eq!(mode(tmp.path().join("default.toml")), 0o644);
+    assert_eq!(mode(tmp.path().join("shared.toml")), 0o644);
+}
+
+#[test]
+fn test_file_mode_option() {
+    assert_eq!(ModePrefs::OPTIONS.file_mode, Some(0o640));
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+    let mut prefs = ModePrefs::load_with_error(dir).unwrap();
+    prefs.save_token("first".to_string()).unwrap();
+    prefs.save_token("second".to_string()).unwrap();
+    let path = prefs.get_preferences_file_path();
+    assert_eq!(mode(&path), 0o640);
+    assert_eq!(mode(format!("{path}.bak")), 0o640);
+
+    let testing = ModePrefs::load_testing();
+    assert_eq!(mode(testing.get_preferences_file_path()), 0o640);
+}
@@ -0,0 +1,117 @@
+use easy_prefs::storage::{MemoryStorage, Storage};
+use easy_prefs::easy_prefs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+easy_prefs! {
+    struct FlushedPrefs {
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "flushed-prefs"
+}
+
+// Tests share the global registry, so they take turns.
+static SERIAL: Mutex<()> = Mutex::new(());
+
+/// Storage whose writes fail while `offline` is set.
+#[derive(Debug, Clone, Default)]
+struct FlakyStorage {
+    inner: Arc<MemoryStorage>,
+    offline: Arc<AtomicBool>,
+}
+
+impl Storage for FlakyStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+        self.inner.read(key)
+    }
+
+    fn write(&self, key: &str, data: &str) -> Result<(), std::io::Error> {
+        if self.offline.load(Ordering::SeqCst) {
+            return Err(std::io::Error::other("disk unavailable"));
+        }
+        self.inner.write(key, data)
+    }
+
+    fn get_path(&self, key: &str) -> String {
+        format!("flaky::{key}")
+    }
+}
+
+#[test]
+fn test_flush_retries_failed_saves() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let storage = FlakyStorage::default();
+    let mut prefs = FlushedPrefs::load_with_storage(Box::new(storage.clone())).unwrap();
+
+    storage.offline.store(true, Ordering::SeqCst);
+    assert!(prefs.save_theme("dark".to_string()).is_err());
+    assert_eq!(prefs.get_theme(), "dark");
+    let error = easy_prefs::flush_all().unwrap_err();
+    assert_eq!(error.failures.len(), 1);
+    assert!(error.to_string().contains("flaky::flushed-prefs.toml: disk unavailable"));
+
+    storage.offline.store(false, Ordering::SeqCst);
+    assert_eq!(easy_prefs::flush_all().unwrap(), 1);
+    let stored = storage.read("flushed-prefs.toml").unwrap().unwrap();
+    assert!(stored.contains("theme = \"dark\""));
+    // Nothing is pending any more.
+    assert_eq!(easy_prefs::flush_all().unwrap(), 0);
+}
+
+#[test]
+fn test_successful_save_clears_pending() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let storage = FlakyStorage::default();
+    let mut prefs = FlushedPrefs::load_with_storage(Box::new(storage.clone())).unwrap();
+
+    storage.offline.store(true, Ordering::SeqCst);
+    prefs.edit().set_theme("dark".to_string());
+    storage.offline.store(false, Ordering::SeqCst);
+    prefs.edit().set_theme("blue".to_string());
+    assert_eq!(easy_prefs::flush_all().unwrap(), 0);
+    assert!(storage.read("flushed-prefs.toml").unwrap().unwrap().contains("theme = \"blue\""));
+}
+
+#[test]
+fn test_dropped_instances_are_unregistered() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let storage = FlakyStorage::default();
+    let mut prefs = FlushedPrefs::load_testing();
+    drop(FlushedPrefs::load_with_storage(Box::new(storage.clone())));
+
+    prefs.save_theme("blue".to_string()).unwrap();
+    storage.offline.store(true, Ordering::SeqCst);
+    assert_eq!(easy_prefs::flush_all().unwrap(), 0);
+}
+
+/// Storage that loads other preferences while writing, which registers them.
+#[derive(Debug, Clone, Default)]
+struct LoadingStorage(FlakyStorage);
+
+impl Storage for LoadingStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+        self.0.read(key)
+    }
+
+    fn write(&self, key: &str, data: &str) -> Result<(), std::io::Error> {
+        drop(FlushedPrefs::load_testing());
+        self.0.write(key, data)
+    }
+
+    fn get_path(&self, key: &str) -> String {
+        self.0.get_path(key)
+    }
+}
+
+#[test]
+fn test_flush_does_not_hold_the_registry() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let storage = LoadingStorage::default();
+    let mut prefs = FlushedPrefs::load_with_storage(Box::new(storage.clone())).unwrap();
+
+    storage.0.offline.store(true, Ordering::SeqCst);
+    assert!(prefs.save_theme("dark".to_string()).is_err());
+    storage.0.offline.store(false, Ordering::SeqCst);
+    assert_eq!(easy_prefs::flush_all().unwrap(), 1);
+    assert!(storage.read("flushed-prefs.toml").unwrap().unwrap().contains("theme = \"dark\""));
+}
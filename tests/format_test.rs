@@ -0,0 +1,92 @@
+use easy_prefs::easy_prefs;
+use easy_prefs::format::{Format, Toml};
+use easy_prefs::LoadError;
+use serde::{de::Error as _, ser::Error as _};
+use toml::{Table, Value};
+
+/// Stores each value as `key: value`, strings quoted with `'`.
+struct Colon;
+
+impl Format for Colon {
+    fn name(&self) -> &'static str {
+        "colon"
+    }
+
+    fn to_string(&self, table: &Table) -> Result<String, toml::ser::Error> {
+        let mut text = String::new();
+        for (key, value) in table {
+            match value {
+                Value::String(value) => text.push_str(&format!("{key}: '{value}'\n")),
+                Value::Integer(value) => text.push_str(&format!("{key}: {value}\n")),
+                _ => return Err(toml::ser::Error::custom(format!("unsupported value for '{key}'"))),
+            }
+        }
+        Ok(text)
+    }
+
+    fn parse(&self, document: &str) -> Result<Table, toml::de::Error> {
+        let mut table = Table::new();
+        for line in document.lines().filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let (key, value) = line
+                .split_once(": ")
+                .ok_or_else(|| toml::de::Error::custom(format!("expected 'key: value', found '{line}'")))?;
+            let value = match value.strip_prefix('\'').and_then(|value| value.strip_suffix('\'')) {
+                Some(text) => Value::String(text.to_string()),
+                None => Value::Integer(value.parse().map_err(toml::de::Error::custom)?),
+            };
+            table.insert(key.to_string(), value);
+        }
+        Ok(table)
+    }
+}
+
+easy_prefs! {
+    struct ColonPrefs {
+        pub station: String = "lobby".to_string() => "station",
+        pub volume: i32 = 5 => "volume",
+    },
+    "colon-prefs",
+    format = &Colon,
+    integrity = true,
+    backups = 1
+}
+
+#[test]
+fn test_default_format() {
+    assert_eq!(Toml.name(), "toml");
+    assert_eq!(ColonPrefs::OPTIONS.format.name(), "colon");
+}
+
+#[test]
+fn test_custom_format_round_trip() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+    let mut prefs = ColonPrefs::load_with_error(dir).unwrap();
+    prefs.save_station("entrance".to_string()).unwrap();
+    prefs.save_volume(8).unwrap();
+    let path = prefs.get_preferences_file_path();
+    drop(prefs);
+
+    let stored = std::fs::read_to_string(&path).unwrap();
+    assert!(stored.starts_with("station: 'entrance'\nvolume: 8\n_generation: 2\n# easy_prefs integrity"));
+    let backup = std::fs::read_to_string(format!("{path}.bak")).unwrap();
+    assert!(backup.contains("volume: 5"));
+
+    let prefs = ColonPrefs::load_with_error(dir).unwrap();
+    assert_eq!(prefs.get_station(), "entrance");
+    assert_eq!(*prefs.get_volume(), 8);
+    assert_eq!(prefs.generation(), 2);
+}
+
+#[test]
+fn test_custom_format_errors() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+    std::fs::write(tmp.path().join("colon-prefs.toml"), "station = 'lobby'\n").unwrap();
+    let Err(LoadError::IntegrityFailure(..)) = ColonPrefs::load_with_error(dir) else {
+        panic!("expected an integrity failure");
+    };
+
+    let error = Colon.parse("station = 'lobby'").unwrap_err();
+    assert!(error.to_string().contains("expected 'key: value'"));
+}
@@ -0,0 +1,107 @@
+use easy_prefs::easy_prefs;
+
+easy_prefs! {
+    struct CliPrefs {
+        pub enabled: bool = false => "enabled",
+        pub retries: i32 = 3 => "retries" (min = 0, max = 10),
+        pub ratio: f64 = 0.5 => "ratio",
+        pub theme: String = "light".to_string() => "theme" (choices = ["light", "dark"]),
+        pub label: String = "default".to_string() => "label",
+        pub tags: Vec<String> = Vec::new() => "tags",
+        pub nickname: Option<String> = None => "nickname",
+    },
+    "cli-prefs"
+}
+
+#[test]
+fn test_set_from_str_parses_by_field_type() {
+    let mut prefs = CliPrefs::load_testing();
+    prefs.set_enabled_from_str("true").unwrap();
+    prefs.set_retries_from_str("7").unwrap();
+    prefs.set_ratio_from_str("2").unwrap();
+    prefs.set_theme_from_str("dark").unwrap();
+    prefs.set_label_from_str("42").unwrap();
+    prefs.set_tags_from_str(r#"["a", "b"]"#).unwrap();
+    prefs.set_nickname_from_str("ace").unwrap();
+
+    prefs.reload().unwrap();
+    assert!(*prefs.get_enabled());
+    assert_eq!(*prefs.get_retries(), 7);
+    assert_eq!(*prefs.get_ratio(), 2.0);
+    assert_eq!(prefs.get_theme(), "dark");
+    assert_eq!(prefs.get_label(), "42");
+    assert_eq!(prefs.get_tags(), &["a", "b"]);
+    assert_eq!(prefs.get_nickname().as_deref(), Some("ace"));
+}
+
+#[test]
+fn test_set_from_str_rejects_bad_input() {
+    let mut prefs = CliPrefs::load_testing();
+
+    let err = prefs.set_retries_from_str("many").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("retries"));
+
+    // Parses, but violates the declared constraints.
+    assert!(prefs.set_retries_from_str("11").is_err());
+    assert!(prefs.set_theme_from_str("blue").is_err());
+
+    assert_eq!(*prefs.get_retries(), 3);
+    assert_eq!(prefs.get_theme(), "light");
+}
+
+#[test]
+fn test_get_as_string() {
+    let mut prefs = CliPrefs::load_testing();
+    prefs.save_tags(vec!["x".to_string()]).unwrap();
+
+    assert_eq!(prefs.get_enabled_as_string(), "false");
+    assert_eq!(prefs.get_ratio_as_string(), "0.5");
+    assert_eq!(prefs.get_label_as_string(), "default");
+    assert_eq!(prefs.get_tags_as_string(), r#"["x"]"#);
+    assert_eq!(prefs.get_nickname_as_string(), "");
+}
+
+#[test]
+fn test_dynamic_access_by_key() {
+    use easy_prefs::dynamic::DynamicPrefs;
+
+    let mut prefs = CliPrefs::load_testing();
+    prefs.set_field_from_str("retries", "4").unwrap();
+    assert_eq!(*prefs.get_retries(), 4);
+    assert_eq!(prefs.get_field_string("theme").as_deref(), Some("light"));
+    assert_eq!(prefs.fields().len(), 7);
+
+    assert!(prefs.get_field_string("missing").is_none());
+    let err = prefs.set_field_from_str("missing", "1").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
+
+#[test]
+fn test_dynamic_values() {
+    use easy_prefs::dynamic::DynamicPrefs;
+    use toml::Value;
+
+    let mut prefs = CliPrefs::load_testing();
+    assert_eq!(prefs.get_value("retries"), Some(Value::Integer(3)));
+    assert_eq!(prefs.get_value("nickname"), None);
+    assert_eq!(prefs.get_value("missing"), None);
+
+    prefs.set_value("retries", Value::Float(5.0)).unwrap();
+    prefs.set_value("label", Value::Integer(42)).unwrap();
+    prefs
+        .set_value("tags", Value::Array(vec![Value::from("x")]))
+        .unwrap();
+    prefs.set_value("nickname", Value::from("ace")).unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(*prefs.get_retries(), 5);
+    assert_eq!(prefs.get_label(), "42");
+    assert_eq!(prefs.get_tags(), &["x"]);
+    assert_eq!(prefs.get_value("nickname"), Some(Value::from("ace")));
+
+    let kind = |result: Result<(), std::io::Error>| result.unwrap_err().kind();
+    assert_eq!(kind(prefs.set_value("retries", Value::from("many"))), std::io::ErrorKind::InvalidInput);
+    assert_eq!(kind(prefs.set_value("retries", Value::Integer(11))), std::io::ErrorKind::InvalidInput);
+    assert_eq!(kind(prefs.set_value("missing", Value::Integer(1))), std::io::ErrorKind::NotFound);
+    assert_eq!(*prefs.get_retries(), 5);
+}
@@ -0,0 +1,92 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use easy_prefs::easy_prefs;
+use easy_prefs::gc::GcPolicy;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+mod common;
+
+easy_prefs! {
+    struct GcPrefs {
+        pub zoom: i64 = 100 => "zoom",
+        pub token: String = String::new() => "token" (secret),
+    },
+    "gc-prefs",
+    backups = 1
+}
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+fn files(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+fn save_twice(prefs: &mut GcPrefs) {
+    prefs.save_zoom(110).unwrap();
+    prefs.save_zoom(120).unwrap();
+}
+
+#[test]
+fn test_gc_removes_unreferenced_files() {
+    common::use_memory_secret_store();
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+    save_twice(&mut GcPrefs::load_with_error(dir).unwrap());
+    save_twice(&mut GcPrefs::load_profile(dir, "work").unwrap());
+    let mut old = GcPrefs::load_profile(dir, "old").unwrap();
+    save_twice(&mut old);
+    old.save_token("old-token".to_string()).unwrap();
+    drop(old);
+    save_twice(&mut GcPrefs::load_for(dir, "/home/ann/notes.md").unwrap());
+    save_twice(&mut GcPrefs::load_for(dir, "/home/ann/gone.md").unwrap());
+    let mut open = GcPrefs::load_profile(dir, "open").unwrap();
+    open.save_zoom(130).unwrap();
+    std::fs::write(tmp.path().join("unrelated.txt"), "").unwrap();
+    let before = files(tmp.path());
+
+    let policy = GcPolicy::new().keep_only(&["work"], &["/home/ann/notes.md"]).dry_run(true);
+    let report = GcPrefs::gc(dir, &policy).unwrap();
+    assert!(report.dry_run);
+    let gone = easy_prefs::document::storage_key("gc-prefs.toml", "/home/ann/gone.md").unwrap();
+    assert_eq!(report.removed, [gone.as_str(), "gc-prefs.old.toml"]);
+    assert_eq!(report.files.len(), 4, "{:?}", report.files);
+    assert!(report.files.contains(&tmp.path().join("gc-prefs.old.toml.bak")));
+    assert_eq!(files(tmp.path()), before, "a dry run removes nothing");
+
+    let report = GcPrefs::gc(dir, &policy.dry_run(false)).unwrap();
+    assert_eq!(report.removed.len(), 2);
+    let remaining = files(tmp.path());
+    assert_eq!(remaining.len(), before.len() - 4);
+    assert!(remaining.iter().all(|name| !name.starts_with("gc-prefs.old.") && !name.starts_with(&gone)));
+    assert!(remaining.contains(&"gc-prefs.toml.bak".to_string()));
+    assert!(remaining.contains(&"gc-prefs.open.toml".to_string()), "loaded instances are kept");
+    assert!(remaining.contains(&"unrelated.txt".to_string()));
+
+    let old = GcPrefs::load_profile(dir, "old").unwrap();
+    assert_eq!(old.get_token(), "", "secrets are removed with the file");
+    drop(open);
+}
+
+#[test]
+fn test_gc_removes_unused_files() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+    GcPrefs::load_profile(dir, "recent").unwrap().save_zoom(90).unwrap();
+    GcPrefs::load_profile(dir, "stale").unwrap().save_zoom(80).unwrap();
+    let stale = std::fs::File::options().write(true).open(tmp.path().join("gc-prefs.stale.toml")).unwrap();
+    stale.set_modified(SystemTime::now() - 100 * DAY).unwrap();
+
+    let report = GcPrefs::gc(dir, &GcPolicy::new().unused_for(30 * DAY)).unwrap();
+    assert_eq!(report.removed, ["gc-prefs.stale.toml"]);
+    assert_eq!(files(tmp.path()), ["gc-prefs.recent.toml"]);
+
+    assert_eq!(GcPrefs::gc(dir, &GcPolicy::new()).unwrap(), Default::default(), "the default policy removes nothing");
+    let missing = tmp.path().join("missing");
+    assert!(GcPrefs::gc(missing.to_str().unwrap(), &GcPolicy::new().unused_for(DAY)).unwrap().removed.is_empty());
+}
@@ -0,0 +1,83 @@
+use easy_prefs::easy_prefs;
+
+easy_prefs! {
+    struct CountedPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub volume: i32 = 50 => "volume",
+    },
+    "generation-prefs"
+}
+
+easy_prefs! {
+    struct FormattedPrefs {
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "generation-formatted-prefs",
+    preserve_formatting = true
+}
+
+#[test]
+fn test_generation_bumps_on_save() {
+    let mut prefs = CountedPrefs::load_testing();
+    let start = prefs.generation();
+    prefs.save_theme("dark".to_string()).unwrap();
+    assert_eq!(prefs.generation(), start + 1);
+
+    // Unchanged values aren't saved.
+    prefs.save_theme("dark".to_string()).unwrap();
+    assert_eq!(prefs.generation(), start + 1);
+
+    {
+        let mut edit = prefs.edit();
+        edit.set_volume(10);
+        edit.set_theme("blue".to_string());
+    }
+    assert_eq!(prefs.generation(), start + 2);
+
+    let path = prefs.get_preferences_file_path();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(contents.contains(&format!("_generation = {}\n", start + 2)));
+
+    prefs.reload().unwrap();
+    assert_eq!(prefs.generation(), start + 2);
+    assert_eq!(prefs.get_theme(), "blue");
+}
+
+#[test]
+fn test_generation_continues_from_memory() {
+    let mut prefs = CountedPrefs::load_testing();
+    let start = prefs.generation();
+    let path = prefs.get_preferences_file_path();
+
+    // Another process saved in the meantime; the save doesn't read the file.
+    std::fs::write(&path, "_generation = 41\ntheme = \"dark\"\n").unwrap();
+    prefs.save_volume(1).unwrap();
+    assert_eq!(prefs.generation(), start + 1);
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(contents.matches("_generation").count(), 1);
+}
+
+#[test]
+fn test_generation_continues_from_stored_value() {
+    let mut prefs = FormattedPrefs::load_testing();
+    let path = prefs.get_preferences_file_path();
+
+    // Preserving formatting reads the file, so another process's save is picked up.
+    std::fs::write(&path, "_generation = 41\ntheme = \"dark\"\n").unwrap();
+    prefs.save_theme("blue".to_string()).unwrap();
+    assert_eq!(prefs.generation(), 42);
+}
+
+#[test]
+fn test_generation_with_preserved_formatting() {
+    let mut prefs = FormattedPrefs::load_testing();
+    let path = prefs.get_preferences_file_path();
+    std::fs::write(&path, "# my settings\n_generation = 7\ntheme = \"light\" # default\n").unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(prefs.generation(), 7);
+
+    prefs.save_theme("dark".to_string()).unwrap();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(contents, "# my settings\n_generation = 8\ntheme = \"dark\" # default\n");
+}
@@ -0,0 +1,67 @@
+use easy_prefs::{easy_prefs, LoadError};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+fn global_directory() -> Result<String, std::io::Error> {
+    let directory = std::env::temp_dir().join(format!("easy_prefs_global_test_{}", std::process::id()));
+    Ok(directory.to_string_lossy().into_owned())
+}
+
+static PROVIDER_READY: AtomicBool = AtomicBool::new(false);
+
+/// Fails until `PROVIDER_READY` is set, like a directory that isn't available yet.
+fn flaky_directory() -> Result<String, std::io::Error> {
+    if !PROVIDER_READY.load(Ordering::SeqCst) {
+        return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "no directory yet"));
+    }
+    let directory = std::env::temp_dir().join(format!("easy_prefs_flaky_global_test_{}", std::process::id()));
+    Ok(directory.to_string_lossy().into_owned())
+}
+
+easy_prefs! {
+    pub struct GlobalPrefs {
+        pub counter: i32 = 0 => "counter",
+    },
+    "global-prefs",
+    global = global_directory
+}
+
+easy_prefs! {
+    pub struct FlakyGlobalPrefs {
+        pub counter: i32 = 0 => "counter",
+    },
+    "flaky-global-prefs",
+    global = flaky_directory
+}
+
+#[test]
+fn test_global_is_loaded_once() {
+    let handles: Vec<_> = (0..4)
+        .map(|_| std::thread::spawn(|| GlobalPrefs::global() as *const GlobalPrefsHandle as usize))
+        .collect();
+    let addresses: Vec<usize> = handles.into_iter().map(|thread| thread.join().unwrap()).collect();
+    assert!(addresses.iter().all(|address| *address == addresses[0]));
+
+    GlobalPrefs::global().update_counter(|counter| *counter += 1).unwrap();
+    assert_eq!(GlobalPrefs::try_global().unwrap().get_counter(), 1);
+    let stored = std::fs::read_to_string(GlobalPrefs::global().read().get_preferences_file_path()).unwrap();
+    assert!(stored.contains("counter = 1"), "{stored}");
+
+    // The global handle holds the single-instance claim.
+    assert!(matches!(
+        GlobalPrefs::load_with_error(&global_directory().unwrap()),
+        Err(LoadError::InstanceAlreadyLoaded)
+    ));
+    let _ = std::fs::remove_dir_all(global_directory().unwrap());
+}
+
+#[test]
+fn test_failed_load_is_retried() {
+    match FlakyGlobalPrefs::try_global() {
+        Err(LoadError::StorageError(e)) => assert_eq!(e.kind(), std::io::ErrorKind::NotFound),
+        other => panic!("Expected StorageError, got {other:?}"),
+    }
+    PROVIDER_READY.store(true, Ordering::SeqCst);
+    FlakyGlobalPrefs::try_global().unwrap().save_counter(3).unwrap();
+    assert_eq!(FlakyGlobalPrefs::global().get_counter(), 3);
+    let _ = std::fs::remove_dir_all(flaky_directory().unwrap());
+}
@@ -0,0 +1,76 @@
+use easy_prefs::easy_prefs;
+
+easy_prefs! {
+    struct HistoryPrefs {
+        pub volume: i32 = 50 => "volume",
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "history-prefs",
+    history = 3
+}
+
+easy_prefs! {
+    struct NoHistoryPrefs {
+        pub volume: i32 = 50 => "volume",
+    },
+    "no-history-prefs"
+}
+
+#[test]
+fn test_history_keeps_distinct_states() {
+    let mut prefs = HistoryPrefs::load_testing();
+    prefs.save_volume(10).unwrap();
+    prefs.save_volume(20).unwrap();
+    prefs.save_theme("dark".to_string()).unwrap();
+
+    let history = prefs.history().unwrap();
+    assert_eq!(history.len(), 3);
+    assert!(history.iter().all(|entry| entry.hash.len() == 16));
+
+    // Saving an existing state again moves it to the front.
+    prefs.save_theme("light".to_string()).unwrap();
+    let again = prefs.history().unwrap();
+    assert_eq!(again.len(), 3);
+    assert_eq!(again[0].hash, history[1].hash);
+    assert_eq!(again[1].hash, history[0].hash);
+
+    // The oldest state falls out once the limit is reached.
+    prefs.save_volume(30).unwrap();
+    let trimmed = prefs.history().unwrap();
+    assert_eq!(trimmed.len(), 3);
+    assert!(!trimmed.iter().any(|entry| entry.hash == history[2].hash));
+}
+
+#[test]
+fn test_restore_by_hash_or_prefix() {
+    let mut prefs = HistoryPrefs::load_testing();
+    prefs.save_volume(10).unwrap();
+    let yesterday = prefs.history().unwrap()[0].hash.clone();
+    {
+        let mut edit = prefs.edit();
+        edit.set_volume(99);
+        edit.set_theme("dark".to_string());
+    }
+
+    prefs.restore(&yesterday[..8]).unwrap();
+    assert_eq!(*prefs.get_volume(), 10);
+    assert_eq!(prefs.get_theme(), "light");
+    assert_eq!(prefs.history().unwrap()[0].hash, yesterday);
+
+    // The restored values were saved.
+    prefs.reload().unwrap();
+    assert_eq!(*prefs.get_volume(), 10);
+
+    assert!(matches!(
+        prefs.restore("not-a-hash"),
+        Err(easy_prefs::LoadError::StorageError(e)) if e.kind() == std::io::ErrorKind::NotFound
+    ));
+    assert_eq!(*prefs.get_volume(), 10);
+}
+
+#[test]
+fn test_history_disabled_by_default() {
+    let mut prefs = NoHistoryPrefs::load_testing();
+    prefs.save_volume(10).unwrap();
+    assert!(prefs.history().unwrap().is_empty());
+}
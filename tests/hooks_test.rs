@@ -0,0 +1,50 @@
+use easy_prefs::{easy_prefs, SaveError};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static SYNCS_SCHEDULED: AtomicUsize = AtomicUsize::new(0);
+
+fn normalize(prefs: &mut ProfilePrefs) {
+    prefs._email = prefs._email.trim().to_lowercase();
+}
+
+fn schedule_sync(prefs: &ProfilePrefs) -> Result<(), String> {
+    if prefs.get_email().is_empty() {
+        return Err("an email is required".to_string());
+    }
+    SYNCS_SCHEDULED.fetch_add(1, Ordering::SeqCst);
+    Ok(())
+}
+
+easy_prefs! {
+    struct ProfilePrefs {
+        pub email: String = "user@example.com".to_string() => "email",
+        pub newsletter: bool = false => "newsletter",
+    },
+    "hooks-profile-prefs",
+    on_after_load = normalize,
+    on_before_save = schedule_sync
+}
+
+#[test]
+fn test_after_load_normalizes_values() {
+    let mut prefs = ProfilePrefs::load_testing();
+    std::fs::write(prefs.get_preferences_file_path(), "email = \"  Alice@Example.COM \"\n").unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_email(), "alice@example.com");
+
+    prefs.apply_toml_patch("email = \"BOB@example.com\"").unwrap();
+    assert_eq!(prefs.get_email(), "bob@example.com");
+}
+
+#[test]
+fn test_before_save_runs_and_can_abort() {
+    let mut prefs = ProfilePrefs::load_testing();
+    let scheduled = SYNCS_SCHEDULED.load(Ordering::SeqCst);
+    prefs.save_newsletter(true).unwrap();
+    assert!(SYNCS_SCHEDULED.load(Ordering::SeqCst) > scheduled);
+
+    let stored = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+    let result = prefs.save_email(String::new());
+    assert!(matches!(result, Err(SaveError::ValidationError(e)) if e.reason == "an email is required"));
+    assert_eq!(std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap(), stored);
+}
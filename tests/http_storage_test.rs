@@ -0,0 +1,205 @@
+#![cfg(all(feature = "http", not(target_arch = "wasm32")))]
+
+use easy_prefs::storage::http::{self, HttpStorage, Request, Response, Transport};
+use easy_prefs::{easy_prefs, LoadError, SaveError};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+
+easy_prefs! {
+    struct TeamPrefs {
+        pub channel: String = "stable".to_string() => "channel",
+        pub seats: i64 = 5 => "seats",
+    },
+    "team-prefs",
+    single_instance = false
+}
+
+/// Documents by path, with a counter for ETags.
+type Documents = Arc<Mutex<(HashMap<String, (String, u64)>, u64)>>;
+
+/// Serves GET and PUT with ETags and conditional writes, sending GET bodies chunked.
+fn serve() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let documents = Documents::default();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            handle(stream.unwrap(), &documents);
+        }
+    });
+    format!("http://{address}/teams/42")
+}
+
+fn handle(mut stream: TcpStream, documents: &Documents) {
+    let mut received = Vec::new();
+    let mut buffer = [0; 4096];
+    let (method, path, headers, offset) = loop {
+        let read = stream.read(&mut buffer).unwrap();
+        received.extend_from_slice(&buffer[..read]);
+        let mut headers = [httparse::EMPTY_HEADER; 32];
+        let mut request = httparse::Request::new(&mut headers);
+        if let httparse::Status::Complete(offset) = request.parse(&received).unwrap() {
+            let headers: HashMap<String, String> = request
+                .headers
+                .iter()
+                .map(|h| (h.name.to_ascii_lowercase(), String::from_utf8_lossy(h.value).to_string()))
+                .collect();
+            break (request.method.unwrap().to_string(), request.path.unwrap().to_string(), headers, offset);
+        }
+    };
+    let length: usize = headers.get("content-length").map_or(0, |length| length.parse().unwrap());
+    while received.len() < offset + length {
+        let read = stream.read(&mut buffer).unwrap();
+        received.extend_from_slice(&buffer[..read]);
+    }
+    let body = String::from_utf8(received[offset..offset + length].to_vec()).unwrap();
+
+    let mut documents = documents.lock().unwrap();
+    let (documents, counter) = &mut *documents;
+    let response = match method.as_str() {
+        "GET" => match documents.get(&path) {
+            Some((document, etag)) => format!(
+                "HTTP/1.1 200 OK\r\nETag: \"{etag}\"\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{document}\r\n0\r\n\r\n",
+                document.len()
+            ),
+            None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_string(),
+        },
+        _ => {
+            let current = documents.get(&path).map(|(_, etag)| format!("\"{etag}\""));
+            let rejected = match (headers.get("if-match"), headers.get("if-none-match")) {
+                (Some(expected), _) => current.as_ref() != Some(expected),
+                (_, Some(_)) => current.is_some(),
+                _ => false,
+            };
+            if rejected {
+                "HTTP/1.1 412 Precondition Failed\r\nContent-Length: 0\r\n\r\n".to_string()
+            } else {
+                *counter += 1;
+                documents.insert(path, (body, *counter));
+                format!("HTTP/1.1 204 No Content\r\nETag: \"{counter}\"\r\n\r\n")
+            }
+        }
+    };
+    stream.write_all(response.as_bytes()).unwrap();
+}
+
+#[test]
+fn test_concurrent_saves_conflict() {
+    let url = serve();
+    let mut first = TeamPrefs::load_with_storage(Box::new(HttpStorage::new(&url))).unwrap();
+    assert_eq!(first.get_preferences_file_path(), format!("{url}/team-prefs.toml"));
+    first.save_channel("beta".to_string()).unwrap();
+    first.save_seats(6).unwrap();
+
+    let mut second = TeamPrefs::load_with_storage(Box::new(HttpStorage::new(&url))).unwrap();
+    assert_eq!((second.get_channel().as_str(), *second.get_seats()), ("beta", 6));
+    second.save_seats(10).unwrap();
+
+    match first.save_channel("nightly".to_string()) {
+        Err(SaveError::Io(e)) => assert!(http::is_conflict(&e), "{e}"),
+        other => panic!("expected a conflict, got {other:?}"),
+    }
+    first.reload().unwrap();
+    assert_eq!(*first.get_seats(), 10);
+    first.save_channel("nightly".to_string()).unwrap();
+
+    second.reload().unwrap();
+    assert_eq!((second.get_channel().as_str(), *second.get_seats()), ("nightly", 10));
+}
+
+#[test]
+fn test_first_writes_conflict() {
+    let url = serve();
+    let mut first = TeamPrefs::load_with_storage(Box::new(HttpStorage::new(&url))).unwrap();
+    let mut second = TeamPrefs::load_with_storage(Box::new(HttpStorage::new(&url))).unwrap();
+    second.save_seats(7).unwrap();
+    let e = std::io::Error::from(first.save_seats(8).unwrap_err());
+    assert!(http::is_conflict(&e));
+    assert!(e.to_string().ends_with("/team-prefs.toml was changed by someone else since it was loaded"));
+}
+
+/// Answers every request with a fixed status, recording the requests.
+#[derive(Debug, Default)]
+struct Recorder {
+    status: u16,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl Transport for Recorder {
+    fn send(&self, request: &Request) -> Result<Response, std::io::Error> {
+        self.requests.lock().unwrap().push(request.clone());
+        Ok(Response { status: self.status, ..Default::default() })
+    }
+}
+
+#[test]
+fn test_transport_and_errors() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let transport = Recorder { status: 401, requests: requests.clone() };
+    let storage = HttpStorage::new("https://prefs.example.com/v1/")
+        .with_header("Authorization", "Bearer token")
+        .with_transport(transport);
+    match TeamPrefs::load_with_storage(Box::new(storage)) {
+        Err(LoadError::StorageError(e)) => {
+            assert_eq!(e.kind(), std::io::ErrorKind::PermissionDenied);
+            assert!(e.to_string().contains("HTTP 401"), "{e}");
+        }
+        other => panic!("expected a storage error, got {:?}", other.err()),
+    }
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests[0].method, "GET");
+    assert_eq!(requests[0].url, "https://prefs.example.com/v1/team-prefs.toml");
+    assert_eq!(requests[0].headers, [("Authorization".to_string(), "Bearer token".to_string())]);
+
+    // Without a transport, only plain HTTP works.
+    let result = TeamPrefs::load_with_storage(Box::new(HttpStorage::new("https://prefs.example.com")));
+    assert!(matches!(result, Err(LoadError::StorageError(e)) if e.kind() == std::io::ErrorKind::InvalidInput));
+}
+
+#[test]
+fn test_headers_stay_private() {
+    let storage = HttpStorage::new("http://127.0.0.1:1").with_header("Authorization", "Bearer s3cr3t");
+    let debug = format!("{storage:?}");
+    assert!(debug.contains("Authorization"), "{debug}");
+    assert!(!debug.contains("s3cr3t"), "{debug}");
+
+    // CR or LF would let a header value smuggle in more headers; nothing is sent.
+    let request = Request {
+        method: "GET",
+        url: "http://127.0.0.1:1/team-prefs.toml".to_string(),
+        headers: vec![("Authorization".to_string(), "Bearer x\r\nX-Injected: 1".to_string())],
+        body: None,
+    };
+    let e = http::PlainHttp::new(std::time::Duration::from_secs(1)).send(&request).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(!e.to_string().contains("X-Injected"), "{e}");
+    assert!(!format!("{request:?}").contains("Bearer x"));
+}
+
+#[test]
+fn test_oversized_responses_are_rejected() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let _ = stream.read(&mut [0; 4096]);
+        let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n");
+        let chunk = vec![b'#'; 1024 * 1024];
+        for _ in 0..65 {
+            if stream.write_all(&chunk).is_err() {
+                break;
+            }
+        }
+    });
+
+    let request = Request {
+        method: "GET",
+        url: format!("http://{address}/team-prefs.toml"),
+        headers: Vec::new(),
+        body: None,
+    };
+    let e = http::PlainHttp::new(std::time::Duration::from_secs(5)).send(&request).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+}
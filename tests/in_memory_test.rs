@@ -0,0 +1,59 @@
+use easy_prefs::easy_prefs;
+
+easy_prefs! {
+    struct SessionPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub volume: i32 = 5 => "volume",
+    },
+    "session-prefs"
+}
+
+#[test]
+fn test_in_memory_instances_are_isolated() {
+    let mut first = SessionPrefs::load_in_memory();
+    let second = SessionPrefs::load_in_memory();
+    assert!(!first.is_persistent());
+    assert_eq!(first.get_preferences_file_path(), "memory::session-prefs.toml");
+
+    first.save_theme("dark".to_string()).unwrap();
+    first.edit().set_volume(9);
+    assert_eq!(first.get_theme(), "dark");
+    assert_eq!(*first.get_volume(), 9);
+    assert_eq!(second.get_theme(), "light");
+
+    // Reloading reads back what was saved to memory.
+    first.reload().unwrap();
+    assert_eq!(*first.get_volume(), 9);
+}
+
+#[test]
+fn test_in_memory_ignores_single_instance() {
+    let tmp = tempfile::tempdir().unwrap();
+    let loaded = SessionPrefs::load_with_error(tmp.path().to_str().unwrap()).unwrap();
+    let mut ephemeral = SessionPrefs::load_in_memory();
+    ephemeral.save_volume(1).unwrap();
+    assert_eq!(*loaded.get_volume(), 5);
+}
+
+#[test]
+fn test_with_test_prefs() {
+    let volume = SessionPrefs::with_test_prefs(|prefs| {
+        prefs.save_volume(7).unwrap();
+        *prefs.get_volume()
+    });
+    assert_eq!(volume, 7);
+    SessionPrefs::with_test_prefs(|prefs| assert_eq!(*prefs.get_volume(), 5));
+}
+
+#[test]
+fn test_with_test_prefs_cleans_up_on_panic() {
+    let result = std::panic::catch_unwind(|| {
+        SessionPrefs::with_test_prefs(|prefs| {
+            prefs.save_theme("dark".to_string()).unwrap();
+            panic!("assertion failed mid-test");
+        })
+    });
+    assert!(result.is_err());
+    // The next test starts from the defaults again.
+    SessionPrefs::with_test_prefs(|prefs| assert_eq!(prefs.get_theme(), "light"));
+}
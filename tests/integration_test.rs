@@ -30,7 +30,7 @@ fn test_complete_workflow() {
 
     // Verify defaults
     assert_eq!(settings.get_username(), "guest");
-    assert_eq!(*settings.get_dark_mode(), false);
+    assert!(!*settings.get_dark_mode());
     assert_eq!(*settings.get_font_size(), 14);
 
     // Update individual fields
@@ -51,10 +51,10 @@ fn test_complete_workflow() {
     assert_eq!(settings.get_username(), "alice");
     assert_eq!(settings.get_email(), "alice@example.com");
     assert_eq!(*settings.get_age(), 25);
-    assert_eq!(*settings.get_dark_mode(), true);
+    assert!(*settings.get_dark_mode());
     assert_eq!(*settings.get_window_width(), 1920);
     assert_eq!(*settings.get_window_height(), 1080);
-    assert_eq!(*settings.get_telemetry(), true);
+    assert!(*settings.get_telemetry());
 
     // Test serialization
     let serialized = settings.to_string();
@@ -110,3 +110,19 @@ fn test_type_safety() {
 
     // The macro ensures type safety at compile time
 }
+
+#[test]
+fn test_copied_getters() {
+    let mut settings = AppSettings::load_testing();
+    settings.save_dark_mode(true).unwrap();
+
+    let dark_mode: bool = settings.get_dark_mode_copied();
+    assert!(dark_mode);
+    assert_eq!(settings.get_font_size_copied(), 14);
+    {
+        let mut guard = settings.edit();
+        guard.set_font_size(16);
+        assert_eq!(guard.get_font_size_copied(), 16);
+    }
+    assert_eq!(settings.get_font_size_copied(), 16);
+}
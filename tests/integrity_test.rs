@@ -0,0 +1,108 @@
+use easy_prefs::integrity::{self, IntegrityError};
+use easy_prefs::storage::{MemoryStorage, Storage};
+use easy_prefs::{easy_prefs, LoadError, LoadOutcome};
+
+easy_prefs! {
+    struct SealedPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub volume: i32 = 5 => "volume",
+    },
+    "sealed-prefs",
+    integrity = true
+}
+
+easy_prefs! {
+    struct SealedBackupPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub volume: i32 = 5 => "volume",
+    },
+    "sealed-backup-prefs",
+    integrity = true,
+    backups = 2
+}
+
+easy_prefs! {
+    struct ResettingPrefs {
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "resetting-prefs",
+    integrity = true,
+    reset_on_integrity_failure = true
+}
+
+#[test]
+fn test_seal_and_verify() {
+    let sealed = integrity::seal("theme = \"dark\"\n");
+    assert!(sealed.starts_with("theme = \"dark\"\n# easy_prefs integrity: fnv1a-64 "));
+    assert_eq!(integrity::verify(&sealed), Ok("theme = \"dark\"\n"));
+    assert_eq!(integrity::strip(&sealed), "theme = \"dark\"\n");
+    assert_eq!(integrity::verify(&integrity::seal("")), Ok(""));
+
+    assert_eq!(integrity::verify("theme = \"dark\"\n"), Err(IntegrityError::MissingFooter));
+    assert!(matches!(
+        integrity::verify(&sealed.replace("dark", "dork")),
+        Err(IntegrityError::Mismatch { .. })
+    ));
+    assert!(integrity::verify(&sealed[..sealed.len() - 4]).is_err());
+    assert_eq!(integrity::strip("theme = 1\n"), "theme = 1\n");
+}
+
+#[test]
+fn test_saved_file_is_sealed_and_verified() {
+    let mut prefs = SealedPrefs::load_testing();
+    prefs.save_theme("dark".to_string()).unwrap();
+    prefs.save_volume(8).unwrap();
+    let path = prefs.get_preferences_file_path();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(contents.matches("# easy_prefs integrity").count(), 1);
+
+    prefs.reload().unwrap();
+    assert_eq!(*prefs.get_volume(), 8);
+
+    // Truncated after the first value: still valid TOML, but fails the check.
+    let truncated = &contents[..contents.find('\n').unwrap() + 1];
+    std::fs::write(&path, truncated).unwrap();
+    match prefs.reload() {
+        Err(LoadError::IntegrityFailure(location, IntegrityError::MissingFooter)) => assert_eq!(location, path),
+        other => panic!("expected an integrity failure, got {other:?}"),
+    }
+    assert_eq!(*prefs.get_volume(), 8, "in-memory values are kept");
+
+    std::fs::write(&path, contents.replace("dark", "dork")).unwrap();
+    assert!(matches!(
+        prefs.reload(),
+        Err(LoadError::IntegrityFailure(_, IntegrityError::Mismatch { .. }))
+    ));
+}
+
+#[test]
+fn test_recover_from_sealed_backup() {
+    let mut prefs = SealedBackupPrefs::load_testing();
+    prefs.save_volume(7).unwrap();
+    prefs.save_volume(9).unwrap();
+    let path = prefs.get_preferences_file_path();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    std::fs::write(&path, contents.replace('9', "1")).unwrap();
+
+    prefs.reload().unwrap();
+    assert_eq!(*prefs.get_volume(), 7);
+    match prefs.load_outcome() {
+        LoadOutcome::RecoveredFromBackup { error, .. } => assert!(error.contains("integrity check failed")),
+        other => panic!("expected a recovery, got {other:?}"),
+    }
+
+    // The corrupt file is not backed up by the next save.
+    prefs.save_volume(3).unwrap();
+    let backup = std::fs::read_to_string(format!("{path}.bak")).unwrap();
+    assert!(integrity::verify(&backup).is_ok());
+    assert!(backup.contains("volume = 7"));
+}
+
+#[test]
+fn test_reset_on_integrity_failure() {
+    let storage = MemoryStorage::new();
+    storage.write(ResettingPrefs::PREFERENCES_FILENAME, "theme = \"dark\"\n").unwrap();
+    let prefs = ResettingPrefs::load_with_storage(Box::new(storage)).unwrap();
+    assert_eq!(prefs.get_theme(), "light");
+    assert!(matches!(prefs.load_outcome(), LoadOutcome::ResetToDefaults { .. }));
+}
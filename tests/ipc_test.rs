@@ -0,0 +1,153 @@
+#![cfg(all(feature = "ipc", unix))]
+
+use easy_prefs::easy_prefs;
+use easy_prefs::ipc::{Access, IpcServer};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::sync::{Arc, Mutex};
+
+mod common;
+
+easy_prefs! {
+    struct IpcPrefs {
+        /// Color scheme of the main window.
+        pub theme: String = "light".to_string() => "theme" (choices = ["light", "dark"]),
+        pub volume: i32 = 5 => "volume",
+        pub token: String = "abc".to_string() => "token" (secret),
+    },
+    "ipc-prefs"
+}
+
+struct Client {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl Client {
+    fn connect(stream: UnixStream) -> Self {
+        Self {
+            reader: BufReader::new(stream.try_clone().unwrap()),
+            writer: stream,
+        }
+    }
+
+    fn request(&mut self, line: &str) -> serde_json::Value {
+        writeln!(self.writer, "{line}").unwrap();
+        self.read()
+    }
+
+    fn read(&mut self) -> serde_json::Value {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+}
+
+fn serve(server: &IpcServer<IpcPrefs>) -> Client {
+    let (ours, theirs) = UnixStream::pair().unwrap();
+    let server = server.clone();
+    std::thread::spawn(move || {
+        let reader = BufReader::new(theirs.try_clone().unwrap());
+        // The client hanging up ends the connection with an error; that's expected here.
+        let _ = server.serve_stream(reader, theirs);
+    });
+    Client::connect(ours)
+}
+
+#[test]
+fn test_default_access_is_read_only() {
+    let prefs = Arc::new(Mutex::new(IpcPrefs::load_testing()));
+    let server = IpcServer::new(prefs.clone());
+    let mut client = serve(&server);
+
+    let response = client.request(r#"{"id": 1, "op": "get", "key": "volume"}"#);
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["ok"], true);
+    assert_eq!(response["value"], "5");
+
+    let response = client.request(r#"{"op": "set", "key": "volume", "value": "7"}"#);
+    assert_eq!(response["ok"], false);
+    assert_eq!(*prefs.lock().unwrap().get_volume(), 5);
+
+    // Secret fields are hidden unless the policy exposes them.
+    let response = client.request(r#"{"op": "list"}"#);
+    let keys: Vec<_> = response["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["key"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(keys, ["theme", "volume"]);
+    assert_eq!(client.request(r#"{"op": "get", "key": "token"}"#)["ok"], false);
+}
+
+#[test]
+fn test_descriptions() {
+    let prefs = Arc::new(Mutex::new(IpcPrefs::load_testing()));
+    let mut client = serve(&IpcServer::new(prefs));
+
+    let response = client.request(r#"{"op": "list"}"#);
+    assert_eq!(response["fields"][0]["description"], "Color scheme of the main window.");
+    assert_eq!(response["fields"][1]["description"], "");
+
+    let response = client.request(r#"{"id": 2, "op": "describe", "key": "theme"}"#);
+    assert_eq!(response["ok"], true);
+    assert_eq!(response["type"], "String");
+    assert_eq!(response["default"], "\"light\".to_string()");
+    assert_eq!(response["description"], "Color scheme of the main window.");
+    assert_eq!(response["constraints"], "one of: light, dark");
+    assert!(client.request(r#"{"op": "describe", "key": "volume"}"#)["constraints"].is_null());
+    assert_eq!(client.request(r#"{"op": "describe", "key": "token"}"#)["ok"], false);
+}
+
+#[test]
+fn test_set_and_subscribe() {
+    common::use_memory_secret_store();
+    let prefs = Arc::new(Mutex::new(IpcPrefs::load_testing()));
+    let server = IpcServer::new(prefs.clone()).with_access(|_| Access::ReadWrite);
+    let mut watcher = serve(&server);
+    let mut client = serve(&server);
+
+    assert_eq!(watcher.request(r#"{"op": "subscribe"}"#)["ok"], true);
+
+    let response = client.request(r#"{"op": "set", "key": "theme", "value": "dark"}"#);
+    assert_eq!(response["ok"], true);
+    assert_eq!(prefs.lock().unwrap().get_theme(), "dark");
+
+    let event = watcher.read();
+    assert_eq!(event["event"], "changed");
+    assert_eq!(event["key"], "theme");
+    assert_eq!(event["value"], "dark");
+
+    // Invalid values are rejected by the field's constraints.
+    let response = client.request(r#"{"op": "set", "key": "theme", "value": "blue"}"#);
+    assert_eq!(response["ok"], false);
+
+    // In-process changes reach subscribers once the host calls notify_changes().
+    prefs.lock().unwrap().save_volume(9).unwrap();
+    server.notify_changes();
+    let event = watcher.read();
+    assert_eq!(event["key"], "volume");
+    assert_eq!(event["value"], "9");
+
+    // Readable secrets are redacted in events and responses.
+    prefs.lock().unwrap().save_token("xyz".to_string()).unwrap();
+    server.notify_changes();
+    assert_eq!(watcher.read()["value"], "<redacted>");
+    assert_eq!(client.request(r#"{"op": "get", "key": "token"}"#)["value"], "<redacted>");
+}
+
+#[test]
+fn test_listen_on_unix_socket() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("prefs.sock");
+    let prefs = Arc::new(Mutex::new(IpcPrefs::load_testing()));
+    IpcServer::new(prefs).listen(&path).unwrap();
+
+    let mut client = Client::connect(UnixStream::connect(&path).unwrap());
+    let response = client.request(r#"{"op": "get", "key": "theme"}"#);
+    assert_eq!(response["value"], "light");
+
+    let response = client.request("not json");
+    assert_eq!(response["ok"], false);
+}
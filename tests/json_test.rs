@@ -0,0 +1,67 @@
+#![cfg(feature = "json")]
+
+use easy_prefs::{easy_prefs, LoadError};
+
+mod common;
+
+easy_prefs! {
+    pub struct JsonPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub font_size: i32 = 14 => "font_size" (range = 8..=72, on_invalid = clamp),
+        pub zoom: f64 = 1.0 => "zoom",
+        pub recent: Vec<String> = Vec::new() => "recent",
+        pub proxy: Option<String> = None => "proxy",
+        pub token: String = String::new() => "token" (secret),
+        pub window: JsonWindow {
+            pub width: i32 = 800 => "width",
+        } => "window",
+    },
+    "json-prefs"
+}
+
+#[test]
+fn test_json_round_trip() {
+    common::use_memory_secret_store();
+    let mut prefs = JsonPrefs::load_testing();
+    prefs.save_theme("dark".to_string()).unwrap();
+    prefs.save_recent(vec!["a.txt".to_string(), "b.txt".to_string()]).unwrap();
+    prefs.save_token("hunter22".to_string()).unwrap();
+
+    let json = prefs.to_json().unwrap();
+    assert_eq!(
+        json,
+        r#"{"font_size":14,"recent":["a.txt","b.txt"],"theme":"dark","window":{"width":800},"zoom":1.0}"#
+    );
+
+    let mut other = JsonPrefs::load_testing();
+    other.from_json(&json).unwrap();
+    assert_eq!(other.get_theme(), "dark");
+    assert_eq!(other.get_recent(), &["a.txt", "b.txt"]);
+    assert_eq!(other.get_window().width, 800);
+    assert_eq!(other.get_token(), "");
+}
+
+#[test]
+fn test_from_json_values() {
+    common::use_memory_secret_store();
+    let mut prefs = JsonPrefs::load_testing();
+    prefs.save_proxy(Some("socks5://localhost".to_string())).unwrap();
+
+    // `null` leaves a field out, repairs apply, and secrets are ignored.
+    prefs
+        .from_json(r#"{"font_size": 100, "zoom": 1.5, "proxy": null, "token": "x", "window": {"width": 1024}}"#)
+        .unwrap();
+    assert_eq!(*prefs.get_font_size(), 72);
+    assert_eq!(*prefs.get_zoom(), 1.5);
+    assert_eq!(prefs.get_proxy().as_deref(), Some("socks5://localhost"));
+    assert_eq!(prefs.get_token(), "");
+    assert_eq!(prefs.get_window().width, 1024);
+
+    for json in ["[1, 2]", "{\"theme\": ", r#"{"recent": ["a", null]}"#, r#"{"font_size": 18446744073709551615}"#] {
+        match prefs.from_json(json) {
+            Err(LoadError::DeserializationError(location, _)) => assert_eq!(location, "json"),
+            other => panic!("Expected DeserializationError for {json}, got {other:?}"),
+        }
+    }
+    assert_eq!(prefs.get_theme(), "light");
+}
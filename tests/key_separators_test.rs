@@ -0,0 +1,91 @@
+use easy_prefs::easy_prefs;
+use easy_prefs::storage::{resolve_key, KeySeparators, MemoryStorage, Storage};
+use easy_prefs::LoadError;
+
+easy_prefs! {
+    struct NestedPrefs {
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "profiles/nested-prefs"
+}
+
+easy_prefs! {
+    struct FlatPrefs {
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "profiles/flat-prefs",
+    key_separators = easy_prefs::storage::KeySeparators::Flatten
+}
+
+easy_prefs! {
+    struct StrictPrefs {
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "profiles/strict-prefs",
+    key_separators = easy_prefs::storage::KeySeparators::Reject
+}
+
+#[test]
+fn test_resolve_key() {
+    assert_eq!(resolve_key("a/b.toml", KeySeparators::Nested).unwrap(), "a/b.toml");
+    assert_eq!(resolve_key("a/b.toml", KeySeparators::Flatten).unwrap(), "a_b.toml");
+    assert_eq!(resolve_key("b.toml", KeySeparators::Reject).unwrap(), "b.toml");
+    assert!(resolve_key("a/b.toml", KeySeparators::Reject).is_err());
+
+    for key in ["", "/a.toml", "a//b.toml", "a/", "../a.toml", "a/./b.toml", "a\\b.toml", "c:a.toml", "a\0"] {
+        let err = resolve_key(key, KeySeparators::Nested).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput, "{key:?}");
+    }
+}
+
+#[test]
+fn test_nested_keys_use_subdirectories() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+
+    let mut prefs = NestedPrefs::load_with_error(dir).unwrap();
+    prefs.save_theme("dark".to_string()).unwrap();
+    assert!(tmp.path().join("profiles/nested-prefs.toml").is_file());
+
+    let mut work = NestedPrefs::load_profile(dir, "work").unwrap();
+    work.save_theme("dark".to_string()).unwrap();
+    drop(work);
+    assert!(tmp.path().join("profiles/nested-prefs.work.toml").is_file());
+
+    let report = NestedPrefs::gc(dir, &easy_prefs::gc::GcPolicy::new().keep_only(&[], &[])).unwrap();
+    assert_eq!(report.removed, ["profiles/nested-prefs.work.toml"]);
+    assert!(!tmp.path().join("profiles/nested-prefs.work.toml").exists());
+}
+
+#[test]
+fn test_flatten_and_reject() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+
+    let mut prefs = FlatPrefs::load_with_error(dir).unwrap();
+    prefs.save_theme("dark".to_string()).unwrap();
+    assert!(tmp.path().join("profiles_flat-prefs.toml").is_file());
+    assert!(!tmp.path().join("profiles").exists());
+
+    match StrictPrefs::load_with_error(dir) {
+        Err(LoadError::StorageError(e)) => assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput),
+        other => panic!("Expected StorageError, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_backends_refuse_invalid_keys() {
+    let tmp = tempfile::tempdir().unwrap();
+    let storage = easy_prefs::storage::native::FileStorage::new(tmp.path().join("inner").to_str().unwrap());
+    let err = storage.write("../escaped.toml", "x = 1").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(!tmp.path().join("escaped.toml").exists());
+
+    // Structs check keys the same way whatever the backend.
+    let memory = Box::new(MemoryStorage::new());
+    let storage = easy_prefs::storage::ResolvedKeys::new(memory, KeySeparators::Flatten);
+    storage.write("a/b.toml", "x = 1").unwrap();
+    assert_eq!(storage.read("a/b.toml").unwrap().as_deref(), Some("x = 1"));
+    assert_eq!(storage.get_path("a/b.toml"), MemoryStorage::new().get_path("a_b.toml"));
+    assert!(storage.read("a/../b.toml").is_err());
+}
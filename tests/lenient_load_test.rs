@@ -0,0 +1,91 @@
+use easy_prefs::easy_prefs;
+use easy_prefs::lenient::LoadWarning;
+use easy_prefs::secrets::REDACTED;
+
+mod common;
+
+easy_prefs! {
+    struct SalvagedPrefs {
+        pub volume: i32 = 5 => "volume",
+        pub theme: String = "light".to_string() => "theme",
+        pub font_size: i32 = 14 => "font_size" (range = 8..=72, on_invalid = clamp),
+        pub zoom: i32 = 100 => "zoom" (range = 50..=200, on_invalid = error),
+        pub token: String = String::new() => "token" (secret),
+        pub window: SalvagedWindow {
+            pub width: i32 = 800 => "width",
+            pub height: i32 = 600 => "height",
+        } => "window",
+    },
+    "salvaged-prefs"
+}
+
+easy_prefs! {
+    struct StrictSalvagedPrefs {
+        pub dark_mode: bool = false => "dark_mode" (lenient_types),
+    },
+    "strict-salvaged-prefs",
+    strict = true,
+    integrity = true
+}
+
+fn warning(field: &str, reason: &str, raw_value: Option<&str>) -> LoadWarning {
+    LoadWarning { field: field.to_string(), reason: reason.to_string(), raw_value: raw_value.map(str::to_string) }
+}
+
+#[test]
+fn test_keeps_valid_fields() {
+    common::use_memory_secret_store();
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+    let document = "volume = 1.5\ntheme = \"dark\"\nfont_size = 99\nzoom = 10\ntoken = \"hunter22\n\n[window]\nwidth = 1024\nheight = 7 7\n";
+    std::fs::write(tmp.path().join("salvaged-prefs.toml"), document).unwrap();
+
+    let (prefs, warnings) = SalvagedPrefs::load_lenient(dir);
+    assert_eq!(*prefs.get_volume(), 5);
+    assert_eq!(prefs.get_theme(), "dark");
+    assert_eq!(*prefs.get_font_size(), 72);
+    assert_eq!(*prefs.get_zoom(), 100);
+    assert_eq!(prefs.get_token(), "");
+    assert_eq!(prefs.get_window().width, 1024);
+    assert_eq!(prefs.get_window().height, 600);
+
+    assert_eq!(warnings.len(), 5, "{warnings:#?}");
+    assert_eq!(warnings[0].field, "token");
+    assert_eq!(warnings[0].raw_value.as_deref(), Some(format!("token = {REDACTED}").as_str()));
+    assert_eq!(warnings[1].field, "window.height");
+    assert_eq!(warnings[1].raw_value.as_deref(), Some("height = 7 7"));
+    assert_eq!(warnings[2], warning("volume", "invalid type: floating point `1.5`, expected i32", Some("1.5")));
+    assert_eq!(warnings[3], warning("font_size", "99 is above the maximum of 72, clamped to 72", Some("99")));
+    assert_eq!(warnings[4], warning("zoom", "10 is below the minimum of 50", Some("10")));
+    assert_eq!(warnings[2].to_string(), "'volume' dropped (1.5): invalid type: floating point `1.5`, expected i32");
+
+    // The instance is claimed and saves go to the same file.
+    let mut prefs = prefs;
+    prefs.save_volume(9).unwrap();
+    drop(prefs);
+    let (prefs, warnings) = SalvagedPrefs::load_lenient(dir);
+    assert_eq!(*prefs.get_volume(), 9);
+    assert!(warnings.is_empty(), "{warnings:#?}");
+}
+
+#[test]
+fn test_strict_and_integrity() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+    let document = easy_prefs::integrity::seal("dark_mode = \"yes\"\ncolour = \"red\"\n");
+    std::fs::write(tmp.path().join("strict-salvaged-prefs.toml"), document.replace("red", "blue")).unwrap();
+
+    let (prefs, warnings) = StrictSalvagedPrefs::load_lenient(dir);
+    assert!(*prefs.get_dark_mode());
+    assert_eq!(warnings.len(), 2, "{warnings:#?}");
+    assert!(warnings[0].field.is_empty() && warnings[0].reason.starts_with("integrity check failed"), "{warnings:#?}");
+    assert_eq!(warnings[1], warning("colour", "unknown key", Some("\"blue\"")));
+    drop(prefs);
+
+    // A document that can't be salvaged at all leaves the defaults.
+    std::fs::write(tmp.path().join("strict-salvaged-prefs.toml"), "[[[").unwrap();
+    let (prefs, warnings) = StrictSalvagedPrefs::load_lenient(dir);
+    assert!(!*prefs.get_dark_mode());
+    assert_eq!(warnings.len(), 2, "{warnings:#?}");
+    assert_eq!(warnings[1].raw_value.as_deref(), Some("[[["));
+}
@@ -0,0 +1,98 @@
+use easy_prefs::easy_prefs;
+use easy_prefs::lint::{self, LintIssue};
+use std::time::Duration;
+use web_time::UNIX_EPOCH;
+
+easy_prefs! {
+    struct LintedPrefs {
+        pub volume: i32 = 5 => "volume" (min = 0, max = 10),
+        pub theme: String = "light".to_string() => "theme" (choices = ["light", "dark"]),
+        pub account: String = "guest".to_string() => "account" (required),
+        pub nickname: String = String::new() => "nickname",
+        pub last_sync: i64 = 0 => "last_sync" (timestamp),
+        pub token: String = "none".to_string() => "token" (secret, required, min = 1),
+    },
+    "linted-prefs"
+}
+
+fn issues(document: &str) -> Vec<LintIssue> {
+    lint::lint_document(document, LintedPrefs::FIELDS).unwrap().issues
+}
+
+#[test]
+fn test_clean_document() {
+    let prefs = LintedPrefs::load_testing();
+    let report = prefs.lint().unwrap();
+    assert!(report.is_clean(), "{report}");
+    assert_eq!(report.to_string(), "no issues");
+    assert!(issues("nickname = \"\"\nlast_sync = 1700000000\n_generation = 3\n").is_empty());
+}
+
+#[test]
+fn test_constraint_violations_and_empty_required() {
+    let found = issues("volume = 4000\ntheme = \"purple\"\naccount = \"\"\ntoken = \"\"\n");
+    assert_eq!(found.len(), 4, "{found:?}");
+    assert!(matches!(&found[0], LintIssue::Invalid(e) if e.key == "volume"));
+    assert!(matches!(&found[1], LintIssue::Invalid(e) if e.key == "theme"));
+    assert_eq!(found[2], LintIssue::EmptyRequired { key: "account" });
+    assert_eq!(found[3], LintIssue::EmptyRequired { key: "token" });
+    assert_eq!(found[2].to_string(), "required value 'account' is empty");
+}
+
+#[test]
+fn test_unknown_keys() {
+    let found = issues("volume = 3\nlegacy_flag = true\n");
+    assert_eq!(found, [LintIssue::UnknownKey("legacy_flag".to_string())]);
+    assert_eq!(found[0].to_string(), "unknown key 'legacy_flag'");
+}
+
+#[test]
+fn test_future_timestamps() {
+    let document = r#"
+last_sync = 32503680000
+legacy_flag = 1979-05-27T07:32:00Z
+nickname = "2999-01-01T00:00:00+01:00"
+
+[_temporary.volume]
+expires = 2999-01-01T00:00:00Z
+"#;
+    let found = issues(document);
+    assert_eq!(found.len(), 3, "{found:?}");
+    assert!(matches!(&found[0], LintIssue::FutureTimestamp { key, .. } if key == "last_sync"));
+    assert_eq!(found[1], LintIssue::UnknownKey("legacy_flag".to_string()));
+    assert!(matches!(&found[2], LintIssue::FutureTimestamp { key, .. } if key == "nickname"));
+    // Plain dates and date-like strings without a time are not timestamps.
+    assert!(issues("nickname = \"2999-01-01\"\n").is_empty());
+}
+
+#[test]
+fn test_timestamp_tolerance_and_offsets() {
+    let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000); // 2023-11-14T22:13:20Z
+    let lint_at = |document: &str| {
+        let table = toml::from_str(document).unwrap();
+        lint::lint_table(&table, LintedPrefs::FIELDS, now).issues.len()
+    };
+    assert_eq!(lint_at("last_sync = 1700080000\n"), 0);
+    assert_eq!(lint_at("last_sync = 1700090000\n"), 1);
+    assert_eq!(lint_at("nickname = 2023-11-15T00:00:00Z\n"), 0);
+    assert_eq!(lint_at("nickname = 2023-11-16T00:00:00Z\n"), 1);
+    assert_eq!(lint_at("nickname = 2023-11-16T00:00:00+02:00\n"), 0);
+    assert_eq!(lint_at("nickname = 2023-11-16T00:00:00-02:00\n"), 1);
+    assert_eq!(lint_at("nickname = [2023-11-10T00:00:00Z, 2024-01-01T00:00:00Z]\n"), 1);
+    // Unknown keys are reported once, without looking inside.
+    assert_eq!(lint_at("[section]\nat = 2999-01-01T00:00:00Z\n"), 1);
+}
+
+#[test]
+fn test_lint_reads_stored_document() {
+    let prefs = LintedPrefs::load_testing();
+    let path = prefs.get_preferences_file_path();
+    std::fs::write(&path, "volume = -50\ntoken = \"hunter2hunter2\"\n").unwrap();
+    let report = prefs.lint().unwrap();
+    assert_eq!(report.issues.len(), 1, "{report}");
+    assert!(report.to_string().starts_with("invalid value for 'volume'"));
+    assert!(!report.to_string().contains("hunter2"));
+
+    std::fs::write(&path, "volume = ").unwrap();
+    assert_eq!(prefs.lint().unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,112 @@
+#![cfg(all(feature = "websocket", not(target_arch = "wasm32")))]
+
+use easy_prefs::easy_prefs;
+use easy_prefs::live_sync::LiveSync;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+mod common;
+
+easy_prefs! {
+    struct DesktopPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub volume: i32 = 5 => "volume",
+        pub token: String = String::new() => "token" (secret),
+        pub window_width: i32 = 800 => "window_width" (device),
+    },
+    "live-sync-desktop"
+}
+
+easy_prefs! {
+    struct ExtensionPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub volume: i32 = 5 => "volume",
+        pub token: String = String::new() => "token" (secret),
+        pub window_width: i32 = 800 => "window_width" (device),
+    },
+    "live-sync-extension"
+}
+
+easy_prefs! {
+    struct OriginPrefs {
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "live-sync-origin"
+}
+
+fn wait_for(mut condition: impl FnMut() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !condition() {
+        assert!(Instant::now() < deadline, "timed out waiting for sync");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn test_instances_stay_in_sync() {
+    common::use_memory_secret_store();
+    let desktop = Arc::new(Mutex::new(DesktopPrefs::load_testing()));
+    let extension = Arc::new(Mutex::new(ExtensionPrefs::load_testing()));
+    desktop.lock().unwrap().save_theme("dark".to_string()).unwrap();
+    desktop.lock().unwrap().save_token("desktop-secret".to_string()).unwrap();
+    desktop.lock().unwrap().save_window_width(1920).unwrap();
+    extension.lock().unwrap().save_theme("blue".to_string()).unwrap();
+
+    // The extension changed the volume twice while disconnected, so its value is newer.
+    let extension_sync = LiveSync::new(extension.clone());
+    extension.lock().unwrap().save_volume(6).unwrap();
+    extension_sync.publish_changes();
+    extension.lock().unwrap().save_volume(7).unwrap();
+    extension_sync.publish_changes();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    LiveSync::new(desktop.clone()).listen(listener);
+    extension_sync.connect(&url).unwrap();
+
+    // Neither side edited the theme since startup, so the listening side wins.
+    wait_for(|| extension.lock().unwrap().get_theme() == "dark");
+    wait_for(|| *desktop.lock().unwrap().get_volume() == 7);
+    assert_eq!(desktop.lock().unwrap().get_theme(), "dark");
+    assert_eq!(extension.lock().unwrap().get_token(), "");
+    assert_eq!(*extension.lock().unwrap().get_window_width(), 800);
+
+    // Local edits are picked up without calling publish_changes().
+    desktop.lock().unwrap().save_volume(9).unwrap();
+    wait_for(|| *extension.lock().unwrap().get_volume() == 9);
+
+    extension.lock().unwrap().save_theme("green".to_string()).unwrap();
+    wait_for(|| desktop.lock().unwrap().get_theme() == "green");
+
+    // Received values are saved.
+    let contents = std::fs::read_to_string(desktop.lock().unwrap().get_preferences_file_path()).unwrap();
+    assert!(contents.contains("theme = \"green\""));
+}
+
+#[test]
+fn test_unknown_browser_origins_are_refused() {
+    let prefs = Arc::new(Mutex::new(OriginPrefs::load_testing()));
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    LiveSync::new(prefs)
+        .with_allowed_origins(&["chrome-extension://trusted"])
+        .listen(listener);
+
+    let handshake = |origin: &str| {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "GET / HTTP/1.1\r\nHost: {addr}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\
+             Origin: {origin}\r\n\r\n"
+        )
+        .unwrap();
+        let mut response = [0; 12];
+        stream.read_exact(&mut response).unwrap();
+        String::from_utf8_lossy(&response).to_string()
+    };
+    assert_eq!(handshake("https://evil.example"), "HTTP/1.1 403");
+    assert_eq!(handshake("chrome-extension://trusted"), "HTTP/1.1 101");
+}
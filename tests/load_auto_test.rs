@@ -0,0 +1,30 @@
+#![cfg(target_os = "linux")]
+
+use easy_prefs::easy_prefs;
+use easy_prefs::storage::config_directory;
+
+easy_prefs! {
+    struct AutoPrefs {
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "auto-prefs"
+}
+
+// One test per binary: it points XDG_CONFIG_HOME at a temporary directory.
+#[test]
+fn test_load_auto_uses_xdg_config_home() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::env::set_var("XDG_CONFIG_HOME", tmp.path());
+    let expected = tmp.path().join("app");
+    assert_eq!(config_directory("com.example.app").unwrap(), expected.to_str().unwrap());
+    assert_eq!(config_directory("example.app").unwrap(), expected.to_str().unwrap());
+    assert_eq!(config_directory("app").unwrap(), expected.to_str().unwrap());
+
+    let mut prefs = AutoPrefs::load_auto("com.example.app").unwrap();
+    prefs.save_theme("dark".to_string()).unwrap();
+    assert_eq!(prefs.get_preferences_file_path(), expected.join("auto-prefs.toml").to_str().unwrap());
+    drop(prefs);
+
+    let prefs = AutoPrefs::load_with_error(expected.to_str().unwrap()).unwrap();
+    assert_eq!(prefs.get_theme(), "dark");
+}
@@ -39,7 +39,7 @@ fn test_load_creates_defaults_when_file_missing() {
     let prefs = TestDefaultPrefs::load(&test_dir);
 
     // Verify default values
-    assert_eq!(*prefs.get_enabled(), true);
+    assert!(*prefs.get_enabled());
     assert_eq!(*prefs.get_count(), 42);
     assert_eq!(prefs.get_name(), "default");
 
@@ -128,7 +128,7 @@ fn test_load_returns_defaults_on_error_in_release() {
     let prefs = TestDefaultPrefs::load(&test_dir);
 
     // Verify we got defaults
-    assert_eq!(*prefs.get_enabled(), true);
+    assert!(*prefs.get_enabled());
     assert_eq!(*prefs.get_count(), 42);
     assert_eq!(prefs.get_name(), "default");
 
@@ -0,0 +1,56 @@
+use easy_prefs::easy_prefs;
+use easy_prefs::storage::{MemoryStorage, Storage};
+use std::time::Duration;
+
+easy_prefs! {
+    struct MeasuredPrefs {
+        pub recent_files: Vec<String> = Vec::new() => "recent_files",
+    },
+    "measured-prefs",
+    single_instance = false,
+    load_budget = Duration::from_millis(1)
+}
+
+/// Storage taking a while to read.
+#[derive(Debug, Default)]
+struct SlowStorage(MemoryStorage);
+
+impl Storage for SlowStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+        std::thread::sleep(Duration::from_millis(5));
+        self.0.read(key)
+    }
+
+    fn write(&self, key: &str, data: &str) -> Result<(), std::io::Error> {
+        self.0.write(key, data)
+    }
+
+    fn get_path(&self, key: &str) -> String {
+        format!("slow::{key}")
+    }
+}
+
+#[test]
+fn test_load_stats() {
+    let prefs = MeasuredPrefs::load_with_storage(Box::new(SlowStorage::default())).unwrap();
+    let stats = prefs.load_stats();
+    assert_eq!(stats.size, 0);
+    assert!(stats.duration >= Duration::from_millis(5), "{stats:?}");
+    assert_eq!(MeasuredPrefs::OPTIONS.load_budget, Some(Duration::from_millis(1)));
+
+    let mut prefs = MeasuredPrefs::load_testing();
+    assert_eq!(prefs.load_stats(), easy_prefs::LoadStats::default());
+    prefs.save_recent_files(vec!["a.txt".to_string(), "b.txt".to_string()]).unwrap();
+    prefs.reload().unwrap();
+    let stored = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+    assert_eq!(prefs.load_stats().size, stored.len());
+}
+
+#[test]
+fn test_lenient_load_is_measured() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(tmp.path().join("measured-prefs.toml"), "recent_files = 3\n").unwrap();
+    let (prefs, warnings) = MeasuredPrefs::load_lenient(tmp.path().to_str().unwrap());
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(prefs.load_stats().size, "recent_files = 3\n".len());
+}
@@ -25,7 +25,7 @@ fn test_macro_hygiene() {
         // Guard drops here, testing web_time::Instant usage
     }
 
-    assert_eq!(*prefs.get_test_field(), true);
+    assert!(*prefs.get_test_field());
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -39,5 +39,5 @@ fn test_macro_hygiene_wasm() {
         edit.set_test_field(true);
     }
 
-    assert_eq!(*prefs.get_test_field(), true);
+    assert!(*prefs.get_test_field());
 }
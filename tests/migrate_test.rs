@@ -0,0 +1,78 @@
+use easy_prefs::easy_prefs;
+use easy_prefs::migrate::migrate_storage;
+use easy_prefs::storage::{MemoryStorage, Storage};
+
+mod common;
+
+easy_prefs! {
+    struct MigratePrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub custom_css: String = String::new() => "custom_css" (external),
+        pub token: String = String::new() => "token" (secret),
+    },
+    "migrate-prefs",
+    backups = 2
+}
+
+#[test]
+fn test_migrate_directory() {
+    common::use_memory_secret_store();
+    let tmp = tempfile::tempdir().unwrap();
+    let old_dir = tmp.path().join("old");
+    let new_dir = tmp.path().join("nested/new");
+    let (old_dir, new_dir) = (old_dir.to_str().unwrap(), new_dir.to_str().unwrap());
+
+    {
+        let mut prefs = MigratePrefs::load_with_error(old_dir).unwrap();
+        let mut edit = prefs.edit();
+        edit.set_theme("dark".to_string());
+        edit.set_custom_css("body { color: red; }".to_string());
+        edit.set_token("hunter2".to_string());
+        drop(edit);
+        prefs.save_theme("blue".to_string()).unwrap();
+    }
+
+    assert!(MigratePrefs::migrate(old_dir, new_dir).unwrap());
+    let prefs = MigratePrefs::load_with_error(new_dir).unwrap();
+    assert_eq!(prefs.get_theme(), "blue");
+    assert_eq!(prefs.get_custom_css(), "body { color: red; }");
+    assert_eq!(prefs.get_token(), "hunter2");
+    let backup = std::fs::read_to_string(tmp.path().join("nested/new/migrate-prefs.toml.bak")).unwrap();
+    assert!(backup.contains("dark"));
+    drop(prefs);
+
+    // The source stays in place.
+    assert_eq!(MigratePrefs::load_with_error(old_dir).unwrap().get_theme(), "blue");
+}
+
+#[test]
+fn test_migrate_does_not_overwrite() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (old_dir, new_dir) = (tmp.path().join("old"), tmp.path().join("new"));
+    std::fs::create_dir_all(&old_dir).unwrap();
+    std::fs::create_dir_all(&new_dir).unwrap();
+    let (old_dir, new_dir) = (old_dir.to_str().unwrap(), new_dir.to_str().unwrap());
+
+    assert!(!MigratePrefs::migrate(old_dir, new_dir).unwrap(), "nothing to migrate");
+
+    std::fs::write(tmp.path().join("old/migrate-prefs.toml"), "theme = \"dark\"\n").unwrap();
+    std::fs::write(tmp.path().join("new/migrate-prefs.toml"), "theme = \"green\"\n").unwrap();
+    assert!(!MigratePrefs::migrate(old_dir, new_dir).unwrap());
+    let kept = std::fs::read_to_string(tmp.path().join("new/migrate-prefs.toml")).unwrap();
+    assert_eq!(kept, "theme = \"green\"\n");
+}
+
+#[test]
+fn test_migrate_storage_between_backends() {
+    let from = MemoryStorage::new();
+    let to = MemoryStorage::new();
+    assert!(!migrate_storage(&from, &to, "app.toml").unwrap());
+
+    from.write("app.toml", "theme = \"dark\"\n").unwrap();
+    assert!(migrate_storage(&from, &to, "app.toml").unwrap());
+    assert_eq!(to.read("app.toml").unwrap().as_deref(), Some("theme = \"dark\"\n"));
+
+    from.write("app.toml", "theme = \"blue\"\n").unwrap();
+    assert!(!migrate_storage(&from, &to, "app.toml").unwrap());
+    assert_eq!(to.read("app.toml").unwrap().as_deref(), Some("theme = \"dark\"\n"));
+}
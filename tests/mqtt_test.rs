@@ -0,0 +1,128 @@
+#![cfg(all(feature = "mqtt", not(target_arch = "wasm32")))]
+
+use easy_prefs::easy_prefs;
+use easy_prefs::mqtt::{MqttBridge, MqttTransport};
+use std::sync::{Arc, Mutex};
+
+mod common;
+
+easy_prefs! {
+    struct DevicePrefs {
+        pub brightness: i32 = 80 => "brightness" (min = 0, max = 100),
+        pub mode: String = "auto".to_string() => "mode",
+        pub wifi_password: String = String::new() => "wifi_password" (secret),
+        pub local_only: bool = false => "local_only",
+    },
+    "mqtt-device-prefs"
+}
+
+/// Records what the bridge sends.
+#[derive(Clone, Default)]
+struct Recorder {
+    published: Arc<Mutex<Vec<(String, String, bool)>>>,
+    subscribed: Arc<Mutex<Vec<String>>>,
+}
+
+impl Recorder {
+    fn take(&self) -> Vec<(String, String, bool)> {
+        std::mem::take(&mut *self.published.lock().unwrap())
+    }
+}
+
+impl MqttTransport for Recorder {
+    fn publish(&self, topic: &str, payload: &str, retain: bool) -> Result<(), std::io::Error> {
+        self.published
+            .lock()
+            .unwrap()
+            .push((topic.to_string(), payload.to_string(), retain));
+        Ok(())
+    }
+
+    fn subscribe(&self, filter: &str) -> Result<(), std::io::Error> {
+        self.subscribed.lock().unwrap().push(filter.to_string());
+        Ok(())
+    }
+}
+
+fn bridge() -> (Arc<Mutex<DevicePrefs>>, MqttBridge<DevicePrefs>, Recorder) {
+    common::use_memory_secret_store();
+    let prefs = Arc::new(Mutex::new(DevicePrefs::load_testing()));
+    let recorder = Recorder::default();
+    let bridge = MqttBridge::new(prefs.clone(), recorder.clone(), "home/device/")
+        .with_fields(&["brightness", "mode", "wifi_password"]);
+    (prefs, bridge, recorder)
+}
+
+fn state(topic: &str, value: &str) -> (String, String, bool) {
+    (topic.to_string(), value.to_string(), true)
+}
+
+#[test]
+fn test_retained_state_applies_on_startup() {
+    let (prefs, bridge, recorder) = bridge();
+    bridge.start().unwrap();
+    assert_eq!(*recorder.subscribed.lock().unwrap(), ["home/device/#"]);
+
+    // Retained state from the broker wins over local values; unselected fields are ignored.
+    assert!(bridge.handle_message("home/device/brightness", b"35", true).unwrap());
+    assert!(bridge.handle_message("home/device/local_only", b"true", true).is_ok_and(|changed| !changed));
+    assert_eq!(*prefs.lock().unwrap().get_brightness(), 35);
+    assert!(!*prefs.lock().unwrap().get_local_only());
+
+    // Only values the broker doesn't have yet are published.
+    bridge.publish_changes();
+    assert_eq!(recorder.take(), [state("home/device/mode", "auto")]);
+
+    // Live (non-retained) state messages, such as our own echoes, are ignored.
+    assert!(!bridge.handle_message("home/device/brightness", b"10", false).unwrap());
+    assert_eq!(*prefs.lock().unwrap().get_brightness(), 35);
+}
+
+#[test]
+fn test_set_commands_and_local_changes() {
+    let (prefs, bridge, recorder) = bridge();
+    bridge.start().unwrap();
+    bridge.publish_changes();
+    recorder.take();
+
+    assert!(bridge.handle_message("home/device/mode/set", b"night", false).unwrap());
+    assert_eq!(prefs.lock().unwrap().get_mode(), "night");
+    assert_eq!(recorder.take(), [state("home/device/mode", "night")]);
+
+    // Invalid commands are rejected and nothing is published.
+    assert!(bridge.handle_message("home/device/brightness/set", b"150", false).is_err());
+    assert!(recorder.take().is_empty());
+
+    prefs.lock().unwrap().save_brightness(50).unwrap();
+    bridge.publish_changes();
+    assert_eq!(recorder.take(), [state("home/device/brightness", "50")]);
+
+    // Secret and unselected fields are never bridged.
+    assert!(!bridge.handle_message("home/device/wifi_password/set", b"x", false).unwrap());
+    assert!(!bridge.handle_message("home/device/local_only/set", b"true", false).unwrap());
+    assert!(!bridge.handle_message("other/brightness/set", b"1", false).unwrap());
+    assert_eq!(bridge.state_topic("mode"), "home/device/mode");
+}
+
+easy_prefs! {
+    struct ScopedPrefs {
+        pub brightness: i32 = 80 => "brightness",
+        pub window_x: i32 = 0 => "window_x" (device),
+        pub cache_hash: u32 = 0 => "cache_hash" (transient),
+        pub debug: bool = false => "debug" (no_export),
+        pub api_key: String = String::new() => "api_key" (secret),
+    },
+    "mqtt-scoped-prefs"
+}
+
+#[test]
+fn test_only_exported_fields_are_bridged() {
+    common::use_memory_secret_store();
+    let prefs = Arc::new(Mutex::new(ScopedPrefs::load_testing()));
+    let recorder = Recorder::default();
+    let bridge = MqttBridge::new(prefs, recorder.clone(), "home/device");
+    bridge.publish_changes();
+    assert_eq!(recorder.take(), [state("home/device/brightness", "80")]);
+    assert!(!bridge.handle_message("home/device/window_x/set", b"5", false).unwrap());
+    assert!(!bridge.handle_message("home/device/cache_hash/set", b"5", false).unwrap());
+}
@@ -0,0 +1,49 @@
+use easy_prefs::easy_prefs;
+
+easy_prefs! {
+    struct OptionalPrefs {
+        pub nickname: Option<String> = None => "nickname",
+        pub last_port: Option<u16> = None => "last_port",
+        pub count: i32 = 0 => "count",
+    },
+    "optional-prefs"
+}
+
+#[test]
+fn test_none_is_stored_as_absent_key() {
+    let mut prefs = OptionalPrefs::load_testing();
+    prefs.save_count(1).unwrap();
+
+    let contents = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+    assert!(!contents.contains("nickname"));
+    assert!(!contents.contains("last_port"));
+    assert!(!prefs.to_string().contains("nickname"));
+}
+
+#[test]
+fn test_option_round_trip() {
+    let mut prefs = OptionalPrefs::load_testing();
+    let path = prefs.get_preferences_file_path();
+
+    prefs.save_nickname(Some("ace".to_string())).unwrap();
+    {
+        let mut edit = prefs.edit();
+        edit.set_last_port(Some(8080));
+    }
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(contents.contains("nickname = \"ace\""));
+    assert!(contents.contains("last_port = 8080"));
+
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_nickname().as_deref(), Some("ace"));
+    assert_eq!(*prefs.get_last_port(), Some(8080));
+
+    // Clearing a value removes the key again.
+    prefs.save_nickname(None).unwrap();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(!contents.contains("nickname"));
+
+    prefs.reload().unwrap();
+    assert_eq!(*prefs.get_nickname(), None);
+    assert_eq!(*prefs.get_last_port(), Some(8080));
+}
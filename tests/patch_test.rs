@@ -0,0 +1,113 @@
+use easy_prefs::{easy_prefs, LoadError};
+use std::time::Duration;
+
+easy_prefs! {
+    struct PatchPrefs {
+        pub theme: String = "light".to_string() => "theme" (choices = ["light", "dark"]),
+        pub retries: i32 = 3 => "retries" (min = 0, max = 10),
+        pub machine_id: String = String::new() => "machine_id" (device),
+        pub window: PatchWindow {
+            pub width: i32 = 800 => "width",
+            pub height: i32 = 600 => "height",
+        } => "window",
+    },
+    "patch-prefs"
+}
+
+#[test]
+fn test_patch_applies_present_keys() {
+    let mut prefs = PatchPrefs::load_testing();
+    prefs.save_retries(4).unwrap();
+
+    let report = prefs
+        .apply_toml_patch("theme = \"dark\"\nretries = 4\nmachine_id = \"m-1\"\nobsolete = 1\n")
+        .unwrap();
+    assert_eq!(report.applied, ["theme", "machine_id"]);
+    assert_eq!(report.unchanged, ["retries"]);
+    assert_eq!(report.unknown, ["obsolete"]);
+    assert_eq!(report.to_string(), "applied: [theme, machine_id], unchanged: [retries], unknown: [obsolete]");
+
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_theme(), "dark");
+    assert_eq!(prefs.get_machine_id(), "m-1", "device fields can be patched");
+    assert_eq!(*prefs.get_retries(), 4);
+}
+
+#[test]
+fn test_patch_sections_key_by_key() {
+    let mut prefs = PatchPrefs::load_testing();
+    prefs.apply_toml_patch("[window]\nheight = 900\n").unwrap();
+    assert_eq!(prefs.get_window().width, 800);
+    assert_eq!(prefs.get_window().height, 900);
+
+    let report = prefs.apply_toml_patch("[window]\nheight = 900\n").unwrap();
+    assert!(!report.changed());
+}
+
+#[test]
+fn test_invalid_patches_change_nothing() {
+    let mut prefs = PatchPrefs::load_testing();
+    prefs.save_retries(6).unwrap();
+
+    assert!(matches!(
+        prefs.apply_toml_patch("retries = 11"),
+        Err(LoadError::ValidationError(_))
+    ));
+    assert!(matches!(
+        prefs.apply_toml_patch("theme = \"blue\""),
+        Err(LoadError::ValidationError(_))
+    ));
+    match prefs.apply_toml_patch("theme = \"dark\"\nretries = \"many\"") {
+        Err(LoadError::DeserializationError(location, _)) => assert_eq!(location, "patch"),
+        other => panic!("expected a type error, got {other:?}"),
+    }
+    assert!(matches!(
+        prefs.apply_toml_patch("retries = "),
+        Err(LoadError::DeserializationError(..))
+    ));
+    assert_eq!(*prefs.get_retries(), 6);
+    assert_eq!(prefs.get_theme(), "light");
+}
+
+#[test]
+fn test_patch_ends_temporary_override() {
+    let mut prefs = PatchPrefs::load_testing();
+    prefs.set_retries_temporarily(9, Duration::from_secs(3600)).unwrap();
+    prefs.apply_toml_patch("retries = 2").unwrap();
+    assert_eq!(*prefs.get_retries(), 2);
+    assert_eq!(prefs.get_retries_expiry(), None);
+}
+
+#[test]
+fn test_merge_policies() {
+    use easy_prefs::patch::MergePolicy;
+    let document = "theme = \"light\"\nretries = 8\n[window]\nwidth = 1024\nheight = 600\n";
+
+    let mut prefs = PatchPrefs::load_testing();
+    prefs.save_theme("dark".to_string()).unwrap();
+    prefs.apply_toml_patch("[window]\nheight = 700\n").unwrap();
+    let report = prefs.merge_from_toml(document, MergePolicy::SkipDefaults).unwrap();
+    assert_eq!(report.applied, ["retries", "window"]);
+    assert_eq!(report.skipped, ["theme", "window"]);
+    assert_eq!(report.to_string(), "applied: [retries, window], unchanged: [], unknown: [], skipped: [theme, window]");
+    assert_eq!(prefs.get_theme(), "dark");
+    assert_eq!((prefs.get_window().width, prefs.get_window().height), (1024, 700));
+
+    let mut prefs = PatchPrefs::load_testing();
+    prefs.save_retries(5).unwrap();
+    prefs.apply_toml_patch("[window]\nheight = 700\n").unwrap();
+    let report = prefs.merge_from_toml(document, MergePolicy::OnlyMissing).unwrap();
+    assert_eq!(report.skipped, ["retries", "window"]);
+    assert_eq!(*prefs.get_retries(), 5);
+    assert_eq!((prefs.get_window().width, prefs.get_window().height), (1024, 700));
+
+    let report = prefs.merge_from_toml(document, MergePolicy::OverwriteAll).unwrap();
+    assert!(report.skipped.is_empty());
+    prefs.reload().unwrap();
+    assert_eq!(*prefs.get_retries(), 8);
+    assert_eq!(prefs.get_window().height, 600);
+
+    // Invalid values fail the whole merge.
+    assert!(prefs.merge_from_toml("theme = \"dark\"\nretries = 80\n", MergePolicy::OverwriteAll).is_err());
+    assert_eq!(prefs.get_theme(), "light");
+}
@@ -0,0 +1,47 @@
+use easy_prefs::dynamic::DynamicPrefs;
+use easy_prefs::{easy_prefs, Preferences};
+use toml::Value;
+
+easy_prefs! {
+    struct UiPrefs {
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "trait-ui-prefs"
+}
+
+easy_prefs! {
+    struct NetPrefs {
+        pub retries: i32 = 3 => "retries",
+    },
+    "trait-net-prefs"
+}
+
+fn load<P: Preferences>(directory: &str) -> P {
+    P::load_with_error(directory).unwrap()
+}
+
+#[test]
+fn test_heterogeneous_structs() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+    let mut ui: UiPrefs = load(dir);
+    let mut net: NetPrefs = load(dir);
+
+    {
+        let mut all: Vec<&mut dyn Preferences> = vec![&mut ui, &mut net];
+        let keys: Vec<&str> = all.iter().flat_map(|prefs| prefs.fields()).map(|field| field.key).collect();
+        assert_eq!(keys, ["theme", "retries"]);
+
+        all[0].set_value("theme", Value::from("dark")).unwrap();
+        all[1].set_value("retries", Value::from(5)).unwrap();
+        for prefs in &all {
+            prefs.save().unwrap();
+            assert!(prefs.get_preferences_file_path().starts_with(dir));
+        }
+    }
+    assert_eq!(ui.get_theme(), "dark");
+
+    std::fs::write(net.get_preferences_file_path(), "retries = 9\n").unwrap();
+    Preferences::reload(&mut net).unwrap();
+    assert_eq!(net.get_value("retries"), Some(Value::Integer(9)));
+}
@@ -0,0 +1,115 @@
+use easy_prefs::easy_prefs;
+
+easy_prefs! {
+    struct PreservePrefs {
+        pub volume: i32 = 5 => "volume",
+        pub flags: i64 = 16 => "flags",
+        pub nickname: Option<String> = None => "nickname",
+        pub window: PreserveWindow {
+            pub width: i32 = 800 => "width",
+            pub height: i32 = 600 => "height",
+        } => "window",
+    },
+    "preserve-prefs",
+    preserve_formatting = true
+}
+
+easy_prefs! {
+    struct RewritePrefs {
+        pub volume: i32 = 5 => "volume",
+    },
+    "rewrite-prefs"
+}
+
+#[test]
+fn test_unknown_keys_and_comments_survive_save() {
+    let mut prefs = PreservePrefs::load_testing();
+    let path = prefs.get_preferences_file_path();
+    std::fs::write(
+        &path,
+        "# Written by hand\n\
+         volume = 5 # loudness\n\
+         flags = 0x10\n\
+         nickname = 'bob'\n\
+         added_by_newer_version = true\n\
+         \n\
+         [window]\n\
+         # the main window\n\
+         width = 800\n\
+         height = 600\n",
+    )
+    .unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(*prefs.get_flags(), 16);
+
+    prefs.save_volume(7).unwrap();
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(contents.contains("# Written by hand"));
+    assert!(contents.contains("volume = 7 # loudness"));
+    assert!(contents.contains("flags = 0x10"));
+    assert!(contents.contains("nickname = 'bob'"));
+    assert!(contents.contains("added_by_newer_version = true"));
+    assert!(contents.contains("# the main window"));
+}
+
+#[test]
+fn test_sections_and_none_are_merged() {
+    let mut prefs = PreservePrefs::load_testing();
+    let path = prefs.get_preferences_file_path();
+    std::fs::write(
+        &path,
+        "nickname = \"bob\"\n\
+         [window]\n\
+         width = 800 # pixels\n\
+         legacy_x = 10\n",
+    )
+    .unwrap();
+    prefs.reload().unwrap();
+
+    {
+        let mut edit = prefs.edit();
+        edit.set_nickname(None);
+        edit.set_window(PreserveWindow {
+            width: 1024,
+            height: 768,
+        });
+    }
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    let table: toml::Table = contents.parse().unwrap();
+    assert!(!table.contains_key("nickname"));
+    assert_eq!(table["window"]["width"].as_integer(), Some(1024));
+    assert_eq!(table["window"]["height"].as_integer(), Some(768));
+    // Keys inside a field's table mirror the value.
+    assert!(!table["window"].as_table().unwrap().contains_key("legacy_x"));
+    assert!(contents.contains("width = 1024 # pixels"));
+
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_window().height, 768);
+}
+
+#[test]
+fn test_invalid_existing_file_is_rewritten() {
+    let mut prefs = PreservePrefs::load_testing();
+    let path = prefs.get_preferences_file_path();
+    prefs.save_volume(3).unwrap();
+    std::fs::write(&path, "volume = [unterminated").unwrap();
+
+    prefs.save_volume(4).unwrap();
+    let table: toml::Table = std::fs::read_to_string(&path).unwrap().parse().unwrap();
+    assert_eq!(table["volume"].as_integer(), Some(4));
+}
+
+#[test]
+fn test_without_option_file_is_rewritten() {
+    let mut prefs = RewritePrefs::load_testing();
+    let path = prefs.get_preferences_file_path();
+    std::fs::write(&path, "# comment\nvolume = 5\nunknown = 1\n").unwrap();
+    prefs.reload().unwrap();
+
+    prefs.save_volume(6).unwrap();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(!contents.contains("# comment"));
+    assert!(!contents.contains("unknown"));
+}
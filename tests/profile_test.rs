@@ -0,0 +1,45 @@
+use easy_prefs::{easy_prefs, LoadError};
+
+easy_prefs! {
+    struct ProfilePrefs {
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "profile-prefs"
+}
+
+#[test]
+fn test_profiles_are_separate_instances() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    let mut default = ProfilePrefs::load_with_error(path).unwrap();
+    let mut work = ProfilePrefs::load_profile(path, "work").unwrap();
+    let home = ProfilePrefs::load_profile(path, "home").unwrap();
+    default.save_theme("blue".to_string()).unwrap();
+    work.save_theme("dark".to_string()).unwrap();
+    assert_eq!(home.get_theme(), "light");
+    assert!(work.get_preferences_file_path().ends_with("profile-prefs.work.toml"));
+    assert!(dir.path().join("profile-prefs.toml").exists());
+
+    // One instance per profile.
+    assert!(matches!(
+        ProfilePrefs::load_profile(path, "work"),
+        Err(LoadError::InstanceAlreadyLoaded)
+    ));
+    drop(work);
+    let work = ProfilePrefs::load_profile(path, "work").unwrap();
+    assert_eq!(work.get_theme(), "dark");
+    assert_eq!(default.get_theme(), "blue");
+}
+
+#[test]
+fn test_invalid_profile_names() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().to_str().unwrap();
+    for name in ["", "../work", "a.b", "work space"] {
+        assert!(matches!(
+            ProfilePrefs::load_profile(path, name),
+            Err(LoadError::StorageError(e)) if e.kind() == std::io::ErrorKind::InvalidInput
+        ));
+    }
+}
@@ -0,0 +1,46 @@
+use easy_prefs::easy_prefs;
+use easy_prefs::storage::native::FileStorage;
+use easy_prefs::storage::Storage;
+
+easy_prefs! {
+    struct PolledPrefs {
+        pub volume: i32 = 5 => "volume",
+    },
+    "polled-prefs",
+    cache_reads = true
+}
+
+#[test]
+fn test_cached_reads_check_metadata() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut storage = FileStorage::new(tmp.path().to_str().unwrap());
+    storage.set_read_cache(true);
+    storage.write("cached.toml", "a = 1\n").unwrap();
+    let path = tmp.path().join("cached.toml");
+    let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+
+    // Same size and modification time: the cached contents are returned without reading.
+    std::fs::write(&path, "a = 2\n").unwrap();
+    std::fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+    assert_eq!(storage.read("cached.toml").unwrap().as_deref(), Some("a = 1\n"));
+
+    // A different size means the file changed.
+    std::fs::write(&path, "a = 30\n").unwrap();
+    assert_eq!(storage.read("cached.toml").unwrap().as_deref(), Some("a = 30\n"));
+
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(storage.read("cached.toml").unwrap(), None);
+}
+
+#[test]
+fn test_reload_polling_sees_external_changes() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut prefs = PolledPrefs::load_with_error(tmp.path().to_str().unwrap()).unwrap();
+    prefs.save_volume(7).unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(*prefs.get_volume(), 7);
+
+    std::fs::write(prefs.get_preferences_file_path(), "volume = 11\n").unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(*prefs.get_volume(), 11);
+}
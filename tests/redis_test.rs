@@ -0,0 +1,164 @@
+#![cfg(all(feature = "redis", not(target_arch = "wasm32")))]
+
+use easy_prefs::storage::redis::RedisStorage;
+use easy_prefs::storage::Storage;
+use easy_prefs::{easy_prefs, SaveError};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+easy_prefs! {
+    struct ServicePrefs {
+        pub max_connections: i64 = 100 => "max_connections",
+        pub region: String = "eu".to_string() => "region",
+    },
+    "service-prefs",
+    single_instance = false
+}
+
+/// A database number and key.
+type Key = (u32, Vec<u8>);
+
+/// A fake server: strings per database, the commands it received, and switches for failures.
+#[derive(Default)]
+struct Server {
+    strings: Mutex<HashMap<Key, Vec<u8>>>,
+    commands: Mutex<Vec<String>>,
+    out_of_memory: AtomicBool,
+    drop_next: AtomicBool,
+    huge_reply: AtomicBool,
+}
+
+/// Serves RESP on a local port, requiring the password `secret`.
+fn serve() -> (Arc<Server>, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = Arc::new(Server::default());
+    let shared = server.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let server = shared.clone();
+            std::thread::spawn(move || handle(stream.unwrap(), &server));
+        }
+    });
+    (server, address.to_string())
+}
+
+fn read_command(reader: &mut BufReader<TcpStream>) -> Option<Vec<Vec<u8>>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).ok()? == 0 {
+        return None;
+    }
+    let count: usize = line.trim_end().strip_prefix('*')?.parse().ok()?;
+    let mut arguments = Vec::new();
+    for _ in 0..count {
+        line.clear();
+        reader.read_line(&mut line).ok()?;
+        let len: usize = line.trim_end().strip_prefix('$')?.parse().ok()?;
+        let mut argument = vec![0; len + 2];
+        reader.read_exact(&mut argument).ok()?;
+        argument.truncate(len);
+        arguments.push(argument);
+    }
+    Some(arguments)
+}
+
+fn handle(stream: TcpStream, server: &Server) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut writer = stream;
+    let (mut authenticated, mut database) = (false, 0);
+    while let Some(command) = read_command(&mut reader) {
+        if server.drop_next.swap(false, Ordering::SeqCst) {
+            return;
+        }
+        let name = String::from_utf8_lossy(&command[0]).to_string();
+        server.commands.lock().unwrap().push(name.clone());
+        let reply = match (name.as_str(), authenticated) {
+            ("AUTH", _) if command.last().unwrap() == b"secret" => {
+                authenticated = true;
+                b"+OK\r\n".to_vec()
+            }
+            ("AUTH", _) => b"-WRONGPASS invalid username-password pair\r\n".to_vec(),
+            (_, false) => b"-NOAUTH Authentication required.\r\n".to_vec(),
+            ("PING", _) => b"+PONG\r\n".to_vec(),
+            ("SELECT", _) => {
+                database = String::from_utf8_lossy(&command[1]).parse().unwrap();
+                b"+OK\r\n".to_vec()
+            }
+            ("GET", _) if server.huge_reply.load(Ordering::SeqCst) => b"$1099511627776\r\n".to_vec(),
+            ("GET", _) => match server.strings.lock().unwrap().get(&(database, command[1].clone())) {
+                Some(value) => [format!("${}\r\n", value.len()).into_bytes(), value.clone(), b"\r\n".to_vec()].concat(),
+                None => b"$-1\r\n".to_vec(),
+            },
+            ("SET", _) if server.out_of_memory.load(Ordering::SeqCst) => {
+                b"-OOM command not allowed when used memory > 'maxmemory'.\r\n".to_vec()
+            }
+            ("SET", _) => {
+                server.strings.lock().unwrap().insert((database, command[1].clone()), command[2].clone());
+                b"+OK\r\n".to_vec()
+            }
+            _ => b"-ERR unknown command\r\n".to_vec(),
+        };
+        if writer.write_all(&reply).is_err() {
+            return;
+        }
+    }
+}
+
+#[test]
+fn test_round_trip() {
+    let (server, address) = serve();
+    let storage = RedisStorage::open(&format!("redis://:secret@{address}/3")).unwrap().with_prefix("billing:");
+    assert_eq!(storage.get_path("service-prefs.toml"), format!("redis://{address}/3/billing:service-prefs.toml"));
+
+    let mut prefs = ServicePrefs::load_with_storage(Box::new(storage)).unwrap();
+    prefs.save_max_connections(250).unwrap();
+    let stored = server.strings.lock().unwrap().get(&(3, b"billing:service-prefs.toml".to_vec())).cloned();
+    assert!(String::from_utf8(stored.unwrap()).unwrap().contains("max_connections = 250"));
+
+    // Another instance of the service sees the value.
+    let storage = RedisStorage::open(&format!("redis://:secret@{address}/3")).unwrap().with_prefix("billing:");
+    let other = ServicePrefs::load_with_storage(Box::new(storage)).unwrap();
+    assert_eq!(*other.get_max_connections(), 250);
+
+    // Databases and prefixes keep values apart.
+    let storage = RedisStorage::open(&format!("redis://:secret@{address}")).unwrap();
+    assert_eq!(storage.read("billing:service-prefs.toml").unwrap(), None);
+    let commands = server.commands.lock().unwrap();
+    assert_eq!(commands[..3], ["AUTH", "SELECT", "PING"]);
+}
+
+#[test]
+fn test_errors() {
+    let (server, address) = serve();
+    let err = RedisStorage::open(&format!("redis://:wrong@{address}")).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+    let err = RedisStorage::open(&format!("redis://{address}")).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+    let err = RedisStorage::open("http://localhost").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    let err = RedisStorage::open("redis://:secret@localhost/main").unwrap_err();
+    assert!(!err.to_string().contains("secret"), "{err}");
+
+    let storage = RedisStorage::open(&format!("redis://:secret@{address}")).unwrap();
+    assert!(!format!("{storage:?}").contains("secret"));
+    // A length the server can't mean is refused before anything is allocated.
+    server.huge_reply.store(true, Ordering::SeqCst);
+    assert_eq!(storage.read("service-prefs.toml").unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    server.huge_reply.store(false, Ordering::SeqCst);
+    assert_eq!(storage.read("service-prefs.toml").unwrap(), None);
+
+    let storage = RedisStorage::open(&format!("redis://default:secret@{address}")).unwrap();
+    let mut prefs = ServicePrefs::load_with_storage(Box::new(storage)).unwrap();
+    server.out_of_memory.store(true, Ordering::SeqCst);
+    assert!(matches!(prefs.save_region("us".to_string()), Err(SaveError::QuotaExceeded)));
+    server.out_of_memory.store(false, Ordering::SeqCst);
+
+    // A connection the server closed is reopened.
+    server.drop_next.store(true, Ordering::SeqCst);
+    prefs.save_region("ap".to_string()).unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_region(), "ap");
+}
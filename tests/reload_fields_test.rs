@@ -0,0 +1,72 @@
+use easy_prefs::{easy_prefs, LoadError};
+
+easy_prefs! {
+    struct MixedPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub server_url: String = String::new() => "server_url",
+        pub max_connections: i32 = 4 => "max_connections",
+    },
+    "mixed-prefs"
+}
+
+fn require_capacity(prefs: &CheckedMixedPrefs) -> Result<(), String> {
+    if *prefs.get_min_connections() > *prefs.get_max_connections() {
+        return Err("min above max".to_string());
+    }
+    Ok(())
+}
+
+easy_prefs! {
+    struct CheckedMixedPrefs {
+        pub min_connections: i32 = 1 => "min_connections",
+        pub max_connections: i32 = 4 => "max_connections",
+    },
+    "checked-mixed-prefs",
+    validate_struct = require_capacity
+}
+
+/// Simulates a provisioning tool rewriting the file.
+fn write_externally(path: &str, contents: &str) {
+    std::fs::write(path, contents).unwrap();
+}
+
+#[test]
+fn test_reload_selected_fields() {
+    let mut prefs = MixedPrefs::load_testing();
+    prefs.save_theme("dark".to_string()).unwrap();
+    let path = prefs.get_preferences_file_path();
+    write_externally(&path, "theme = \"light\"\nserver_url = \"https://example.com\"\nmax_connections = 16\n");
+
+    prefs.reload_fields(&["server_url", "max_connections"]).unwrap();
+    assert_eq!(prefs.get_server_url(), "https://example.com");
+    assert_eq!(*prefs.get_max_connections(), 16);
+    assert_eq!(prefs.get_theme(), "dark", "other fields keep their in-memory values");
+
+    write_externally(&path, "server_url = \"https://other.example.com\"\n");
+    prefs.reload_server_url().unwrap();
+    assert_eq!(prefs.get_server_url(), "https://other.example.com");
+    assert_eq!(*prefs.get_max_connections(), 16);
+
+    // A key missing from the file reloads as the default.
+    prefs.reload_max_connections().unwrap();
+    assert_eq!(*prefs.get_max_connections(), 4);
+}
+
+#[test]
+fn test_reload_fields_errors() {
+    let mut prefs = MixedPrefs::load_testing();
+    match prefs.reload_fields(&["theme", "no_such_key"]) {
+        Err(LoadError::StorageError(e)) => assert_eq!(e.kind(), std::io::ErrorKind::NotFound),
+        other => panic!("expected NotFound, got {other:?}"),
+    }
+
+    let mut checked = CheckedMixedPrefs::load_testing();
+    checked.save_max_connections(8).unwrap();
+    checked.save_min_connections(6).unwrap();
+    write_externally(&checked.get_preferences_file_path(), "min_connections = 6\nmax_connections = 2\n");
+    assert!(matches!(
+        checked.reload_max_connections(),
+        Err(LoadError::ValidationError(_))
+    ));
+    assert_eq!(*checked.get_max_connections(), 8);
+}
@@ -0,0 +1,53 @@
+use easy_prefs::{easy_prefs, LoadError};
+
+easy_prefs! {
+    struct ReloadPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub font_size: i32 = 14 => "font_size",
+    },
+    "reload-prefs"
+}
+
+#[test]
+fn test_reload_picks_up_external_changes() {
+    let mut prefs = ReloadPrefs::load_testing();
+    prefs.save_font_size(16).unwrap();
+
+    // Simulate another process (or a text editor) changing the file.
+    let path = prefs.get_preferences_file_path();
+    std::fs::write(&path, "theme = \"dark\"\nfont_size = 20\n").unwrap();
+
+    prefs.reload().expect("reload should succeed");
+    assert_eq!(prefs.get_theme(), "dark");
+    assert_eq!(*prefs.get_font_size(), 20);
+
+    // The instance is still usable for saving afterwards.
+    prefs.save_font_size(22).unwrap();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(contents.contains("theme = \"dark\""));
+    assert!(contents.contains("font_size = 22"));
+}
+
+#[test]
+fn test_reload_error_keeps_values() {
+    let mut prefs = ReloadPrefs::load_testing();
+    prefs.save_theme("dark".to_string()).unwrap();
+
+    std::fs::write(prefs.get_preferences_file_path(), "font_size = \"huge\"").unwrap();
+
+    let result = prefs.reload();
+    assert!(matches!(result, Err(LoadError::DeserializationError(_, _))));
+    assert_eq!(prefs.get_theme(), "dark");
+    assert_eq!(*prefs.get_font_size(), 14);
+}
+
+#[test]
+fn test_reload_resets_to_defaults_when_file_is_gone() {
+    let mut prefs = ReloadPrefs::load_testing();
+    prefs.save_font_size(30).unwrap();
+
+    std::fs::remove_file(prefs.get_preferences_file_path()).unwrap();
+
+    prefs.reload().unwrap();
+    assert_eq!(*prefs.get_font_size(), 14);
+}
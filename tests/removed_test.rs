@@ -0,0 +1,76 @@
+use easy_prefs::easy_prefs;
+
+fn legacy_theme(value: &toml::Value) -> Option<String> {
+    match value.as_str()? {
+        "night" => Some("dark".to_string()),
+        "day" => Some("light".to_string()),
+        _ => None,
+    }
+}
+
+easy_prefs! {
+    pub struct RetiredPrefs {
+        pub theme: String = "light".to_string() => "theme" (choices = ["light", "dark"]),
+        removed "legacy_theme" => |value, prefs| {
+            if let Some(theme) = legacy_theme(&value) {
+                prefs._theme = theme;
+            }
+        },
+        pub window: RetiredWindow {
+            pub width: i32 = 800 => "width",
+        } => "window",
+        removed "window_width" => |value, prefs| {
+            if let Some(width) = value.as_integer() {
+                prefs._window.width = width as i32;
+            }
+        },
+    },
+    "retired-prefs",
+    strict = true,
+}
+
+easy_prefs! {
+    pub struct PreservedRetiredPrefs {
+        pub volume: i32 = 5 => "volume",
+        removed "loudness" => |value, prefs| prefs._volume = value.as_integer().unwrap_or(5) as i32,
+    },
+    "preserved-retired-prefs",
+    preserve_formatting = true
+}
+
+#[test]
+fn test_removed_fields_are_migrated_once() {
+    assert_eq!(RetiredPrefs::OPTIONS.removed.len(), 2);
+    assert_eq!(RetiredPrefs::OPTIONS.removed[0].key, "legacy_theme");
+
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+    let path = tmp.path().join("retired-prefs.toml");
+    std::fs::write(&path, "legacy_theme = \"night\"\nwindow_width = 1024\n").unwrap();
+
+    let mut prefs = RetiredPrefs::load_with_error(dir).unwrap();
+    assert_eq!(prefs.get_theme(), "dark");
+    assert_eq!(prefs.get_window().width, 1024);
+
+    prefs.save_theme("light".to_string()).unwrap();
+    let stored = std::fs::read_to_string(&path).unwrap();
+    assert!(!stored.contains("legacy_theme") && !stored.contains("window_width"), "{stored}");
+
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_theme(), "light");
+    assert_eq!(prefs.get_window().width, 1024);
+}
+
+#[test]
+fn test_preserved_formatting_drops_removed_keys() {
+    let mut prefs = PreservedRetiredPrefs::load_testing();
+    let path = prefs.get_preferences_file_path();
+    std::fs::write(&path, "# kept\nloudness = 9\nadded_by_newer_version = true\n").unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(*prefs.get_volume(), 9);
+
+    prefs.save().unwrap();
+    let stored = std::fs::read_to_string(&path).unwrap();
+    assert!(!stored.contains("loudness"), "{stored}");
+    assert!(stored.contains("added_by_newer_version = true"), "{stored}");
+}
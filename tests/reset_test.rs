@@ -0,0 +1,78 @@
+use easy_prefs::easy_prefs;
+use std::time::Duration;
+
+easy_prefs! {
+    struct ResetPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub font_size: i32 = 14 => "font_size",
+        pub recent_files: Vec<String> = Vec::new() => "recent_files",
+    },
+    "reset-prefs"
+}
+
+fn require_small_font(prefs: &StrictResetPrefs) -> Result<(), String> {
+    if *prefs.get_font_size() > 12 {
+        return Err("font too large".to_string());
+    }
+    Ok(())
+}
+
+easy_prefs! {
+    struct StrictResetPrefs {
+        pub font_size: i32 = 14 => "font_size",
+    },
+    "strict-reset-prefs",
+    validate_struct = require_small_font
+}
+
+#[test]
+fn test_reset_field() {
+    let mut prefs = ResetPrefs::load_testing();
+    assert!(prefs.is_default_theme());
+    assert!(prefs.is_default_recent_files());
+
+    prefs.save_theme("dark".to_string()).unwrap();
+    prefs.save_font_size(20).unwrap();
+    assert!(!prefs.is_default_theme());
+
+    prefs.reset_theme().unwrap();
+    assert!(prefs.is_default_theme());
+    assert_eq!(*prefs.get_font_size(), 20);
+
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_theme(), "light");
+    assert_eq!(*prefs.get_font_size(), 20);
+}
+
+#[test]
+fn test_reset_to_defaults() {
+    let mut prefs = ResetPrefs::load_testing();
+    prefs.save_theme("dark".to_string()).unwrap();
+    prefs.push_recent_files("notes.txt".to_string()).unwrap();
+    prefs
+        .set_font_size_temporarily(30, Duration::from_secs(3600))
+        .unwrap();
+
+    prefs.reset_to_defaults().unwrap();
+    assert!(prefs.is_default_theme() && prefs.is_default_font_size() && prefs.is_default_recent_files());
+    assert_eq!(prefs.get_font_size_expiry(), None);
+
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_theme(), "light");
+    assert!(prefs.get_recent_files().is_empty());
+    assert_eq!(*prefs.get_font_size(), 14);
+}
+
+#[test]
+fn test_reset_rejected_by_struct_validation() {
+    let mut prefs = StrictResetPrefs::load_testing();
+    prefs.save_font_size(10).unwrap();
+
+    assert!(matches!(
+        prefs.reset_to_defaults(),
+        Err(easy_prefs::SaveError::ValidationError(_))
+    ));
+    assert!(prefs.reset_font_size().is_err());
+    assert_eq!(*prefs.get_font_size(), 10);
+    assert!(!prefs.is_default_font_size());
+}
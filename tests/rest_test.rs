@@ -0,0 +1,123 @@
+#![cfg(feature = "rest")]
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use axum::Router;
+use easy_prefs::easy_prefs;
+use easy_prefs::rest::{Access, RestService};
+use easy_prefs::secrets::REDACTED;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use tower_service::Service;
+
+mod common;
+
+easy_prefs! {
+    struct RestPrefs {
+        pub theme: String = "light".to_string() => "theme" (choices = ["light", "dark"]),
+        pub volume: i32 = 5 => "volume" (max = 10),
+        pub build: String = "1.0".to_string() => "build",
+        pub token: String = "abc".to_string() => "token" (secret),
+    },
+    "rest-prefs"
+}
+
+/// Wakes the test thread; the router's futures complete without a runtime.
+struct ThreadWaker(std::thread::Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    let mut context = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+/// Sends a request and returns the status, `ETag`, and body.
+fn send(router: &mut Router, method: &str, uri: &str, if_match: Option<&str>, body: &str) -> (StatusCode, String, String) {
+    let mut request = Request::builder().method(method).uri(uri);
+    if let Some(tag) = if_match {
+        request = request.header(header::IF_MATCH, tag);
+    }
+    let response = block_on(router.call(request.body(Body::from(body.to_string())).unwrap())).unwrap();
+    let status = response.status();
+    let etag = response.headers()[header::ETAG].to_str().unwrap().to_string();
+    let body = block_on(axum::body::to_bytes(response.into_body(), usize::MAX)).unwrap();
+    (status, etag, String::from_utf8(body.to_vec()).unwrap())
+}
+
+fn service() -> (Arc<Mutex<RestPrefs>>, Router) {
+    common::use_memory_secret_store();
+    let prefs = Arc::new(Mutex::new(RestPrefs::load_testing()));
+    let router = RestService::new(prefs.clone())
+        .with_access(|field| if field.key == "build" { Access::ReadOnly } else { Access::ReadWrite })
+        .router();
+    (prefs, router)
+}
+
+#[test]
+fn test_fields() {
+    let (prefs, mut router) = service();
+
+    let (status, _, body) = send(&mut router, "GET", "/fields/theme", None, "");
+    assert_eq!((status, body.as_str()), (StatusCode::OK, "light"));
+    assert_eq!(send(&mut router, "GET", "/fields/token", None, "").2, REDACTED);
+
+    let (status, _, _) = send(&mut router, "PUT", "/fields/theme", None, "dark\n");
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(prefs.lock().unwrap().get_theme(), "dark");
+
+    assert_eq!(send(&mut router, "PUT", "/fields/build", None, "2.0").0, StatusCode::FORBIDDEN);
+    assert_eq!(send(&mut router, "PUT", "/fields/volume", None, "11").0, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(send(&mut router, "GET", "/fields/missing", None, "").0, StatusCode::NOT_FOUND);
+
+    // Secret fields are hidden by default.
+    let mut router = RestService::new(prefs).router();
+    assert_eq!(send(&mut router, "GET", "/fields/token", None, "").0, StatusCode::NOT_FOUND);
+    assert_eq!(send(&mut router, "PUT", "/fields/theme", None, "light").0, StatusCode::FORBIDDEN);
+}
+
+#[test]
+fn test_document_with_etag() {
+    let (prefs, mut router) = service();
+
+    let (status, etag, document) = send(&mut router, "GET", "/", None, "");
+    assert_eq!(status, StatusCode::OK);
+    assert!(document.contains("build = \"1.0\""));
+    assert!(document.contains(&format!("token = \"{REDACTED}\"")));
+
+    // An edited copy of the document is accepted: unchanged read-only and secret fields are
+    // left alone.
+    let edited = document.replace("volume = 5", "volume = 7");
+    let (status, new_etag, _) = send(&mut router, "PUT", "/", Some(&etag), &edited);
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_ne!(new_etag, etag);
+    assert_eq!(*prefs.lock().unwrap().get_volume(), 7);
+    assert_eq!(prefs.lock().unwrap().get_token(), "abc");
+
+    // A stale ETag is refused.
+    let (status, current, _) = send(&mut router, "PUT", "/", Some(&etag), "volume = 8");
+    assert_eq!((status, current.as_str()), (StatusCode::PRECONDITION_FAILED, new_etag.as_str()));
+    assert_eq!(send(&mut router, "PUT", "/fields/volume", Some(&etag), "8").0, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(*prefs.lock().unwrap().get_volume(), 7);
+
+    // A rejected field rolls back the fields saved before it.
+    let (status, _, body) = send(&mut router, "PUT", "/", None, "theme = \"dark\"\nvolume = 99");
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body.contains("volume"));
+    assert_eq!(prefs.lock().unwrap().get_theme(), "light");
+
+    assert_eq!(send(&mut router, "PUT", "/", None, "build = \"2.0\"").0, StatusCode::FORBIDDEN);
+    assert_eq!(send(&mut router, "PUT", "/", None, "not toml").0, StatusCode::BAD_REQUEST);
+}
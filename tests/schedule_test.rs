@@ -0,0 +1,88 @@
+use easy_prefs::easy_prefs;
+use easy_prefs::schedule::{Scheduled, TimeOfDay};
+
+easy_prefs! {
+    struct QuietPrefs {
+        pub do_not_disturb: Scheduled<bool> = Scheduled::new(false)
+            .with_window(TimeOfDay::new(22, 0), TimeOfDay::new(7, 0), true) => "do_not_disturb",
+        pub volume: Scheduled<i32> = Scheduled::new(80) => "volume",
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "schedule-prefs"
+}
+
+fn at(hour: u8, minute: u8) -> TimeOfDay {
+    TimeOfDay::new(hour, minute)
+}
+
+#[test]
+fn test_windows_wrap_past_midnight() {
+    let prefs = QuietPrefs::load_testing();
+    assert!(*prefs.effective_do_not_disturb(at(22, 0)));
+    assert!(*prefs.effective_do_not_disturb(at(3, 15)));
+    assert!(!*prefs.effective_do_not_disturb(at(7, 0)));
+    assert!(!*prefs.effective_do_not_disturb(at(21, 59)));
+    assert_eq!(*prefs.effective_volume(at(3, 15)), 80);
+}
+
+#[test]
+fn test_first_matching_window_wins() {
+    let schedule = Scheduled::new(80)
+        .with_window(at(12, 0), at(13, 0), 20)
+        .with_window(at(9, 0), at(17, 0), 50)
+        .with_window(at(10, 0), at(10, 0), 0);
+    assert_eq!(*schedule.effective(at(12, 30)), 20);
+    assert_eq!(*schedule.effective(at(10, 0)), 50);
+    assert_eq!(*schedule.effective(at(17, 0)), 80);
+}
+
+#[test]
+fn test_schedule_is_saved_and_editable() {
+    let mut prefs = QuietPrefs::load_testing();
+    {
+        let mut edit = prefs.edit();
+        let mut volume = edit.get_volume().clone();
+        volume.windows.push(easy_prefs::schedule::Window {
+            start: at(8, 30),
+            end: at(17, 0),
+            value: 30,
+        });
+        edit.set_volume(volume);
+    }
+
+    let contents = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+    assert!(contents.contains("start = \"08:30\""));
+    assert!(contents.contains("end = \"17:00\""));
+
+    prefs.reload().unwrap();
+    assert_eq!(*prefs.effective_volume(at(9, 0)), 30);
+    assert_eq!(*prefs.effective_volume(at(18, 0)), 80);
+}
+
+#[test]
+fn test_load_hand_written_schedule() {
+    let mut prefs = QuietPrefs::load_testing();
+    std::fs::write(
+        prefs.get_preferences_file_path(),
+        "[do_not_disturb]\ndefault = true\n\n[[do_not_disturb.windows]]\nstart = \"09:00\"\nend = \"18:00\"\nvalue = false\n",
+    )
+    .unwrap();
+    prefs.reload().unwrap();
+    assert!(!*prefs.effective_do_not_disturb(at(12, 0)));
+    assert!(*prefs.effective_do_not_disturb(at(20, 0)));
+
+    std::fs::write(
+        prefs.get_preferences_file_path(),
+        "[do_not_disturb]\ndefault = true\n\n[[do_not_disturb.windows]]\nstart = \"25:00\"\nend = \"18:00\"\nvalue = false\n",
+    )
+    .unwrap();
+    assert!(matches!(prefs.reload(), Err(easy_prefs::LoadError::DeserializationError(..))));
+}
+
+#[test]
+fn test_parse_time_of_day() {
+    assert_eq!("07:05".parse::<TimeOfDay>(), Ok(at(7, 5)));
+    assert_eq!(at(7, 5).to_string(), "07:05");
+    assert!("7".parse::<TimeOfDay>().is_err());
+    assert!("12:60".parse::<TimeOfDay>().is_err());
+}
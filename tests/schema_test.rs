@@ -0,0 +1,67 @@
+use easy_prefs::easy_prefs;
+use easy_prefs::schema::{self, SchemaMismatch};
+
+easy_prefs! {
+    struct SchemaV1 {
+        pub theme: String = "light".to_string() => "theme",
+        pub font_size: i32 = 14 => "font_size",
+    },
+    "schema-v1"
+}
+
+easy_prefs! {
+    /// Same schema as `SchemaV1` with different defaults, docs, and spacing.
+    struct SchemaV1Tweaked {
+        /// The color theme.
+        pub theme: String = "dark".to_string() => "theme",
+        pub font_size: i32 = 16 => "font_size",
+    },
+    "schema-v1-tweaked"
+}
+
+easy_prefs! {
+    struct SchemaV2 {
+        pub theme: String = "light".to_string() => "color_theme",
+        pub font_size: i64 = 14 => "font_size",
+    },
+    "schema-v2"
+}
+
+#[test]
+fn test_schema_hash() {
+    assert_eq!(SchemaV1::SCHEMA_HASH, schema::hash(SchemaV1::FIELDS));
+    assert_eq!(SchemaV1::SCHEMA_HASH, SchemaV1Tweaked::SCHEMA_HASH);
+    assert_ne!(SchemaV1::SCHEMA_HASH, SchemaV2::SCHEMA_HASH);
+    assert_ne!(schema::hash(&SchemaV1::FIELDS[..1]), SchemaV1::SCHEMA_HASH);
+}
+
+#[test]
+fn test_describe() {
+    let description = schema::describe(SchemaV1::FIELDS);
+    assert_eq!(
+        description,
+        format!(
+            "schema_hash = {:016x}\ntheme: String (theme)\nfont_size: i32 (font_size)\n",
+            SchemaV1::SCHEMA_HASH
+        )
+    );
+}
+
+#[test]
+fn test_check_recorded() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("prefs.schema");
+
+    schema::check_recorded(&path, SchemaV1::FIELDS).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), schema::describe(SchemaV1::FIELDS));
+    schema::check_recorded(&path, SchemaV1Tweaked::FIELDS).unwrap();
+
+    let err = schema::check_recorded(&path, SchemaV2::FIELDS).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let mismatch = err.get_ref().unwrap().downcast_ref::<SchemaMismatch>().unwrap();
+    assert!(mismatch.removed.contains(&"theme: String (theme)".to_string()));
+    assert!(mismatch.added.contains(&"color_theme: String (theme)".to_string()));
+    assert!(mismatch.added.contains(&"font_size: i64 (font_size)".to_string()));
+    assert!(err.to_string().contains("+ color_theme: String (theme)"));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), schema::describe(SchemaV1::FIELDS));
+}
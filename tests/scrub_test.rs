@@ -0,0 +1,96 @@
+use easy_prefs::easy_prefs;
+use easy_prefs::scrub::scrub;
+use easy_prefs::secrets::REDACTED;
+
+mod common;
+
+easy_prefs! {
+    struct ScrubPrefs {
+        pub token: String = "hunter22".to_string() => "token" (secret),
+        pub pin: i64 = 7 => "pin" (secret, max = 9999),
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "scrub-prefs"
+}
+
+#[test]
+fn test_scrub_secret_values() {
+    common::use_memory_secret_store();
+    let prefs = ScrubPrefs::load_testing();
+
+    let message = "TOML parse error at line 2, column 9\n  |\n2 | token = \"abc\n  |         ^\nexpected `\"`";
+    let scrubbed = scrub(message, ScrubPrefs::FIELDS, None::<&ScrubPrefs>);
+    assert_eq!(scrubbed, format!("TOML parse error at line 2, column 9\n  |\n2 | token = {REDACTED}\n  |         ^\nexpected `\"`"));
+
+    // Current values are replaced anywhere; short ones only after their key.
+    let scrubbed = scrub("sent hunter22 with { pin = 7, theme = \"light\" } to 7 hosts", ScrubPrefs::FIELDS, Some(&prefs));
+    assert_eq!(scrubbed, format!("sent {REDACTED} with {{ pin = {REDACTED}"));
+    assert_eq!(scrub("a_token = 1", ScrubPrefs::FIELDS, Some(&prefs)), "a_token = 1");
+
+    let error = ScrubPrefs::load_testing().save_pin(10000).unwrap_err();
+    assert!(!error.to_string().contains("10000"), "{error}");
+}
+
+#[test]
+fn test_load_panic_is_scrubbed() {
+    common::use_memory_secret_store();
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(tmp.path().join("scrub-prefs.toml"), "theme = \"dark\"\ntoken = \"hunter33\nbroken").unwrap();
+    let dir = tmp.path().to_str().unwrap().to_string();
+
+    let panic = std::panic::catch_unwind(|| ScrubPrefs::load(&dir)).unwrap_err();
+    let message = panic.downcast_ref::<String>().unwrap();
+    assert!(message.contains("Failed to load preferences"));
+    assert!(!message.contains("hunter33"), "{message}");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_home_redaction() {
+    let home = std::env::var("HOME").unwrap();
+    let message = format!("failed to write {home}/.config/app/prefs.toml");
+    assert_eq!(scrub(&message, &[], None::<&ScrubPrefs>), message);
+
+    easy_prefs::scrub::set_home_redaction(true);
+    assert!(easy_prefs::scrub::is_home_redaction_enabled());
+    assert_eq!(scrub(&message, &[], None::<&ScrubPrefs>), "failed to write ~/.config/app/prefs.toml");
+    easy_prefs::scrub::set_home_redaction(false);
+}
+
+easy_prefs! {
+    struct BackupPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub token: String = String::new() => "token" (secret),
+    },
+    "scrub-backup-prefs",
+    backups = 1
+}
+
+#[cfg(unix)]
+#[test]
+fn test_logged_warnings_are_scrubbed() {
+    // The test harness captures stderr, so the warning is read from a child process running
+    // only this test.
+    if std::env::var_os("SCRUB_LOG_CHILD").is_some() {
+        common::use_memory_secret_store();
+        easy_prefs::scrub::set_home_redaction(true);
+        let home = std::env::var("HOME").unwrap();
+        std::fs::write(format!("{home}/scrub-backup-prefs.toml"), "theme = ").unwrap();
+        std::fs::write(format!("{home}/scrub-backup-prefs.toml.bak"), "theme = \"dark\"\ntoken = \"hunter44\n").unwrap();
+        assert!(BackupPrefs::load_with_error(&home).is_err());
+        return;
+    }
+
+    let home = tempfile::tempdir().unwrap();
+    let output = std::process::Command::new(std::env::current_exe().unwrap())
+        .args(["test_logged_warnings_are_scrubbed", "--exact", "--nocapture"])
+        .env("SCRUB_LOG_CHILD", "1")
+        .env("HOME", home.path())
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert!(stderr.contains("Skipping backup ~/scrub-backup-prefs.toml.bak"), "{stderr}");
+    assert!(!stderr.contains(home.path().to_str().unwrap()), "{stderr}");
+    assert!(!stderr.contains("hunter44"), "{stderr}");
+}
@@ -0,0 +1,99 @@
+use easy_prefs::easy_prefs;
+use easy_prefs::secrets;
+
+mod common;
+
+easy_prefs! {
+    struct AccountPrefs {
+        pub username: String = String::new() => "username",
+        pub api_key: String = String::new() => "api_key" (secret),
+        pub pin: i32 = 0 => "pin" (secret),
+    },
+    "account-prefs"
+}
+
+#[test]
+fn test_secret_fields_are_redacted() {
+    common::use_memory_secret_store();
+    let mut prefs = AccountPrefs::load_testing();
+    prefs.save_username("alice".to_string()).unwrap();
+    prefs.save_api_key("sk-live-123".to_string()).unwrap();
+
+    let debug = format!("{:?}", prefs);
+    assert!(debug.contains("alice"));
+    assert!(!debug.contains("sk-live-123"));
+    assert!(debug.contains(secrets::REDACTED));
+
+    let text = prefs.to_string();
+    assert!(text.contains("username = \"alice\""));
+    assert!(!text.contains("sk-live-123"));
+
+    // The getter still returns the real value.
+    assert_eq!(prefs.get_api_key(), "sk-live-123");
+}
+
+#[test]
+fn test_secret_fields_go_to_secret_store() {
+    common::use_memory_secret_store();
+    let mut prefs = AccountPrefs::load_testing();
+    {
+        let mut edit = prefs.edit();
+        edit.set_username("bob".to_string());
+        edit.set_api_key("token-xyz".to_string());
+        edit.set_pin(1234);
+    }
+
+    let contents = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+    assert!(contents.contains("username = \"bob\""));
+    assert!(!contents.contains("api_key"));
+    assert!(!contents.contains("1234"));
+
+    // Reloading pulls the secrets back from the store.
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_api_key(), "token-xyz");
+    assert_eq!(*prefs.get_pin(), 1234);
+}
+
+#[test]
+fn test_plaintext_secret_is_migrated_on_save() {
+    common::use_memory_secret_store();
+    let mut prefs = AccountPrefs::load_testing();
+    let path = prefs.get_preferences_file_path();
+    std::fs::write(&path, "username = \"carol\"\napi_key = \"legacy\"\n").unwrap();
+
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_api_key(), "legacy");
+
+    prefs.save_username("carol2".to_string()).unwrap();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(!contents.contains("legacy"));
+
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_api_key(), "legacy");
+}
+
+#[test]
+fn test_test_instances_keep_secrets_to_themselves() {
+    common::use_memory_secret_store();
+    let mut prefs = AccountPrefs::load_testing();
+    prefs.save_api_key("sk-test".to_string()).unwrap();
+    let contents = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+    assert!(!contents.contains("sk-test"));
+
+    // The installed store never saw the secret.
+    let path = std::path::PathBuf::from(prefs.get_preferences_file_path());
+    let storage = easy_prefs::storage::create_storage(path.parent().unwrap().to_str().unwrap());
+    let key = path.file_name().unwrap().to_str().unwrap();
+    let mut table = toml::Table::new();
+    secrets::inject(&mut table, AccountPrefs::FIELDS, storage.as_ref(), key).unwrap();
+    assert!(table.is_empty());
+
+    // In-memory instances share a location but not their secrets.
+    let mut first = AccountPrefs::load_in_memory();
+    first.save_api_key("sk-first".to_string()).unwrap();
+    let mut second = AccountPrefs::load_in_memory();
+    second.reload().unwrap();
+    assert_eq!(second.get_api_key(), "");
+    first.reload().unwrap();
+    assert_eq!(first.get_api_key(), "sk-first");
+}
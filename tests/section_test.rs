@@ -0,0 +1,69 @@
+use easy_prefs::easy_prefs;
+
+easy_prefs! {
+    struct SectionPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub window: WindowPrefs {
+            pub width: i32 = 800 => "width",
+            pub height: i32 = 600 => "height",
+            pub maximized: bool = false => "maximized",
+        } => "window",
+        pub editor: EditorPrefs {
+            pub font_size: i32 = 14 => "font_size",
+            pub tab_width: u8 = 4 => "tab-width",
+        } => "editor",
+        pub volume: i32 = 5 => "volume",
+    },
+    "section-prefs"
+}
+
+#[test]
+fn test_sections_are_saved_as_tables() {
+    let mut prefs = SectionPrefs::load_testing();
+    assert_eq!(prefs.get_window(), &WindowPrefs::default());
+    assert_eq!(prefs.get_editor().tab_width, 4);
+
+    prefs
+        .save_window(WindowPrefs {
+            width: 1024,
+            ..WindowPrefs::default()
+        })
+        .unwrap();
+    {
+        let mut edit = prefs.edit();
+        let mut editor = edit.get_editor().clone();
+        editor.font_size = 16;
+        edit.set_editor(editor);
+        edit.set_volume(7);
+    }
+
+    let contents = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+    let table: toml::Table = contents.parse().unwrap();
+    assert_eq!(table["theme"].as_str(), Some("light"));
+    assert_eq!(table["volume"].as_integer(), Some(7));
+    assert_eq!(table["window"]["width"].as_integer(), Some(1024));
+    assert_eq!(table["editor"]["font_size"].as_integer(), Some(16));
+    assert_eq!(table["editor"]["tab-width"].as_integer(), Some(4));
+    assert!(contents.contains("[window]"));
+
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_window().width, 1024);
+    assert_eq!(prefs.get_editor().font_size, 16);
+    assert_eq!(*prefs.get_volume(), 7);
+}
+
+#[test]
+fn test_missing_section_keys_use_defaults() {
+    let mut prefs = SectionPrefs::load_testing();
+    std::fs::write(
+        prefs.get_preferences_file_path(),
+        "theme = \"dark\"\n\n[window]\nmaximized = true\n",
+    )
+    .unwrap();
+
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_theme(), "dark");
+    assert!(prefs.get_window().maximized);
+    assert_eq!(prefs.get_window().width, 800);
+    assert_eq!(prefs.get_editor(), &EditorPrefs::default());
+}
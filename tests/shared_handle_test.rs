@@ -0,0 +1,129 @@
+use easy_prefs::easy_prefs;
+use std::collections::HashMap;
+
+easy_prefs! {
+    pub struct SharedPrefs {
+        pub counter: i32 = 0 => "counter",
+        pub name: String = "default".to_string() => "name",
+        pub scores: HashMap<String, i32> = HashMap::new() => "scores",
+    },
+    "shared-prefs"
+}
+
+/// A field type without `Clone`; its handle getter is unavailable but the struct compiles.
+#[derive(Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Opaque {
+    pub level: u8,
+}
+
+easy_prefs! {
+    pub struct OpaquePrefs {
+        pub opaque: Opaque = Opaque::default() => "opaque",
+    },
+    "opaque-shared-prefs"
+}
+
+fn assert_send_sync<T: Clone + Send + Sync + 'static>() {}
+
+#[test]
+fn test_handle_is_shareable() {
+    assert_send_sync::<SharedPrefsHandle>();
+
+    let handle = OpaquePrefs::load_testing().into_shared();
+    handle.save_opaque(Opaque { level: 2 }).unwrap();
+    assert_eq!(handle.read().get_opaque().level, 2);
+}
+
+#[test]
+fn test_readers_and_writers_across_threads() {
+    let handle = SharedPrefs::load_testing().into_shared();
+
+    let writers: Vec<_> = (0..4)
+        .map(|_| {
+            let handle = handle.clone();
+            std::thread::spawn(move || {
+                for _ in 0..25 {
+                    let mut prefs = handle.write();
+                    let next = prefs.get_counter() + 1;
+                    prefs.save_counter(next).unwrap();
+                }
+            })
+        })
+        .collect();
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let handle = handle.clone();
+            std::thread::spawn(move || {
+                for _ in 0..100 {
+                    assert!((0..=100).contains(&handle.get_counter()));
+                    assert_eq!(handle.get_name(), "default");
+                }
+            })
+        })
+        .collect();
+    for thread in writers.into_iter().chain(readers) {
+        thread.join().unwrap();
+    }
+
+    assert_eq!(handle.get_counter(), 100);
+    handle.save_name("shared".to_string()).unwrap();
+    assert_eq!(handle.read().get_name(), "shared");
+
+    {
+        let mut prefs = handle.write();
+        let mut edit = prefs.edit();
+        edit.insert_scores("alice".to_string(), 3);
+    }
+    assert_eq!(handle.get_scores().get("alice"), Some(&3));
+
+    handle.write().reload().unwrap();
+    assert_eq!(handle.get_counter(), 100);
+}
+
+#[test]
+fn test_setters_from_shared_references() {
+    let handle = SharedPrefs::load_testing().into_shared();
+
+    // A callback that only gets `&handle`, as in GUI event handlers.
+    let on_click = |prefs: &SharedPrefsHandle| prefs.update_counter(|counter| *counter += 1).unwrap();
+    let increments: Vec<_> = (0..4)
+        .map(|_| {
+            let handle = handle.clone();
+            std::thread::spawn(move || (0..25).for_each(|_| on_click(&handle)))
+        })
+        .collect();
+    for thread in increments {
+        thread.join().unwrap();
+    }
+    assert_eq!(handle.get_counter(), 100);
+
+    handle.insert_scores("bob".to_string(), 5).unwrap();
+    let renamed = handle
+        .edit(|prefs| {
+            prefs.set_name("edited".to_string());
+            prefs.insert_scores("carol".to_string(), 1);
+            prefs.get_scores().len()
+        })
+        .unwrap();
+    assert_eq!(renamed, 2);
+    assert_eq!(handle.get_name(), "edited");
+
+    handle.reset_name().unwrap();
+    handle.reload().unwrap();
+    assert_eq!(handle.get_name(), "default");
+    assert_eq!(handle.get_scores().len(), 2);
+}
+
+#[test]
+fn test_load_shared() {
+    let dir = format!("/tmp/easy_prefs_shared_test_{}", std::process::id());
+    {
+        let handle = SharedPrefs::load_shared(&dir).unwrap();
+        handle.save_counter(7).unwrap();
+        assert!(format!("{handle:?}").contains("counter: 7"));
+        // The handle owns the instance until the last clone is dropped.
+        assert!(SharedPrefs::load_with_error(&dir).is_err());
+    }
+    assert_eq!(SharedPrefs::load_shared(&dir).unwrap().get_counter(), 7);
+    let _ = std::fs::remove_dir_all(&dir);
+}
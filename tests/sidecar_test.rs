@@ -0,0 +1,100 @@
+use easy_prefs::easy_prefs;
+
+easy_prefs! {
+    struct StablePrefs {
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "sidecar-prefs",
+    compat_sidecar = true
+}
+
+easy_prefs! {
+    struct BetaPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub minimap: bool = false => "minimap",
+        pub tab_width: i64 = 4 => "tab_width",
+    },
+    "sidecar-prefs",
+    compat_sidecar = true
+}
+
+easy_prefs! {
+    struct PlainStablePrefs {
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "sidecar-plain-prefs"
+}
+
+easy_prefs! {
+    struct PlainBetaPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub minimap: bool = false => "minimap",
+    },
+    "sidecar-plain-prefs"
+}
+
+#[test]
+fn test_older_version_keeps_newer_fields() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path().to_str().unwrap();
+
+    let mut beta = BetaPrefs::load_with_error(dir).unwrap();
+    beta.edit().set_minimap(true);
+    beta.save_tab_width(2).unwrap();
+    drop(beta);
+
+    // The stable build rewrites the file without the fields it doesn't know.
+    let mut stable = StablePrefs::load_with_error(dir).unwrap();
+    stable.save_theme("dark".to_string()).unwrap();
+    let document = std::fs::read_to_string(stable.get_preferences_file_path()).unwrap();
+    assert!(!document.contains("minimap"));
+    let sidecar_path = format!("{}.compat", stable.get_preferences_file_path());
+    let sidecar = std::fs::read_to_string(&sidecar_path).unwrap();
+    assert!(sidecar.contains(&format!("[{:016x}]", StablePrefs::SCHEMA_HASH)));
+    assert!(sidecar.contains("minimap = true"));
+    drop(stable);
+
+    // The beta build gets its settings back, along with the stable build's change.
+    let mut beta = BetaPrefs::load_with_error(dir).unwrap();
+    assert!(*beta.get_minimap());
+    assert_eq!(*beta.get_tab_width(), 2);
+    assert_eq!(beta.get_theme(), "dark");
+
+    // Once the beta build saves them in the document, the sidecar is cleared.
+    beta.save_theme("sepia".to_string()).unwrap();
+    let document = std::fs::read_to_string(beta.get_preferences_file_path()).unwrap();
+    assert!(document.contains("minimap = true"));
+    assert!(std::fs::read_to_string(&sidecar_path).unwrap().is_empty());
+}
+
+#[test]
+fn test_document_wins_over_sidecar() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path().to_str().unwrap();
+
+    let mut stable = StablePrefs::load_with_error(dir).unwrap();
+    let path = stable.get_preferences_file_path();
+    std::fs::write(&path, "theme = \"dark\"\nminimap = true\n").unwrap();
+    stable.reload().unwrap();
+    stable.save().unwrap();
+    drop(stable);
+
+    // A newer save of the field in the document takes precedence.
+    std::fs::write(&path, "theme = \"dark\"\nminimap = false\n").unwrap();
+    let beta = BetaPrefs::load_with_error(dir).unwrap();
+    assert!(!*beta.get_minimap());
+}
+
+#[test]
+fn test_without_sidecar_fields_are_dropped() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path().to_str().unwrap();
+
+    PlainBetaPrefs::load_with_error(dir).unwrap().save_minimap(true).unwrap();
+    let mut stable = PlainStablePrefs::load_with_error(dir).unwrap();
+    stable.save_theme("dark".to_string()).unwrap();
+    assert!(std::fs::read_to_string(format!("{}.compat", stable.get_preferences_file_path())).is_err());
+    drop(stable);
+
+    assert!(!*PlainBetaPrefs::load_with_error(dir).unwrap().get_minimap());
+}
@@ -0,0 +1,41 @@
+use easy_prefs::{easy_prefs, SaveError};
+
+mod common;
+
+easy_prefs! {
+    pub struct SnapshotPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub font_size: i32 = 14 => "font_size" (range = 8..=72),
+        pub token: String = String::new() => "token" (secret),
+    },
+    "snapshot-prefs"
+}
+
+#[test]
+fn test_snapshot_and_apply() {
+    common::use_memory_secret_store();
+    let mut prefs = SnapshotPrefs::load_testing();
+    assert_eq!(SnapshotPrefsData::default(), prefs.snapshot());
+
+    let mut data = prefs.snapshot();
+    data.theme = "dark".to_string();
+    data.token = "hunter22".to_string();
+    let data = std::thread::spawn(move || data.clone()).join().unwrap();
+    assert!(!format!("{data:?}").contains("hunter22"));
+
+    // Serialized with the saved names, like the stored file.
+    let serialized = easy_prefs::toml::to_string(&data).unwrap();
+    assert!(serialized.contains("font_size = 14"), "{serialized}");
+    let data: SnapshotPrefsData = easy_prefs::toml::from_str(&serialized).unwrap();
+
+    prefs.apply(data.clone()).unwrap();
+    assert_eq!(prefs.get_theme(), "dark");
+    assert_eq!(prefs.get_token(), "hunter22");
+    prefs.reload().unwrap();
+    assert_eq!(prefs.snapshot(), data);
+
+    // Invalid values are rejected without touching the in-memory values.
+    let invalid = SnapshotPrefsData { theme: "blue".to_string(), font_size: 100, ..data };
+    assert!(matches!(prefs.apply(invalid), Err(SaveError::ValidationError(_))));
+    assert_eq!(prefs.get_theme(), "dark");
+}
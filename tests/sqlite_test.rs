@@ -0,0 +1,62 @@
+#![cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+
+use easy_prefs::easy_prefs;
+use easy_prefs::storage::sqlite::SqliteStorage;
+use easy_prefs::storage::{Durability, Storage};
+
+easy_prefs! {
+    struct EditorPrefs {
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "editor-prefs",
+    backups = 1
+}
+
+easy_prefs! {
+    struct SyncPrefs {
+        pub interval: i64 = 60 => "interval",
+    },
+    "sync-prefs",
+    durability = Durability::Fsync
+}
+
+#[test]
+fn test_structs_share_one_database() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("data").join("app.db");
+    let path = path.to_str().unwrap();
+
+    let mut editor = EditorPrefs::load_with_storage(Box::new(SqliteStorage::open(path).unwrap())).unwrap();
+    let mut sync = SyncPrefs::load_with_storage(Box::new(SqliteStorage::open(path).unwrap())).unwrap();
+    editor.save_theme("dark".to_string()).unwrap();
+    editor.save_theme("sepia".to_string()).unwrap();
+    sync.save_interval(30).unwrap();
+    assert_eq!(editor.get_preferences_file_path(), format!("{path}#editor-prefs.toml"));
+    drop((editor, sync));
+
+    let names: Vec<_> = std::fs::read_dir(tmp.path().join("data")).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+    assert_eq!(names, ["app.db"], "one data file, no temporary files");
+
+    let storage = SqliteStorage::open(path).unwrap();
+    assert!(storage.read("editor-prefs.toml.bak").unwrap().unwrap().contains("theme = \"dark\""));
+    assert_eq!(storage.read("missing.toml").unwrap(), None);
+    let editor = EditorPrefs::load_with_storage(Box::new(storage)).unwrap();
+    let sync = SyncPrefs::load_with_storage(Box::new(SqliteStorage::open(path).unwrap())).unwrap();
+    assert_eq!((editor.get_theme().as_str(), *sync.get_interval()), ("sepia", 30));
+}
+
+#[test]
+fn test_storage_errors_and_memory_database() {
+    let mut storage = SqliteStorage::open(":memory:").unwrap();
+    assert!(!storage.is_persistent());
+    storage.set_durability(Durability::Fast);
+    storage.write("a.toml", "a = 'it''s'\n").unwrap();
+    storage.write("a.toml", "a = 2\n").unwrap();
+    assert_eq!(storage.read("a.toml").unwrap().as_deref(), Some("a = 2\n"));
+
+    let tmp = tempfile::tempdir().unwrap();
+    let not_a_db = tmp.path().join("prefs.toml");
+    std::fs::write(&not_a_db, "theme = \"dark\"\n".repeat(100)).unwrap();
+    let e = SqliteStorage::open(not_a_db.to_str().unwrap()).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData, "{e}");
+}
@@ -75,3 +75,132 @@ mod native_storage_tests {
         let _ = fs::remove_dir_all(&test_dir);
     }
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+mod environment_report_tests {
+    use easy_prefs::easy_prefs;
+    use easy_prefs::storage::{create_storage, EnvironmentQuirk};
+    use std::fs;
+
+    easy_prefs! {
+        struct ReportPrefs {
+            pub value: i32 = 0 => "value",
+        },
+        "report-prefs"
+    }
+
+    easy_prefs! {
+        struct LazyDirPrefs {
+            pub value: i32 = 0 => "value",
+        },
+        "lazy-dir-prefs"
+    }
+
+    #[test]
+    fn test_report_for_missing_directory() {
+        let test_dir = format!("/tmp/easy_prefs_report_missing_{}", std::process::id());
+        let report = create_storage(&test_dir).environment_report("prefs.toml");
+
+        assert_eq!(report.backend, "file");
+        assert!(report.location.ends_with("prefs.toml"));
+        assert!(report.quirks.contains(&EnvironmentQuirk::DirectoryMissing));
+    }
+
+    #[test]
+    fn test_report_when_directory_is_a_file() {
+        let test_file = format!("/tmp/easy_prefs_report_file_{}", std::process::id());
+        fs::write(&test_file, "not a directory").unwrap();
+
+        let report = create_storage(&test_file).environment_report("prefs.toml");
+        assert!(report.quirks.contains(&EnvironmentQuirk::NotADirectory));
+
+        let _ = fs::remove_file(&test_file);
+    }
+
+    #[test]
+    fn test_directory_is_a_file() {
+        let test_file = format!("/tmp/easy_prefs_not_a_dir_{}", std::process::id());
+        fs::write(&test_file, "not a directory").unwrap();
+
+        let storage = create_storage(&format!("{test_file}/nested"));
+        let error = storage.write("prefs.toml", "value = 1").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotADirectory);
+        assert!(error.to_string().contains(&test_file));
+
+        match ReportPrefs::load_with_error(&test_file) {
+            Err(easy_prefs::LoadError::StorageError(e)) => {
+                assert_eq!(e.kind(), std::io::ErrorKind::NotADirectory)
+            }
+            other => panic!("expected NotADirectory, got {:?}", other.map(|_| ())),
+        }
+
+        let _ = fs::remove_file(&test_file);
+    }
+
+    #[test]
+    fn test_directory_created_on_first_write() {
+        let test_dir = format!("/tmp/easy_prefs_lazy_dir_{}", std::process::id());
+        let mut prefs = LazyDirPrefs::load_with_error(&test_dir).unwrap();
+        assert!(!std::path::Path::new(&test_dir).exists());
+
+        prefs.save_value(3).unwrap();
+        assert!(std::path::Path::new(&test_dir).is_dir());
+
+        drop(prefs);
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_generated_environment_report() {
+        let prefs = ReportPrefs::load_testing();
+        let report = prefs.environment_report();
+
+        assert_eq!(report.location, prefs.get_preferences_file_path());
+        assert!(!report.quirks.contains(&EnvironmentQuirk::ReadOnly));
+
+        let text = report.to_string();
+        assert!(text.contains("backend: file"));
+        assert!(text.contains(&report.location));
+    }
+}
+
+mod memory_storage_tests {
+    use easy_prefs::easy_prefs;
+    use easy_prefs::storage::{EnvironmentQuirk, MemoryStorage, Storage};
+
+    easy_prefs! {
+        struct MemoryPrefs {
+            pub value: i32 = 0 => "value",
+        },
+        "memory-prefs"
+    }
+
+    #[test]
+    fn test_memory_storage() {
+        let storage = MemoryStorage::new();
+        assert_eq!(storage.read("prefs.toml").unwrap(), None);
+        storage.write("prefs.toml", "value = 1").unwrap();
+        assert_eq!(storage.read("prefs.toml").unwrap().as_deref(), Some("value = 1"));
+        assert!(!storage.is_persistent());
+        assert!(storage.environment_report("prefs.toml").quirks.is_empty());
+
+        let fallback = MemoryStorage::unavailable("localStorage is not writable".to_string());
+        assert_eq!(
+            fallback.environment_report("prefs.toml").quirks,
+            [EnvironmentQuirk::StorageUnavailable("localStorage is not writable".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_prefs_in_memory() {
+        let mut prefs = MemoryPrefs::load_with_storage(Box::new(MemoryStorage::new())).unwrap();
+        assert!(!prefs.is_persistent());
+        prefs.save_value(5).unwrap();
+        prefs.reload().unwrap();
+        assert_eq!(*prefs.get_value(), 5);
+        drop(prefs);
+
+        assert!(MemoryPrefs::load_testing().is_persistent());
+        assert!(!MemoryPrefs::default().is_persistent());
+    }
+}
@@ -0,0 +1,79 @@
+use easy_prefs::{easy_prefs, LoadError};
+
+mod common;
+
+easy_prefs! {
+    struct StrictPrefs {
+        pub volume: i32 = 5 => "volume",
+        pub token: String = String::new() => "token" (secret),
+        pub window: StrictWindow {
+            pub width: i32 = 800 => "width",
+        } => "window",
+    },
+    "strict-prefs",
+    strict = true
+}
+
+easy_prefs! {
+    struct LenientStrictPrefs {
+        pub dark_mode: bool = false => "dark_mode" (lenient_types),
+    },
+    "lenient-strict-prefs",
+    strict = true,
+    lenient_keys = true
+}
+
+easy_prefs! {
+    struct RelaxedPrefs {
+        pub volume: i32 = 5 => "volume",
+    },
+    "relaxed-prefs"
+}
+
+fn load_error<T: std::fmt::Debug>(result: Result<T, LoadError>) -> String {
+    match result {
+        Err(LoadError::ValidationError(e)) => {
+            assert_eq!(e.key, "");
+            e.reason
+        }
+        other => panic!("Expected ValidationError, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_lists_every_problem() {
+    common::use_memory_secret_store();
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+    let document = "volume = 1.5\ncolour = \"red\"\ntoken = 42\n\n[window]\nwidth = \"wide\"\n";
+    std::fs::write(tmp.path().join("strict-prefs.toml"), document).unwrap();
+
+    let reason = load_error(StrictPrefs::load_with_error(dir));
+    let lines: Vec<&str> = reason.lines().collect();
+    assert_eq!(lines[0], "4 problems in the stored document:");
+    assert_eq!(lines[1], "line 1, column 10: 'volume': invalid type: floating point `1.5`, expected i32");
+    assert_eq!(lines[2], "line 2, column 1: unknown key 'colour'");
+    assert_eq!(lines[3], "line 3, column 9: 'token': value has the wrong type");
+    assert!(lines[4].starts_with("line 5, column 1: 'window': invalid type: string \"wide\""), "{}", lines[4]);
+
+    // A valid document loads.
+    std::fs::write(tmp.path().join("strict-prefs.toml"), "volume = 7\n[window]\nwidth = 1024\n").unwrap();
+    let prefs = StrictPrefs::load_with_error(dir).unwrap();
+    assert_eq!(*prefs.get_volume(), 7);
+}
+
+#[test]
+fn test_lenient_options_and_default_mode() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+
+    std::fs::write(tmp.path().join("lenient-strict-prefs.toml"), "Dark-Mode = \"yes\"\n").unwrap();
+    assert!(*LenientStrictPrefs::load_with_error(dir).unwrap().get_dark_mode());
+    std::fs::write(tmp.path().join("lenient-strict-prefs.toml"), "darkmode = true\nextra = 1\n").unwrap();
+    let reason = load_error(LenientStrictPrefs::load_with_error(dir));
+    assert_eq!(reason, "1 problem in the stored document:\nline 2, column 1: unknown key 'extra'");
+
+    // Without `strict`, unknown keys are ignored and only the first type error is reported.
+    std::fs::write(tmp.path().join("relaxed-prefs.toml"), "volume = 3\ncolour = \"red\"\n").unwrap();
+    assert_eq!(*RelaxedPrefs::load_with_error(dir).unwrap().get_volume(), 3);
+}
@@ -0,0 +1,186 @@
+use easy_prefs::easy_prefs;
+use easy_prefs::sync::{FieldConflict, Resolution, SyncProvider};
+use easy_prefs::LoadError;
+use std::sync::{Arc, Mutex};
+
+mod common;
+
+easy_prefs! {
+    struct SyncPrefs {
+        pub theme: String = "light".to_string() => "theme" (choices = ["light", "dark", "sepia"]),
+        pub font_size: i64 = 14 => "font_size",
+        pub nickname: Option<String> = None => "nickname",
+        pub token: String = String::new() => "token" (secret),
+        pub window_x: i64 = 0 => "window_x" (device),
+    },
+    "sync-prefs"
+}
+
+/// A remote copy shared by several instances, standing in for a cloud service.
+#[derive(Clone, Default)]
+struct Remote(Arc<Mutex<Option<(String, u64)>>>);
+
+impl Remote {
+    fn document(&self) -> String {
+        self.0.lock().unwrap().clone().unwrap().0
+    }
+
+    fn version(&self) -> u64 {
+        self.0.lock().unwrap().as_ref().map_or(0, |(_, version)| *version)
+    }
+}
+
+impl SyncProvider for Remote {
+    fn pull(&mut self) -> Result<Option<(String, u64)>, std::io::Error> {
+        Ok(self.0.lock().unwrap().clone())
+    }
+
+    fn push(&mut self, serialized: &str, version: u64) -> Result<(), std::io::Error> {
+        let mut remote = self.0.lock().unwrap();
+        let current = remote.as_ref().map_or(0, |(_, version)| *version);
+        if version != current + 1 {
+            return Err(std::io::Error::other("remote copy changed"));
+        }
+        *remote = Some((serialized.to_string(), version));
+        Ok(())
+    }
+}
+
+#[test]
+fn test_first_sync_pushes_and_fresh_install_adopts() {
+    common::use_memory_secret_store();
+    let mut remote = Remote::default();
+    let mut desktop = SyncPrefs::load_testing();
+    desktop.save_theme("dark".to_string()).unwrap();
+    desktop.save_token("secret-token".to_string()).unwrap();
+    desktop.save_window_x(300).unwrap();
+
+    let report = desktop.sync(&mut remote, Resolution::MergeFields).unwrap();
+    assert!(report.pushed);
+    assert_eq!(report.version, 1);
+    let document = remote.document();
+    assert!(document.contains("theme = \"dark\""));
+    assert!(!document.contains("secret-token"), "secret fields stay on the device");
+    assert!(!document.contains("window_x"), "device fields stay on the device");
+
+    let mut phone = SyncPrefs::load_testing();
+    let report = phone.sync(&mut remote, Resolution::MergeFields).unwrap();
+    assert_eq!(report.pulled, ["theme"]);
+    assert!(!report.pushed);
+    assert_eq!(phone.get_theme(), "dark");
+    assert_eq!(*phone.get_window_x(), 0);
+    phone.reload().unwrap();
+    assert_eq!(phone.get_theme(), "dark", "pulled values are saved");
+
+    // Nothing changed since: syncing again is a no-op.
+    let report = phone.sync(&mut remote, Resolution::MergeFields).unwrap();
+    assert_eq!(report, easy_prefs::sync::SyncReport { version: 1, ..Default::default() });
+}
+
+#[test]
+fn test_merge_keeps_changes_of_both_sides() {
+    common::use_memory_secret_store();
+    let mut remote = Remote::default();
+    let mut desktop = SyncPrefs::load_testing();
+    let mut phone = SyncPrefs::load_testing();
+    desktop.sync(&mut remote, Resolution::MergeFields).unwrap();
+    phone.sync(&mut remote, Resolution::MergeFields).unwrap();
+
+    desktop.save_font_size(18).unwrap();
+    desktop.save_nickname(Some("ada".to_string())).unwrap();
+    desktop.sync(&mut remote, Resolution::MergeFields).unwrap();
+    phone.save_theme("sepia".to_string()).unwrap();
+    let report = phone.sync(&mut remote, Resolution::MergeFields).unwrap();
+    assert_eq!(report.pulled, ["font_size", "nickname"]);
+    assert!(report.conflicts.is_empty());
+    assert!(report.pushed);
+    assert_eq!(report.version, 3);
+    assert_eq!((phone.get_theme().as_str(), *phone.get_font_size()), ("sepia", 18));
+
+    // Clearing an optional value syncs as an absent key.
+    phone.save_nickname(None).unwrap();
+    phone.sync(&mut remote, Resolution::MergeFields).unwrap();
+    let report = desktop.sync(&mut remote, Resolution::MergeFields).unwrap();
+    assert_eq!(report.pulled, ["theme", "nickname"]);
+    assert_eq!(desktop.get_nickname(), &None);
+    assert_eq!(remote.version(), 4);
+}
+
+#[test]
+fn test_conflict_resolutions() {
+    fn pick_remote(conflict: &FieldConflict) -> Option<toml::Value> {
+        assert_eq!(conflict.field, "theme");
+        assert_eq!(conflict.base, Some(&"light".into()));
+        conflict.remote.cloned()
+    }
+
+    for (resolution, theme, font_size) in [
+        (Resolution::MergeFields, "dark", 20),
+        (Resolution::MergeFieldsWith(pick_remote), "sepia", 20),
+        (Resolution::LastWriteWins, "dark", 14),
+    ] {
+        common::use_memory_secret_store();
+        let mut remote = Remote::default();
+        let mut desktop = SyncPrefs::load_testing();
+        let mut phone = SyncPrefs::load_testing();
+        desktop.sync(&mut remote, resolution).unwrap();
+        phone.sync(&mut remote, resolution).unwrap();
+
+        phone.save_theme("sepia".to_string()).unwrap();
+        phone.save_font_size(20).unwrap();
+        phone.sync(&mut remote, resolution).unwrap();
+        desktop.save_theme("dark".to_string()).unwrap();
+        let report = desktop.sync(&mut remote, resolution).unwrap();
+        assert_eq!(report.conflicts, ["theme"], "{resolution:?}");
+        assert_eq!(desktop.get_theme(), theme, "{resolution:?}");
+        assert_eq!(*desktop.get_font_size(), font_size, "{resolution:?}");
+        assert!(remote.document().contains(&format!("theme = \"{theme}\"")), "{resolution:?}");
+    }
+}
+
+#[test]
+fn test_invalid_remote_copy_changes_nothing() {
+    common::use_memory_secret_store();
+    let mut remote = Remote(Arc::new(Mutex::new(Some(("theme = \"neon\"\n".to_string(), 7)))));
+    let mut prefs = SyncPrefs::load_testing();
+    let result = prefs.sync(&mut remote, Resolution::MergeFields);
+    assert!(matches!(result, Err(LoadError::ValidationError(_))));
+    assert_eq!(prefs.get_theme(), "light");
+
+    *remote.0.lock().unwrap() = Some(("theme = ".to_string(), 8));
+    let result = prefs.sync(&mut remote, Resolution::MergeFields);
+    assert!(matches!(result, Err(LoadError::DeserializationError(..))));
+    assert_eq!(remote.version(), 8);
+}
+
+#[test]
+fn test_rejected_push_can_be_retried() {
+    common::use_memory_secret_store();
+    let mut remote = Remote::default();
+    let mut desktop = SyncPrefs::load_testing();
+    desktop.sync(&mut remote, Resolution::MergeFields).unwrap();
+
+    /// Lets another device push between our pull and push.
+    struct Racing(Remote);
+
+    impl SyncProvider for Racing {
+        fn pull(&mut self) -> Result<Option<(String, u64)>, std::io::Error> {
+            let pulled = self.0.pull()?;
+            let (document, version) = pulled.clone().unwrap();
+            self.0.push(&document.replace("font_size = 14", "font_size = 16"), version + 1)?;
+            Ok(pulled)
+        }
+
+        fn push(&mut self, serialized: &str, version: u64) -> Result<(), std::io::Error> {
+            self.0.push(serialized, version)
+        }
+    }
+
+    desktop.save_theme("dark".to_string()).unwrap();
+    let result = desktop.sync(&mut Racing(remote.clone()), Resolution::MergeFields);
+    assert!(matches!(result, Err(LoadError::StorageError(_))));
+    let report = desktop.sync(&mut remote, Resolution::MergeFields).unwrap();
+    assert_eq!(report.pulled, ["font_size"]);
+    assert_eq!(report.version, 3);
+    assert_eq!((desktop.get_theme().as_str(), *desktop.get_font_size()), ("dark", 16));
+}
@@ -0,0 +1,153 @@
+use easy_prefs::easy_prefs;
+use std::time::Duration;
+
+mod common;
+
+easy_prefs! {
+    struct SnoozePrefs {
+        pub notifications: bool = true => "notifications",
+        pub nickname: Option<String> = None => "nickname",
+        pub volume: i32 = 50 => "volume",
+    },
+    "temporary-prefs"
+}
+
+#[test]
+fn test_override_is_saved_with_expiry() {
+    let mut prefs = SnoozePrefs::load_testing();
+    prefs
+        .set_notifications_temporarily(false, Duration::from_secs(3600))
+        .unwrap();
+    assert!(!prefs.get_notifications());
+    assert!(prefs.get_notifications_expiry().is_some());
+    assert!(prefs.get_volume_expiry().is_none());
+
+    let contents = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+    assert!(contents.contains("notifications = false"));
+    assert!(contents.contains("[_temporary.notifications]"));
+    assert!(contents.contains("previous = true"));
+
+    prefs.reload().unwrap();
+    assert!(!prefs.get_notifications());
+    assert!(prefs.get_notifications_expiry().is_some());
+
+    // A second override keeps the value from before the first one.
+    prefs
+        .set_notifications_temporarily(false, Duration::from_secs(7200))
+        .unwrap();
+    let contents = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+    assert!(contents.contains("previous = true"));
+}
+
+#[test]
+fn test_override_reverts_after_expiry() {
+    let mut prefs = SnoozePrefs::load_testing();
+    prefs.save_volume(30).unwrap();
+    prefs.set_volume_temporarily(90, Duration::from_millis(1100)).unwrap();
+    prefs
+        .set_nickname_temporarily(Some("guest".to_string()), Duration::from_millis(1100))
+        .unwrap();
+    assert_eq!(*prefs.get_volume(), 90);
+    assert_eq!(prefs.get_nickname().as_deref(), Some("guest"));
+
+    std::thread::sleep(Duration::from_millis(2100));
+
+    // Getters revert right away, loading reverts the stored values.
+    assert_eq!(*prefs.get_volume(), 30);
+    assert_eq!(*prefs.get_nickname(), None);
+    assert!(prefs.get_volume_expiry().is_none());
+    prefs.reload().unwrap();
+    assert_eq!(*prefs.get_volume(), 30);
+    assert_eq!(*prefs.get_nickname(), None);
+
+    prefs.save_notifications(false).unwrap();
+    let contents = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+    assert!(contents.contains("volume = 30"));
+    assert!(!contents.contains("_temporary"));
+    assert!(!contents.contains("nickname"));
+}
+
+#[test]
+fn test_saving_cancels_override() {
+    let mut prefs = SnoozePrefs::load_testing();
+    prefs.set_volume_temporarily(90, Duration::from_secs(3600)).unwrap();
+    prefs.save_volume(90).unwrap();
+    assert!(prefs.get_volume_expiry().is_none());
+
+    prefs.set_volume_temporarily(10, Duration::from_secs(3600)).unwrap();
+    prefs.edit().set_volume(20);
+    assert!(prefs.get_volume_expiry().is_none());
+
+    prefs.reload().unwrap();
+    assert_eq!(*prefs.get_volume(), 20);
+    assert!(prefs.get_volume_expiry().is_none());
+}
+
+#[test]
+fn test_handle_and_guard_see_expiry() {
+    let mut prefs = SnoozePrefs::load_testing();
+    prefs.save_volume(30).unwrap();
+    prefs.set_volume_temporarily(90, Duration::from_millis(1100)).unwrap();
+    let handle = prefs.into_shared();
+    assert_eq!(handle.get_volume(), 90);
+
+    {
+        let mut prefs = handle.write();
+        let guard = prefs.edit();
+        assert_eq!(*guard.get_volume(), 90);
+        // The override expires while the guard is held.
+        std::thread::sleep(Duration::from_millis(2100));
+        assert_eq!(*guard.get_volume(), 30);
+        assert_eq!(guard.get_volume_copied(), 30);
+    }
+    assert_eq!(handle.get_volume(), 30);
+}
+
+easy_prefs! {
+    struct SecretSnoozePrefs {
+        pub token: String = "s3cr3t-token".to_string() => "token" (secret),
+    },
+    "temporary-secret-prefs"
+}
+
+#[test]
+fn test_secret_fields_are_not_overridden() {
+    common::use_memory_secret_store();
+    let mut prefs = SecretSnoozePrefs::load_testing();
+    let result = prefs.set_token_temporarily("guest".to_string(), Duration::from_secs(3600));
+    assert!(matches!(result, Err(easy_prefs::SaveError::ValidationError(e)) if e.key == "token"));
+    assert_eq!(prefs.get_token(), "s3cr3t-token");
+    assert!(prefs.get_token_expiry().is_none());
+
+    prefs.save().unwrap();
+    let contents = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+    assert!(!contents.contains("_temporary"));
+    assert!(!contents.contains("s3cr3t-token"));
+}
+
+#[test]
+fn test_cancel_and_rollback_keep_overrides() {
+    let mut prefs = SnoozePrefs::load_testing();
+    prefs.save_volume(30).unwrap();
+    prefs.set_volume_temporarily(90, Duration::from_secs(3600)).unwrap();
+
+    let mut guard = prefs.edit();
+    guard.set_volume(40);
+    guard.cancel();
+    assert_eq!(*prefs.get_volume(), 90);
+    assert!(prefs.get_volume_expiry().is_some());
+
+    let result = prefs.transaction(|guard| {
+        guard.set_volume(40);
+        Err("aborted")
+    });
+    assert!(result.is_err());
+    assert_eq!(*prefs.get_volume(), 90);
+    assert!(prefs.get_volume_expiry().is_some());
+
+    // The restored override still reverts to the value from before it.
+    prefs.reload().unwrap();
+    assert_eq!(*prefs.get_volume(), 90);
+    let contents = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+    assert!(contents.contains("previous = 30"), "{contents}");
+}
@@ -0,0 +1,94 @@
+use easy_prefs::easy_prefs;
+use easy_prefs::storage::{MemoryStorage, Storage};
+use easy_prefs::transaction::TxError;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+easy_prefs! {
+    struct AccountPrefs {
+        pub username: String = String::new() => "username",
+        pub server: String = "example.com".to_string() => "server",
+    },
+    "account-prefs"
+}
+
+/// Storage whose writes fail while `offline` is set.
+#[derive(Debug, Clone, Default)]
+struct FlakyStorage {
+    inner: Arc<MemoryStorage>,
+    offline: Arc<AtomicBool>,
+}
+
+impl Storage for FlakyStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+        self.inner.read(key)
+    }
+
+    fn write(&self, key: &str, data: &str) -> Result<(), std::io::Error> {
+        if self.offline.load(Ordering::SeqCst) {
+            return Err(std::io::Error::other("disk unavailable"));
+        }
+        self.inner.write(key, data)
+    }
+
+    fn get_path(&self, key: &str) -> String {
+        format!("flaky::{key}")
+    }
+}
+
+#[test]
+fn test_transaction_commits() {
+    let mut prefs = AccountPrefs::load_in_memory();
+    prefs
+        .transaction(|guard| {
+            guard.set_username("alice".to_string());
+            guard.set_server("corp.example.com".to_string());
+            Ok::<_, String>(())
+        })
+        .unwrap();
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_username(), "alice");
+    assert_eq!(prefs.get_server(), "corp.example.com");
+}
+
+#[test]
+fn test_aborted_transaction_rolls_back() {
+    let mut prefs = AccountPrefs::load_in_memory();
+    let result = prefs.transaction(|guard| {
+        guard.set_username("alice".to_string());
+        Err("server unreachable")
+    });
+    assert!(matches!(result, Err(TxError::Aborted("server unreachable"))));
+    assert_eq!(prefs.get_username(), "");
+}
+
+#[test]
+fn test_failed_save_rolls_back() {
+    let storage = FlakyStorage::default();
+    let mut prefs = AccountPrefs::load_with_storage(Box::new(storage.clone())).unwrap();
+    storage.offline.store(true, Ordering::SeqCst);
+
+    let result = prefs.transaction(|guard| {
+        guard.set_username("alice".to_string());
+        Ok::<_, String>(())
+    });
+    let error = result.unwrap_err();
+    assert!(matches!(error, TxError::Save(_)));
+    assert_eq!(error.to_string(), "transaction not saved: storage error: disk unavailable");
+    assert_eq!(prefs.get_username(), "");
+}
+
+#[test]
+fn test_panicking_transaction_rolls_back() {
+    let storage = FlakyStorage::default();
+    let mut prefs = AccountPrefs::load_with_storage(Box::new(storage.clone())).unwrap();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let _ = prefs.transaction(|guard| -> Result<(), String> {
+            guard.set_username("alice".to_string());
+            panic!("bug in the closure");
+        });
+    }));
+    assert!(result.is_err());
+    assert_eq!(prefs.get_username(), "");
+    assert!(storage.read("account-prefs.toml").unwrap().is_none());
+}
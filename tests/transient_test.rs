@@ -0,0 +1,41 @@
+use easy_prefs::easy_prefs;
+
+easy_prefs! {
+    pub struct TransientPrefs {
+        pub block_list: Vec<String> = Vec::new() => "block_list",
+        pub block_list_len: i64 = -1 => "block_list_len" (transient),
+        pub session: String = String::new() => "session" (transient),
+    },
+    "transient-prefs",
+    strict = true
+}
+
+#[test]
+fn test_transient_fields_are_not_stored() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+    {
+        let mut prefs = TransientPrefs::load_with_error(dir).unwrap();
+        prefs.save_block_list(vec!["ads.example.com".to_string()]).unwrap();
+        prefs.save_block_list_len(1).unwrap();
+        prefs.edit().set_session("abc".to_string());
+        assert_eq!(*prefs.get_block_list_len(), 1);
+        assert_eq!(prefs.get_session(), "abc");
+
+        let stored = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+        assert!(stored.contains("block_list = "), "{stored}");
+        assert!(!stored.contains("block_list_len") && !stored.contains("session"), "{stored}");
+        assert_eq!(prefs.export_toml().unwrap(), "block_list = [\"ads.example.com\"]\n");
+
+        prefs.reload().unwrap();
+        assert_eq!(*prefs.get_block_list_len(), -1);
+        assert_eq!(prefs.get_block_list(), &["ads.example.com"]);
+    }
+
+    // Stored values of transient fields are ignored, also by `strict`.
+    let path = tmp.path().join("transient-prefs.toml");
+    std::fs::write(&path, "block_list = []\nblock_list_len = 7\nsession = \"old\"\n").unwrap();
+    let prefs = TransientPrefs::load_with_error(dir).unwrap();
+    assert_eq!(*prefs.get_block_list_len(), -1);
+    assert_eq!(prefs.get_session(), "");
+}
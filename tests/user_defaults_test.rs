@@ -0,0 +1,37 @@
+#![cfg(all(target_vendor = "apple", feature = "user-defaults"))]
+
+use easy_prefs::easy_prefs;
+use easy_prefs::storage::apple::UserDefaultsStorage;
+use easy_prefs::storage::Storage;
+
+easy_prefs! {
+    struct DefaultsPrefs {
+        pub enabled: bool = true => "enabled",
+        pub count: i32 = 0 => "count",
+    },
+    "defaults-prefs"
+}
+
+const SUITE: &str = "com.everaccountable.easy-prefs-test";
+
+#[test]
+fn test_user_defaults_round_trip() {
+    let storage = UserDefaultsStorage::suite(SUITE);
+    assert_eq!(storage.backend_name(), "user-defaults");
+    assert_eq!(
+        storage.get_path("defaults-prefs.toml"),
+        format!("defaults::{SUITE}::defaults-prefs.toml")
+    );
+    storage.write("round-trip.toml", "count = 1\n").unwrap();
+    assert_eq!(storage.read("round-trip.toml").unwrap().as_deref(), Some("count = 1\n"));
+    assert_eq!(storage.read("never-written.toml").unwrap(), None);
+
+    let mut prefs = DefaultsPrefs::load_with_storage(Box::new(UserDefaultsStorage::suite(SUITE))).unwrap();
+    prefs.save_count(4).unwrap();
+    prefs.save_enabled(false).unwrap();
+    drop(prefs);
+
+    let prefs = DefaultsPrefs::load_with_storage(Box::new(UserDefaultsStorage::suite(SUITE))).unwrap();
+    assert_eq!(*prefs.get_count(), 4);
+    assert!(!*prefs.get_enabled());
+}
@@ -0,0 +1,244 @@
+use easy_prefs::easy_prefs;
+use easy_prefs::validate::{OnInvalid, ValidationError};
+use easy_prefs::{LoadError, SaveError};
+
+easy_prefs! {
+    struct ChoicePrefs {
+        pub theme: String = "system".to_string() => "theme" (choices = ["light", "dark", "system"]),
+        pub label: String = String::new() => "label",
+    },
+    "choice-prefs"
+}
+
+#[test]
+fn test_choices_metadata() {
+    let theme = &ChoicePrefs::FIELDS[0];
+    assert_eq!(theme.options.choices, Some(&["light", "dark", "system"][..]));
+    assert_eq!(ChoicePrefs::FIELDS[1].options.choices, None);
+}
+
+#[test]
+fn test_choices_enforced_on_set() {
+    let mut prefs = ChoicePrefs::load_testing();
+
+    prefs.save_theme("dark".to_string()).unwrap();
+    assert_eq!(prefs.get_theme(), "dark");
+
+    let err = prefs.save_theme("purple".to_string()).unwrap_err();
+    assert!(matches!(err, SaveError::ValidationError(ValidationError { key: "theme", .. })));
+    assert_eq!(prefs.get_theme(), "dark");
+
+    {
+        let mut edit = prefs.edit();
+        let result = edit.try_set_theme("neon".to_string());
+        assert!(matches!(result, Err(ValidationError { key: "theme", .. })));
+
+        // set_* ignores invalid values instead of storing them.
+        edit.set_theme("neon".to_string());
+        assert_eq!(edit.get_theme(), "dark");
+
+        edit.set_theme("light".to_string());
+        edit.set_label("anything goes".to_string());
+    }
+    assert_eq!(prefs.get_theme(), "light");
+}
+
+#[test]
+fn test_invalid_choice_falls_back_to_default_on_load() {
+    let mut prefs = ChoicePrefs::load_testing();
+    std::fs::write(
+        prefs.get_preferences_file_path(),
+        "theme = \"purple\"\nlabel = \"kept\"\n",
+    )
+    .unwrap();
+
+    prefs.reload().unwrap();
+    assert_eq!(prefs.get_theme(), "system");
+    assert_eq!(prefs.get_label(), "kept");
+}
+
+easy_prefs! {
+    struct RangePrefs {
+        pub volume: i32 = 50 => "volume" (min = 0, max = 100, step = 5),
+        pub scale: f64 = 1.0 => "scale" (min = 0.5, max = 3.0),
+        pub offset: i32 = 0 => "offset" (min = -10),
+    },
+    "range-prefs"
+}
+
+#[test]
+fn test_range_metadata() {
+    let volume = RangePrefs::FIELDS[0].options;
+    assert_eq!(
+        (volume.min, volume.max, volume.step),
+        (Some(0.0), Some(100.0), Some(5.0))
+    );
+    let offset = RangePrefs::FIELDS[2].options;
+    assert_eq!((offset.min, offset.max), (Some(-10.0), None));
+}
+
+#[test]
+fn test_range_enforced_on_set() {
+    let mut prefs = RangePrefs::load_testing();
+
+    prefs.save_volume(75).unwrap();
+    assert!(prefs.save_volume(101).is_err());
+    assert!(prefs.save_volume(-5).is_err());
+    assert!(prefs.save_volume(42).is_err(), "42 is not on a step of 5");
+    assert_eq!(*prefs.get_volume(), 75);
+
+    prefs.save_scale(2.5).unwrap();
+    assert!(prefs.save_scale(0.1).is_err());
+    prefs.save_offset(-10).unwrap();
+    assert!(prefs.save_offset(-11).is_err());
+
+    let mut edit = prefs.edit();
+    assert!(edit.try_set_scale(3.5).is_err());
+    assert!(edit.try_set_volume(100).is_ok());
+}
+
+#[test]
+fn test_out_of_range_falls_back_to_default_on_load() {
+    let mut prefs = RangePrefs::load_testing();
+    std::fs::write(
+        prefs.get_preferences_file_path(),
+        "volume = 500\nscale = 2.0\n",
+    )
+    .unwrap();
+
+    prefs.reload().unwrap();
+    assert_eq!(*prefs.get_volume(), 50);
+    assert_eq!(*prefs.get_scale(), 2.0);
+}
+
+easy_prefs! {
+    struct RepairPrefs {
+        pub font_size: i32 = 14 => "font_size" (range = 8..=72, on_invalid = clamp),
+        pub volume: i32 = 50 => "volume" (range = 0..=100, step = 5, on_invalid = clamp),
+        pub scale: f64 = 1.0 => "scale" (range = 0.5..=3.0, on_invalid = clamp),
+        pub theme: String = "light".to_string() => "theme" (choices = ["light", "dark"], on_invalid = clamp),
+        pub offset: i32 = 0 => "offset" (range = -10..=10),
+    },
+    "repair-prefs"
+}
+
+easy_prefs! {
+    struct StrictRangePrefs {
+        pub font_size: i32 = 14 => "font_size" (range = 8..=72, on_invalid = error),
+    },
+    "strict-range-prefs"
+}
+
+#[test]
+fn test_range_option() {
+    let font_size = RepairPrefs::FIELDS[0].options;
+    assert_eq!((font_size.min, font_size.max), (Some(8.0), Some(72.0)));
+    assert_eq!(font_size.on_invalid, OnInvalid::Clamp);
+    assert_eq!(RepairPrefs::FIELDS[4].options.min, Some(-10.0));
+    assert_eq!(RepairPrefs::FIELDS[4].options.on_invalid, OnInvalid::UseDefault);
+
+    let mut prefs = RepairPrefs::load_testing();
+    assert!(matches!(
+        prefs.save_font_size(-3),
+        Err(SaveError::ValidationError(ValidationError { key: "font_size", .. }))
+    ));
+    prefs.save_font_size(72).unwrap();
+}
+
+#[test]
+fn test_clamp_on_load() {
+    let mut prefs = RepairPrefs::load_testing();
+    std::fs::write(
+        prefs.get_preferences_file_path(),
+        "font_size = -3
+volume = 103
+scale = 9.5
+theme = \"blue\"\noffset = 11
+",
+    )
+    .unwrap();
+
+    prefs.reload().unwrap();
+    assert_eq!(*prefs.get_font_size(), 8);
+    assert_eq!(*prefs.get_volume(), 100);
+    assert_eq!(*prefs.get_scale(), 3.0);
+    assert_eq!(prefs.get_theme(), "light", "strings can't be clamped");
+    assert_eq!(*prefs.get_offset(), 0, "default mode falls back to the default");
+
+    // The repaired values are what the next save writes.
+    prefs.save_offset(1).unwrap();
+    let contents = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+    assert!(contents.contains("font_size = 8"));
+}
+
+#[test]
+fn test_clamp_value_snaps_to_step() {
+    let volume = &RepairPrefs::FIELDS[1];
+    let clamp = |value: i64| easy_prefs::validate::clamp_value(volume, &toml::Value::Integer(value));
+    assert_eq!(clamp(42), Some(toml::Value::Integer(40)));
+    assert_eq!(clamp(99), Some(toml::Value::Integer(100)));
+    assert_eq!(clamp(-7), Some(toml::Value::Integer(0)));
+}
+
+#[test]
+fn test_error_on_invalid_load() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("strict-range-prefs.toml"), "font_size = -3
+").unwrap();
+
+    let result = StrictRangePrefs::load_with_error(dir.path().to_str().unwrap());
+    assert!(matches!(
+        result,
+        Err(LoadError::ValidationError(ValidationError { key: "font_size", .. }))
+    ));
+}
+
+fn check_window(prefs: &WindowPrefs) -> Result<(), String> {
+    if *prefs.get_width() >= *prefs.get_min_width() && *prefs.get_height() < *prefs.get_min_height() {
+        return Err("height must be at least min_height".to_string());
+    }
+    Ok(())
+}
+
+easy_prefs! {
+    struct WindowPrefs {
+        pub width: i32 = 800 => "width",
+        pub height: i32 = 600 => "height",
+        pub min_width: i32 = 400 => "min_width",
+        pub min_height: i32 = 300 => "min_height",
+    },
+    "window-prefs",
+    validate_struct = check_window
+}
+
+#[test]
+fn test_struct_validator_on_save() {
+    let mut prefs = WindowPrefs::load_testing();
+
+    prefs.save_height(400).unwrap();
+    let err = prefs.save_height(100).unwrap_err();
+    assert!(matches!(err, SaveError::ValidationError(ValidationError { key: "", .. })));
+    assert!(err.to_string().contains("height must be at least min_height"));
+    assert_eq!(*prefs.get_height(), 400, "rejected value is rolled back");
+
+    // Edit guards are checked when they save.
+    {
+        let mut edit = prefs.edit();
+        edit.set_height(100);
+    }
+    let contents = std::fs::read_to_string(prefs.get_preferences_file_path()).unwrap();
+    assert!(contents.contains("height = 400"));
+}
+
+#[test]
+fn test_struct_validator_on_load() {
+    let mut prefs = WindowPrefs::load_testing();
+    std::fs::write(prefs.get_preferences_file_path(), "height = 10\n").unwrap();
+
+    match prefs.reload() {
+        Err(easy_prefs::LoadError::ValidationError(e)) => {
+            assert!(e.to_string().contains("height must be at least min_height"));
+        }
+        other => panic!("Expected ValidationError, got {:?}", other),
+    }
+}
@@ -19,7 +19,7 @@ fn test_wasm_basic_functionality() {
     let mut prefs = WasmTestPrefs::load_testing();
 
     // Test defaults
-    assert_eq!(*prefs.get_enabled(), true);
+    assert!(*prefs.get_enabled());
     assert_eq!(*prefs.get_count(), 0);
 
     // Test saving
@@ -27,7 +27,7 @@ fn test_wasm_basic_functionality() {
     prefs.save_count(42).expect("Failed to save count");
 
     // Verify values were updated
-    assert_eq!(*prefs.get_enabled(), false);
+    assert!(!*prefs.get_enabled());
     assert_eq!(*prefs.get_count(), 42);
 }
 
@@ -41,7 +41,7 @@ fn test_wasm_edit_guard() {
         guard.set_count(100);
     }
 
-    assert_eq!(*prefs.get_enabled(), false);
+    assert!(!*prefs.get_enabled());
     assert_eq!(*prefs.get_count(), 100);
 }
 
@@ -20,7 +20,7 @@ fn test_wasm_storage() {
     let mut prefs = TestWasmPrefs::load(&test_id).expect("Failed to load prefs");
 
     // Test default values
-    assert_eq!(*prefs.get_enabled(), true);
+    assert!(*prefs.get_enabled());
     assert_eq!(*prefs.get_count(), 0);
 
     // Test saving values
@@ -30,7 +30,7 @@ fn test_wasm_storage() {
     // Reload and verify persistence
     drop(prefs);
     let prefs2 = TestWasmPrefs::load(&test_id).expect("Failed to reload prefs");
-    assert_eq!(*prefs2.get_enabled(), false);
+    assert!(!*prefs2.get_enabled());
     assert_eq!(*prefs2.get_count(), 42);
 }
 
@@ -45,6 +45,52 @@ fn test_wasm_edit_guard() {
         edit.set_count(100);
     }
 
-    assert_eq!(*prefs.get_enabled(), false);
+    assert!(!*prefs.get_enabled());
     assert_eq!(*prefs.get_count(), 100);
 }
+
+#[wasm_bindgen_test]
+fn test_wasm_load_testing_instances_are_isolated() {
+    let mut first = TestWasmPrefs::load_testing();
+    let second = TestWasmPrefs::load_testing();
+    assert_ne!(
+        first.get_preferences_file_path(),
+        second.get_preferences_file_path()
+    );
+
+    first.save_count(7).expect("Failed to save count");
+    assert_eq!(*second.get_count(), 0);
+}
+
+#[wasm_bindgen_test]
+fn test_wasm_load_testing_cleans_up() {
+    let storage = web_sys::window().unwrap().local_storage().unwrap().unwrap();
+    let mut prefs = TestWasmPrefs::load_testing();
+    prefs.save_count(3).expect("Failed to save count");
+    let key = prefs
+        .get_preferences_file_path()
+        .trim_start_matches("localStorage::")
+        .to_string();
+    assert!(storage.get_item(&key).unwrap().is_some());
+
+    drop(prefs);
+    assert!(storage.get_item(&key).unwrap().is_none());
+}
+
+#[wasm_bindgen_test]
+fn test_wasm_purge_test_keys() {
+    let storage = web_sys::window().unwrap().local_storage().unwrap().unwrap();
+    storage
+        .set_item("easy_prefs_test_leftover_prefs.toml", "count = 1")
+        .unwrap();
+    storage.set_item("easy_prefs_kept_prefs.toml", "count = 2").unwrap();
+
+    let removed = easy_prefs::storage::wasm::purge_test_keys().expect("Failed to purge");
+    assert!(removed >= 1);
+    assert!(storage
+        .get_item("easy_prefs_test_leftover_prefs.toml")
+        .unwrap()
+        .is_none());
+    assert!(storage.get_item("easy_prefs_kept_prefs.toml").unwrap().is_some());
+    storage.remove_item("easy_prefs_kept_prefs.toml").unwrap();
+}
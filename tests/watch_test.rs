@@ -0,0 +1,74 @@
+#![cfg(feature = "watch")]
+
+use easy_prefs::easy_prefs;
+
+easy_prefs! {
+    struct WatchedPrefs {
+        pub theme: String = "light".to_string() => "theme",
+        pub volume: i32 = 50 => "volume",
+    },
+    "watch-prefs"
+}
+
+#[test]
+fn test_receivers_see_changes() {
+    let mut prefs = WatchedPrefs::load_testing();
+    let mut theme = prefs.watch_theme();
+    let mut volume = prefs.watch_volume();
+    assert_eq!(*theme.borrow_and_update(), "light");
+    assert!(!theme.has_changed().unwrap());
+
+    prefs.save_theme("dark".to_string()).unwrap();
+    assert!(theme.has_changed().unwrap());
+    assert_eq!(*theme.borrow_and_update(), "dark");
+    assert!(!volume.has_changed().unwrap());
+
+    {
+        let mut edit = prefs.edit();
+        edit.set_volume(80);
+        edit.set_theme("dark".to_string());
+    }
+    assert_eq!(*volume.borrow_and_update(), 80);
+    // The theme was saved again with the same value.
+    assert!(!theme.has_changed().unwrap());
+
+    // Every receiver shares one channel.
+    let late = prefs.watch_volume();
+    assert_eq!(*late.borrow(), 80);
+}
+
+#[test]
+fn test_reload_notifies() {
+    let mut prefs = WatchedPrefs::load_testing();
+    let mut volume = prefs.watch_volume();
+    std::fs::write(prefs.get_preferences_file_path(), "volume = 7\n").unwrap();
+    prefs.reload().unwrap();
+    assert!(volume.has_changed().unwrap());
+    assert_eq!(*volume.borrow_and_update(), 7);
+}
+
+/// Storage whose writes always fail.
+#[derive(Debug, Default)]
+struct ReadOnlyStorage(easy_prefs::storage::MemoryStorage);
+
+impl easy_prefs::storage::Storage for ReadOnlyStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+        self.0.read(key)
+    }
+
+    fn write(&self, _key: &str, _data: &str) -> Result<(), std::io::Error> {
+        Err(std::io::Error::other("read-only"))
+    }
+
+    fn get_path(&self, key: &str) -> String {
+        format!("read-only::{key}")
+    }
+}
+
+#[test]
+fn test_failed_save_does_not_notify() {
+    let mut prefs = WatchedPrefs::load_with_storage(Box::new(ReadOnlyStorage::default())).unwrap();
+    let theme = prefs.watch_theme();
+    assert!(prefs.save_theme("dark".to_string()).is_err());
+    assert!(!theme.has_changed().unwrap());
+}
@@ -0,0 +1,70 @@
+#![cfg(all(target_arch = "wasm32", feature = "webextension"))]
+
+use easy_prefs::easy_prefs;
+use easy_prefs::storage::extension::{ExtensionStorage, StorageArea};
+use easy_prefs::storage::Storage;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+easy_prefs! {
+    struct SyncedPrefs {
+        pub enabled: bool = true => "enabled",
+        pub count: i32 = 0 => "count",
+    },
+    "synced-prefs",
+    single_instance = false
+}
+
+/// Stands in for `chrome.storage` on a plain test page.
+const FAKE_CHROME_STORAGE: &str = r#"
+    globalThis.chrome = { storage: (() => {
+        const listeners = [];
+        const area = () => {
+            const data = {};
+            return {
+                get: () => Promise.resolve(Object.assign({}, data)),
+                set: (items) => { Object.assign(data, items); return Promise.resolve(); },
+            };
+        };
+        return {
+            local: area(),
+            sync: area(),
+            onChanged: { addListener: (listener) => listeners.push(listener) },
+            emit: (changes, areaName) => listeners.forEach((listener) => listener(changes, areaName)),
+        };
+    })() };
+"#;
+
+#[wasm_bindgen_test]
+async fn test_sync_round_trip_and_remote_changes() {
+    let err = ExtensionStorage::open("com.example.ext", StorageArea::Sync)
+        .await
+        .expect_err("plain pages have no extension storage");
+    assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+
+    js_sys::eval(FAKE_CHROME_STORAGE).unwrap();
+
+    let storage = ExtensionStorage::open("com.example.ext", StorageArea::Sync).await.unwrap();
+    let mut prefs = SyncedPrefs::load_with_storage(Box::new(storage)).unwrap();
+    prefs.save_count(3).unwrap();
+    assert_eq!(prefs.get_preferences_file_path(), "storage.sync::easy_prefs_com_example_ext_synced-prefs.toml");
+
+    let storage = ExtensionStorage::open("com.example.ext", StorageArea::Sync).await.unwrap();
+    let mut reopened = SyncedPrefs::load_with_storage(Box::new(storage)).unwrap();
+    assert_eq!(*reopened.get_count(), 3);
+
+    // A change synced from another browser shows up on reload.
+    js_sys::eval(
+        r#"chrome.storage.emit({"easy_prefs_com_example_ext_synced-prefs.toml": {newValue: "count = 9\n"}}, "sync")"#,
+    )
+    .unwrap();
+    reopened.reload().unwrap();
+    assert_eq!(*reopened.get_count(), 9);
+
+    let local = ExtensionStorage::open("com.example.ext", StorageArea::Local).await.unwrap();
+    assert_eq!(local.read("synced-prefs.toml").unwrap(), None);
+    let too_big = "x".repeat(easy_prefs::storage::extension::SYNC_QUOTA_BYTES_PER_ITEM);
+    let sync = ExtensionStorage::open("com.example.ext", StorageArea::Sync).await.unwrap();
+    assert_eq!(sync.write("big", &too_big).unwrap_err().kind(), std::io::ErrorKind::StorageFull);
+}
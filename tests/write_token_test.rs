@@ -0,0 +1,55 @@
+use easy_prefs::easy_prefs;
+use easy_prefs::write_token::{self, WriteToken};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+easy_prefs! {
+    struct TokenPrefs {
+        pub value: i32 = 0 => "value",
+    },
+    "token-prefs"
+}
+
+// The broker and the enforcement flag are process-wide, so everything runs in one test.
+#[test]
+fn test_write_token_broker_and_enforcement() {
+    // --- Part 1: only one token at a time ---
+    {
+        let token = WriteToken::acquire("ui");
+        assert_eq!(write_token::current_holder().as_deref(), Some("ui"));
+        assert!(WriteToken::try_acquire("sync").is_none());
+
+        let (tx, rx) = mpsc::channel();
+        let waiter = thread::spawn(move || {
+            let token = WriteToken::acquire("sync");
+            tx.send(token.owner().to_string()).unwrap();
+        });
+
+        // The waiter must stay blocked while the token is held.
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+        drop(token);
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), "sync");
+        waiter.join().unwrap();
+        assert_eq!(write_token::current_holder(), None);
+    }
+
+    // --- Part 2: enforcement routes edits through edit_with_token() ---
+    let mut prefs = TokenPrefs::load_testing();
+    write_token::set_enforced(true);
+    {
+        let token = WriteToken::acquire("settings");
+        let mut edit = prefs.edit_with_token(&token);
+        edit.set_value(7);
+    }
+    assert_eq!(*prefs.get_value(), 7);
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        prefs.edit().set_value(8);
+    }));
+    assert!(result.is_err(), "edit() should panic while tokens are enforced");
+    write_token::set_enforced(false);
+
+    prefs.edit().set_value(9);
+    assert_eq!(*prefs.get_value(), 9);
+}
@@ -0,0 +1,92 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use easy_prefs::easy_prefs;
+use easy_prefs::storage::{MemoryStorage, Storage};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+easy_prefs! {
+    struct FirstPrefs {
+        pub theme: String = "light".to_string() => "theme",
+    },
+    "writer-first-prefs",
+    single_instance = false
+}
+
+easy_prefs! {
+    struct SecondPrefs {
+        pub volume: i64 = 5 => "volume",
+    },
+    "writer-second-prefs",
+    single_instance = false
+}
+
+/// Storage recording which thread wrote what, and how many writes ran at once.
+#[derive(Debug, Clone, Default)]
+struct RecordingStorage {
+    inner: Arc<MemoryStorage>,
+    writes: Arc<Mutex<Vec<(String, String)>>>,
+    active: Arc<AtomicUsize>,
+    most_active: Arc<AtomicUsize>,
+}
+
+impl Storage for RecordingStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, std::io::Error> {
+        self.inner.read(key)
+    }
+
+    fn write(&self, key: &str, data: &str) -> Result<(), std::io::Error> {
+        let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+        self.most_active.fetch_max(active, Ordering::SeqCst);
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let thread = std::thread::current().name().unwrap_or_default().to_string();
+        self.writes.lock().unwrap().push((thread, key.to_string()));
+        self.active.fetch_sub(1, Ordering::SeqCst);
+        if data.contains("volume = 13") {
+            return Err(std::io::Error::other("unlucky"));
+        }
+        self.inner.write(key, data)
+    }
+
+    fn get_path(&self, key: &str) -> String {
+        format!("recording::{key}")
+    }
+}
+
+#[test]
+fn test_writes_are_queued_to_one_thread() {
+    easy_prefs::writer::set_enabled(true);
+    assert!(easy_prefs::writer::is_enabled());
+    let storage = RecordingStorage::default();
+
+    let threads: Vec<_> = (0..4)
+        .map(|index| {
+            let storage = storage.clone();
+            std::thread::spawn(move || {
+                let mut first = FirstPrefs::load_with_storage(Box::new(storage.clone())).unwrap();
+                let mut second = SecondPrefs::load_with_storage(Box::new(storage)).unwrap();
+                for round in 0..5 {
+                    first.save_theme(format!("theme-{index}-{round}")).unwrap();
+                    second.save_volume(index * 100 + round).unwrap();
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    let writes = storage.writes.lock().unwrap().clone();
+    assert_eq!(writes.len(), 40);
+    assert!(writes.iter().all(|(thread, _)| thread == "easy_prefs-writer"));
+    assert_eq!(storage.most_active.load(Ordering::SeqCst), 1);
+
+    // Errors still reach the caller.
+    let mut second = SecondPrefs::load_with_storage(Box::new(storage.clone())).unwrap();
+    assert!(second.save_volume(13).is_err());
+
+    easy_prefs::writer::set_enabled(false);
+    second.save_volume(14).unwrap();
+    let (thread, _) = storage.writes.lock().unwrap().last().unwrap().clone();
+    assert_eq!(thread, std::thread::current().name().unwrap_or_default());
+}